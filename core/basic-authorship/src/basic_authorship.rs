@@ -24,7 +24,7 @@ use client::{
 	error, Client as SubstrateClient, CallExecutor,
 	block_builder::api::BlockBuilder as BlockBuilderApi,
 };
-use codec::Decode;
+use codec::{Decode, Encode};
 use consensus_common::{evaluation};
 use inherents::InherentData;
 use log::{error, info, debug, trace};
@@ -33,17 +33,66 @@ use sr_primitives::{
 	traits::{
 		Block as BlockT, Hash as HashT, Header as HeaderT, ProvideRuntimeApi, DigestFor, BlakeTwo256
 	},
-	generic::BlockId,
+	generic::BlockId, Perbill,
 };
 use transaction_pool::txpool::{self, Pool as TransactionPool};
 use substrate_telemetry::{telemetry, CONSENSUS_INFO};
 
+/// Limits of the blocks proposed by the `Proposer`.
+///
+/// The weight of the blocks is limited by the runtime itself, which refuses the transactions
+/// exhausting the resources of the block.
+#[derive(Debug, Clone)]
+pub struct ProposalLimits {
+	/// Portion of the proposal duration after which the proposer stops trying more transactions
+	/// once the block is full.
+	pub soft_deadline: Perbill,
+	/// Portion of the proposal duration after which the proposer stops pulling transactions
+	/// from the pool, the rest being left for evaluation and block finalization.
+	pub hard_deadline: Perbill,
+	/// The maximum encoded size of the extrinsics of the block, including the inherent ones,
+	/// if any.
+	pub block_size_limit: Option<usize>,
+	/// Number of transactions which are tried when the block seems full, before only trying
+	/// the operational ones.
+	pub max_skipped_transactions: usize,
+}
+
+impl Default for ProposalLimits {
+	fn default() -> Self {
+		ProposalLimits {
+			soft_deadline: Perbill::from_percent(50),
+			hard_deadline: Perbill::from_rational_approximation(2u32, 3u32),
+			block_size_limit: None,
+			max_skipped_transactions: 8,
+		}
+	}
+}
+
+/// Why the proposer stopped pulling transactions from the pool.
+///
+/// Reported with each proposed block in the `prepared_block_for_proposing` telemetry message,
+/// which is how the node exposes its metrics, as it has no Prometheus registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndProposingReason {
+	/// All the ready transactions of the pool were tried.
+	NoMoreTransactions,
+	/// The hard deadline was reached.
+	HitDeadline,
+	/// The block reached the size limit.
+	HitBlockSizeLimit,
+	/// The runtime refused more transactions, the block being full.
+	HitBlockWeightLimit,
+}
+
 /// Proposer factory.
 pub struct ProposerFactory<C, A> where A: txpool::ChainApi {
 	/// The client instance.
 	pub client: Arc<C>,
 	/// The transaction pool.
 	pub transaction_pool: Arc<TransactionPool<A>>,
+	/// The limits of the proposed blocks.
+	pub limits: ProposalLimits,
 }
 
 impl<B, E, Block, RA, A> consensus_common::Environment<Block> for
@@ -77,6 +126,7 @@ where
 			parent_number: *parent_header.number(),
 			transaction_pool: self.transaction_pool.clone(),
 			now: Box::new(time::Instant::now),
+			limits: self.limits.clone(),
 		};
 
 		Ok(proposer)
@@ -91,6 +141,7 @@ pub struct Proposer<Block: BlockT, C, A: txpool::ChainApi> {
 	parent_number: <<Block as BlockT>::Header as HeaderT>::Number,
 	transaction_pool: Arc<TransactionPool<A>>,
 	now: Box<dyn Fn() -> time::Instant>,
+	limits: ProposalLimits,
 }
 
 impl<B, E, Block, RA, A> consensus_common::Proposer<Block> for
@@ -113,9 +164,11 @@ where
 		inherent_digests: DigestFor<Block>,
 		max_duration: time::Duration,
 	) -> Self::Create {
-		// leave some time for evaluation and block finalization (33%)
-		let deadline = (self.now)() + max_duration - max_duration / 3;
-		futures::future::ready(self.propose_with(inherent_data, inherent_digests, deadline))
+		let now = (self.now)();
+		let portion = |portion: Perbill| time::Duration::from_nanos(portion * (max_duration.as_nanos() as u64));
+		let soft_deadline = now + portion(self.limits.soft_deadline);
+		let deadline = now + portion(self.limits.hard_deadline);
+		futures::future::ready(self.propose_with(inherent_data, inherent_digests, soft_deadline, deadline))
 	}
 }
 
@@ -132,14 +185,11 @@ impl<Block, B, E, RA, A> Proposer<Block, SubstrateClient<B, E, Block, RA>, A>	wh
 		&self,
 		inherent_data: InherentData,
 		inherent_digests: DigestFor<Block>,
+		soft_deadline: time::Instant,
 		deadline: time::Instant,
 	) -> Result<Block, error::Error> {
-		/// If the block is full we will attempt to push at most
-		/// this number of transactions before quitting for real.
-		/// It allows us to increase block utilization.
-		const MAX_SKIPPED_TRANSACTIONS: usize = 8;
-
 		let mut block_builder = self.client.new_block_at(&self.parent_id, inherent_digests)?;
+		let mut block_size = 0;
 
 		// We don't check the API versions any further here since the dispatch compatibility
 		// check should be enough.
@@ -150,40 +200,77 @@ impl<Block, B, E, RA, A> Proposer<Block, SubstrateClient<B, E, Block, RA>, A>	wh
 				inherent_data
 			)?
 		{
+			block_size += extrinsic.encoded_size();
 			block_builder.push(extrinsic)?;
 		}
 
 		// proceed with transactions
 		let mut is_first = true;
 		let mut skipped = 0;
+		// once the block is full of normal transactions, only the operational ones can still
+		// fit in the portion of the block the runtime reserves for them.
+		let mut only_operational = false;
 		let mut unqueue_invalid = Vec::new();
+		let mut end_reason = EndProposingReason::NoMoreTransactions;
 		let pending_iterator = self.transaction_pool.ready();
 
 		debug!("Attempting to push transactions from the pool.");
 		for pending in pending_iterator {
-			if (self.now)() > deadline {
+			let now = (self.now)();
+			if now > deadline {
 				debug!("Consensus deadline reached when pushing block transactions, proceeding with proposing.");
+				end_reason = EndProposingReason::HitDeadline;
 				break;
 			}
 
+			if only_operational && !pending.is_operational() {
+				continue;
+			}
+
+			let pending_size = pending.data.encoded_size();
+			if let Some(block_size_limit) = self.limits.block_size_limit {
+				if block_size + pending_size > block_size_limit {
+					if skipped < self.limits.max_skipped_transactions && now < soft_deadline {
+						skipped += 1;
+						debug!(
+							"[{:?}] Transaction would overflow the block size limit, \
+							but will try {} more transactions before quitting.",
+							pending.hash,
+							self.limits.max_skipped_transactions - skipped,
+						);
+						continue;
+					}
+
+					debug!("Block size limit reached, proceeding with proposing.");
+					end_reason = EndProposingReason::HitBlockSizeLimit;
+					break;
+				}
+			}
+
 			trace!("[{:?}] Pushing to the block.", pending.hash);
 			match client::block_builder::BlockBuilder::push(&mut block_builder, pending.data.clone()) {
 				Ok(()) => {
+					block_size += pending_size;
 					debug!("[{:?}] Pushed to the block.", pending.hash);
 				}
 				Err(error::Error::ApplyExtrinsicFailed(e)) if e.exhausted_resources() => {
 					if is_first {
 						debug!("[{:?}] Invalid transaction: FullBlock on empty block", pending.hash);
 						unqueue_invalid.push(pending.hash.clone());
-					} else if skipped < MAX_SKIPPED_TRANSACTIONS {
+					} else if pending.is_operational() {
+						debug!("Block is full, proceed with proposing.");
+						end_reason = EndProposingReason::HitBlockWeightLimit;
+						break;
+					} else if skipped < self.limits.max_skipped_transactions && now < soft_deadline {
 						skipped += 1;
 						debug!(
 							"Block seems full, but will try {} more transactions before quitting.",
-							MAX_SKIPPED_TRANSACTIONS - skipped,
+							self.limits.max_skipped_transactions - skipped,
 						);
 					} else {
-						debug!("Block is full, proceed with proposing.");
-						break;
+						debug!("Block is full of normal transactions, only trying operational ones.");
+						only_operational = true;
+						end_reason = EndProposingReason::HitBlockWeightLimit;
 					}
 				}
 				Err(e) => {
@@ -199,7 +286,7 @@ impl<Block, B, E, RA, A> Proposer<Block, SubstrateClient<B, E, Block, RA>, A>	wh
 
 		let block = block_builder.bake()?;
 
-		info!("Prepared block for proposing at {} [hash: {:?}; parent_hash: {}; extrinsics: [{}]]",
+		info!("Prepared block for proposing at {} [hash: {:?}; parent_hash: {}; extrinsics: [{}]; end reason: {:?}]",
 			block.header().number(),
 			<Block as BlockT>::Hash::from(block.header().hash()),
 			block.header().parent_hash(),
@@ -207,11 +294,14 @@ impl<Block, B, E, RA, A> Proposer<Block, SubstrateClient<B, E, Block, RA>, A>	wh
 				.iter()
 				.map(|xt| format!("{}", BlakeTwo256::hash_of(xt)))
 				.collect::<Vec<_>>()
-				.join(", ")
+				.join(", "),
+			end_reason,
 		);
 		telemetry!(CONSENSUS_INFO; "prepared_block_for_proposing";
 			"number" => ?block.header().number(),
 			"hash" => ?<Block as BlockT>::Hash::from(block.header().hash()),
+			"size" => block_size,
+			"end_reason" => ?end_reason,
 		);
 
 		if Decode::decode(&mut block.encode().as_slice()).as_ref() != Ok(&block) {
@@ -257,6 +347,7 @@ mod tests {
 		let mut proposer_factory = ProposerFactory {
 			client: client.clone(),
 			transaction_pool: txpool.clone(),
+			limits: Default::default(),
 		};
 
 		let mut proposer = proposer_factory.init(
@@ -278,4 +369,40 @@ mod tests {
 		assert_eq!(block.extrinsics().len(), 1);
 		assert_eq!(txpool.ready().count(), 2);
 	}
+
+	#[test]
+	fn should_cease_building_block_when_block_size_limit_is_reached() {
+		// given
+		let client = Arc::new(test_client::new());
+		let chain_api = transaction_pool::FullChainApi::new(client.clone());
+		let txpool = Arc::new(TransactionPool::new(Default::default(), chain_api));
+
+		futures::executor::block_on(
+			txpool.submit_at(&BlockId::number(0), vec![extrinsic(0), extrinsic(1), extrinsic(2)], false)
+		).unwrap();
+
+		let extrinsic_size = extrinsic(0).encoded_size();
+		let mut proposer_factory = ProposerFactory {
+			client: client.clone(),
+			transaction_pool: txpool.clone(),
+			limits: ProposalLimits {
+				block_size_limit: Some(2 * extrinsic_size + 1),
+				..Default::default()
+			},
+		};
+
+		let mut proposer = proposer_factory.init(
+			&client.header(&BlockId::number(0)).unwrap().unwrap(),
+		).unwrap();
+
+		// when
+		let deadline = time::Duration::from_secs(10);
+		let block = futures::executor::block_on(proposer.propose(Default::default(), Default::default(), deadline))
+			.unwrap();
+
+		// then
+		// only the transactions fitting in the size limit are included.
+		assert_eq!(block.extrinsics().len(), 2);
+		assert_eq!(txpool.ready().count(), 3);
+	}
 }
@@ -32,6 +32,7 @@
 //! let mut proposer_factory = ProposerFactory {
 //! 	client: client.clone(),
 //! 	transaction_pool: txpool.clone(),
+//! 	limits: Default::default(),
 //! };
 //!
 //! // From this factory, we create a `Proposer`.
@@ -55,4 +56,4 @@
 
 mod basic_authorship;
 
-pub use crate::basic_authorship::{ProposerFactory, Proposer};
+pub use crate::basic_authorship::{EndProposingReason, ProposalLimits, ProposerFactory, Proposer};
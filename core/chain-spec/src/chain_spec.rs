@@ -33,6 +33,7 @@ enum GenesisSource<G> {
 	File(PathBuf),
 	Binary(Cow<'static, [u8]>),
 	Factory(Rc<dyn Fn() -> G>),
+	Storage((StorageOverlay, ChildrenStorageOverlay)),
 }
 
 impl<G> Clone for GenesisSource<G> {
@@ -41,6 +42,7 @@ impl<G> Clone for GenesisSource<G> {
 			GenesisSource::File(ref path) => GenesisSource::File(path.clone()),
 			GenesisSource::Binary(ref d) => GenesisSource::Binary(d.clone()),
 			GenesisSource::Factory(ref f) => GenesisSource::Factory(f.clone()),
+			GenesisSource::Storage(ref s) => GenesisSource::Storage(s.clone()),
 		}
 	}
 }
@@ -66,6 +68,13 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 				Ok(genesis.genesis)
 			},
 			GenesisSource::Factory(f) => Ok(Genesis::Runtime(f())),
+			GenesisSource::Storage((top, children)) => Ok(Genesis::Raw(
+				top.iter().map(|(k, v)| (StorageKey(k.clone()), StorageData(v.clone()))).collect(),
+				children.iter().map(|(sk, child)| (
+					StorageKey(sk.clone()),
+					child.iter().map(|(k, v)| (StorageKey(k.clone()), StorageData(v.clone()))).collect(),
+				)).collect(),
+			)),
 		}
 	}
 }
@@ -187,6 +196,12 @@ impl<G, E> ChainSpec<G, E> {
 		&self.spec.extensions
 	}
 
+	/// Replace the genesis of the spec with the given raw storage, e.g. the state of a live
+	/// chain exported to bootstrap a new one from.
+	pub fn set_storage(&mut self, storage: (StorageOverlay, ChildrenStorageOverlay)) {
+		self.genesis = GenesisSource::Storage(storage);
+	}
+
 	/// Create hardcoded spec.
 	pub fn from_genesis<F: Fn() -> G + 'static>(
 		name: &str,
@@ -329,4 +344,19 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	#[test]
+	fn should_replace_genesis_with_storage() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		let mut top = StorageOverlay::default();
+		top.insert(b"key".to_vec(), b"value".to_vec());
+		let mut children = ChildrenStorageOverlay::default();
+		children.insert(b":child_storage:default:child".to_vec(), top.clone());
+
+		spec.set_storage((top.clone(), children.clone()));
+		assert_eq!((&spec).build_storage().unwrap(), (top, children));
+		assert!(spec.to_json(false).unwrap().contains("\"raw\""));
+	}
 }
@@ -0,0 +1,313 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Loading of node options from a TOML configuration file.
+//!
+//! The keys of the file are the long names of the command line options, e.g.:
+//!
+//! ```toml
+//! validator = true
+//! name = "my-node"
+//! port = 30333
+//! bootnodes = ["/ip4/127.0.0.1/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV"]
+//! ```
+//!
+//! The options of the file are appended to the command line before it is parsed, unless the same
+//! option is also given on the command line, so that command line flags override file values.
+//!
+//! On Unix, the file is read again when the node receives `SIGHUP`, and the changed options that
+//! can be changed at runtime are applied to the running node.
+
+use std::{ffi::OsString, fs, path::Path};
+use toml::{Value, value::Table};
+
+use crate::error;
+
+/// Set when the process receives `SIGHUP`.
+#[cfg(unix)]
+static HANGUP: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Interval at which the watcher thread checks whether `SIGHUP` was received.
+#[cfg(unix)]
+const HANGUP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Name of the command line option pointing to the configuration file.
+const CONFIG_OPTION: &str = "config";
+
+/// Short command line options and the long option they stand for.
+const SHORT_OPTIONS: &[(&str, &str)] = &[("d", "base-path"), ("l", "log")];
+
+/// Returns the options given on the command line, by long option name.
+///
+/// An option followed by a value that isn't itself an option takes that value, other options are
+/// flags. Options that are given several times are collected in an array.
+pub fn cli_options(args: &[OsString]) -> Table {
+	let args = args.iter().map(|arg| arg.to_string_lossy().into_owned()).collect::<Vec<_>>();
+	let mut options = Table::new();
+	let mut args = args.iter().peekable();
+	while let Some(arg) = args.next() {
+		let name = if arg.starts_with("--") {
+			&arg[2..]
+		} else if arg.starts_with('-') {
+			&arg[1..]
+		} else {
+			continue
+		};
+		if name.is_empty() {
+			// everything after `--` is positional.
+			break
+		}
+
+		let (name, value) = match name.find('=') {
+			Some(pos) => (&name[..pos], Value::String(name[pos + 1..].to_string())),
+			None => match args.peek() {
+				Some(next) if !next.starts_with('-') =>
+					(name, Value::String(args.next().expect("peeked above; qed").clone())),
+				_ => (name, Value::Boolean(true)),
+			},
+		};
+		let name = SHORT_OPTIONS.iter()
+			.find(|(short, _)| *short == name)
+			.map_or(name, |&(_, long)| long);
+		let value = match value {
+			Value::String(s) => s.parse::<i64>().map(Value::Integer).unwrap_or(Value::String(s)),
+			value => value,
+		};
+
+		match options.remove(name) {
+			Some(Value::Array(mut values)) => {
+				values.push(value);
+				options.insert(name.to_string(), Value::Array(values));
+			},
+			Some(previous) => {
+				options.insert(name.to_string(), Value::Array(vec![previous, value]));
+			},
+			None => {
+				options.insert(name.to_string(), value);
+			},
+		}
+	}
+	options
+}
+
+/// Returns the options of the configuration file given with `--config`, if any, that are not
+/// overridden on the command line.
+pub fn file_options(args: &[OsString]) -> error::Result<Table> {
+	let cli = cli_options(args);
+	let path = match cli.get(CONFIG_OPTION) {
+		Some(Value::String(path)) => path.clone(),
+		Some(_) => return Err(
+			error::Error::Input(format!("`--{}` requires a path", CONFIG_OPTION))
+		),
+		None => return Ok(Table::new()),
+	};
+
+	Ok(load(Path::new(&path))?
+		.into_iter()
+		.filter(|(name, _)| name != CONFIG_OPTION && !cli.contains_key(name))
+		.collect())
+}
+
+/// Returns the command line arguments, followed by the options of the configuration file that are
+/// not overridden on the command line.
+pub fn merge_args(mut args: Vec<OsString>) -> error::Result<Vec<OsString>> {
+	let file = file_options(&args)?;
+	args.extend(to_args(&file)?);
+	Ok(args)
+}
+
+/// Returns the effective options of the given command line, as a TOML document that can be given
+/// back with `--config`.
+pub fn effective_config(args: &[OsString]) -> error::Result<String> {
+	let mut options = cli_options(args);
+	options.remove(CONFIG_OPTION);
+	for (name, value) in file_options(args)? {
+		options.insert(name, value);
+	}
+	toml::to_string(&options).map_err(|e| error::Error::Other(e.to_string()))
+}
+
+/// Returns the options that differ between two versions of the configuration file, as expected by
+/// the `ConfigReloader`. Removed options are `null`.
+pub fn changed_options(
+	previous: &Table,
+	current: &Table,
+) -> serde_json::Map<String, serde_json::Value> {
+	let mut changed = serde_json::Map::new();
+	for (name, value) in current {
+		if previous.get(name) != Some(value) {
+			changed.insert(name.clone(), serde_json::to_value(value).unwrap_or_default());
+		}
+	}
+	for name in previous.keys().filter(|name| !current.contains_key(*name)) {
+		changed.insert(name.clone(), serde_json::Value::Null);
+	}
+	changed
+}
+
+/// Reads the configuration file again whenever the process receives `SIGHUP`, and applies the
+/// changed options with the given reloader.
+///
+/// The watch stops once the reloader is dropped.
+#[cfg(unix)]
+pub fn reload_on_hangup(
+	args: Vec<OsString>,
+	reloader: std::sync::Weak<service::config::ConfigReloader>,
+) -> error::Result<()> {
+	use std::sync::atomic::Ordering;
+	use log::{info, warn};
+
+	extern "C" fn on_hangup(_: libc::c_int) {
+		HANGUP.store(true, Ordering::SeqCst);
+	}
+
+	let mut previous = file_options(&args)?;
+	unsafe {
+		libc::signal(libc::SIGHUP, on_hangup as libc::sighandler_t);
+	}
+
+	std::thread::Builder::new()
+		.name("config-reload".into())
+		.spawn(move || loop {
+			std::thread::sleep(HANGUP_CHECK_INTERVAL);
+			let reloader = match reloader.upgrade() {
+				Some(reloader) => reloader,
+				None => break,
+			};
+			if !HANGUP.swap(false, Ordering::SeqCst) {
+				continue;
+			}
+
+			let current = match file_options(&args) {
+				Ok(current) => current,
+				Err(e) => {
+					warn!("Failed to reload the configuration file: {}", e);
+					continue;
+				},
+			};
+			let report = reloader.reload(&changed_options(&previous, &current));
+			info!(
+				"Reloaded configuration file. Applied: {:?}, requiring a restart: {:?}",
+				report.applied,
+				report.restart_required,
+			);
+			for (option, err) in report.failed {
+				warn!("Failed to apply `{}`: {}", option, err);
+			}
+			previous = current;
+		})?;
+	Ok(())
+}
+
+fn load(path: &Path) -> error::Result<Table> {
+	let content = fs::read_to_string(path)?;
+	toml::from_str(&content).map_err(|e| error::Error::Input(
+		format!("Invalid configuration file {}: {}", path.display(), e)
+	))
+}
+
+fn to_args(options: &Table) -> error::Result<Vec<OsString>> {
+	let mut args = Vec::new();
+	for (name, value) in options {
+		let values = match value {
+			Value::Array(values) => values.clone(),
+			value => vec![value.clone()],
+		};
+		for value in values {
+			let value = match value {
+				Value::Boolean(true) => None,
+				Value::Boolean(false) => continue,
+				Value::String(s) => Some(s),
+				Value::Integer(i) => Some(i.to_string()),
+				Value::Float(f) => Some(f.to_string()),
+				_ => return Err(error::Error::Input(
+					format!("Unsupported value for `{}` in configuration file", name)
+				)),
+			};
+			args.push(format!("--{}", name).into());
+			args.extend(value.map(Into::into));
+		}
+	}
+	Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	fn args(args: &[&str]) -> Vec<OsString> {
+		args.iter().map(Into::into).collect()
+	}
+
+	#[test]
+	fn cli_options_are_parsed() {
+		let options = cli_options(&args(&[
+			"node", "--validator", "--name=foo", "-d", "/tmp/node", "--port", "30333",
+			"--bootnodes", "a", "--bootnodes", "b",
+		]));
+
+		assert_eq!(options.get("validator"), Some(&Value::Boolean(true)));
+		assert_eq!(options.get("name"), Some(&Value::String("foo".into())));
+		assert_eq!(options.get("base-path"), Some(&Value::String("/tmp/node".into())));
+		assert_eq!(options.get("port"), Some(&Value::Integer(30333)));
+		assert_eq!(
+			options.get("bootnodes"),
+			Some(&Value::Array(vec![Value::String("a".into()), Value::String("b".into())])),
+		);
+	}
+
+	#[test]
+	fn cli_flags_override_file_values() {
+		let dir = tempdir::TempDir::new("substrate-cli-config").unwrap();
+		let path = dir.path().join("config.toml");
+		fs::File::create(&path).unwrap().write_all(
+			b"name = \"from-file\"\nport = 30334\nvalidator = true\nrpc-external = false\n"
+		).unwrap();
+		let path = path.to_string_lossy().into_owned();
+
+		let merged = merge_args(args(&["node", "--config", &path, "--name", "from-cli"])).unwrap();
+		assert_eq!(merged, args(&[
+			"node", "--config", &path, "--name", "from-cli", "--port", "30334", "--validator",
+		]));
+
+		let effective = effective_config(&args(&["node", "--config", &path, "--name", "from-cli"]))
+			.unwrap();
+		assert_eq!(
+			effective,
+			"name = \"from-cli\"\nport = 30334\nrpc-external = false\nvalidator = true\n",
+		);
+	}
+
+	#[test]
+	fn changed_options_are_detected() {
+		let previous: Table = toml::from_str("log = \"sync=debug\"\nport = 30333\nvalidator = true\n")
+			.unwrap();
+		let current: Table = toml::from_str("log = \"sync=trace\"\nport = 30333\n").unwrap();
+
+		let changed = changed_options(&previous, &current);
+		assert_eq!(changed.len(), 2);
+		assert_eq!(changed.get("log"), Some(&serde_json::Value::String("sync=trace".into())));
+		assert_eq!(changed.get("validator"), Some(&serde_json::Value::Null));
+	}
+
+	#[test]
+	fn unsupported_values_are_rejected() {
+		let mut options = Table::new();
+		options.insert("network".into(), Value::Table(Table::new()));
+		assert!(to_args(&options).is_err());
+	}
+}
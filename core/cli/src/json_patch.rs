@@ -0,0 +1,149 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! Application of JSON patches (RFC 6902) to the chain specifications built by `build-spec`.
+
+use serde_json::Value;
+
+/// Applies the operations of the JSON `patch` to `doc`, in order.
+///
+/// Only the `add`, `remove` and `replace` operations are supported. The document is left
+/// partially patched if an operation fails.
+pub fn apply(doc: &mut Value, patch: &Value) -> Result<(), String> {
+	let operations = patch.as_array().ok_or("A JSON patch must be an array of operations")?;
+	for operation in operations {
+		let field = |name: &str| operation.get(name)
+			.ok_or_else(|| format!("Missing `{}` in operation {}", name, operation));
+		let op = field("op")?.as_str().ok_or_else(|| format!("Invalid `op` in operation {}", operation))?;
+		let path = field("path")?.as_str().ok_or_else(|| format!("Invalid `path` in operation {}", operation))?;
+		match op {
+			"add" => add(doc, path, field("value")?.clone())?,
+			"remove" => {
+				remove(doc, path)?;
+			},
+			"replace" => {
+				let value = field("value")?.clone();
+				if path.is_empty() {
+					*doc = value;
+				} else {
+					remove(doc, path)?;
+					add(doc, path, value)?;
+				}
+			},
+			op => return Err(format!("Unsupported JSON patch operation {:?}", op)),
+		}
+	}
+	Ok(())
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<(), String> {
+	if path.is_empty() {
+		*doc = value;
+		return Ok(());
+	}
+
+	let (parent, token) = split_parent(doc, path)?;
+	match parent {
+		Value::Object(map) => {
+			map.insert(token, value);
+		},
+		Value::Array(array) => {
+			let index = if token == "-" { array.len() } else { array_index(&token, array.len() + 1)? };
+			array.insert(index, value);
+		},
+		_ => return Err(format!("Cannot add {:?}, its parent is neither an object nor an array", path)),
+	}
+	Ok(())
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value, String> {
+	let (parent, token) = split_parent(doc, path)?;
+	let removed = match parent {
+		Value::Object(map) => map.remove(&token),
+		Value::Array(array) => match array_index(&token, array.len()) {
+			Ok(index) => Some(array.remove(index)),
+			Err(_) => None,
+		},
+		_ => None,
+	};
+	removed.ok_or_else(|| format!("Path {:?} does not exist", path))
+}
+
+/// Returns the value designated by the JSON pointer `path` without its last token, along with
+/// this unescaped last token.
+fn split_parent<'a>(doc: &'a mut Value, path: &str) -> Result<(&'a mut Value, String), String> {
+	if !path.starts_with('/') {
+		return Err(format!("Invalid JSON pointer {:?}", path));
+	}
+	let split = path.rfind('/').expect("path starts with a '/'; qed");
+	let (parent, token) = (&path[..split], &path[split + 1..]);
+	let parent_value = doc.pointer_mut(parent).ok_or_else(|| format!("Path {:?} does not exist", parent))?;
+	Ok((parent_value, token.replace("~1", "/").replace("~0", "~")))
+}
+
+fn array_index(token: &str, len: usize) -> Result<usize, String> {
+	token.parse::<usize>().ok()
+		.filter(|index| *index < len)
+		.ok_or_else(|| format!("Invalid array index {:?}", token))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn applies_operations_in_order() {
+		let mut doc = json!({
+			"name": "Flaming Fir",
+			"bootNodes": ["/ip4/10.0.0.1/tcp/30333"],
+			"genesis": { "raw": [{ "0x01": "0x02", "0x03": "0x04" }, {}] },
+		});
+		let patch = json!([
+			{ "op": "replace", "path": "/name", "value": "Fork" },
+			{ "op": "add", "path": "/bootNodes/-", "value": "/ip4/10.0.0.2/tcp/30333" },
+			{ "op": "remove", "path": "/bootNodes/0" },
+			{ "op": "add", "path": "/genesis/raw/0/0x05", "value": "0x06" },
+			{ "op": "remove", "path": "/genesis/raw/0/0x01" },
+			{ "op": "add", "path": "/a~1b~0c", "value": true },
+		]);
+
+		apply(&mut doc, &patch).unwrap();
+		assert_eq!(doc, json!({
+			"name": "Fork",
+			"bootNodes": ["/ip4/10.0.0.2/tcp/30333"],
+			"genesis": { "raw": [{ "0x03": "0x04", "0x05": "0x06" }, {}] },
+			"a/b~c": true,
+		}));
+	}
+
+	#[test]
+	fn rejects_invalid_operations() {
+		let mut doc = json!({ "name": "Flaming Fir", "bootNodes": [] });
+		let invalid = vec![
+			json!({ "op": "add", "path": "/name", "value": "Fork" }),
+			json!([{ "op": "move", "from": "/name", "path": "/id" }]),
+			json!([{ "op": "remove", "path": "/id" }]),
+			json!([{ "op": "replace", "path": "/id", "value": "fork" }]),
+			json!([{ "op": "add", "path": "/bootNodes/1", "value": "/ip4/10.0.0.2/tcp/30333" }]),
+			json!([{ "op": "add", "path": "name", "value": "Fork" }]),
+			json!([{ "op": "add", "path": "/name" }]),
+		];
+		for patch in invalid {
+			assert!(apply(&mut doc, &patch).is_err(), "{} should be rejected", patch);
+		}
+		assert_eq!(doc, json!({ "name": "Flaming Fir", "bootNodes": [] }));
+	}
+}
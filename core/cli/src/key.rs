@@ -0,0 +1,190 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implementation of the `key` subcommand, managing the keys in the keystore of the node
+//! directly on disk.
+
+use keystore::KeyStorePtr;
+use primitives::{
+	crypto::{KeyTypeId, Pair, Public, Ss58Codec},
+	ecdsa, ed25519, sr25519,
+	hexdisplay::HexDisplay,
+};
+
+use crate::{error, params::CryptoScheme};
+
+/// Call the function with the pair type of the given scheme.
+macro_rules! with_crypto_scheme {
+	( $scheme:expr, $method:ident ( $( $param:expr ),* ) ) => {
+		match $scheme {
+			CryptoScheme::Ed25519 => $method::<ed25519::Pair>( $( $param ),* ),
+			CryptoScheme::Sr25519 => $method::<sr25519::Pair>( $( $param ),* ),
+			CryptoScheme::Ecdsa => $method::<ecdsa::Pair>( $( $param ),* ),
+		}
+	}
+}
+
+/// Generate a new key of the given scheme and type and store it in the keystore.
+///
+/// Returns a description of the key, including its secret phrase.
+pub fn generate(
+	keystore: &KeyStorePtr,
+	password: Option<&str>,
+	scheme: CryptoScheme,
+	key_type: KeyTypeId,
+) -> error::Result<String> {
+	with_crypto_scheme!(scheme, generate_pair(keystore, password, key_type))
+}
+
+/// Describe the key given by a secret URI.
+pub fn inspect(uri: &str, password: Option<&str>, scheme: CryptoScheme) -> error::Result<String> {
+	with_crypto_scheme!(scheme, inspect_pair(uri, password))
+}
+
+/// Describe the public key given either hex encoded or as an SS58 address.
+pub fn inspect_public(public: &str, scheme: CryptoScheme) -> error::Result<String> {
+	with_crypto_scheme!(scheme, inspect_public_key(public))
+}
+
+/// Insert the key given by a secret URI into the keystore.
+///
+/// Returns a description of the public part of the key.
+pub fn insert(
+	keystore: &KeyStorePtr,
+	suri: &str,
+	scheme: CryptoScheme,
+	key_type: KeyTypeId,
+) -> error::Result<String> {
+	with_crypto_scheme!(scheme, insert_pair(keystore, suri, key_type))
+}
+
+fn generate_pair<P: Pair>(
+	keystore: &KeyStorePtr,
+	password: Option<&str>,
+	key_type: KeyTypeId,
+) -> error::Result<String> {
+	let (pair, phrase, _) = P::generate_with_phrase(password);
+	keystore.read()
+		.insert_by_type::<P>(key_type, &phrase)
+		.map_err(keystore_error)?;
+
+	Ok(format!("Secret phrase `{}` is:\n{}", phrase, describe_public(&pair.public())))
+}
+
+fn inspect_pair<P: Pair>(uri: &str, password: Option<&str>) -> error::Result<String> {
+	let (pair, seed) = P::from_string_with_seed(uri, password)
+		.map_err(|e| error::Error::Input(format!("Invalid secret URI {:?}: {:?}", uri, e)))?;
+	let seed = seed.map(|s| format!("  Secret seed:       0x{}\n", HexDisplay::from(&s.as_ref())));
+
+	Ok(format!(
+		"Secret URI `{}` is:\n{}{}",
+		uri,
+		seed.unwrap_or_default(),
+		describe_public(&pair.public()),
+	))
+}
+
+fn inspect_public_key<P: Pair>(public: &str) -> error::Result<String> {
+	let key = if public.starts_with("0x") {
+		decode_public::<P::Public>(&public[2..])
+	} else {
+		P::Public::from_string(public).ok()
+	};
+
+	key
+		.map(|key| format!("Public key `{}` is:\n{}", public, describe_public(&key)))
+		.ok_or_else(|| error::Error::Input(format!("Invalid public key {:?}", public)))
+}
+
+fn insert_pair<P: Pair>(keystore: &KeyStorePtr, suri: &str, key_type: KeyTypeId) -> error::Result<String> {
+	let pair = keystore.read()
+		.insert_by_type::<P>(key_type, suri)
+		.map_err(keystore_error)?;
+
+	Ok(format!("Inserted key:\n{}", describe_public(&pair.public())))
+}
+
+/// Decode a hex encoded public key of the right length.
+fn decode_public<T: Public>(hex: &str) -> Option<T> {
+	if hex.len() % 2 != 0 {
+		return None;
+	}
+	let bytes = (0..hex.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+		.collect::<Option<Vec<u8>>>()?;
+
+	if bytes.len() == T::default().as_ref().len() {
+		Some(T::from_slice(&bytes))
+	} else {
+		None
+	}
+}
+
+fn describe_public<T: Public>(public: &T) -> String {
+	format!(
+		"  Public key (hex):  0x{}\n  Address (SS58):    {}",
+		HexDisplay::from(&public.as_ref()),
+		public.to_ss58check(),
+	)
+}
+
+fn keystore_error(e: keystore::Error) -> error::Error {
+	error::Error::Other(format!("Keystore error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use keystore::Store;
+	use primitives::testing::{ED25519, SR25519};
+
+	#[test]
+	fn generated_key_is_stored() {
+		let dir = tempdir::TempDir::new("keystore").unwrap();
+		let keystore = Store::open(dir.path(), None).unwrap();
+
+		generate(&keystore, None, CryptoScheme::Sr25519, SR25519).unwrap();
+
+		assert_eq!(keystore.read().raw_public_keys(SR25519).unwrap().len(), 1);
+	}
+
+	#[test]
+	fn inserted_key_is_stored() {
+		let dir = tempdir::TempDir::new("keystore").unwrap();
+		let keystore = Store::open(dir.path(), None).unwrap();
+
+		insert(&keystore, "//Alice", CryptoScheme::Ed25519, ED25519).unwrap();
+
+		let alice = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		assert_eq!(keystore.read().raw_public_keys(ED25519).unwrap(), vec![alice.as_ref().to_vec()]);
+	}
+
+	#[test]
+	fn inspect_describes_secrets_and_public_keys() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		let description = describe_public(&alice);
+
+		assert!(inspect("//Alice", None, CryptoScheme::Sr25519).unwrap().ends_with(&description));
+
+		let hex = format!("0x{}", HexDisplay::from(&alice.as_ref()));
+		assert!(inspect_public(&hex, CryptoScheme::Sr25519).unwrap().ends_with(&description));
+		assert!(inspect_public(&alice.to_ss58check(), CryptoScheme::Sr25519).unwrap().ends_with(&description));
+
+		assert!(inspect_public("0x1234", CryptoScheme::Sr25519).is_err());
+		assert!(inspect("not a secret", None, CryptoScheme::Sr25519).is_err());
+	}
+}
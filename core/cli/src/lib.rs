@@ -23,6 +23,9 @@
 mod traits;
 mod params;
 mod execution_strategy;
+mod config_file;
+mod key;
+mod json_patch;
 pub mod error;
 pub mod informant;
 
@@ -39,11 +42,12 @@ use network::{
 		NetworkConfiguration, TransportConfig, NonReservedPeerMode, NodeKeyConfig, build_multiaddr
 	},
 };
-use primitives::H256;
+use primitives::{H256, crypto::Protected};
+use sr_primitives::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
 
 use std::{
-	io::{Write, Read, Seek, Cursor, stdin, stdout, ErrorKind}, iter, fs::{self, File},
-	net::{Ipv4Addr, SocketAddr}, path::{Path, PathBuf}, str::FromStr,
+	ffi::OsString, io::{Write, Read, Seek, Cursor, BufReader, stdin, stdout, ErrorKind}, iter, fs::{self, File},
+	net::{Ipv4Addr, SocketAddr}, path::{Path, PathBuf}, str::FromStr, sync::RwLock, time::Duration,
 };
 
 use names::{Generator, Name};
@@ -52,11 +56,14 @@ use structopt::{StructOpt, clap::AppSettings};
 #[doc(hidden)]
 pub use structopt::clap::App;
 use params::{
-	RunCmd, PurgeChainCmd, RevertCmd, ImportBlocksCmd, ExportBlocksCmd, BuildSpecCmd,
-	NetworkConfigurationParams, MergeParameters, TransactionPoolParams,
-	NodeKeyParams, NodeKeyType, Cors,
+	RunCmd, PurgeChainCmd, RevertCmd, ImportBlocksCmd, ExportBlocksCmd, ExportStateCmd, BuildSpecCmd,
+	ExportSnapshotCmd, ImportSnapshotCmd, MigrateDbCmd, NetworkConfigurationParams, MergeParameters, TransactionPoolParams,
+	NodeKeyParams, NodeKeyType, Cors, Database, KeyCmd, KeySubCmd, KeystoreParams, RpcMethods,
+};
+pub use params::{
+	NoCustom, CoreParams, SharedParams, ExecutionStrategy as ExecutionStrategyParam,
+	WasmExecutionMethod as WasmExecutionMethodParam,
 };
-pub use params::{NoCustom, CoreParams, SharedParams, ExecutionStrategy as ExecutionStrategyParam};
 pub use traits::{GetLogFilter, AugmentClap};
 use app_dirs::{AppInfo, AppDataType};
 use log::info;
@@ -69,8 +76,12 @@ use substrate_telemetry::TelemetryEndpoints;
 const DEFAULT_NETWORK_CONFIG_PATH : &'static str = "network";
 /// default sub directory to store database
 const DEFAULT_DB_CONFIG_PATH : &'static str = "db";
+/// default sub directory to store parity-db database
+const DEFAULT_PARITY_DB_CONFIG_PATH : &'static str = "paritydb";
 /// default sub directory for the key store
 const DEFAULT_KEYSTORE_CONFIG_PATH : &'static str =  "keystore";
+/// default file for the journal of the locally submitted transactions
+const DEFAULT_TRANSACTION_JOURNAL_PATH : &'static str = "transactions";
 
 /// The maximum number of characters for a node name.
 const NODE_NAME_MAX_LENGTH: usize = 32;
@@ -177,6 +188,9 @@ fn is_node_name_valid(_name: &str) -> Result<(), &str> {
 
 /// Parse command line interface arguments and prepares the command for execution.
 ///
+/// The options of the file given with `--config` are merged with the command line arguments, the
+/// latter taking precedence.
+///
 /// Before returning, this function performs various initializations, such as initializing the
 /// panic handler and the logger, or increasing the limit for file descriptors.
 ///
@@ -206,6 +220,14 @@ where
 
 	panic_handler::set(version.support_url, &full_version);
 
+	let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+	let merged_args = config_file::merge_args(args.clone()).unwrap_or_else(|e|
+		structopt::clap::Error::with_description(
+			&e.to_string(),
+			structopt::clap::ErrorKind::InvalidValue,
+		).exit()
+	);
+
 	let matches = CoreParams::<CC, RP>::clap()
 		.name(version.executable_name)
 		.author(version.author)
@@ -214,7 +236,7 @@ where
 		.setting(AppSettings::GlobalVersion)
 		.setting(AppSettings::ArgsNegateSubcommands)
 		.setting(AppSettings::SubcommandsNegateReqs)
-		.get_matches_from(args);
+		.get_matches_from(merged_args);
 	let cli_args = CoreParams::<CC, RP>::from_clap(&matches);
 
 	init_logger(cli_args.get_log_filter().as_ref().map(|v| v.as_ref()).unwrap_or(""));
@@ -222,7 +244,7 @@ where
 
 	match cli_args {
 		params::CoreParams::Run(params) => ParseAndPrepare::Run(
-			ParseAndPrepareRun { params, impl_name, version }
+			ParseAndPrepareRun { params, args, impl_name, version }
 		),
 		params::CoreParams::BuildSpec(params) => ParseAndPrepare::BuildSpec(
 			ParseAndPrepareBuildSpec { params, version }
@@ -230,6 +252,15 @@ where
 		params::CoreParams::ExportBlocks(params) => ParseAndPrepare::ExportBlocks(
 			ParseAndPrepareExport { params, version }
 		),
+		params::CoreParams::ExportState(params) => ParseAndPrepare::ExportState(
+			ParseAndPrepareExportState { params, version }
+		),
+		params::CoreParams::ExportSnapshot(params) => ParseAndPrepare::ExportSnapshot(
+			ParseAndPrepareExportSnapshot { params, version }
+		),
+		params::CoreParams::ImportSnapshot(params) => ParseAndPrepare::ImportSnapshot(
+			ParseAndPrepareImportSnapshot { params, version }
+		),
 		params::CoreParams::ImportBlocks(params) => ParseAndPrepare::ImportBlocks(
 			ParseAndPrepareImport { params, version }
 		),
@@ -239,6 +270,15 @@ where
 		params::CoreParams::Revert(params) => ParseAndPrepare::RevertChain(
 			ParseAndPrepareRevert { params, version }
 		),
+		params::CoreParams::MigrateDb(params) => ParseAndPrepare::MigrateDb(
+			ParseAndPrepareMigrateDb { params, version }
+		),
+		params::CoreParams::Key(params) => ParseAndPrepare::Key(
+			ParseAndPrepareKey { params, version }
+		),
+		params::CoreParams::PrintConfig(_) => ParseAndPrepare::PrintConfig(
+			ParseAndPreparePrintConfig { args }
+		),
 		params::CoreParams::Custom(params) => ParseAndPrepare::CustomCommand(params),
 	}
 }
@@ -263,12 +303,24 @@ pub enum ParseAndPrepare<'a, CC, RP> {
 	BuildSpec(ParseAndPrepareBuildSpec<'a>),
 	/// Command ready to export the chain.
 	ExportBlocks(ParseAndPrepareExport<'a>),
+	/// Command ready to export the state of a block.
+	ExportState(ParseAndPrepareExportState<'a>),
+	/// Command ready to export a snapshot of the state of a block.
+	ExportSnapshot(ParseAndPrepareExportSnapshot<'a>),
+	/// Command ready to initialize a database from a state snapshot.
+	ImportSnapshot(ParseAndPrepareImportSnapshot<'a>),
 	/// Command ready to import the chain.
 	ImportBlocks(ParseAndPrepareImport<'a>),
 	/// Command ready to purge the chain.
 	PurgeChain(ParseAndPreparePurge<'a>),
 	/// Command ready to revert the chain.
 	RevertChain(ParseAndPrepareRevert<'a>),
+	/// Command ready to migrate the database of the chain.
+	MigrateDb(ParseAndPrepareMigrateDb<'a>),
+	/// Command ready to manage the keys of the keystore.
+	Key(ParseAndPrepareKey<'a>),
+	/// Command ready to print the effective configuration.
+	PrintConfig(ParseAndPreparePrintConfig),
 	/// An additional custom command passed to `parse_and_prepare`.
 	CustomCommand(CC),
 }
@@ -276,11 +328,19 @@ pub enum ParseAndPrepare<'a, CC, RP> {
 /// Command ready to run the main client.
 pub struct ParseAndPrepareRun<'a, RP> {
 	params: MergeParameters<RunCmd, RP>,
+	args: Vec<OsString>,
 	impl_name: &'static str,
 	version: &'a VersionInfo,
 }
 
 impl<'a, RP> ParseAndPrepareRun<'a, RP> {
+	/// Returns the custom parameters of the command.
+	///
+	/// Useful when they affect the chain spec, which is loaded by `run`.
+	pub fn custom_params(&self) -> &RP {
+		&self.params.right
+	}
+
 	/// Runs the command and runs the main client.
 	pub fn run<C, G, CE, S, Exit, RS, E>(
 		self,
@@ -302,6 +362,16 @@ impl<'a, RP> ParseAndPrepareRun<'a, RP> {
 			self.params.left.clone(), spec_factory, self.impl_name, self.version,
 		)?;
 
+		#[cfg(unix)]
+		{
+			if self.params.left.config.is_some() {
+				config_file::reload_on_hangup(
+					self.args,
+					std::sync::Arc::downgrade(&config.config_reloader),
+				)?;
+			}
+		}
+
 		run_service(exit, self.params.left, self.params.right, config).map_err(Into::into)
 	}
 }
@@ -314,18 +384,34 @@ pub struct ParseAndPrepareBuildSpec<'a> {
 
 impl<'a> ParseAndPrepareBuildSpec<'a> {
 	/// Runs the command and build the chain specs.
-	pub fn run<C, G, S, E>(
+	///
+	/// The builder is only used by `--from-block`, to read the state of the block.
+	pub fn run_with_builder<C, G, E, F, B, S>(
 		self,
+		builder: F,
 		spec_factory: S
 	) -> error::Result<()> where
 		S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		F: FnOnce(Configuration<C, G, E>) -> Result<B, error::Error>,
+		B: ServiceBuilderExport,
+		B::Block: BlockT<Hash = H256>,
 		C: Default,
 		G: RuntimeGenesis,
 		E: ChainSpecExtension,
 	{
 		info!("Building chain spec");
-		let raw_output = self.params.raw;
-		let mut spec = load_spec(&self.params.shared_params, spec_factory)?;
+		let mut raw_output = self.params.raw;
+		let mut spec = match &self.params.from_block {
+			Some(block) => {
+				let block = parse_block_id::<B::Block>(block)?;
+				let config = create_config_with_db_path(spec_factory, &self.params.shared_params, self.version)?;
+				let mut spec = config.chain_spec.clone();
+				spec.set_storage(builder(config)?.export_raw_state(Some(block))?);
+				raw_output = true;
+				spec
+			},
+			None => load_spec(&self.params.shared_params, spec_factory)?,
+		};
 
 		if spec.boot_nodes().is_empty() && !self.params.disable_default_bootnode {
 			let base_path = base_path(&self.params.shared_params, self.version);
@@ -344,7 +430,20 @@ impl<'a> ParseAndPrepareBuildSpec<'a> {
 			spec.add_boot_node(addr)
 		}
 		
-		let json = service::chain_ops::build_spec(spec, raw_output)?;
+		let mut json = service::chain_ops::build_spec(spec, raw_output)?;
+
+		if !self.params.patches.is_empty() {
+			let mut doc: serde_json::Value = serde_json::from_str(&json)
+				.map_err(|e| format!("Error parsing the chain spec: {}", e))?;
+			for path in &self.params.patches {
+				let patch: serde_json::Value = serde_json::from_reader(File::open(path)?)
+					.map_err(|e| format!("Error parsing JSON patch {}: {}", path.display(), e))?;
+				json_patch::apply(&mut doc, &patch)
+					.map_err(|e| format!("Error applying JSON patch {}: {}", path.display(), e))?;
+			}
+			json = serde_json::to_string_pretty(&doc)
+				.map_err(|e| format!("Error serializing the chain spec: {}", e))?;
+		}
 
 		print!("{}", json);
 
@@ -393,6 +492,139 @@ impl<'a> ParseAndPrepareExport<'a> {
 	}
 }
 
+/// Command ready to export the state of a block.
+pub struct ParseAndPrepareExportState<'a> {
+	params: ExportStateCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareExportState<'a> {
+	/// Runs the command and prints the state of the block as a raw chain spec.
+	pub fn run_with_builder<C, G, E, F, B, S>(
+		self,
+		builder: F,
+		spec_factory: S,
+	) -> error::Result<()>
+	where S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		F: FnOnce(Configuration<C, G, E>) -> Result<B, error::Error>,
+		B: ServiceBuilderExport,
+		B::Block: BlockT<Hash = H256>,
+		C: Default,
+		G: RuntimeGenesis,
+		E: ChainSpecExtension,
+	{
+		let config = create_config_with_db_path(spec_factory, &self.params.shared_params, self.version)?;
+		let block = self.params.at.as_ref().map(|at| parse_block_id::<B::Block>(at)).transpose()?;
+
+		let mut spec = config.chain_spec.clone();
+		let raw_state = builder(config)?.export_raw_state(block)?;
+		spec.set_storage(raw_state);
+
+		let json = service::chain_ops::build_spec(spec, true)?;
+		print!("{}", json);
+
+		Ok(())
+	}
+}
+
+/// Command ready to export a snapshot of the state of a block.
+pub struct ParseAndPrepareExportSnapshot<'a> {
+	params: ExportSnapshotCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareExportSnapshot<'a> {
+	/// Runs the command and writes the snapshot.
+	pub fn run_with_builder<C, G, E, F, B, S>(
+		self,
+		builder: F,
+		spec_factory: S,
+	) -> error::Result<()>
+	where S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		F: FnOnce(Configuration<C, G, E>) -> Result<B, error::Error>,
+		B: ServiceBuilderExport,
+		B::Block: BlockT<Hash = H256>,
+		C: Default,
+		G: RuntimeGenesis,
+		E: ChainSpecExtension,
+	{
+		let config = create_config_with_db_path(spec_factory, &self.params.shared_params, self.version)?;
+		let block = self.params.at.as_ref().map(|at| parse_block_id::<B::Block>(at)).transpose()?;
+
+		let file: Box<dyn Write> = match self.params.output {
+			Some(filename) => Box::new(File::create(filename)?),
+			None => Box::new(stdout()),
+		};
+
+		let chunks = builder(config)?.export_snapshot(block, self.params.chunk_size, file)?;
+		info!("Exported {} chunks", chunks);
+		Ok(())
+	}
+}
+
+/// Command ready to initialize a database from a state snapshot.
+pub struct ParseAndPrepareImportSnapshot<'a> {
+	params: ImportSnapshotCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareImportSnapshot<'a> {
+	/// Runs the command and imports the snapshot into the database of the chain, which must not
+	/// exist yet.
+	///
+	/// The database is opened without starting a client, which would initialize it with the
+	/// genesis block, hence the block type has to be given explicitly.
+	pub fn run<B, G, E, S>(
+		self,
+		spec_factory: S,
+	) -> error::Result<()> where
+		B: BlockT<Hash = H256>,
+		S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		G: RuntimeGenesis,
+		E: ChainSpecExtension,
+	{
+		let config = create_config_with_db_path::<(), _, _, _>(
+			spec_factory, &self.params.shared_params, self.version
+		)?;
+		match &config.database {
+			DatabaseConfig::Path { path, .. } | DatabaseConfig::ParityDb { path } if path.exists() => {
+				return Err(format!(
+					"{} already exists, remove it with `purge-chain` first.",
+					path.display(),
+				).into());
+			},
+			_ => {},
+		}
+
+		let input: Box<dyn Read> = match self.params.input {
+			Some(filename) => Box::new(BufReader::new(File::open(filename)?)),
+			None => Box::new(stdin()),
+		};
+
+		let header = service::chain_ops::import_snapshot::<B, _, _, _>(&config, input)
+			.map_err(|e| format!(
+				"Failed to import the snapshot: {}. The database may hold a partial state and should \
+				be removed with `purge-chain`.",
+				e,
+			))?;
+		info!("Imported the state of block #{} ({})", header.number(), header.hash());
+		Ok(())
+	}
+}
+
+/// Parses a block given either as a `0x` prefixed hash or as a number.
+fn parse_block_id<B: BlockT<Hash = H256>>(block: &str) -> error::Result<BlockId<B>> {
+	if block.starts_with("0x") {
+		let hash = block[2..].parse::<H256>()
+			.map_err(|e| format!("Invalid block hash {}: {:?}", block, e))?;
+		Ok(BlockId::Hash(hash))
+	} else {
+		let number = block.parse::<u32>()
+			.map_err(|e| format!("Invalid block number {}: {}", block, e))?;
+		Ok(BlockId::Number(number.into()))
+	}
+}
+
 /// Command ready to import the chain.
 pub struct ParseAndPrepareImport<'a> {
 	params: ImportBlocksCmd,
@@ -433,8 +665,20 @@ impl<'a> ParseAndPrepareImport<'a> {
 		};
 
 		let fut = builder(config)?.import_blocks(exit.into_exit(), file)?;
-		tokio::run(fut);
+		run_import(fut)
+	}
+}
+
+/// Runs the future importing blocks to completion, and fails if any block couldn't be imported.
+fn run_import(fut: impl Future<Item=(), Error=()> + Send + 'static) -> error::Result<()> {
+	let (result_send, result_recv) = std::sync::mpsc::channel();
+	tokio::run(fut.then(move |result| {
+		let _ = result_send.send(result);
 		Ok(())
+	}));
+	match result_recv.recv() {
+		Ok(Ok(())) => Ok(()),
+		_ => Err("Failed to import the blocks".to_string().into()),
 	}
 }
 
@@ -446,40 +690,106 @@ pub struct ParseAndPreparePurge<'a> {
 
 impl<'a> ParseAndPreparePurge<'a> {
 	/// Runs the command and purges the chain.
-	pub fn run<G, E, S>(
+	///
+	/// The builder is only used by `--state-only`, which exports the blocks of the database and
+	/// imports them back into an empty one.
+	pub fn run_with_builder<C, G, E, F, B, S, Exit>(
 		self,
-		spec_factory: S
+		builder: F,
+		spec_factory: S,
+		exit: Exit,
 	) -> error::Result<()> where
-		S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		S: Fn(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		F: Fn(Configuration<C, G, E>) -> Result<B, error::Error>,
+		B: ServiceBuilderExport + ServiceBuilderImport,
+		C: Default,
 		G: RuntimeGenesis,
 		E: ChainSpecExtension,
+		Exit: IntoExit,
 	{
-		let config = create_config_with_db_path::<(), _, _, _>(
-			spec_factory, &self.params.shared_params, self.version
-		)?;
-		let db_path = match config.database {
-			DatabaseConfig::Path { path, .. } => path,
+		let config = create_config_with_db_path(&spec_factory, &self.params.shared_params, self.version)?;
+		let db_path = match &config.database {
+			DatabaseConfig::Path { path, .. } | DatabaseConfig::ParityDb { path } => path.clone(),
 			_ => {
 				eprintln!("Cannot purge custom database implementation");
 				return Ok(());
 			}
 		};
 
-		if !self.params.yes {
-			print!("Are you sure to remove {:?}? [y/N]: ", &db_path);
-			stdout().flush().expect("failed to flush stdout");
+		let question = if self.params.state_only {
+			format!("Are you sure to remove the state of {:?} and re-execute all its blocks?", &db_path)
+		} else if self.params.blocks_only {
+			format!(
+				"Are you sure to remove the bodies of all but the last {} finalized blocks from {:?}?",
+				self.params.keep_blocks.unwrap_or(0),
+				&db_path,
+			)
+		} else {
+			format!("Are you sure to remove {:?}?", &db_path)
+		};
+		if !self.params.yes && !confirm(&question)? {
+			println!("Aborted");
+			return Ok(());
+		}
+
+		if (self.params.state_only || self.params.blocks_only) && !db_path.exists() {
+			eprintln!("{:?} did not exist.", &db_path);
+			return Ok(());
+		}
+
+		if self.params.blocks_only {
+			let removed = service::chain_ops::purge_block_bodies(
+				&config.database,
+				self.params.keep_blocks.unwrap_or(0),
+			)?;
+			println!("Removed the bodies of {} blocks from {:?}.", removed, &db_path);
+			return Ok(());
+		}
 
-			let mut input = String::new();
-			stdin().read_line(&mut input)?;
-			let input = input.trim();
+		if self.params.state_only {
+			// The blocks are kept next to the database, so that they survive its removal.
+			let blocks_path = db_path.with_extension("blocks");
+			let exit = exit.into_exit().shared();
+			let exit_future = || exit.clone().map(drop).map_err(drop);
+
+			let blocks_file = File::create(&blocks_path)?;
+			let exported = builder(config).and_then(|builder| Ok(
+				builder.export_blocks(exit_future(), blocks_file, 1u32.into(), None, false)?
+			));
+			if let Err(err) = exported {
+				fs::remove_file(&blocks_path)?;
+				return Err(err);
+			}
+			if exit.peek().is_some() {
+				fs::remove_file(&blocks_path)?;
+				println!("Aborted");
+				return Ok(());
+			}
 
-			match input.chars().nth(0) {
-				Some('y') | Some('Y') => {},
-				_ => {
-					println!("Aborted");
-					return Ok(());
-				},
+			// From here on, the exported blocks are only removed once they are all imported back.
+			fs::remove_dir_all(&db_path)?;
+			let config = create_config_with_db_path(&spec_factory, &self.params.shared_params, self.version)?;
+			let imported = builder(config)
+				.and_then(|builder| Ok(builder.import_blocks(exit_future(), File::open(&blocks_path)?)?))
+				.and_then(run_import);
+			if let Err(err) = imported {
+				eprintln!(
+					"Failed to rebuild the state, the blocks can be imported from {:?} with `import-blocks`.",
+					&blocks_path,
+				);
+				return Err(err);
+			}
+			if exit.peek().is_some() {
+				eprintln!(
+					"Interrupted, the remaining blocks can be imported from {:?} with `import-blocks`.",
+					&blocks_path,
+				);
+				return Ok(());
 			}
+
+			fs::remove_file(&blocks_path)?;
+			println!("State of {:?} rebuilt.", &db_path);
+			return Ok(());
 		}
 
 		match fs::remove_dir_all(&db_path) {
@@ -496,6 +806,19 @@ impl<'a> ParseAndPreparePurge<'a> {
 	}
 }
 
+/// Asks the user a yes/no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> error::Result<bool> {
+	print!("{} [y/N]: ", question);
+	stdout().flush().expect("failed to flush stdout");
+
+	let mut input = String::new();
+	stdin().read_line(&mut input)?;
+	match input.trim().chars().nth(0) {
+		Some('y') | Some('Y') => Ok(true),
+		_ => Ok(false),
+	}
+}
+
 /// Command ready to revert the chain.
 pub struct ParseAndPrepareRevert<'a> {
 	params: RevertCmd,
@@ -520,11 +843,117 @@ impl<'a> ParseAndPrepareRevert<'a> {
 			spec_factory, &self.params.shared_params, self.version
 		)?;
 		let blocks = self.params.num;
-		builder(config)?.revert_chain(blocks.into())?;
+		builder(config)?.revert_chain(blocks.into(), self.params.force_finalized, self.params.dry_run)?;
 		Ok(())
 	}
 }
 
+/// Command ready to print the effective configuration.
+pub struct ParseAndPreparePrintConfig {
+	args: Vec<OsString>,
+}
+
+impl ParseAndPreparePrintConfig {
+	/// Runs the command and prints the configuration to stdout.
+	pub fn run(self) -> error::Result<()> {
+		print!("{}", config_file::effective_config(&self.args)?);
+		Ok(())
+	}
+}
+
+/// Command ready to migrate the database of the chain.
+pub struct ParseAndPrepareMigrateDb<'a> {
+	params: MigrateDbCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareMigrateDb<'a> {
+	/// Runs the command and converts the RocksDB database of the chain to parity-db.
+	pub fn run<G, E, S>(
+		self,
+		spec_factory: S
+	) -> error::Result<()> where
+		S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		G: RuntimeGenesis,
+		E: ChainSpecExtension,
+	{
+		let config = create_config_with_db_path::<(), _, _, _>(
+			spec_factory, &self.params.shared_params, self.version
+		)?;
+		let source = config.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH).expect("We provided a base_path.");
+		let target = config.in_chain_config_dir(DEFAULT_PARITY_DB_CONFIG_PATH)
+			.expect("We provided a base_path.");
+
+		info!("Migrating {} to {}", source.display(), target.display());
+		let copied = service::migrate_to_parity_db(&source, &target)?;
+		info!(
+			"Migrated {} entries. Run the node with `--database paritydb` to use the new database.",
+			copied,
+		);
+		Ok(())
+	}
+}
+
+/// Command ready to manage the keys of the keystore.
+pub struct ParseAndPrepareKey<'a> {
+	params: KeyCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareKey<'a> {
+	/// Runs the command on the keystore of the chain, without starting the node.
+	pub fn run<G, E, S>(
+		self,
+		spec_factory: S
+	) -> error::Result<()> where
+		S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		G: RuntimeGenesis,
+		E: ChainSpecExtension,
+	{
+		let output = match self.params.command {
+			KeySubCmd::Generate { kind, keystore_params, shared_params } => {
+				let (keystore, password) =
+					open_keystore(spec_factory, &shared_params, &keystore_params, self.version)?;
+				key::generate(&keystore, password.as_ref().map(|p| &***p), kind.scheme, kind.key_type)?
+			},
+			KeySubCmd::Inspect { uri, public: true, scheme, .. } => key::inspect_public(&uri, scheme)?,
+			KeySubCmd::Inspect { uri, password, scheme, .. } =>
+				key::inspect(&uri, password.as_ref().map(String::as_str), scheme)?,
+			KeySubCmd::Insert { suri, kind, keystore_params, shared_params } => {
+				let (keystore, _) = open_keystore(spec_factory, &shared_params, &keystore_params, self.version)?;
+				key::insert(&keystore, &suri, kind.scheme, kind.key_type)?
+			},
+		};
+
+		println!("{}", output);
+		Ok(())
+	}
+}
+
+/// Open the keystore selected by the parameters, returning it along with its password.
+fn open_keystore<G, E, S>(
+	spec_factory: S,
+	shared_params: &SharedParams,
+	keystore_params: &KeystoreParams,
+	version: &VersionInfo,
+) -> error::Result<(keystore::KeyStorePtr, Option<Protected<String>>)> where
+	S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+	G: RuntimeGenesis,
+	E: ChainSpecExtension,
+{
+	let path = match keystore_params.keystore_path {
+		Some(ref path) => path.clone(),
+		None => create_config_with_db_path::<(), _, _, _>(spec_factory, shared_params, version)?
+			.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH)
+			.expect("We provided a base_path."),
+	};
+	let password = keystore_password(keystore_params)?;
+	let keystore = keystore::Store::open(path, password.clone())
+		.map_err(|e| error::Error::Other(format!("Failed to open keystore: {}", e)))?;
+
+	Ok((keystore, password))
+}
+
 /// Create a `NodeKeyConfig` from the given `NodeKeyParams` in the context
 /// of an optional network config storage directory.
 fn node_key_config<P>(params: NodeKeyParams, net_config_dir: &Option<P>)
@@ -577,6 +1006,20 @@ fn fill_transaction_pool_configuration<C, G, E>(
 	options.transaction_pool.future.count = params.pool_limit / factor;
 	options.transaction_pool.future.total_bytes = params.pool_kbytes * 1024 / factor;
 
+	options.transaction_pool.max_per_sender = params.pool_sender_limit;
+	options.transaction_pool.replacement_bump = params.pool_replacement_bump;
+	options.transaction_pool.ban_time = Duration::from_secs(params.pool_ban_time);
+	options.transaction_pool.ban_senders = params.pool_ban_senders;
+	options.transaction_pool.revalidation_batch = params.pool_revalidation_batch;
+	options.transaction_pool.future_max_age = match params.pool_future_max_age {
+		0 => None,
+		secs => Some(Duration::from_secs(secs)),
+	};
+
+	if !params.no_pool_journal {
+		options.transaction_journal = options.in_chain_config_dir(DEFAULT_TRANSACTION_JOURNAL_PATH);
+	}
+
 	Ok(())
 }
 
@@ -622,6 +1065,11 @@ fn fill_network_configuration(
 
 	config.in_peers = cli.in_peers;
 	config.out_peers = cli.out_peers;
+	config.max_announced_digest_item_size = cli.max_announced_digest_item_size;
+	config.non_authority_propagation_delay = cli.non_authority_propagation_delay.map(Duration::from_millis);
+	config.sync_import_batch_size = cli.sync_import_batch_size;
+	config.sync_max_queued_blocks = cli.sync_max_queued_blocks;
+	config.sync_mode = cli.sync.map(Into::into);
 
 	config.transport = TransportConfig::Normal {
 		enable_mdns: !is_dev && !cli.no_mdns,
@@ -636,20 +1084,25 @@ fn input_keystore_password() -> Result<String, String> {
 		.map_err(|e| format!("{:?}", e))
 }
 
-/// Fill the password field of the given config instance.
-fn fill_config_keystore_password<C, G, E>(
-	config: &mut service::Configuration<C, G, E>,
-	cli: &RunCmd,
-) -> Result<(), String> {
-	config.keystore_password = if cli.password_interactive {
+/// Returns the keystore password selected by the given parameters.
+fn keystore_password(params: &KeystoreParams) -> Result<Option<Protected<String>>, String> {
+	Ok(if params.password_interactive {
 		Some(input_keystore_password()?.into())
-	} else if let Some(ref file) = cli.password_filename {
+	} else if let Some(ref file) = params.password_filename {
 		Some(fs::read_to_string(file).map_err(|e| format!("{}", e))?.into())
-	} else if let Some(ref password) = cli.password {
+	} else if let Some(ref password) = params.password {
 		Some(password.clone().into())
 	} else {
 		None
-	};
+	})
+}
+
+/// Fill the password field of the given config instance.
+fn fill_config_keystore_password<C, G, E>(
+	config: &mut service::Configuration<C, G, E>,
+	cli: &RunCmd,
+) -> Result<(), String> {
+	config.keystore_password = keystore_password(&cli.keystore_params)?;
 
 	Ok(())
 }
@@ -669,6 +1122,12 @@ where
 
 	fill_config_keystore_password(&mut config, &cli)?;
 
+	config.config_reloader.register("log", |value| match value {
+		serde_json::Value::Null => reload_logger(""),
+		serde_json::Value::String(pattern) => reload_logger(pattern),
+		value => Err(format!("Expected a log filter, got {}", value)),
+	});
+
 	config.impl_name = impl_name;
 	config.impl_commit = version.commit;
 	config.impl_version = version.version;
@@ -689,12 +1148,15 @@ where
 		)?
 	}
 
-	config.keystore_path = cli.keystore_path.or_else(|| config.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH));
+	config.keystore_path = cli.keystore_params.keystore_path.or_else(|| config.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH));
 
-	config.database = DatabaseConfig::Path {
-		path: config.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH).expect("We provided a base_path."),
-		cache_size: cli.database_cache_size,
+	let rocksdb = service::config::RocksDbSettings {
+		max_open_files: cli.database_max_open_files,
+		compaction: cli.database_compaction.into(),
+		column_memory_budgets: cli.database_column_budgets.iter().cloned().collect(),
 	};
+	config.database = database_config(&config, &cli.shared_params, cli.database_cache_size, rocksdb);
+	config.genesis_mismatch = cli.genesis_mismatch.into();
 	config.state_cache_size = cli.state_cache_size;
 
 	let is_dev = cli.shared_params.dev;
@@ -734,6 +1196,9 @@ where
 		},
 	};
 
+	config.blocks_pruning = cli.blocks_pruning;
+	config.index_extrinsics = cli.index_extrinsics;
+
 	config.wasm_method = cli.wasm_method.into();
 
 	let exec = cli.execution_strategies;
@@ -783,6 +1248,47 @@ where
 	config.rpc_ws = Some(parse_address(&format!("{}:{}", ws_interface, 9944), cli.ws_port)?);
 
 	config.rpc_ws_max_connections = cli.ws_max_connections;
+	config.rpc_ipc = cli.ipc_path;
+	config.rpc_call_fuel_limit = cli.rpc_call_fuel_limit;
+	config.rpc_allow_key_removal = cli.rpc_allow_key_removal;
+	let is_external = cli.rpc_external || cli.ws_external;
+	let deny_unsafe = rpc_deny_unsafe(cli.rpc_methods, cli.rpc_deny_unsafe, is_external);
+	if deny_unsafe == service::config::DenyUnsafe::No && is_external {
+		log::warn!("Serving the unsafe RPC methods on all interfaces, only do it on trusted networks.");
+	}
+	config.rpc_policy = service::config::RpcPolicy {
+		allowed_methods: if cli.rpc_allowed_methods.is_empty() {
+			None
+		} else {
+			Some(cli.rpc_allowed_methods.iter().cloned().collect())
+		},
+		denied_methods: cli.rpc_denied_methods.iter().cloned().collect(),
+		deny_unsafe,
+		rate_limits: cli.rpc_rate_limits.iter().cloned().collect(),
+		max_batch_size: cli.rpc_max_batch_size,
+		max_response_size: cli.rpc_max_response_size.map(|size| size.saturating_mul(1024 * 1024)),
+	};
+	config.rpc_max_payload = cli.rpc_max_payload.map(|size| size.saturating_mul(1024 * 1024));
+	config.chain_stats = if cli.chain_stats {
+		let default = service::config::StatsConfig::default();
+		Some(service::config::StatsConfig {
+			max_block_size: cli.chain_stats_max_block_size.unwrap_or(default.max_block_size),
+			..default
+		})
+	} else {
+		None
+	};
+	config.execution_metrics = cli.execution_metrics.map(|keep_blocks| service::config::ExecutionMetricsConfig {
+		keep_blocks,
+		meter_fuel: cli.execution_metrics_fuel,
+		proof_size: cli.execution_metrics_proof_size,
+	});
+	config.event_hooks = service::config::EventHooksConfig {
+		command: cli.event_hook_command,
+		webhook: cli.event_hook_url,
+		finality_stall: cli.event_hook_finality_stall.map(Duration::from_secs),
+		max_blocks_behind: cli.event_hook_blocks_behind,
+	};
 	config.rpc_cors = cli.rpc_cors.unwrap_or_else(|| if is_dev {
 		log::warn!("Running in --dev mode, RPC CORS has been disabled.");
 		Cors::All
@@ -810,6 +1316,17 @@ where
 	Ok(config)
 }
 
+/// Returns whether the HTTP & WS RPC servers deny the unsafe methods, given whether they listen to
+/// all interfaces.
+fn rpc_deny_unsafe(methods: RpcMethods, deny_unsafe: bool, is_external: bool) -> service::config::DenyUnsafe {
+	match methods {
+		_ if deny_unsafe => service::config::DenyUnsafe::Yes,
+		RpcMethods::Safe => service::config::DenyUnsafe::Yes,
+		RpcMethods::Auto if is_external => service::config::DenyUnsafe::Yes,
+		RpcMethods::Auto | RpcMethods::Unsafe => service::config::DenyUnsafe::No,
+	}
+}
+
 /// Creates a configuration including the database path.
 pub fn create_config_with_db_path<C, G, E, S>(
 	spec_factory: S, cli: &SharedParams, version: &VersionInfo,
@@ -824,14 +1341,31 @@ where
 	let base_path = base_path(cli, version);
 
 	let mut config = service::Configuration::default_with_spec_and_base_path(spec.clone(), Some(base_path));
-	config.database = DatabaseConfig::Path {
-		path: config.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH).expect("We provided a base_path."),
-		cache_size: None,
-	};
+	config.database = database_config(&config, cli, None, Default::default());
 
 	Ok(config)
 }
 
+/// Returns the configuration of the database selected by `cli`, stored in the chain directory.
+fn database_config<C, G, E>(
+	config: &Configuration<C, G, E>,
+	cli: &SharedParams,
+	cache_size: Option<u32>,
+	rocksdb: service::config::RocksDbSettings,
+) -> DatabaseConfig {
+	match cli.database {
+		Database::RocksDb => DatabaseConfig::Path {
+			path: config.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH).expect("We provided a base_path."),
+			cache_size,
+			rocksdb,
+		},
+		Database::ParityDb => DatabaseConfig::ParityDb {
+			path: config.in_chain_config_dir(DEFAULT_PARITY_DB_CONFIG_PATH)
+				.expect("We provided a base_path."),
+		},
+	}
+}
+
 /// Internal trait used to cast to a dynamic type that implements Read and Seek.
 trait ReadPlusSeek: Read + Seek {}
 
@@ -851,7 +1385,66 @@ fn parse_address(
 	Ok(address)
 }
 
+lazy_static! {
+	/// Logger all the log records are dispatched to, replaced when reloading the log filters.
+	static ref LOGGER: RwLock<Option<env_logger::Logger>> = RwLock::new(None);
+}
+
+/// Forwards the log records to the current `LOGGER`.
+struct ReloadableLogger;
+
+impl log::Log for ReloadableLogger {
+	fn enabled(&self, metadata: &log::Metadata) -> bool {
+		match LOGGER.read() {
+			Ok(logger) => logger.as_ref().map_or(false, |logger| logger.enabled(metadata)),
+			Err(_) => false,
+		}
+	}
+
+	fn log(&self, record: &log::Record) {
+		if let Ok(logger) = LOGGER.read() {
+			if let Some(logger) = logger.as_ref() {
+				logger.log(record);
+			}
+		}
+	}
+
+	fn flush(&self) {
+		if let Ok(logger) = LOGGER.read() {
+			if let Some(logger) = logger.as_ref() {
+				logger.flush();
+			}
+		}
+	}
+}
+
 fn init_logger(pattern: &str) {
+	if log::set_logger(&ReloadableLogger).is_err() {
+		info!("Not registering Substrate logger, as there is already a global logger registered!");
+		return;
+	}
+	if let Ok(mut current) = LOGGER.write() {
+		set_logger_filters(&mut current, pattern);
+	}
+}
+
+/// Replaces the log filters of the Substrate logger with the given pattern.
+fn reload_logger(pattern: &str) -> Result<(), String> {
+	let mut current = LOGGER.write().map_err(|_| "Logger lock is poisoned".to_string())?;
+	if current.is_none() {
+		return Err("Substrate logger isn't registered".into());
+	}
+	set_logger_filters(&mut current, pattern);
+	Ok(())
+}
+
+fn set_logger_filters(current: &mut Option<env_logger::Logger>, pattern: &str) {
+	let logger = logger_builder(pattern).build();
+	log::set_max_level(logger.filter());
+	*current = Some(logger);
+}
+
+fn logger_builder(pattern: &str) -> env_logger::Builder {
 	use ansi_term::Colour;
 
 	let mut builder = env_logger::Builder::new();
@@ -905,9 +1498,7 @@ fn init_logger(pattern: &str) {
 		writeln!(buf, "{}", output)
 	});
 
-	if builder.try_init().is_err() {
-		info!("Not registering Substrate logger, as there is already a global logger registered!");
-	}
+	builder
 }
 
 fn kill_color(s: &str) -> String {
@@ -1033,4 +1624,111 @@ mod tests {
 		assert!(no_config_dir().is_ok());
 		assert!(some_config_dir("x".to_string()).is_ok());
 	}
+	#[test]
+	fn test_database_tuning_params() {
+		let cli = RunCmd::from_iter(&[
+			"node", "--db-max-open-files", "512", "--db-compaction", "hdd",
+			"--db-column-budget", "1=256", "--db-column-budget", "4=64",
+		]);
+		assert_eq!(cli.database_max_open_files, Some(512));
+		assert_eq!(cli.database_compaction, params::DbCompaction::Hdd);
+		assert_eq!(cli.database_column_budgets, vec![(1, 256), (4, 64)]);
+
+		assert!(RunCmd::from_iter_safe(&["node", "--db-column-budget", "256"]).is_err());
+	}
+
+	#[test]
+	fn test_key_params() {
+		let cli = KeyCmd::from_iter(&[
+			"key", "insert", "--suri", "//Alice", "--key-type", "babe", "--scheme", "ed25519",
+			"--keystore-path", "/tmp/keystore",
+		]);
+		match cli.command {
+			KeySubCmd::Insert { suri, kind, keystore_params, .. } => {
+				assert_eq!(suri, "//Alice");
+				assert_eq!(kind.key_type, primitives::crypto::KeyTypeId(*b"babe"));
+				assert_eq!(kind.scheme, params::CryptoScheme::Ed25519);
+				assert_eq!(keystore_params.keystore_path, Some(PathBuf::from("/tmp/keystore")));
+			},
+			command => panic!("Unexpected command {:?}", command),
+		}
+
+		assert!(KeyCmd::from_iter_safe(&["key", "generate", "--key-type", "babel"]).is_err());
+		assert!(KeyCmd::from_iter_safe(&["key", "inspect", "--public", "--password", "x", "//Alice"]).is_err());
+	}
+
+	#[test]
+	fn test_rpc_policy_params() {
+		let cli = RunCmd::from_iter(&[
+			"node", "--rpc-deny-method", "author_rotateKeys",
+			"--rpc-rate-limit", "state_call=10", "--rpc-rate-limit", "state_getKeys=1",
+		]);
+		assert!(cli.rpc_allowed_methods.is_empty());
+		assert_eq!(cli.rpc_denied_methods, vec!["author_rotateKeys".to_string()]);
+		assert_eq!(cli.rpc_rate_limits, vec![("state_call".into(), 10), ("state_getKeys".into(), 1)]);
+
+		assert!(RunCmd::from_iter_safe(&["node", "--rpc-rate-limit", "state_call"]).is_err());
+
+		let deny_unsafe = |args: &[&str]| {
+			let cli = RunCmd::from_iter(args);
+			rpc_deny_unsafe(cli.rpc_methods, cli.rpc_deny_unsafe, cli.rpc_external || cli.ws_external)
+		};
+		assert_eq!(deny_unsafe(&["node"]), service::config::DenyUnsafe::No);
+		assert_eq!(deny_unsafe(&["node", "--rpc-external"]), service::config::DenyUnsafe::Yes);
+		assert_eq!(deny_unsafe(&["node", "--ws-external"]), service::config::DenyUnsafe::Yes);
+		assert_eq!(
+			deny_unsafe(&["node", "--ws-external", "--rpc-methods", "unsafe"]),
+			service::config::DenyUnsafe::No,
+		);
+		assert_eq!(deny_unsafe(&["node", "--rpc-methods", "safe"]), service::config::DenyUnsafe::Yes);
+		assert_eq!(
+			deny_unsafe(&["node", "--rpc-methods", "unsafe", "--rpc-deny-unsafe"]),
+			service::config::DenyUnsafe::Yes,
+		);
+		assert!(RunCmd::from_iter_safe(&["node", "--rpc-methods", "some"]).is_err());
+
+		let cli = RunCmd::from_iter(&[
+			"node", "--rpc-max-batch-size", "16", "--rpc-max-payload", "4", "--rpc-max-response-size", "1",
+		]);
+		assert_eq!(cli.rpc_max_batch_size, Some(16));
+		assert_eq!(cli.rpc_max_payload, Some(4));
+		assert_eq!(cli.rpc_max_response_size, Some(1));
+	}
+
+	#[test]
+	fn test_sync_mode_param() {
+		let cli = RunCmd::from_iter(&["node"]);
+		assert_eq!(cli.network_config.sync, None);
+
+		let cli = RunCmd::from_iter(&["node", "--sync", "warp"]);
+		assert_eq!(cli.network_config.sync, Some(params::SyncMode::Warp));
+		let mode: network::config::SyncMode = cli.network_config.sync.unwrap().into();
+		assert!(mode.downloads_state() && !mode.downloads_bodies());
+
+		assert!(RunCmd::from_iter_safe(&["node", "--sync", "instant"]).is_err());
+	}
+
+	#[test]
+	fn test_purge_chain_modes() {
+		let cli = PurgeChainCmd::from_iter(&["purge-chain"]);
+		assert!(!cli.state_only && !cli.blocks_only);
+
+		let cli = PurgeChainCmd::from_iter(&["purge-chain", "--blocks-only", "--keep-blocks", "100"]);
+		assert!(cli.blocks_only);
+		assert_eq!(cli.keep_blocks, Some(100));
+
+		assert!(PurgeChainCmd::from_iter_safe(&["purge-chain", "--state-only", "--blocks-only"]).is_err());
+		assert!(PurgeChainCmd::from_iter_safe(&["purge-chain", "--keep-blocks", "100"]).is_err());
+	}
+
+	#[test]
+	fn test_revert_flags() {
+		let cli = RevertCmd::from_iter(&["revert"]);
+		assert_eq!(cli.num, 256);
+		assert!(!cli.force_finalized && !cli.dry_run);
+
+		let cli = RevertCmd::from_iter(&["revert", "10", "--force-finalized", "--dry-run"]);
+		assert_eq!(cli.num, 10);
+		assert!(cli.force_finalized && cli.dry_run);
+	}
 }
@@ -16,7 +16,8 @@
 
 use crate::traits::{AugmentClap, GetLogFilter};
 
-use std::path::PathBuf;
+use std::{convert::TryFrom, path::PathBuf};
+use primitives::crypto::KeyTypeId;
 use structopt::{StructOpt, clap::{arg_enum, App, AppSettings, SubCommand, Arg}};
 
 pub use crate::execution_strategy::ExecutionStrategy;
@@ -57,7 +58,7 @@ arg_enum! {
 
 impl WasmExecutionMethod {
 	/// Returns list of variants that are not disabled by feature flags.
-	fn enabled_variants() -> Vec<&'static str> {
+	pub fn enabled_variants() -> Vec<&'static str> {
 		Self::variants()
 			.iter()
 			.cloned()
@@ -91,6 +92,92 @@ arg_enum! {
 	}
 }
 
+arg_enum! {
+	/// What to do when the genesis block of the database doesn't match the chain specification.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy)]
+	pub enum GenesisMismatch {
+		Refuse,
+		Purge,
+		Namespace,
+	}
+}
+
+arg_enum! {
+	/// Database backend to use.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum Database {
+		RocksDb,
+		ParityDb,
+	}
+}
+
+arg_enum! {
+	/// Compaction profile of the RocksDB database.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum DbCompaction {
+		Ssd,
+		Hdd,
+		Auto,
+	}
+}
+
+impl Into<service::config::CompactionStyle> for DbCompaction {
+	fn into(self) -> service::config::CompactionStyle {
+		match self {
+			DbCompaction::Ssd => service::config::CompactionStyle::Ssd,
+			DbCompaction::Hdd => service::config::CompactionStyle::Hdd,
+			DbCompaction::Auto => service::config::CompactionStyle::Auto,
+		}
+	}
+}
+
+arg_enum! {
+	/// How to sync the chain.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum SyncMode {
+		Full,
+		Light,
+		Fast,
+		Warp,
+	}
+}
+
+arg_enum! {
+	/// Methods exposed by the HTTP & WS RPC servers.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum RpcMethods {
+		Auto,
+		Safe,
+		Unsafe,
+	}
+}
+
+impl Into<network::config::SyncMode> for SyncMode {
+	fn into(self) -> network::config::SyncMode {
+		match self {
+			SyncMode::Full => network::config::SyncMode::Full,
+			SyncMode::Light => network::config::SyncMode::Light,
+			SyncMode::Fast => network::config::SyncMode::Fast,
+			SyncMode::Warp => network::config::SyncMode::Warp,
+		}
+	}
+}
+
+impl Into<service::config::GenesisMismatchBehavior> for GenesisMismatch {
+	fn into(self) -> service::config::GenesisMismatchBehavior {
+		match self {
+			GenesisMismatch::Refuse => service::config::GenesisMismatchBehavior::Refuse,
+			GenesisMismatch::Purge => service::config::GenesisMismatchBehavior::Purge,
+			GenesisMismatch::Namespace => service::config::GenesisMismatchBehavior::Namespace,
+		}
+	}
+}
+
 /// Shared parameters used by all `CoreParams`.
 #[derive(Debug, StructOpt, Clone)]
 pub struct SharedParams {
@@ -109,6 +196,16 @@ pub struct SharedParams {
 	/// Sets a custom logging filter.
 	#[structopt(short = "l", long = "log", value_name = "LOG_PATTERN")]
 	pub log: Option<String>,
+
+	/// Select database backend to use.
+	#[structopt(
+		long = "database",
+		value_name = "DB",
+		possible_values = &Database::variants(),
+		case_insensitive = true,
+		default_value = "RocksDb"
+	)]
+	pub database: Database,
 }
 
 impl GetLogFilter for SharedParams {
@@ -160,6 +257,46 @@ pub struct NetworkConfigurationParams {
 	#[structopt(long = "no-mdns")]
 	pub no_mdns: bool,
 
+	/// Omit the digest items larger than this many bytes from the announced block headers.
+	///
+	/// Reduces the bandwidth used by block announcements on chains with large seals. Peers
+	/// download the complete header when they need it.
+	#[structopt(long = "max-announced-digest-item-size", value_name = "BYTES")]
+	pub max_announced_digest_item_size: Option<usize>,
+
+	/// Propagate transactions to authorities first, and to the other peers after a random delay
+	/// of at most this many milliseconds.
+	///
+	/// Reduces the time for transactions to reach the block authors on large networks. By
+	/// default transactions are propagated to all peers at once.
+	#[structopt(long = "non-authority-propagation-delay", value_name = "MS")]
+	pub non_authority_propagation_delay: Option<u64>,
+
+	/// Maximum number of downloaded blocks handed over to the import queue at once during sync.
+	///
+	/// Default is 256.
+	#[structopt(long = "sync-import-batch-size", value_name = "BLOCKS")]
+	pub sync_import_batch_size: Option<usize>,
+
+	/// Maximum number of blocks waiting in the import queue during sync.
+	///
+	/// Downloaded blocks are kept aside until the queue drains below, which bounds the memory
+	/// used by the queue. Default is 2048.
+	#[structopt(long = "sync-max-queued-blocks", value_name = "BLOCKS")]
+	pub sync_max_queued_blocks: Option<usize>,
+
+	/// How to sync the chain.
+	///
+	/// Defaults to `Light` for light clients, and to `Full` otherwise. `Fast` and `Warp` are not
+	/// supported yet.
+	#[structopt(
+		long = "sync",
+		value_name = "MODE",
+		possible_values = &SyncMode::variants(),
+		case_insensitive = true,
+	)]
+	pub sync: Option<SyncMode>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub node_key_params: NodeKeyParams
@@ -244,6 +381,73 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "10240")]
 	pub pool_kbytes: usize,
+	/// Maximum number of transactions of a single sender in the transaction pool.
+	///
+	/// Unlimited by default. Only the transactions of known senders, e.g. signed transactions of
+	/// the node runtime, are limited.
+	#[structopt(long = "pool-sender-limit", value_name = "COUNT")]
+	pub pool_sender_limit: Option<usize>,
+	/// Percentage by which a transaction has to exceed the priority of the transaction
+	/// with the same sender and nonce it replaces in the pool.
+	#[structopt(long = "pool-replacement-bump", value_name = "PERCENT", default_value = "0")]
+	pub pool_replacement_bump: u32,
+	/// Number of seconds invalid transactions are banned from entering the pool again.
+	///
+	/// Banned transactions are rejected without being validated.
+	#[structopt(long = "pool-ban-time", value_name = "SECONDS", default_value = "1800")]
+	pub pool_ban_time: u64,
+	/// Also ban the senders of transactions which can't pay for their inclusion.
+	#[structopt(long = "pool-ban-senders")]
+	pub pool_ban_senders: bool,
+	/// Maximum number of transactions of the pool revalidated against the best block at once.
+	///
+	/// The transactions validated the longest time ago are revalidated every ten seconds,
+	/// and the ones which became invalid are dropped. Zero disables the revalidation.
+	#[structopt(long = "pool-revalidation-batch", value_name = "COUNT", default_value = "64")]
+	pub pool_revalidation_batch: usize,
+	/// Number of seconds a transaction can wait in the future queue before being dropped.
+	///
+	/// Transactions usually wait there because of a gap in the nonces of their sender.
+	/// Zero lets them wait until the pool is full.
+	#[structopt(long = "pool-future-max-age", value_name = "SECONDS", default_value = "1800")]
+	pub pool_future_max_age: u64,
+	/// Don't keep the transactions submitted through this node across restarts.
+	///
+	/// By default they are saved to disk, and submitted again on startup until they
+	/// are included or become invalid.
+	#[structopt(long = "no-pool-journal")]
+	pub no_pool_journal: bool,
+}
+
+/// Parameters of the keystore.
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeystoreParams {
+	/// Specify custom keystore path.
+	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+	pub keystore_path: Option<PathBuf>,
+
+	/// Use interactive shell for entering the password used by the keystore.
+	#[structopt(
+		long = "password-interactive",
+		conflicts_with_all = &[ "password", "password-filename" ]
+	)]
+	pub password_interactive: bool,
+
+	/// Password used by the keystore.
+	#[structopt(
+		long = "password",
+		conflicts_with_all = &[ "password-interactive", "password-filename" ]
+	)]
+	pub password: Option<String>,
+
+	/// File that contains the password used by the keystore.
+	#[structopt(
+		long = "password-filename",
+		value_name = "PATH",
+		parse(from_os_str),
+		conflicts_with_all = &[ "password-interactive", "password" ]
+	)]
+	pub password_filename: Option<PathBuf>,
 }
 
 /// Execution strategies parameters.
@@ -357,22 +561,64 @@ pub struct RunCmd {
 	#[structopt(long = "db-cache", value_name = "MiB")]
 	pub database_cache_size: Option<u32>,
 
+	/// Limit the number of files the RocksDB database keeps open.
+	#[structopt(long = "db-max-open-files", value_name = "COUNT")]
+	pub database_max_open_files: Option<i32>,
+
+	/// Compaction profile of the RocksDB database, depending on the kind of disk it is stored on.
+	#[structopt(
+		long = "db-compaction",
+		value_name = "PROFILE",
+		possible_values = &DbCompaction::variants(),
+		case_insensitive = true,
+		default_value = "Ssd"
+	)]
+	pub database_compaction: DbCompaction,
+
+	/// Give an additional memory budget to a column of the RocksDB database.
+	///
+	/// Can be given several times. The budgets are added to the database cache.
+	#[structopt(
+		long = "db-column-budget",
+		value_name = "COLUMN=MiB",
+		parse(try_from_str = parse_column_budget),
+		number_of_values = 1
+	)]
+	pub database_column_budgets: Vec<(u32, usize)>,
+
 	/// Specify the state cache size.
 	#[structopt(long = "state-cache-size", value_name = "Bytes", default_value = "67108864")]
 	pub state_cache_size: usize,
 
 	/// Listen to all RPC interfaces.
 	///
-	/// Default is local.
+	/// Default is local. The unsafe RPC methods are then denied, unless `--rpc-methods unsafe` is
+	/// given.
 	#[structopt(long = "rpc-external")]
 	pub rpc_external: bool,
 
 	/// Listen to all Websocket interfaces.
 	///
-	/// Default is local.
+	/// Default is local. The unsafe RPC methods are then denied, unless `--rpc-methods unsafe` is
+	/// given.
 	#[structopt(long = "ws-external")]
 	pub ws_external: bool,
 
+	/// Which RPC methods the HTTP & WS RPC servers serve.
+	///
+	/// `safe` denies the unsafe methods, which alter the keystore, the transaction pool or the
+	/// configuration of the node, reveal its keys or its peers, or are expensive to serve, like
+	/// `author_rotateKeys`, `system_networkState` or `state_getKeys`. `unsafe` serves them. `auto` denies them if
+	/// `--rpc-external` or `--ws-external` is given, and serves them otherwise.
+	#[structopt(
+		long = "rpc-methods",
+		value_name = "METHOD SET",
+		possible_values = &RpcMethods::variants(),
+		case_insensitive = true,
+		default_value = "Auto"
+	)]
+	pub rpc_methods: RpcMethods,
+
 	/// Specify HTTP RPC server TCP port.
 	#[structopt(long = "rpc-port", value_name = "PORT")]
 	pub rpc_port: Option<u16>,
@@ -385,6 +631,14 @@ pub struct RunCmd {
 	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
 	pub ws_max_connections: Option<usize>,
 
+	/// Serve RPC over the Unix socket, or the named pipe on Windows, at the given path.
+	///
+	/// The IPC server is only reachable by local processes, and serves the full API: the
+	/// restrictions of the HTTP & WS servers, like `--rpc-deny-method`, don't apply to it, and
+	/// `author_removeKeys` is allowed.
+	#[structopt(long = "ipc-path", value_name = "PATH")]
+	pub ipc_path: Option<String>,
+
 	/// Specify browser Origins allowed to access the HTTP & WS RPC servers.
 	///
 	/// A comma-separated list of origins (protocol://domain or special `null`
@@ -395,6 +649,123 @@ pub struct RunCmd {
 	#[structopt(long = "rpc-cors", value_name = "ORIGINS", parse(try_from_str = parse_cors))]
 	pub rpc_cors: Option<Cors>,
 
+	/// Limit the fuel available to runtime calls made through RPC (e.g. `state_call`).
+	///
+	/// Calls exceeding the limit are aborted. Limited calls are always executed in the
+	/// interpreted Wasm runtime. By default calls are unlimited.
+	#[structopt(long = "rpc-call-fuel-limit", value_name = "FUEL")]
+	pub rpc_call_fuel_limit: Option<u64>,
+
+	/// Allow removing keys from the keystore through the `author_removeKeys` RPC method.
+	///
+	/// Anyone able to reach the RPC interface can then delete the session keys of the node, only
+	/// use it with RPC interfaces that aren't exposed publicly.
+	#[structopt(long = "rpc-allow-key-removal")]
+	pub rpc_allow_key_removal: bool,
+
+	/// Only serve the given method on the HTTP & WS RPC servers.
+	///
+	/// Can be given several times. By default all the methods are served.
+	#[structopt(long = "rpc-allow-method", value_name = "METHOD", number_of_values = 1)]
+	pub rpc_allowed_methods: Vec<String>,
+
+	/// Don't serve the given method on the HTTP & WS RPC servers, even if it is allowed.
+	///
+	/// Can be given several times.
+	#[structopt(long = "rpc-deny-method", value_name = "METHOD", number_of_values = 1)]
+	pub rpc_denied_methods: Vec<String>,
+
+	/// Don't serve the unsafe methods on the HTTP & WS RPC servers, even if they are allowed.
+	///
+	/// Same as `--rpc-methods safe`, which takes precedence over `--rpc-methods unsafe`.
+	#[structopt(long = "rpc-deny-unsafe")]
+	pub rpc_deny_unsafe: bool,
+
+	/// Limit the number of calls per second to a method of the HTTP & WS RPC servers.
+	///
+	/// Can be given several times. The limit applies to the calls of all the clients together,
+	/// the calls exceeding it are refused.
+	#[structopt(
+		long = "rpc-rate-limit",
+		value_name = "METHOD=CALLS",
+		parse(try_from_str = parse_rate_limit),
+		number_of_values = 1
+	)]
+	pub rpc_rate_limits: Vec<(String, u32)>,
+
+	/// Maximum number of calls in a batch sent to the HTTP & WS RPC servers.
+	///
+	/// Larger batches are refused as a whole.
+	#[structopt(long = "rpc-max-batch-size", value_name = "CALLS")]
+	pub rpc_max_batch_size: Option<usize>,
+
+	/// Maximum size in MiB of the requests to the HTTP & WS RPC servers.
+	///
+	/// Default is 15 MiB.
+	#[structopt(long = "rpc-max-payload", value_name = "MiB")]
+	pub rpc_max_payload: Option<usize>,
+
+	/// Maximum size in MiB of the response to a call to the HTTP & WS RPC servers.
+	///
+	/// Calls with a larger response get an error instead. The responses of the calls of a batch
+	/// are limited individually.
+	#[structopt(long = "rpc-max-response-size", value_name = "MiB")]
+	pub rpc_max_response_size: Option<usize>,
+
+	/// Collect statistics of the most recent imported blocks.
+	///
+	/// The statistics are served through the `chain_getStats` RPC method.
+	#[structopt(long = "chain-stats")]
+	pub chain_stats: bool,
+
+	/// Maximal size in bytes of the extrinsics of a block, used to compute the fullness of blocks.
+	///
+	/// Should match the maximal block length of the runtime. Defaults to 5 MiB.
+	#[structopt(long = "chain-stats-max-block-size", value_name = "Bytes", requires = "chain-stats")]
+	pub chain_stats_max_block_size: Option<u32>,
+
+	/// Record the execution metrics of the given number of most recent imported blocks.
+	///
+	/// The wall time and storage accesses of the execution of each block are kept in the
+	/// database and served through the `chain_getExecutionMetrics` RPC method.
+	#[structopt(long = "execution-metrics", value_name = "BLOCKS")]
+	pub execution_metrics: Option<u32>,
+
+	/// Also measure the Wasm fuel consumed by the execution of blocks.
+	///
+	/// Metered blocks are always executed in the interpreted Wasm runtime, which is
+	/// significantly slower than the native runtime.
+	#[structopt(long = "execution-metrics-fuel", requires = "execution-metrics")]
+	pub execution_metrics_fuel: bool,
+
+	/// Also estimate the size of the storage proof that the execution of blocks would require.
+	///
+	/// Gives early feedback on the size of the proofs light clients would need to verify the
+	/// blocks, at the cost of a slower block import.
+	#[structopt(long = "execution-metrics-proof-size", requires = "execution-metrics")]
+	pub execution_metrics_proof_size: bool,
+
+	/// Run the given shell command on each event of the node.
+	///
+	/// The name of the event is given in the `SUBSTRATE_EVENT` environment variable, and its
+	/// details as JSON in `SUBSTRATE_EVENT_JSON`. The upgrades of the runtime are always reported,
+	/// see `--event-hook-finality-stall` and `--event-hook-blocks-behind` for the other events.
+	#[structopt(long = "event-hook-command", value_name = "COMMAND")]
+	pub event_hook_command: Option<String>,
+
+	/// Post the details of each event of the node as JSON to the given URL.
+	#[structopt(long = "event-hook-url", value_name = "URL")]
+	pub event_hook_url: Option<String>,
+
+	/// Report to the event hooks when no block was finalized for the given number of seconds.
+	#[structopt(long = "event-hook-finality-stall", value_name = "SECS")]
+	pub event_hook_finality_stall: Option<u64>,
+
+	/// Report to the event hooks when the best block is the given number of blocks behind the
+	/// best block seen on the network.
+	#[structopt(long = "event-hook-blocks-behind", value_name = "BLOCKS")]
+	pub event_hook_blocks_behind: Option<u32>,
+
 	/// Specify the state pruning mode, a number of blocks to keep or 'archive'.
 	///
 	/// Default is to keep all block states if the node is running as a
@@ -411,6 +782,34 @@ pub struct RunCmd {
 	#[structopt(long = "unsafe-pruning")]
 	pub unsafe_pruning: bool,
 
+	/// Specify the number of finalized blocks to keep the bodies and justifications of.
+	///
+	/// Older blocks are pruned independently of the state pruning mode and can't be served to
+	/// other nodes anymore. By default all blocks are kept.
+	#[structopt(long = "blocks-pruning", value_name = "COUNT")]
+	pub blocks_pruning: Option<u32>,
+
+	/// Maintain an index of extrinsics by hash, used by the `chain_getExtrinsic` RPC.
+	///
+	/// Only extrinsics of blocks imported while the index is enabled are indexed.
+	#[structopt(long = "index-extrinsics")]
+	pub index_extrinsics: bool,
+
+	/// What to do when the genesis block of an existing database doesn't match the chain spec.
+	///
+	/// `Refuse` stops the node. `Purge` removes the existing database and
+	/// starts from the new genesis. `Namespace` keeps the existing database
+	/// and uses a separate one for the new genesis. Mismatches are common on
+	/// development chains when the runtime or the genesis config changes.
+	#[structopt(
+		long = "on-genesis-mismatch",
+		value_name = "BEHAVIOR",
+		possible_values = &GenesisMismatch::variants(),
+		case_insensitive = true,
+		default_value = "Refuse"
+	)]
+	pub genesis_mismatch: GenesisMismatch,
+
 	/// The human-readable name for this node.
 	///
 	/// The node name will be reported to the telemetry server, if enabled.
@@ -478,32 +877,16 @@ pub struct RunCmd {
 	#[structopt(long = "force-authoring")]
 	pub force_authoring: bool,
 
-	/// Specify custom keystore path.
-	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
-	pub keystore_path: Option<PathBuf>,
-
-	/// Use interactive shell for entering the password used by the keystore.
-	#[structopt(
-		long = "password-interactive",
-		conflicts_with_all = &[ "password", "password-filename" ]
-	)]
-	pub password_interactive: bool,
-
-	/// Password used by the keystore.
-	#[structopt(
-		long = "password",
-		conflicts_with_all = &[ "password-interactive", "password-filename" ]
-	)]
-	pub password: Option<String>,
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
 
-	/// File that contains the password used by the keystore.
-	#[structopt(
-		long = "password-filename",
-		value_name = "PATH",
-		parse(from_os_str),
-		conflicts_with_all = &[ "password-interactive", "password" ]
-	)]
-	pub password_filename: Option<PathBuf>
+	/// Load options from the given TOML file.
+	///
+	/// The keys of the file are the long names of the command line options. Options given on the
+	/// command line take precedence over the ones of the file.
+	#[structopt(long = "config", value_name = "PATH", parse(from_os_str))]
+	pub config: Option<PathBuf>,
 }
 
 /// Stores all required Cli values for a keyring test account.
@@ -595,6 +978,23 @@ fn parse_telemetry_endpoints(s: &str) -> Result<(String, u8), Box<dyn std::error
 	}
 }
 
+/// Parse a key type, given as its four character identifier like `babe`.
+fn parse_key_type(s: &str) -> Result<KeyTypeId, String> {
+	KeyTypeId::try_from(s).map_err(|_| format!("Invalid key type {:?}, expected four characters", s))
+}
+
+/// Parse a memory budget of a database column, given as `COLUMN=MiB`.
+fn parse_column_budget(s: &str) -> Result<(u32, usize), Box<dyn std::error::Error>> {
+	let pos = s.find('=').ok_or("Expected a budget of the form `COLUMN=MiB`")?;
+	Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
+}
+
+/// Parse a rate limit of an RPC method, given as `METHOD=CALLS`.
+fn parse_rate_limit(s: &str) -> Result<(String, u32), Box<dyn std::error::Error>> {
+	let pos = s.find('=').ok_or("Expected a rate limit of the form `METHOD=CALLS`")?;
+	Ok((s[..pos].to_string(), s[pos + 1..].parse()?))
+}
+
 /// CORS setting
 ///
 /// The type is introduced to overcome `Option<Option<T>>`
@@ -650,6 +1050,21 @@ pub struct BuildSpecCmd {
 	#[structopt(long = "disable-default-bootnode")]
 	pub disable_default_bootnode: bool,
 
+	/// Take the genesis storage from the state of the given block of the database, instead of
+	/// building it from the chain specification.
+	///
+	/// Implies `--raw`. Combined with `--patch`, this allows starting a test network off the
+	/// state of an existing chain.
+	#[structopt(long = "from-block", value_name = "HASH or NUMBER")]
+	pub from_block: Option<String>,
+
+	/// JSON patch (RFC 6902) to apply to the resulting specification.
+	///
+	/// Can be given several times, the patches are then applied in order. Only the `add`, `remove`
+	/// and `replace` operations are supported.
+	#[structopt(long = "patch", value_name = "FILE", parse(from_os_str))]
+	pub patches: Vec<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -691,6 +1106,60 @@ pub struct ExportBlocksCmd {
 
 impl_get_log_filter!(ExportBlocksCmd);
 
+/// The `export-state` command used to export the state of a block as a chain spec.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportStateCmd {
+	/// Block hash (`0x` prefixed) or number to export the state of.
+	///
+	/// Default is best block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(ExportStateCmd);
+
+/// The `export-snapshot` command used to export a snapshot of the state of a block.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportSnapshotCmd {
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Block hash (`0x` prefixed) or number to export the state of.
+	///
+	/// Default is best block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<String>,
+
+	/// Maximal number of storage entries of a chunk of the snapshot.
+	#[structopt(long = "chunk-size", value_name = "COUNT", default_value = "4096")]
+	pub chunk_size: usize,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(ExportSnapshotCmd);
+
+/// The `import-snapshot` command used to initialize a database from a state snapshot.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ImportSnapshotCmd {
+	/// Input file or stdin if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub input: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(ImportSnapshotCmd);
+
 /// The `import-blocks` command used to import blocks.
 #[derive(Debug, StructOpt, Clone)]
 pub struct ImportBlocksCmd {
@@ -738,6 +1207,18 @@ pub struct RevertCmd {
 	#[structopt(default_value = "256")]
 	pub num: u32,
 
+	/// Also revert finalized blocks, as long as the state of their parent is still available.
+	///
+	/// The revert stops at the last finalized block changing the consensus authorities, e.g.
+	/// starting a new epoch or authority set, as the consensus state isn't reverted. Only meant for
+	/// recovering from a finalized fork, the node must not be running.
+	#[structopt(long = "force-finalized")]
+	pub force_finalized: bool,
+
+	/// Report the blocks that would be reverted, without modifying the database.
+	#[structopt(long = "dry-run")]
+	pub dry_run: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -745,13 +1226,30 @@ pub struct RevertCmd {
 
 impl_get_log_filter!(RevertCmd);
 
-/// The `purge-chain` command used to remove the whole chain.
+/// The `purge-chain` command used to remove the whole chain, or parts of it.
 #[derive(Debug, StructOpt, Clone)]
 pub struct PurgeChainCmd {
 	/// Skip interactive prompt by answering yes automatically.
 	#[structopt(short = "y")]
 	pub yes: bool,
 
+	/// Only remove the state, and rebuild it by re-executing the blocks of the database instead of
+	/// downloading them again.
+	#[structopt(long = "state-only", conflicts_with = "blocks-only")]
+	pub state_only: bool,
+
+	/// Only remove the bodies of old blocks, keeping the headers, justifications and state.
+	///
+	/// Run the node with `--blocks-pruning` afterwards to keep the bodies from growing again.
+	#[structopt(long = "blocks-only")]
+	pub blocks_only: bool,
+
+	/// Number of most recently finalized blocks whose bodies are kept by `--blocks-only`.
+	///
+	/// Defaults to 0.
+	#[structopt(long = "keep-blocks", value_name = "COUNT", requires = "blocks-only")]
+	pub keep_blocks: Option<u32>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -759,6 +1257,131 @@ pub struct PurgeChainCmd {
 
 impl_get_log_filter!(PurgeChainCmd);
 
+/// The `migrate-db` command used to convert the RocksDB database of a chain to parity-db.
+#[derive(Debug, StructOpt, Clone)]
+pub struct MigrateDbCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl_get_log_filter!(MigrateDbCmd);
+
+arg_enum! {
+	/// The cryptography scheme of a key.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum CryptoScheme {
+		Ed25519,
+		Sr25519,
+		Ecdsa,
+	}
+}
+
+/// Parameters selecting the kind of a key.
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeyKindParams {
+	/// The cryptography scheme of the key.
+	#[structopt(
+		long = "scheme",
+		value_name = "SCHEME",
+		possible_values = &CryptoScheme::variants(),
+		case_insensitive = true,
+		default_value = "Sr25519"
+	)]
+	pub scheme: CryptoScheme,
+
+	/// The key type the key is used for, e.g. `babe`, `gran` or `imon`.
+	#[structopt(long = "key-type", value_name = "KEY_TYPE", parse(try_from_str = parse_key_type))]
+	pub key_type: KeyTypeId,
+}
+
+/// The `key` command used to manage the keys in the keystore of the node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeyCmd {
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub command: KeySubCmd,
+}
+
+/// The operations of the `key` command.
+#[derive(Debug, StructOpt, Clone)]
+pub enum KeySubCmd {
+	/// Generate a new key and store it in the keystore.
+	///
+	/// Prints the secret phrase of the key, which should be kept as a backup.
+	#[structopt(name = "generate")]
+	Generate {
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		kind: KeyKindParams,
+
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		keystore_params: KeystoreParams,
+
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		shared_params: SharedParams,
+	},
+
+	/// Print the public key and address of a secret URI or of a public key.
+	#[structopt(name = "inspect")]
+	Inspect {
+		/// The secret URI, like a secret phrase with an optional derivation path, or with
+		/// `--public` the public key, either hex encoded or as an SS58 address.
+		uri: String,
+
+		/// Treat the input as a public key.
+		#[structopt(long = "public", conflicts_with = "password")]
+		public: bool,
+
+		/// The cryptography scheme of the key.
+		#[structopt(
+			long = "scheme",
+			value_name = "SCHEME",
+			possible_values = &CryptoScheme::variants(),
+			case_insensitive = true,
+			default_value = "Sr25519"
+		)]
+		scheme: CryptoScheme,
+
+		/// Password of the secret URI, if it doesn't contain one.
+		#[structopt(long = "password")]
+		password: Option<String>,
+	},
+
+	/// Insert an existing key, given by its secret URI, into the keystore.
+	#[structopt(name = "insert")]
+	Insert {
+		/// The secret URI of the key, like a secret phrase with an optional derivation path.
+		#[structopt(long = "suri", value_name = "SURI")]
+		suri: String,
+
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		kind: KeyKindParams,
+
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		keystore_params: KeystoreParams,
+
+		#[allow(missing_docs)]
+		#[structopt(flatten)]
+		shared_params: SharedParams,
+	},
+}
+
+impl GetLogFilter for KeyCmd {
+	fn get_log_filter(&self) -> Option<String> {
+		match &self.command {
+			KeySubCmd::Generate { shared_params, .. } | KeySubCmd::Insert { shared_params, .. } =>
+				shared_params.get_log_filter(),
+			KeySubCmd::Inspect { .. } => None,
+		}
+	}
+}
+
 /// All core commands that are provided by default.
 ///
 /// The core commands are split into multiple subcommands and `Run` is the default subcommand. From
@@ -775,6 +1398,15 @@ pub enum CoreParams<CC, RP> {
 	/// Export blocks to a file.
 	ExportBlocks(ExportBlocksCmd),
 
+	/// Export the state of a block as a raw chain spec.
+	ExportState(ExportStateCmd),
+
+	/// Export a snapshot of the state of a block.
+	ExportSnapshot(ExportSnapshotCmd),
+
+	/// Initialize an empty database from a state snapshot.
+	ImportSnapshot(ImportSnapshotCmd),
+
 	/// Import blocks from file.
 	ImportBlocks(ImportBlocksCmd),
 
@@ -784,6 +1416,15 @@ pub enum CoreParams<CC, RP> {
 	/// Remove the whole chain data.
 	PurgeChain(PurgeChainCmd),
 
+	/// Convert the RocksDB database of the chain to parity-db.
+	MigrateDb(MigrateDbCmd),
+
+	/// Manage the keys in the keystore.
+	Key(KeyCmd),
+
+	/// Print the effective configuration of the run command.
+	PrintConfig(MergeParameters<RunCmd, RP>),
+
 	/// Further custom subcommands.
 	Custom(CC),
 }
@@ -807,6 +1448,20 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 						if it is in binary format (not JSON!)."
 					)
 		)
+		.subcommand(
+			ExportStateCmd::augment_clap(SubCommand::with_name("export-state"))
+				.about("Export the state of a block as a raw chain spec, outputting to stdout.")
+		)
+		.subcommand(
+			ExportSnapshotCmd::augment_clap(SubCommand::with_name("export-snapshot"))
+				.about("Export a snapshot of the state of a block to a file.")
+		)
+		.subcommand(
+			ImportSnapshotCmd::augment_clap(SubCommand::with_name("import-snapshot"))
+				.about("Initialize an empty database from a state snapshot, instead of syncing \
+						the chain from genesis."
+					)
+		)
 		.subcommand(
 			ImportBlocksCmd::augment_clap(SubCommand::with_name("import-blocks"))
 				.about("Import blocks from file.")
@@ -819,6 +1474,21 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			PurgeChainCmd::augment_clap(SubCommand::with_name("purge-chain"))
 				.about("Remove the whole chain data.")
 		)
+		.subcommand(
+			MigrateDbCmd::augment_clap(SubCommand::with_name("migrate-db"))
+				.about("Convert the RocksDB database of the chain to parity-db.")
+		)
+		.subcommand(
+			KeyCmd::augment_clap(SubCommand::with_name("key"))
+				.setting(AppSettings::SubcommandRequiredElseHelp)
+				.about("Generate, inspect and insert keys of the keystore.")
+		)
+		.subcommand(
+			RunCmd::augment_clap(RP::augment_clap(SubCommand::with_name("print-config")))
+				.about("Print the effective configuration of the node, merging the file given with \
+						`--config` and the other options, in the TOML format of `--config`."
+					)
+		)
 	}
 
 	fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
@@ -827,11 +1497,22 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 				CoreParams::BuildSpec(BuildSpecCmd::from_clap(matches)),
 			("export-blocks", Some(matches)) =>
 				CoreParams::ExportBlocks(ExportBlocksCmd::from_clap(matches)),
+			("export-state", Some(matches)) =>
+				CoreParams::ExportState(ExportStateCmd::from_clap(matches)),
+			("export-snapshot", Some(matches)) =>
+				CoreParams::ExportSnapshot(ExportSnapshotCmd::from_clap(matches)),
+			("import-snapshot", Some(matches)) =>
+				CoreParams::ImportSnapshot(ImportSnapshotCmd::from_clap(matches)),
 			("import-blocks", Some(matches)) =>
 				CoreParams::ImportBlocks(ImportBlocksCmd::from_clap(matches)),
 			("revert", Some(matches)) => CoreParams::Revert(RevertCmd::from_clap(matches)),
 			("purge-chain", Some(matches)) =>
 				CoreParams::PurgeChain(PurgeChainCmd::from_clap(matches)),
+			("migrate-db", Some(matches)) =>
+				CoreParams::MigrateDb(MigrateDbCmd::from_clap(matches)),
+			("key", Some(matches)) => CoreParams::Key(KeyCmd::from_clap(matches)),
+			("print-config", Some(matches)) =>
+				CoreParams::PrintConfig(MergeParameters::from_clap(matches)),
 			(_, None) => CoreParams::Run(MergeParameters::from_clap(matches)),
 			_ => CoreParams::Custom(CC::from_clap(matches)),
 		}
@@ -844,8 +1525,14 @@ impl<CC, RP> GetLogFilter for CoreParams<CC, RP> where CC: GetLogFilter {
 			CoreParams::Run(c) => c.left.get_log_filter(),
 			CoreParams::BuildSpec(c) => c.get_log_filter(),
 			CoreParams::ExportBlocks(c) => c.get_log_filter(),
+			CoreParams::ExportState(c) => c.get_log_filter(),
+			CoreParams::ExportSnapshot(c) => c.get_log_filter(),
+			CoreParams::ImportSnapshot(c) => c.get_log_filter(),
 			CoreParams::ImportBlocks(c) => c.get_log_filter(),
 			CoreParams::PurgeChain(c) => c.get_log_filter(),
+			CoreParams::MigrateDb(c) => c.get_log_filter(),
+			CoreParams::Key(c) => c.get_log_filter(),
+			CoreParams::PrintConfig(c) => c.left.get_log_filter(),
 			CoreParams::Revert(c) => c.get_log_filter(),
 			CoreParams::Custom(c) => c.get_log_filter(),
 		}
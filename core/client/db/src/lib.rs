@@ -28,20 +28,26 @@
 
 pub mod light;
 pub mod offchain;
+pub mod upgrade;
 
 mod cache;
 mod storage_cache;
 mod utils;
+#[cfg(feature = "parity-db")]
+mod parity_db;
 
 use std::sync::Arc;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io;
 use std::collections::{HashMap, HashSet};
 
-use client::backend::NewBlockState;
-use client::blockchain::{well_known_cache_keys, HeaderBackend};
+use client::backend::{
+	NewBlockState, RevertedBlock, ChangesTrieConfigurationRange, PrunableStateChangesTrieStorage,
+};
+use client::blockchain::{well_known_cache_keys, Cache as BlockchainCache, HeaderBackend};
 use client::{ForkBlocks, ExecutionStrategies};
 use client::backend::{StorageCollection, ChildStorageCollection};
+use client::snapshot::SnapshotChunk;
 use client::error::{Result as ClientResult, Error as ClientError};
 use codec::{Decode, Encode};
 use hash_db::{Hasher, Prefix};
@@ -51,17 +57,18 @@ use parking_lot::{Mutex, RwLock};
 use primitives::{H256, Blake2Hasher, ChangesTrieConfiguration, convert_hash, traits::CodeExecutor};
 use primitives::storage::well_known_keys;
 use sr_primitives::{
-	generic::{BlockId, DigestItem}, Justification, StorageOverlay, ChildrenStorageOverlay,
+	generic::{BlockId, DigestItem, ChangesTrieSignal}, Justification, StorageOverlay, ChildrenStorageOverlay,
 	BuildStorage,
 };
 use sr_primitives::traits::{
-	Block as BlockT, Header as HeaderT, NumberFor, Zero, One, SaturatedConversion
+	Block as BlockT, Header as HeaderT, Hash as HashT, NumberFor, Zero, One, SaturatedConversion
 };
 use executor::RuntimeInfo;
 use state_machine::{
 	DBValue, ChangesTrieTransaction, ChangesTrieCacheAction, ChangesTrieBuildCache,
 	backend::Backend as StateBackend,
 };
+use crate::cache::{DbCache, DbCacheSync, DbCacheTransactionOps, ComplexBlockId, EntryType as CacheEntryType};
 use crate::utils::{Meta, db_err, meta_keys, read_db, read_meta};
 use client::leaves::{LeafSet, FinalizationDisplaced};
 use client::children;
@@ -76,6 +83,8 @@ use client::in_mem::Backend as InMemoryBackend;
 
 const CANONICALIZATION_DELAY: u64 = 4096;
 const MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR: u32 = 32768;
+/// Maximal number of blocks whose bodies are pruned in a single database transaction.
+const BLOCKS_PRUNING_BATCH: u64 = 1024;
 
 /// Default value for storage cache child ratio.
 const DEFAULT_CHILD_RATIO: (usize, usize) = (1, 10);
@@ -187,6 +196,20 @@ impl<B: BlockT> StateBackend<Blake2Hasher> for RefTrackingState<B> {
 		self.state.child_keys(child_key, prefix)
 	}
 
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		self.state.keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		self.state.child_keys_paged(child_key, prefix, start_key, count)
+	}
+
 	fn as_trie_backend(&mut self) -> Option<&state_machine::TrieBackend<Self::TrieBackendStorage, Blake2Hasher>> {
 		self.state.as_trie_backend()
 	}
@@ -200,10 +223,48 @@ pub struct DatabaseSettings {
 	pub state_cache_child_ratio: Option<(usize, usize)>,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Number of finalized blocks to keep the bodies and justifications of. `None` keeps all of them.
+	///
+	/// This is independent of the state pruning mode. Only the blocks of the finalized chain are
+	/// pruned, the blocks of abandoned forks are kept along with their headers.
+	pub blocks_pruning: Option<u32>,
+	/// Maintain an index of extrinsics by hash.
+	pub index_extrinsics: bool,
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
 }
 
+/// Tuning of a RocksDB database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RocksDbSettings {
+	/// Maximal number of files kept open by RocksDB. If `None` default is used.
+	pub max_open_files: Option<i32>,
+	/// Compaction profile.
+	pub compaction: CompactionStyle,
+	/// Memory budgets in MiB of specific columns, by column index.
+	///
+	/// The RocksDB bindings share a single memory budget evenly between all the columns, so these
+	/// budgets are added to the cache size of the database.
+	pub column_memory_budgets: HashMap<u32, usize>,
+}
+
+/// Compaction profile of a RocksDB database, depending on the kind of disk it is stored on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactionStyle {
+	/// Profile for solid-state drives.
+	Ssd,
+	/// Profile for hard disk drives.
+	Hdd,
+	/// Detect the kind of disk of the database path.
+	Auto,
+}
+
+impl Default for CompactionStyle {
+	fn default() -> Self {
+		CompactionStyle::Ssd
+	}
+}
+
 /// Where to find the database..
 pub enum DatabaseSettingsSrc {
 	/// Load a database from a given path. Recommended for most uses.
@@ -212,6 +273,14 @@ pub enum DatabaseSettingsSrc {
 		path: PathBuf,
 		/// Cache size in bytes. If `None` default is used.
 		cache_size: Option<usize>,
+		/// Tuning of the RocksDB database.
+		rocksdb: RocksDbSettings,
+	},
+
+	/// Load a parity-db database from a given path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
 	},
 
 	/// Use a custom already-open database.
@@ -250,6 +319,134 @@ pub fn new_client<E, S, Block, RA>(
 	))
 }
 
+/// Read the hash of the genesis block of the full database described by `settings`.
+///
+/// Returns `None` if the database hasn't been initialized with a genesis block yet.
+pub fn read_genesis_hash<Block: BlockT>(settings: &DatabaseSettings) -> ClientResult<Option<Block::Hash>> {
+	let db = crate::utils::open_database(settings, columns::META, "full")?;
+	match db.get(columns::META, meta_keys::GENESIS_HASH).map_err(db_err)? {
+		Some(hash) => Decode::decode(&mut &hash[..])
+			.map(Some)
+			.map_err(|err| ClientError::Backend(format!("Error decoding genesis hash: {}", err))),
+		None => Ok(None),
+	}
+}
+
+/// Initialize the empty full database described by `settings` with a state snapshot of the chain
+/// with the given genesis hash, see [`client::snapshot`].
+///
+/// The snapshot is streamed into the database: if the import fails, the database must be removed
+/// before trying again. Returns the header of the snapshot block.
+pub fn import_snapshot<Block: BlockT<Hash=H256>>(
+	settings: DatabaseSettings,
+	genesis_hash: Block::Hash,
+	input: impl io::Read,
+) -> ClientResult<Block::Header> {
+	let backend = Backend::<Block>::new(settings, CANONICALIZATION_DELAY)?;
+	client::snapshot::import_snapshot(&backend, genesis_hash, input)
+}
+
+/// Copy the content of the RocksDB database at `source` into a new parity-db database at `target`.
+///
+/// Works for both full and light databases. Returns the number of copied entries.
+#[cfg(all(feature = "kvdb-rocksdb", feature = "parity-db"))]
+pub fn migrate_to_parity_db(source: &Path, target: &Path) -> ClientResult<u64> {
+	/// Maximal number of entries written at once.
+	const BATCH_SIZE: usize = 16 * 1024;
+
+	if target.exists() {
+		return Err(ClientError::Backend(format!("{} already exists", target.display())));
+	}
+
+	let source = crate::utils::open_rocksdb(source, None, &Default::default())?;
+	let db_type = KeyValueDB::get(&source, crate::utils::COLUMN_META, meta_keys::TYPE)
+		.map_err(db_err)?
+		.map(|db_type| String::from_utf8_lossy(&db_type).into_owned())
+		.unwrap_or_default();
+	let target = crate::parity_db::open(
+		target,
+		crate::utils::NUM_COLUMNS,
+		crate::utils::iterable_columns(&db_type),
+	).map_err(db_err)?;
+
+	let mut copied = 0;
+	for col in (0..crate::utils::NUM_COLUMNS).map(Some) {
+		let mut transaction = DBTransaction::new();
+		// The inherent `iter` of RocksDB returns an `Option`.
+		for (key, value) in KeyValueDB::iter(&source, col) {
+			transaction.put(col, &key, &value);
+			copied += 1;
+			if transaction.ops.len() >= BATCH_SIZE {
+				target.write(std::mem::replace(&mut transaction, DBTransaction::new())).map_err(db_err)?;
+			}
+		}
+		target.write(transaction).map_err(db_err)?;
+	}
+
+	Ok(copied)
+}
+
+/// Copy the content of the RocksDB database at `source` into a new parity-db database at `target`.
+///
+/// Always fails, since support for RocksDB or parity-db is disabled.
+#[cfg(not(all(feature = "kvdb-rocksdb", feature = "parity-db")))]
+pub fn migrate_to_parity_db(_source: &Path, _target: &Path) -> ClientResult<u64> {
+	Err(ClientError::Backend("Migration requires both RocksDB and parity-db support".into()))
+}
+
+/// Remove the bodies of the blocks of a full node database, except for those of the `keep` most
+/// recently finalized blocks.
+///
+/// Headers, justifications and state are left untouched, so that the node can keep following the
+/// chain and still prove the finality of the purged blocks. Returns the number of removed bodies.
+pub fn purge_block_bodies(source: DatabaseSettingsSrc, keep: u32) -> ClientResult<u64> {
+	/// Maximal number of entries removed at once.
+	const BATCH_SIZE: usize = 16 * 1024;
+
+	let settings = DatabaseSettings {
+		state_cache_size: 0,
+		state_cache_child_ratio: None,
+		pruning: PruningMode::default(),
+		blocks_pruning: None,
+		index_extrinsics: false,
+		source,
+	};
+	let db = crate::utils::open_database(&settings, columns::META, "full")?;
+	let finalized: u32 = match db.get(columns::META, meta_keys::FINALIZED_BLOCK).map_err(db_err)? {
+		Some(lookup_key) => utils::lookup_key_to_number(&lookup_key)?,
+		None => return Ok(0),
+	};
+
+	let mut removed = 0;
+	let mut removed_hashes = HashSet::new();
+	let mut transaction = DBTransaction::new();
+	for (key, _) in db.iter(columns::BODY) {
+		let number: u32 = utils::lookup_key_to_number(&key)?;
+		if u64::from(number) + u64::from(keep) > u64::from(finalized) {
+			continue;
+		}
+		removed += 1;
+		removed_hashes.insert(key[4..].to_vec());
+		transaction.delete(columns::BODY, &key);
+		if transaction.ops.len() >= BATCH_SIZE {
+			db.write(std::mem::replace(&mut transaction, DBTransaction::new())).map_err(db_err)?;
+		}
+	}
+
+	let index_entries = db.iter(columns::AUX).filter(|(key, _)| key.starts_with(EXTRINSIC_INDEX_PREFIX));
+	for (key, _) in index_entries {
+		let mut locations = read_extrinsic_locations::<H256>(&*db, &key)?;
+		let len = locations.len();
+		locations.retain(|(block_hash, _)| !removed_hashes.contains(block_hash.as_bytes()));
+		if locations.len() != len {
+			write_extrinsic_locations(&mut transaction, &key, &locations);
+		}
+	}
+	db.write(transaction).map_err(db_err)?;
+
+	Ok(removed)
+}
+
 pub(crate) mod columns {
 	pub const META: Option<u32> = crate::utils::COLUMN_META;
 	pub const STATE: Option<u32> = Some(1);
@@ -263,6 +460,42 @@ pub(crate) mod columns {
 	pub const AUX: Option<u32> = Some(8);
 	/// Offchain workers local storage
 	pub const OFFCHAIN: Option<u32> = Some(9);
+	/// Blockchain data cache (changes tries configurations).
+	pub const CACHE: Option<u32> = Some(10);
+	/// Columns that are iterated, e.g. to read the leaves or to prune block bodies.
+	pub const ITERABLE: &[Option<u32>] = &[META, HEADER, BODY, JUSTIFICATION, AUX];
+}
+
+/// Prefix of the keys of the extrinsic index.
+///
+/// The index is kept in the auxiliary column rather than in a column of its own, so that it can be
+/// enabled on existing databases.
+const EXTRINSIC_INDEX_PREFIX: &[u8] = b"extrinsic_index";
+
+fn extrinsic_index_key<H: AsRef<[u8]>>(extrinsic_hash: &H) -> Vec<u8> {
+	let mut key = EXTRINSIC_INDEX_PREFIX.to_vec();
+	key.extend_from_slice(extrinsic_hash.as_ref());
+	key
+}
+
+/// Read the locations of an extrinsic from the index, as `(block_hash, index)`.
+///
+/// An extrinsic has several locations when it is included in blocks of different forks.
+fn read_extrinsic_locations<H: Decode>(db: &dyn KeyValueDB, key: &[u8]) -> ClientResult<Vec<(H, u32)>> {
+	match db.get(columns::AUX, key).map_err(db_err)? {
+		Some(locations) => Decode::decode(&mut &locations[..]).map_err(|err| ClientError::Backend(
+			format!("Error decoding extrinsic locations: {}", err)
+		)),
+		None => Ok(Vec::new()),
+	}
+}
+
+fn write_extrinsic_locations<H: Encode>(transaction: &mut DBTransaction, key: &[u8], locations: &[(H, u32)]) {
+	if locations.is_empty() {
+		transaction.delete(columns::AUX, key);
+	} else {
+		transaction.put(columns::AUX, key, &locations.encode());
+	}
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -289,10 +522,11 @@ pub struct BlockchainDb<Block: BlockT> {
 	meta: Arc<RwLock<Meta<NumberFor<Block>, Block::Hash>>>,
 	leaves: RwLock<LeafSet<Block::Hash, NumberFor<Block>>>,
 	header_metadata_cache: HeaderMetadataCache<Block>,
+	index_extrinsics: bool,
 }
 
 impl<Block: BlockT> BlockchainDb<Block> {
-	fn new(db: Arc<dyn KeyValueDB>) -> ClientResult<Self> {
+	fn new(db: Arc<dyn KeyValueDB>, index_extrinsics: bool) -> ClientResult<Self> {
 		let meta = read_meta::<Block>(&*db, columns::META, columns::HEADER)?;
 		let leaves = LeafSet::read_from_db(&*db, columns::META, meta_keys::LEAF_PREFIX)?;
 		Ok(BlockchainDb {
@@ -300,6 +534,7 @@ impl<Block: BlockT> BlockchainDb<Block> {
 			leaves: RwLock::new(leaves),
 			meta: Arc::new(RwLock::new(meta)),
 			header_metadata_cache: HeaderMetadataCache::default(),
+			index_extrinsics,
 		})
 	}
 
@@ -401,6 +636,22 @@ impl<Block: BlockT> client::blockchain::Backend<Block> for BlockchainDb<Block> {
 		Ok(self.meta.read().finalized_hash.clone())
 	}
 
+	fn extrinsic_location(&self, hash: &Block::Hash) -> ClientResult<Option<(Block::Hash, u32)>> {
+		if !self.index_extrinsics {
+			return Err(ClientError::ExtrinsicIndexDisabled);
+		}
+		let locations = read_extrinsic_locations::<Block::Hash>(&*self.db, &extrinsic_index_key(hash))?;
+		// prefer the block of the canonical chain, then the most recently imported one.
+		for &(block_hash, index) in locations.iter().rev() {
+			if let Some(number) = self.number(block_hash)? {
+				if self.hash(number)? == Some(block_hash) {
+					return Ok(Some((block_hash, index)));
+				}
+			}
+		}
+		Ok(locations.last().cloned())
+	}
+
 	fn cache(&self) -> Option<Arc<dyn client::blockchain::Cache<Block>>> {
 		None
 	}
@@ -453,11 +704,14 @@ pub struct BlockImportOperation<Block: BlockT, H: Hasher> {
 	child_storage_updates: ChildStorageCollection,
 	changes_trie_updates: MemoryDB<H>,
 	changes_trie_cache_update: Option<ChangesTrieCacheAction<H::Out, NumberFor<Block>>>,
+	changes_trie_config_update: Option<Option<ChangesTrieConfiguration>>,
 	pending_block: Option<PendingBlock<Block>>,
 	aux_ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 	finalized_blocks: Vec<(BlockId<Block>, Option<Justification>)>,
 	set_head: Option<BlockId<Block>>,
+	base_genesis_hash: Option<Block::Hash>,
 	commit_state: bool,
+	db: Arc<dyn KeyValueDB>,
 }
 
 impl<Block: BlockT, H: Hasher> BlockImportOperation<Block, H> {
@@ -526,6 +780,12 @@ impl<Block> client::backend::BlockImportOperation<Block, Blake2Hasher>
 			.map(|(storage_key, child_overlay)|
 				(storage_key, child_overlay.into_iter().map(|(k, v)| (k, Some(v)))));
 
+		let changes_trie_config = top.get(well_known_keys::CHANGES_TRIE_CONFIG)
+			.map(|config| Decode::decode(&mut &config[..]))
+			.transpose()
+			.map_err(|_| client::error::Error::GenesisInvalid)?;
+		self.changes_trie_config_update = Some(changes_trie_config);
+
 		let (root, transaction) = self.old_state.full_storage_root(
 			top.into_iter().map(|(k, v)| (k, Some(v))),
 			child_delta
@@ -536,6 +796,22 @@ impl<Block> client::backend::BlockImportOperation<Block, Blake2Hasher>
 		Ok(root)
 	}
 
+	fn import_state(
+		&mut self,
+		chunks: &mut dyn Iterator<Item=ClientResult<SnapshotChunk>>,
+	) -> ClientResult<H256> {
+		let mut import = StateImport::new(self.db.clone());
+		for chunk in chunks {
+			import.import_chunk(chunk?)?;
+		}
+		let (root, changes_trie_config) = import.finish()?;
+
+		self.changes_trie_config_update = Some(changes_trie_config);
+		self.db_updates = PrefixedMemoryDB::default();
+		self.commit_state = true;
+		Ok(root)
+	}
+
 	fn update_changes_trie(
 		&mut self,
 		update: ChangesTrieTransaction<Blake2Hasher, NumberFor<Block>>,
@@ -572,6 +848,11 @@ impl<Block> client::backend::BlockImportOperation<Block, Blake2Hasher>
 		self.set_head = Some(block);
 		Ok(())
 	}
+
+	fn set_base_block(&mut self, genesis_hash: Block::Hash) -> ClientResult<()> {
+		self.base_genesis_hash = Some(genesis_hash);
+		Ok(())
+	}
 }
 
 struct StorageDb<Block: BlockT> {
@@ -613,16 +894,160 @@ impl state_machine::Storage<Blake2Hasher> for DbGenesisStorage {
 	}
 }
 
+/// Trie nodes read straight from the state column, bypassing the state database.
+struct StateColumn(Arc<dyn KeyValueDB>);
+
+impl state_machine::Storage<Blake2Hasher> for StateColumn {
+	fn get(&self, key: &H256, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let key = prefixed_key::<Blake2Hasher>(key, prefix);
+		self.0.get(columns::STATE, &key).map(|r| r.map(|v| DBValue::from_slice(&v)))
+			.map_err(|e| format!("Database backend error: {:?}", e))
+	}
+}
+
+/// Writes the state of a snapshot to the state column as its chunks are read.
+///
+/// Every chunk is inserted in the trie built so far, and the new trie nodes are written right
+/// away, outside of the transaction of the import operation. The nodes replaced by later chunks
+/// are left in the database, and so are the nodes of a failed import.
+struct StateImport {
+	db: Arc<dyn KeyValueDB>,
+	empty_root: H256,
+	/// Root of the trie being built.
+	root: H256,
+	/// Storage key of the trie being built, if it is a child trie.
+	child_storage_key: Option<Vec<u8>>,
+	/// Roots of the child tries built so far, inserted in the top trie at the end.
+	child_roots: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+	top_started: bool,
+	changes_trie_config: Option<ChangesTrieConfiguration>,
+}
+
+impl StateImport {
+	fn new(db: Arc<dyn KeyValueDB>) -> Self {
+		let empty_root = DbGenesisStorage::new().0;
+		StateImport {
+			db,
+			empty_root,
+			root: empty_root,
+			child_storage_key: None,
+			child_roots: Vec::new(),
+			top_started: false,
+			changes_trie_config: None,
+		}
+	}
+
+	fn import_chunk(&mut self, chunk: SnapshotChunk) -> ClientResult<()> {
+		match chunk.child_storage_key {
+			Some(child_storage_key) => {
+				// the roots of the child tries are needed to build the top trie.
+				if self.top_started || !well_known_keys::is_child_storage_key(&child_storage_key) {
+					return Err(client::error::Error::GenesisInvalid.into());
+				}
+				if self.child_storage_key.as_ref() != Some(&child_storage_key) {
+					self.finish_child_trie();
+					self.child_storage_key = Some(child_storage_key);
+				}
+			},
+			None => {
+				if !self.top_started {
+					self.finish_child_trie();
+					self.top_started = true;
+				}
+				for (key, value) in &chunk.entries {
+					if well_known_keys::is_child_storage_key(key) {
+						return Err(client::error::Error::GenesisInvalid.into());
+					}
+					if &key[..] == well_known_keys::CHANGES_TRIE_CONFIG {
+						let config = Decode::decode(&mut &value[..])
+							.map_err(|_| client::error::Error::GenesisInvalid)?;
+						self.changes_trie_config = Some(config);
+					}
+				}
+			},
+		}
+
+		self.insert(chunk.entries.into_iter().map(|(k, v)| (k, Some(v))).collect())
+	}
+
+	fn finish_child_trie(&mut self) {
+		if let Some(child_storage_key) = self.child_storage_key.take() {
+			let root = std::mem::replace(&mut self.root, self.empty_root);
+			if root != self.empty_root {
+				self.child_roots.push((child_storage_key, Some(root.as_ref().to_vec())));
+			}
+		}
+	}
+
+	fn insert(&mut self, delta: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> ClientResult<()> {
+		let storage: Arc<dyn state_machine::Storage<Blake2Hasher>> = Arc::new(StateColumn(self.db.clone()));
+		let (root, mut nodes) = DbState::new(storage, self.root).storage_root(delta);
+
+		let mut transaction = DBTransaction::new();
+		for (key, (value, rc)) in nodes.drain() {
+			if rc > 0 {
+				transaction.put(columns::STATE, &key, &value);
+			}
+		}
+		self.db.write(transaction).map_err(db_err)?;
+		self.root = root;
+		Ok(())
+	}
+
+	fn finish(mut self) -> ClientResult<(H256, Option<ChangesTrieConfiguration>)> {
+		self.finish_child_trie();
+		let child_roots = std::mem::replace(&mut self.child_roots, Vec::new());
+		self.insert(child_roots)?;
+		Ok((self.root, self.changes_trie_config))
+	}
+}
+
 /// A database wrapper for changes tries.
 pub struct DbChangesTrieStorage<Block: BlockT> {
 	db: Arc<dyn KeyValueDB>,
 	meta: Arc<RwLock<Meta<NumberFor<Block>, Block::Hash>>>,
 	min_blocks_to_keep: Option<u32>,
 	cache: RwLock<ChangesTrieBuildCache<Block::Hash, NumberFor<Block>>>,
+	/// Changes tries configurations, keyed by the blocks where they have been set.
+	config_cache: DbCacheSync<Block>,
 	_phantom: ::std::marker::PhantomData<Block>,
 }
 
 impl<Block: BlockT<Hash=H256>> DbChangesTrieStorage<Block> {
+	/// Create new changes trie storage.
+	fn new(
+		db: Arc<dyn KeyValueDB>,
+		meta: Arc<RwLock<Meta<NumberFor<Block>, Block::Hash>>>,
+		min_blocks_to_keep: Option<u32>,
+	) -> Self {
+		let config_cache = DbCacheSync(RwLock::new(Self::new_config_cache(db.clone(), &*meta.read())));
+		DbChangesTrieStorage {
+			db,
+			meta,
+			min_blocks_to_keep,
+			cache: RwLock::new(ChangesTrieBuildCache::new()),
+			config_cache,
+			_phantom: Default::default(),
+		}
+	}
+
+	fn new_config_cache(db: Arc<dyn KeyValueDB>, meta: &Meta<NumberFor<Block>, Block::Hash>) -> DbCache<Block> {
+		DbCache::new(
+			db,
+			columns::KEY_LOOKUP,
+			columns::HEADER,
+			columns::CACHE,
+			meta.genesis_hash,
+			ComplexBlockId::new(meta.finalized_hash, meta.finalized_number),
+		)
+	}
+
+	/// Drop the in-memory state of the configuration cache, so that it is read from the database
+	/// again. This is required when the finalized block goes backwards.
+	fn reset_config_cache(&self) {
+		*self.config_cache.0.write() = Self::new_config_cache(self.db.clone(), &*self.meta.read());
+	}
+
 	/// Commit new changes trie.
 	pub fn commit(&self, tx: &mut DBTransaction, mut changes_trie: MemoryDB<Blake2Hasher>) {
 		for (key, (val, _)) in changes_trie.drain() {
@@ -635,49 +1060,191 @@ impl<Block: BlockT<Hash=H256>> DbChangesTrieStorage<Block> {
 		self.cache.write().perform(cache_update);
 	}
 
+	/// Insert the configuration of the new block into the configuration cache. The configuration
+	/// is only inserted if it has been changed at this block, or if this is the first block of the
+	/// database. Returns the cache operations that are to be committed after the transaction.
+	fn insert_config(
+		&self,
+		tx: &mut DBTransaction,
+		parent_hash: Block::Hash,
+		block_hash: Block::Hash,
+		block_num: NumberFor<Block>,
+		new_config: Option<Option<ChangesTrieConfiguration>>,
+		entry_type: CacheEntryType,
+	) -> ClientResult<DbCacheTransactionOps<Block>> {
+		let parent_num = if block_num.is_zero() { Zero::zero() } else { block_num - One::one() };
+		let mut cache_at = HashMap::new();
+		if let Some(new_config) = new_config {
+			cache_at.insert(well_known_cache_keys::CHANGES_TRIE_CONFIG, new_config.encode());
+		}
+
+		Ok(self.config_cache.0.write().transaction(tx)
+			.on_block_insert(
+				ComplexBlockId::new(parent_hash, parent_num),
+				ComplexBlockId::new(block_hash, block_num),
+				cache_at,
+				entry_type,
+			)?
+			.into_ops())
+	}
+
+	/// Commit the configuration cache operations after the transaction has been committed.
+	fn commit_config_cache(&self, ops: DbCacheTransactionOps<Block>) {
+		self.config_cache.0.write().commit(ops);
+	}
+
+	/// Finalize the previously inserted block in the configuration cache.
+	///
+	/// Several blocks could be finalized within the same transaction and every finalization
+	/// depends on the previous one, so the cache operations are committed right away.
+	fn finalize(
+		&self,
+		tx: &mut DBTransaction,
+		parent_hash: Block::Hash,
+		block_hash: Block::Hash,
+		block_num: NumberFor<Block>,
+	) -> ClientResult<()> {
+		let parent_num = if block_num.is_zero() { Zero::zero() } else { block_num - One::one() };
+		let mut config_cache = self.config_cache.0.write();
+		let ops = config_cache.transaction(tx)
+			.on_block_finalize(
+				ComplexBlockId::new(parent_hash, parent_num),
+				ComplexBlockId::new(block_hash, block_num),
+			)?
+			.into_ops();
+		config_cache.commit(ops);
+		Ok(())
+	}
+
+	/// Revert the non-finalized block in the configuration cache. Returns the cache operations
+	/// that are to be committed after the transaction.
+	fn revert(
+		&self,
+		tx: &mut DBTransaction,
+		block_hash: Block::Hash,
+		block_num: NumberFor<Block>,
+	) -> ClientResult<DbCacheTransactionOps<Block>> {
+		Ok(self.config_cache.0.write().transaction(tx)
+			.on_block_revert(&ComplexBlockId::new(block_hash, block_num))?
+			.into_ops())
+	}
+
 	/// Prune obsolete changes tries.
-	pub fn prune(
+	///
+	/// Changes tries are pruned in ranges that are covered by the max-level digests (or by the
+	/// skewed digests at the end of configurations). The range is pruned when all its changes
+	/// tries are older than `min_blocks_to_keep` blocks.
+	fn prune(
 		&self,
-		config: &ChangesTrieConfiguration,
 		tx: &mut DBTransaction,
 		block_hash: Block::Hash,
 		block_num: NumberFor<Block>,
-	) {
+	) -> ClientResult<()> {
 		// never prune on archive nodes
 		let min_blocks_to_keep = match self.min_blocks_to_keep {
 			Some(min_blocks_to_keep) => min_blocks_to_keep,
-			None => return,
+			None => return Ok(()),
 		};
 
-		state_machine::prune_changes_tries(
-			config,
-			&*self,
-			min_blocks_to_keep.into(),
-			&state_machine::ChangesTrieAnchorBlockId {
-				hash: convert_hash(&block_hash),
-				number: block_num,
-			},
-			|node| tx.delete(columns::CHANGES_TRIE, node.as_ref()));
+		let anchor = state_machine::ChangesTrieAnchorBlockId {
+			hash: convert_hash(&block_hash),
+			number: block_num,
+		};
+		let mut next_to_prune = self.oldest_non_pruned_block(block_num)?;
+		while next_to_prune <= block_num {
+			// the changes trie of the block is built using the configuration of its parent
+			let config_range = self.configuration_at(&BlockId::Number(next_to_prune - One::one()))?;
+			let config_end = config_range.end.map(|(end, _)| end);
+			let range_end = match config_range.config {
+				Some(ref config) => {
+					// when digests are disabled, every changes trie is pruned on its own
+					let digest_end = config
+						.next_max_level_digest_range(config_range.zero.0, next_to_prune)
+						.map(|(_, digest_end)| digest_end)
+						.unwrap_or(next_to_prune);
+					match config_end {
+						Some(config_end) if config_end < digest_end => config_end,
+						_ => digest_end,
+					}
+				},
+				// there are no changes tries until the configuration is changed
+				None => match config_end {
+					Some(config_end) => config_end,
+					None => break,
+				},
+			};
+
+			if range_end > block_num || block_num - range_end < min_blocks_to_keep.into() {
+				break;
+			}
+
+			if config_range.config.is_some() {
+				state_machine::prune_changes_tries(
+					&*self,
+					next_to_prune,
+					range_end,
+					&anchor,
+					|node| tx.delete(columns::CHANGES_TRIE, node.as_ref()),
+				);
+			}
+
+			next_to_prune = range_end + One::one();
+		}
+
+		tx.put(columns::META, meta_keys::CHANGES_TRIES_META, &next_to_prune.encode());
+		Ok(())
+	}
+
+	/// Get number of the oldest block whose changes trie isn't pruned yet.
+	fn oldest_non_pruned_block(&self, best_finalized: NumberFor<Block>) -> ClientResult<NumberFor<Block>> {
+		if let Some(next_to_prune) = self.db.get(columns::META, meta_keys::CHANGES_TRIES_META).map_err(db_err)? {
+			return Decode::decode(&mut &next_to_prune[..])
+				.map_err(|_| ClientError::Backend("Error decoding changes tries meta".into()));
+		}
+
+		// databases that have been created before the configuration could be changed were pruning
+		// changes tries using the genesis configuration, without keeping the pruning meta
+		let genesis_config = match self.min_blocks_to_keep {
+			Some(min_blocks_to_keep) => self.configuration_at(&BlockId::Number(best_finalized))
+				.ok()
+				.filter(|config_range| config_range.zero.0.is_zero())
+				.and_then(|config_range| config_range.config)
+				.map(|config| (config, min_blocks_to_keep)),
+			None => None,
+		};
+		Ok(match genesis_config {
+			Some((config, min_blocks_to_keep)) => state_machine::oldest_non_pruned_changes_trie(
+				&config,
+				min_blocks_to_keep.into(),
+				best_finalized,
+			),
+			None => One::one(),
+		})
 	}
 }
 
-impl<Block> client::backend::PrunableStateChangesTrieStorage<Block, Blake2Hasher>
+impl<Block> PrunableStateChangesTrieStorage<Block, Blake2Hasher>
 	for DbChangesTrieStorage<Block>
 where
 	Block: BlockT<Hash=H256>,
 {
-	fn oldest_changes_trie_block(
+	fn configuration_at(
 		&self,
-		config: &ChangesTrieConfiguration,
-		best_finalized_block: NumberFor<Block>,
-	) -> NumberFor<Block> {
+		at: &BlockId<Block>,
+	) -> ClientResult<ChangesTrieConfigurationRange<NumberFor<Block>, Block::Hash>> {
+		self.config_cache
+			.get_at(&well_known_cache_keys::CHANGES_TRIE_CONFIG, at)
+			.and_then(|(zero, end, encoded)| Decode::decode(&mut &encoded[..]).ok()
+				.map(|config| ChangesTrieConfigurationRange { zero, end, config }))
+			.ok_or_else(|| ClientError::Backend(
+				format!("Failed to read changes tries configuration at {:?}", at)
+			))
+	}
+
+	fn oldest_changes_trie_block(&self) -> ClientResult<NumberFor<Block>> {
 		match self.min_blocks_to_keep {
-			Some(min_blocks_to_keep) => state_machine::oldest_non_pruned_changes_trie(
-				config,
-				min_blocks_to_keep.into(),
-				best_finalized_block,
-			),
-			None => One::one(),
+			Some(_) => self.oldest_non_pruned_block(self.meta.read().finalized_number),
+			None => Ok(One::one()),
 		}
 	}
 }
@@ -766,6 +1333,12 @@ where
 		self.cache.read().with_changed_keys(root, functor)
 	}
 
+	fn configuration_zero(&self, parent: &state_machine::ChangesTrieAnchorBlockId<H256, NumberFor<Block>>) -> Result<NumberFor<Block>, String> {
+		self.configuration_at(&BlockId::Hash(parent.hash))
+			.map(|config_range| config_range.zero.0)
+			.map_err(|e| e.to_string())
+	}
+
 	fn get(&self, key: &H256, _prefix: Prefix) -> Result<Option<DBValue>, String> {
 		self.db.get(columns::CHANGES_TRIE, &key[..])
 			.map_err(|err| format!("{}", err))
@@ -778,11 +1351,9 @@ pub struct Backend<Block: BlockT> {
 	storage: Arc<StorageDb<Block>>,
 	offchain_storage: offchain::LocalStorage,
 	changes_tries_storage: DbChangesTrieStorage<Block>,
-	/// None<*> means that the value hasn't been cached yet. Some(*) means that the value (either None or
-	/// Some(*)) has been cached and is valid.
-	changes_trie_config: Mutex<Option<Option<ChangesTrieConfiguration>>>,
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
+	blocks_pruning: Option<u32>,
 	shared_cache: SharedCache<Block, Blake2Hasher>,
 	import_lock: Mutex<()>,
 	is_archive: bool,
@@ -805,6 +1376,8 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(keep_blocks),
+			blocks_pruning: None,
+			index_extrinsics: false,
 			source: DatabaseSettingsSrc::Custom(db),
 		};
 
@@ -817,7 +1390,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		config: &DatabaseSettings
 	) -> ClientResult<Self> {
 		let is_archive_pruning = config.pruning.is_archive();
-		let blockchain = BlockchainDb::new(db.clone())?;
+		let blockchain = BlockchainDb::new(db.clone(), config.index_extrinsics)?;
 		let meta = blockchain.meta.clone();
 		let map_e = |e: state_db::Error<io::Error>| ::client::error::Error::from(format!("State database error: {:?}", e));
 		let state_db: StateDb<_, _> = StateDb::new(config.pruning.clone(), &StateMetaDb(&*db)).map_err(map_e)?;
@@ -826,28 +1399,55 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			state_db,
 		};
 		let offchain_storage = offchain::LocalStorage::new(db.clone());
-		let changes_tries_storage = DbChangesTrieStorage {
+		let changes_tries_storage = DbChangesTrieStorage::new(
 			db,
 			meta,
-			min_blocks_to_keep: if is_archive_pruning { None } else { Some(MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR) },
-			cache: RwLock::new(ChangesTrieBuildCache::new()),
-			_phantom: Default::default(),
-		};
+			if is_archive_pruning { None } else { Some(MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR) },
+		);
 
-		Ok(Backend {
+		let backend = Backend {
 			storage: Arc::new(storage_db),
 			offchain_storage,
 			changes_tries_storage,
-			changes_trie_config: Mutex::new(None),
 			blockchain,
 			canonicalization_delay,
+			blocks_pruning: config.blocks_pruning,
 			shared_cache: new_shared_cache(
 				config.state_cache_size,
 				config.state_cache_child_ratio.unwrap_or(DEFAULT_CHILD_RATIO),
 			),
 			import_lock: Default::default(),
 			is_archive: is_archive_pruning,
-		})
+		};
+
+		backend.initialize_changes_tries_config_cache()?;
+
+		Ok(backend)
+	}
+
+	/// Fill the changes tries configuration cache of the database that has been created before
+	/// the cache has been introduced. The configuration couldn't be changed back then, so the
+	/// configuration of the finalized block is the genesis configuration.
+	fn initialize_changes_tries_config_cache(&self) -> ClientResult<()> {
+		let (genesis_hash, finalized_hash) = {
+			let meta = self.blockchain.meta.read();
+			(meta.genesis_hash, meta.finalized_hash)
+		};
+		if genesis_hash == Default::default() {
+			return Ok(());
+		}
+
+		let config_cache = &self.changes_tries_storage.config_cache;
+		let finalized = BlockId::Hash(finalized_hash);
+		if config_cache.get_at(&well_known_cache_keys::CHANGES_TRIE_CONFIG, &finalized).is_some() {
+			return Ok(());
+		}
+
+		use client::backend::Backend;
+		let config: Option<ChangesTrieConfiguration> = self.state_at(finalized)?
+			.storage(well_known_keys::CHANGES_TRIE_CONFIG)?
+			.and_then(|v| Decode::decode(&mut &*v).ok());
+		config_cache.initialize(&well_known_cache_keys::CHANGES_TRIE_CONFIG, config.encode())
 	}
 
 	/// Returns in-memory blockchain that contains the same set of blocks that the self.
@@ -892,8 +1492,20 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			inmem.commit_operation(op).unwrap();
 		}
 
-		// and now finalize the best block we have
-		inmem.finalize_block(BlockId::Hash(info.finalized_hash), None).unwrap();
+		// and now finalize the blocks up to the last finalized block we have, in order
+		let mut finalized = Vec::new();
+		let mut hash = info.finalized_hash;
+		while hash != info.genesis_hash {
+			let header = match HeaderBackend::header(&self.blockchain, BlockId::Hash(hash)).unwrap() {
+				Some(header) => header,
+				None => break,
+			};
+			finalized.push(hash);
+			hash = *header.parent_hash();
+		}
+		for hash in finalized.into_iter().rev() {
+			inmem.finalize_block(BlockId::Hash(hash), None).unwrap();
+		}
 
 		inmem
 	}
@@ -904,26 +1516,6 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		self.blockchain.db.iter(columns::HEADER).count() as u64
 	}
 
-	/// Read (from storage or cache) changes trie config.
-	///
-	/// Currently changes tries configuration is set up once (at genesis) and could not
-	/// be changed. Thus, we'll actually read value once and then just use cached value.
-	fn changes_trie_config(&self, block: Block::Hash) -> ClientResult<Option<ChangesTrieConfiguration>> {
-		let mut cached_changes_trie_config = self.changes_trie_config.lock();
-		match cached_changes_trie_config.clone() {
-			Some(cached_changes_trie_config) => Ok(cached_changes_trie_config),
-			None => {
-				use client::backend::Backend;
-				let changes_trie_config = self
-					.state_at(BlockId::Hash(block))?
-					.storage(well_known_keys::CHANGES_TRIE_CONFIG)?
-					.and_then(|v| Decode::decode(&mut &*v).ok());
-				*cached_changes_trie_config = Some(changes_trie_config.clone());
-				Ok(changes_trie_config)
-			},
-		}
-	}
-
 	/// Handle setting head within a transaction. `route_to` should be the last
 	/// block that existed in the database. `best_to` should be the best block
 	/// to be set.
@@ -1020,6 +1612,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		// TODO: ensure best chain contains this block.
 		let number = *header.number();
 		self.ensure_sequential_finalization(header, last_finalized)?;
+		self.changes_tries_storage.finalize(transaction, *header.parent_hash(), *hash, number)?;
 		self.note_finalized(
 			transaction,
 			header,
@@ -1097,6 +1690,13 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			last_finalized_hash = block_hash;
 		}
 
+		let base_genesis_hash = operation.base_genesis_hash;
+		if base_genesis_hash.is_some() && self.blockchain.meta.read().best_hash != Default::default() {
+			return Err(client::error::Error::Backend(
+				"Base blocks can only be imported into an empty database".into()
+			));
+		}
+
 		let imported = if let Some(pending_block) = operation.pending_block {
 			let hash = pending_block.header.hash();
 			let parent_hash = *pending_block.header.parent_hash();
@@ -1127,14 +1727,29 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			transaction.put(columns::HEADER, &lookup_key, &pending_block.header.encode());
 			if let Some(body) = &pending_block.body {
 				transaction.put(columns::BODY, &lookup_key, &body.encode());
+				if self.blockchain.index_extrinsics {
+					for (index, extrinsic) in body.iter().enumerate() {
+						let extrinsic_hash = <Block::Header as HeaderT>::Hashing::hash_of(extrinsic);
+						let key = extrinsic_index_key(&extrinsic_hash);
+						let mut locations = read_extrinsic_locations(&*self.blockchain.db, &key)?;
+						if !locations.contains(&(hash, index as u32)) {
+							locations.push((hash, index as u32));
+						}
+						write_extrinsic_locations(&mut transaction, &key, &locations);
+					}
+				}
 			}
 			if let Some(justification) = pending_block.justification {
 				transaction.put(columns::JUSTIFICATION, &lookup_key, &justification.encode());
 			}
 
-			if number.is_zero() {
+			let is_genesis_or_base = number.is_zero() || base_genesis_hash.is_some();
+			if is_genesis_or_base {
+				let genesis_hash = base_genesis_hash.unwrap_or(hash);
 				transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
-				transaction.put(columns::META, meta_keys::GENESIS_HASH, hash.as_ref());
+				transaction.put(columns::META, meta_keys::GENESIS_HASH, genesis_hash.as_ref());
+				// there are no changes tries before the first block of the database
+				transaction.put(columns::META, meta_keys::CHANGES_TRIES_META, &(number + One::one()).encode());
 			}
 
 			let finalized = if operation.commit_state {
@@ -1151,8 +1766,10 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 					.map_err(|e: state_db::Error<io::Error>| client::error::Error::from(format!("State database error: {:?}", e)))?;
 				apply_state_commit(&mut transaction, commit);
 
-				// Check if need to finalize. Genesis is always finalized instantly.
-				let finalized = number_u64 == 0 || pending_block.leaf_state.is_final();
+				// Check if need to finalize. Genesis and base blocks are always finalized instantly.
+				let finalized = number_u64 == 0
+					|| base_genesis_hash.is_some()
+					|| pending_block.leaf_state.is_final();
 				finalized
 			} else {
 				false
@@ -1165,9 +1782,33 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			self.changes_tries_storage.commit(&mut transaction, changes_trie_updates);
 			let cache = operation.old_state.release(); // release state reference so that it can be finalized
 
+			// the configuration that is set at this block is only used to build changes tries of its
+			// children. The configuration of the first block of the database must always be known.
+			let new_config = header.digest()
+				.log(|item| item.as_changes_trie_signal())
+				.and_then(ChangesTrieSignal::as_new_configuration)
+				.cloned();
+			let (new_config, config_entry_type) = if is_genesis_or_base {
+				(Some(new_config.or(operation.changes_trie_config_update).unwrap_or(None)), CacheEntryType::Genesis)
+			} else if finalized {
+				(new_config, CacheEntryType::Final)
+			} else {
+				(new_config, CacheEntryType::NonFinal)
+			};
+			let config_cache_ops = self.changes_tries_storage.insert_config(
+				&mut transaction,
+				parent_hash,
+				hash,
+				number,
+				new_config,
+				config_entry_type,
+			)?;
+
 			if finalized {
 				// TODO: ensure best chain contains this block.
-				self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
+				if base_genesis_hash.is_none() {
+					self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
+				}
 				self.note_finalized(
 					&mut transaction,
 					header,
@@ -1195,7 +1836,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 			meta_updates.push((hash, number, pending_block.leaf_state.is_best(), finalized));
 
-			Some((number, hash, enacted, retracted, displaced_leaf, is_best, cache))
+			Some((number, hash, enacted, retracted, displaced_leaf, is_best, cache, config_cache_ops))
 		} else {
 			None
 		};
@@ -1219,13 +1860,21 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 			None
 		};
 
+		let last_finalized = meta_updates.iter()
+			.filter(|(_, _, _, is_finalized)| *is_finalized)
+			.map(|(_, number, _, _)| *number)
+			.max();
+		if let Some(last_finalized) = last_finalized {
+			self.prune_blocks(&mut transaction, last_finalized)?;
+		}
+
 		let write_result = self.storage.db.write(transaction).map_err(db_err);
 
 		if let Some(changes_trie_cache_update) = operation.changes_trie_cache_update {
 			self.changes_tries_storage.commit_cache(changes_trie_cache_update);
 		}
 
-		if let Some((number, hash, enacted, retracted, displaced_leaf, is_best, mut cache)) = imported {
+		if let Some((number, hash, enacted, retracted, displaced_leaf, is_best, mut cache, config_cache_ops)) = imported {
 			if let Err(e) = write_result {
 				let mut leaves = self.blockchain.leaves.write();
 				let mut undo = leaves.undo();
@@ -1240,6 +1889,7 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				return Err(e)
 			}
 
+			self.changes_tries_storage.commit_config_cache(config_cache_ops);
 			cache.sync_cache(
 				&enacted,
 				&retracted,
@@ -1258,6 +1908,9 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 		for (hash, number, is_best, is_finalized) in meta_updates {
 			self.blockchain.update_meta(hash, number, is_best, is_finalized);
 		}
+		if let Some(genesis_hash) = base_genesis_hash {
+			self.blockchain.meta.write().genesis_hash = genesis_hash;
+		}
 
 		Ok(())
 	}
@@ -1287,9 +1940,10 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 				.map_err(|e: state_db::Error<io::Error>| client::error::Error::from(format!("State database error: {:?}", e)))?;
 			apply_state_commit(transaction, commit);
 
-			let changes_trie_config = self.changes_trie_config(parent_hash)?;
-			if let Some(changes_trie_config) = changes_trie_config {
-				self.changes_tries_storage.prune(&changes_trie_config, transaction, f_hash, f_num);
+			// the parent of a base block is unknown, and there are no changes tries to prune before it.
+			let is_base = !f_num.is_zero() && self.blockchain.header_metadata(parent_hash).is_err();
+			if !is_base {
+				self.changes_tries_storage.prune(transaction, f_hash, f_num)?;
 			}
 		}
 
@@ -1301,6 +1955,86 @@ impl<Block: BlockT<Hash=H256>> Backend<Block> {
 
 		Ok(())
 	}
+
+	// remove the bodies and justifications of the blocks that fall out of the blocks pruning window
+	// when the block numbered `finalized` is finalized, and of the older blocks that haven't been
+	// pruned yet (e.g. after finalizing several blocks at once, or when `keep` was lowered).
+	//
+	// must be called once per transaction, after all of its finalizations: the pruning cursor is
+	// read from the committed database. at most `BLOCKS_PRUNING_BATCH` blocks are pruned at once,
+	// so enabling pruning on an existing database catches up over the next finalizations.
+	//
+	// only the blocks of the finalized chain are pruned. the blocks of abandoned forks are never
+	// removed from the database, so their bodies are kept along with their headers.
+	fn prune_blocks(
+		&self,
+		transaction: &mut DBTransaction,
+		finalized: NumberFor<Block>,
+	) -> ClientResult<()> {
+		let keep = match self.blocks_pruning {
+			Some(keep) => keep,
+			None => return Ok(()),
+		};
+
+		let finalized = finalized.saturated_into::<u64>();
+		if finalized < u64::from(keep) {
+			return Ok(());
+		}
+		let last = finalized - u64::from(keep);
+		let pruning_meta = self.blockchain.db.get(columns::META, meta_keys::BLOCKS_PRUNING_META)
+			.map_err(db_err)?;
+		let first = match pruning_meta {
+			Some(first) => u64::decode(&mut &first[..])
+				.map_err(|err| ClientError::Backend(format!("Error decoding blocks pruning meta: {}", err)))?,
+			None => 0,
+		};
+
+		if first > last {
+			return Ok(());
+		}
+		let last = ::std::cmp::min(last, first + BLOCKS_PRUNING_BATCH - 1);
+
+		let mut pruned = HashSet::new();
+		for number in first..=last {
+			let number: NumberFor<Block> = number.saturated_into();
+			if let Some(hash) = ::client::blockchain::HeaderBackend::hash(&self.blockchain, number)? {
+				trace!(target: "db", "Removing body and justification of block #{} ({:?})", number, hash);
+				let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+				transaction.delete(columns::BODY, &lookup_key);
+				transaction.delete(columns::JUSTIFICATION, &lookup_key);
+				pruned.insert(hash);
+			}
+		}
+		if self.blockchain.index_extrinsics {
+			self.prune_extrinsic_index(transaction, &pruned)?;
+		}
+		transaction.put(columns::META, meta_keys::BLOCKS_PRUNING_META, &(last + 1).encode());
+		Ok(())
+	}
+
+	/// Remove the locations in the `pruned` blocks from the extrinsic index. The locations of the
+	/// extrinsics in other blocks are kept.
+	fn prune_extrinsic_index(
+		&self,
+		transaction: &mut DBTransaction,
+		pruned: &HashSet<Block::Hash>,
+	) -> ClientResult<()> {
+		use client::blockchain::Backend as BlockchainBackend;
+
+		let mut extrinsics = HashSet::new();
+		for hash in pruned {
+			for extrinsic in self.blockchain.body(BlockId::Hash(*hash))?.unwrap_or_default() {
+				extrinsics.insert(<Block::Header as HeaderT>::Hashing::hash_of(&extrinsic));
+			}
+		}
+		for extrinsic_hash in extrinsics {
+			let key = extrinsic_index_key(&extrinsic_hash);
+			let mut locations = read_extrinsic_locations::<Block::Hash>(&*self.blockchain.db, &key)?;
+			locations.retain(|(block_hash, _)| !pruned.contains(block_hash));
+			write_extrinsic_locations(transaction, &key, &locations);
+		}
+		Ok(())
+	}
 }
 
 fn apply_state_commit(transaction: &mut DBTransaction, commit: state_db::CommitSet<Vec<u8>>) {
@@ -1359,10 +2093,13 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 			child_storage_updates: Default::default(),
 			changes_trie_updates: MemoryDB::default(),
 			changes_trie_cache_update: None,
+			changes_trie_config_update: None,
 			aux_ops: Vec::new(),
 			finalized_blocks: Vec::new(),
 			set_head: None,
+			base_genesis_hash: None,
 			commit_state: false,
+			db: self.storage.db.clone(),
 		})
 	}
 
@@ -1407,6 +2144,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 				justification,
 				displaced,
 			)?;
+			self.prune_blocks(&mut transaction, number)?;
 			self.storage.db.write(transaction).map_err(db_err)?;
 			self.blockchain.update_meta(hash, number, is_best, is_finalized);
 			Ok(())
@@ -1432,40 +2170,105 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 		Some(self.offchain_storage.clone())
 	}
 
-	fn revert(&self, n: NumberFor<Block>) -> ClientResult<NumberFor<Block>> {
-		let mut best = self.blockchain.info().best_number;
-		let finalized = self.blockchain.info().finalized_number;
-		let revertible = best - finalized;
-		let n = if revertible < n { revertible } else { n };
+	fn revert(
+		&self,
+		n: NumberFor<Block>,
+		revert_finalized: bool,
+	) -> ClientResult<Vec<RevertedBlock<Block>>> {
+		let plan = self.plan_revert(n, revert_finalized)?;
+		let mut reverted = Vec::with_capacity(plan.len());
+		for block in plan {
+			let parent_number = block.number - One::one();
+			let commit = if block.canonical {
+				self.storage.state_db.revert_canonical_one(
+					&block.hash,
+					(block.parent_hash.clone(), parent_number.saturated_into::<u64>()),
+				)
+			} else {
+				self.storage.state_db.revert_one()
+			};
+			let commit = match commit {
+				Some(commit) => commit,
+				None => break,
+			};
 
-		for c in 0 .. n.saturated_into::<u64>() {
-			if best.is_zero() {
-				return Ok(c.saturated_into::<NumberFor<Block>>())
-			}
 			let mut transaction = DBTransaction::new();
-			match self.storage.state_db.revert_one() {
-				Some(commit) => {
-					apply_state_commit(&mut transaction, commit);
-					let removed = self.blockchain.header(BlockId::Number(best))?.ok_or_else(
-						|| client::error::Error::UnknownBlock(
-							format!("Error reverting to {}. Block hash not found.", best)))?;
-
-					best -= One::one();	// prev block
-					let hash = self.blockchain.hash(best)?.ok_or_else(
-						|| client::error::Error::UnknownBlock(
-							format!("Error reverting to {}. Block hash not found.", best)))?;
-					let key = utils::number_and_hash_to_lookup_key(best.clone(), &hash)?;
-					transaction.put(columns::META, meta_keys::BEST_BLOCK, &key);
-					transaction.delete(columns::KEY_LOOKUP, removed.hash().as_ref());
-					children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, hash);
-					self.storage.db.write(transaction).map_err(db_err)?;
-					self.blockchain.update_meta(hash, best, true, false);
-					self.blockchain.leaves.write().revert(removed.hash().clone(), removed.number().clone(), removed.parent_hash().clone());
-				}
-				None => return Ok(c.saturated_into::<NumberFor<Block>>())
+			apply_state_commit(&mut transaction, commit);
+			let key = utils::number_and_hash_to_lookup_key(parent_number, &block.parent_hash)?;
+			transaction.put(columns::META, meta_keys::BEST_BLOCK, &key);
+			let config_cache_ops = if block.finalized {
+				transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &key);
+				None
+			} else {
+				Some(self.changes_tries_storage.revert(&mut transaction, block.hash, block.number)?)
+			};
+			utils::remove_key_mappings(&mut transaction, columns::KEY_LOOKUP, block.number, &block.hash)?;
+			children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, block.parent_hash);
+			{
+				let mut leaves = self.blockchain.leaves.write();
+				leaves.revert(block.hash, block.number, block.parent_hash);
+				leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			}
+			self.storage.db.write(transaction).map_err(db_err)?;
+			self.blockchain.update_meta(block.parent_hash, parent_number, true, block.finalized);
+			self.blockchain.remove_header_metadata(block.hash);
+			match config_cache_ops {
+				Some(config_cache_ops) => self.changes_tries_storage.commit_config_cache(config_cache_ops),
+				// the finalized block has moved backwards
+				None => self.changes_tries_storage.reset_config_cache(),
+			}
+			reverted.push(block);
+		}
+		Ok(reverted)
+	}
+
+	fn plan_revert(
+		&self,
+		n: NumberFor<Block>,
+		revert_finalized: bool,
+	) -> ClientResult<Vec<RevertedBlock<Block>>> {
+		let info = self.blockchain.info();
+		let best_canonical = self.storage.state_db.best_canonical()
+			.map(|number| number.saturated_into::<NumberFor<Block>>());
+		let mut plan = Vec::new();
+		let mut number = info.best_number;
+		let mut c: NumberFor<Block> = Zero::zero();
+		while c < n && !number.is_zero() {
+			let finalized = number <= info.finalized_number;
+			if finalized && !revert_finalized {
+				break;
+			}
+			let header = self.blockchain.header(BlockId::Number(number))?.ok_or_else(
+				|| client::error::Error::UnknownBlock(
+					format!("Error reverting {}. Block header not found.", number)))?;
+			let parent_number = number - One::one();
+			// canonicalized state changes can only be reverted as long as the parent state is kept.
+			let canonical = best_canonical.map_or(false, |best_canonical| number <= best_canonical);
+			if canonical && !self.have_state_at(header.parent_hash(), parent_number) {
+				break;
 			}
+			// finalized changes tries configurations can't be removed from the cache.
+			let changes_trie_signal = header.digest().log(|item| item.as_changes_trie_signal());
+			if finalized && changes_trie_signal.is_some() {
+				break;
+			}
+			// the consensus engines keep the authority sets and epochs signalled by finalized blocks
+			// in their own state, which isn't reverted.
+			let consensus_change = header.digest().log(|item| item.as_consensus());
+			if finalized && consensus_change.is_some() {
+				break;
+			}
+			plan.push(RevertedBlock {
+				hash: header.hash(),
+				number,
+				parent_hash: header.parent_hash().clone(),
+				finalized,
+				canonical,
+			});
+			number = parent_number;
+			c += One::one();
 		}
-		Ok(n)
+		Ok(plan)
 	}
 
 	fn blockchain(&self) -> &BlockchainDb<Block> {
@@ -1496,7 +2299,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 			Ok(Some(ref hdr)) => {
 				let hash = hdr.hash();
 				if !self.have_state_at(&hash, *hdr.number()) {
-					return Err(client::error::Error::UnknownBlock(format!("State already discarded for {:?}", block)))
+					return Err(client::error::Error::StatePruned(format!("{:?}", block)))
 				}
 				if let Ok(()) = self.storage.state_db.pin(&hash) {
 					let root = H256::from_slice(hdr.state_root().as_ref());
@@ -1504,7 +2307,7 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 					let state = RefTrackingState::new(db_state, self.storage.clone(), Some(hash.clone()));
 					Ok(CachingState::new(state, self.shared_cache.clone(), Some(hash)))
 				} else {
-					Err(client::error::Error::UnknownBlock(format!("State already discarded for {:?}", block)))
+					Err(client::error::Error::StatePruned(format!("{:?}", block)))
 				}
 			},
 			Ok(None) => Err(client::error::Error::UnknownBlock(format!("Unknown state for block {:?}", block))),
@@ -1541,12 +2344,6 @@ impl<Block> client::backend::Backend<Block, Blake2Hasher> for Backend<Block> whe
 impl<Block> client::backend::LocalBackend<Block, Blake2Hasher> for Backend<Block>
 where Block: BlockT<Hash=H256> {}
 
-/// TODO: remove me in #3201
-pub fn unused_sink<Block: BlockT>(cache_tx: crate::cache::DbCacheTransaction<Block>) {
-	cache_tx.on_block_revert(&crate::cache::ComplexBlockId::new(Default::default(), 0.into())).unwrap();
-	unimplemented!()
-}
-
 #[cfg(test)]
 mod tests {
 	use hash_db::{HashDB, EMPTY_PREFIX};
@@ -1586,14 +2383,28 @@ mod tests {
 		parent_hash: H256,
 		changes: Vec<(Vec<u8>, Vec<u8>)>,
 		extrinsics_root: H256,
+	) -> H256 {
+		insert_header_with_configuration_change(backend, number, parent_hash, changes, extrinsics_root, None)
+	}
+
+	fn insert_header_with_configuration_change(
+		backend: &Backend<Block>,
+		number: u64,
+		parent_hash: H256,
+		changes: Vec<(Vec<u8>, Vec<u8>)>,
+		extrinsics_root: H256,
+		new_config: Option<Option<ChangesTrieConfiguration>>,
 	) -> H256 {
 		use sr_primitives::testing::Digest;
 		let (changes_root, changes_trie_update) = prepare_changes(changes);
-		let digest = Digest {
+		let mut digest = Digest {
 			logs: vec![
 				DigestItem::ChangesTrieRoot(changes_root),
 			],
 		};
+		if let Some(new_config) = new_config {
+			digest.logs.push(DigestItem::ChangesTrieSignal(ChangesTrieSignal::NewConfiguration(new_config)));
+		}
 		let header = Header {
 			number,
 			parent_hash,
@@ -1663,6 +2474,8 @@ mod tests {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(1),
+			blocks_pruning: None,
+			index_extrinsics: false,
 			source: DatabaseSettingsSrc::Custom(backing),
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
@@ -2016,7 +2829,7 @@ mod tests {
 		};
 
 		// insert some blocks
-		let block0 = insert_header(&backend, 0, Default::default(), vec![(b"key_at_0".to_vec(), b"val_at_0".to_vec())], Default::default());
+		let block0 = insert_header_with_configuration_change(&backend, 0, Default::default(), vec![(b"key_at_0".to_vec(), b"val_at_0".to_vec())], Default::default(), Some(Some(config)));
 		let block1 = insert_header(&backend, 1, block0, vec![(b"key_at_1".to_vec(), b"val_at_1".to_vec())], Default::default());
 		let block2 = insert_header(&backend, 2, block1, vec![(b"key_at_2".to_vec(), b"val_at_2".to_vec())], Default::default());
 		let block3 = insert_header(&backend, 3, block2, vec![(b"key_at_3".to_vec(), b"val_at_3".to_vec())], Default::default());
@@ -2053,7 +2866,7 @@ mod tests {
 
 		// now simulate finalization of block#12, causing prune of tries at #1..#4
 		let mut tx = DBTransaction::new();
-		backend.changes_tries_storage.prune(&config, &mut tx, Default::default(), 12);
+		backend.changes_tries_storage.prune(&mut tx, Default::default(), 12).unwrap();
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root1, EMPTY_PREFIX).unwrap().is_none());
 		assert!(backend.changes_tries_storage.get(&root2, EMPTY_PREFIX).unwrap().is_none());
@@ -2066,7 +2879,7 @@ mod tests {
 
 		// now simulate finalization of block#16, causing prune of tries at #5..#8
 		let mut tx = DBTransaction::new();
-		backend.changes_tries_storage.prune(&config, &mut tx, Default::default(), 16);
+		backend.changes_tries_storage.prune(&mut tx, Default::default(), 16).unwrap();
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root5, EMPTY_PREFIX).unwrap().is_none());
 		assert!(backend.changes_tries_storage.get(&root6, EMPTY_PREFIX).unwrap().is_none());
@@ -2077,7 +2890,7 @@ mod tests {
 		// => no changes tries are pruned, because we never prune in archive mode
 		backend.changes_tries_storage.min_blocks_to_keep = None;
 		let mut tx = DBTransaction::new();
-		backend.changes_tries_storage.prune(&config, &mut tx, Default::default(), 20);
+		backend.changes_tries_storage.prune(&mut tx, Default::default(), 20).unwrap();
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root9, EMPTY_PREFIX).unwrap().is_some());
 		assert!(backend.changes_tries_storage.get(&root10, EMPTY_PREFIX).unwrap().is_some());
@@ -2095,7 +2908,7 @@ mod tests {
 		};
 
 		// insert some blocks
-		let block0 = insert_header(&backend, 0, Default::default(), vec![(b"key_at_0".to_vec(), b"val_at_0".to_vec())], Default::default());
+		let block0 = insert_header_with_configuration_change(&backend, 0, Default::default(), vec![(b"key_at_0".to_vec(), b"val_at_0".to_vec())], Default::default(), Some(Some(config)));
 		let block1 = insert_header(&backend, 1, block0, vec![(b"key_at_1".to_vec(), b"val_at_1".to_vec())], Default::default());
 		let block2 = insert_header(&backend, 2, block1, vec![(b"key_at_2".to_vec(), b"val_at_2".to_vec())], Default::default());
 		let block3 = insert_header(&backend, 3, block2, vec![(b"key_at_3".to_vec(), b"val_at_3".to_vec())], Default::default());
@@ -2119,19 +2932,46 @@ mod tests {
 
 		// now simulate finalization of block#5, causing prune of trie at #1
 		let mut tx = DBTransaction::new();
-		backend.changes_tries_storage.prune(&config, &mut tx, block5, 5);
+		backend.changes_tries_storage.prune(&mut tx, block5, 5).unwrap();
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root1, EMPTY_PREFIX).unwrap().is_none());
 		assert!(backend.changes_tries_storage.get(&root2, EMPTY_PREFIX).unwrap().is_some());
 
 		// now simulate finalization of block#6, causing prune of tries at #2
 		let mut tx = DBTransaction::new();
-		backend.changes_tries_storage.prune(&config, &mut tx, block6, 6);
+		backend.changes_tries_storage.prune(&mut tx, block6, 6).unwrap();
 		backend.storage.db.write(tx).unwrap();
 		assert!(backend.changes_tries_storage.get(&root2, EMPTY_PREFIX).unwrap().is_none());
 		assert!(backend.changes_tries_storage.get(&root3, EMPTY_PREFIX).unwrap().is_some());
 	}
 
+	#[test]
+	fn changes_tries_configuration_is_read_from_cache() {
+		let backend = Backend::<Block>::new_test(1000, 100);
+		let config1 = ChangesTrieConfiguration { digest_interval: 2, digest_levels: 2 };
+		let config2 = ChangesTrieConfiguration { digest_interval: 4, digest_levels: 1 };
+
+		let block0 = insert_header_with_configuration_change(&backend, 0, Default::default(), Vec::new(), Default::default(), Some(Some(config1.clone())));
+		let block1 = insert_header(&backend, 1, block0, Vec::new(), Default::default());
+		let block2 = insert_header_with_configuration_change(&backend, 2, block1, Vec::new(), Default::default(), Some(None));
+		let block3 = insert_header_with_configuration_change(&backend, 3, block2, Vec::new(), Default::default(), Some(Some(config2.clone())));
+		let _ = insert_header(&backend, 4, block3, Vec::new(), Default::default());
+
+		let configuration_at = |number: u64| backend.changes_tries_storage.configuration_at(&BlockId::Number(number)).unwrap();
+		let range1 = ChangesTrieConfigurationRange { zero: (0, block0), end: Some((2, block2)), config: Some(config1) };
+		let range2 = ChangesTrieConfigurationRange { zero: (2, block2), end: Some((3, block3)), config: None };
+		let range3 = ChangesTrieConfigurationRange { zero: (3, block3), end: None, config: Some(config2) };
+		assert_eq!(configuration_at(0), range1);
+		assert_eq!(configuration_at(1), range1);
+		assert_eq!(configuration_at(2), range2);
+		assert_eq!(configuration_at(3), range3);
+		assert_eq!(configuration_at(4), range3);
+
+		// the configuration is forgotten when the block that has changed it is reverted
+		assert_eq!(backend.revert(2, false).unwrap().len(), 2);
+		assert_eq!(configuration_at(2), ChangesTrieConfigurationRange { end: None, ..range2 });
+	}
+
 	#[test]
 	fn tree_route_works() {
 		let backend = Backend::<Block>::new_test(1000, 100);
@@ -2331,6 +3171,37 @@ mod tests {
 		assert_eq!(backend.blockchain().leaves().unwrap(), vec![block2_a, block2_b, block2_c]);
 	}
 
+	#[cfg(feature = "parity-db")]
+	#[test]
+	fn parity_db_leaves_survive_reopening() {
+		let dir = tempdir::TempDir::new("substrate-client-db").unwrap();
+		let open = || {
+			let settings = DatabaseSettings {
+				state_cache_size: 16777216,
+				state_cache_child_ratio: Some((50, 100)),
+				pruning: PruningMode::keep_blocks(10),
+				blocks_pruning: None,
+				index_extrinsics: false,
+				source: DatabaseSettingsSrc::ParityDb { path: dir.path().join("db") },
+			};
+			Backend::<Block>::new(settings, 10).unwrap()
+		};
+
+		let leaves = {
+			let backend = open();
+			let block0 = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+			let block1_a = insert_header(&backend, 1, block0, Default::default(), Default::default());
+			insert_header(&backend, 1, block0, Default::default(), [1; 32].into());
+			insert_header(&backend, 2, block1_a, Default::default(), Default::default());
+			backend.blockchain().leaves().unwrap()
+		};
+		assert_eq!(leaves.len(), 2);
+
+		let backend = open();
+		assert_eq!(backend.blockchain().leaves().unwrap(), leaves);
+		assert_eq!(backend.blockchain().info().best_hash, leaves[0]);
+	}
+
 	#[test]
 	fn test_aux() {
 		let backend: Backend<test_client::runtime::Block> = Backend::new_test(0, 0);
@@ -2359,6 +3230,377 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_blocks_pruning_removes_old_bodies() {
+		use client::blockchain::{Backend as BlockChainBackend};
+
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			blocks_pruning: Some(2),
+			index_extrinsics: false,
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 10).unwrap();
+
+		let mut hash = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+		for number in 1..6 {
+			hash = insert_header(&backend, number, hash, Default::default(), Default::default());
+			backend.finalize_block(BlockId::Number(number), Some(vec![number as u8])).unwrap();
+		}
+
+		for number in 0..4 {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), None);
+			assert_eq!(backend.blockchain().justification(BlockId::Number(number)).unwrap(), None);
+			assert!(backend.blockchain().header(BlockId::Number(number)).unwrap().is_some());
+		}
+		for number in 4..6 {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), Some(Vec::new()));
+			assert_eq!(
+				backend.blockchain().justification(BlockId::Number(number)).unwrap(),
+				Some(vec![number as u8]),
+			);
+		}
+	}
+
+	#[test]
+	fn test_blocks_pruning_catches_up_after_finality_jump() {
+		use client::blockchain::{Backend as BlockChainBackend};
+
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			blocks_pruning: Some(2),
+			index_extrinsics: false,
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 10).unwrap();
+
+		let mut hashes = vec![
+			insert_header(&backend, 0, Default::default(), Default::default(), Default::default()),
+		];
+		for number in 1..8 {
+			let parent = hashes[number as usize - 1];
+			hashes.push(insert_header(&backend, number, parent, Default::default(), Default::default()));
+		}
+		{
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(hashes[0])).unwrap();
+			for hash in &hashes[1..7] {
+				op.mark_finalized(BlockId::Hash(*hash), None).unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+		}
+
+		for number in 0..5 {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), None);
+		}
+		for number in 5..8 {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), Some(Vec::new()));
+		}
+	}
+
+	#[test]
+	fn test_blocks_pruning_proceeds_in_batches() {
+		use client::blockchain::{Backend as BlockChainBackend};
+
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::ArchiveAll,
+			blocks_pruning: Some(2),
+			index_extrinsics: false,
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 10).unwrap();
+
+		let last = BLOCKS_PRUNING_BATCH + 10;
+		let mut hashes = vec![
+			insert_header(&backend, 0, Default::default(), Default::default(), Default::default()),
+		];
+		for number in 1..=last {
+			let parent = hashes[number as usize - 1];
+			hashes.push(insert_header(&backend, number, parent, Default::default(), Default::default()));
+		}
+		{
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(hashes[0])).unwrap();
+			for hash in &hashes[1..last as usize] {
+				op.mark_finalized(BlockId::Hash(*hash), None).unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+		}
+
+		// a single transaction prunes a single batch of blocks.
+		assert_eq!(backend.blockchain().body(BlockId::Number(BLOCKS_PRUNING_BATCH - 1)).unwrap(), None);
+		assert_eq!(
+			backend.blockchain().body(BlockId::Number(BLOCKS_PRUNING_BATCH)).unwrap(),
+			Some(Vec::new()),
+		);
+
+		// the next finalization carries on from there.
+		backend.finalize_block(BlockId::Number(last), None).unwrap();
+		for number in 0..last - 1 {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), None);
+		}
+		for number in last - 1..=last {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), Some(Vec::new()));
+		}
+	}
+
+	#[test]
+	fn test_extrinsic_index() {
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			blocks_pruning: Some(1),
+			index_extrinsics: true,
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 10).unwrap();
+		let insert_block_with_state = |number: u64, parent_hash: H256, body: Vec<u64>, state| {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: BlakeTwo256::trie_root(Vec::new()),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			let hash = header.hash();
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(parent_hash)).unwrap();
+			let body = body.into_iter().map(ExtrinsicWrapper::from).collect();
+			op.set_block_data(header, Some(body), None, state).unwrap();
+			backend.commit_operation(op).unwrap();
+			hash
+		};
+		let insert_block = |number, parent_hash, body| {
+			insert_block_with_state(number, parent_hash, body, NewBlockState::Best)
+		};
+		let extrinsic_hash = |x: u64| BlakeTwo256::hash_of(&ExtrinsicWrapper::from(x));
+
+		let block0 = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+		let block1 = insert_block(1, block0, vec![1, 2]);
+		let block2 = insert_block(2, block1, vec![2, 3]);
+		// a fork including the same extrinsic doesn't replace its canonical location.
+		let fork2 = insert_block_with_state(2, block1, vec![5, 3], NewBlockState::Normal);
+
+		let blockchain = backend.blockchain();
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(1)).unwrap(), Some((block1, 0)));
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(2)).unwrap(), Some((block2, 0)));
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(3)).unwrap(), Some((block2, 1)));
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(4)).unwrap(), None);
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(5)).unwrap(), Some((fork2, 0)));
+
+		// pruning the body of block #1 removes the extrinsics that are only in that block.
+		backend.finalize_block(BlockId::Number(1), None).unwrap();
+		backend.finalize_block(BlockId::Number(2), None).unwrap();
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(1)).unwrap(), None);
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(2)).unwrap(), Some((block2, 0)));
+
+		match Backend::<Block>::new_test(10, 10).blockchain().extrinsic_location(&extrinsic_hash(1)) {
+			Err(ClientError::ExtrinsicIndexDisabled) => {},
+			_ => panic!("Extrinsics are not indexed by default"),
+		}
+	}
+
+	#[test]
+	fn test_purge_block_bodies() {
+		use client::blockchain::{Backend as BlockChainBackend};
+
+		let db = Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = || DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			blocks_pruning: None,
+			index_extrinsics: true,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+		};
+		let extrinsic_hash = |x: u64| BlakeTwo256::hash_of(&ExtrinsicWrapper::from(x));
+
+		let mut hashes = Vec::new();
+		{
+			let backend = Backend::<Block>::new(settings(), 10).unwrap();
+			let mut hash = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+			for number in 1..6 {
+				let header = Header {
+					number,
+					parent_hash: hash,
+					state_root: BlakeTwo256::trie_root(Vec::new()),
+					digest: Default::default(),
+					extrinsics_root: Default::default(),
+				};
+				let mut op = backend.begin_operation().unwrap();
+				backend.begin_state_operation(&mut op, BlockId::Hash(hash)).unwrap();
+				hash = header.hash();
+				let body = vec![ExtrinsicWrapper::from(number)];
+				op.set_block_data(header, Some(body), Some(vec![number as u8]), NewBlockState::Final).unwrap();
+				backend.commit_operation(op).unwrap();
+				hashes.push(hash);
+			}
+		}
+
+		// the genesis block and blocks #1 to #3 fall out of the two last finalized blocks.
+		assert_eq!(purge_block_bodies(DatabaseSettingsSrc::Custom(db.clone()), 2).unwrap(), 4);
+		assert_eq!(purge_block_bodies(DatabaseSettingsSrc::Custom(db.clone()), 2).unwrap(), 0);
+
+		let backend = Backend::<Block>::new(settings(), 10).unwrap();
+		let blockchain = backend.blockchain();
+		for number in 0..4 {
+			assert_eq!(blockchain.body(BlockId::Number(number)).unwrap(), None);
+			assert!(blockchain.header(BlockId::Number(number)).unwrap().is_some());
+		}
+		for number in 4..6 {
+			assert_eq!(blockchain.body(BlockId::Number(number)).unwrap(), Some(vec![ExtrinsicWrapper::from(number)]));
+		}
+		// the justifications are kept, so that the finality of the purged blocks can still be proven.
+		for number in 1..6 {
+			assert_eq!(blockchain.justification(BlockId::Number(number)).unwrap(), Some(vec![number as u8]));
+		}
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(3)).unwrap(), None);
+		assert_eq!(blockchain.extrinsic_location(&extrinsic_hash(4)).unwrap(), Some((hashes[3], 0)));
+	}
+
+	#[test]
+	fn test_revert_finalized_blocks() {
+		let db = Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let settings = || DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			blocks_pruning: None,
+			index_extrinsics: false,
+			source: DatabaseSettingsSrc::Custom(db.clone()),
+		};
+
+		let mut hashes = Vec::new();
+		{
+			let backend = Backend::<Block>::new(settings(), 10).unwrap();
+			let mut hash = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+			hashes.push(hash);
+			for number in 1..6 {
+				hash = insert_header(&backend, number, hash, Default::default(), Default::default());
+				hashes.push(hash);
+			}
+			for number in 1..4 {
+				backend.finalize_block(BlockId::Number(number), None).unwrap();
+			}
+
+			// finalized blocks are kept unless forced.
+			let reverted = backend.revert(10, false).unwrap();
+			assert_eq!(reverted.iter().map(|block| block.number).collect::<Vec<_>>(), vec![5, 4]);
+			assert!(reverted.iter().all(|block| !block.finalized && !block.canonical));
+			assert_eq!(backend.blockchain().info().best_hash, hashes[3]);
+			assert!(backend.revert(10, false).unwrap().is_empty());
+
+			// the dry run reports the blocks down to the genesis, without reverting them.
+			let plan = backend.plan_revert(10, true).unwrap();
+			assert_eq!(plan.iter().map(|block| block.number).collect::<Vec<_>>(), vec![3, 2, 1]);
+			assert!(plan.iter().all(|block| block.finalized && block.canonical));
+			assert_eq!(plan[0].parent_hash, hashes[2]);
+			assert_eq!(backend.blockchain().info().best_hash, hashes[3]);
+
+			let reverted = backend.revert(2, true).unwrap();
+			assert_eq!(reverted, plan[..2].to_vec());
+		}
+
+		let backend = Backend::<Block>::new(settings(), 10).unwrap();
+		let info = backend.blockchain().info();
+		assert_eq!((info.best_number, info.best_hash), (1, hashes[1]));
+		assert_eq!((info.finalized_number, info.finalized_hash), (1, hashes[1]));
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![hashes[1]]);
+		assert_eq!(backend.blockchain().hash(2).unwrap(), None);
+		assert!(backend.have_state_at(&hashes[1], 1));
+
+		// the chain can be extended again from the new best block.
+		let hash = insert_header(&backend, 2, hashes[1], Default::default(), Default::default());
+		backend.finalize_block(BlockId::Hash(hash), None).unwrap();
+		assert_eq!(backend.blockchain().info().finalized_hash, hash);
+	}
+
+	#[test]
+	fn test_revert_stops_at_finalized_consensus_changes() {
+		let backend = Backend::<Block>::new_test(10, 10);
+		let mut hash = insert_header(&backend, 0, Default::default(), Default::default(), Default::default());
+		hash = insert_header(&backend, 1, hash, Default::default(), Default::default());
+
+		// block 2 changes the authorities.
+		let header = Header {
+			number: 2,
+			parent_hash: hash,
+			state_root: BlakeTwo256::trie_root(Vec::new()),
+			digest: sr_primitives::testing::Digest {
+				logs: vec![DigestItem::Consensus(*b"FRNK", vec![1, 2, 3])],
+			},
+			extrinsics_root: Default::default(),
+		};
+		hash = header.hash();
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Number(1)).unwrap();
+		op.set_block_data(header, Some(Vec::new()), None, NewBlockState::Best).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		let change_hash = hash;
+		for number in 3..5 {
+			hash = insert_header(&backend, number, hash, Default::default(), Default::default());
+		}
+		for number in 1..5 {
+			backend.finalize_block(BlockId::Number(number), None).unwrap();
+		}
+
+		// the finalized blocks are reverted down to the change, which is kept.
+		let reverted = backend.revert(10, true).unwrap();
+		assert_eq!(reverted.iter().map(|block| block.number).collect::<Vec<_>>(), vec![4, 3]);
+		assert_eq!(backend.blockchain().info().finalized_hash, change_hash);
+		assert!(backend.plan_revert(10, true).unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_import_snapshot() {
+		let child_storage_key = b":child_storage:default:child".to_vec();
+		let top = (0..100u32).map(|i| (i.encode(), Some(b"value".to_vec())));
+		let child = (0..40u32).map(|i| (i.encode(), Some(b"child value".to_vec())));
+		let (state_root, transaction) = state_machine::backend::InMemory::<Blake2Hasher>::default()
+			.full_storage_root(top, std::iter::once((child_storage_key.clone(), child)));
+		let state = state_machine::backend::InMemory::<Blake2Hasher>::default().update(transaction);
+		let header = Header {
+			number: 10,
+			parent_hash: H256::random(),
+			state_root,
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let hash = header.hash();
+		let genesis_hash = H256::random();
+		let mut snapshot = Vec::new();
+		let chunks = client::snapshot::write_snapshot::<Block, _, _>(
+			&state,
+			genesis_hash,
+			header,
+			16,
+			&mut snapshot,
+		).unwrap();
+		assert_eq!(chunks, 7 + 3);
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		assert!(client::snapshot::import_snapshot(&backend, H256::random(), &snapshot[..]).is_err());
+		assert_eq!(backend.blockchain().info().best_hash, Default::default());
+		client::snapshot::import_snapshot(&backend, genesis_hash, &snapshot[..]).unwrap();
+		let info = backend.blockchain().info();
+		assert_eq!((info.best_hash, info.best_number), (hash, 10));
+		assert_eq!((info.finalized_hash, info.finalized_number), (hash, 10));
+		assert_eq!(info.genesis_hash, genesis_hash);
+		let imported = backend.state_at(BlockId::Hash(hash)).unwrap();
+		assert_eq!(imported.storage(&99u32.encode()).unwrap(), Some(b"value".to_vec()));
+		assert_eq!(
+			imported.child_storage(&child_storage_key, &39u32.encode()).unwrap(),
+			Some(b"child value".to_vec()),
+		);
+
+		// snapshots can only be imported into an empty database.
+		assert!(client::snapshot::import_snapshot(&backend, genesis_hash, &snapshot[..]).is_err());
+	}
+
 	#[test]
 	fn test_finalize_multiple_blocks_in_single_op() {
 		let backend = Backend::<Block>::new_test(10, 10);
@@ -33,7 +33,7 @@ use client::error::{Error as ClientError, Result as ClientResult};
 use client::light::blockchain::Storage as LightBlockchainStorage;
 use codec::{Decode, Encode};
 use primitives::Blake2Hasher;
-use sr_primitives::generic::{DigestItem, BlockId};
+use sr_primitives::generic::{DigestItem, ChangesTrieSignal, BlockId};
 use sr_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, One, NumberFor};
 use header_metadata::{CachedHeaderMetadata, HeaderMetadata, HeaderMetadataCache};
 use crate::cache::{DbCacheSync, DbCache, ComplexBlockId, EntryType as CacheEntryType};
@@ -48,6 +48,8 @@ pub(crate) mod columns {
 	pub const CACHE: Option<u32> = Some(3);
 	pub const CHT: Option<u32> = Some(4);
 	pub const AUX: Option<u32> = Some(5);
+	/// Columns that are iterated, e.g. to read the leaves.
+	pub const ITERABLE: &[Option<u32>] = &[META];
 }
 
 /// Prefix for headers CHT.
@@ -403,7 +405,7 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 	fn import_header(
 		&self,
 		header: Block::Header,
-		cache_at: HashMap<well_known_cache_keys::Id, Vec<u8>>,
+		mut cache_at: HashMap<well_known_cache_keys::Id, Vec<u8>>,
 		leaf_state: NewBlockState,
 		aux_ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 	) -> ClientResult<()> {
@@ -447,6 +449,14 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 			transaction.put(columns::META, meta_keys::GENESIS_HASH, hash.as_ref());
 		}
 
+		// remember the changes tries configuration that is set at this block
+		let new_changes_trie_config = header.digest()
+			.log(|item| item.as_changes_trie_signal())
+			.and_then(ChangesTrieSignal::as_new_configuration);
+		if let Some(new_changes_trie_config) = new_changes_trie_config {
+			cache_at.insert(well_known_cache_keys::CHANGES_TRIE_CONFIG, new_changes_trie_config.encode());
+		}
+
 		let finalized = match leaf_state {
 			_ if is_genesis => true,
 			NewBlockState::Final => true,
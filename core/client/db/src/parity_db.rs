@@ -0,0 +1,295 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `KeyValueDB` implementation backed by parity-db.
+//!
+//! Parity-db is a hash-indexed store: point lookups of trie nodes are cheaper than in RocksDB, but
+//! the keys of a column can't be enumerated. The client iterates a few columns (e.g. to read the
+//! leaves of the block tree on startup), so the adapter keeps the keys of those columns in an
+//! in-memory index, persisted in an append-only log next to the database.
+//!
+//! Keys are logged as inserted before the database is written to, and as removed after. A crash in
+//! between may leave keys in the index that aren't in the database, so iteration skips the keys
+//! that have no value.
+
+use std::{
+	collections::{BTreeSet, HashMap}, fs::{self, File, OpenOptions}, io::{self, Read, Write},
+	path::{Path, PathBuf},
+};
+use codec::{Decode, Encode};
+use kvdb::{KeyValueDB, DBTransaction, DBOp, DBValue};
+use log::{error, warn};
+use parking_lot::Mutex;
+
+/// Name of the file holding the log of the key index.
+const KEY_INDEX_FILE: &str = "key_index";
+
+/// A parity-db database, exposed as a `KeyValueDB`.
+pub struct DbAdapter {
+	db: parity_db::Db,
+	index: Mutex<KeyIndex>,
+}
+
+/// Open the parity-db database at `path`, creating it if it doesn't exist.
+///
+/// Only the `iterable` columns can be iterated.
+pub fn open(path: &Path, num_columns: u32, iterable: &[Option<u32>]) -> io::Result<DbAdapter> {
+	let index_path = path.join(KEY_INDEX_FILE);
+	if !index_path.exists() && path.exists() && fs::read_dir(path)?.next().is_some() {
+		return Err(other_io_err(format!(
+			"{} has no key index. It was created by an older version and must be migrated again",
+			path.display(),
+		)));
+	}
+
+	let options = parity_db::Options::with_columns(path, num_columns as u8);
+	let db = parity_db::Db::open(&options).map_err(|e| other_io_err(format!("{:?}", e)))?;
+	let iterable = iterable.iter().map(|col| column(*col)).collect::<io::Result<Vec<_>>>()?;
+	let index = KeyIndex::open(index_path, &iterable)?;
+	Ok(DbAdapter { db, index: Mutex::new(index) })
+}
+
+fn other_io_err(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, msg)
+}
+
+fn column(col: Option<u32>) -> io::Result<u8> {
+	col.map(|col| col as u8)
+		.ok_or_else(|| other_io_err("parity-db has no default column".into()))
+}
+
+/// Entry of the log of the key index.
+#[derive(Encode, Decode)]
+enum KeyIndexEntry {
+	Inserted(u8, Vec<u8>),
+	Removed(u8, Vec<u8>),
+}
+
+/// Keys of the iterable columns.
+struct KeyIndex {
+	columns: HashMap<u8, BTreeSet<Vec<u8>>>,
+	log: File,
+}
+
+impl KeyIndex {
+	/// Load the index from the log at `path`, creating it if it doesn't exist.
+	///
+	/// The log is compacted, so that it only holds the keys that are in the index.
+	fn open(path: PathBuf, iterable: &[u8]) -> io::Result<Self> {
+		let mut columns: HashMap<_, _> = iterable.iter().map(|col| (*col, BTreeSet::new())).collect();
+
+		let mut content = Vec::new();
+		if path.exists() {
+			File::open(&path)?.read_to_end(&mut content)?;
+		}
+		let mut input = &content[..];
+		while !input.is_empty() {
+			match KeyIndexEntry::decode(&mut input) {
+				Ok(KeyIndexEntry::Inserted(col, key)) => {
+					columns.get_mut(&col).map(|keys| keys.insert(key));
+				},
+				Ok(KeyIndexEntry::Removed(col, key)) => {
+					columns.get_mut(&col).map(|keys| keys.remove(&key));
+				},
+				Err(_) => {
+					// The last entry was only partially written.
+					warn!(target: "db", "Ignoring truncated entry of the parity-db key index");
+					break;
+				},
+			}
+		}
+
+		let compacted_path = path.with_extension("tmp");
+		let mut compacted = File::create(&compacted_path)?;
+		content.clear();
+		for (col, keys) in &columns {
+			for key in keys {
+				KeyIndexEntry::Inserted(*col, key.clone()).encode_to(&mut content);
+			}
+		}
+		compacted.write_all(&content)?;
+		compacted.sync_all()?;
+		fs::rename(&compacted_path, &path)?;
+
+		let log = OpenOptions::new().append(true).open(&path)?;
+		Ok(KeyIndex { columns, log })
+	}
+
+	/// Durably record the entries, and apply them to the index.
+	fn apply(&mut self, entries: Vec<KeyIndexEntry>) -> io::Result<()> {
+		if entries.is_empty() {
+			return Ok(());
+		}
+
+		let mut buffer = Vec::new();
+		for entry in &entries {
+			entry.encode_to(&mut buffer);
+		}
+		self.log.write_all(&buffer)?;
+		self.log.sync_data()?;
+
+		for entry in entries {
+			match entry {
+				KeyIndexEntry::Inserted(col, key) => {
+					self.columns.get_mut(&col).map(|keys| keys.insert(key));
+				},
+				KeyIndexEntry::Removed(col, key) => {
+					self.columns.get_mut(&col).map(|keys| keys.remove(&key));
+				},
+			}
+		}
+		Ok(())
+	}
+
+	/// Returns the keys of `col` starting with `prefix`, in order.
+	fn keys(&self, col: u8, prefix: &[u8]) -> Option<Vec<Vec<u8>>> {
+		self.columns.get(&col).map(|keys| keys.range(prefix.to_vec()..)
+			.take_while(|key| key.starts_with(prefix))
+			.cloned()
+			.collect()
+		)
+	}
+}
+
+impl DbAdapter {
+	/// Iterate the entries of `col` whose key starts with `prefix`, in key order.
+	fn iter_prefix<'a>(
+		&'a self,
+		col: Option<u32>,
+		prefix: &[u8],
+	) -> Box<dyn Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		let keys = column(col).ok().and_then(|col| Some((col, self.index.lock().keys(col, prefix)?)));
+		let (col, keys) = match keys {
+			Some(keys) => keys,
+			None => {
+				error!(target: "db", "Column {:?} can't be iterated with parity-db", col);
+				return Box::new(std::iter::empty());
+			},
+		};
+
+		Box::new(keys.into_iter().filter_map(move |key| match self.db.get(col, &key) {
+			Ok(Some(value)) => Some((key.into_boxed_slice(), value.into_boxed_slice())),
+			Ok(None) => None,
+			Err(e) => {
+				error!(target: "db", "Failed to read from parity-db: {:?}", e);
+				None
+			},
+		}))
+	}
+}
+
+impl KeyValueDB for DbAdapter {
+	fn get(&self, col: Option<u32>, key: &[u8]) -> io::Result<Option<DBValue>> {
+		self.db.get(column(col)?, key)
+			.map(|value| value.map(|value| DBValue::from_slice(&value)))
+			.map_err(|e| other_io_err(format!("{:?}", e)))
+	}
+
+	fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+		self.iter_prefix(col, prefix).next().map(|(_, value)| value)
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) {
+		if let Err(e) = self.write(transaction) {
+			error!(target: "db", "Failed to write to parity-db: {:?}", e);
+		}
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+		let ops = transaction.ops.into_iter()
+			.map(|op| match op {
+				DBOp::Insert { col, key, value } => Ok((column(col)?, key.to_vec(), Some(value.to_vec()))),
+				DBOp::Delete { col, key } => Ok((column(col)?, key.to_vec(), None)),
+			})
+			.collect::<io::Result<Vec<_>>>()?;
+
+		let mut index = self.index.lock();
+		let (inserted, removed): (Vec<_>, Vec<_>) = ops.iter()
+			.filter(|(col, _, _)| index.columns.contains_key(col))
+			.map(|(col, key, value)| match value {
+				Some(_) => KeyIndexEntry::Inserted(*col, key.clone()),
+				None => KeyIndexEntry::Removed(*col, key.clone()),
+			})
+			.partition(|entry| match entry {
+				KeyIndexEntry::Inserted(..) => true,
+				KeyIndexEntry::Removed(..) => false,
+			});
+
+		index.apply(inserted)?;
+		self.db.commit(ops).map_err(|e| other_io_err(format!("{:?}", e)))?;
+		index.apply(removed)
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		// Every write is committed straight away.
+		Ok(())
+	}
+
+	fn iter<'a>(&'a self, col: Option<u32>) -> Box<dyn Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.iter_prefix(col, &[])
+	}
+
+	fn iter_from_prefix<'a>(
+		&'a self,
+		col: Option<u32>,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item=(Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.iter_prefix(col, prefix)
+	}
+
+	fn restore(&self, _new_db: &str) -> io::Result<()> {
+		Err(other_io_err("Restoring is not supported by parity-db".into()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn iterable_columns_survive_reopening() {
+		let dir = tempdir::TempDir::new("parity-db").unwrap();
+		let path = dir.path().join("db");
+
+		{
+			let db = open(&path, 2, &[Some(0)]).unwrap();
+			let mut transaction = DBTransaction::new();
+			transaction.put(Some(0), b"leaf_b", b"2");
+			transaction.put(Some(0), b"leaf_a", b"1");
+			transaction.put(Some(0), b"other", b"3");
+			transaction.put(Some(1), b"leaf_c", b"4");
+			db.write(transaction).unwrap();
+
+			let mut transaction = DBTransaction::new();
+			transaction.delete(Some(0), b"leaf_b");
+			transaction.put(Some(0), b"leaf_d", b"5");
+			db.write(transaction).unwrap();
+		}
+
+		let db = open(&path, 2, &[Some(0)]).unwrap();
+		let leaves = db.iter_from_prefix(Some(0), b"leaf_")
+			.map(|(key, value)| (key.to_vec(), value.to_vec()))
+			.collect::<Vec<_>>();
+		assert_eq!(leaves, vec![
+			(b"leaf_a".to_vec(), b"1".to_vec()),
+			(b"leaf_d".to_vec(), b"5".to_vec()),
+		]);
+		assert_eq!(db.iter(Some(0)).count(), 3);
+		assert_eq!(db.get_by_prefix(Some(0), b"leaf_d").map(|value| value.to_vec()), Some(b"5".to_vec()));
+		assert_eq!(db.iter(Some(1)).count(), 0);
+		assert_eq!(db.get(Some(1), b"leaf_c").unwrap().map(|value| value.to_vec()), Some(b"4".to_vec()));
+	}
+}
@@ -578,6 +578,20 @@ impl<H: Hasher, S: StateBackend<H>, B: BlockT> StateBackend<H> for CachingState<
 		self.state.child_keys(child_key, prefix)
 	}
 
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		self.state.keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		self.state.child_keys_paged(child_key, prefix, start_key, count)
+	}
+
 	fn as_trie_backend(&mut self) -> Option<&TrieBackend<Self::TrieBackendStorage, H>> {
 		self.state.as_trie_backend()
 	}
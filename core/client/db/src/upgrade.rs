@@ -0,0 +1,225 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database schema versioning and migrations.
+//!
+//! The version of the schema is stored in the meta column. When a database written by an older
+//! version of the node is opened, the registered migrations are run in order to bring it to the
+//! current version. Databases written by a newer version are refused, as downgrades aren't
+//! supported.
+//!
+//! Changing the columns or the encoding of the stored data requires bumping `CURRENT_VERSION`
+//! and registering a migration to the new version in `MIGRATIONS`.
+
+use std::time::{Duration, Instant};
+
+use codec::{Decode, Encode};
+use kvdb::{DBTransaction, KeyValueDB};
+use log::info;
+
+use crate::utils::{db_err, meta_keys};
+
+/// Version of the schema of the databases written by this version of the node.
+///
+/// Databases created before the schema was versioned are at version 1.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Registered migrations, ordered by version.
+const MIGRATIONS: &[Migration] = &[
+	Migration {
+		version: 2,
+		description: "add the changes tries configuration cache column",
+		migrate: migrate_to_2,
+	},
+];
+
+/// Minimal interval between two progress reports of a migration.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Migration of the database from the previous version of the schema.
+pub struct Migration {
+	/// Version of the schema after the migration.
+	pub version: u32,
+	/// Description of the migration, logged when it starts.
+	pub description: &'static str,
+	/// Migrates the database, given its meta column.
+	///
+	/// The migration is run again if the node is stopped before it completes, so it must cope
+	/// with a partially migrated database.
+	pub migrate: fn(&dyn KeyValueDB, Option<u32>, &mut Progress) -> client::error::Result<()>,
+}
+
+/// Progress of a migration, logged periodically.
+pub struct Progress {
+	version: u32,
+	last_report: Instant,
+}
+
+impl Progress {
+	fn new(version: u32) -> Self {
+		Progress { version, last_report: Instant::now() }
+	}
+
+	/// Reports that `done` out of `total` items have been migrated.
+	pub fn report(&mut self, done: u64, total: u64) {
+		if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+			self.last_report = Instant::now();
+			info!(
+				"Migrating database to version {}: {}/{} ({}%)",
+				self.version,
+				done,
+				total,
+				done * 100 / total.max(1),
+			);
+		}
+	}
+}
+
+/// Version 2 adds the cache column to the full database.
+///
+/// The column itself is created when the database is opened, and the changes tries configuration
+/// cache that is stored in it is initialized by the backend on the first start. So there's no
+/// data to migrate.
+fn migrate_to_2(
+	_db: &dyn KeyValueDB,
+	_col_meta: Option<u32>,
+	_progress: &mut Progress,
+) -> client::error::Result<()> {
+	Ok(())
+}
+
+/// Writes the current schema version to a new database.
+pub(crate) fn init_version(transaction: &mut DBTransaction, col_meta: Option<u32>) {
+	transaction.put(col_meta, meta_keys::DB_VERSION, &CURRENT_VERSION.encode());
+}
+
+/// Brings an existing database to the current version of the schema.
+pub(crate) fn upgrade_db(db: &dyn KeyValueDB, col_meta: Option<u32>) -> client::error::Result<()> {
+	run_migrations(db, col_meta, MIGRATIONS, CURRENT_VERSION)
+}
+
+fn run_migrations(
+	db: &dyn KeyValueDB,
+	col_meta: Option<u32>,
+	migrations: &[Migration],
+	current_version: u32,
+) -> client::error::Result<()> {
+	let version = match db.get(col_meta, meta_keys::DB_VERSION).map_err(db_err)? {
+		Some(version) => u32::decode(&mut &version[..]).map_err(|e| client::error::Error::Backend(
+			format!("Invalid database version: {}", e.what())
+		))?,
+		None => 1,
+	};
+
+	if version > current_version {
+		return Err(client::error::Error::Backend(format!(
+			"Database version {} is newer than the version {} supported by this node. \
+			Downgrading the database isn't supported.",
+			version,
+			current_version,
+		)));
+	}
+
+	for migration in migrations.iter().filter(|m| m.version > version && m.version <= current_version) {
+		info!("Migrating database to version {}: {}", migration.version, migration.description);
+		let started = Instant::now();
+		(migration.migrate)(db, col_meta, &mut Progress::new(migration.version))?;
+
+		// the version is written after each migration, so that an interrupted upgrade resumes
+		// from the last completed migration.
+		let mut transaction = DBTransaction::new();
+		transaction.put(col_meta, meta_keys::DB_VERSION, &migration.version.encode());
+		db.write(transaction).map_err(db_err)?;
+		info!("Migrated database to version {} in {:?}", migration.version, started.elapsed());
+	}
+
+	if version < current_version {
+		let mut transaction = DBTransaction::new();
+		transaction.put(col_meta, meta_keys::DB_VERSION, &current_version.encode());
+		db.write(transaction).map_err(db_err)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const COLUMN_META: Option<u32> = Some(0);
+
+	fn version(db: &dyn KeyValueDB) -> Option<u32> {
+		db.get(COLUMN_META, meta_keys::DB_VERSION).unwrap()
+			.map(|version| u32::decode(&mut &version[..]).unwrap())
+	}
+
+	fn migrate_to_2(
+		db: &dyn KeyValueDB,
+		col_meta: Option<u32>,
+		_: &mut Progress,
+	) -> client::error::Result<()> {
+		let mut transaction = DBTransaction::new();
+		transaction.put(col_meta, b"migrated_to", &2u32.encode());
+		db.write(transaction).map_err(db_err)
+	}
+
+	fn migrate_to_3(
+		db: &dyn KeyValueDB,
+		col_meta: Option<u32>,
+		_: &mut Progress,
+	) -> client::error::Result<()> {
+		let mut transaction = DBTransaction::new();
+		transaction.put(col_meta, b"migrated_to", &3u32.encode());
+		db.write(transaction).map_err(db_err)
+	}
+
+	const TEST_MIGRATIONS: &[Migration] = &[
+		Migration { version: 2, description: "test migration to 2", migrate: migrate_to_2 },
+		Migration { version: 3, description: "test migration to 3", migrate: migrate_to_3 },
+	];
+
+	#[test]
+	fn migrations_are_run_in_order() {
+		let db = kvdb_memorydb::create(1);
+		run_migrations(&db, COLUMN_META, TEST_MIGRATIONS, 3).unwrap();
+
+		assert_eq!(version(&db), Some(3));
+		assert_eq!(db.get(COLUMN_META, b"migrated_to").unwrap().unwrap().to_vec(), 3u32.encode());
+	}
+
+	#[test]
+	fn only_pending_migrations_are_run() {
+		let db = kvdb_memorydb::create(1);
+		run_migrations(&db, COLUMN_META, TEST_MIGRATIONS, 2).unwrap();
+		assert_eq!(version(&db), Some(2));
+		assert_eq!(db.get(COLUMN_META, b"migrated_to").unwrap().unwrap().to_vec(), 2u32.encode());
+
+		let mut transaction = DBTransaction::new();
+		transaction.delete(COLUMN_META, b"migrated_to");
+		db.write(transaction).unwrap();
+		run_migrations(&db, COLUMN_META, TEST_MIGRATIONS, 2).unwrap();
+		assert!(db.get(COLUMN_META, b"migrated_to").unwrap().is_none());
+	}
+
+	#[test]
+	fn downgrade_is_refused() {
+		let db = kvdb_memorydb::create(1);
+		run_migrations(&db, COLUMN_META, TEST_MIGRATIONS, 3).unwrap();
+
+		assert!(run_migrations(&db, COLUMN_META, TEST_MIGRATIONS, 2).is_err());
+		assert!(upgrade_db(&db, COLUMN_META).is_err());
+	}
+}
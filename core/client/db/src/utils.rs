@@ -19,10 +19,12 @@
 
 use std::sync::Arc;
 use std::{io, convert::TryInto};
+#[cfg(feature = "kvdb-rocksdb")]
+use std::path::Path;
 
 use kvdb::{KeyValueDB, DBTransaction};
 #[cfg(feature = "kvdb-rocksdb")]
-use kvdb_rocksdb::{Database, DatabaseConfig};
+use kvdb_rocksdb::{CompactionProfile, Database, DatabaseConfig};
 use log::debug;
 
 use client;
@@ -34,10 +36,12 @@ use sr_primitives::traits::{
 	UniqueSaturatedFrom, UniqueSaturatedInto,
 };
 use crate::{DatabaseSettings, DatabaseSettingsSrc};
+#[cfg(feature = "kvdb-rocksdb")]
+use crate::{RocksDbSettings, CompactionStyle};
 
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
-pub const NUM_COLUMNS: u32 = 10;
+pub const NUM_COLUMNS: u32 = 11;
 /// Meta column. The set of keys in the column is shared by full && light storages.
 pub const COLUMN_META: Option<u32> = Some(0);
 
@@ -57,6 +61,12 @@ pub mod meta_keys {
 	pub const LEAF_PREFIX: &[u8; 4] = b"leaf";
 	/// Children prefix list key.
 	pub const CHILDREN_PREFIX: &[u8; 8] = b"children";
+	/// Version of the database schema.
+	pub const DB_VERSION: &[u8; 9] = b"dbversion";
+	/// Changes tries pruning meta: the oldest block whose changes trie isn't pruned yet.
+	pub const CHANGES_TRIES_META: &[u8; 18] = b"changes_tries_meta";
+	/// Blocks pruning meta: the oldest block whose body isn't pruned yet.
+	pub const BLOCKS_PRUNING_META: &[u8; 19] = b"blocks_pruning_meta";
 }
 
 /// Database metadata.
@@ -203,6 +213,15 @@ pub fn db_err(err: io::Error) -> client::error::Error {
 	client::error::Error::Backend(format!("{}", err))
 }
 
+/// Returns the columns of a database of the given type that must support iteration.
+#[cfg(feature = "parity-db")]
+pub(crate) fn iterable_columns(db_type: &str) -> &'static [Option<u32>] {
+	match db_type {
+		"light" => crate::light::columns::ITERABLE,
+		_ => crate::columns::ITERABLE,
+	}
+}
+
 /// Open RocksDB database.
 pub fn open_database(
 	config: &DatabaseSettings,
@@ -211,31 +230,38 @@ pub fn open_database(
 ) -> client::error::Result<Arc<dyn KeyValueDB>> {
 	let db: Arc<dyn KeyValueDB> = match &config.source {
 		#[cfg(feature = "kvdb-rocksdb")]
-		DatabaseSettingsSrc::Path { path, cache_size } => {
-			let mut db_config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
-			db_config.memory_budget = *cache_size;
-			let path = path.to_str().ok_or_else(|| client::error::Error::Backend("Invalid database path".into()))?;
-			Arc::new(Database::open(&db_config, &path).map_err(db_err)?)
-		},
+		DatabaseSettingsSrc::Path { path, cache_size, rocksdb } =>
+			Arc::new(open_rocksdb(path, *cache_size, rocksdb)?),
 		#[cfg(not(feature = "kvdb-rocksdb"))]
 		DatabaseSettingsSrc::Path { .. } => {
 			let msg = "Try to open RocksDB database with RocksDB disabled".into();
 			return Err(client::error::Error::Backend(msg));
 		},
+		#[cfg(feature = "parity-db")]
+		DatabaseSettingsSrc::ParityDb { path } => Arc::new(
+			crate::parity_db::open(path, NUM_COLUMNS, iterable_columns(db_type)).map_err(db_err)?
+		),
+		#[cfg(not(feature = "parity-db"))]
+		DatabaseSettingsSrc::ParityDb { .. } => {
+			let msg = "Try to open parity-db database with parity-db disabled".into();
+			return Err(client::error::Error::Backend(msg));
+		},
 		DatabaseSettingsSrc::Custom(db) => db.clone(),
 	};
 
-	// check database type
+	// check database type, and bring existing databases to the current schema
 	match db.get(col_meta, meta_keys::TYPE).map_err(db_err)? {
 		Some(stored_type) => {
 			if db_type.as_bytes() != &*stored_type {
 				return Err(client::error::Error::Backend(
 					format!("Unexpected database type. Expected: {}", db_type)).into());
 			}
+			crate::upgrade::upgrade_db(&*db, col_meta)?;
 		},
 		None => {
 			let mut transaction = DBTransaction::new();
 			transaction.put(col_meta, meta_keys::TYPE, db_type.as_bytes());
+			crate::upgrade::init_version(&mut transaction, col_meta);
 			db.write(transaction).map_err(db_err)?;
 		},
 	}
@@ -243,6 +269,34 @@ pub fn open_database(
 	Ok(db)
 }
 
+/// Open the RocksDB database at `path`.
+#[cfg(feature = "kvdb-rocksdb")]
+pub fn open_rocksdb(
+	path: &Path,
+	cache_size: Option<usize>,
+	settings: &RocksDbSettings,
+) -> client::error::Result<Database> {
+	let mut db_config = DatabaseConfig::with_columns(Some(NUM_COLUMNS));
+	if let Some(column) = settings.column_memory_budgets.keys().find(|column| **column >= NUM_COLUMNS) {
+		return Err(client::error::Error::Backend(format!("Unknown database column {}", column)));
+	}
+	let column_budgets: usize = settings.column_memory_budgets.values().sum();
+	db_config.memory_budget = match cache_size {
+		None if column_budgets == 0 => None,
+		cache_size => Some(cache_size.unwrap_or(0) + column_budgets),
+	};
+	if let Some(max_open_files) = settings.max_open_files {
+		db_config.max_open_files = max_open_files;
+	}
+	db_config.compaction = match settings.compaction {
+		CompactionStyle::Ssd => CompactionProfile::ssd(),
+		CompactionStyle::Hdd => CompactionProfile::hdd(),
+		CompactionStyle::Auto => CompactionProfile::auto(path),
+	};
+	let path = path.to_str().ok_or_else(|| client::error::Error::Backend("Invalid database path".into()))?;
+	Database::open(&db_config, &path).map_err(db_err)
+}
+
 /// Read database column entry for the given block.
 pub fn read_db<Block>(
 	db: &dyn KeyValueDB,
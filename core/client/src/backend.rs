@@ -26,6 +26,7 @@ use sr_primitives::traits::{Block as BlockT, NumberFor};
 use state_machine::backend::Backend as StateBackend;
 use state_machine::{ChangesTrieStorage as StateChangesTrieStorage, ChangesTrieTransaction};
 use crate::blockchain::well_known_cache_keys;
+use crate::snapshot::SnapshotChunk;
 use consensus::BlockOrigin;
 use hash_db::Hasher;
 use parking_lot::Mutex;
@@ -56,6 +57,22 @@ pub struct ClientImportOperation<
 	pub(crate) notify_finalized: Vec<Block::Hash>,
 }
 
+/// A block removed from the chain by a revert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevertedBlock<Block: BlockT> {
+	/// Hash of the block.
+	pub hash: Block::Hash,
+	/// Number of the block.
+	pub number: NumberFor<Block>,
+	/// Hash of the parent of the block, which becomes the best block.
+	pub parent_hash: Block::Hash,
+	/// Whether the block was finalized.
+	pub finalized: bool,
+	/// Whether the state changes of the block were canonicalized, i.e. moved from the journal of
+	/// the non-canonical blocks to the pruning journal.
+	pub canonical: bool,
+}
+
 /// State of a new block.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NewBlockState {
@@ -118,6 +135,28 @@ pub trait BlockImportOperation<Block, H> where
 	/// Inject storage data into the database replacing any existing data.
 	fn reset_storage(&mut self, top: StorageOverlay, children: ChildrenStorageOverlay) -> error::Result<H::Out>;
 
+	/// Inject the chunks of a state snapshot into the database replacing any existing data.
+	///
+	/// The default implementation collects the whole state and calls `reset_storage`. Backends
+	/// may write the state as the chunks are read, in which case a failed import can leave data
+	/// behind: the database should then be removed.
+	fn import_state(
+		&mut self,
+		chunks: &mut dyn Iterator<Item=error::Result<SnapshotChunk>>,
+	) -> error::Result<H::Out> {
+		let mut top = StorageOverlay::new();
+		let mut children = ChildrenStorageOverlay::new();
+		for chunk in chunks {
+			let chunk = chunk?;
+			let storage = match chunk.child_storage_key {
+				Some(child_storage_key) => children.entry(child_storage_key).or_default(),
+				None => &mut top,
+			};
+			storage.extend(chunk.entries);
+		}
+		self.reset_storage(top, children)
+	}
+
 	/// Set storage changes.
 	fn update_storage(
 		&mut self,
@@ -138,6 +177,15 @@ pub trait BlockImportOperation<Block, H> where
 	fn mark_finalized(&mut self, id: BlockId<Block>, justification: Option<Justification>) -> error::Result<()>;
 	/// Mark a block as new head. If both block import and set head are specified, set head overrides block import's best block rule.
 	fn mark_head(&mut self, id: BlockId<Block>) -> error::Result<()>;
+
+	/// Make the pending block the base of the chain, e.g. when initializing an empty database from
+	/// a state snapshot.
+	///
+	/// The block is finalized on import even though its parent is unknown, and `genesis_hash` is
+	/// recorded as the hash of the genesis block of the chain.
+	fn set_base_block(&mut self, _genesis_hash: Block::Hash) -> error::Result<()> {
+		Err(error::Error::Backend("Base blocks are not supported by this backend".into()))
+	}
 }
 
 /// Finalize Facilities
@@ -267,8 +315,22 @@ pub trait Backend<Block, H>: AuxStore + Send + Sync where
 
 	/// Attempts to revert the chain by `n` blocks.
 	///
-	/// Returns the number of blocks that were successfully reverted.
-	fn revert(&self, n: NumberFor<Block>) -> error::Result<NumberFor<Block>>;
+	/// Finalized blocks are only reverted if `revert_finalized` is set, and as long as the state of
+	/// their parent is available. The revert stops at the last finalized block with a consensus
+	/// digest, e.g. changing the authorities, as the state of the consensus engines isn't reverted.
+	/// Returns the blocks that were successfully reverted, starting with the former best block.
+	fn revert(
+		&self,
+		n: NumberFor<Block>,
+		revert_finalized: bool,
+	) -> error::Result<Vec<RevertedBlock<Block>>>;
+
+	/// Returns the blocks `revert` would revert, without modifying the database.
+	fn plan_revert(
+		&self,
+		n: NumberFor<Block>,
+		revert_finalized: bool,
+	) -> error::Result<Vec<RevertedBlock<Block>>>;
 
 	/// Insert auxiliary data into key-value store.
 	fn insert_aux<
@@ -315,16 +377,31 @@ pub trait OffchainStorage: Clone + Send + Sync {
 	) -> bool;
 }
 
+/// Changes trie configuration along with the range of blocks where it has been active.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangesTrieConfigurationRange<Number, Hash> {
+	/// Zero block of the configuration, i.e. the block where it has been set. The configuration
+	/// is active starting from the next block.
+	pub zero: (Number, Hash),
+	/// End block of the configuration, i.e. the last block where it has been active. None if
+	/// the configuration is still active.
+	pub end: Option<(Number, Hash)>,
+	/// The configuration itself. None if changes tries are disabled within this range.
+	pub config: Option<ChangesTrieConfiguration>,
+}
+
 /// Changes trie storage that supports pruning.
 pub trait PrunableStateChangesTrieStorage<Block: BlockT, H: Hasher>:
 	StateChangesTrieStorage<H, NumberFor<Block>>
 {
-	/// Get number block of oldest, non-pruned changes trie.
-	fn oldest_changes_trie_block(
+	/// Get the changes trie configuration that is active at the children of the given block,
+	/// along with the range of blocks where it is active.
+	fn configuration_at(
 		&self,
-		config: &ChangesTrieConfiguration,
-		best_finalized: NumberFor<Block>,
-	) -> NumberFor<Block>;
+		at: &BlockId<Block>,
+	) -> error::Result<ChangesTrieConfigurationRange<NumberFor<Block>, Block::Hash>>;
+	/// Get number of the oldest block for which changes trie is not pruned.
+	fn oldest_changes_trie_block(&self) -> error::Result<NumberFor<Block>>;
 }
 
 /// Mark for all Backend implementations, that are making use of state data, stored locally.
@@ -0,0 +1,193 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracing of the execution of blocks.
+//!
+//! A block is traced by executing it again on top of the state of its parent, one step at a time:
+//! the initialization of the block, each of its extrinsics in order, and the finalization of the
+//! block. The duration and the storage accesses of every step are recorded, so that the
+//! extrinsics and the storage items dominating the execution time of a block can be found.
+//!
+//! The steps are executed through the runtime API calls of the block builder, and their durations
+//! include the computation of the storage root that follows every call. They are meant to be
+//! compared with one another, not with the time taken to import the block.
+//!
+//! The changes of the storage made by a block are found the same way, by comparing the values
+//! written by the execution with the values in the state of the parent.
+
+use std::time::{Duration, Instant};
+use codec::Encode;
+use primitives::{Blake2Hasher, NeverNativeValue};
+use sr_primitives::traits::{Block as BlockT, Header as HeaderT};
+use state_machine::{self, AccessRecordingBackend, OverlayedChanges};
+use crate::call_executor::CallExecutor;
+use crate::error;
+
+pub use state_machine::{StorageAccess, StorageAccessKind};
+
+/// Step of the execution of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceTarget {
+	/// Initialization of the block, including the `on_initialize` hooks of the runtime modules.
+	Initialization,
+	/// Application of the extrinsic at the given index in the block body.
+	Extrinsic(u32),
+	/// Finalization of the block, including the `on_finalize` hooks of the runtime modules.
+	Finalization,
+}
+
+/// Trace of a step of the execution of a block.
+#[derive(Debug, Clone)]
+pub struct TraceSpan {
+	/// The traced step.
+	pub target: TraceTarget,
+	/// Wall time taken by the step.
+	pub duration: Duration,
+	/// Storage accesses of the step: the reads, in the order they were made, followed by the
+	/// writes.
+	///
+	/// Keys read again after being written by an earlier step are served from memory, and aren't
+	/// recorded as reads.
+	pub storage: Vec<StorageAccess>,
+}
+
+/// Trace of the execution of a block.
+#[derive(Debug, Clone)]
+pub struct BlockTrace<Hash> {
+	/// Hash of the traced block.
+	pub hash: Hash,
+	/// Wall time taken by the whole execution.
+	pub duration: Duration,
+	/// The steps of the execution, in order.
+	pub spans: Vec<TraceSpan>,
+}
+
+/// Change of the value of a storage key by the execution of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageChange {
+	/// The child storage the key belongs to, `None` for the top-level storage.
+	pub child_storage_key: Option<Vec<u8>>,
+	/// The changed key.
+	pub key: Vec<u8>,
+	/// Value before the execution of the block, `None` if there was no value.
+	pub old_value: Option<Vec<u8>>,
+	/// Value after the execution of the block, `None` if the value was removed.
+	pub new_value: Option<Vec<u8>>,
+}
+
+/// Execute the given block on top of `state`, the state of its parent, one step at a time.
+///
+/// `on_step` is called after each step, before its changes are committed to `overlay`.
+fn execute_steps<Block, E, S>(
+	executor: &E,
+	state: &S,
+	overlay: &mut OverlayedChanges,
+	mut header: Block::Header,
+	body: &[Block::Extrinsic],
+	mut on_step: impl FnMut(TraceTarget, Duration, &OverlayedChanges),
+) -> error::Result<()> where
+	Block: BlockT,
+	E: CallExecutor<Block, Blake2Hasher>,
+	S: state_machine::Backend<Blake2Hasher>,
+{
+	// the seal is added after the execution, and is refused by the runtime.
+	header.digest_mut().logs.retain(|item| item.as_seal().is_none());
+
+	let mut execute = |target, method: &str, call_data: &[u8]| -> error::Result<()> {
+		let started = Instant::now();
+		executor.call_at_state::<_, _, NeverNativeValue, fn() -> _>(
+			state,
+			overlay,
+			method,
+			call_data,
+			state_machine::native_else_wasm(),
+			None,
+			None,
+		)?;
+		on_step(target, started.elapsed(), overlay);
+		overlay.commit_prospective();
+		Ok(())
+	};
+
+	execute(TraceTarget::Initialization, "Core_initialize_block", &header.encode())?;
+	for (index, extrinsic) in body.iter().enumerate() {
+		execute(TraceTarget::Extrinsic(index as u32), "BlockBuilder_apply_extrinsic", &extrinsic.encode())?;
+	}
+	execute(TraceTarget::Finalization, "BlockBuilder_finalize_block", &[])
+}
+
+/// Execute the given block on top of `state`, the state of its parent, and trace the execution.
+pub fn trace_block<Block, E, S>(
+	executor: &E,
+	state: &S,
+	header: Block::Header,
+	body: &[Block::Extrinsic],
+) -> error::Result<BlockTrace<Block::Hash>> where
+	Block: BlockT,
+	E: CallExecutor<Block, Blake2Hasher>,
+	S: state_machine::Backend<Blake2Hasher>,
+{
+	let hash = header.hash();
+	let state = AccessRecordingBackend::new(state);
+	let mut overlay = OverlayedChanges::default();
+	let mut spans = Vec::with_capacity(body.len() + 2);
+	let started = Instant::now();
+	execute_steps(executor, &state, &mut overlay, header, body, |target, duration, overlay| {
+		let mut storage = state.take_accesses();
+		storage.extend(StorageAccess::prospective_writes(overlay));
+		spans.push(TraceSpan { target, duration, storage });
+	})?;
+
+	Ok(BlockTrace { hash, duration: started.elapsed(), spans })
+}
+
+/// Execute the given block on top of `state`, the state of its parent, and return the changes of
+/// the storage made by the block.
+///
+/// Keys set to their previous value aren't listed. The changes are ordered by child storage, the
+/// top-level storage first, then by key.
+pub fn storage_diff<Block, E, S>(
+	executor: &E,
+	state: &S,
+	header: Block::Header,
+	body: &[Block::Extrinsic],
+) -> error::Result<Vec<StorageChange>> where
+	Block: BlockT,
+	E: CallExecutor<Block, Blake2Hasher>,
+	S: state_machine::Backend<Blake2Hasher>,
+{
+	let mut overlay = OverlayedChanges::default();
+	execute_steps(executor, state, &mut overlay, header, body, |_, _, _| {})?;
+
+	let (top, children) = overlay.into_committed();
+	let top = top.map(|(key, value)| (None, key, value));
+	let children = children.flat_map(|(storage_key, changes)|
+		changes.map(move |(key, value)| (Some(storage_key.clone()), key, value))
+	);
+
+	let mut diff = Vec::new();
+	for (child_storage_key, key, new_value) in top.chain(children) {
+		let old_value = match &child_storage_key {
+			None => state.storage(&key),
+			Some(storage_key) => state.child_storage(storage_key, &key),
+		}.map_err(|e| error::Error::from_state(Box::new(e)))?;
+		if old_value != new_value {
+			diff.push(StorageChange { child_storage_key, key, old_value, new_value });
+		}
+	}
+	diff.sort_by(|a, b| (&a.child_storage_key, &a.key).cmp(&(&b.child_storage_key, &b.key)));
+	Ok(diff)
+}
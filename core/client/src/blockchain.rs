@@ -85,6 +85,13 @@ pub trait Backend<Block: BlockT>: HeaderBackend<Block> + HeaderMetadata<Block, E
 	fn last_finalized(&self) -> Result<Block::Hash>;
 	/// Returns data cache reference, if it is enabled on this backend.
 	fn cache(&self) -> Option<Arc<dyn Cache<Block>>>;
+	/// Get the hash of the block that contains the extrinsic with the given hash, and the index of
+	/// the extrinsic in the block. Returns `None` if the extrinsic is not found.
+	///
+	/// Fails if the backend doesn't maintain an index of extrinsics.
+	fn extrinsic_location(&self, _hash: &Block::Hash) -> Result<Option<(Block::Hash, u32)>> {
+		Err(Error::ExtrinsicIndexDisabled)
+	}
 
 	/// Returns hashes of all blocks that are leaves of the block tree.
 	/// in other words, that have no children, are chain heads.
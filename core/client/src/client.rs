@@ -17,8 +17,8 @@
 //! Substrate Client
 
 use std::{
-	marker::PhantomData, collections::{HashSet, BTreeMap, HashMap}, sync::Arc,
-	panic::UnwindSafe, result, cell::RefCell, rc::Rc,
+	marker::PhantomData, collections::{HashSet, BTreeMap, BTreeSet, HashMap}, sync::Arc,
+	panic::UnwindSafe, result, cell::RefCell, rc::Rc, time::Instant,
 };
 use log::{info, trace, warn};
 use futures03::channel::mpsc;
@@ -41,9 +41,11 @@ use sr_primitives::{
 };
 use state_machine::{
 	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId, ExecutionStrategy, ExecutionManager,
-	prove_read, prove_child_read, ChangesTrieRootsStorage, ChangesTrieStorage,
-	ChangesTrieTransaction, ChangesTrieConfigurationRange, key_changes, key_changes_proof,
-	OverlayedChanges, BackendTrustLevel, StorageProof, merge_storage_proofs,
+	prove_read, prove_child_read, prove_prefix_read, ChangesTrieRootsStorage, ChangesTrieStorage,
+	ChangesTrieTransaction, ChangesTrieConfigurationRange as StateChangesTrieConfigurationRange,
+	key_changes, key_changes_proof,
+	OverlayedChanges, BackendTrustLevel, StorageProof, merge_storage_proofs, ReadCountingBackend,
+	storage_proof_size,
 };
 use executor::{RuntimeVersion, RuntimeInfo};
 use consensus::{
@@ -60,7 +62,7 @@ use crate::{
 	},
 	backend::{
 		self, BlockImportOperation, PrunableStateChangesTrieStorage,
-		ClientImportOperation, Finalizer, ImportSummary,
+		ClientImportOperation, Finalizer, ImportSummary, RevertedBlock,
 	},
 	blockchain::{
 		self, Info as ChainInfo, Backend as ChainBackend,
@@ -68,6 +70,8 @@ use crate::{
 		well_known_cache_keys::Id as CacheKeyId,
 	},
 	call_executor::{CallExecutor, LocalCallExecutor},
+	block_trace::{self, BlockTrace, StorageChange},
+	execution_metrics::{self, BlockExecutionMetrics, ExecutionMetricsConfig},
 	notifications::{StorageNotifications, StorageEventStream},
 	light::{call_executor::prove_execution, fetcher::ChangesProof},
 	block_builder::{self, api::BlockBuilder as BlockBuilderAPI},
@@ -131,6 +135,7 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	importing_block: RwLock<Option<Block::Hash>>,
 	fork_blocks: ForkBlocks<Block>,
 	execution_strategies: ExecutionStrategies,
+	execution_metrics: Option<ExecutionMetricsConfig>,
 	_phantom: PhantomData<RA>,
 }
 
@@ -300,7 +305,9 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		fork_blocks: ForkBlocks<Block>,
 		execution_strategies: ExecutionStrategies
 	) -> error::Result<Self> {
-		if backend.blockchain().header(BlockId::Number(Zero::zero()))?.is_none() {
+		// databases initialized from a state snapshot have no genesis block.
+		let is_empty = backend.blockchain().info().finalized_hash == Default::default();
+		if is_empty && backend.blockchain().header(BlockId::Number(Zero::zero()))?.is_none() {
 			let (genesis_storage, children_genesis_storage) = build_genesis_storage.build_storage()?;
 			let mut op = backend.begin_operation()?;
 			backend.begin_state_operation(&mut op, BlockId::Hash(Default::default()))?;
@@ -328,6 +335,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			importing_block: Default::default(),
 			fork_blocks,
 			execution_strategies,
+			execution_metrics: None,
 			_phantom: Default::default(),
 		})
 	}
@@ -337,6 +345,50 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		&self.execution_strategies
 	}
 
+	/// Record the execution metrics of imported blocks, or stop recording them if `None`.
+	pub fn set_execution_metrics(&mut self, config: Option<ExecutionMetricsConfig>) {
+		self.execution_metrics = config;
+	}
+
+	/// Get the recorded execution metrics of a block.
+	///
+	/// Returns `None` if the block is unknown or its metrics were not recorded.
+	pub fn execution_metrics(&self, id: &BlockId<Block>) -> error::Result<Option<BlockExecutionMetrics>> {
+		let header = match self.backend.blockchain().header(*id)? {
+			Some(header) => header,
+			None => return Ok(None),
+		};
+		let hash = header.hash();
+		Ok(execution_metrics::read_metrics::<Block, _>(&*self.backend, *header.number())?
+			.into_iter()
+			.find(|(h, _)| *h == hash)
+			.map(|(_, metrics)| metrics))
+	}
+
+	/// Execute a block again on top of the state of its parent, and trace the duration and the
+	/// storage accesses of each step of the execution.
+	///
+	/// See [`block_trace`](../block_trace/index.html) for the details.
+	pub fn trace_block(&self, id: &BlockId<Block>) -> error::Result<BlockTrace<Block::Hash>> {
+		let header = self.header(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", id)))?;
+		let body = self.body(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("Body of {:?}", id)))?;
+		let state = self.state_at(&BlockId::Hash(*header.parent_hash()))?;
+		block_trace::trace_block(&self.executor, &state, header, &body)
+	}
+
+	/// Execute a block again on top of the state of its parent, and return the changes of the
+	/// storage made by the block, with the values before and after the block.
+	pub fn block_storage_diff(&self, id: &BlockId<Block>) -> error::Result<Vec<StorageChange>> {
+		let header = self.header(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", id)))?;
+		let body = self.body(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("Body of {:?}", id)))?;
+		let state = self.state_at(&BlockId::Hash(*header.parent_hash()))?;
+		block_trace::storage_diff(&self.executor, &state, header, &body)
+	}
+
 	/// Get a reference to the state at a given block.
 	pub fn state_at(&self, block: &BlockId<Block>) -> error::Result<B::State> {
 		self.backend.state_at(*block)
@@ -348,6 +400,50 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(keys)
 	}
 
+	/// Given a `BlockId`, a key prefix and an optional key to start from, return at most `count`
+	/// keys of that block's state, in lexicographic order.
+	///
+	/// Only keys strictly greater than `start_key` are returned, so the last key of a page can be
+	/// used as the start of the next one.
+	pub fn storage_keys_paged(
+		&self,
+		id: &BlockId<Block>,
+		prefix: Option<&StorageKey>,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> error::Result<Vec<StorageKey>> {
+		let prefix = prefix.map(|prefix| &prefix.0[..]).unwrap_or(&[]);
+		let start_key = start_key.map(|key| &key.0[..]);
+		let keys = self.state_at(id)?
+			.keys_paged(prefix, start_key, count)
+			.into_iter()
+			.map(StorageKey)
+			.collect();
+		Ok(keys)
+	}
+
+	/// Write a snapshot of the state at the given block, in the format of the
+	/// [`snapshot`](crate::snapshot) module.
+	///
+	/// Returns the number of chunks of the snapshot.
+	pub fn export_state<W: std::io::Write>(
+		&self,
+		id: &BlockId<Block>,
+		chunk_size: usize,
+		output: &mut W,
+	) -> error::Result<u32> {
+		let header = self.backend.blockchain().header(*id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", id)))?;
+		let genesis_hash = self.backend.blockchain().info().genesis_hash;
+		crate::snapshot::write_snapshot::<Block, _, _>(
+			&self.state_at(id)?,
+			genesis_hash,
+			header,
+			chunk_size,
+			output,
+		)
+	}
+
 	/// Given a `BlockId` and a key, return the value under the key in that block.
 	pub fn storage(&self, id: &BlockId<Block>, key: &StorageKey) -> error::Result<Option<StorageData>> {
 		Ok(self.state_at(id)?
@@ -379,6 +475,26 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(keys)
 	}
 
+	/// Given a `BlockId`, a child storage key, a key prefix and an optional key to start from, return
+	/// at most `count` keys of the child storage in that block, in lexicographic order.
+	pub fn child_storage_keys_paged(
+		&self,
+		id: &BlockId<Block>,
+		child_storage_key: &StorageKey,
+		prefix: Option<&StorageKey>,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> error::Result<Vec<StorageKey>> {
+		let prefix = prefix.map(|prefix| &prefix.0[..]).unwrap_or(&[]);
+		let start_key = start_key.map(|key| &key.0[..]);
+		let keys = self.state_at(id)?
+			.child_keys_paged(&child_storage_key.0, prefix, start_key, count)
+			.into_iter()
+			.map(StorageKey)
+			.collect();
+		Ok(keys)
+	}
+
 	/// Given a `BlockId`, a key and a child storage key, return the value under the key in that block.
 	pub fn child_storage(
 		&self,
@@ -415,6 +531,14 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		self.executor.runtime_version(id)
 	}
 
+	/// Get the names of the functions exported by the runtime at a given block.
+	///
+	/// Calls to other methods of the Wasm runtime fail without entering it.
+	pub fn runtime_exports_at(&self, id: &BlockId<Block>) -> error::Result<BTreeSet<String>> {
+		executor::runtime_exports(&self.code_at(id)?)
+			.map_err(|e| error::Error::Execution(Box::new(e.to_string())))
+	}
+
 	/// Get call executor reference.
 	pub fn executor(&self) -> &E {
 		&self.executor
@@ -430,6 +554,17 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	/// Reads all the storage values with a key starting with `prefix` at a given block,
+	/// returning read proof.
+	///
+	/// The proof lets the verifier enumerate every key with the prefix, not only check the
+	/// values of known keys.
+	pub fn prefix_read_proof(&self, id: &BlockId<Block>, prefix: &[u8]) -> error::Result<StorageProof> {
+		self.state_at(id)
+			.and_then(|state| prove_prefix_read(state, prefix)
+				.map_err(Into::into))
+	}
+
 	/// Reads child storage value at a given block + storage_key + key, returning
 	/// read proof.
 	pub fn read_child_proof<I>(
@@ -503,17 +638,25 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		first: NumberFor<Block>,
 		last: BlockId<Block>,
 	) -> error::Result<Option<(NumberFor<Block>, BlockId<Block>)>> {
-		let (config, storage) = match self.require_changes_trie().ok() {
-			Some((config, storage)) => (config, storage),
-			None => return Ok(None),
-		};
-		let last_num = self.backend.blockchain().expect_block_number_from_id(&last)?;
-		if first > last_num {
+		let last_number = self.backend.blockchain().expect_block_number_from_id(&last)?;
+		let last_hash = self.backend.blockchain().expect_block_hash_from_id(&last)?;
+		if first > last_number {
 			return Err(error::Error::ChangesTrieAccessFailed("Invalid changes trie range".into()));
 		}
-		let finalized_number = self.backend.blockchain().info().finalized_number;
-		let oldest = storage.oldest_changes_trie_block(&config, finalized_number);
-		let first = ::std::cmp::max(first, oldest);
+
+		let (storage, configs) = match self.require_changes_trie(first, last_hash, false).ok() {
+			Some((storage, configs)) => (storage, configs),
+			None => return Ok(None),
+		};
+
+		// changes tries are only available after the zero block of the oldest configuration
+		// that has been active without interruption up to the last block
+		let first_available_changes_trie = match configs.last() {
+			Some((config_zero, _, _)) => *config_zero + One::one(),
+			None => return Ok(None),
+		};
+		let oldest_unpruned = storage.oldest_changes_trie_block()?;
+		let first = ::std::cmp::max(first, ::std::cmp::max(first_available_changes_trie, oldest_unpruned));
 		Ok(Some((first, last)))
 	}
 
@@ -528,30 +671,35 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		storage_key: Option<&StorageKey>,
 		key: &StorageKey
 	) -> error::Result<Vec<(NumberFor<Block>, u32)>> {
-		let (config, storage) = self.require_changes_trie()?;
 		let last_number = self.backend.blockchain().expect_block_number_from_id(&last)?;
 		let last_hash = self.backend.blockchain().expect_block_hash_from_id(&last)?;
+		let (storage, configs) = self.require_changes_trie(first, last_hash, true)?;
+		let best_number = self.backend.blockchain().info().best_number;
+
+		// configurations are ordered from the latest to the oldest => so are the changes
+		let mut result = Vec::new();
+		for (config_zero, config_end, config) in configs {
+			let range_first = ::std::cmp::max(first, config_zero + One::one());
+			let range_anchor = changes_trie_range_anchor::<Block>(config_end, last_number, last_hash);
+			let config_range = StateChangesTrieConfigurationRange {
+				config: &config,
+				zero: config_zero,
+				end: config_end.map(|(config_end_number, _)| config_end_number),
+			};
+			let range_result: Vec<(NumberFor<Block>, u32)> = key_changes::<Blake2Hasher, _>(
+				config_range,
+				&*storage,
+				range_first,
+				&range_anchor,
+				best_number,
+				storage_key.as_ref().map(|sk| sk.0.as_slice()),
+				&key.0)
+			.and_then(|r| r.map(|r| r.map(|(block, tx)| (block, tx))).collect::<Result<_, _>>())
+			.map_err(|err| error::Error::ChangesTrieAccessFailed(err))?;
+			result.extend(range_result);
+		}
 
-		// FIXME: remove this in https://github.com/paritytech/substrate/pull/3201
-		let config_range = ChangesTrieConfigurationRange {
-			config: &config,
-			zero: Zero::zero(),
-			end: None,
-		};
-
-		key_changes::<Blake2Hasher, _>(
-			config_range,
-			&*storage,
-			first,
-			&ChangesTrieAnchorBlockId {
-				hash: convert_hash(&last_hash),
-				number: last_number,
-			},
-			self.backend.blockchain().info().best_number,
-			storage_key.as_ref().map(|sk| sk.0.as_slice()),
-			&key.0)
-		.and_then(|r| r.map(|r| r.map(|(block, tx)| (block, tx))).collect::<Result<_, _>>())
-		.map_err(|err| error::Error::ChangesTrieAccessFailed(err))
+		Ok(result)
 	}
 
 	/// Get proof for computation of (block, extrinsic) pairs where key has been changed at given blocks range.
@@ -633,12 +781,24 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				self.storage.with_cached_changed_keys(root, functor)
 			}
 
+			fn configuration_zero(
+				&self,
+				parent: &ChangesTrieAnchorBlockId<H256, NumberFor<Block>>,
+			) -> Result<NumberFor<Block>, String> {
+				self.storage.configuration_zero(parent)
+			}
+
 			fn get(&self, key: &H256, prefix: Prefix) -> Result<Option<DBValue>, String> {
 				self.storage.get(key, prefix)
 			}
 		}
 
-		let (config, storage) = self.require_changes_trie()?;
+		// fetch key changes proof
+		let first_number = self.backend.blockchain()
+			.expect_block_number_from_id(&BlockId::Hash(first))?;
+		let last_number = self.backend.blockchain()
+			.expect_block_number_from_id(&BlockId::Hash(last))?;
+		let (storage, configs) = self.require_changes_trie(first_number, last, true)?;
 		let min_number = self.backend.blockchain().expect_block_number_from_id(&BlockId::Hash(min))?;
 
 		let recording_storage = AccessedRootsRecorder::<Block> {
@@ -652,31 +812,27 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			self.backend.blockchain().expect_block_number_from_id(&BlockId::Hash(max))?,
 		);
 
-		// FIXME: remove this in https://github.com/paritytech/substrate/pull/3201
-		let config_range = ChangesTrieConfigurationRange {
-			config: &config,
-			zero: Zero::zero(),
-			end: None,
-		};
-
-		// fetch key changes proof
-		let first_number = self.backend.blockchain()
-			.expect_block_number_from_id(&BlockId::Hash(first))?;
-		let last_number = self.backend.blockchain()
-			.expect_block_number_from_id(&BlockId::Hash(last))?;
-		let key_changes_proof = key_changes_proof::<Blake2Hasher, _>(
-			config_range,
-			&recording_storage,
-			first_number,
-			&ChangesTrieAnchorBlockId {
-				hash: convert_hash(&last),
-				number: last_number,
-			},
-			max_number,
-			storage_key.as_ref().map(|sk| sk.0.as_slice()),
-			&key.0,
-		)
-		.map_err(|err| error::Error::from(error::Error::ChangesTrieAccessFailed(err)))?;
+		let mut key_changes_proof = Vec::new();
+		for (config_zero, config_end, config) in configs {
+			let range_first = ::std::cmp::max(first_number, config_zero + One::one());
+			let range_anchor = changes_trie_range_anchor::<Block>(config_end, last_number, last);
+			let config_range = StateChangesTrieConfigurationRange {
+				config: &config,
+				zero: config_zero,
+				end: config_end.map(|(config_end_number, _)| config_end_number),
+			};
+			let proof_range = key_changes_proof::<Blake2Hasher, _>(
+				config_range,
+				&recording_storage,
+				range_first,
+				&range_anchor,
+				max_number,
+				storage_key.as_ref().map(|sk| sk.0.as_slice()),
+				&key.0,
+			)
+			.map_err(|err| error::Error::from(error::Error::ChangesTrieAccessFailed(err)))?;
+			key_changes_proof.extend(proof_range);
+		}
 
 		// now gather proofs for all changes tries roots that were touched during key_changes_proof
 		// execution AND are unknown (i.e. replaced with CHT) to the requester
@@ -731,14 +887,51 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(proof)
 	}
 
-	/// Returns changes trie configuration and storage or an error if it is not supported.
-	fn require_changes_trie(&self) -> error::Result<(ChangesTrieConfiguration, &B::ChangesTrieStorage)> {
-		let config = self.changes_trie_config()?;
-		let storage = self.backend.changes_trie_storage();
-		match (config, storage) {
-			(Some(config), Some(storage)) => Ok((config, storage)),
-			_ => Err(error::Error::ChangesTriesNotSupported.into()),
+	/// Returns changes trie storage and all configurations that have been active within the
+	/// [first; last] blocks range, ordered from the latest to the oldest. Every configuration
+	/// is returned as (zero block number, end block, configuration).
+	///
+	/// If changes tries have been disabled within the range, then either an error is returned
+	/// (when `fail_if_disabled` is true), or only the configurations that are active after the
+	/// latest disabled range are returned.
+	fn require_changes_trie(
+		&self,
+		first: NumberFor<Block>,
+		last: Block::Hash,
+		fail_if_disabled: bool,
+	) -> error::Result<(
+		&B::ChangesTrieStorage,
+		Vec<(NumberFor<Block>, Option<(NumberFor<Block>, Block::Hash)>, ChangesTrieConfiguration)>,
+	)> {
+		let storage = match self.backend.changes_trie_storage() {
+			Some(storage) => storage,
+			None => return Err(error::Error::ChangesTriesNotSupported),
+		};
+
+		// the changes trie of the block is built using the configuration that is active at its parent
+		let last_header = self.backend.blockchain().expect_header(BlockId::Hash(last))?;
+		if last_header.number().is_zero() {
+			return Ok((storage, Vec::new()));
 		}
+
+		let mut configs = Vec::with_capacity(1);
+		let mut current = *last_header.parent_hash();
+		loop {
+			let config_range = storage.configuration_at(&BlockId::Hash(current))?;
+			match config_range.config {
+				Some(config) => configs.push((config_range.zero.0, config_range.end, config)),
+				None if !fail_if_disabled => return Ok((storage, configs)),
+				None => return Err(error::Error::ChangesTriesNotSupported),
+			}
+
+			if config_range.zero.0 < first || config_range.zero.0.is_zero() {
+				break;
+			}
+
+			current = *self.backend.blockchain().expect_header(BlockId::Hash(config_range.zero.1))?.parent_hash();
+		}
+
+		Ok((storage, configs))
 	}
 
 	/// Create a new block, built on the head of the chain.
@@ -947,7 +1140,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				}
 
 				// FIXME #1232: correct path logic for when to execute this function
-				let (storage_update, changes_update, storage_changes) = self.block_execution(
+				let (storage_update, changes_update, storage_changes, metrics) = self.block_execution(
 					&operation.op,
 					&import_headers,
 					origin,
@@ -955,6 +1148,16 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 					&body,
 				)?;
 
+				if let (Some(config), Some(metrics)) = (&self.execution_metrics, metrics) {
+					operation.op.insert_aux(execution_metrics::record_metrics::<Block, _>(
+						&*self.backend,
+						config.keep_blocks,
+						*import_headers.post().number(),
+						hash,
+						metrics,
+					)?)?;
+				}
+
 				operation.op.update_cache(new_cache);
 				if let Some(storage_update) = storage_update {
 					operation.op.update_db_storage(storage_update)?;
@@ -1036,7 +1239,8 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Option<(
 			Vec<(Vec<u8>, Option<Vec<u8>>)>,
 			Vec<(Vec<u8>, Vec<(Vec<u8>, Option<Vec<u8>>)>)>
-		)>
+		)>,
+		Option<BlockExecutionMetrics>,
 	)>
 		where
 			E: CallExecutor<Block, Blake2Hasher> + Send + Sync + Clone,
@@ -1070,34 +1274,171 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 					body,
 				);
 
-				let (_, storage_update, changes_update) = self.executor
-					.call_at_state::<_, _, NeverNativeValue, fn() -> _>(
-						transaction_state,
-						&mut overlay,
-						"Core_execute_block",
-						&encoded_block,
-						match origin {
-							BlockOrigin::NetworkInitialSync => get_execution_manager(
-								self.execution_strategies().syncing,
-							),
-							_ => get_execution_manager(self.execution_strategies().importing),
-						},
-						None,
-						None,
-					)?;
+				let execution_manager = || match origin {
+					BlockOrigin::NetworkInitialSync => get_execution_manager(
+						self.execution_strategies().syncing,
+					),
+					_ => get_execution_manager(self.execution_strategies().importing),
+				};
+
+				let (storage_update, changes_update, measured) = match self.execution_metrics {
+					None => {
+						let (_, storage_update, changes_update) = self.executor
+							.call_at_state::<_, _, NeverNativeValue, fn() -> _>(
+								transaction_state,
+								&mut overlay,
+								"Core_execute_block",
+								&encoded_block,
+								execution_manager(),
+								None,
+								None,
+							)?;
+						(storage_update, changes_update, None)
+					},
+					Some(ref config) => {
+						let counting_state = if config.proof_size {
+							ReadCountingBackend::with_read_keys(transaction_state)
+						} else {
+							ReadCountingBackend::new(transaction_state)
+						};
+						let started = Instant::now();
+						let mut execute = || self.executor
+							.call_at_state::<_, _, NeverNativeValue, fn() -> _>(
+								&counting_state,
+								&mut overlay,
+								"Core_execute_block",
+								&encoded_block,
+								execution_manager(),
+								None,
+								None,
+							);
+						let (result, fuel) = if config.meter_fuel {
+							let (result, fuel) = executor::with_fuel_limit_and_usage(u64::max_value(), execute);
+							(result, Some(fuel))
+						} else {
+							(execute(), None)
+						};
+						let (_, storage_update, changes_update) = result?;
+						let execution_time = started.elapsed();
+						(storage_update, changes_update, Some((config, counting_state, execution_time, fuel)))
+					},
+				};
 
 				overlay.commit_prospective();
 
 				let (top, children) = overlay.into_committed();
-				let children = children.map(|(sk, it)| (sk, it.collect())).collect();
+				let top: Vec<_> = top.collect();
+				let children: Vec<(_, Vec<_>)> = children.map(|(sk, it)| (sk, it.collect())).collect();
 				if import_headers.post().state_root() != &storage_update.1 {
 					return Err(error::Error::InvalidStateRoot);
 				}
 
-				Ok((Some(storage_update.0), Some(changes_update), Some((top.collect(), children))))
+				if let Some((_, Some(new_code))) = top.iter().find(|(key, _)| &key[..] == well_known_keys::CODE) {
+					self.check_runtime_upgrade(transaction_state, *import_headers.pre().parent_hash(), new_code)?;
+				}
+
+				let metrics = match measured {
+					Some((config, counting_state, execution_time, fuel)) => {
+						let proof_size = if config.proof_size {
+							Some(self.execution_proof_size(
+								*import_headers.pre().parent_hash(),
+								counting_state.read_keys(),
+								&top,
+								&children,
+							)?)
+						} else {
+							None
+						};
+						let reads = counting_state.stats();
+						Some(BlockExecutionMetrics {
+							execution_time_us: execution_time.as_micros() as u64,
+							storage_reads: reads.reads,
+							storage_read_bytes: reads.bytes,
+							storage_writes: (top.len() + children.iter().map(|(_, c)| c.len()).sum::<usize>()) as u64,
+							fuel,
+							proof_size,
+						})
+					},
+					None => None,
+				};
+
+				Ok((Some(storage_update.0), Some(changes_update), Some((top, children)), metrics))
 			},
-			None => Ok((None, None, None))
+			None => Ok((None, None, None, None))
+		}
+	}
+
+	/// Check the runtime code set by a block on top of `parent_hash`, whose state is given.
+	///
+	/// The new code has to be instantiable and to have the same `spec_name` and at least the same
+	/// `spec_version` as the current runtime. A malformed upgrade is refused here, as otherwise
+	/// the import of all the following blocks would fail.
+	fn check_runtime_upgrade<S: StateBackend<Blake2Hasher>>(
+		&self,
+		state: &S,
+		parent_hash: Block::Hash,
+		new_code: &[u8],
+	) -> error::Result<()> {
+		let current = self.executor.runtime_version(&BlockId::Hash(parent_hash))?;
+
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(well_known_keys::CODE.to_vec(), Some(new_code.to_vec()));
+		let manager = ExecutionManager::<fn(
+			Result<NativeOrEncoded<NeverNativeValue>, E::Error>,
+			Result<NativeOrEncoded<NeverNativeValue>, E::Error>,
+		) -> Result<NativeOrEncoded<NeverNativeValue>, E::Error>>::AlwaysWasm(BackendTrustLevel::Trusted);
+		let (version, _, _) = self.executor
+			.call_at_state::<_, _, NeverNativeValue, fn() -> _>(
+				state,
+				&mut overlay,
+				"Core_version",
+				&[],
+				manager,
+				None,
+				None,
+			)
+			.map_err(|e| error::Error::InvalidRuntimeUpgrade(format!("can't call the new code: {}", e)))?;
+		let new = RuntimeVersion::decode(&mut &version.into_encoded()[..])
+			.map_err(|e| error::Error::InvalidRuntimeUpgrade(format!("can't decode the new version: {}", e)))?;
+
+		if new.spec_name != current.spec_name {
+			return Err(error::Error::InvalidRuntimeUpgrade(
+				format!("spec name changed from {} to {}", current.spec_name, new.spec_name)
+			))
+		}
+		if new.spec_version < current.spec_version {
+			return Err(error::Error::InvalidRuntimeUpgrade(
+				format!("spec version decreased from {} to {}", current.spec_version, new.spec_version)
+			))
 		}
+		Ok(())
+	}
+
+	/// Estimate the size of the storage proof a light client would need to verify the execution
+	/// of a block on top of `parent_hash`.
+	///
+	/// The proof covers the keys read by the execution and the keys it wrote, whose trie nodes
+	/// are needed to compute the new state root.
+	fn execution_proof_size(
+		&self,
+		parent_hash: Block::Hash,
+		read_keys: Vec<(Option<Vec<u8>>, Vec<u8>)>,
+		top: &[(Vec<u8>, Option<Vec<u8>>)],
+		children: &[(Vec<u8>, Vec<(Vec<u8>, Option<Vec<u8>>)>)],
+	) -> error::Result<u64> {
+		let mut state = self.backend.state_at(BlockId::Hash(parent_hash))?;
+		let trie_state = state.as_trie_backend()
+			.ok_or_else(||
+				Box::new(state_machine::ExecutionError::UnableToGenerateProof)
+					as Box<dyn state_machine::Error>
+			)?;
+		let written_keys = top.iter()
+			.map(|(key, _)| (None, key.clone()))
+			.chain(children.iter().flat_map(|(storage_key, changes)| changes.iter()
+				.map(move |(key, _)| (Some(storage_key.clone()), key.clone()))
+			));
+		storage_proof_size(trie_state, read_keys.into_iter().chain(written_keys))
+			.map_err(error::Error::Backend)
 	}
 
 	fn apply_finality_with_block_hash(
@@ -1213,10 +1554,23 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(())
 	}
 
-	/// Attempts to revert the chain by `n` blocks. Returns the number of blocks that were
-	/// successfully reverted.
-	pub fn revert(&self, n: NumberFor<Block>) -> error::Result<NumberFor<Block>> {
-		Ok(self.backend.revert(n)?)
+	/// Attempts to revert the chain by `n` blocks. Finalized blocks are only reverted if
+	/// `revert_finalized` is set. Returns the blocks that were successfully reverted.
+	pub fn revert(
+		&self,
+		n: NumberFor<Block>,
+		revert_finalized: bool,
+	) -> error::Result<Vec<RevertedBlock<Block>>> {
+		Ok(self.backend.revert(n, revert_finalized)?)
+	}
+
+	/// Returns the blocks `revert` would revert, without modifying the chain.
+	pub fn plan_revert(
+		&self,
+		n: NumberFor<Block>,
+		revert_finalized: bool,
+	) -> error::Result<Vec<RevertedBlock<Block>>> {
+		Ok(self.backend.plan_revert(n, revert_finalized)?)
 	}
 
 	/// Get blockchain info.
@@ -1262,6 +1616,23 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		self.backend.blockchain().body(*id)
 	}
 
+	/// Get the extrinsic with the given hash, along with the hash of the block that includes it and
+	/// its index in that block.
+	///
+	/// Requires the backend to maintain an index of extrinsics.
+	pub fn extrinsic(
+		&self,
+		hash: &Block::Hash,
+	) -> error::Result<Option<(Block::Hash, u32, <Block as BlockT>::Extrinsic)>> {
+		let (block_hash, index) = match self.backend.blockchain().extrinsic_location(hash)? {
+			Some(location) => location,
+			None => return Ok(None),
+		};
+		Ok(self.body(&BlockId::Hash(block_hash))?
+			.and_then(|body| body.into_iter().nth(index as usize))
+			.map(|extrinsic| (block_hash, index, extrinsic)))
+	}
+
 	/// Get block justification set by id.
 	pub fn justification(&self, id: &BlockId<Block>) -> error::Result<Option<Justification>> {
 		self.backend.blockchain().justification(*id)
@@ -1309,13 +1680,6 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(uncles)
 	}
 
-	fn changes_trie_config(&self) -> Result<Option<ChangesTrieConfiguration>, Error> {
-		Ok(self.backend.state_at(BlockId::Number(self.backend.blockchain().info().best_number))?
-			.storage(well_known_keys::CHANGES_TRIE_CONFIG)
-			.map_err(|e| error::Error::from_state(Box::new(e)))?
-			.and_then(|c| Decode::decode(&mut &*c).ok()))
-	}
-
 	/// Prepare in-memory header that is used in execution environment.
 	fn prepare_environment_block(&self, parent: &BlockId<Block>) -> error::Result<Block::Header> {
 		let parent_header = self.backend.blockchain().expect_header(*parent)?;
@@ -1805,6 +2169,20 @@ impl<B, E, Block, RA> backend::AuxStore for &Client<B, E, Block, RA>
 	}
 }
 
+/// Returns the anchor block that is used to query changes tries of the configuration range
+/// ending at `config_end`, when the whole query ends at the `last` block.
+fn changes_trie_range_anchor<Block: BlockT>(
+	config_end: Option<(NumberFor<Block>, Block::Hash)>,
+	last_number: NumberFor<Block>,
+	last_hash: Block::Hash,
+) -> ChangesTrieAnchorBlockId<H256, NumberFor<Block>> {
+	match config_end {
+		Some((config_end_number, config_end_hash)) if config_end_number < last_number =>
+			ChangesTrieAnchorBlockId { hash: convert_hash(&config_end_hash), number: config_end_number },
+		_ => ChangesTrieAnchorBlockId { hash: convert_hash(&last_hash), number: last_number },
+	}
+}
+
 /// Helper function to apply auxiliary data insertion into an operation.
 pub fn apply_aux<'a, 'b: 'a, 'c: 'a, B, Block, H, D, I>(
 	operation: &mut ClientImportOperation<Block, H, B>,
@@ -1995,6 +2373,129 @@ pub(crate) mod tests {
 		assert_eq!(client.info().chain.best_number, 1);
 	}
 
+	#[test]
+	fn execution_metrics_are_recorded() {
+		let mut client = test_client::new();
+		client.set_execution_metrics(Some(ExecutionMetricsConfig {
+			keep_blocks: 1,
+			meter_fuel: false,
+			proof_size: true,
+		}));
+
+		for nonce in 0..2 {
+			let mut builder = client.new_block(Default::default()).unwrap();
+			builder.push_transfer(Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Ferdie.into(),
+				amount: 42,
+				nonce,
+			}).unwrap();
+			client.import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		}
+
+		// the metrics of block #1 were pruned when importing block #2.
+		assert_eq!(client.execution_metrics(&BlockId::Number(1)).unwrap(), None);
+		let metrics = client.execution_metrics(&BlockId::Number(2)).unwrap().unwrap();
+		assert!(metrics.storage_reads > 0);
+		assert!(metrics.storage_writes > 0);
+		assert_eq!(metrics.fuel, None);
+		// at least the runtime code is in the proof.
+		let code_len = client.storage(&BlockId::Number(1), &StorageKey(well_known_keys::CODE.to_vec()))
+			.unwrap().unwrap().0.len() as u64;
+		assert!(metrics.proof_size.unwrap() > code_len);
+	}
+
+	#[test]
+	fn calls_to_missing_exports_are_refused() {
+		let client = test_client::new();
+		let exports = client.runtime_exports_at(&BlockId::Number(0)).unwrap();
+		assert!(exports.contains("Core_version"));
+		assert!(!exports.contains("Core_missing"));
+
+		let err = client.executor().call(
+			&BlockId::Number(0),
+			"Core_missing",
+			&[],
+			ExecutionStrategy::AlwaysWasm,
+			None,
+		).unwrap_err();
+		assert!(err.to_string().contains("Method not found: 'Core_missing'"));
+	}
+
+	#[test]
+	fn runtime_upgrades_are_checked_on_import() {
+		let client = test_client::new();
+		let code = client.storage(&BlockId::Number(0), &StorageKey(well_known_keys::CODE.to_vec()))
+			.unwrap().unwrap().0;
+		let version = client.runtime_version_at(&BlockId::Number(0)).unwrap();
+
+		// re-setting the current code is a valid upgrade.
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_storage_change(well_known_keys::CODE.to_vec(), Some(code.clone())).unwrap();
+		client.import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		assert_eq!(client.runtime_version_at(&BlockId::Number(1)).unwrap(), version);
+
+		let state = client.state_at(&BlockId::Number(1)).unwrap();
+		let err = client.check_runtime_upgrade(&state, client.info().chain.best_hash, &[1, 2, 3]).unwrap_err();
+		assert!(err.to_string().starts_with("Invalid runtime upgrade"));
+		client.check_runtime_upgrade(&state, client.info().chain.best_hash, &code).unwrap();
+	}
+
+	#[test]
+	fn blocks_are_traced() {
+		use crate::block_trace::{StorageAccessKind, TraceTarget};
+
+		let client = test_client::new();
+		let mut builder = client.new_block(Default::default()).unwrap();
+		for nonce in 0..2 {
+			builder.push_transfer(Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Ferdie.into(),
+				amount: 42,
+				nonce,
+			}).unwrap();
+		}
+		let block = builder.bake().unwrap();
+		let hash = block.header().hash();
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		let trace = client.trace_block(&BlockId::Number(1)).unwrap();
+		assert_eq!(trace.hash, hash);
+		assert_eq!(
+			trace.spans.iter().map(|span| span.target).collect::<Vec<_>>(),
+			vec![TraceTarget::Initialization, TraceTarget::Extrinsic(0), TraceTarget::Extrinsic(1), TraceTarget::Finalization],
+		);
+		let transfer = &trace.spans[1].storage;
+		assert!(transfer.iter().any(|access| access.kind == StorageAccessKind::Read));
+		assert!(transfer.iter().any(|access| access.kind == StorageAccessKind::Write));
+
+		assert!(client.trace_block(&BlockId::Number(2)).is_err());
+	}
+
+	#[test]
+	fn block_storage_diff_works() {
+		let client = test_client::new();
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		client.import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+
+		let diff = client.block_storage_diff(&BlockId::Number(1)).unwrap();
+		let alice = blake2_256(&runtime::system::balance_of_key(AccountKeyring::Alice.into())).to_vec();
+		let change = diff.iter().find(|change| change.key == alice).unwrap();
+		assert_eq!(change.child_storage_key, None);
+		let balance = |value: &Option<Vec<u8>>| u64::decode(&mut &value.as_ref().unwrap()[..]).unwrap();
+		assert_eq!(balance(&change.old_value) - 42, balance(&change.new_value));
+		assert!(diff.iter().all(|change| change.old_value != change.new_value));
+		assert!(diff.windows(2).all(|pair| pair[0].key < pair[1].key));
+
+		assert!(client.block_storage_diff(&BlockId::Number(2)).is_err());
+	}
+
 	#[test]
 	fn block_builder_works_with_transactions() {
 		let client = test_client::new();
@@ -2774,9 +3275,12 @@ pub(crate) mod tests {
 				state_cache_size: 1 << 20,
 				state_cache_child_ratio: None,
 				pruning: PruningMode::ArchiveAll,
+				blocks_pruning: None,
+				index_extrinsics: false,
 				source: DatabaseSettingsSrc::Path {
 					path: tmp.path().into(),
 					cache_size: None,
+					rocksdb: Default::default(),
 				}
 			},
 			u64::max_value(),
@@ -37,6 +37,9 @@ pub enum Error {
 	/// Unknown block.
 	#[display(fmt = "UnknownBlock: {}", _0)]
 	UnknownBlock(String),
+	/// The state of the block has been pruned.
+	#[display(fmt = "State already discarded for {}", _0)]
+	StatePruned(String),
 	/// Applying extrinsic error.
 	#[display(fmt = "Extrinsic error: {:?}", _0)]
 	ApplyExtrinsicFailed(ApplyError),
@@ -64,6 +67,12 @@ pub enum Error {
 	/// Not available on light client.
 	#[display(fmt = "This method is not currently available when running in light client mode")]
 	NotAvailableOnLightClient,
+	/// Extrinsics are not indexed by the backend.
+	#[display(fmt = "Extrinsics are not indexed, restart the node with `--index-extrinsics` to enable it")]
+	ExtrinsicIndexDisabled,
+	/// Invalid state snapshot.
+	#[display(fmt = "Invalid state snapshot: {}", _0)]
+	InvalidSnapshot(String),
 	/// Invalid remote CHT-based proof.
 	#[display(fmt = "Remote node has responded with invalid header proof")]
 	InvalidCHTProof,
@@ -97,6 +106,9 @@ pub enum Error {
 	/// Invalid calculated state root on block import.
 	#[display(fmt = "Calculated state root does not match.")]
 	InvalidStateRoot,
+	/// The runtime code set by an imported block is unusable.
+	#[display(fmt = "Invalid runtime upgrade: {}", _0)]
+	InvalidRuntimeUpgrade(String),
 	/// A convenience variant for String
 	#[display(fmt = "{}", _0)]
 	Msg(String),
@@ -0,0 +1,194 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Execution metrics of imported blocks.
+//!
+//! When enabled, the client records how expensive the execution of every imported block was, so
+//! that performance regressions, e.g. after a runtime upgrade, can be measured on production
+//! nodes. The metrics of the most recent blocks are kept in the auxiliary storage.
+
+use codec::{Encode, Decode};
+use sr_primitives::traits::{Block as BlockT, NumberFor, SaturatedConversion};
+use crate::backend::AuxStore;
+use crate::{error, versioned_aux};
+
+const METRICS_PREFIX: &[u8] = b"execution_metrics";
+
+/// Version of the encoding of the stored metrics.
+const METRICS_VERSION: u32 = 2;
+
+/// Configuration of the execution metrics.
+#[derive(Debug, Clone)]
+pub struct ExecutionMetricsConfig {
+	/// Number of most recent blocks to keep the metrics of.
+	pub keep_blocks: u32,
+	/// Measure the fuel consumed by the execution of blocks.
+	///
+	/// Metered blocks are always executed in the interpreted Wasm runtime, which is significantly
+	/// slower than the native runtime.
+	pub meter_fuel: bool,
+	/// Estimate the size of the storage proof of the execution of blocks.
+	///
+	/// The keys accessed by the execution are looked up again in the trie of the parent state
+	/// after the execution, which makes the import of blocks slower.
+	pub proof_size: bool,
+}
+
+/// Metrics of the execution of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct BlockExecutionMetrics {
+	/// Wall time taken by the execution, in microseconds.
+	pub execution_time_us: u64,
+	/// Number of storage keys read.
+	pub storage_reads: u64,
+	/// Total size of the storage values read.
+	pub storage_read_bytes: u64,
+	/// Number of storage keys written.
+	pub storage_writes: u64,
+	/// Fuel consumed by the execution, if metered.
+	pub fuel: Option<u64>,
+	/// Encoded size of the storage proof a light client would need to verify the execution, if
+	/// estimated.
+	pub proof_size: Option<u64>,
+}
+
+/// Metrics written with the version 1 of the encoding, without proof size.
+#[derive(Decode)]
+struct BlockExecutionMetricsV1 {
+	execution_time_us: u64,
+	storage_reads: u64,
+	storage_read_bytes: u64,
+	storage_writes: u64,
+	fuel: Option<u64>,
+}
+
+impl From<BlockExecutionMetricsV1> for BlockExecutionMetrics {
+	fn from(metrics: BlockExecutionMetricsV1) -> Self {
+		BlockExecutionMetrics {
+			execution_time_us: metrics.execution_time_us,
+			storage_reads: metrics.storage_reads,
+			storage_read_bytes: metrics.storage_read_bytes,
+			storage_writes: metrics.storage_writes,
+			fuel: metrics.fuel,
+			proof_size: None,
+		}
+	}
+}
+
+fn decode_old_metrics<Hash: Decode>(
+	version: u32,
+	input: &mut &[u8],
+) -> Result<Vec<(Hash, BlockExecutionMetrics)>, codec::Error> {
+	match version {
+		0 | 1 => Vec::<(Hash, BlockExecutionMetricsV1)>::decode(input)
+			.map(|entries| entries.into_iter().map(|(hash, metrics)| (hash, metrics.into())).collect()),
+		_ => Err("No migration from this version of the execution metrics".into()),
+	}
+}
+
+fn metrics_key<N: SaturatedConversion>(number: N) -> Vec<u8> {
+	let mut key = METRICS_PREFIX.to_vec();
+	key.extend(&number.saturated_into::<u64>().to_be_bytes());
+	key
+}
+
+/// Read the execution metrics of all known blocks with the given number.
+pub fn read_metrics<Block: BlockT, S: AuxStore>(
+	store: &S,
+	number: NumberFor<Block>,
+) -> error::Result<Vec<(Block::Hash, BlockExecutionMetrics)>> {
+	let key = metrics_key(number);
+	versioned_aux::load(store, &key, METRICS_VERSION, decode_old_metrics)
+		.map(Option::unwrap_or_default)
+}
+
+/// Returns the auxiliary storage changes recording `metrics` for the block `hash` numbered
+/// `number`, and removing the metrics of blocks that fall out of the `keep_blocks` window.
+pub fn record_metrics<Block: BlockT, S: AuxStore>(
+	store: &S,
+	keep_blocks: u32,
+	number: NumberFor<Block>,
+	hash: Block::Hash,
+	metrics: BlockExecutionMetrics,
+) -> error::Result<Vec<(Vec<u8>, Option<Vec<u8>>)>> {
+	let mut entries = read_metrics::<Block, _>(store, number)?;
+	entries.retain(|(h, _)| *h != hash);
+	entries.push((hash, metrics));
+
+	let mut changes = vec![(metrics_key(number), Some(versioned_aux::encode(METRICS_VERSION, &entries)))];
+	let number = number.saturated_into::<u64>();
+	if number >= u64::from(keep_blocks) {
+		changes.push((metrics_key(number - u64::from(keep_blocks)), None));
+	}
+	Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::H256;
+	use test_client::runtime::Block;
+	use crate::in_mem::Blockchain;
+
+	fn metrics(storage_reads: u64) -> BlockExecutionMetrics {
+		BlockExecutionMetrics {
+			execution_time_us: 1,
+			storage_reads,
+			storage_read_bytes: 0,
+			storage_writes: 0,
+			fuel: None,
+			proof_size: Some(storage_reads * 100),
+		}
+	}
+
+	#[test]
+	fn records_and_prunes_metrics() {
+		let backend = Blockchain::<Block>::new();
+		let apply = |changes: Vec<(Vec<u8>, Option<Vec<u8>>)>| {
+			let (insert, delete): (Vec<_>, Vec<_>) = changes.into_iter().partition(|(_, v)| v.is_some());
+			let insert = insert.into_iter().map(|(k, v)| (k, v.unwrap())).collect::<Vec<_>>();
+			let delete = delete.into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+			backend.insert_aux(
+				insert.iter().map(|(k, v)| (&k[..], &v[..])).collect::<Vec<_>>().iter(),
+				delete.iter().map(|k| &k[..]).collect::<Vec<_>>().iter(),
+			).unwrap();
+		};
+
+		for number in 0..3u64 {
+			apply(record_metrics::<Block, _>(&backend, 2, number, H256::from_low_u64_be(number), metrics(number)).unwrap());
+		}
+		// fork at #2
+		apply(record_metrics::<Block, _>(&backend, 2, 2, H256::from_low_u64_be(22), metrics(22)).unwrap());
+
+		assert!(read_metrics::<Block, _>(&backend, 0).unwrap().is_empty());
+		assert_eq!(read_metrics::<Block, _>(&backend, 1).unwrap(), vec![(H256::from_low_u64_be(1), metrics(1))]);
+		assert_eq!(read_metrics::<Block, _>(&backend, 2).unwrap(), vec![
+			(H256::from_low_u64_be(2), metrics(2)),
+			(H256::from_low_u64_be(22), metrics(22)),
+		]);
+	}
+
+	#[test]
+	fn metrics_without_proof_size_are_migrated() {
+		let backend = Blockchain::<Block>::new();
+		let old = BlockExecutionMetrics { proof_size: None, ..metrics(7) };
+		let encoded = (old.execution_time_us, old.storage_reads, old.storage_read_bytes, old.storage_writes, old.fuel);
+		let value = versioned_aux::encode(1, &vec![(H256::from_low_u64_be(7), encoded)]);
+		backend.insert_aux(&[(&metrics_key(7u64)[..], &value[..])], &[]).unwrap();
+
+		assert_eq!(read_metrics::<Block, _>(&backend, 7).unwrap(), vec![(H256::from_low_u64_be(7), old)]);
+	}
+}
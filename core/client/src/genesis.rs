@@ -16,7 +16,11 @@
 
 //! Tool for creating the genesis block.
 
+use std::collections::HashMap;
+use primitives::{Blake2Hasher, H256};
+use sr_primitives::{BuildStorage, StorageOverlay};
 use sr_primitives::traits::{Block as BlockT, Header as HeaderT, Hash as HashT, Zero};
+use state_machine::{Backend as StateBackend, backend::InMemory as InMemoryState};
 
 /// Create a genesis block, given the initial storage.
 pub fn construct_genesis_block<
@@ -40,6 +44,32 @@ pub fn construct_genesis_block<
 	)
 }
 
+/// Compute the hash of the genesis block that would be built from the given genesis storage.
+///
+/// Used to detect whether an existing database was initialized from a different genesis.
+pub fn genesis_hash<Block, S>(build_genesis_storage: S) -> crate::error::Result<Block::Hash>
+	where
+		Block: BlockT<Hash=H256>,
+		S: BuildStorage,
+{
+	let (top, children) = build_genesis_storage.build_storage()?;
+	crate::in_mem::check_genesis_storage(&top, &children)?;
+
+	let child_delta = children.keys()
+		.cloned()
+		.map(|storage_key| (storage_key, None))
+		.collect::<Vec<_>>();
+	let mut storage: HashMap<Option<Vec<u8>>, StorageOverlay> = HashMap::new();
+	storage.insert(None, top);
+	for (child_key, child_storage) in children {
+		storage.insert(Some(child_key), child_storage);
+	}
+
+	let state: InMemoryState<Blake2Hasher> = storage.into();
+	let (state_root, _) = state.full_storage_root(std::iter::empty(), child_delta);
+	Ok(construct_genesis_block::<Block>(state_root).header().hash())
+}
+
 #[cfg(test)]
 mod tests {
 	use codec::{Encode, Decode, Joiner};
@@ -240,4 +270,19 @@ mod tests {
 		);
 		assert!(r.is_err());
 	}
+
+	#[test]
+	fn genesis_hash_should_match_constructed_genesis_block() {
+		let mut storage = GenesisConfig::new(false,
+			vec![Sr25519Keyring::One.public().into(), Sr25519Keyring::Two.public().into()],
+			vec![AccountKeyring::One.into(), AccountKeyring::Two.into()],
+			1000,
+			None,
+			map![],
+			map![],
+		).genesis_map();
+		let expected = super::genesis_hash::<Block, _>(storage.clone()).unwrap();
+
+		assert_eq!(insert_genesis_block(&mut storage), expected);
+	}
 }
@@ -19,9 +19,9 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use parking_lot::{RwLock, Mutex};
-use primitives::{ChangesTrieConfiguration, storage::well_known_keys};
+use primitives::storage::well_known_keys;
 use sr_primitives::generic::{BlockId, DigestItem};
-use sr_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, NumberFor};
+use sr_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, NumberFor, Saturating};
 use sr_primitives::{Justification, StorageOverlay, ChildrenStorageOverlay};
 use state_machine::backend::{Backend as StateBackend, InMemory};
 use state_machine::{self, InMemoryChangesTrieStorage, ChangesTrieAnchorBlockId, ChangesTrieTransaction};
@@ -99,6 +99,7 @@ struct BlockchainStorage<Block: BlockT> {
 	header_cht_roots: HashMap<NumberFor<Block>, Block::Hash>,
 	changes_trie_cht_roots: HashMap<NumberFor<Block>, Block::Hash>,
 	leaves: LeafSet<Block::Hash, NumberFor<Block>>,
+	children: HashMap<Block::Hash, Vec<Block::Hash>>,
 	aux: HashMap<Vec<u8>, Vec<u8>>,
 }
 
@@ -139,6 +140,7 @@ impl<Block: BlockT> Blockchain<Block> {
 				header_cht_roots: HashMap::new(),
 				changes_trie_cht_roots: HashMap::new(),
 				leaves: LeafSet::new(),
+				children: HashMap::new(),
 				aux: HashMap::new(),
 			}));
 		Blockchain {
@@ -156,18 +158,26 @@ impl<Block: BlockT> Blockchain<Block> {
 		new_state: NewBlockState,
 	) -> crate::error::Result<()> {
 		let number = header.number().clone();
+		let parent_hash = header.parent_hash().clone();
 		if new_state.is_best() {
 			self.apply_head(&header)?;
 		}
 
 		{
 			let mut storage = self.storage.write();
-			storage.leaves.import(hash.clone(), number.clone(), header.parent_hash().clone());
+			storage.leaves.import(hash.clone(), number.clone(), parent_hash.clone());
 			storage.blocks.insert(hash.clone(), StoredBlock::new(header, body, justification));
+			if number != Zero::zero() {
+				let children = storage.children.entry(parent_hash).or_default();
+				if !children.contains(&hash) {
+					children.push(hash.clone());
+				}
+			}
 
 			if let NewBlockState::Final = new_state {
 				storage.finalized_hash = hash;
 				storage.finalized_number = number.clone();
+				storage.leaves.finalize_height(number.clone());
 			}
 
 			if number == Zero::zero() {
@@ -252,13 +262,15 @@ impl<Block: BlockT> Blockchain<Block> {
 	}
 
 	fn finalize_header(&self, id: BlockId<Block>, justification: Option<Justification>) -> error::Result<()> {
-		let hash = match self.header(id)? {
-			Some(h) => h.hash(),
+		let (hash, number) = match self.header(id)? {
+			Some(h) => (h.hash(), *h.number()),
 			None => return Err(error::Error::UnknownBlock(format!("{}", id))),
 		};
 
 		let mut storage = self.storage.write();
 		storage.finalized_hash = hash;
+		storage.finalized_number = number;
+		storage.leaves.finalize_height(number);
 
 		if justification.is_some() {
 			let block = storage.blocks.get_mut(&hash)
@@ -361,8 +373,8 @@ impl<Block: BlockT> blockchain::Backend<Block> for Blockchain<Block> {
 		Ok(self.storage.read().leaves.hashes())
 	}
 
-	fn children(&self, _parent_hash: Block::Hash) -> error::Result<Vec<Block::Hash>> {
-		unimplemented!()
+	fn children(&self, parent_hash: Block::Hash) -> error::Result<Vec<Block::Hash>> {
+		Ok(self.storage.read().children.get(&parent_hash).cloned().unwrap_or_default())
 	}
 }
 
@@ -545,7 +557,11 @@ where
 	}
 }
 
-/// In-memory backend. Keeps all states and blocks in memory.
+/// In-memory backend. Keeps all blocks in memory, and all states unless pruning is enabled.
+///
+/// Finality and pruning follow the same rules as the database backend: blocks must be finalized
+/// in order, and once a block is finalized the states of the blocks of other forks and, when
+/// pruning, of the old finalized blocks are discarded.
 ///
 /// > **Warning**: Doesn't support all the features necessary for a proper database. Only use this
 /// > struct for testing purposes. Do **NOT** use in production.
@@ -558,6 +574,7 @@ where
 	states: RwLock<HashMap<Block::Hash, InMemory<H>>>,
 	changes_trie_storage: ChangesTrieStorage<Block, H>,
 	blockchain: Blockchain<Block>,
+	keep_blocks: Option<u32>,
 	import_lock: Mutex<()>,
 }
 
@@ -567,15 +584,66 @@ where
 	H: Hasher<Out=Block::Hash>,
 	H::Out: Ord,
 {
-	/// Create a new instance of in-mem backend.
+	/// Create a new instance of in-mem backend, keeping the states of all the blocks.
 	pub fn new() -> Backend<Block, H> {
 		Backend {
 			states: RwLock::new(HashMap::new()),
 			changes_trie_storage: ChangesTrieStorage(InMemoryChangesTrieStorage::new()),
 			blockchain: Blockchain::new(),
+			keep_blocks: None,
 			import_lock: Default::default(),
 		}
 	}
+
+	/// Create a new instance of in-mem backend, keeping the states of the last `keep_blocks`
+	/// finalized blocks and of the blocks that aren't finalized yet.
+	pub fn new_with_pruning(keep_blocks: u32) -> Backend<Block, H> {
+		Backend {
+			keep_blocks: Some(keep_blocks),
+			..Self::new()
+		}
+	}
+
+	/// Returns an error unless the block with given header is the child of the last finalized
+	/// block.
+	fn ensure_sequential_finalization(
+		&self,
+		header: &Block::Header,
+		last_finalized: Block::Hash,
+	) -> error::Result<()> {
+		if *header.parent_hash() != last_finalized {
+			return Err(error::Error::NonSequentialFinalization(
+				format!("Last finalized {:?} not parent of {:?}", last_finalized, header.hash()),
+			));
+		}
+		Ok(())
+	}
+
+	/// Discards the states that are no longer available after finalizing the block with given
+	/// number.
+	fn prune_states(&self, finalized_number: NumberFor<Block>) {
+		let keep_blocks = match self.keep_blocks {
+			Some(keep_blocks) => keep_blocks,
+			None => return,
+		};
+
+		let mut states = self.states.write();
+		let storage = self.blockchain.storage.read();
+		states.retain(|hash, _| {
+			let number = match storage.blocks.get(hash) {
+				Some(block) => *block.header().number(),
+				None => return true,
+			};
+			if number > finalized_number {
+				return true;
+			}
+			// the forks of the finalized chain can't be finalized anymore.
+			if storage.hashes.get(&number) != Some(hash) {
+				return false;
+			}
+			number.saturating_add(keep_blocks.into()) > finalized_number
+		});
+	}
 }
 
 impl<Block, H> backend::AuxStore for Backend<Block, H>
@@ -631,11 +699,35 @@ where
 	}
 
 	fn commit_operation(&self, operation: Self::BlockImportOperation) -> error::Result<()> {
-		if !operation.finalized_blocks.is_empty() {
-			for (block, justification) in operation.finalized_blocks {
-				self.blockchain.finalize_header(block, justification)?;
+		// Check the operation before applying it, so that it is applied either entirely or not at
+		// all, like a database transaction.
+		let mut last_finalized = self.blockchain.storage.read().finalized_hash;
+		let mut finalized_number = None;
+		for (block, _) in &operation.finalized_blocks {
+			let header = self.blockchain.header(*block)?
+				.ok_or_else(|| error::Error::UnknownBlock(format!("{}", block)))?;
+			self.ensure_sequential_finalization(&header, last_finalized)?;
+			last_finalized = header.hash();
+			finalized_number = Some(*header.number());
+		}
+		if let Some(pending_block) = &operation.pending_block {
+			let header = pending_block.block.header();
+			if let NewBlockState::Final = pending_block.state {
+				if !header.number().is_zero() {
+					self.ensure_sequential_finalization(header, last_finalized)?;
+				}
+				finalized_number = Some(*header.number());
 			}
 		}
+		if let Some(set_head) = operation.set_head {
+			if self.blockchain.header(set_head)?.is_none() {
+				return Err(error::Error::UnknownBlock(format!("{}", set_head)));
+			}
+		}
+
+		for (block, justification) in operation.finalized_blocks {
+			self.blockchain.finalize_header(block, justification)?;
+		}
 
 		if let Some(pending_block) = operation.pending_block {
 			let old_state = &operation.old_state;
@@ -667,11 +759,21 @@ where
 			self.blockchain.set_head(set_head)?;
 		}
 
+		if let Some(finalized_number) = finalized_number {
+			self.prune_states(finalized_number);
+		}
+
 		Ok(())
 	}
 
 	fn finalize_block(&self, block: BlockId<Block>, justification: Option<Justification>) -> error::Result<()> {
-		self.blockchain.finalize_header(block, justification)
+		let header = self.blockchain.header(block)?
+			.ok_or_else(|| error::Error::UnknownBlock(format!("{}", block)))?;
+		let last_finalized = self.blockchain.storage.read().finalized_hash;
+		self.ensure_sequential_finalization(&header, last_finalized)?;
+		self.blockchain.finalize_header(block, justification)?;
+		self.prune_states(*header.number());
+		Ok(())
 	}
 
 	fn blockchain(&self) -> &Self::Blockchain {
@@ -698,14 +800,30 @@ where
 			_ => {},
 		}
 
-		match self.blockchain.id(block).and_then(|id| self.states.read().get(&id).cloned()) {
+		let hash = match self.blockchain.header(block)? {
+			Some(header) => header.hash(),
+			None => return Err(error::Error::UnknownBlock(format!("{}", block))),
+		};
+		match self.states.read().get(&hash).cloned() {
 			Some(state) => Ok(state),
-			None => Err(error::Error::UnknownBlock(format!("{}", block))),
+			None => Err(error::Error::StatePruned(format!("{}", block))),
 		}
 	}
 
-	fn revert(&self, _n: NumberFor<Block>) -> error::Result<NumberFor<Block>> {
-		Ok(Zero::zero())
+	fn revert(
+		&self,
+		_n: NumberFor<Block>,
+		_revert_finalized: bool,
+	) -> error::Result<Vec<backend::RevertedBlock<Block>>> {
+		Ok(Vec::new())
+	}
+
+	fn plan_revert(
+		&self,
+		_n: NumberFor<Block>,
+		_revert_finalized: bool,
+	) -> error::Result<Vec<backend::RevertedBlock<Block>>> {
+		Ok(Vec::new())
 	}
 
 	fn get_import_lock(&self) -> &Mutex<()> {
@@ -740,12 +858,15 @@ where
 /// Prunable in-memory changes trie storage.
 pub struct ChangesTrieStorage<Block: BlockT, H: Hasher>(InMemoryChangesTrieStorage<H, NumberFor<Block>>);
 impl<Block: BlockT, H: Hasher> backend::PrunableStateChangesTrieStorage<Block, H> for ChangesTrieStorage<Block, H> {
-	fn oldest_changes_trie_block(
+	fn configuration_at(
 		&self,
-		_config: &ChangesTrieConfiguration,
-		_best_finalized: NumberFor<Block>,
-	) -> NumberFor<Block> {
-		Zero::zero()
+		_at: &BlockId<Block>,
+	) -> error::Result<backend::ChangesTrieConfigurationRange<NumberFor<Block>, Block::Hash>> {
+		Err(error::Error::ChangesTriesNotSupported)
+	}
+
+	fn oldest_changes_trie_block(&self) -> error::Result<NumberFor<Block>> {
+		Ok(Zero::zero())
 	}
 }
 
@@ -787,6 +908,13 @@ impl<Block, H> state_machine::ChangesTrieStorage<H, NumberFor<Block>> for Change
 		false
 	}
 
+	fn configuration_zero(
+		&self,
+		_parent: &ChangesTrieAnchorBlockId<H::Out, NumberFor<Block>>,
+	) -> Result<NumberFor<Block>, String> {
+		Err("Dummy implementation".into())
+	}
+
 	fn get(&self, key: &H::Out, prefix: Prefix) -> Result<Option<state_machine::DBValue>, String> {
 		self.0.get(key, prefix)
 	}
@@ -862,6 +990,13 @@ mod tests {
 		test_client::trait_tests::test_leaves_for_backend(backend);
 	}
 
+	#[test]
+	fn test_children_with_complex_block_tree() {
+		let backend = Arc::new(TestBackend::new());
+
+		test_client::trait_tests::test_children_for_backend(backend);
+	}
+
 	#[test]
 	fn test_blockchain_query_by_number_gets_canonical() {
 		let backend = Arc::new(TestBackend::new());
@@ -869,6 +1004,49 @@ mod tests {
 		test_client::trait_tests::test_blockchain_query_by_number_gets_canonical(backend);
 	}
 
+	#[test]
+	fn finalization_is_sequential_and_prunes_states() {
+		use crate::backend::Backend as _;
+		use test_client::{prelude::*, consensus::BlockOrigin, runtime::Transfer};
+
+		let backend = Arc::new(TestBackend::new_with_pruning(1));
+		let client = test_client::TestClientBuilder::with_backend(backend.clone()).build();
+
+		// G -> A1 -> A2
+		// G -> B1
+		let a1 = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+		let a2 = client.new_block_at(&BlockId::Hash(a1.hash()), Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, a2.clone()).unwrap();
+		let mut builder = client.new_block_at(&BlockId::Number(0), Default::default()).unwrap();
+		builder.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 41,
+			nonce: 0,
+		}).unwrap();
+		let b1 = builder.bake().unwrap();
+		client.import(BlockOrigin::Own, b1.clone()).unwrap();
+
+		match backend.finalize_block(BlockId::Hash(a2.hash()), None) {
+			Err(error::Error::NonSequentialFinalization(_)) => (),
+			result => panic!("Unexpected result: {:?}", result.map(|_| ())),
+		}
+		assert!(backend.state_at(BlockId::Hash(b1.hash())).is_ok());
+
+		client.finalize_block(BlockId::Hash(a2.hash()), None).unwrap();
+		assert_eq!(backend.blockchain().info().finalized_number, 2);
+		assert!(backend.state_at(BlockId::Hash(a2.hash())).is_ok());
+		match backend.state_at(BlockId::Hash(a1.hash())) {
+			Err(error::Error::StatePruned(_)) => (),
+			_ => panic!("state of A1 should be pruned"),
+		}
+		match backend.state_at(BlockId::Hash(b1.hash())) {
+			Err(error::Error::StatePruned(_)) => (),
+			_ => panic!("state of B1 should be pruned"),
+		}
+	}
+
 	#[test]
 	fn in_memory_offchain_storage() {
 		use crate::backend::OffchainStorage as _;
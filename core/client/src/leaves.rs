@@ -161,9 +161,15 @@ impl<H, N> LeafSet<H, N> where
 	/// currently since revert only affects the canonical chain
 	/// we assume that parent has no further children
 	/// and we add it as leaf again
+	///
+	/// The changes are written by the next call to `prepare_transaction`.
 	pub fn revert(&mut self, hash: H, number: N, parent_hash: H) {
-		self.insert_leaf(Reverse(number.clone() - N::one()), parent_hash);
-		self.remove_leaf(&Reverse(number), &hash);
+		let parent_number = Reverse(number.clone() - N::one());
+		self.insert_leaf(parent_number.clone(), parent_hash.clone());
+		self.pending_added.push(LeafSetItem { hash: parent_hash, number: parent_number });
+		if self.remove_leaf(&Reverse(number), &hash) {
+			self.pending_removed.push(hash);
+		}
 	}
 
 	/// returns an iterator over all hashes in the leaf set
@@ -295,6 +301,33 @@ mod tests {
 		assert_eq!(set, set2);
 	}
 
+	#[test]
+	fn revert_consistent_with_disk() {
+		const PREFIX: &[u8] = b"prefix";
+		let db = ::kvdb_memorydb::create(0);
+
+		let mut set = LeafSet::new();
+		set.import(0u32, 0u32, 0u32);
+		set.import(1_1, 1, 0);
+		set.import(2_1, 2, 1_1);
+		set.import(2_2, 2, 1_1);
+
+		let mut tx = DBTransaction::new();
+		set.prepare_transaction(&mut tx, None, PREFIX);
+		db.write(tx).unwrap();
+
+		set.revert(2_1, 2, 1_1);
+		assert!(set.contains(1, 1_1));
+		assert!(!set.contains(2, 2_1));
+
+		let mut tx = DBTransaction::new();
+		set.prepare_transaction(&mut tx, None, PREFIX);
+		db.write(tx).unwrap();
+
+		let set2 = LeafSet::read_from_db(&db, None, PREFIX).unwrap();
+		assert_eq!(set, set2);
+	}
+
 	#[test]
 	fn two_leaves_same_height_can_be_included() {
 		let mut set = LeafSet::new();
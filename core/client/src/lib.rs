@@ -99,6 +99,16 @@ pub mod leaves;
 #[cfg(feature = "std")]
 pub mod children;
 #[cfg(feature = "std")]
+pub mod block_trace;
+#[cfg(feature = "std")]
+pub mod execution_metrics;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod versioned_aux;
+#[cfg(feature = "std")]
 mod call_executor;
 #[cfg(feature = "std")]
 mod client;
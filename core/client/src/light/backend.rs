@@ -23,11 +23,11 @@ use parking_lot::{RwLock, Mutex};
 
 use sr_primitives::{generic::BlockId, Justification, StorageOverlay, ChildrenStorageOverlay};
 use state_machine::{Backend as StateBackend, TrieBackend, backend::InMemory as InMemoryState, ChangesTrieTransaction};
-use sr_primitives::traits::{Block as BlockT, NumberFor, Zero, Header};
+use sr_primitives::traits::{Block as BlockT, NumberFor, Header};
 use crate::in_mem::{self, check_genesis_storage};
 use crate::backend::{
 	AuxStore, Backend as ClientBackend, BlockImportOperation, RemoteBackend, NewBlockState,
-	StorageCollection, ChildStorageCollection,
+	StorageCollection, ChildStorageCollection, RevertedBlock,
 };
 use crate::blockchain::{HeaderBackend as BlockchainHeaderBackend, well_known_cache_keys};
 use crate::error::{Error as ClientError, Result as ClientResult};
@@ -204,7 +204,19 @@ impl<S, Block, H> ClientBackend<Block, H> for Backend<S, H> where
 		Ok(GenesisOrUnavailableState::Unavailable)
 	}
 
-	fn revert(&self, _n: NumberFor<Block>) -> ClientResult<NumberFor<Block>> {
+	fn revert(
+		&self,
+		_n: NumberFor<Block>,
+		_revert_finalized: bool,
+	) -> ClientResult<Vec<RevertedBlock<Block>>> {
+		Err(ClientError::NotAvailableOnLightClient)
+	}
+
+	fn plan_revert(
+		&self,
+		_n: NumberFor<Block>,
+		_revert_finalized: bool,
+	) -> ClientResult<Vec<RevertedBlock<Block>>> {
 		Err(ClientError::NotAvailableOnLightClient)
 	}
 
@@ -24,6 +24,7 @@ use std::future::Future;
 use hash_db::{HashDB, Hasher, EMPTY_PREFIX};
 use codec::{Decode, Encode};
 use primitives::{ChangesTrieConfiguration, convert_hash, traits::CodeExecutor, H256};
+use sr_primitives::Justification;
 use sr_primitives::traits::{
 	Block as BlockT, Header as HeaderT, Hash, HashFor, NumberFor,
 	SimpleArithmetic, CheckedConversion, Zero,
@@ -142,6 +143,32 @@ pub struct RemoteBodyRequest<Header: HeaderT> {
 	pub retry_count: Option<usize>,
 }
 
+/// Remote block proof request: the header of a block, its justification and a read proof of
+/// storage keys at this block, all fetched in a single round trip.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteBlockProofRequest<Header: HeaderT> {
+	/// Hash of the block.
+	pub block: Header::Hash,
+	/// Number of the block. Used to pick the peers that may know the block.
+	pub number: Header::Number,
+	/// Storage keys to read at the block.
+	pub keys: Vec<Vec<u8>>,
+	/// Number of times to retry request. None means that default RETRY_COUNT is used.
+	pub retry_count: Option<usize>,
+}
+
+/// Checked response to a remote block proof request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockProof<Header: HeaderT> {
+	/// Header of the block.
+	pub header: Header,
+	/// Justification of the block, if the remote node has one. It is not checked against the
+	/// finality rules of the chain, this is up to the caller.
+	pub justification: Option<Justification>,
+	/// Values of the requested storage keys at the block.
+	pub values: HashMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
 /// Light client data fetcher. Implementations of this trait must check if remote data
 /// is correct (see FetchedDataChecker) and return already checked data.
 pub trait Fetcher<Block: BlockT>: Send + Sync {
@@ -155,6 +182,8 @@ pub trait Fetcher<Block: BlockT>: Send + Sync {
 	type RemoteChangesResult: Future<Output = Result<Vec<(NumberFor<Block>, u32)>, ClientError>> + Send + 'static;
 	/// Remote block body result future.
 	type RemoteBodyResult: Future<Output = Result<Vec<Block::Extrinsic>, ClientError>> + Send + 'static;
+	/// Remote block proof result future.
+	type RemoteBlockProofResult: Future<Output = Result<BlockProof<Block::Header>, ClientError>> + Send + 'static;
 
 	/// Fetch remote header.
 	fn remote_header(&self, request: RemoteHeaderRequest<Block::Header>) -> Self::RemoteHeaderResult;
@@ -175,6 +204,8 @@ pub trait Fetcher<Block: BlockT>: Send + Sync {
 	fn remote_changes(&self, request: RemoteChangesRequest<Block::Header>) -> Self::RemoteChangesResult;
 	/// Fetch remote block body
 	fn remote_body(&self, request: RemoteBodyRequest<Block::Header>) -> Self::RemoteBodyResult;
+	/// Fetch remote block header, justification and storage values in one request.
+	fn remote_block_proof(&self, request: RemoteBlockProofRequest<Block::Header>) -> Self::RemoteBlockProofResult;
 }
 
 /// Light client remote data checker.
@@ -219,6 +250,14 @@ pub trait FetchChecker<Block: BlockT>: Send + Sync {
 		request: &RemoteBodyRequest<Block::Header>,
 		body: Vec<Block::Extrinsic>
 	) -> ClientResult<Vec<Block::Extrinsic>>;
+	/// Check remote block proof.
+	fn check_block_proof(
+		&self,
+		request: &RemoteBlockProofRequest<Block::Header>,
+		header: Option<Block::Header>,
+		justification: Option<Justification>,
+		remote_proof: StorageProof,
+	) -> ClientResult<BlockProof<Block::Header>>;
 }
 
 /// Remote data checker.
@@ -452,6 +491,29 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		}
 
 	}
+
+	fn check_block_proof(
+		&self,
+		request: &RemoteBlockProofRequest<Block::Header>,
+		remote_header: Option<Block::Header>,
+		justification: Option<Justification>,
+		remote_proof: StorageProof,
+	) -> ClientResult<BlockProof<Block::Header>> {
+		let header = remote_header.ok_or_else(||
+			ClientError::from("RemoteBlockProofRequest: the header is missing"))?;
+		if header.hash() != request.block || *header.number() != request.number {
+			return Err(format!("RemoteBlockProofRequest: invalid header, expected {} but got {}",
+				request.block,
+				header.hash(),
+			).into());
+		}
+		let values = read_proof_check::<H, _>(
+			convert_hash(header.state_root()),
+			remote_proof,
+			request.keys.iter(),
+		)?;
+		Ok(BlockProof { header, justification, values })
+	}
 }
 
 /// A view of BTreeMap<Number, Hash> as a changes trie roots storage.
@@ -536,6 +598,7 @@ pub mod tests {
 		type RemoteCallResult = Ready<Result<Vec<u8>, ClientError>>;
 		type RemoteChangesResult = Ready<Result<Vec<(NumberFor<Block>, u32)>, ClientError>>;
 		type RemoteBodyResult = Ready<Result<Vec<Extrinsic>, ClientError>>;
+		type RemoteBlockProofResult = Ready<Result<BlockProof<Header>, ClientError>>;
 
 		fn remote_header(&self, _request: RemoteHeaderRequest<Header>) -> Self::RemoteHeaderResult {
 			not_implemented_in_tests()
@@ -560,6 +623,10 @@ pub mod tests {
 		fn remote_body(&self, _request: RemoteBodyRequest<Header>) -> Self::RemoteBodyResult {
 			not_implemented_in_tests()
 		}
+
+		fn remote_block_proof(&self, _request: RemoteBlockProofRequest<Header>) -> Self::RemoteBlockProofResult {
+			not_implemented_in_tests()
+		}
 	}
 
 	type TestChecker = LightDataChecker<
@@ -697,6 +764,33 @@ pub mod tests {
 		}, remote_read_proof).unwrap().remove(well_known_keys::HEAP_PAGES).unwrap().unwrap()[0], heap_pages as u8);
 	}
 
+	#[test]
+	fn block_proof_is_generated_and_checked() {
+		let (local_checker, remote_block_header, remote_read_proof, heap_pages) = prepare_for_read_proof_check();
+		let request = RemoteBlockProofRequest::<Header> {
+			block: remote_block_header.hash(),
+			number: *remote_block_header.number(),
+			keys: vec![well_known_keys::HEAP_PAGES.to_vec()],
+			retry_count: None,
+		};
+		let checker = &local_checker as &dyn FetchChecker<Block>;
+
+		assert!(checker.check_block_proof(&request, None, None, remote_read_proof.clone()).is_err());
+		let mut other_header = remote_block_header.clone();
+		other_header.number = 1;
+		assert!(checker.check_block_proof(&request, Some(other_header), None, remote_read_proof.clone()).is_err());
+
+		let mut proof = checker.check_block_proof(
+			&request,
+			Some(remote_block_header.clone()),
+			Some(vec![42]),
+			remote_read_proof,
+		).unwrap();
+		assert_eq!(proof.header, remote_block_header);
+		assert_eq!(proof.justification, Some(vec![42]));
+		assert_eq!(proof.values.remove(well_known_keys::HEAP_PAGES).unwrap().unwrap()[0], heap_pages as u8);
+	}
+
 	#[test]
 	fn storage_child_read_proof_is_generated_and_checked() {
 		let (
@@ -182,9 +182,14 @@ decl_runtime_apis! {
 	}
 
 	/// The `TaggedTransactionQueue` api trait for interfering with the new transaction queue.
+	#[api_version(2)]
 	pub trait TaggedTransactionQueue {
 		/// Validate the given transaction.
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity;
+		/// Returns the SCALE encoded account id of the signer of the given transaction.
+		///
+		/// Returns `None` for unsigned transactions and for signers that can't be looked up.
+		fn transaction_sender(tx: <Block as BlockT>::Extrinsic) -> Option<Vec<u8>>;
 	}
 }
 
@@ -0,0 +1,373 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State snapshots.
+//!
+//! A snapshot holds the header of a block and the full state at that block, so that a new node can
+//! be initialized from a trusted snapshot instead of syncing the chain from genesis.
+//!
+//! A snapshot is a stream of SCALE-encoded items:
+//!
+//! - a [`SnapshotHeader`], starting with the version of the format;
+//! - the storage entries, split in chunks of bounded size, each of them followed by its
+//!   Blake2-256 checksum. The entries of the child tries come first, then those of the top trie,
+//!   each trie in key order;
+//! - an end marker holding the number of chunks, so that truncated snapshots are detected.
+
+use std::io::{Read, Write};
+use codec::{Encode, Decode, IoReader};
+use primitives::{blake2_256, Blake2Hasher, H256, storage::well_known_keys};
+use sr_primitives::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
+use state_machine::backend::Backend as StateBackend;
+
+use crate::{
+	backend::{self, BlockImportOperation, NewBlockState},
+	blockchain::HeaderBackend,
+	error::{Error, Result},
+};
+
+/// Current version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Default maximal number of storage entries of a snapshot chunk.
+pub const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+/// Header of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotHeader<Hash, Header> {
+	/// Version of the snapshot format.
+	pub version: u32,
+	/// Hash of the genesis block of the chain.
+	pub genesis_hash: Hash,
+	/// Header of the block the state belongs to.
+	pub header: Header,
+}
+
+/// Storage entries of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotChunk {
+	/// Storage key of the child trie of the entries, or `None` for the top trie.
+	pub child_storage_key: Option<Vec<u8>>,
+	/// Key-value pairs.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+#[derive(Encode, Decode)]
+enum Item {
+	Chunk {
+		data: Vec<u8>,
+		checksum: [u8; 32],
+	},
+	End {
+		chunks: u32,
+	},
+}
+
+/// Write a snapshot of the given state, which is the state at the block with the given header.
+///
+/// The child tries are written before the top trie, so that their roots are known when the top
+/// trie is imported. Returns the number of chunks written.
+pub fn write_snapshot<Block, S, W>(
+	state: &S,
+	genesis_hash: Block::Hash,
+	header: Block::Header,
+	chunk_size: usize,
+	output: &mut W,
+) -> Result<u32> where
+	Block: BlockT,
+	S: StateBackend<Blake2Hasher>,
+	W: Write,
+{
+	let header = SnapshotHeader { version: SNAPSHOT_VERSION, genesis_hash, header };
+	output.write_all(&header.encode()).map_err(io_err)?;
+
+	let mut writer = ChunkWriter::new(output, chunk_size);
+	for child_storage_key in state.keys(well_known_keys::CHILD_STORAGE_KEY_PREFIX) {
+		writer.start_trie(Some(child_storage_key.clone()));
+		state.for_child_keys_with_prefix(&child_storage_key, &[], |key| {
+			let value = state.child_storage(&child_storage_key, key).map_err(state_err);
+			writer.push(key, value);
+		});
+	}
+	writer.start_trie(None);
+	state.for_key_values_with_prefix(&[], |key, value| {
+		// child tries are written apart, their roots are recomputed on import.
+		if !well_known_keys::is_child_storage_key(key) {
+			writer.push(key, Ok(Some(value.to_vec())));
+		}
+	});
+	writer.finish()
+}
+
+/// Reader of a snapshot, yielding its chunks one at a time.
+///
+/// The version of the snapshot is checked on creation, and the checksum of every chunk as it is
+/// read. The iteration stops at the end marker, which fails if chunks are missing, or at the first
+/// error.
+pub struct SnapshotReader<Block: BlockT, R: Read> {
+	input: IoReader<R>,
+	header: SnapshotHeader<Block::Hash, Block::Header>,
+	chunks: u32,
+	done: bool,
+}
+
+impl<Block: BlockT, R: Read> SnapshotReader<Block, R> {
+	/// Read the header of a snapshot.
+	pub fn new(input: R) -> Result<Self> {
+		let mut input = IoReader(input);
+		let version = u32::decode(&mut input).map_err(decode_err)?;
+		if version != SNAPSHOT_VERSION {
+			return Err(Error::InvalidSnapshot(format!("unsupported version {}", version)));
+		}
+		let genesis_hash = Decode::decode(&mut input).map_err(decode_err)?;
+		let header = Decode::decode(&mut input).map_err(decode_err)?;
+
+		Ok(SnapshotReader {
+			input,
+			header: SnapshotHeader { version, genesis_hash, header },
+			chunks: 0,
+			done: false,
+		})
+	}
+
+	/// Header of the snapshot.
+	pub fn header(&self) -> &SnapshotHeader<Block::Hash, Block::Header> {
+		&self.header
+	}
+
+	fn next_chunk(&mut self) -> Result<Option<SnapshotChunk>> {
+		match Item::decode(&mut self.input).map_err(decode_err)? {
+			Item::Chunk { data, checksum } => {
+				if blake2_256(&data) != checksum {
+					return Err(Error::InvalidSnapshot(
+						format!("checksum mismatch in chunk {}", self.chunks)
+					));
+				}
+				self.chunks += 1;
+				SnapshotChunk::decode(&mut &data[..]).map(Some).map_err(decode_err)
+			},
+			Item::End { chunks } => {
+				if chunks != self.chunks {
+					return Err(Error::InvalidSnapshot(
+						format!("expected {} chunks, found {}", chunks, self.chunks)
+					));
+				}
+				Ok(None)
+			},
+		}
+	}
+}
+
+impl<Block: BlockT, R: Read> Iterator for SnapshotReader<Block, R> {
+	type Item = Result<SnapshotChunk>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		let chunk = self.next_chunk().transpose();
+		self.done = match chunk {
+			Some(Ok(_)) => false,
+			_ => true,
+		};
+		chunk
+	}
+}
+
+/// Initialize an empty database with the state of a snapshot of the chain with the given genesis
+/// hash.
+///
+/// The snapshot is streamed into the backend, see
+/// [`BlockImportOperation::import_state`](crate::backend::BlockImportOperation::import_state).
+/// The state root is checked against the header of the snapshot, and the snapshot block becomes
+/// the best and finalized block of the database. Returns the header of that block.
+pub fn import_snapshot<Block, B, R>(
+	backend: &B,
+	genesis_hash: Block::Hash,
+	input: R,
+) -> Result<Block::Header> where
+	Block: BlockT<Hash=H256>,
+	B: backend::Backend<Block, Blake2Hasher>,
+	R: Read,
+{
+	// nodes may be written before the operation is committed, refuse early.
+	if backend.blockchain().info().best_hash != Default::default() {
+		return Err(Error::Backend("Snapshots can only be imported into an empty database".into()));
+	}
+
+	let mut reader = SnapshotReader::<Block, _>::new(input)?;
+	if reader.header().genesis_hash != genesis_hash {
+		return Err(Error::InvalidSnapshot(format!(
+			"snapshot of the chain with genesis {}, expected {}",
+			reader.header().genesis_hash,
+			genesis_hash,
+		)));
+	}
+	let header = reader.header().header.clone();
+
+	let mut op = backend.begin_operation()?;
+	backend.begin_state_operation(&mut op, BlockId::Hash(Default::default()))?;
+	let state_root = op.import_state(&mut reader)?;
+	if state_root != *header.state_root() {
+		return Err(Error::InvalidSnapshot(format!(
+			"state root {} doesn't match the one of block {}",
+			state_root,
+			header.hash(),
+		)));
+	}
+	op.set_block_data(header.clone(), None, None, NewBlockState::Final)?;
+	op.set_base_block(genesis_hash)?;
+	backend.commit_operation(op)?;
+
+	Ok(header)
+}
+
+/// Groups storage entries in chunks and writes them, keeping the first error met.
+struct ChunkWriter<'a, W> {
+	output: &'a mut W,
+	chunk_size: usize,
+	chunks: u32,
+	current: SnapshotChunk,
+	error: Option<Error>,
+}
+
+impl<'a, W: Write> ChunkWriter<'a, W> {
+	fn new(output: &'a mut W, chunk_size: usize) -> Self {
+		ChunkWriter {
+			output,
+			chunk_size: chunk_size.max(1),
+			chunks: 0,
+			current: SnapshotChunk { child_storage_key: None, entries: Vec::new() },
+			error: None,
+		}
+	}
+
+	fn start_trie(&mut self, child_storage_key: Option<Vec<u8>>) {
+		self.flush();
+		self.current.child_storage_key = child_storage_key;
+	}
+
+	fn push(&mut self, key: &[u8], value: Result<Option<Vec<u8>>>) {
+		if self.error.is_some() {
+			return;
+		}
+		match value {
+			Ok(Some(value)) => self.current.entries.push((key.to_vec(), value)),
+			Ok(None) => {},
+			Err(err) => self.error = Some(err),
+		}
+		if self.current.entries.len() >= self.chunk_size {
+			self.flush();
+		}
+	}
+
+	fn flush(&mut self) {
+		if self.error.is_some() || self.current.entries.is_empty() {
+			return;
+		}
+		let data = self.current.encode();
+		self.current.entries.clear();
+		let checksum = blake2_256(&data);
+		match self.output.write_all(&Item::Chunk { data, checksum }.encode()) {
+			Ok(()) => self.chunks += 1,
+			Err(err) => self.error = Some(io_err(err)),
+		}
+	}
+
+	fn finish(mut self) -> Result<u32> {
+		self.flush();
+		if let Some(err) = self.error.take() {
+			return Err(err);
+		}
+		self.output.write_all(&Item::End { chunks: self.chunks }.encode()).map_err(io_err)?;
+		Ok(self.chunks)
+	}
+}
+
+fn state_err<E: state_machine::Error>(err: E) -> Error {
+	Error::from_state(Box::new(err))
+}
+
+fn decode_err(err: codec::Error) -> Error {
+	Error::InvalidSnapshot(err.what().into())
+}
+
+fn io_err(err: std::io::Error) -> Error {
+	Error::Backend(format!("Snapshot I/O error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::storage::StorageKey;
+	use test_client::{self, runtime::Block};
+
+	fn genesis_snapshot(chunk_size: usize) -> Vec<u8> {
+		let client = test_client::new();
+		let mut output = Vec::new();
+		client.export_state(&BlockId::Number(0), chunk_size, &mut output).unwrap();
+		output
+	}
+
+	fn read_all(snapshot: &[u8]) -> Result<Vec<SnapshotChunk>> {
+		SnapshotReader::<Block, _>::new(snapshot)?.collect()
+	}
+
+	#[test]
+	fn snapshot_roundtrip() {
+		let client = test_client::new();
+		let genesis_hash = client.info().chain.genesis_hash;
+		let snapshot = genesis_snapshot(2);
+		let reader = SnapshotReader::<Block, _>::new(&snapshot[..]).unwrap();
+
+		assert_eq!(reader.header().version, SNAPSHOT_VERSION);
+		assert_eq!(reader.header().genesis_hash, genesis_hash);
+		assert_eq!(reader.header().header.hash(), genesis_hash);
+
+		let chunks = reader.collect::<Result<Vec<_>>>().unwrap();
+		assert!(chunks.len() > 1);
+		assert!(chunks.iter().all(|chunk| chunk.entries.len() <= 2));
+		let top = chunks.into_iter()
+			.filter(|chunk| chunk.child_storage_key.is_none())
+			.flat_map(|chunk| chunk.entries)
+			.collect::<Vec<_>>();
+		let code = client.storage(&BlockId::Number(0), &StorageKey(well_known_keys::CODE.to_vec()))
+			.unwrap()
+			.map(|data| data.0);
+		assert!(code.is_some());
+		let exported_code = top.iter()
+			.find(|(key, _)| key == well_known_keys::CODE)
+			.map(|(_, value)| value);
+		assert_eq!(exported_code, code.as_ref());
+		// entries are streamed in key order.
+		assert!(top.windows(2).all(|pair| pair[0].0 < pair[1].0));
+	}
+
+	#[test]
+	fn corrupted_snapshot_is_rejected() {
+		let mut snapshot = genesis_snapshot(DEFAULT_CHUNK_SIZE);
+		let last = snapshot.len() - 10;
+		snapshot[last] ^= 0xff;
+		assert!(read_all(&snapshot[..]).is_err());
+
+		let snapshot = genesis_snapshot(DEFAULT_CHUNK_SIZE);
+		assert!(read_all(&snapshot[..]).is_ok());
+		assert!(read_all(&snapshot[..snapshot.len() - 1]).is_err());
+	}
+}
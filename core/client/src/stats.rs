@@ -0,0 +1,204 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Aggregate statistics of imported blocks.
+//!
+//! The [`StatsCollector`] is fed with every imported block and keeps a summary of the most recent
+//! ones, so that statistics over a range of the canonical chain can be computed without reading
+//! block bodies from the database.
+
+use std::collections::BTreeMap;
+use parking_lot::RwLock;
+use sr_primitives::traits::{Block as BlockT, NumberFor, Saturating};
+
+/// Configuration of the statistics collector.
+#[derive(Debug, Clone)]
+pub struct StatsConfig {
+	/// Number of most recent blocks to keep statistics for.
+	pub window: u32,
+	/// Maximal size of the extrinsics of a block, used to compute the fullness of blocks.
+	///
+	/// This is the maximal block length of the runtime, which the collector can't read by itself.
+	pub max_block_size: u32,
+}
+
+impl Default for StatsConfig {
+	fn default() -> Self {
+		StatsConfig {
+			window: 4096,
+			max_block_size: 5 * 1024 * 1024,
+		}
+	}
+}
+
+/// Statistics over a range of blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeStats {
+	/// Number of blocks in the range for which statistics were collected.
+	pub block_count: u32,
+	/// Total number of extrinsics in these blocks.
+	pub total_extrinsics: u64,
+	/// Average size of the extrinsics of these blocks, relative to the maximal block size.
+	pub average_fullness: f64,
+	/// Number of reorgs observed at blocks of the range.
+	pub reorgs: u32,
+}
+
+#[derive(Debug, Clone)]
+struct BlockStats<H> {
+	hash: H,
+	extrinsics: u32,
+	size: u32,
+}
+
+struct Inner<Block: BlockT> {
+	/// Statistics of the imported blocks, including non-canonical ones, by block number.
+	blocks: BTreeMap<NumberFor<Block>, Vec<BlockStats<Block::Hash>>>,
+	/// Number of reorgs, by number of the new best block.
+	reorgs: BTreeMap<NumberFor<Block>, u32>,
+}
+
+/// Collects statistics of imported blocks.
+pub struct StatsCollector<Block: BlockT> {
+	config: StatsConfig,
+	inner: RwLock<Inner<Block>>,
+}
+
+impl<Block: BlockT> StatsCollector<Block> {
+	/// Create a new collector.
+	pub fn new(config: StatsConfig) -> Self {
+		StatsCollector {
+			config,
+			inner: RwLock::new(Inner {
+				blocks: BTreeMap::new(),
+				reorgs: BTreeMap::new(),
+			}),
+		}
+	}
+
+	/// Note an imported block.
+	///
+	/// `size` is the encoded size of its extrinsics, and `reorg` tells whether the import of the
+	/// block retracted some previous best blocks.
+	pub fn note_imported(
+		&self,
+		number: NumberFor<Block>,
+		hash: Block::Hash,
+		extrinsics: usize,
+		size: usize,
+		reorg: bool,
+	) {
+		let mut inner = self.inner.write();
+		inner.blocks.entry(number).or_default().push(BlockStats {
+			hash,
+			extrinsics: extrinsics as u32,
+			size: size as u32,
+		});
+		if reorg {
+			*inner.reorgs.entry(number).or_default() += 1;
+		}
+
+		// Only keep the most recent blocks.
+		let newest = *inner.blocks.keys().next_back().expect("a block was inserted above; qed");
+		let oldest = newest.saturating_sub(self.config.window.into());
+		inner.blocks = inner.blocks.split_off(&oldest);
+		inner.reorgs = inner.reorgs.split_off(&oldest);
+	}
+
+	/// Compute statistics over the blocks numbered `from` to `to`, inclusive.
+	///
+	/// `canonical_hash` returns the hash of the canonical block with the given number. Blocks that
+	/// were imported before the collector was started, or that are older than the configured
+	/// window, are not accounted for.
+	pub fn stats(
+		&self,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		canonical_hash: impl Fn(NumberFor<Block>) -> Option<Block::Hash>,
+	) -> RangeStats {
+		let mut stats = RangeStats {
+			block_count: 0,
+			total_extrinsics: 0,
+			average_fullness: 0.0,
+			reorgs: 0,
+		};
+		if from > to {
+			return stats;
+		}
+
+		let inner = self.inner.read();
+		let mut total_size = 0u64;
+		for (number, blocks) in inner.blocks.range(from..=to) {
+			let canonical = match canonical_hash(*number) {
+				Some(hash) => blocks.iter().find(|block| block.hash == hash),
+				None => None,
+			};
+			if let Some(block) = canonical {
+				stats.block_count += 1;
+				stats.total_extrinsics += u64::from(block.extrinsics);
+				total_size += u64::from(block.size);
+			}
+		}
+		stats.reorgs = inner.reorgs.range(from..=to).map(|(_, count)| count).sum();
+		if stats.block_count > 0 && self.config.max_block_size > 0 {
+			stats.average_fullness = total_size as f64
+				/ (u64::from(stats.block_count) * u64::from(self.config.max_block_size)) as f64;
+		}
+		stats
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::H256;
+	use test_client::runtime::Block;
+
+	#[test]
+	fn stats_only_account_for_canonical_blocks() {
+		let collector = StatsCollector::<Block>::new(StatsConfig { window: 10, max_block_size: 100 });
+		collector.note_imported(1, H256::from_low_u64_be(1), 2, 50, false);
+		collector.note_imported(2, H256::from_low_u64_be(2), 1, 10, false);
+		// fork at #2, which becomes the best block.
+		collector.note_imported(2, H256::from_low_u64_be(22), 3, 30, true);
+
+		let canonical = |n: u64| match n {
+			1 => Some(H256::from_low_u64_be(1)),
+			2 => Some(H256::from_low_u64_be(22)),
+			_ => None,
+		};
+		assert_eq!(collector.stats(1, 5, canonical), RangeStats {
+			block_count: 2,
+			total_extrinsics: 5,
+			average_fullness: 0.4,
+			reorgs: 1,
+		});
+		assert_eq!(collector.stats(1, 1, canonical).reorgs, 0);
+		assert_eq!(collector.stats(3, 1, canonical).block_count, 0);
+	}
+
+	#[test]
+	fn old_blocks_are_pruned() {
+		let collector = StatsCollector::<Block>::new(StatsConfig { window: 2, max_block_size: 100 });
+		for n in 1..=5 {
+			collector.note_imported(n, H256::from_low_u64_be(n), 1, 0, n == 1);
+		}
+
+		let stats = collector.stats(1, 5, |n| Some(H256::from_low_u64_be(n)));
+		assert_eq!(stats.block_count, 3);
+		assert_eq!(stats.reorgs, 0);
+	}
+}
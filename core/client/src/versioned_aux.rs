@@ -0,0 +1,161 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Versioned encoding of the values kept in the auxiliary storage.
+//!
+//! The values written to the auxiliary storage by consensus engines outlive the version of the
+//! node that wrote them. Each value is wrapped in an envelope recording the version of its
+//! encoding, so that a newer node can decode values written with an older encoding through a
+//! migration hook, and re-encode them with the current encoding the next time they are written.
+//!
+//! Values written before the envelope was introduced have no envelope, and are passed to the
+//! migration hook as version 0.
+
+use codec::{Decode, Encode};
+use primitives::hexdisplay::HexDisplay;
+use crate::backend::AuxStore;
+use crate::error;
+
+/// Prefix of the enveloped values.
+///
+/// The first byte is an invalid prefix for the SCALE encoding of any collection length that fits
+/// in memory, so that values without envelope are unlikely to start with the magic.
+const MAGIC: [u8; 4] = [0xff, b'v', b'a', b'x'];
+
+/// A value decoded from an envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded<T> {
+	/// The value, migrated to the current encoding.
+	pub value: T,
+	/// Version of the encoding the value was written with.
+	pub version: u32,
+}
+
+/// Encodes a value with the given version of its encoding.
+pub fn encode<T: Encode>(version: u32, value: &T) -> Vec<u8> {
+	let mut encoded = MAGIC.to_vec();
+	version.encode_to(&mut encoded);
+	value.encode_to(&mut encoded);
+	encoded
+}
+
+/// Decodes a value whose current encoding is at the given version.
+///
+/// Values written with an older version of the encoding are decoded by `decode_old`, which is
+/// given the version they were written with. Values written with a newer version are refused.
+pub fn decode<T, F>(data: &[u8], version: u32, decode_old: F) -> Result<Decoded<T>, codec::Error> where
+	T: Decode,
+	F: FnOnce(u32, &mut &[u8]) -> Result<T, codec::Error>,
+{
+	let mut input = data;
+	let written_version = if input.starts_with(&MAGIC) {
+		input = &input[MAGIC.len()..];
+		u32::decode(&mut input)?
+	} else {
+		0
+	};
+
+	let value = if written_version == version {
+		T::decode(&mut input)?
+	} else if written_version < version {
+		decode_old(written_version, &mut input)?
+	} else {
+		return Err("Value written by a newer version of the node".into())
+	};
+	Ok(Decoded { value, version: written_version })
+}
+
+/// Migration hook of the values whose encoding hasn't changed since the given version.
+///
+/// Decodes the values written with the version `since` and later with the current encoding.
+pub fn decode_unchanged_since<T: Decode>(
+	since: u32,
+) -> impl FnOnce(u32, &mut &[u8]) -> Result<T, codec::Error> {
+	move |version, input| if version >= since {
+		T::decode(input)
+	} else {
+		Err("No migration from this version of the encoding".into())
+	}
+}
+
+/// Loads and decodes a value from the auxiliary storage.
+///
+/// See [`decode`] for the meaning of `version` and `decode_old`.
+pub fn load<B, T, F>(backend: &B, key: &[u8], version: u32, decode_old: F) -> error::Result<Option<T>> where
+	B: AuxStore,
+	T: Decode,
+	F: FnOnce(u32, &mut &[u8]) -> Result<T, codec::Error>,
+{
+	match backend.get_aux(key)? {
+		None => Ok(None),
+		Some(data) => decode(&data, version, decode_old)
+			.map(|decoded| Some(decoded.value))
+			.map_err(|e| error::Error::Backend(format!(
+				"Auxiliary storage value {} is corrupted: {}",
+				HexDisplay::from(&key),
+				e.what(),
+			))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, PartialEq, Encode, Decode)]
+	struct V1 {
+		a: u32,
+	}
+
+	#[derive(Debug, PartialEq, Encode, Decode)]
+	struct V2 {
+		a: u32,
+		b: u64,
+	}
+
+	fn from_v1(version: u32, input: &mut &[u8]) -> Result<V2, codec::Error> {
+		match version {
+			0 | 1 => V1::decode(input).map(|v1| V2 { a: v1.a, b: 0 }),
+			_ => Err("Unknown version".into()),
+		}
+	}
+
+	#[test]
+	fn current_version_round_trips() {
+		let value = V2 { a: 1, b: 2 };
+		let decoded = decode(&encode(2, &value), 2, from_v1).unwrap();
+		assert_eq!(decoded, Decoded { value, version: 2 });
+	}
+
+	#[test]
+	fn old_versions_are_migrated() {
+		let decoded = decode(&encode(1, &V1 { a: 1 }), 2, from_v1).unwrap();
+		assert_eq!(decoded, Decoded { value: V2 { a: 1, b: 0 }, version: 1 });
+
+		// written before the envelope.
+		let decoded = decode(&V1 { a: 3 }.encode(), 2, from_v1).unwrap();
+		assert_eq!(decoded, Decoded { value: V2 { a: 3, b: 0 }, version: 0 });
+
+		let decoded = decode(&V1 { a: 4 }.encode(), 1, decode_unchanged_since(0)).unwrap();
+		assert_eq!(decoded, Decoded { value: V1 { a: 4 }, version: 0 });
+		assert!(decode::<V2, _>(&V1 { a: 4 }.encode(), 2, decode_unchanged_since(1)).is_err());
+	}
+
+	#[test]
+	fn newer_versions_are_refused() {
+		assert!(decode::<V1, _>(&encode(2, &V2 { a: 1, b: 2 }), 1, decode_unchanged_since(0)).is_err());
+	}
+}
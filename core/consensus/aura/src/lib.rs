@@ -39,13 +39,13 @@ use consensus_common::import_queue::{
 	Verifier, BasicQueue, BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport,
 };
 use client::{
-	block_builder::api::BlockBuilder as BlockBuilderApi, blockchain::ProvideCache,
+	block_builder::api::BlockBuilder as BlockBuilderApi, blockchain::{HeaderBackend, ProvideCache},
 	runtime_api::ApiExt, error::Result as CResult, backend::AuxStore, BlockOf,
 	well_known_cache_keys::{self, Id as CacheKeyId},
 };
 
 use sr_primitives::{generic::{BlockId, OpaqueDigestItemId}, Justification};
-use sr_primitives::traits::{Block as BlockT, Header, DigestItemFor, ProvideRuntimeApi, Zero, Member};
+use sr_primitives::traits::{Block as BlockT, Header, DigestItemFor, NumberFor, ProvideRuntimeApi, Zero, Member};
 
 use primitives::crypto::Pair;
 use inherents::{InherentDataProviders, InherentData, RuntimeString};
@@ -68,6 +68,7 @@ use keystore::KeyStorePtr;
 pub use aura_primitives::*;
 pub use consensus_common::SyncOracle;
 pub use digest::CompatibleDigestItem;
+pub use slots::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
 
 mod digest;
 
@@ -130,7 +131,10 @@ impl SlotCompatible for AuraSlotCompatible {
 }
 
 /// Start the aura worker. The returned future should be run in a futures executor.
-pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
+///
+/// If `last_block` is given, no block is authored on top of it, e.g. because the chain
+/// switches to another consensus engine afterwards.
+pub fn start_aura<B, C, SC, E, I, P, SO, Error, H, BS>(
 	slot_duration: SlotDuration,
 	client: Arc<C>,
 	select_chain: SC,
@@ -140,9 +144,11 @@ pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
 	inherent_data_providers: InherentDataProviders,
 	force_authoring: bool,
 	keystore: KeyStorePtr,
+	last_block: Option<NumberFor<B>>,
+	backoff_authoring_blocks: BS,
 ) -> Result<impl futures01::Future<Item = (), Error = ()>, consensus_common::Error> where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + AuxStore + Send + Sync,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + HeaderBackend<B> + AuxStore + Send + Sync,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	SC: SelectChain<B>,
 	E: Environment<B, Error=Error> + Send + Sync + 'static,
@@ -155,6 +161,7 @@ pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
 	I: BlockImport<B> + Send + Sync + 'static,
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
 {
 	let worker = AuraWorker {
 		client: client.clone(),
@@ -163,6 +170,8 @@ pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
 		keystore,
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
+		last_block,
+		backoff_authoring_blocks,
 		_key_type: PhantomData::<P>,
 	};
 	register_aura_inherent_data_provider(
@@ -179,19 +188,22 @@ pub fn start_aura<B, C, SC, E, I, P, SO, Error, H>(
 	).map(|()| Ok::<(), ()>(())).compat())
 }
 
-struct AuraWorker<C, E, I, P, SO> {
+struct AuraWorker<C, E, I, P, SO, N, BS> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: E,
 	keystore: KeyStorePtr,
 	sync_oracle: SO,
 	force_authoring: bool,
+	last_block: Option<N>,
+	backoff_authoring_blocks: BS,
 	_key_type: PhantomData<P>,
 }
 
-impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E, I, P, SO> where
+impl<H, B, C, E, I, P, Error, SO, BS> slots::SimpleSlotWorker<B>
+	for AuraWorker<C, E, I, P, SO, NumberFor<B>, BS> where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + Sync,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error=Error>,
 	E::Proposer: Proposer<B, Error=Error>,
@@ -203,6 +215,7 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 	P::Signature: Member + Encode + Decode + Hash + Debug,
 	SO: SyncOracle + Send + Clone,
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>>,
 {
 	type EpochData = Vec<AuthorityId<P>>;
 	type Claim = P;
@@ -228,10 +241,14 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 
 	fn claim_slot(
 		&self,
-		_header: &B::Header,
+		header: &B::Header,
 		slot_number: u64,
 		epoch_data: &Self::EpochData,
 	) -> Option<Self::Claim> {
+		if self.last_block.map_or(false, |last_block| *header.number() >= last_block) {
+			return None;
+		}
+
 		let expected_author = slot_author::<P>(slot_number, epoch_data);
 
 		expected_author.and_then(|p| {
@@ -276,6 +293,14 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 		self.force_authoring
 	}
 
+	fn should_backoff(&self, chain_head: &B::Header) -> bool {
+		self.backoff_authoring_blocks.should_backoff(
+			*chain_head.number(),
+			self.client.info().finalized_number,
+			self.logging_target(),
+		)
+	}
+
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
 		&mut self.sync_oracle
 	}
@@ -287,9 +312,9 @@ impl<H, B, C, E, I, P, Error, SO> slots::SimpleSlotWorker<B> for AuraWorker<C, E
 	}
 }
 
-impl<H, B: BlockT, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> where
+impl<H, B: BlockT, C, E, I, P, Error, SO, BS> SlotWorker<B> for AuraWorker<C, E, I, P, SO, NumberFor<B>, BS> where
 	B: BlockT<Header=H>,
-	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + Sync + Send,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync + Send,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error=Error> + Send + Sync,
 	E::Proposer: Proposer<B, Error=Error>,
@@ -301,6 +326,7 @@ impl<H, B: BlockT, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I,
 	P::Signature: Member + Encode + Decode + Hash + Debug,
 	SO: SyncOracle + Send + Sync + Clone,
 	Error: ::std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync,
 {
 	type OnSlot = Pin<Box<dyn Future<Output = Result<(), consensus_common::Error>> + Send>>;
 
@@ -655,6 +681,31 @@ fn register_aura_inherent_data_provider(
 	}
 }
 
+/// Create a verifier for Aura blocks, e.g. to be used along with the verifier of another
+/// consensus engine by a chain switching engines.
+pub fn verifier<B, C, P, T>(
+	slot_duration: SlotDuration,
+	client: Arc<C>,
+	inherent_data_providers: InherentDataProviders,
+	transaction_pool: Option<Arc<T>>,
+) -> Result<AuraVerifier<C, P, T>, consensus_common::Error> where
+	B: BlockT,
+	C: ProvideRuntimeApi + BlockOf + ProvideCache<B> + AuxStore,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+	P: Pair,
+	P::Public: Encode + Decode,
+{
+	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
+	initialize_authorities_cache(&*client)?;
+
+	Ok(AuraVerifier {
+		client,
+		inherent_data_providers,
+		phantom: PhantomData,
+		transaction_pool,
+	})
+}
+
 /// Start an import queue for the Aura consensus algorithm.
 pub fn import_queue<B, C, P, T>(
 	slot_duration: SlotDuration,
@@ -674,15 +725,12 @@ pub fn import_queue<B, C, P, T>(
 	P::Signature: Encode + Decode,
 	T: Send + Sync + 'static,
 {
-	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
-	initialize_authorities_cache(&*client)?;
-
-	let verifier = AuraVerifier {
-		client: client.clone(),
+	let verifier = verifier::<B, C, P, T>(
+		slot_duration,
+		client,
 		inherent_data_providers,
-		phantom: PhantomData,
 		transaction_pool,
-	};
+	)?;
 	Ok(BasicQueue::new(
 		verifier,
 		block_import,
@@ -844,7 +892,7 @@ mod tests {
 				&inherent_data_providers, slot_duration.get()
 			).expect("Registers aura inherent data provider");
 
-			let aura = start_aura::<_, _, _, _, _, AuthorityPair, _, _, _>(
+			let aura = start_aura::<_, _, _, _, _, AuthorityPair, _, _, _, _>(
 				slot_duration,
 				client.clone(),
 				select_chain,
@@ -854,6 +902,8 @@ mod tests {
 				inherent_data_providers,
 				false,
 				keystore,
+				None,
+				(),
 			).expect("Starts aura");
 
 			runtime.spawn(aura);
@@ -21,9 +21,10 @@
 
 mod digest;
 
+use app_crypto::RuntimeAppPublic;
 use codec::{Encode, Decode};
 use rstd::vec::Vec;
-use sr_primitives::{ConsensusEngineId, RuntimeDebug};
+use sr_primitives::{ConsensusEngineId, RuntimeDebug, traits::{Block as BlockT, Header as HeaderT}};
 use substrate_client::decl_runtime_apis;
 
 #[cfg(feature = "std")]
@@ -165,13 +166,99 @@ impl slots::SlotData for BabeConfiguration {
 	const SLOT_KEY: &'static [u8] = b"babe_configuration";
 }
 
+/// Proof of an equivocation of a BABE authority, i.e. of two different headers
+/// it sealed for the same slot.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct EquivocationProof<Header> {
+	/// The authority which equivocated.
+	pub offender: AuthorityId,
+	/// The slot at which the equivocation happened.
+	pub slot_number: SlotNumber,
+	/// The first header involved in the equivocation.
+	pub first_header: Header,
+	/// The second header involved in the equivocation.
+	pub second_header: Header,
+}
+
+/// A proof that a key was owned by a validator, encoded by the runtime.
+///
+/// Its actual type depends on the key owner proof system of the runtime.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct OpaqueKeyOwnershipProof(Vec<u8>);
+
+impl OpaqueKeyOwnershipProof {
+	/// Wrap an encoded proof of key ownership.
+	pub fn new(encoded: Vec<u8>) -> Self {
+		OpaqueKeyOwnershipProof(encoded)
+	}
+
+	/// Decode the proof, returning `None` if it isn't of the given type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		T::decode(&mut &self.0[..]).ok()
+	}
+}
+
+/// Check an equivocation proof: both headers must be different, claim the slot
+/// of the proof and be sealed by the offender.
+pub fn check_equivocation_proof<H: HeaderT>(proof: EquivocationProof<H>) -> bool {
+	let EquivocationProof { offender, slot_number, first_header, second_header } = proof;
+
+	first_header.hash() != second_header.hash() &&
+		check_sealed_at(first_header, slot_number, &offender) &&
+		check_sealed_at(second_header, slot_number, &offender)
+}
+
+/// Check that the header claims the given slot and is sealed by the given authority.
+fn check_sealed_at<H: HeaderT>(mut header: H, slot_number: SlotNumber, authority: &AuthorityId) -> bool {
+	let seal = match header.digest_mut().pop() {
+		Some(seal) => seal,
+		None => return false,
+	};
+	let signature = match seal.as_seal() {
+		Some((id, mut signature)) if id == BABE_ENGINE_ID =>
+			match AuthoritySignature::decode(&mut signature) {
+				Ok(signature) => signature,
+				Err(_) => return false,
+			},
+		_ => return false,
+	};
+
+	let claimed_slot = header.digest().logs().iter()
+		.filter_map(|log| log.as_pre_runtime())
+		.find(|(id, _)| *id == BABE_ENGINE_ID)
+		.and_then(|(_, mut pre_digest)| RawBabePreDigest::decode(&mut pre_digest).ok())
+		.map(|pre_digest| pre_digest.slot_number());
+
+	// the pre-hash of the header doesn't include the seal.
+	claimed_slot == Some(slot_number) && authority.verify(&header.hash(), &signature)
+}
+
 decl_runtime_apis! {
 	/// API necessary for block authorship with BABE.
+	#[api_version(2)]
 	pub trait BabeApi {
 		/// Return the configuration for BABE. Currently,
 		/// only the value provided by this type at genesis will be used.
 		///
 		/// Dynamic configuration may be supported in the future.
 		fn configuration() -> BabeConfiguration;
+
+		/// Generate a proof that the given authority is part of the current
+		/// validator set, needed to report its equivocations.
+		///
+		/// Returns `None` if the ownership of the key can't be proved, e.g. if
+		/// the runtime doesn't handle equivocations.
+		fn generate_key_ownership_proof(authority_id: AuthorityId) -> Option<OpaqueKeyOwnershipProof>;
+
+		/// Create the unsigned extrinsic reporting the given equivocation, along
+		/// with the proof of key ownership of the offender returned by
+		/// `generate_key_ownership_proof`.
+		///
+		/// The extrinsic is only meant to be included by the local block authors.
+		/// Returns `None` if the runtime doesn't handle equivocations.
+		fn report_equivocation_unsigned_extrinsic(
+			equivocation_proof: EquivocationProof<<Block as BlockT>::Header>,
+			key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> Option<<Block as BlockT>::Extrinsic>;
 	}
 }
@@ -21,6 +21,7 @@ use codec::{Decode, Encode};
 
 use client::backend::AuxStore;
 use client::error::{Result as ClientResult, Error as ClientError};
+use client::versioned_aux;
 use sr_primitives::traits::Block as BlockT;
 use babe_primitives::BabeBlockWeight;
 
@@ -28,11 +29,16 @@ use super::{epoch_changes::EpochChangesFor, SharedEpochChanges};
 
 const BABE_EPOCH_CHANGES: &[u8] = b"babe_epoch_changes";
 
+/// Version of the encoding of the epoch changes.
+const EPOCH_CHANGES_VERSION: u32 = 1;
+/// Version of the encoding of the block weights.
+const BLOCK_WEIGHT_VERSION: u32 = 1;
+
 fn block_weight_key<H: Encode>(block_hash: H) -> Vec<u8> {
 	(b"block_weight", block_hash).encode()
 }
 
-fn load_decode<B, T>(backend: &B, key: &[u8]) -> ClientResult<Option<T>>
+fn load_decode<B, T>(backend: &B, key: &[u8], version: u32) -> ClientResult<Option<T>>
 	where
 		B: AuxStore,
 		T: Decode,
@@ -42,7 +48,10 @@ fn load_decode<B, T>(backend: &B, key: &[u8]) -> ClientResult<Option<T>>
 	};
 	match backend.get_aux(key)? {
 		None => Ok(None),
-		Some(t) => T::decode(&mut &t[..]).map(Some).map_err(corrupt)
+		// the encoding of the values hasn't changed since they are versioned.
+		Some(t) => versioned_aux::decode(&t, version, versioned_aux::decode_unchanged_since(0))
+			.map(|decoded| Some(decoded.value))
+			.map_err(corrupt)
 	}
 }
 
@@ -50,7 +59,7 @@ fn load_decode<B, T>(backend: &B, key: &[u8]) -> ClientResult<Option<T>>
 pub(crate) fn load_epoch_changes<Block: BlockT, B: AuxStore>(
 	backend: &B,
 ) -> ClientResult<SharedEpochChanges<Block>> {
-	let epoch_changes = load_decode::<_, EpochChangesFor<Block>>(backend, BABE_EPOCH_CHANGES)?
+	let epoch_changes = load_decode::<_, EpochChangesFor<Block>>(backend, BABE_EPOCH_CHANGES, EPOCH_CHANGES_VERSION)?
 		.map(Into::into)
 		.unwrap_or_else(|| {
 			info!(target: "babe",
@@ -69,7 +78,7 @@ pub(crate) fn write_epoch_changes<Block: BlockT, F, R>(
 ) -> R where
 	F: FnOnce(&[(&'static [u8], &[u8])]) -> R,
 {
-	let encoded_epoch_changes = epoch_changes.encode();
+	let encoded_epoch_changes = versioned_aux::encode(EPOCH_CHANGES_VERSION, epoch_changes);
 	write_aux(
 		&[(BABE_EPOCH_CHANGES, encoded_epoch_changes.as_slice())],
 	)
@@ -85,10 +94,9 @@ pub(crate) fn write_block_weight<H: Encode, F, R>(
 {
 
 	let key = block_weight_key(block_hash);
-	block_weight.using_encoded(|s|
-		write_aux(
-			&[(key, s)],
-		)
+	let encoded = versioned_aux::encode(BLOCK_WEIGHT_VERSION, block_weight);
+	write_aux(
+		&[(key, &encoded[..])],
 	)
 }
 
@@ -97,5 +105,5 @@ pub(crate) fn load_block_weight<H: Encode, B: AuxStore>(
 	backend: &B,
 	block_hash: H,
 ) -> ClientResult<Option<BabeBlockWeight>> {
-	load_decode(backend, block_weight_key(block_hash).as_slice())
+	load_decode(backend, block_weight_key(block_hash).as_slice(), BLOCK_WEIGHT_VERSION)
 }
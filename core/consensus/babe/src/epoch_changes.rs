@@ -236,6 +236,30 @@ impl<Hash, Number> EpochChanges<Hash, Number> where
 		make_genesis: G,
 	) -> Result<Option<ViableEpoch>, fork_tree::Error<D::Error>>
 		where G: FnOnce(SlotNumber) -> Epoch
+	{
+		self.epoch_for_child_of_switched(
+			Zero::zero(),
+			descendent_of_builder,
+			parent_hash,
+			parent_number,
+			slot_number,
+			make_genesis,
+		)
+	}
+
+	/// Same as `epoch_for_child_of`, on a chain which only uses BABE after the block
+	/// `switch_block`, e.g. because it was migrated from Aura. The genesis epoch is the
+	/// one of the children of the blocks up to `switch_block`.
+	pub fn epoch_for_child_of_switched<D: IsDescendentOfBuilder<Hash>, G>(
+		&self,
+		switch_block: Number,
+		descendent_of_builder: D,
+		parent_hash: &Hash,
+		parent_number: Number,
+		slot_number: SlotNumber,
+		make_genesis: G,
+	) -> Result<Option<ViableEpoch>, fork_tree::Error<D::Error>>
+		where G: FnOnce(SlotNumber) -> Epoch
 	{
 		// find_node_where will give you the node in the fork-tree which is an ancestor
 		// of the `parent_hash` by default. if the last epoch was signalled at the parent_hash,
@@ -246,7 +270,7 @@ impl<Hash, Number> EpochChanges<Hash, Number> where
 		let is_descendent_of = descendent_of_builder
 			.build_is_descendent_of(Some((fake_head_hash, *parent_hash)));
 
-		if parent_number == Zero::zero() {
+		if parent_number <= switch_block {
 			// need to insert the genesis epoch.
 			let genesis_epoch = make_genesis(slot_number);
 			return Ok(Some(ViableEpoch::Genesis(UnimportedGenesis(genesis_epoch))));
@@ -450,6 +474,47 @@ mod tests {
 		assert_eq!(genesis_epoch_2.as_ref(), &make_genesis(10102));
 	}
 
+	#[test]
+	fn genesis_epoch_starts_after_the_switch_block() {
+		let is_descendent_of = |base: &Hash, _block: &Hash| -> Result<bool, TestError> {
+			Ok(base == b"0")
+		};
+
+		let make_genesis = |slot| Epoch {
+			epoch_index: 0,
+			start_slot: slot,
+			duration: 100,
+			authorities: Vec::new(),
+			randomness: [0; 32],
+		};
+
+		let epoch_changes = EpochChanges::new();
+		let genesis_epoch = epoch_changes.epoch_for_child_of_switched(
+			10,
+			&is_descendent_of,
+			b"A",
+			10,
+			10101,
+			&make_genesis,
+		).unwrap().unwrap();
+
+		match genesis_epoch {
+			ViableEpoch::Genesis(_) => {},
+			_ => panic!("should be unimported genesis"),
+		};
+		assert_eq!(genesis_epoch.as_ref(), &make_genesis(10101));
+
+		// past the switch block, the epoch must be found in the tree.
+		assert!(epoch_changes.epoch_for_child_of_switched(
+			10,
+			&is_descendent_of,
+			b"B",
+			11,
+			10102,
+			&make_genesis,
+		).unwrap().is_none());
+	}
+
 	#[test]
 	fn epoch_changes_between_blocks() {
 		//
@@ -60,15 +60,16 @@
 #![warn(missing_docs)]
 pub use babe_primitives::*;
 pub use consensus_common::SyncOracle;
+pub use slots::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
 use std::{collections::HashMap, sync::Arc, u64, pin::Pin, time::{Instant, Duration}};
 use babe_primitives;
 use consensus_common::ImportResult;
 use consensus_common::import_queue::{
 	BoxJustificationImport, BoxFinalityProofImport,
 };
-use sr_primitives::{generic::{BlockId, OpaqueDigestItemId}, Justification, RuntimeString};
+use sr_primitives::{generic::{BlockId, OpaqueDigestItemId}, ConsensusEngineId, Justification, RuntimeString};
 use sr_primitives::traits::{
-	Block as BlockT, Header, DigestItemFor, ProvideRuntimeApi,
+	Block as BlockT, Header, DigestItemFor, NumberFor, ProvideRuntimeApi,
 	Zero,
 };
 use keystore::KeyStorePtr;
@@ -94,10 +95,10 @@ use client::{
 	block_builder::api::BlockBuilder as BlockBuilderApi,
 	blockchain::{self, HeaderBackend, ProvideCache}, BlockchainEvents, CallExecutor, Client,
 	error::Result as ClientResult, error::Error as ClientError, backend::{AuxStore, Backend},
-	ProvideUncles,
+	runtime_api::ApiExt, ProvideUncles,
 };
 use slots::{CheckedHeader, check_equivocation};
-use futures::prelude::*;
+use futures::{prelude::*, channel::mpsc};
 use log::{warn, debug, info, trace};
 use slots::{SlotWorker, SlotData, SlotInfo, SlotCompatible};
 use epoch_changes::descendent_query;
@@ -108,6 +109,7 @@ mod aux_schema;
 mod verification;
 mod epoch_changes;
 mod authorship;
+pub mod migration;
 #[cfg(test)]
 mod tests;
 pub use babe_primitives::{
@@ -122,6 +124,8 @@ enum Error<B: BlockT> {
 	MultiplePreRuntimeDigests,
 	#[display(fmt = "No BABE pre-runtime digest found")]
 	NoPreRuntimeDigest,
+	#[display(fmt = "No pre-runtime digest of the engine preceding BABE found")]
+	NoPreviousPreRuntimeDigest,
 	#[display(fmt = "Multiple BABE epoch change digests, rejecting!")]
 	MultipleEpochChangeDigests,
 	#[display(fmt = "Could not extract timestamp and slot: {:?}", _0)]
@@ -214,6 +218,15 @@ impl Config {
 		}
 	}
 
+	/// Either fetch the slot duration from disk or compute it from the state at
+	/// the given block, for chains which didn't use BABE from genesis.
+	pub fn get_or_compute_at<B: BlockT, C>(client: &C, at: &BlockId<B>) -> ClientResult<Self> where
+		C: AuxStore + ProvideRuntimeApi, C::Api: BabeApi<B>,
+	{
+		trace!(target: "babe", "Getting slot duration");
+		slots::SlotDuration::get_or_compute_at(client, at, |a, b| a.configuration(b)).map(Self)
+	}
+
 	/// Create the genesis epoch (epoch #0). This is defined to start at the slot of
 	/// the first block, so that has to be provided.
 	pub fn genesis_epoch(&self, slot_number: SlotNumber) -> Epoch {
@@ -236,7 +249,7 @@ impl std::ops::Deref for Config {
 }
 
 /// Parameters for BABE.
-pub struct BabeParams<B: BlockT, C, E, I, SO, SC> {
+pub struct BabeParams<B: BlockT, C, E, I, SO, SC, BS> {
 	/// The keystore that manages the keys of the node.
 	pub keystore: KeyStorePtr,
 
@@ -265,10 +278,14 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC> {
 
 	/// The source of timestamps for relative slots
 	pub babe_link: BabeLink<B>,
+
+	/// Strategy deciding when to back off from authoring blocks, e.g. because
+	/// finality is lagging.
+	pub backoff_authoring_blocks: BS,
 }
 
 /// Start the babe worker. The returned future should be run in a tokio runtime.
-pub fn start_babe<B, C, SC, E, I, SO, Error>(BabeParams {
+pub fn start_babe<B, C, SC, E, I, SO, Error, BS>(BabeParams {
 	keystore,
 	client,
 	select_chain,
@@ -278,7 +295,8 @@ pub fn start_babe<B, C, SC, E, I, SO, Error>(BabeParams {
 	inherent_data_providers,
 	force_authoring,
 	babe_link,
-}: BabeParams<B, C, E, I, SO, SC>) -> Result<
+	backoff_authoring_blocks,
+}: BabeParams<B, C, E, I, SO, SC, BS>) -> Result<
 	impl futures01::Future<Item=(), Error=()>,
 	consensus_common::Error,
 > where
@@ -293,6 +311,7 @@ pub fn start_babe<B, C, SC, E, I, SO, Error>(BabeParams {
 	I: BlockImport<B,Error=ConsensusError> + Send + Sync + 'static,
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
 {
 	let config = babe_link.config;
 	let worker = BabeWorker {
@@ -304,6 +323,9 @@ pub fn start_babe<B, C, SC, E, I, SO, Error>(BabeParams {
 		keystore,
 		epoch_changes: babe_link.epoch_changes.clone(),
 		config: config.clone(),
+		switch_block: babe_link.switch_block,
+		previous_engine: babe_link.previous_engine,
+		backoff_authoring_blocks,
 	};
 
 	register_babe_inherent_data_provider(&inherent_data_providers, config.slot_duration())?;
@@ -326,7 +348,7 @@ pub fn start_babe<B, C, SC, E, I, SO, Error>(BabeParams {
 	Ok(slot_worker.map(|_| Ok::<(), ()>(())).compat())
 }
 
-struct BabeWorker<B: BlockT, C, E, I, SO> {
+struct BabeWorker<B: BlockT, C, E, I, SO, BS> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: E,
@@ -335,9 +357,12 @@ struct BabeWorker<B: BlockT, C, E, I, SO> {
 	keystore: KeyStorePtr,
 	epoch_changes: SharedEpochChanges<B>,
 	config: Config,
+	switch_block: NumberFor<B>,
+	previous_engine: Option<ConsensusEngineId>,
+	backoff_authoring_blocks: BS,
 }
 
-impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I, SO> where
+impl<B, C, E, I, Error, SO, BS> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I, SO, BS> where
 	B: BlockT<Hash=H256>,
 	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + HeaderMetadata<B, Error=ClientError>,
 	C::Api: BabeApi<B>,
@@ -347,6 +372,7 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 	I: BlockImport<B> + Send + Sync + 'static,
 	SO: SyncOracle + Send + Clone,
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>>,
 {
 	type EpochData = Epoch;
 	type Claim = (BabePreDigest, AuthorityPair);
@@ -363,7 +389,8 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 	}
 
 	fn epoch_data(&self, parent: &B::Header, slot_number: u64) -> Result<Self::EpochData, consensus_common::Error> {
-		self.epoch_changes.lock().epoch_for_child_of(
+		self.epoch_changes.lock().epoch_for_child_of_switched(
+			self.switch_block,
 			descendent_query(&*self.client),
 			&parent.hash(),
 			parent.number().clone(),
@@ -381,10 +408,23 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 
 	fn claim_slot(
 		&self,
-		_parent_header: &B::Header,
+		parent_header: &B::Header,
 		slot_number: SlotNumber,
 		epoch_data: &Epoch,
 	) -> Option<Self::Claim> {
+		// the blocks up to the switch block are authored by the previous consensus engine.
+		if *parent_header.number() < self.switch_block {
+			return None;
+		}
+
+		// the first BABE block must come after the last slot of the previous engine.
+		if *parent_header.number() == self.switch_block {
+			match find_slot_number::<B>(parent_header, self.switch_block, self.previous_engine) {
+				Ok(parent_slot) if slot_number > parent_slot => {},
+				_ => return None,
+			}
+		}
+
 		debug!(target: "babe", "Attempting to claim slot {}", slot_number);
 		let s = authorship::claim_slot(
 			slot_number,
@@ -439,6 +479,14 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 		self.force_authoring
 	}
 
+	fn should_backoff(&self, chain_head: &B::Header) -> bool {
+		self.backoff_authoring_blocks.should_backoff(
+			*chain_head.number(),
+			self.client.info().finalized_number,
+			self.logging_target(),
+		)
+	}
+
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
 		&mut self.sync_oracle
 	}
@@ -450,7 +498,7 @@ impl<B, C, E, I, Error, SO> slots::SimpleSlotWorker<B> for BabeWorker<B, C, E, I
 	}
 }
 
-impl<B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<B, C, E, I, SO> where
+impl<B, C, E, I, Error, SO, BS> SlotWorker<B> for BabeWorker<B, C, E, I, SO, BS> where
 	B: BlockT<Hash=H256>,
 	C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + HeaderMetadata<B, Error=ClientError> + Send + Sync,
 	C::Api: BabeApi<B>,
@@ -460,6 +508,7 @@ impl<B, C, E, I, Error, SO> SlotWorker<B> for BabeWorker<B, C, E, I, SO> where
 	I: BlockImport<B> + Send + Sync + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
 	Error: std::error::Error + Send + From<::consensus_common::Error> + From<I::Error> + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync,
 {
 	type OnSlot = Pin<Box<dyn Future<Output = Result<(), consensus_common::Error>> + Send>>;
 
@@ -493,6 +542,24 @@ fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<BabePreDigest, Error
 	pre_digest.ok_or_else(|| babe_err(Error::NoPreRuntimeDigest))
 }
 
+/// Extract the slot number of the given header. The blocks up to `switch_block` were
+/// authored by the engine preceding BABE, whose pre-runtime digest holds their slot.
+fn find_slot_number<B: BlockT>(
+	header: &B::Header,
+	switch_block: NumberFor<B>,
+	previous_engine: Option<ConsensusEngineId>,
+) -> Result<SlotNumber, Error<B>> {
+	if header.number().is_zero() || *header.number() > switch_block {
+		return find_pre_digest::<B>(header).map(|d| d.slot_number());
+	}
+
+	previous_engine
+		.and_then(|engine| header.digest().logs().iter().find_map(|log|
+			log.try_to::<SlotNumber>(OpaqueDigestItemId::PreRuntime(&engine))
+		))
+		.ok_or_else(|| babe_err(Error::NoPreviousPreRuntimeDigest))
+}
+
 /// Extract the BABE epoch change digest from the given header, if it exists.
 fn find_next_epoch_digest<B: BlockT>(header: &B::Header)
 	-> Result<Option<NextEpochDescriptor>, Error<B>>
@@ -536,6 +603,8 @@ pub struct BabeLink<Block: BlockT> {
 	time_source: TimeSource,
 	epoch_changes: SharedEpochChanges<Block>,
 	config: Config,
+	switch_block: NumberFor<Block>,
+	previous_engine: Option<ConsensusEngineId>,
 }
 /// A verifier for Babe blocks.
 pub struct BabeVerifier<B, E, Block: BlockT, RA, PRA> {
@@ -545,6 +614,8 @@ pub struct BabeVerifier<B, E, Block: BlockT, RA, PRA> {
 	config: Config,
 	epoch_changes: SharedEpochChanges<Block>,
 	time_source: TimeSource,
+	equivocation_reports: Option<mpsc::UnboundedSender<Block::Extrinsic>>,
+	switch_block: NumberFor<Block>,
 }
 
 impl<B, E, Block: BlockT, RA, PRA> BabeVerifier<B, E, Block, RA, PRA> {
@@ -572,6 +643,61 @@ impl<B, E, Block: BlockT, RA, PRA> BabeVerifier<B, E, Block, RA, PRA> {
 			Ok(())
 		}
 	}
+
+	/// Create the extrinsic reporting the equivocation of the given authority, and send
+	/// it through `equivocation_reports` to be submitted to the transaction pool.
+	///
+	/// Nothing is reported if the runtime doesn't support equivocation reports.
+	fn report_equivocation(
+		&self,
+		offender: AuthorityId,
+		slot_number: SlotNumber,
+		first_header: Block::Header,
+		second_header: Block::Header,
+	) -> Result<(), Error<Block>> where
+		B: Backend<Block, Blake2Hasher>,
+		E: CallExecutor<Block, Blake2Hasher>,
+		PRA: ProvideRuntimeApi,
+		PRA::Api: BabeApi<Block>,
+	{
+		let sender = match self.equivocation_reports {
+			Some(ref sender) => sender,
+			None => return Ok(()),
+		};
+
+		let best_id = BlockId::Hash(self.client.info().chain.best_hash);
+		let runtime_api = self.api.runtime_api();
+		if !runtime_api.has_api_with::<dyn BabeApi<Block>, _>(&best_id, |version| version >= 2)
+			.map_err(Error::Client)?
+		{
+			debug!(target: "babe", "The runtime doesn't support equivocation reports.");
+			return Ok(())
+		}
+
+		let key_owner_proof = match runtime_api.generate_key_ownership_proof(&best_id, offender.clone())
+			.map_err(Error::Client)?
+		{
+			Some(proof) => proof,
+			None => {
+				debug!(target: "babe", "Equivocation offender {:?} isn't part of the current validator set.", offender);
+				return Ok(())
+			}
+		};
+
+		let equivocation_proof = EquivocationProof { offender, slot_number, first_header, second_header };
+		let extrinsic = runtime_api.report_equivocation_unsigned_extrinsic(
+			&best_id,
+			equivocation_proof,
+			key_owner_proof,
+		).map_err(Error::Client)?;
+
+		if let Some(extrinsic) = extrinsic {
+			// the receiver is gone if the node is shutting down.
+			let _ = sender.unbounded_send(extrinsic);
+		}
+
+		Ok(())
+	}
 }
 
 #[allow(dead_code)]
@@ -659,7 +785,8 @@ impl<B, E, Block, RA, PRA> Verifier<Block> for BabeVerifier<B, E, Block, RA, PRA
 		let pre_digest = find_pre_digest::<Block>(&header)?;
 		let epoch = {
 			let epoch_changes = self.epoch_changes.lock();
-			epoch_changes.epoch_for_child_of(
+			epoch_changes.epoch_for_child_of_switched(
+				self.switch_block,
 				descendent_query(&*self.client),
 				&parent_hash,
 				parent_header_metadata.number,
@@ -705,6 +832,16 @@ impl<B, E, Block, RA, PRA> Verifier<Block> for BabeVerifier<B, E, Block, RA, PRA
 						equivocation_proof.fst_header().hash(),
 						equivocation_proof.snd_header().hash(),
 					);
+
+					let report = self.report_equivocation(
+						author.clone(),
+						equivocation_proof.slot(),
+						equivocation_proof.fst_header().clone(),
+						equivocation_proof.snd_header().clone(),
+					);
+					if let Err(err) = report {
+						warn!(target: "babe", "Failed to report the equivocation of {:?}: {}", author, err);
+					}
 				}
 
 				// if the body is passed through, we need to use the runtime
@@ -791,6 +928,8 @@ pub struct BabeBlockImport<B, E, Block: BlockT, I, RA, PRA> {
 	api: Arc<PRA>,
 	epoch_changes: SharedEpochChanges<Block>,
 	config: Config,
+	switch_block: NumberFor<Block>,
+	previous_engine: Option<ConsensusEngineId>,
 }
 
 impl<B, E, Block: BlockT, I: Clone, RA, PRA> Clone for BabeBlockImport<B, E, Block, I, RA, PRA> {
@@ -801,6 +940,8 @@ impl<B, E, Block: BlockT, I: Clone, RA, PRA> Clone for BabeBlockImport<B, E, Blo
 			api: self.api.clone(),
 			epoch_changes: self.epoch_changes.clone(),
 			config: self.config.clone(),
+			switch_block: self.switch_block,
+			previous_engine: self.previous_engine,
 		}
 	}
 }
@@ -812,6 +953,8 @@ impl<B, E, Block: BlockT, I, RA, PRA> BabeBlockImport<B, E, Block, I, RA, PRA> {
 		epoch_changes: SharedEpochChanges<Block>,
 		block_import: I,
 		config: Config,
+		switch_block: NumberFor<Block>,
+		previous_engine: Option<ConsensusEngineId>,
 	) -> Self {
 		BabeBlockImport {
 			client,
@@ -819,6 +962,8 @@ impl<B, E, Block: BlockT, I, RA, PRA> BabeBlockImport<B, E, Block, I, RA, PRA> {
 			inner: block_import,
 			epoch_changes,
 			config,
+			switch_block,
+			previous_engine,
 		}
 	}
 }
@@ -851,6 +996,12 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 			Err(e) => return Err(ConsensusError::ClientImport(e.to_string())),
 		}
 
+		// the blocks up to the switch block were authored by the previous consensus
+		// engine, and have already been verified by its verifier.
+		if number <= self.switch_block {
+			return self.inner.import_block(block, new_cache).map_err(Into::into);
+		}
+
 		let pre_digest = find_pre_digest::<Block>(&block.header)
 			.expect("valid babe headers must contain a predigest; \
 					 header has been already verified; qed");
@@ -863,10 +1014,9 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 				Error::<Block>::ParentUnavailable(parent_hash, hash)
 			).into()))?;
 
-		let parent_slot = find_pre_digest::<Block>(&parent_header)
-			.map(|d| d.slot_number())
-			.expect("parent is non-genesis; valid BABE headers contain a pre-digest; \
-					header has already been verified; qed");
+		// the parent of the first BABE block carries the last slot of the previous engine.
+		let parent_slot = find_slot_number::<Block>(&parent_header, self.switch_block, self.previous_engine)
+			.map_err(|e| ConsensusError::ClientImport(e.to_string()))?;
 
 		// make sure that slot number is strictly increasing
 		if slot_number <= parent_slot {
@@ -885,7 +1035,7 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 		//
 		// also provides the total weight of the chain, including the imported block.
 		let (epoch, first_in_epoch, parent_weight) = {
-			let parent_weight = if *parent_header.number() == Zero::zero() {
+			let parent_weight = if *parent_header.number() <= self.switch_block {
 				0
 			} else {
 				aux_schema::load_block_weight(&*self.client, parent_hash)
@@ -895,7 +1045,8 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 					))?
 			};
 
-			let epoch = epoch_changes.epoch_for_child_of(
+			let epoch = epoch_changes.epoch_for_child_of_switched(
+				self.switch_block,
 				descendent_query(&*self.client),
 				&parent_hash,
 				*parent_header.number(),
@@ -960,6 +1111,8 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 				prune_finalized(
 					&self.client,
 					&mut epoch_changes,
+					self.switch_block,
+					self.previous_engine,
 				)?;
 
 				epoch_changes.import(
@@ -1005,6 +1158,8 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 				// the parent=genesis case is already covered for loading parent weight,
 				// so we don't need to cover again here.
 				parent_weight
+			} else if last_best_number <= self.switch_block {
+				0
 			} else {
 				aux_schema::load_block_weight(&*self.client, last_best)
 					.map_err(|e| ConsensusError::ChainLookup(format!("{:?}", e)))?
@@ -1047,6 +1202,8 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 fn prune_finalized<B, E, Block, RA>(
 	client: &Client<B, E, Block, RA>,
 	epoch_changes: &mut EpochChangesFor<Block>,
+	switch_block: NumberFor<Block>,
+	previous_engine: Option<ConsensusEngineId>,
 ) -> Result<(), ConsensusError> where
 	Block: BlockT<Hash=H256>,
 	E: CallExecutor<Block, Blake2Hasher> + Send + Sync,
@@ -1061,10 +1218,9 @@ fn prune_finalized<B, E, Block, RA>(
 			.expect("best finalized hash was given by client; \
 				 finalized headers must exist in db; qed");
 
-		find_pre_digest::<Block>(&finalized_header)
+		find_slot_number::<Block>(&finalized_header, switch_block, previous_engine)
 			.expect("finalized header must be valid; \
 					 valid blocks have a pre-digest; qed")
-			.slot_number()
 	};
 
 	epoch_changes.prune_finalized(
@@ -1091,12 +1247,31 @@ pub fn block_import<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 	B: Backend<Block, Blake2Hasher>,
 	E: CallExecutor<Block, Blake2Hasher> + Send + Sync,
 	RA: Send + Sync,
+{
+	new_block_import(config, Zero::zero(), None, wrapped_block_import, client, api)
+}
+
+/// Create the block import of a chain switching to BABE from `previous_engine` after
+/// `switch_block`, along with the `BabeLink`.
+fn new_block_import<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
+	config: Config,
+	switch_block: NumberFor<Block>,
+	previous_engine: Option<ConsensusEngineId>,
+	wrapped_block_import: I,
+	client: Arc<Client<B, E, Block, RA>>,
+	api: Arc<PRA>,
+) -> ClientResult<(BabeBlockImport<B, E, Block, I, RA, PRA>, BabeLink<Block>)> where
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync,
+	RA: Send + Sync,
 {
 	let epoch_changes = aux_schema::load_epoch_changes(&*client)?;
 	let link = BabeLink {
 		epoch_changes: epoch_changes.clone(),
 		time_source: Default::default(),
 		config: config.clone(),
+		switch_block,
+		previous_engine,
 	};
 
 	// NOTE: this isn't entirely necessary, but since we didn't use to prune the
@@ -1105,6 +1280,8 @@ pub fn block_import<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 	prune_finalized(
 		&client,
 		&mut epoch_changes.lock(),
+		switch_block,
+		previous_engine,
 	)?;
 
 	let import = BabeBlockImport::new(
@@ -1113,6 +1290,8 @@ pub fn block_import<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 		epoch_changes,
 		wrapped_block_import,
 		config,
+		switch_block,
+		previous_engine,
 	);
 
 	Ok((import, link))
@@ -1127,6 +1306,10 @@ pub fn block_import<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 ///
 /// The block import object provided must be the `BabeBlockImport` or a wrapper
 /// of it, otherwise crucial import logic will be omitted.
+///
+/// The extrinsics reporting the equivocations detected by the import queue are sent
+/// through `equivocation_reports`, if given, and are meant to be submitted to the
+/// transaction pool.
 pub fn import_queue<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 	babe_link: BabeLink<Block>,
 	block_import: I,
@@ -1135,6 +1318,7 @@ pub fn import_queue<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 	client: Arc<Client<B, E, Block, RA>>,
 	api: Arc<PRA>,
 	inherent_data_providers: InherentDataProviders,
+	equivocation_reports: Option<mpsc::UnboundedSender<Block::Extrinsic>>,
 ) -> ClientResult<BabeImportQueue<Block>> where
 	B: Backend<Block, Blake2Hasher> + 'static,
 	I: BlockImport<Block,Error=ConsensusError> + Send + Sync + 'static,
@@ -1143,16 +1327,7 @@ pub fn import_queue<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 	PRA: ProvideRuntimeApi + ProvideCache<Block> + Send + Sync + AuxStore + 'static,
 	PRA::Api: BlockBuilderApi<Block> + BabeApi<Block>,
 {
-	register_babe_inherent_data_provider(&inherent_data_providers, babe_link.config.slot_duration)?;
-
-	let verifier = BabeVerifier {
-		client: client.clone(),
-		api,
-		inherent_data_providers,
-		config: babe_link.config,
-		epoch_changes: babe_link.epoch_changes,
-		time_source: babe_link.time_source,
-	};
+	let verifier = new_verifier(babe_link, client, api, inherent_data_providers, equivocation_reports)?;
 
 	Ok(BasicQueue::new(
 		verifier,
@@ -1162,6 +1337,28 @@ pub fn import_queue<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
 	))
 }
 
+/// Create the verifier of a BABE import queue.
+fn new_verifier<B, E, Block: BlockT, RA, PRA>(
+	babe_link: BabeLink<Block>,
+	client: Arc<Client<B, E, Block, RA>>,
+	api: Arc<PRA>,
+	inherent_data_providers: InherentDataProviders,
+	equivocation_reports: Option<mpsc::UnboundedSender<Block::Extrinsic>>,
+) -> ClientResult<BabeVerifier<B, E, Block, RA, PRA>> {
+	register_babe_inherent_data_provider(&inherent_data_providers, babe_link.config.slot_duration)?;
+
+	Ok(BabeVerifier {
+		client,
+		api,
+		inherent_data_providers,
+		config: babe_link.config,
+		epoch_changes: babe_link.epoch_changes,
+		time_source: babe_link.time_source,
+		equivocation_reports,
+		switch_block: babe_link.switch_block,
+	})
+}
+
 /// BABE test helpers. Utility methods for manually authoring blocks.
 #[cfg(feature = "test-helpers")]
 pub mod test_helpers {
@@ -1180,7 +1377,8 @@ pub mod test_helpers {
 		C: ProvideRuntimeApi + ProvideCache<B> + HeaderBackend<B> + HeaderMetadata<B, Error=ClientError>,
 		C::Api: BabeApi<B>,
 	{
-		let epoch = link.epoch_changes.lock().epoch_for_child_of(
+		let epoch = link.epoch_changes.lock().epoch_for_child_of_switched(
+			link.switch_block,
 			descendent_query(client),
 			&parent.hash(),
 			parent.number().clone(),
@@ -0,0 +1,119 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Switching the block production of an existing chain to BABE.
+//!
+//! A chain using another slot-based engine, e.g. Aura, schedules the switch at a
+//! given block: the blocks up to that block are authored and verified by the previous
+//! engine, and their descendants by BABE. The first BABE block starts the genesis
+//! epoch, so the runtime is expected to start its BABE module at that block too.
+//!
+//! The slots of the blocks up to the switch block are read from the pre-runtime digests
+//! of the previous engine, so that the first BABE block must claim a slot after the last
+//! one of the previous engine. Both engines must thus use the same slot duration.
+//!
+//! Around the switch, both engines must be running: the import queue of this module
+//! dispatches the blocks to the verifier of the right engine, while the authoring
+//! worker of the previous engine must stop at the switch block, e.g. with the
+//! `last_block` of Aura, and BABE only authors on top of it.
+//!
+//! The BABE configuration can't be read from the genesis state of such a chain, it
+//! must be computed with `Config::get_or_compute_at` at a block whose runtime already
+//! implements `BabeApi`.
+
+use super::*;
+
+/// Create the BABE block import of a chain switching to BABE after `switch_block`,
+/// along with the `BabeLink` to give to the import queue and authoring worker.
+///
+/// The blocks up to `switch_block` are passed as they are to the wrapped block import.
+/// `previous_engine` is the id of the engine authoring them, e.g. `AURA_ENGINE_ID`,
+/// whose pre-runtime digest must hold the slot number of the block.
+pub fn block_import<B, E, Block: BlockT<Hash=H256>, I, RA, PRA>(
+	config: Config,
+	switch_block: NumberFor<Block>,
+	previous_engine: ConsensusEngineId,
+	wrapped_block_import: I,
+	client: Arc<Client<B, E, Block, RA>>,
+	api: Arc<PRA>,
+) -> ClientResult<(BabeBlockImport<B, E, Block, I, RA, PRA>, BabeLink<Block>)> where
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync,
+	RA: Send + Sync,
+{
+	new_block_import(config, switch_block, Some(previous_engine), wrapped_block_import, client, api)
+}
+
+/// A verifier using the verifier of the previous engine for the blocks up to the
+/// switch block, and the BABE verifier for their descendants.
+pub struct SwitchVerifier<B, E, Block: BlockT, RA, PRA, V> {
+	previous: V,
+	babe: BabeVerifier<B, E, Block, RA, PRA>,
+}
+
+impl<B, E, Block, RA, PRA, V> Verifier<Block> for SwitchVerifier<B, E, Block, RA, PRA, V> where
+	Block: BlockT<Hash=H256>,
+	V: Verifier<Block>,
+	BabeVerifier<B, E, Block, RA, PRA>: Verifier<Block>,
+{
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: Block::Header,
+		justification: Option<Justification>,
+		body: Option<Vec<Block::Extrinsic>>,
+	) -> Result<(BlockImportParams<Block>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		if *header.number() <= self.babe.switch_block {
+			self.previous.verify(origin, header, justification, body)
+		} else {
+			self.babe.verify(origin, header, justification, body)
+		}
+	}
+}
+
+/// Start the import queue of a chain switching to BABE, verifying the blocks up to
+/// the switch block of `babe_link` with `previous_verifier`.
+///
+/// The block import must be the one created by `block_import`, or a wrapper of it.
+pub fn import_queue<B, E, Block: BlockT<Hash=H256>, I, RA, PRA, V>(
+	babe_link: BabeLink<Block>,
+	previous_verifier: V,
+	block_import: I,
+	justification_import: Option<BoxJustificationImport<Block>>,
+	finality_proof_import: Option<BoxFinalityProofImport<Block>>,
+	client: Arc<Client<B, E, Block, RA>>,
+	api: Arc<PRA>,
+	inherent_data_providers: InherentDataProviders,
+	equivocation_reports: Option<mpsc::UnboundedSender<Block::Extrinsic>>,
+) -> ClientResult<BabeImportQueue<Block>> where
+	B: Backend<Block, Blake2Hasher> + 'static,
+	I: BlockImport<Block,Error=ConsensusError> + Send + Sync + 'static,
+	E: CallExecutor<Block, Blake2Hasher> + Clone + Send + Sync + 'static,
+	RA: Send + Sync + 'static,
+	PRA: ProvideRuntimeApi + ProvideCache<Block> + Send + Sync + AuxStore + 'static,
+	PRA::Api: BlockBuilderApi<Block> + BabeApi<Block>,
+	V: Verifier<Block> + 'static,
+{
+	let babe = new_verifier(babe_link, client, api, inherent_data_providers, equivocation_reports)?;
+	let verifier = SwitchVerifier { previous: previous_verifier, babe };
+
+	Ok(BasicQueue::new(
+		verifier,
+		Box::new(block_import),
+		justification_import,
+		finality_proof_import,
+	))
+}
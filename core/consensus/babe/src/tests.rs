@@ -296,6 +296,8 @@ impl TestNetFactory for BabeTestNet {
 				config: data.link.config.clone(),
 				epoch_changes: data.link.epoch_changes.clone(),
 				time_source: data.link.time_source.clone(),
+				equivocation_reports: None,
+				switch_block: data.link.switch_block,
 			},
 			mutator: MUTATOR.with(|m| m.borrow().clone()),
 		}
@@ -404,6 +406,7 @@ fn run_one_test(
 			force_authoring: false,
 			babe_link: data.link.clone(),
 			keystore,
+			backoff_authoring_blocks: (),
 		}).expect("Starts babe"));
 	}
 
@@ -777,3 +780,95 @@ fn verify_slots_are_strictly_increasing() {
 		&mut block_import,
 	);
 }
+
+#[test]
+fn imports_blocks_across_the_switch_from_aura() {
+	use codec::Encode;
+
+	const AURA_ENGINE_ID: ConsensusEngineId = *b"aura";
+
+	let mut net = BabeTestNet::new(1);
+	let client = net.peer(0).client().as_full().expect("Only full clients are used in tests").clone();
+
+	let config = Config::get_or_compute(&*client).expect("config available");
+	let (block_import, link) = crate::migration::block_import(
+		config,
+		2,
+		AURA_ENGINE_ID,
+		client.clone(),
+		client.clone(),
+		client.clone(),
+	).expect("can initialize block-import");
+	let mut block_import: BoxBlockImport<TestBlock> = Box::new(block_import);
+
+	let mut import_block = |parent_hash, pre_digest, next_epoch: bool| {
+		let mut block = client.new_block_at(
+			&BlockId::Hash(parent_hash),
+			sr_primitives::generic::Digest { logs: vec![pre_digest] },
+		).unwrap().bake().unwrap();
+
+		if next_epoch {
+			let genesis_epoch = link.config.genesis_epoch(0);
+			let digest_data = ConsensusLog::NextEpochData(NextEpochDescriptor {
+				authorities: genesis_epoch.authorities,
+				randomness: genesis_epoch.randomness,
+			}).encode();
+			block.header.digest_mut().push(DigestItem::Consensus(BABE_ENGINE_ID, digest_data));
+		}
+
+		let hash = block.header.hash();
+		block_import.import_block(
+			BlockImportParams {
+				origin: BlockOrigin::Own,
+				header: block.header,
+				justification: None,
+				post_digests: Vec::new(),
+				body: Some(block.extrinsics),
+				finalized: false,
+				auxiliary: Vec::new(),
+				fork_choice: ForkChoiceStrategy::LongestChain,
+				allow_missing_state: false,
+			},
+			Default::default(),
+		).map(|result| (hash, result))
+	};
+
+	let aura_pre_digest = |slot_number: SlotNumber| {
+		Item::PreRuntime(AURA_ENGINE_ID, slot_number.encode())
+	};
+	let babe_pre_digest = |slot_number| Item::babe_pre_digest(
+		BabePreDigest::Secondary { authority_index: 0, slot_number },
+	);
+
+	// the blocks up to the switch block are passed through.
+	let genesis_hash = client.info().chain.genesis_hash;
+	let (b1, _) = import_block(genesis_hash, aura_pre_digest(10), false).unwrap();
+	let (b2, _) = import_block(b1, aura_pre_digest(11), false).unwrap();
+
+	// the first BABE block must come after the last Aura slot.
+	assert!(import_block(b2, babe_pre_digest(11), true).is_err());
+
+	// it starts the genesis epoch and announces the next one.
+	assert!(import_block(b2, babe_pre_digest(12), false).is_err());
+	let (b3, result) = import_block(b2, babe_pre_digest(12), true).unwrap();
+	match result {
+		ImportResult::Imported(_) => {},
+		_ => panic!("expected block to be imported"),
+	}
+
+	let epoch = link.epoch_changes.lock().epoch_for_child_of_switched(
+		2,
+		descendent_query(&*client),
+		&b3,
+		3,
+		13,
+		|slot| link.config.genesis_epoch(slot),
+	).unwrap().unwrap().into_inner();
+	assert_eq!(epoch, link.config.genesis_epoch(12));
+
+	let (_, result) = import_block(b3, babe_pre_digest(13), false).unwrap();
+	match result {
+		ImportResult::Imported(_) => {},
+		_ => panic!("expected block to be imported"),
+	}
+}
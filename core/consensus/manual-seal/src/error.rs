@@ -0,0 +1,92 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Errors of the manual seal engine.
+
+use consensus_common::ImportResult;
+use futures::channel::{mpsc::SendError, oneshot::Canceled};
+use sr_primitives::RuntimeString;
+
+/// Base code of the manual seal RPC errors.
+const BASE_ERROR: i64 = 15000;
+
+/// Error of the manual seal engine.
+#[derive(Debug, derive_more::Display)]
+pub enum Error {
+	/// The pool has no transaction to seal and empty blocks weren't requested.
+	#[display(fmt = "Transaction pool is empty, set create_empty to true if you want to create empty blocks")]
+	EmptyTransactionPool,
+	/// The sealed block couldn't be imported.
+	#[display(fmt = "Block import failed: {:?}", _0)]
+	BlockImportError(ImportResult),
+	/// The requested block doesn't exist.
+	#[display(fmt = "Block not found: {}", _0)]
+	BlockNotFound(String),
+	/// Consensus error.
+	#[display(fmt = "Consensus error: {}", _0)]
+	ConsensusError(consensus_common::Error),
+	/// Client error.
+	#[display(fmt = "Client error: {}", _0)]
+	ClientError(client::error::Error),
+	/// The inherent data couldn't be created.
+	#[display(fmt = "Creating inherents failed: {}", _0)]
+	CreateInherents(RuntimeString),
+	/// The block couldn't be proposed.
+	#[display(fmt = "Block proposing error: {}", _0)]
+	BlockProposingError(String),
+	/// The engine task isn't running anymore.
+	#[display(fmt = "The manual seal task is not running: {}", _0)]
+	SendError(SendError),
+	/// The engine task dropped the command without answering.
+	#[display(fmt = "The manual seal task dropped the command: {}", _0)]
+	Canceled(Canceled),
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::ConsensusError(ref err) => Some(err),
+			Error::ClientError(ref err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl Error {
+	/// Code of the error, when reported through the RPC.
+	fn to_code(&self) -> i64 {
+		match self {
+			Error::EmptyTransactionPool => BASE_ERROR + 1,
+			Error::BlockImportError(_) => BASE_ERROR + 2,
+			Error::BlockNotFound(_) => BASE_ERROR + 3,
+			Error::ConsensusError(_) => BASE_ERROR + 4,
+			Error::ClientError(_) => BASE_ERROR + 5,
+			Error::CreateInherents(_) => BASE_ERROR + 6,
+			Error::BlockProposingError(_) => BASE_ERROR + 7,
+			Error::SendError(_) | Error::Canceled(_) => BASE_ERROR + 8,
+		}
+	}
+}
+
+impl From<Error> for jsonrpc_core::Error {
+	fn from(error: Error) -> Self {
+		jsonrpc_core::Error {
+			code: jsonrpc_core::ErrorCode::ServerError(error.to_code()),
+			message: error.to_string(),
+			data: None,
+		}
+	}
+}
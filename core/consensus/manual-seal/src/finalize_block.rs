@@ -0,0 +1,38 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Finalization of blocks on demand.
+
+use client::backend::{Backend, Finalizer};
+use primitives::Blake2Hasher;
+use sr_primitives::{Justification, generic::BlockId, traits::Block as BlockT};
+use crate::{error::Error, rpc};
+
+/// Finalize the block `hash` and report the outcome to `sender`.
+pub fn finalize_block<B, CB, F>(
+	finalizer: &F,
+	hash: B::Hash,
+	justification: Option<Justification>,
+	sender: rpc::Sender<()>,
+) where
+	B: BlockT<Hash = primitives::H256>,
+	CB: Backend<B, Blake2Hasher>,
+	F: Finalizer<B, Blake2Hasher, CB>,
+{
+	let finalized = finalizer.finalize_block(BlockId::Hash(hash), justification, true)
+		.map_err(Error::ClientError);
+	rpc::send_result(sender, finalized)
+}
@@ -0,0 +1,299 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Manual sealing engine: blocks are authored on demand rather than at regular intervals.
+//!
+//! `run_instant_seal` seals a block as soon as a transaction enters the ready queue of the pool.
+//! `run_manual_seal` seals and finalizes blocks when told so through `EngineCommand`s, which
+//! are usually sent by the `engine_createBlock` and `engine_finalizeBlock` RPCs implemented by
+//! `rpc::ManualSeal`.
+//!
+//! This is meant for development chains with a single authoring node: blocks aren't checked
+//! for any seal, so that any block imported from the network is accepted.
+
+use std::sync::Arc;
+use futures::{
+	future::{Either, ready},
+	Future, Stream, StreamExt,
+};
+use parking_lot::Mutex;
+use client::{backend::{Backend, Finalizer}, blockchain::HeaderBackend};
+use consensus_common::{
+	BlockImportParams, BlockOrigin, Environment, ForkChoiceStrategy, SelectChain,
+	import_queue::{BasicQueue, BoxBlockImport, CacheKeyId, Verifier},
+};
+use inherents::InherentDataProviders;
+use primitives::{Blake2Hasher, H256};
+use sr_primitives::{Justification, traits::Block as BlockT};
+use transaction_pool::txpool;
+
+mod error;
+mod finalize_block;
+mod seal_new_block;
+pub mod rpc;
+
+pub use self::{
+	error::Error,
+	finalize_block::finalize_block,
+	rpc::{CreatedBlock, EngineCommand},
+	seal_new_block::seal_new_block,
+};
+
+/// Verifier accepting any block.
+struct ManualSealVerifier;
+
+impl<B: BlockT> Verifier<B> for ManualSealVerifier {
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: B::Header,
+		justification: Option<Justification>,
+		body: Option<Vec<B::Extrinsic>>,
+	) -> Result<(BlockImportParams<B>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		let import_block = BlockImportParams {
+			origin,
+			header,
+			justification,
+			post_digests: Vec::new(),
+			body,
+			finalized: false,
+			auxiliary: Vec::new(),
+			fork_choice: ForkChoiceStrategy::LongestChain,
+			allow_missing_state: false,
+		};
+
+		Ok((import_block, None))
+	}
+}
+
+/// The manual seal import queue type.
+pub type ManualSealImportQueue<B> = BasicQueue<B>;
+
+/// Import queue for the manual seal engine, accepting any block.
+pub fn import_queue<B: BlockT>(block_import: BoxBlockImport<B>) -> ManualSealImportQueue<B> {
+	BasicQueue::new(ManualSealVerifier, block_import, None, None)
+}
+
+/// Run the manual seal engine, executing the `commands` one after the other.
+///
+/// The returned future resolves once the stream of commands ends.
+pub fn run_manual_seal<B, CB, C, E, A, SC, S>(
+	block_import: BoxBlockImport<B>,
+	mut env: E,
+	client: Arc<C>,
+	pool: Arc<txpool::Pool<A>>,
+	commands: S,
+	select_chain: SC,
+	inherent_data_providers: InherentDataProviders,
+) -> impl Future<Output = ()> where
+	B: BlockT<Hash = H256>,
+	CB: Backend<B, Blake2Hasher>,
+	C: HeaderBackend<B> + Finalizer<B, Blake2Hasher, CB>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	A: txpool::ChainApi<Block = B>,
+	SC: SelectChain<B>,
+	S: Stream<Item = EngineCommand<B::Hash>>,
+{
+	let block_import = Arc::new(Mutex::new(block_import));
+	commands.for_each(move |command| match command {
+		EngineCommand::SealNewBlock { create_empty, finalize, parent_hash, sender } => Either::Left(
+			seal_new_block(
+				create_empty,
+				finalize,
+				parent_hash,
+				sender,
+				&*client,
+				&pool,
+				&select_chain,
+				&mut env,
+				block_import.clone(),
+				&inherent_data_providers,
+			)
+		),
+		EngineCommand::FinalizeBlock { hash, justification, sender } => {
+			finalize_block(&*client, hash, justification, sender);
+			Either::Right(ready(()))
+		},
+	})
+}
+
+/// Run the instant seal engine, sealing a block whenever transactions enter the ready queue of
+/// the pool.
+///
+/// The blocks aren't finalized.
+pub fn run_instant_seal<B, CB, C, E, A, SC>(
+	block_import: BoxBlockImport<B>,
+	env: E,
+	client: Arc<C>,
+	pool: Arc<txpool::Pool<A>>,
+	select_chain: SC,
+	inherent_data_providers: InherentDataProviders,
+) -> impl Future<Output = ()> where
+	B: BlockT<Hash = H256>,
+	CB: Backend<B, Blake2Hasher>,
+	C: HeaderBackend<B> + Finalizer<B, Blake2Hasher, CB>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	A: txpool::ChainApi<Block = B>,
+	SC: SelectChain<B>,
+{
+	let commands = pool.import_notification_stream().map(|_| EngineCommand::SealNewBlock {
+		create_empty: false,
+		finalize: false,
+		parent_hash: None,
+		sender: None,
+	});
+
+	run_manual_seal(block_import, env, client, pool, commands, select_chain, inherent_data_providers)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::{channel::oneshot, executor::block_on, future::select, stream};
+	use basic_authorship::ProposerFactory;
+	use client::LongestChain;
+	use sr_primitives::generic::BlockId;
+	use test_client::{
+		prelude::*,
+		runtime::{Block, Extrinsic, Transfer},
+	};
+	use transaction_pool::FullChainApi;
+
+	type TestPool = txpool::Pool<FullChainApi<TestClient, Block>>;
+
+	fn extrinsic(nonce: u64) -> Extrinsic {
+		Transfer {
+			amount: Default::default(),
+			nonce,
+			from: AccountKeyring::Alice.into(),
+			to: Default::default(),
+		}.into_signed_tx()
+	}
+
+	fn setup() -> (Arc<TestClient>, LongestChain<Backend, Block>, Arc<TestPool>) {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let pool = Arc::new(txpool::Pool::new(Default::default(), FullChainApi::new(client.clone())));
+		(client, select_chain, pool)
+	}
+
+	fn run(
+		client: &Arc<TestClient>,
+		select_chain: &LongestChain<Backend, Block>,
+		pool: &Arc<TestPool>,
+		command: EngineCommand<H256>,
+	) {
+		let env = ProposerFactory {
+			client: client.clone(),
+			transaction_pool: pool.clone(),
+			limits: Default::default(),
+		};
+		block_on(run_manual_seal(
+			Box::new(client.clone()),
+			env,
+			client.clone(),
+			pool.clone(),
+			stream::iter(vec![command]),
+			select_chain.clone(),
+			InherentDataProviders::new(),
+		));
+	}
+
+	#[test]
+	fn should_seal_and_finalize_blocks_on_demand() {
+		let (client, select_chain, pool) = setup();
+
+		// nothing to seal
+		let (sender, receiver) = oneshot::channel();
+		run(&client, &select_chain, &pool, EngineCommand::SealNewBlock {
+			create_empty: false,
+			finalize: false,
+			parent_hash: None,
+			sender: Some(sender),
+		});
+		match block_on(receiver).unwrap() {
+			Err(Error::EmptyTransactionPool) => {},
+			other => panic!("Unexpected outcome: {:?}", other),
+		}
+		assert_eq!(client.info().chain.best_number, 0);
+
+		// sealing the transaction
+		block_on(pool.submit_one(&BlockId::number(0), extrinsic(0))).unwrap();
+		let (sender, receiver) = oneshot::channel();
+		run(&client, &select_chain, &pool, EngineCommand::SealNewBlock {
+			create_empty: false,
+			finalize: false,
+			parent_hash: None,
+			sender: Some(sender),
+		});
+		let created = block_on(receiver).unwrap().unwrap();
+		assert!(created.is_new_best);
+		assert_eq!(client.info().chain.best_number, 1);
+		assert_eq!(client.info().chain.best_hash, created.hash);
+		assert_eq!(client.info().chain.finalized_number, 0);
+		assert_eq!(client.body(&BlockId::hash(created.hash)).unwrap().unwrap(), vec![extrinsic(0)]);
+
+		// finalizing it
+		let (sender, receiver) = oneshot::channel();
+		run(&client, &select_chain, &pool, EngineCommand::FinalizeBlock {
+			hash: created.hash,
+			justification: None,
+			sender: Some(sender),
+		});
+		block_on(receiver).unwrap().unwrap();
+		assert_eq!(client.info().chain.finalized_hash, created.hash);
+
+		// sealing an empty block, finalized at once
+		let (sender, receiver) = oneshot::channel();
+		run(&client, &select_chain, &pool, EngineCommand::SealNewBlock {
+			create_empty: true,
+			finalize: true,
+			parent_hash: Some(created.hash),
+			sender: Some(sender),
+		});
+		let created = block_on(receiver).unwrap().unwrap();
+		assert_eq!(client.info().chain.best_number, 2);
+		assert_eq!(client.info().chain.finalized_hash, created.hash);
+	}
+
+	#[test]
+	fn should_seal_instantly() {
+		let (client, select_chain, pool) = setup();
+		let env = ProposerFactory {
+			client: client.clone(),
+			transaction_pool: pool.clone(),
+			limits: Default::default(),
+		};
+		let engine = run_instant_seal(
+			Box::new(client.clone()),
+			env,
+			client.clone(),
+			pool.clone(),
+			select_chain,
+			InherentDataProviders::new(),
+		);
+		let mut imported = client.import_notification_stream();
+
+		block_on(pool.submit_one(&BlockId::number(0), extrinsic(0))).unwrap();
+		block_on(select(Box::pin(engine), imported.next()));
+
+		assert_eq!(client.info().chain.best_number, 1);
+		let best = BlockId::hash(client.info().chain.best_hash);
+		assert_eq!(client.body(&best).unwrap().unwrap(), vec![extrinsic(0)]);
+	}
+}
@@ -0,0 +1,155 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC of the manual seal engine, to create and finalize blocks on demand.
+
+use futures::{
+	channel::{mpsc, oneshot},
+	future::{Either, ready},
+	FutureExt, TryFutureExt,
+};
+use jsonrpc_core::futures::Future as _;
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sr_primitives::Justification;
+use crate::error::Error;
+
+pub use self::gen_client::Client as ManualSealClient;
+
+/// Future result of the RPC calls.
+pub type FutureResult<T> = Box<dyn jsonrpc_core::futures::Future<Item = T, Error = jsonrpc_core::Error> + Send>;
+
+/// Channel used to report the outcome of a command, if anyone is interested.
+pub type Sender<T> = Option<oneshot::Sender<Result<T, Error>>>;
+
+/// Command sent to the manual seal task.
+pub enum EngineCommand<Hash> {
+	/// Seal a new block on top of the given parent, or of the best block if `None`.
+	SealNewBlock {
+		/// Seal the block even if there is no transaction to include.
+		create_empty: bool,
+		/// Finalize the block once imported.
+		finalize: bool,
+		/// Parent of the block.
+		parent_hash: Option<Hash>,
+		/// Where to report the outcome.
+		sender: Sender<CreatedBlock<Hash>>,
+	},
+	/// Finalize the given block.
+	FinalizeBlock {
+		/// Hash of the block.
+		hash: Hash,
+		/// Justification of the finality.
+		justification: Option<Justification>,
+		/// Where to report the outcome.
+		sender: Sender<()>,
+	},
+}
+
+/// A block sealed through the manual seal engine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedBlock<Hash> {
+	/// Hash of the block.
+	pub hash: Hash,
+	/// Whether the block became the new best block.
+	pub is_new_best: bool,
+}
+
+/// Manual seal RPC API.
+#[rpc]
+pub trait ManualSealApi<Hash> {
+	/// Seal a new block, on top of the given parent or of the best block.
+	#[rpc(name = "engine_createBlock")]
+	fn create_block(
+		&self,
+		create_empty: bool,
+		finalize: bool,
+		parent_hash: Option<Hash>,
+	) -> FutureResult<CreatedBlock<Hash>>;
+
+	/// Finalize the given block.
+	#[rpc(name = "engine_finalizeBlock")]
+	fn finalize_block(
+		&self,
+		hash: Hash,
+		justification: Option<Justification>,
+	) -> FutureResult<bool>;
+}
+
+/// Implementation of the manual seal RPC API, forwarding the calls to the manual seal task.
+pub struct ManualSeal<Hash> {
+	commands: mpsc::UnboundedSender<EngineCommand<Hash>>,
+}
+
+impl<Hash> ManualSeal<Hash> {
+	/// Create the RPC, sending the commands to the task started with `run_manual_seal`.
+	pub fn new(commands: mpsc::UnboundedSender<EngineCommand<Hash>>) -> Self {
+		ManualSeal { commands }
+	}
+
+	fn send<T: Send + 'static>(
+		&self,
+		command: impl FnOnce(Sender<T>) -> EngineCommand<Hash>,
+	) -> FutureResult<T> {
+		let (sender, receiver) = oneshot::channel();
+		let future = match self.commands.unbounded_send(command(Some(sender))) {
+			Ok(()) => Either::Left(receiver.map(|outcome|
+				outcome.map_err(Error::Canceled).and_then(|outcome| outcome)
+			)),
+			Err(e) => Either::Right(ready(Err(Error::SendError(e.into_send_error())))),
+		};
+		Box::new(future.map_err(jsonrpc_core::Error::from).compat())
+	}
+}
+
+impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
+	fn create_block(
+		&self,
+		create_empty: bool,
+		finalize: bool,
+		parent_hash: Option<Hash>,
+	) -> FutureResult<CreatedBlock<Hash>> {
+		self.send(|sender| EngineCommand::SealNewBlock { create_empty, finalize, parent_hash, sender })
+	}
+
+	fn finalize_block(
+		&self,
+		hash: Hash,
+		justification: Option<Justification>,
+	) -> FutureResult<bool> {
+		Box::new(
+			self.send(|sender| EngineCommand::FinalizeBlock { hash, justification, sender })
+				.map(|()| true)
+		)
+	}
+}
+
+/// Report the outcome of a command to its sender, or log it if nobody is interested.
+pub fn send_result<T: std::fmt::Debug>(sender: Sender<T>, result: Result<T, Error>) {
+	match sender {
+		Some(sender) => {
+			// the receiver may be gone if the RPC call was dropped.
+			let _ = sender.send(result);
+		},
+		None => match result {
+			Ok(outcome) => log::info!(target: "manual-seal", "Manual seal command succeeded: {:?}", outcome),
+			// expected when sealing instantly, as several transactions may be sealed at once.
+			Err(Error::EmptyTransactionPool) => log::debug!(target: "manual-seal", "No transaction to seal"),
+			Err(e) => log::error!(target: "manual-seal", "Manual seal command failed: {}", e),
+		},
+	}
+}
@@ -0,0 +1,139 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Sealing of new blocks on demand.
+
+use std::{sync::Arc, time::Duration};
+use futures::{
+	future::{Either, ready},
+	Future, FutureExt, TryFutureExt,
+};
+use parking_lot::Mutex;
+use client::blockchain::HeaderBackend;
+use consensus_common::{
+	BlockImport, BlockImportParams, BlockOrigin, Environment, ForkChoiceStrategy, ImportResult,
+	Proposer, SelectChain, import_queue::BoxBlockImport,
+};
+use inherents::InherentDataProviders;
+use sr_primitives::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use transaction_pool::txpool;
+use crate::{error::Error, rpc::{self, CreatedBlock}};
+
+/// Maximal time spent building a block.
+const MAX_PROPOSAL_DURATION: Duration = Duration::from_secs(10);
+
+/// Seal a new block on top of `parent_hash`, or of the best block if `None`, and import it.
+///
+/// Unless `create_empty` is set, no block is sealed when the pool has no ready transaction.
+/// The outcome is reported to `sender`.
+pub fn seal_new_block<B, C, E, A, SC>(
+	create_empty: bool,
+	finalize: bool,
+	parent_hash: Option<B::Hash>,
+	sender: rpc::Sender<CreatedBlock<B::Hash>>,
+	client: &C,
+	pool: &txpool::Pool<A>,
+	select_chain: &SC,
+	env: &mut E,
+	block_import: Arc<Mutex<BoxBlockImport<B>>>,
+	inherent_data_providers: &InherentDataProviders,
+) -> impl Future<Output = ()> where
+	B: BlockT,
+	C: HeaderBackend<B>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	A: txpool::ChainApi<Block = B>,
+	SC: SelectChain<B>,
+{
+	let proposal = match propose(
+		create_empty,
+		parent_hash,
+		client,
+		pool,
+		select_chain,
+		env,
+		inherent_data_providers,
+	) {
+		Ok(proposal) => Either::Left(
+			proposal.map_err(|e| Error::BlockProposingError(format!("{:?}", e)))
+		),
+		Err(e) => Either::Right(ready(Err(e))),
+	};
+
+	proposal.map(move |block| {
+		let created = block.and_then(|block| import_block(&mut *block_import.lock(), block, finalize));
+		rpc::send_result(sender, created)
+	})
+}
+
+fn propose<B, C, E, A, SC>(
+	create_empty: bool,
+	parent_hash: Option<B::Hash>,
+	client: &C,
+	pool: &txpool::Pool<A>,
+	select_chain: &SC,
+	env: &mut E,
+	inherent_data_providers: &InherentDataProviders,
+) -> Result<<E::Proposer as Proposer<B>>::Create, Error> where
+	B: BlockT,
+	C: HeaderBackend<B>,
+	E: Environment<B>,
+	E::Error: std::fmt::Debug,
+	A: txpool::ChainApi<Block = B>,
+	SC: SelectChain<B>,
+{
+	if !create_empty && pool.status().ready == 0 {
+		return Err(Error::EmptyTransactionPool)
+	}
+
+	let parent = match parent_hash {
+		Some(hash) => client.header(BlockId::Hash(hash))
+			.map_err(Error::ClientError)?
+			.ok_or_else(|| Error::BlockNotFound(format!("{:?}", hash)))?,
+		None => select_chain.best_chain().map_err(Error::ConsensusError)?,
+	};
+	let mut proposer = env.init(&parent)
+		.map_err(|e| Error::BlockProposingError(format!("{:?}", e)))?;
+	let inherent_data = inherent_data_providers.create_inherent_data()
+		.map_err(Error::CreateInherents)?;
+
+	Ok(proposer.propose(inherent_data, Default::default(), MAX_PROPOSAL_DURATION))
+}
+
+fn import_block<B: BlockT>(
+	block_import: &mut BoxBlockImport<B>,
+	block: B,
+	finalize: bool,
+) -> Result<CreatedBlock<B::Hash>, Error> {
+	let (header, body) = block.deconstruct();
+	let hash = header.hash();
+	let import_block = BlockImportParams {
+		origin: BlockOrigin::Own,
+		header,
+		justification: None,
+		post_digests: Vec::new(),
+		body: Some(body),
+		finalized: finalize,
+		auxiliary: Vec::new(),
+		fork_choice: ForkChoiceStrategy::LongestChain,
+		allow_missing_state: false,
+	};
+
+	match block_import.import_block(import_block, Default::default()).map_err(Error::ConsensusError)? {
+		ImportResult::Imported(aux) => Ok(CreatedBlock { hash, is_new_best: aux.is_new_best }),
+		other => Err(Error::BlockImportError(other)),
+	}
+}
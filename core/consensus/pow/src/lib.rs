@@ -35,7 +35,7 @@ use std::collections::HashMap;
 use client::{
 	BlockOf, blockchain::{HeaderBackend, ProvideCache},
 	block_builder::api::BlockBuilder as BlockBuilderApi, backend::AuxStore,
-	well_known_cache_keys::Id as CacheKeyId,
+	well_known_cache_keys::Id as CacheKeyId, versioned_aux,
 };
 use sr_primitives::{Justification, RuntimeString};
 use sr_primitives::generic::{BlockId, Digest, DigestItem};
@@ -93,6 +93,9 @@ impl<B: BlockT> std::convert::From<Error<B>> for String {
 /// Auxiliary storage prefix for PoW engine.
 pub const POW_AUX_PREFIX: [u8; 4] = *b"PoW:";
 
+/// Version of the encoding of `PowAux` in the auxiliary storage.
+const POW_AUX_VERSION: u32 = 1;
+
 /// Get the auxiliary storage key used by engine to store total difficulty.
 fn aux_key(hash: &H256) -> Vec<u8> {
 	POW_AUX_PREFIX.iter().chain(&hash[..])
@@ -116,13 +119,22 @@ impl<Difficulty> PowAux<Difficulty> where
 		let key = aux_key(hash);
 
 		match client.get_aux(&key).map_err(Error::Client)? {
-			Some(bytes) => Self::decode(&mut &bytes[..])
+			// the encoding hasn't changed since it is versioned.
+			Some(bytes) => versioned_aux::decode(&bytes, POW_AUX_VERSION, versioned_aux::decode_unchanged_since(0))
+				.map(|decoded| decoded.value)
 				.map_err(Error::Codec),
 			None => Ok(Self::default()),
 		}
 	}
 }
 
+impl<Difficulty: Encode> PowAux<Difficulty> {
+	/// Encode the auxiliary for the auxiliary storage.
+	pub fn encode_versioned(&self) -> Vec<u8> {
+		versioned_aux::encode(POW_AUX_VERSION, self)
+	}
+}
+
 /// Algorithm used for proof of work.
 pub trait PowAlgorithm<B: BlockT> {
 	/// Difficulty for the algorithm.
@@ -302,7 +314,7 @@ impl<B: BlockT<Hash=H256>, C, S, Algorithm> Verifier<B> for PowVerifier<B, C, S,
 			body,
 			finalized: false,
 			justification,
-			auxiliary: vec![(key, Some(aux.encode()))],
+			auxiliary: vec![(key, Some(aux.encode_versioned()))],
 			fork_choice: ForkChoiceStrategy::Custom(aux.total_difficulty > best_aux.total_difficulty),
 			allow_missing_state: false,
 		};
@@ -530,7 +542,7 @@ fn mine_loop<B: BlockT<Hash=H256>, C, Algorithm, E, SO, S>(
 			post_digests: vec![DigestItem::Seal(POW_ENGINE_ID, seal)],
 			body: Some(body),
 			finalized: false,
-			auxiliary: vec![(key, Some(aux.encode()))],
+			auxiliary: vec![(key, Some(aux.encode_versioned()))],
 			fork_choice: ForkChoiceStrategy::Custom(true),
 			allow_missing_state: false,
 		};
@@ -19,11 +19,15 @@
 use codec::{Encode, Decode};
 use client::backend::AuxStore;
 use client::error::{Result as ClientResult, Error as ClientError};
+use client::versioned_aux;
 use sr_primitives::traits::Header;
 
 const SLOT_HEADER_MAP_KEY: &[u8] = b"slot_header_map";
 const SLOT_HEADER_START: &[u8] = b"slot_header_start";
 
+/// Version of the encoding of the values in the aux-db.
+const SLOTS_AUX_VERSION: u32 = 1;
+
 /// We keep at least this number of slots in database.
 pub const MAX_SLOT_CAPACITY: u64 = 1000;
 /// We prune slots when they reach this number.
@@ -36,11 +40,12 @@ fn load_decode<C, T>(backend: &C, key: &[u8]) -> ClientResult<Option<T>>
 {
 	match backend.get_aux(key)? {
 		None => Ok(None),
-		Some(t) => T::decode(&mut &t[..])
+		// the encoding of the values hasn't changed since they are versioned.
+		Some(t) => versioned_aux::decode(&t, SLOTS_AUX_VERSION, versioned_aux::decode_unchanged_since(0))
 			.map_err(
 				|e| ClientError::Backend(format!("Slots DB is corrupted. Decode error: {}", e.what())),
 			)
-			.map(Some)
+			.map(|decoded| Some(decoded.value))
 	}
 }
 
@@ -140,8 +145,8 @@ pub fn check_equivocation<C, H, P>(
 
 	backend.insert_aux(
 		&[
-			(&curr_slot_key[..], headers_with_sig.encode().as_slice()),
-			(&slot_header_start[..], new_first_saved_slot.encode().as_slice()),
+			(&curr_slot_key[..], versioned_aux::encode(SLOTS_AUX_VERSION, &headers_with_sig).as_slice()),
+			(&slot_header_start[..], versioned_aux::encode(SLOTS_AUX_VERSION, &new_first_saved_slot).as_slice()),
 		],
 		&keys_to_delete.iter().map(|k| &k[..]).collect::<Vec<&[u8]>>()[..],
 	)?;
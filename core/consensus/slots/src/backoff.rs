@@ -0,0 +1,152 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Strategies to back off from authoring blocks.
+//!
+//! When finality lags, e.g. because too many GRANDPA voters are offline, every authored block
+//! makes the unfinalized chain longer. Nodes have to keep all of it around, and finality has
+//! to catch up with it once it resumes. Backing off from authoring bounds that growth, while
+//! still authoring some blocks so that the chain doesn't halt.
+
+use std::fmt::Debug;
+use log::debug;
+use rand::Rng;
+use sr_primitives::traits::{One, Saturating, SimpleArithmetic, UniqueSaturatedInto};
+
+/// A strategy deciding whether to back off from authoring a block.
+pub trait BackoffAuthoringBlocksStrategy<N> {
+	/// Whether to skip authoring a block on top of the given chain head, given the number of
+	/// the last finalized block.
+	fn should_backoff(&self, chain_head_number: N, finalized_number: N, logging_target: &str) -> bool;
+}
+
+/// Never back off from authoring.
+impl<N> BackoffAuthoringBlocksStrategy<N> for () {
+	fn should_backoff(&self, _: N, _: N, _: &str) -> bool {
+		false
+	}
+}
+
+/// Back off from authoring as the unfinalized chain grows.
+///
+/// Past the slack, a claimed slot is only used to author a block with a probability of
+/// `1 / interval`, where the interval grows by one slot every `authoring_bias` unfinalized
+/// blocks, up to `max_interval`.
+#[derive(Debug, Clone)]
+pub struct BackoffAuthoringOnFinalizedHeadLagging<N> {
+	/// Number of unfinalized blocks which are authored without backing off.
+	pub unfinalized_slack: N,
+	/// Number of unfinalized blocks past the slack which increase the expected interval
+	/// between authored blocks by one slot.
+	pub authoring_bias: N,
+	/// Maximum expected interval between authored blocks, in slots.
+	pub max_interval: u32,
+}
+
+impl<N: From<u32>> Default for BackoffAuthoringOnFinalizedHeadLagging<N> {
+	fn default() -> Self {
+		BackoffAuthoringOnFinalizedHeadLagging {
+			unfinalized_slack: 50.into(),
+			authoring_bias: 2.into(),
+			max_interval: 100,
+		}
+	}
+}
+
+impl<N: SimpleArithmetic + Copy> BackoffAuthoringOnFinalizedHeadLagging<N> {
+	/// Expected interval between authored blocks, in slots, given the number of unfinalized
+	/// blocks.
+	pub fn interval(&self, unfinalized: N) -> u32 {
+		if unfinalized <= self.unfinalized_slack {
+			return 1;
+		}
+
+		let lag = (unfinalized - self.unfinalized_slack) / self.authoring_bias.max(One::one());
+		let interval: u32 = lag.saturating_add(One::one()).unique_saturated_into();
+		interval.min(self.max_interval).max(1)
+	}
+}
+
+impl<N: SimpleArithmetic + Copy + Debug> BackoffAuthoringBlocksStrategy<N>
+	for BackoffAuthoringOnFinalizedHeadLagging<N>
+{
+	fn should_backoff(&self, chain_head_number: N, finalized_number: N, logging_target: &str) -> bool {
+		let unfinalized = chain_head_number.saturating_sub(finalized_number);
+		let interval = self.interval(unfinalized);
+		if interval == 1 || rand::thread_rng().gen_ratio(1, interval) {
+			return false;
+		}
+
+		debug!(
+			target: logging_target,
+			"Backing off from authoring on top of #{:?}: {:?} blocks are unfinalized, \
+			authoring once every {} slots on average.",
+			chain_head_number,
+			unfinalized,
+			interval,
+		);
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn interval_grows_with_unfinalized_blocks() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64> {
+			unfinalized_slack: 10,
+			authoring_bias: 2,
+			max_interval: 5,
+		};
+
+		assert_eq!(strategy.interval(0), 1);
+		assert_eq!(strategy.interval(10), 1);
+		assert_eq!(strategy.interval(11), 1);
+		assert_eq!(strategy.interval(12), 2);
+		assert_eq!(strategy.interval(16), 4);
+		assert_eq!(strategy.interval(1000), 5);
+	}
+
+	#[test]
+	fn never_backs_off_within_slack() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64>::default();
+
+		for _ in 0..100 {
+			assert!(!strategy.should_backoff(150, 100, "slots"));
+			assert!(!().should_backoff(1000u64, 0, "slots"));
+		}
+		// the chain head can't be behind the finalized block, but don't underflow if it is.
+		assert!(!strategy.should_backoff(0, 100, "slots"));
+
+	}
+
+	#[test]
+	fn still_authors_when_backing_off() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64> {
+			unfinalized_slack: 10,
+			authoring_bias: 1,
+			max_interval: 2,
+		};
+
+		// a block is authored on average every other slot.
+		let results = (0..100).map(|_| strategy.should_backoff(1000, 0, "slots")).collect::<Vec<_>>();
+		assert!(results.contains(&true));
+		assert!(results.contains(&false));
+	}
+}
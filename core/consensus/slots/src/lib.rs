@@ -25,11 +25,14 @@
 
 mod slots;
 mod aux_schema;
+mod backoff;
 
 pub use slots::{SignedDuration, SlotInfo};
 use slots::Slots;
 pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
+pub use backoff::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
 
+use client::versioned_aux;
 use codec::{Decode, Encode};
 use consensus_common::{BlockImport, Proposer, SyncOracle, SelectChain};
 use futures::{prelude::*, future::{self, Either}};
@@ -42,6 +45,9 @@ use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc};
 use substrate_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
 use parking_lot::Mutex;
 
+/// Version of the encoding of the slot duration in the aux-db.
+const SLOT_DURATION_VERSION: u32 = 1;
+
 /// A worker that should be invoked at every new slot.
 pub trait SlotWorker<B: BlockT> {
 	/// The type of the future that will be returned when a new slot is
@@ -106,6 +112,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// Whether to force authoring if offline.
 	fn force_authoring(&self) -> bool;
 
+	/// Whether to back off from authoring on top of the given chain head, e.g. because
+	/// finality is lagging. Never backs off by default.
+	fn should_backoff(&self, _chain_head: &B::Header) -> bool {
+		false
+	}
+
 	/// Returns a handle to a `SyncOracle`.
 	fn sync_oracle(&mut self) -> &mut Self::SyncOracle;
 
@@ -154,6 +166,17 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			Some(claim) => claim,
 		};
 
+		if self.should_backoff(&chain_head) {
+			telemetry!(
+				CONSENSUS_DEBUG;
+				"slots.backing_off";
+				"slot" => slot_number,
+				"chain_head" => ?chain_head.hash(),
+			);
+
+			return Box::pin(future::ready(Ok(())));
+		}
+
 		debug!(
 			target: self.logging_target(), "Starting authorship at slot {}; timestamp = {}",
 			slot_number,
@@ -394,10 +417,27 @@ impl<T: Clone> SlotDuration<T> {
 		C: ProvideRuntimeApi,
 		CB: FnOnce(ApiRef<C::Api>, &BlockId<B>) -> ::client::error::Result<T>,
 		T: SlotData + Encode + Decode + Debug,
+	{
+		use sr_primitives::traits::Zero;
+		Self::get_or_compute_at(client, &BlockId::number(Zero::zero()), cb)
+	}
+
+	/// Either fetch the slot duration from disk or compute it from the state
+	/// at the given block, e.g. for a chain which started using the consensus
+	/// engine after genesis.
+	pub fn get_or_compute_at<B: BlockT, C, CB>(
+		client: &C,
+		at: &BlockId<B>,
+		cb: CB,
+	) -> ::client::error::Result<Self> where
+		C: client::backend::AuxStore,
+		C: ProvideRuntimeApi,
+		CB: FnOnce(ApiRef<C::Api>, &BlockId<B>) -> ::client::error::Result<T>,
+		T: SlotData + Encode + Decode + Debug,
 	{
 		match client.get_aux(T::SLOT_KEY)? {
-			Some(v) => <T as codec::Decode>::decode(&mut &v[..])
-				.map(SlotDuration)
+			Some(v) => versioned_aux::decode(&v, SLOT_DURATION_VERSION, versioned_aux::decode_unchanged_since(0))
+				.map(|decoded| SlotDuration(decoded.value))
 				.map_err(|_| {
 					client::error::Error::Backend({
 						error!(target: "slots", "slot duration kept in invalid format");
@@ -405,19 +445,18 @@ impl<T: Clone> SlotDuration<T> {
 					})
 				}),
 			None => {
-				use sr_primitives::traits::Zero;
-				let genesis_slot_duration =
-					cb(client.runtime_api(), &BlockId::number(Zero::zero()))?;
+				let slot_duration = cb(client.runtime_api(), at)?;
 
 				info!(
-					"Loaded block-time = {:?} seconds from genesis on first-launch",
-					genesis_slot_duration
+					"Loaded block-time = {:?} seconds from block {} on first-launch",
+					slot_duration,
+					at,
 				);
 
-				genesis_slot_duration
-					.using_encoded(|s| client.insert_aux(&[(T::SLOT_KEY, &s[..])], &[]))?;
+				let encoded = versioned_aux::encode(SLOT_DURATION_VERSION, &slot_duration);
+				client.insert_aux(&[(T::SLOT_KEY, &encoded[..])], &[])?;
 
-				Ok(SlotDuration(genesis_slot_duration))
+				Ok(SlotDuration(slot_duration))
 			}
 		}
 	}
@@ -69,11 +69,28 @@ const MIN_POSSIBLE_ALLOCATION: u32 = 8;
 // to which it belongs.
 const PREFIX_SIZE: u32 = 8;
 
+/// Statistics about the allocations performed by a heap allocator during a single call.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AllocationStats {
+	/// The number of bytes currently allocated, including the allocation prefixes.
+	pub bytes_allocated: u32,
+	/// The highest number of bytes that were allocated at the same time.
+	pub bytes_allocated_peak: u32,
+	/// The total number of bytes handed out by the allocator, including the allocation
+	/// prefixes. Memory that is freed and allocated again is counted again.
+	pub bytes_allocated_sum: u64,
+	/// The number of allocations performed.
+	pub allocation_count: u32,
+	/// The total size of the heap the allocator operates on.
+	pub heap_size: u32,
+}
+
 pub struct FreeingBumpHeapAllocator {
 	bumper: u32,
 	heads: [u32; N],
 	ptr_offset: u32,
 	total_size: u32,
+	stats: AllocationStats,
 }
 
 /// Create an allocator error.
@@ -97,9 +114,15 @@ impl FreeingBumpHeapAllocator {
 			heads: [0; N],
 			ptr_offset,
 			total_size: 0,
+			stats: AllocationStats::default(),
 		}
 	}
 
+	/// Returns the statistics about the allocations performed so far.
+	pub fn stats(&self) -> AllocationStats {
+		self.stats
+	}
+
 	/// Gets requested number of bytes to allocate and returns a pointer.
 	/// The maximum size which can be allocated at once is 16 MiB.
 	/// There is no minimum size, but whatever size is passed into
@@ -150,6 +173,12 @@ impl FreeingBumpHeapAllocator {
 		self.total_size = self.total_size + item_size + PREFIX_SIZE;
 		trace!(target: "wasm-heap", "Heap size is {} bytes after allocation", self.total_size);
 
+		self.stats.bytes_allocated = self.total_size;
+		self.stats.bytes_allocated_peak = self.stats.bytes_allocated_peak.max(self.total_size);
+		self.stats.bytes_allocated_sum += (item_size + PREFIX_SIZE) as u64;
+		self.stats.allocation_count += 1;
+		self.stats.heap_size = max_heap_size;
+
 		Ok(Pointer::new(self.ptr_offset + ptr))
 	}
 
@@ -179,6 +208,8 @@ impl FreeingBumpHeapAllocator {
 			.ok_or_else(|| error("Unable to subtract from total heap size without overflow"))?;
 		trace!(target: "wasm-heap", "Heap size is {} bytes after deallocation", self.total_size);
 
+		self.stats.bytes_allocated = self.total_size;
+
 		Ok(())
 	}
 
@@ -551,4 +582,23 @@ mod tests {
 		assert_eq!(item_size as u32, MAX_POSSIBLE_ALLOCATION);
 	}
 
+	#[test]
+	fn should_track_allocation_stats() {
+		// given
+		let mut mem = [0u8; PAGE_SIZE as usize];
+		let mut heap = FreeingBumpHeapAllocator::new(0);
+
+		// when
+		let ptr1 = heap.allocate(&mut mem[..], 8).unwrap();
+		let _ = heap.allocate(&mut mem[..], 16).unwrap();
+		heap.deallocate(&mut mem[..], ptr1).unwrap();
+
+		// then
+		let stats = heap.stats();
+		assert_eq!(stats.allocation_count, 2);
+		assert_eq!(stats.bytes_allocated, 16 + PREFIX_SIZE);
+		assert_eq!(stats.bytes_allocated_peak, (8 + PREFIX_SIZE) + (16 + PREFIX_SIZE));
+		assert_eq!(stats.bytes_allocated_sum, ((8 + PREFIX_SIZE) + (16 + PREFIX_SIZE)) as u64);
+		assert_eq!(stats.heap_size, PAGE_SIZE);
+	}
 }
@@ -83,6 +83,9 @@ pub enum Error {
 	/// Execution of a host function failed.
 	#[display(fmt="Host function {} execution failed with: {}", _0, _1)]
 	FunctionExecution(String, String),
+	/// The call ran out of fuel before completing.
+	#[display(fmt="Runtime call ran out of fuel")]
+	OutOfFuel,
 }
 
 impl std::error::Error for Error {
@@ -127,6 +130,8 @@ pub enum WasmError {
 	InvalidHeapPages,
 	/// Instantiation error.
 	Instantiation(String),
+	/// The runtime ran out of fuel while its version was determined.
+	OutOfFuel,
 	/// The compiler does not support the host machine as a target.
 	#[cfg(feature = "wasmtime")]
 	MissingCompilerSupport(&'static str),
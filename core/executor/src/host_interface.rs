@@ -212,6 +212,19 @@ impl_wasm_host_interface! {
 			Ok(runtime_io::set_storage(&key, &value))
 		}
 
+		ext_storage_append(
+			key_data: Pointer<u8>,
+			key_len: WordSize,
+			value_data: Pointer<u8>,
+			value_len: WordSize,
+		) {
+			let key = context.read_memory(key_data, key_len)
+				.map_err(|_| "Invalid attempt to determine key in ext_storage_append")?;
+			let value = context.read_memory(value_data, value_len)
+				.map_err(|_| "Invalid attempt to determine value in ext_storage_append")?;
+			Ok(runtime_io::storage_append(&key, &value))
+		}
+
 		ext_set_child_storage(
 			storage_key_data: Pointer<u8>,
 			storage_key_len: WordSize,
@@ -289,12 +302,39 @@ impl_wasm_host_interface! {
 			Ok(runtime_io::clear_child_prefix(&storage_key, &prefix))
 		}
 
+		ext_storage_start_transaction() {
+			runtime_io::storage_start_transaction();
+			Ok(())
+		}
+
+		ext_storage_rollback_transaction() -> u32 {
+			Ok(if runtime_io::storage_rollback_transaction().is_ok() { 0 } else { 1 })
+		}
+
+		ext_storage_commit_transaction() -> u32 {
+			Ok(if runtime_io::storage_commit_transaction().is_ok() { 0 } else { 1 })
+		}
+
 		ext_kill_child_storage(storage_key_data: Pointer<u8>, storage_key_len: WordSize) {
 			let storage_key = context.read_memory(storage_key_data, storage_key_len)
 				.map_err(|_| "Invalid attempt to determine storage_key in ext_kill_child_storage")?;
 			Ok(runtime_io::kill_child_storage(&storage_key))
 		}
 
+		ext_kill_child_storage_limited(
+			storage_key_data: Pointer<u8>,
+			storage_key_len: WordSize,
+			limit: u32,
+			removed_out: Pointer<u32>,
+		) -> u32 {
+			let storage_key = context.read_memory(storage_key_data, storage_key_len)
+				.map_err(|_| "Invalid attempt to determine storage_key in ext_kill_child_storage_limited")?;
+			let (complete, removed) = runtime_io::kill_child_storage_limited(&storage_key, limit);
+			context.write_primitive(removed_out, removed)
+				.map_err(|_| "Invalid attempt to write removed_out in ext_kill_child_storage_limited")?;
+			Ok(if complete { 1 } else { 0 })
+		}
+
 		ext_get_allocated_storage(
 			key_data: Pointer<u8>,
 			key_len: WordSize,
@@ -1068,6 +1108,23 @@ impl_wasm_host_interface! {
 				}
 			})
 		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		ext_benchmark_current_time() -> u64 {
+			Ok(runtime_io::benchmark_current_time())
+		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		ext_benchmark_commit_db() {
+			runtime_io::benchmark_commit_db();
+			Ok(())
+		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		ext_benchmark_wipe_db() {
+			runtime_io::benchmark_wipe_db();
+			Ok(())
+		}
 	}
 }
 
@@ -16,6 +16,7 @@
 
 mod sandbox;
 
+use assert_matches::assert_matches;
 use codec::{Encode, Decode};
 use hex_literal::hex;
 use primitives::{
@@ -439,3 +440,22 @@ fn offchain_http_should_work(wasm_method: WasmExecutionMethod) {
 	);
 }
 
+
+#[test]
+fn metered_calls_should_respect_fuel_limit() {
+	let mut ext = TestExternalities::default();
+	let mut ext = ext.ext();
+	let mut runtime = crate::wasm_runtime::create_metered_wasm_runtime_with_code(
+		8,
+		&WASM_BINARY[..],
+	).unwrap();
+	let data = b"Hello world!".to_vec().encode();
+
+	let output = crate::with_fuel_limit(u64::max_value(), || {
+		runtime.call(&mut ext, "test_blake2_256", &data)
+	});
+	assert_eq!(output.unwrap(), blake2_256(&b"Hello world!"[..]).to_vec().encode());
+
+	let output = crate::with_fuel_limit(1, || runtime.call(&mut ext, "test_blake2_256", &data));
+	assert_matches!(output, Err(crate::error::Error::OutOfFuel));
+}
@@ -36,6 +36,7 @@ mod wasmi_execution;
 mod native_executor;
 mod sandbox;
 mod allocator;
+mod metering;
 mod host_interface;
 mod wasm_runtime;
 #[cfg(feature = "wasmtime")]
@@ -45,14 +46,18 @@ mod integration_tests;
 
 pub mod error;
 pub use wasmi;
-pub use native_executor::{with_native_environment, NativeExecutor, NativeExecutionDispatch};
+pub use native_executor::{
+	with_native_environment, take_last_allocation_stats, NativeExecutor, NativeExecutionDispatch,
+};
+pub use allocator::AllocationStats;
+pub use metering::{with_fuel_limit, with_fuel_limit_and_usage, fuel_limit};
 pub use runtime_version::{RuntimeVersion, NativeVersion};
 pub use codec::Codec;
 #[doc(hidden)]
 pub use primitives::traits::Externalities;
 #[doc(hidden)]
 pub use wasm_interface;
-pub use wasm_runtime::WasmExecutionMethod;
+pub use wasm_runtime::{WasmExecutionMethod, runtime_exports};
 
 /// Call the given `function` in the given wasm `code`.
 ///
@@ -112,4 +117,14 @@ mod tests {
 		).unwrap();
 		assert_eq!(res, vec![0u8; 0]);
 	}
+
+	#[test]
+	fn runtime_exports_are_listed() {
+		let exports = runtime_exports(&WASM_BINARY).unwrap();
+		assert!(exports.contains("test_empty_return"));
+		// only functions are listed.
+		assert!(!exports.contains("memory"));
+		assert!(!exports.contains("__heap_base"));
+		assert!(runtime_exports(&[0, 1, 2]).is_err());
+	}
 }
@@ -0,0 +1,146 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fuel metering of runtime calls.
+//!
+//! While a fuel limit is set for the current thread (see [`with_fuel_limit`]), the
+//! `NativeExecutor` always executes the Wasm runtime, using an interpreted instance whose code
+//! was instrumented with gas metering. Each instrumented block charges its cost against the fuel
+//! of the call, and the call is aborted with [`Error::OutOfFuel`](crate::error::Error::OutOfFuel)
+//! as soon as the fuel is exhausted.
+//!
+//! This is meant for calls made on behalf of untrusted parties, e.g. `state_call` on public RPC
+//! nodes, which must not be able to keep the node busy with unbounded runtime calls.
+
+use std::cell::Cell;
+use parity_wasm::elements::{deserialize_buffer, serialize, Module as RawModule};
+use pwasm_utils::rules;
+use crate::error::WasmError;
+
+/// Name of the host function called by instrumented code to charge fuel.
+pub(crate) const GAS_FUNCTION_NAME: &str = "gas";
+
+/// Fuel available to the `Core_version` call made when a metered runtime is instantiated.
+///
+/// The version is determined once per runtime on behalf of every later call, so it must not
+/// depend on the fuel limit of the call that happens to instantiate the runtime.
+pub(crate) const VERSION_PROBE_FUEL: u64 = 100_000_000;
+
+thread_local! {
+	/// Fuel available to each runtime call executed on this thread, if metering is enabled.
+	static FUEL_LIMIT: Cell<Option<u64>> = Cell::new(None);
+
+	/// Fuel consumed by the metered runtime calls executed on this thread.
+	static FUEL_CONSUMED: Cell<u64> = Cell::new(0);
+}
+
+/// Restores the previous fuel limit when dropped.
+struct RestoreFuelLimit(Option<u64>);
+
+impl Drop for RestoreFuelLimit {
+	fn drop(&mut self) {
+		FUEL_LIMIT.with(|limit| limit.set(self.0));
+	}
+}
+
+/// Run `f` with every runtime call made by a `NativeExecutor` on this thread limited to `fuel`.
+///
+/// Calls exceeding the limit fail with `Error::OutOfFuel`. Metered calls are always executed in
+/// the interpreted Wasm runtime, whatever the requested execution strategy is.
+pub fn with_fuel_limit<R>(fuel: u64, f: impl FnOnce() -> R) -> R {
+	let previous = FUEL_LIMIT.with(|limit| limit.replace(Some(fuel)));
+	let _restore = RestoreFuelLimit(previous);
+	f()
+}
+
+/// Like [`with_fuel_limit`], but also returns the total fuel consumed by the calls made by `f`.
+pub fn with_fuel_limit_and_usage<R>(fuel: u64, f: impl FnOnce() -> R) -> (R, u64) {
+	let previous = FUEL_CONSUMED.with(|consumed| consumed.replace(0));
+	let result = with_fuel_limit(fuel, f);
+	let consumed = FUEL_CONSUMED.with(|consumed| consumed.replace(previous));
+	note_fuel_consumed(consumed);
+	(result, consumed)
+}
+
+/// Run `f` with its own fuel limit of `fuel`.
+///
+/// Unlike [`with_fuel_limit_and_usage`], the fuel consumed by `f` is not accounted to the
+/// enclosing calls.
+pub(crate) fn with_separate_fuel_limit<R>(fuel: u64, f: impl FnOnce() -> R) -> R {
+	let previous = FUEL_CONSUMED.with(|consumed| consumed.replace(0));
+	let result = with_fuel_limit(fuel, f);
+	FUEL_CONSUMED.with(|consumed| consumed.set(previous));
+	result
+}
+
+/// Returns the fuel limit of runtime calls made on this thread, if any.
+pub fn fuel_limit() -> Option<u64> {
+	FUEL_LIMIT.with(|limit| limit.get())
+}
+
+/// Account for fuel consumed by a metered call.
+pub(crate) fn note_fuel_consumed(amount: u64) {
+	FUEL_CONSUMED.with(|consumed| consumed.set(consumed.get().saturating_add(amount)));
+}
+
+/// Instrument the given Wasm `code` so that it calls the `gas` host function with the cost of
+/// every block before executing it.
+pub(crate) fn inject_gas_metering(code: &[u8]) -> Result<Vec<u8>, WasmError> {
+	let module: RawModule = deserialize_buffer(code)
+		.map_err(|_| WasmError::CantDeserializeWasm)?;
+	let module = pwasm_utils::inject_gas_counter(module, &rules::Set::default())
+		.map_err(|_| WasmError::Instantiation("gas metering instrumentation failed".into()))?;
+	serialize(module).map_err(|_| WasmError::InvalidModule)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fuel_limit_is_scoped() {
+		assert_eq!(fuel_limit(), None);
+		with_fuel_limit(10, || {
+			assert_eq!(fuel_limit(), Some(10));
+			with_fuel_limit(5, || assert_eq!(fuel_limit(), Some(5)));
+			assert_eq!(fuel_limit(), Some(10));
+		});
+		assert_eq!(fuel_limit(), None);
+	}
+
+	#[test]
+	fn fuel_usage_includes_nested_calls() {
+		let ((), outer) = with_fuel_limit_and_usage(100, || {
+			note_fuel_consumed(3);
+			let ((), inner) = with_fuel_limit_and_usage(10, || note_fuel_consumed(4));
+			assert_eq!(inner, 4);
+		});
+		assert_eq!(outer, 7);
+	}
+
+	#[test]
+	fn separate_fuel_limit_is_not_accounted() {
+		let ((), usage) = with_fuel_limit_and_usage(10, || {
+			note_fuel_consumed(3);
+			with_separate_fuel_limit(100, || {
+				assert_eq!(fuel_limit(), Some(100));
+				note_fuel_consumed(50);
+			});
+			assert_eq!(fuel_limit(), Some(10));
+		});
+		assert_eq!(usage, 3);
+	}
+}
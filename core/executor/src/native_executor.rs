@@ -14,10 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{result, cell::RefCell, panic::{UnwindSafe, AssertUnwindSafe}};
+use std::{result, cell::RefCell, collections::BTreeSet, panic::{UnwindSafe, AssertUnwindSafe}};
+use crate::allocator::AllocationStats;
 use crate::error::{Error, Result};
 use crate::wasm_runtime::{RuntimesCache, WasmExecutionMethod, WasmRuntime};
 use crate::RuntimeInfo;
+use crate::metering;
 use runtime_version::{NativeVersion, RuntimeVersion};
 use codec::{Decode, Encode};
 use primitives::{NativeOrEncoded, traits::{CodeExecutor, Externalities}};
@@ -25,6 +27,9 @@ use log::{trace, warn};
 
 thread_local! {
 	static RUNTIMES_CACHE: RefCell<RuntimesCache> = RefCell::new(RuntimesCache::new());
+
+	/// Allocation statistics of the last Wasm call executed on this thread.
+	static LAST_ALLOCATION_STATS: RefCell<Option<AllocationStats>> = RefCell::new(None);
 }
 
 /// Default num of pages for the heap
@@ -38,6 +43,33 @@ pub(crate) fn safe_call<F, U>(f: F) -> Result<U>
 	std::panic::catch_unwind(f).map_err(|_| Error::Runtime)
 }
 
+/// Takes the allocation statistics of the last call executed by a `NativeExecutor` on this thread.
+///
+/// Returns `None` if the last call was dispatched to the native runtime, if it failed before the
+/// allocator was set up or if the statistics were already taken.
+pub fn take_last_allocation_stats() -> Option<AllocationStats> {
+	LAST_ALLOCATION_STATS.with(|stats| stats.borrow_mut().take())
+}
+
+/// Call `method` in the given Wasm `runtime` and record the allocation statistics of the call.
+///
+/// Calls to methods missing from the `exports` of the runtime fail with `Error::MethodNotFound`
+/// without entering the runtime.
+fn call_in_wasm_runtime(
+	runtime: &mut dyn WasmRuntime,
+	exports: &BTreeSet<String>,
+	ext: &mut dyn Externalities,
+	method: &str,
+	data: &[u8],
+) -> Result<Vec<u8>> {
+	if !exports.contains(method) {
+		return Err(Error::MethodNotFound(method.to_owned()))
+	}
+	let (result, stats) = runtime.call_with_allocation_stats(ext, method, data);
+	LAST_ALLOCATION_STATS.with(|last| *last.borrow_mut() = stats);
+	result
+}
+
 /// Set up the externalities and safe calling environment to execute calls to a native runtime.
 ///
 /// If the inner closure panics, it will be caught and return an error.
@@ -94,6 +126,9 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 
 	/// Execute the given closure `f` with the latest runtime (based on the `CODE` key in `ext`).
 	///
+	/// If a fuel limit is set for the current thread, the runtime is instrumented with fuel
+	/// metering.
+	///
 	/// The closure `f` is expected to return `Err(_)` when there happened a `panic!` in native code
 	/// while executing the runtime in Wasm. If a `panic!` occurred, the runtime is invalidated to
 	/// prevent any poisoned state. Native runtime execution does not need to report back
@@ -111,24 +146,27 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 		f: impl for<'a> FnOnce(
 			AssertUnwindSafe<&'a mut (dyn WasmRuntime + 'static)>,
 			&'a RuntimeVersion,
+			&'a BTreeSet<String>,
 			AssertUnwindSafe<&'a mut E>,
 		) -> Result<Result<R>>,
 	) -> Result<R> where E: Externalities {
 		RUNTIMES_CACHE.with(|cache| {
 			let mut cache = cache.borrow_mut();
-			let (runtime, version, code_hash) = cache.fetch_runtime(
+			let metered = metering::fuel_limit().is_some();
+			let (runtime, version, exports, code_hash) = cache.fetch_runtime(
 				ext,
 				self.fallback_method,
 				self.default_heap_pages,
+				metered,
 			)?;
 
 			let runtime = AssertUnwindSafe(runtime);
 			let ext = AssertUnwindSafe(ext);
 
-			match f(runtime, version, ext) {
+			match f(runtime, version, exports, ext) {
 				Ok(res) => res,
 				Err(e) => {
-					cache.invalidate_runtime(self.fallback_method, code_hash);
+					cache.invalidate_runtime(self.fallback_method, code_hash, metered);
 					Err(e)
 				}
 			}
@@ -156,7 +194,7 @@ impl<D: NativeExecutionDispatch> RuntimeInfo for NativeExecutor<D> {
 		&self,
 		ext: &mut E,
 	) -> Option<RuntimeVersion> {
-		match self.with_runtime(ext, |_runtime, version, _ext| Ok(Ok(version.clone()))) {
+		match self.with_runtime(ext, |_runtime, version, _exports, _ext| Ok(Ok(version.clone()))) {
 			Ok(version) => Some(version),
 			Err(e) => {
 				warn!(target: "executor", "Failed to fetch runtime: {:?}", e);
@@ -182,8 +220,11 @@ impl<D: NativeExecutionDispatch> CodeExecutor for NativeExecutor<D> {
 		use_native: bool,
 		native_call: Option<NC>,
 	) -> (Result<NativeOrEncoded<R>>, bool){
+		LAST_ALLOCATION_STATS.with(|last| *last.borrow_mut() = None);
+		// Metered calls must not escape their fuel limit by running natively.
+		let use_native = use_native && metering::fuel_limit().is_none();
 		let mut used_native = false;
-		let result = self.with_runtime(ext, |mut runtime, onchain_version, mut ext| {
+		let result = self.with_runtime(ext, |mut runtime, onchain_version, exports, mut ext| {
 			match (
 				use_native,
 				onchain_version.can_call_with(&self.native_version.runtime_version),
@@ -198,12 +239,14 @@ impl<D: NativeExecutionDispatch> CodeExecutor for NativeExecutor<D> {
 					);
 
 					safe_call(
-						move || runtime.call(&mut **ext, method, data).map(NativeOrEncoded::Encoded)
+						move || call_in_wasm_runtime(&mut **runtime, exports, &mut **ext, method, data)
+							.map(NativeOrEncoded::Encoded)
 					)
 				}
 				(false, _, _) => {
 					safe_call(
-						move || runtime.call(&mut **ext, method, data).map(NativeOrEncoded::Encoded)
+						move || call_in_wasm_runtime(&mut **runtime, exports, &mut **ext, method, data)
+							.map(NativeOrEncoded::Encoded)
 					)
 				},
 				(true, true, Some(call)) => {
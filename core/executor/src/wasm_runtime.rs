@@ -20,14 +20,16 @@
 //! components of the runtime that are expensive to initialize.
 
 use crate::error::{Error, WasmError};
-use crate::wasmi_execution;
+use crate::allocator::AllocationStats;
+use crate::{metering, wasmi_execution};
 #[cfg(feature = "wasmtime")]
 use crate::wasmtime;
 use log::{trace, warn};
 use codec::Decode;
+use parity_wasm::elements::{deserialize_buffer, Internal, Module as RawModule};
 use primitives::{storage::well_known_keys, traits::Externalities, H256};
 use runtime_version::RuntimeVersion;
-use std::{collections::hash_map::{Entry, HashMap}, panic::AssertUnwindSafe};
+use std::{collections::{BTreeSet, HashMap}, panic::AssertUnwindSafe};
 
 /// The Substrate Wasm runtime.
 pub trait WasmRuntime {
@@ -39,7 +41,22 @@ pub trait WasmRuntime {
 
 	/// Call a method in the Substrate runtime by name. Returns the encoded result on success.
 	fn call(&mut self, ext: &mut dyn Externalities, method: &str, data: &[u8])
-		-> Result<Vec<u8>, Error>;
+		-> Result<Vec<u8>, Error>
+	{
+		self.call_with_allocation_stats(ext, method, data).0
+	}
+
+	/// Call a method in the Substrate runtime by name.
+	///
+	/// Returns the encoded result on success, along with the statistics of the heap allocator used
+	/// during the call. The statistics are also returned when the call fails (e.g. because the
+	/// allocator ran out of space), unless the call failed before the allocator was set up.
+	fn call_with_allocation_stats(
+		&mut self,
+		ext: &mut dyn Externalities,
+		method: &str,
+		data: &[u8],
+	) -> (Result<Vec<u8>, Error>, Option<AllocationStats>);
 }
 
 /// Specification of different methods of executing the runtime Wasm code.
@@ -52,11 +69,13 @@ pub enum WasmExecutionMethod {
 	Compiled,
 }
 
-/// A Wasm runtime object along with its cached runtime version.
+/// A Wasm runtime object along with its cached runtime version and exports.
 struct VersionedRuntime {
 	runtime: Box<dyn WasmRuntime>,
 	/// Runtime version according to `Core_version`.
 	version: RuntimeVersion,
+	/// Names of the functions exported by the runtime.
+	exports: BTreeSet<String>,
 }
 
 /// Cache for the runtimes.
@@ -74,8 +93,9 @@ struct VersionedRuntime {
 pub struct RuntimesCache {
 	/// A cache of runtime instances along with metadata, ready to be reused.
 	///
-	/// Instances are keyed by the Wasm execution method and the hash of their code.
-	instances: HashMap<(WasmExecutionMethod, [u8; 32]), Result<VersionedRuntime, WasmError>>,
+	/// Instances are keyed by the Wasm execution method, whether they are instrumented with fuel
+	/// metering and the hash of their code.
+	instances: HashMap<(WasmExecutionMethod, bool, [u8; 32]), Result<VersionedRuntime, WasmError>>,
 }
 
 impl RuntimesCache {
@@ -102,10 +122,14 @@ impl RuntimesCache {
 	///
 	/// `default_heap_pages` - Number of 64KB pages to allocate for Wasm execution.
 	///
+	/// `metered` - Whether to fetch an instance instrumented with fuel metering. Metered
+	/// instances are always interpreted.
+	///
 	/// # Return value
 	///
-	/// If no error occurred a tuple `(&mut WasmRuntime, H256)` is
-	/// returned. `H256` is the hash of the runtime code.
+	/// If no error occurred a tuple `(&mut WasmRuntime, &RuntimeVersion, &BTreeSet<String>, H256)`
+	/// is returned. The set holds the names of the functions exported by the runtime, and `H256`
+	/// is the hash of the runtime code.
 	///
 	/// In case of failure one of two errors can be returned:
 	///
@@ -118,7 +142,8 @@ impl RuntimesCache {
 		ext: &mut E,
 		wasm_method: WasmExecutionMethod,
 		default_heap_pages: u64,
-	) -> Result<(&mut (dyn WasmRuntime + 'static), &RuntimeVersion, H256), Error> {
+		metered: bool,
+	) -> Result<(&mut (dyn WasmRuntime + 'static), &RuntimeVersion, &BTreeSet<String>, H256), Error> {
 		let code_hash = ext
 			.original_storage_hash(well_known_keys::CODE)
 			.ok_or(Error::InvalidCode("`CODE` not found in storage.".into()))?;
@@ -128,35 +153,42 @@ impl RuntimesCache {
 			.and_then(|pages| u64::decode(&mut &pages[..]).ok())
 			.unwrap_or(default_heap_pages);
 
-		let result = match self.instances.entry((wasm_method, code_hash.into())) {
-			Entry::Occupied(o) => {
-				let result = o.into_mut();
-				if let Ok(ref mut cached_runtime) = result {
-					if !cached_runtime.runtime.update_heap_pages(heap_pages) {
-						trace!(
-							target: "runtimes_cache",
-							"heap_pages were changed. Reinstantiating the instance",
-						);
-						*result = create_versioned_wasm_runtime(ext, wasm_method, heap_pages);
-						if let Err(ref err) = result {
-							warn!(target: "runtimes_cache", "cannot create a runtime: {:?}", err);
-						}
-					}
+		let key = (wasm_method, metered, code_hash.into());
+		let instantiate = match self.instances.get_mut(&key) {
+			Some(Ok(cached_runtime)) => {
+				let reinstantiate = !cached_runtime.runtime.update_heap_pages(heap_pages);
+				if reinstantiate {
+					trace!(
+						target: "runtimes_cache",
+						"heap_pages were changed. Reinstantiating the instance",
+					);
 				}
-				result
+				reinstantiate
 			},
-			Entry::Vacant(v) => {
+			Some(Err(_)) => false,
+			None => {
 				trace!(target: "runtimes_cache", "no instance found in cache, creating now.");
-				let result = create_versioned_wasm_runtime(ext, wasm_method, heap_pages);
-				if let Err(ref err) = result {
-					warn!(target: "runtimes_cache", "cannot create a runtime: {:?}", err);
-				}
-				v.insert(result)
-			}
+				true
+			},
 		};
 
-		result.as_mut()
-			.map(|entry| (entry.runtime.as_mut(), &entry.version, code_hash))
+		if instantiate {
+			let result = create_versioned_wasm_runtime(ext, wasm_method, heap_pages, metered);
+			if let Err(ref err) = result {
+				warn!(target: "runtimes_cache", "cannot create a runtime: {:?}", err);
+			}
+			// Running out of fuel is not a property of the runtime code, so it is not cached.
+			if let Err(WasmError::OutOfFuel) = result {
+				self.instances.remove(&key);
+				return Err(Error::OutOfFuel);
+			}
+			self.instances.insert(key, result);
+		}
+
+		self.instances.get_mut(&key)
+			.expect("the instance is inserted above if it is missing; qed")
+			.as_mut()
+			.map(|entry| (entry.runtime.as_mut(), &entry.version, &entry.exports, code_hash))
 			.map_err(|ref e| Error::InvalidCode(format!("{:?}", e)))
 	}
 
@@ -169,12 +201,31 @@ impl RuntimesCache {
 		&mut self,
 		wasm_method: WasmExecutionMethod,
 		code_hash: H256,
+		metered: bool,
 	) {
 		// Just remove the instance, it will be re-created the next time it is requested.
-		self.instances.remove(&(wasm_method, code_hash.into()));
+		self.instances.remove(&(wasm_method, metered, code_hash.into()));
 	}
 }
 
+/// Names of the functions exported by the given Wasm `code`, i.e. of its entry points.
+pub fn runtime_exports(code: &[u8]) -> Result<BTreeSet<String>, WasmError> {
+	let module: RawModule = deserialize_buffer(code)
+		.map_err(|_| WasmError::CantDeserializeWasm)?;
+	let exports = module
+		.export_section()
+		.map(|section| section.entries())
+		.unwrap_or(&[])
+		.iter()
+		.filter(|entry| match entry.internal() {
+			Internal::Function(_) => true,
+			_ => false,
+		})
+		.map(|entry| entry.field().to_owned())
+		.collect();
+	Ok(exports)
+}
+
 /// Create a wasm runtime with the given `code`.
 pub fn create_wasm_runtime_with_code(
 	wasm_method: WasmExecutionMethod,
@@ -192,15 +243,36 @@ pub fn create_wasm_runtime_with_code(
 	}
 }
 
+/// Create a wasm runtime with the given `code`, instrumented with fuel metering.
+///
+/// Metered runtimes are always interpreted.
+pub fn create_metered_wasm_runtime_with_code(
+	heap_pages: u64,
+	code: &[u8],
+) -> Result<Box<dyn WasmRuntime>, WasmError> {
+	wasmi_execution::create_metered_instance(code, heap_pages)
+		.map(|runtime| -> Box<dyn WasmRuntime> { Box::new(runtime) })
+}
+
 fn create_versioned_wasm_runtime<E: Externalities>(
 	ext: &mut E,
 	wasm_method: WasmExecutionMethod,
 	heap_pages: u64,
+	metered: bool,
 ) -> Result<VersionedRuntime, WasmError> {
 	let code = ext
 		.original_storage(well_known_keys::CODE)
 		.ok_or(WasmError::CodeNotFound)?;
-	let mut runtime = create_wasm_runtime_with_code(wasm_method, heap_pages, &code)?;
+	let exports = runtime_exports(&code)?;
+	let mut runtime = if metered {
+		create_metered_wasm_runtime_with_code(heap_pages, &code)?
+	} else {
+		create_wasm_runtime_with_code(wasm_method, heap_pages, &code)?
+	};
+
+	if !exports.contains("Core_version") {
+		return Err(WasmError::Instantiation("the runtime doesn't export \"Core_version\"".into()))
+	}
 
 	// Call to determine runtime version.
 	let version_result = {
@@ -210,17 +282,26 @@ fn create_versioned_wasm_runtime<E: Externalities>(
 		// The following unwind safety assertion is OK because if the method call panics, the
 		// runtime will be dropped.
 		let mut runtime = AssertUnwindSafe(runtime.as_mut());
-		crate::native_executor::safe_call(
-			move || runtime.call(&mut **ext, "Core_version", &[])
-		).map_err(|_| WasmError::Instantiation("panic in call to get runtime version".into()))?
+		let call = move || runtime.call(&mut **ext, "Core_version", &[]);
+		let result = if metered {
+			crate::native_executor::safe_call(
+				move || metering::with_separate_fuel_limit(metering::VERSION_PROBE_FUEL, call)
+			)
+		} else {
+			crate::native_executor::safe_call(call)
+		};
+		result.map_err(|_| WasmError::Instantiation("panic in call to get runtime version".into()))?
 	};
-	let encoded_version = version_result
-		.map_err(|e| WasmError::Instantiation(format!("failed to call \"Core_version\": {}", e)))?;
+	let encoded_version = version_result.map_err(|e| match e {
+		Error::OutOfFuel => WasmError::OutOfFuel,
+		e => WasmError::Instantiation(format!("failed to call \"Core_version\": {}", e)),
+	})?;
 	let version = RuntimeVersion::decode(&mut encoded_version.as_slice())
 		.map_err(|_| WasmError::Instantiation("failed to decode \"Core_version\" result".into()))?;
 
 	Ok(VersionedRuntime {
 		runtime,
 		version,
+		exports,
 	})
 }
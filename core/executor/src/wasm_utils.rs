@@ -52,6 +52,7 @@ macro_rules! gen_functions {
 	(@INTERNAL
 		{ $( $generated:tt )* }
 		$context:ident,
+		$( #[$attr:meta] )*
 		$name:ident ( $( $names:ident: $params:ty ),* ) $( -> $returns:ty )? { $( $body:tt )* }
 		$( $tail:tt )*
 	) => (
@@ -59,6 +60,7 @@ macro_rules! gen_functions {
 			@INTERNAL
 			{
 				$( $generated )*
+				$( #[$attr] )*
 				{
 					struct $name;
 
@@ -157,6 +159,7 @@ macro_rules! impl_wasm_host_interface {
 	(
 		impl $interface_name:ident where $context:ident {
 			$(
+				$( #[$attr:meta] )*
 				$name:ident($( $names:ident : $params:ty ),* $(,)? ) $( -> $returns:ty )?
 				{ $( $body:tt )* }
 			)*
@@ -167,7 +170,10 @@ macro_rules! impl_wasm_host_interface {
 			fn functions() -> &'static [&'static dyn $crate::wasm_interface::Function] {
 				gen_functions!(
 					$context,
-					$( $name( $( $names: $params ),* ) $( -> $returns )? { $( $body )* } )*
+					$(
+						$( #[$attr] )*
+						$name( $( $names: $params ),* ) $( -> $returns )? { $( $body )* }
+					)*
 				)
 			}
 		}
@@ -26,20 +26,29 @@ use codec::{Encode, Decode};
 use primitives::{sandbox as sandbox_primitives, traits::Externalities};
 use crate::host_interface::SubstrateExternals;
 use crate::sandbox;
-use crate::allocator;
+use crate::allocator::{self, AllocationStats};
 use crate::wasm_utils::interpret_runtime_api_result;
 use crate::wasm_runtime::WasmRuntime;
+use crate::metering;
 use log::trace;
 use parity_wasm::elements::{deserialize_buffer, DataSegment, Instruction, Module as RawModule};
 use wasm_interface::{
 	FunctionContext, HostFunctions, Pointer, WordSize, Sandbox, MemoryId, Result as WResult,
 };
 
+/// Index of the host function charging fuel, distinct from the index of any `SubstrateExternals`
+/// function.
+const GAS_FUNCTION_INDEX: usize = usize::max_value();
+
 struct FunctionExecutor {
 	sandbox_store: sandbox::Store<wasmi::FuncRef>,
 	heap: allocator::FreeingBumpHeapAllocator,
 	memory: MemoryRef,
 	table: Option<TableRef>,
+	/// Fuel left to the call, `None` if the call is not limited.
+	fuel: Option<u64>,
+	/// Whether the call was aborted because it ran out of fuel.
+	out_of_fuel: bool,
 }
 
 impl FunctionExecutor {
@@ -49,8 +58,30 @@ impl FunctionExecutor {
 			heap: allocator::FreeingBumpHeapAllocator::new(heap_base),
 			memory: m,
 			table: t,
+			fuel: metering::fuel_limit(),
+			out_of_fuel: false,
 		})
 	}
+
+	/// Charge the fuel requested by instrumented code, trapping if there is not enough left.
+	fn charge_fuel(&mut self, args: wasmi::RuntimeArgs)
+		-> Result<Option<wasmi::RuntimeValue>, wasmi::Trap>
+	{
+		let amount: u32 = args.nth_checked(0)?;
+		if let Some(fuel) = self.fuel.as_mut() {
+			match fuel.checked_sub(amount as u64) {
+				Some(left) => {
+					*fuel = left;
+					metering::note_fuel_consumed(amount as u64);
+				},
+				None => {
+					self.out_of_fuel = true;
+					return Err(Error::OutOfFuel.into());
+				}
+			}
+		}
+		Ok(None)
+	}
 }
 
 impl sandbox::SandboxCapabilities for FunctionExecutor {
@@ -268,6 +299,17 @@ impl FunctionExecutor {
 			fn resolve_func(&self, name: &str, signature: &wasmi::Signature)
 				-> std::result::Result<wasmi::FuncRef, wasmi::Error>
 			{
+				if name == metering::GAS_FUNCTION_NAME {
+					let expected = wasmi::Signature::new(&[wasmi::ValueType::I32][..], None);
+					return if signature == &expected {
+						Ok(wasmi::FuncInstance::alloc_host(expected, GAS_FUNCTION_INDEX))
+					} else {
+						Err(wasmi::Error::Instantiation(
+							format!("Invalid signature for function `{}`", name),
+						))
+					};
+				}
+
 				let signature = wasm_interface::Signature::from(signature);
 
 				if let Some((index, func)) = SubstrateExternals::functions().iter()
@@ -301,6 +343,10 @@ impl wasmi::Externals for FunctionExecutor {
 	fn invoke_index(&mut self, index: usize, args: wasmi::RuntimeArgs)
 		-> Result<Option<wasmi::RuntimeValue>, wasmi::Trap>
 	{
+		if index == GAS_FUNCTION_INDEX {
+			return self.charge_fuel(args);
+		}
+
 		let mut args = args.as_ref().iter().copied().map(Into::into);
 		let function = SubstrateExternals::functions().get(index).ok_or_else(||
 			Error::from(
@@ -341,12 +387,14 @@ fn get_heap_base(module: &ModuleRef) -> Result<u32, Error> {
 }
 
 /// Call a given method in the given wasm-module runtime.
+///
+/// Returns the result of the call along with the statistics of the allocator used by the call.
 fn call_in_wasm_module(
 	ext: &mut dyn Externalities,
 	module_instance: &ModuleRef,
 	method: &str,
 	data: &[u8],
-) -> Result<Vec<u8>, Error> {
+) -> (Result<Vec<u8>, Error>, Option<AllocationStats>) {
 	call_in_wasm_module_with_custom_signature(
 		ext,
 		module_instance,
@@ -367,6 +415,9 @@ fn call_in_wasm_module(
 }
 
 /// Call a given method in the given wasm-module runtime.
+///
+/// Returns the result of the call along with the statistics of the allocator used by the call.
+/// The statistics are `None` if the call failed before the allocator could be set up.
 fn call_in_wasm_module_with_custom_signature<
 	F: FnOnce(&mut dyn FnMut(&[u8]) -> Result<u32, Error>) -> Result<Vec<RuntimeValue>, Error>,
 	FR: FnOnce(Option<RuntimeValue>, &MemoryRef) -> Result<Option<R>, Error>,
@@ -377,21 +428,52 @@ fn call_in_wasm_module_with_custom_signature<
 	method: &str,
 	create_parameters: F,
 	filter_result: FR,
-) -> Result<R, Error> {
+) -> (Result<R, Error>, Option<AllocationStats>) {
 	// extract a reference to a linear memory, optional reference to a table
 	// and then initialize FunctionExecutor.
-	let memory = get_mem_instance(module_instance)?;
+	let memory = match get_mem_instance(module_instance) {
+		Ok(memory) => memory,
+		Err(e) => return (Err(e), None),
+	};
 	let table: Option<TableRef> = module_instance
 		.export_by_name("__indirect_function_table")
 		.and_then(|e| e.as_table().cloned());
-	let heap_base = get_heap_base(module_instance)?;
+	let heap_base = match get_heap_base(module_instance) {
+		Ok(heap_base) => heap_base,
+		Err(e) => return (Err(e), None),
+	};
 
-	let mut fec = FunctionExecutor::new(
-		memory.clone(),
-		heap_base,
-		table,
-	)?;
+	let mut fec = match FunctionExecutor::new(memory.clone(), heap_base, table) {
+		Ok(fec) => fec,
+		Err(e) => return (Err(e), None),
+	};
+
+	let result = invoke_with_executor(
+		ext,
+		module_instance,
+		method,
+		&memory,
+		&mut fec,
+		create_parameters,
+		filter_result,
+	);
+	(result, Some(fec.heap.stats()))
+}
 
+/// Invoke the given method with an already initialized `FunctionExecutor`.
+fn invoke_with_executor<
+	F: FnOnce(&mut dyn FnMut(&[u8]) -> Result<u32, Error>) -> Result<Vec<RuntimeValue>, Error>,
+	FR: FnOnce(Option<RuntimeValue>, &MemoryRef) -> Result<Option<R>, Error>,
+	R,
+>(
+	ext: &mut dyn Externalities,
+	module_instance: &ModuleRef,
+	method: &str,
+	memory: &MemoryRef,
+	fec: &mut FunctionExecutor,
+	create_parameters: F,
+	filter_result: FR,
+) -> Result<R, Error> {
 	let parameters = create_parameters(&mut |data: &[u8]| {
 		let offset = fec.allocate_memory(data.len() as u32)?;
 		fec.write_memory(offset, data).map(|_| offset.into()).map_err(Into::into)
@@ -399,14 +481,15 @@ fn call_in_wasm_module_with_custom_signature<
 
 	let result = externalities::set_and_run_with_externalities(
 		ext,
-		|| module_instance.invoke_export(method, &parameters, &mut fec),
+		|| module_instance.invoke_export(method, &parameters, fec),
 	);
 
 	match result {
-		Ok(val) => match filter_result(val, &memory)? {
+		Ok(val) => match filter_result(val, memory)? {
 			Some(val) => Ok(val),
 			None => Err(Error::InvalidReturn),
 		},
+		Err(_) if fec.out_of_fuel => Err(Error::OutOfFuel),
 		Err(e) => {
 			trace!(
 				target: "wasm-executor",
@@ -583,15 +666,26 @@ impl WasmRuntime for WasmiRuntime {
 		self.state_snapshot.heap_pages == heap_pages
 	}
 
-	fn call(&mut self, ext: &mut dyn Externalities, method: &str, data: &[u8])
-			-> Result<Vec<u8>, Error>
-	{
+	fn call_with_allocation_stats(
+		&mut self,
+		ext: &mut dyn Externalities,
+		method: &str,
+		data: &[u8],
+	) -> (Result<Vec<u8>, Error>, Option<AllocationStats>) {
 		self.with(|module| {
 			call_in_wasm_module(ext, module, method, data)
 		})
 	}
 }
 
+/// Create a new runtime instance from the given `code`, instrumented with gas metering.
+///
+/// Calls into the instance are limited by the fuel limit of the calling thread.
+pub fn create_metered_instance(code: &[u8], heap_pages: u64) -> Result<WasmiRuntime, WasmError> {
+	let code = metering::inject_gas_metering(code)?;
+	create_instance(&code, heap_pages)
+}
+
 pub fn create_instance(code: &[u8], heap_pages: u64) -> Result<WasmiRuntime, WasmError> {
 	let module = Module::from_buffer(&code).map_err(|_| WasmError::InvalidModule)?;
 
@@ -16,6 +16,7 @@
 
 //! Defines the compiled Wasm runtime that uses Wasmtime internally.
 
+use crate::allocator::AllocationStats;
 use crate::error::{Error, Result, WasmError};
 use crate::host_interface::SubstrateExternals;
 use crate::wasm_runtime::WasmRuntime;
@@ -62,15 +63,23 @@ impl WasmRuntime for WasmtimeRuntime {
 		}
 	}
 
-	fn call(&mut self, ext: &mut dyn Externalities, method: &str, data: &[u8]) -> Result<Vec<u8>> {
-		call_method(
+	fn call_with_allocation_stats(
+		&mut self,
+		ext: &mut dyn Externalities,
+		method: &str,
+		data: &[u8],
+	) -> (Result<Vec<u8>>, Option<AllocationStats>) {
+		let mut allocation_stats = None;
+		let result = call_method(
 			&mut self.context,
 			&mut self.module,
 			ext,
 			method,
 			data,
 			self.heap_pages,
-		)
+			&mut allocation_stats,
+		);
+		(result, allocation_stats)
 	}
 }
 
@@ -131,6 +140,9 @@ fn create_compiled_unit(code: &[u8])
 }
 
 /// Call a function inside a precompiled Wasm module.
+///
+/// The statistics of the heap allocator are written to `allocation_stats` once the function
+/// returned or trapped.
 fn call_method(
 	context: &mut Context,
 	module: &mut CompiledModule,
@@ -138,6 +150,7 @@ fn call_method(
 	method: &str,
 	data: &[u8],
 	heap_pages: u32,
+	allocation_stats: &mut Option<AllocationStats>,
 ) -> Result<Vec<u8>> {
 	// Old exports get clobbered in `InstanceHandle::new` if we don't explicitly remove them first.
 	//
@@ -171,6 +184,10 @@ fn call_method(
 			.invoke(&mut instance, method, &args[..])
 			.map_err(Error::Wasmtime)
 	})?;
+	*allocation_stats = get_env_state(context)?
+		.executor_state
+		.as_mut()
+		.map(|executor_state| executor_state.heap().stats());
 	let trap_error = reset_env_state_and_take_trap(context, None)?;
 	let (output_ptr, output_len) = match outcome {
 		ActionOutcome::Returned { values } => match values.as_slice() {
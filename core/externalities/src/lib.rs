@@ -97,6 +97,16 @@ pub trait Externalities: ExtensionStore {
 	/// Clear an entire child storage.
 	fn kill_child_storage(&mut self, storage_key: ChildStorageKey);
 
+	/// Clear at most `limit` entries of a child storage.
+	///
+	/// Returns `true` when the child storage has been emptied completely, together with
+	/// the number of keys removed by this call.
+	///
+	/// Only `limit` keys of the backend are visited. The keys written in the current block are
+	/// cleared without counting towards the limit, while the keys removed by a previous call in
+	/// the current block are still in the backend and counted again.
+	fn kill_child_storage_limited(&mut self, storage_key: ChildStorageKey, limit: u32) -> (bool, u32);
+
 	/// Clear storage entries which keys are start with the given prefix.
 	fn clear_prefix(&mut self, prefix: &[u8]);
 
@@ -106,6 +116,13 @@ pub trait Externalities: ExtensionStore {
 	/// Set or clear a storage entry (`key`) of current contract being called (effective immediately).
 	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>);
 
+	/// Append the encoded `value` to the SCALE-encoded `Vec` stored at `key`.
+	///
+	/// Only the length prefix of the stored value is updated, the items already stored are not
+	/// decoded. A missing value is treated as an empty `Vec`, and a value that doesn't start with a
+	/// valid length is replaced by a `Vec` containing only `value`.
+	fn storage_append(&mut self, key: Vec<u8>, value: Vec<u8>);
+
 	/// Set or clear a child storage entry. Return whether the operation succeeds.
 	fn place_child_storage(&mut self, storage_key: ChildStorageKey, key: Vec<u8>, value: Option<Vec<u8>>);
 
@@ -124,6 +141,32 @@ pub trait Externalities: ExtensionStore {
 
 	/// Get the change trie root of the current storage overlay at a block with given parent.
 	fn storage_changes_root(&mut self, parent: H256) -> Result<Option<H256>, ()>;
+
+	/// Commit all pending changes, so that a following `wipe` keeps them.
+	///
+	/// Used by runtime benchmarks to persist the setup of a benchmark before it is measured.
+	fn commit(&mut self);
+
+	/// Discard all changes made since the last `commit`.
+	///
+	/// Used by runtime benchmarks to reset the state between repetitions.
+	fn wipe(&mut self);
+
+	/// Start a storage transaction nested within the open ones.
+	///
+	/// The storage changes made until the matching `storage_rollback_transaction` are reverted by
+	/// it, while `storage_commit_transaction` keeps them in the enclosing transaction.
+	fn storage_start_transaction(&mut self);
+
+	/// Revert the storage changes made within the innermost open transaction, and close it.
+	///
+	/// Returns `Err(())` if there is no open transaction.
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()>;
+
+	/// Keep the storage changes made within the innermost open transaction, and close it.
+	///
+	/// Returns `Err(())` if there is no open transaction.
+	fn storage_commit_transaction(&mut self) -> Result<(), ()>;
 }
 
 /// Extension for the [`Externalities`] trait.
@@ -23,8 +23,9 @@ extern crate alloc;
 
 #[cfg(feature = "std")]
 use serde::Serialize;
+use app_crypto::RuntimeAppPublic;
 use codec::{Encode, Decode, Input, Codec};
-use sr_primitives::{ConsensusEngineId, RuntimeDebug};
+use sr_primitives::{ConsensusEngineId, RuntimeDebug, traits::{Block as BlockT, NumberFor}};
 use client::decl_runtime_apis;
 use rstd::borrow::Cow;
 use rstd::vec::Vec;
@@ -157,6 +158,91 @@ impl<N: Codec> ConsensusLog<N> {
 	}
 }
 
+/// The stage of a GRANDPA round at which a vote is cast.
+///
+/// The variants match the ones of the signed GRANDPA messages.
+#[derive(Clone, Copy, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub enum VoteStage {
+	/// A prevote.
+	Prevote,
+	/// A precommit.
+	Precommit,
+}
+
+/// A vote of a GRANDPA voter for a block, along with its signature.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct SignedVote<H, N> {
+	/// The hash of the block voted for.
+	pub target_hash: H,
+	/// The number of the block voted for.
+	pub target_number: N,
+	/// The signature of the vote by the voter.
+	pub signature: AuthoritySignature,
+}
+
+/// Proof of an equivocation of a GRANDPA voter, i.e. of two different votes
+/// it cast at the same stage of a round.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct EquivocationProof<H, N> {
+	/// The authority set in which the equivocation happened.
+	pub set_id: SetId,
+	/// The round in which the equivocation happened.
+	pub round: RoundNumber,
+	/// The stage of the round at which the equivocation happened.
+	pub stage: VoteStage,
+	/// The authority which equivocated.
+	pub offender: AuthorityId,
+	/// The first vote involved in the equivocation.
+	pub first: SignedVote<H, N>,
+	/// The second vote involved in the equivocation.
+	pub second: SignedVote<H, N>,
+}
+
+/// A proof that a key was owned by a validator, encoded by the runtime.
+///
+/// Its actual type depends on the key owner proof system of the runtime.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct OpaqueKeyOwnershipProof(Vec<u8>);
+
+impl OpaqueKeyOwnershipProof {
+	/// Wrap an encoded proof of key ownership.
+	pub fn new(encoded: Vec<u8>) -> Self {
+		OpaqueKeyOwnershipProof(encoded)
+	}
+
+	/// Decode the proof, returning `None` if it isn't of the given type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		T::decode(&mut &self.0[..]).ok()
+	}
+}
+
+/// Check an equivocation proof: both votes must be for different blocks and be
+/// signed by the offender.
+pub fn check_equivocation_proof<H, N>(proof: &EquivocationProof<H, N>) -> bool where
+	H: Encode + PartialEq,
+	N: Encode + PartialEq,
+{
+	let EquivocationProof { set_id, round, stage, offender, first, second } = proof;
+
+	(first.target_hash != second.target_hash || first.target_number != second.target_number) &&
+		check_vote_signature(first, *stage, *round, *set_id, offender) &&
+		check_vote_signature(second, *stage, *round, *set_id, offender)
+}
+
+/// Check the signature of a vote cast in the given round and set.
+fn check_vote_signature<H: Encode, N: Encode>(
+	vote: &SignedVote<H, N>,
+	stage: VoteStage,
+	round: RoundNumber,
+	set_id: SetId,
+	voter: &AuthorityId,
+) -> bool {
+	// voters sign the encoded message along with the round and set id, the
+	// message being encoded as its stage followed by the target.
+	let payload = (stage, &vote.target_hash, &vote.target_number, round, set_id).encode();
+	voter.verify(&payload, &vote.signature)
+}
+
 /// WASM function call to check for pending changes.
 pub const PENDING_CHANGE_CALL: &str = "grandpa_pending_change";
 /// WASM function call to get current GRANDPA authorities.
@@ -221,7 +307,7 @@ decl_runtime_apis! {
 	/// applied in the runtime after those N blocks have passed.
 	///
 	/// The consensus protocol will coordinate the handoff externally.
-	#[api_version(2)]
+	#[api_version(3)]
 	pub trait GrandpaApi {
 		/// Get the current GRANDPA authorities and weights. This should not change except
 		/// for when changes are scheduled and the corresponding delay has passed.
@@ -230,5 +316,27 @@ decl_runtime_apis! {
 		/// used to finalize descendants of this block (B+1, B+2, ...). The block B itself
 		/// is finalized by the authorities from block B-1.
 		fn grandpa_authorities() -> AuthorityList;
+
+		/// Generate a proof that the given authority is part of the validator set
+		/// of the given authority set, needed to report its equivocations.
+		///
+		/// Returns `None` if the ownership of the key can't be proved, e.g. if
+		/// the authority set isn't the current one or if the runtime doesn't
+		/// handle equivocations.
+		fn generate_key_ownership_proof(
+			set_id: SetId,
+			authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof>;
+
+		/// Create the unsigned extrinsic reporting the given equivocation, along
+		/// with the proof of key ownership of the offender returned by
+		/// `generate_key_ownership_proof`.
+		///
+		/// The extrinsic is only meant to be included by the local block authors.
+		/// Returns `None` if the runtime doesn't handle equivocations.
+		fn report_equivocation_unsigned_extrinsic(
+			equivocation_proof: EquivocationProof<<Block as BlockT>::Hash, NumberFor<Block>>,
+			key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> Option<<Block as BlockT>::Extrinsic>;
 	}
 }
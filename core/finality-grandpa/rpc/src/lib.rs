@@ -0,0 +1,340 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GRANDPA specific RPC methods.
+//!
+//! Finality proofs are served to light clients and bridges, which are then able to verify
+//! the finality of a block range, given the authority set they last know of.
+//!
+//! The state of the rounds run by the local voter is reported as well, to diagnose
+//! finality stalls.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use client::{backend::Backend, error::Error as ClientError};
+use grandpa::{AuthorityId, BlockNumberOps, FinalityProofProvider, SharedVoterState, VoterState};
+use jsonrpc_core::{Result, Error, ErrorCode};
+use jsonrpc_derive::rpc;
+use primitives::{Blake2Hasher, Bytes, H256};
+use serde::Serialize;
+use sr_primitives::traits::{Block as BlockT, NumberFor};
+
+pub use self::gen_client::Client as GrandpaClient;
+
+/// GRANDPA RPC methods.
+#[rpc]
+pub trait GrandpaApi<Hash, Number> {
+	/// Prove the finality of the best possible block in the `(begin; end]` range.
+	///
+	/// The caller is expected to know about the finality of `begin`, and about all the blocks
+	/// of the range. The first justification of the proof is signed by the authority set with
+	/// the given id, and the proof contains the headers and justifications of the blocks
+	/// enacting the authority set changes found in the range.
+	///
+	/// Returns `None` if there are no finalized blocks unknown to the caller.
+	#[rpc(name = "grandpa_proveFinality")]
+	fn prove_finality(&self, begin: Hash, end: Hash, authorities_set_id: u64) -> Result<Option<Bytes>>;
+
+	/// Returns the state of the rounds run by the local voter: the voters which
+	/// prevoted and precommitted in the best and background rounds, and the
+	/// estimates of the last completed round.
+	#[rpc(name = "grandpa_roundState")]
+	fn round_state(&self) -> Result<ReportedRoundStates<Hash, Number>>;
+}
+
+const PROOF_ERROR: i64 = 1;
+const VOTER_NOT_RUNNING_ERROR: i64 = 2;
+
+/// The votes cast at a stage of a round.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Votes {
+	/// The total weight of the voters which voted.
+	pub current_weight: u64,
+	/// The voters which voted.
+	pub voted: BTreeSet<AuthorityId>,
+	/// The voters which didn't vote yet.
+	pub missing: BTreeSet<AuthorityId>,
+}
+
+/// The state of a round run by the voter.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundState {
+	/// The round number.
+	pub round: u64,
+	/// The total weight of the voters.
+	pub total_weight: u64,
+	/// The weight of the votes needed to make progress.
+	pub threshold_weight: u64,
+	/// The prevotes of the round.
+	pub prevotes: Votes,
+	/// The precommits of the round.
+	pub precommits: Votes,
+}
+
+/// The estimates of the last round completed by the voter.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletedRoundState<Hash, Number> {
+	/// The round number.
+	pub round: u64,
+	/// The block with the most prevotes, if any.
+	pub prevote_ghost: Option<(Hash, Number)>,
+	/// The best block which could have been finalized in the round, if any.
+	pub estimate: Option<(Hash, Number)>,
+	/// The block finalized in the round, if any.
+	pub finalized: Option<(Hash, Number)>,
+}
+
+/// The state of the rounds run by the voter.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedRoundStates<Hash, Number> {
+	/// The id of the authority set of the voter.
+	pub set_id: u64,
+	/// The best round run by the voter, if any.
+	pub best: Option<RoundState>,
+	/// The older rounds still running in the background.
+	pub background: Vec<RoundState>,
+	/// The last round completed by the voter.
+	pub last_completed: CompletedRoundState<Hash, Number>,
+}
+
+impl<Block: BlockT> From<VoterState<Block>> for ReportedRoundStates<Block::Hash, NumberFor<Block>> {
+	fn from(voter_state: VoterState<Block>) -> Self {
+		let total_weight = voter_state.voters.iter().map(|(_, weight)| weight).sum();
+		let votes = |voted: BTreeSet<AuthorityId>| Votes {
+			current_weight: voter_state.voters.iter()
+				.filter(|(id, _)| voted.contains(id))
+				.map(|(_, weight)| weight)
+				.sum(),
+			missing: voter_state.voters.iter()
+				.map(|(id, _)| id.clone())
+				.filter(|id| !voted.contains(id))
+				.collect(),
+			voted,
+		};
+
+		let mut rounds = voter_state.rounds.clone().into_iter()
+			.map(|(round, round_votes)| RoundState {
+				round,
+				total_weight,
+				threshold_weight: voter_state.threshold,
+				prevotes: votes(round_votes.prevotes),
+				precommits: votes(round_votes.precommits),
+			})
+			.collect::<Vec<_>>();
+		let best = rounds.pop();
+
+		let last_completed = voter_state.last_completed_state;
+		ReportedRoundStates {
+			set_id: voter_state.set_id,
+			best,
+			background: rounds,
+			last_completed: CompletedRoundState {
+				round: voter_state.last_completed_round,
+				prevote_ghost: last_completed.prevote_ghost,
+				estimate: last_completed.estimate,
+				finalized: last_completed.finalized,
+			},
+		}
+	}
+}
+
+/// Something that reports the state of the local voter.
+pub trait ReportVoterState<Block: BlockT> {
+	/// Get the state of the voter, if it is running.
+	fn voter_state(&self) -> Option<VoterState<Block>>;
+}
+
+impl<Block: BlockT> ReportVoterState<Block> for SharedVoterState<Block> {
+	fn voter_state(&self) -> Option<VoterState<Block>> {
+		SharedVoterState::voter_state(self)
+	}
+}
+
+/// Something that can prove the finality of blocks.
+pub trait RpcFinalityProofProvider<Block: BlockT> {
+	/// Prove the finality of the best possible block in the `(begin; end]` range.
+	fn rpc_prove_finality(
+		&self,
+		begin: Block::Hash,
+		end: Block::Hash,
+		authorities_set_id: u64,
+	) -> std::result::Result<Option<Vec<u8>>, ClientError>;
+}
+
+impl<B, Block> RpcFinalityProofProvider<Block> for FinalityProofProvider<B, Block> where
+	Block: BlockT<Hash=H256>,
+	NumberFor<Block>: BlockNumberOps,
+	B: Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+{
+	fn rpc_prove_finality(
+		&self,
+		begin: Block::Hash,
+		end: Block::Hash,
+		authorities_set_id: u64,
+	) -> std::result::Result<Option<Vec<u8>>, ClientError> {
+		self.prove_finality(begin, end, authorities_set_id)
+	}
+}
+
+/// An implementation of GRANDPA specific RPC methods.
+pub struct Grandpa<P, V, Block> {
+	finality_proof_provider: Arc<P>,
+	voter_state: V,
+	_marker: std::marker::PhantomData<Block>,
+}
+
+impl<P, V, Block> Grandpa<P, V, Block> {
+	/// Create new `Grandpa` given a finality proof provider and the state of the local voter.
+	pub fn new(finality_proof_provider: Arc<P>, voter_state: V) -> Self {
+		Grandpa {
+			finality_proof_provider,
+			voter_state,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<P, V, Block> GrandpaApi<Block::Hash, NumberFor<Block>> for Grandpa<P, V, Block> where
+	P: RpcFinalityProofProvider<Block> + Send + Sync + 'static,
+	V: ReportVoterState<Block> + Send + Sync + 'static,
+	Block: BlockT,
+{
+	fn prove_finality(
+		&self,
+		begin: Block::Hash,
+		end: Block::Hash,
+		authorities_set_id: u64,
+	) -> Result<Option<Bytes>> {
+		self.finality_proof_provider.rpc_prove_finality(begin, end, authorities_set_id)
+			.map(|proof| proof.map(Into::into))
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(PROOF_ERROR),
+				message: "Unable to prove finality.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn round_state(&self) -> Result<ReportedRoundStates<Block::Hash, NumberFor<Block>>> {
+		self.voter_state.voter_state()
+			.map(Into::into)
+			.ok_or_else(|| Error {
+				code: ErrorCode::ServerError(VOTER_NOT_RUNNING_ERROR),
+				message: "GRANDPA voter is not running.".into(),
+				data: None,
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::collections::BTreeMap;
+	use finality_grandpa::round::State;
+	use grandpa::RoundVotes;
+	use keyring::Ed25519Keyring;
+	use test_client::runtime::{Block, Hash};
+
+	struct TestFinalityProofProvider;
+
+	impl RpcFinalityProofProvider<Block> for TestFinalityProofProvider {
+		fn rpc_prove_finality(
+			&self,
+			begin: Hash,
+			end: Hash,
+			authorities_set_id: u64,
+		) -> std::result::Result<Option<Vec<u8>>, ClientError> {
+			if authorities_set_id != 1 {
+				return Ok(None);
+			}
+			if begin == end {
+				return Err(ClientError::Backend("Invalid range".into()));
+			}
+			Ok(Some(vec![42]))
+		}
+	}
+
+	struct TestVoterState(Option<VoterState<Block>>);
+
+	impl ReportVoterState<Block> for TestVoterState {
+		fn voter_state(&self) -> Option<VoterState<Block>> {
+			self.0.clone()
+		}
+	}
+
+	#[test]
+	fn should_prove_finality() {
+		let grandpa = Grandpa::new(Arc::new(TestFinalityProofProvider), TestVoterState(None));
+		let begin = Hash::repeat_byte(1);
+		let end = Hash::repeat_byte(2);
+
+		assert_eq!(grandpa.prove_finality(begin, end, 1).unwrap(), Some(vec![42].into()));
+		assert_eq!(grandpa.prove_finality(begin, end, 0).unwrap(), None);
+		assert_eq!(
+			grandpa.prove_finality(begin, begin, 1).unwrap_err().code,
+			ErrorCode::ServerError(PROOF_ERROR),
+		);
+	}
+
+	#[test]
+	fn should_report_round_state() {
+		let alice: AuthorityId = Ed25519Keyring::Alice.public().into();
+		let bob: AuthorityId = Ed25519Keyring::Bob.public().into();
+		let voted = |ids: &[&AuthorityId]| ids.iter().cloned().cloned().collect::<BTreeSet<_>>();
+
+		let grandpa = Grandpa::new(Arc::new(TestFinalityProofProvider), TestVoterState(None));
+		assert_eq!(
+			grandpa.round_state().unwrap_err().code,
+			ErrorCode::ServerError(VOTER_NOT_RUNNING_ERROR),
+		);
+
+		let mut rounds = BTreeMap::new();
+		rounds.insert(1, RoundVotes { prevotes: voted(&[&alice, &bob]), precommits: voted(&[&alice]) });
+		rounds.insert(2, RoundVotes { prevotes: voted(&[&bob]), precommits: voted(&[]) });
+		let voter_state = VoterState::<Block> {
+			set_id: 1,
+			voters: vec![(alice.clone(), 1), (bob.clone(), 2)],
+			threshold: 3,
+			last_completed_round: 0,
+			last_completed_state: State::genesis((Hash::repeat_byte(1), 5)),
+			rounds,
+		};
+		let grandpa = Grandpa::new(Arc::new(TestFinalityProofProvider), TestVoterState(Some(voter_state)));
+
+		let round_state = grandpa.round_state().unwrap();
+		assert_eq!(round_state.set_id, 1);
+		assert_eq!(round_state.best, Some(RoundState {
+			round: 2,
+			total_weight: 3,
+			threshold_weight: 3,
+			prevotes: Votes { current_weight: 2, voted: voted(&[&bob]), missing: voted(&[&alice]) },
+			precommits: Votes { current_weight: 0, voted: voted(&[]), missing: voted(&[&alice, &bob]) },
+		}));
+		assert_eq!(round_state.background.len(), 1);
+		assert_eq!(round_state.background[0].prevotes.current_weight, 3);
+		assert_eq!(round_state.background[0].precommits.missing, voted(&[&bob]));
+		assert_eq!(round_state.last_completed.finalized, Some((Hash::repeat_byte(1), 5)));
+
+		let json = serde_json::to_value(&round_state).unwrap();
+		assert_eq!(json["best"]["thresholdWeight"], 3);
+		assert_eq!(json["lastCompleted"]["round"], 0);
+	}
+}
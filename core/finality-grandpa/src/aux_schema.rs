@@ -21,6 +21,7 @@ use std::sync::Arc;
 use codec::{Encode, Decode};
 use client::backend::AuxStore;
 use client::error::{Result as ClientResult, Error as ClientError};
+use client::versioned_aux;
 use fork_tree::ForkTree;
 use grandpa::round::State as RoundState;
 use sr_primitives::traits::{Block as BlockT, NumberFor};
@@ -41,6 +42,13 @@ const CONSENSUS_CHANGES_KEY: &[u8] = b"grandpa_consensus_changes";
 
 const CURRENT_VERSION: u32 = 2;
 
+/// Version of the envelope of the values in the aux-db.
+///
+/// The envelope versions the encoding of each value, while `CURRENT_VERSION` versions the
+/// schema as a whole. Values written before the envelope are decoded according to the schema
+/// version, as before.
+const AUX_VALUE_VERSION: u32 = 1;
+
 /// The voter set state.
 #[derive(Debug, Clone, Encode, Decode)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -108,14 +116,25 @@ where H: Clone + Debug + PartialEq,
 pub(crate) fn load_decode<B: AuxStore, T: Decode>(backend: &B, key: &[u8]) -> ClientResult<Option<T>> {
 	match backend.get_aux(key)? {
 		None => Ok(None),
-		Some(t) => T::decode(&mut &t[..])
+		Some(t) => versioned_aux::decode(&t, AUX_VALUE_VERSION, versioned_aux::decode_unchanged_since(0))
 			.map_err(
 				|e| ClientError::Backend(format!("GRANDPA DB is corrupted: {}", e.what())),
 			)
-			.map(Some)
+			.map(|decoded| Some(decoded.value))
 	}
 }
 
+/// Load the id of the current authority set, if the voter has stored one.
+pub(crate) fn load_authority_set_id<Block: BlockT, B: AuxStore>(backend: &B) -> ClientResult<Option<u64>> {
+	load_decode::<_, AuthoritySet<Block::Hash, NumberFor<Block>>>(backend, AUTHORITY_SET_KEY)
+		.map(|set| set.map(|set| set.set_id))
+}
+
+/// Encodes a value for the aux-db.
+pub(crate) fn encode_versioned<T: Encode>(value: &T) -> Vec<u8> {
+	versioned_aux::encode(AUX_VALUE_VERSION, value)
+}
+
 /// Persistent data kept between runs.
 pub(crate) struct PersistentData<Block: BlockT> {
 	pub(crate) authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
@@ -132,16 +151,14 @@ fn migrate_from_version0<Block: BlockT, B, G>(
 )>> where B: AuxStore,
 		  G: Fn() -> RoundState<Block::Hash, NumberFor<Block>>,
 {
-	CURRENT_VERSION.using_encoded(|s|
-		backend.insert_aux(&[(VERSION_KEY, s)], &[])
-	)?;
+	backend.insert_aux(&[(VERSION_KEY, encode_versioned(&CURRENT_VERSION).as_slice())], &[])?;
 
 	if let Some(old_set) = load_decode::<_, V0AuthoritySet<Block::Hash, NumberFor<Block>>>(
 		backend,
 		AUTHORITY_SET_KEY,
 	)? {
 		let new_set: AuthoritySet<Block::Hash, NumberFor<Block>> = old_set.into();
-		backend.insert_aux(&[(AUTHORITY_SET_KEY, new_set.encode().as_slice())], &[])?;
+		backend.insert_aux(&[(AUTHORITY_SET_KEY, encode_versioned(&new_set).as_slice())], &[])?;
 
 		let (last_round_number, last_round_state) = match load_decode::<_, V0VoterSetState<Block::Hash, NumberFor<Block>>>(
 			backend,
@@ -173,7 +190,7 @@ fn migrate_from_version0<Block: BlockT, B, G>(
 			current_rounds,
 		};
 
-		backend.insert_aux(&[(SET_STATE_KEY, set_state.encode().as_slice())], &[])?;
+		backend.insert_aux(&[(SET_STATE_KEY, encode_versioned(&set_state).as_slice())], &[])?;
 
 		return Ok(Some((new_set, set_state)));
 	}
@@ -190,9 +207,7 @@ fn migrate_from_version1<Block: BlockT, B, G>(
 )>> where B: AuxStore,
 		  G: Fn() -> RoundState<Block::Hash, NumberFor<Block>>,
 {
-	CURRENT_VERSION.using_encoded(|s|
-		backend.insert_aux(&[(VERSION_KEY, s)], &[])
-	)?;
+	backend.insert_aux(&[(VERSION_KEY, encode_versioned(&CURRENT_VERSION).as_slice())], &[])?;
 
 	if let Some(set) = load_decode::<_, AuthoritySet<Block::Hash, NumberFor<Block>>>(
 		backend,
@@ -248,7 +263,7 @@ fn migrate_from_version1<Block: BlockT, B, G>(
 			},
 		};
 
-		backend.insert_aux(&[(SET_STATE_KEY, set_state.encode().as_slice())], &[])?;
+		backend.insert_aux(&[(SET_STATE_KEY, encode_versioned(&set_state).as_slice())], &[])?;
 
 		return Ok(Some((set, set_state)));
 	}
@@ -346,8 +361,8 @@ pub(crate) fn load_persistent<Block: BlockT, B, G>(
 
 	backend.insert_aux(
 		&[
-			(AUTHORITY_SET_KEY, genesis_set.encode().as_slice()),
-			(SET_STATE_KEY, genesis_state.encode().as_slice()),
+			(AUTHORITY_SET_KEY, encode_versioned(&genesis_set).as_slice()),
+			(SET_STATE_KEY, encode_versioned(&genesis_state).as_slice()),
 		],
 		&[],
 	)?;
@@ -372,7 +387,7 @@ pub(crate) fn update_authority_set<Block: BlockT, F, R>(
 	F: FnOnce(&[(&'static [u8], &[u8])]) -> R,
 {
 	// write new authority set state to disk.
-	let encoded_set = set.encode();
+	let encoded_set = encode_versioned(set);
 
 	if let Some(new_set) = new_set {
 		// we also overwrite the "last completed round" entry with a blank slate
@@ -383,7 +398,7 @@ pub(crate) fn update_authority_set<Block: BlockT, F, R>(
 			&set,
 			(new_set.canon_hash, new_set.canon_number),
 		);
-		let encoded = set_state.encode();
+		let encoded = encode_versioned(&set_state);
 
 		write_aux(&[
 			(AUTHORITY_SET_KEY, &encoded_set[..]),
@@ -400,7 +415,7 @@ pub(crate) fn write_voter_set_state<Block: BlockT, B: AuxStore>(
 	state: &VoterSetState<Block>,
 ) -> ClientResult<()> {
 	backend.insert_aux(
-		&[(SET_STATE_KEY, state.encode().as_slice())],
+		&[(SET_STATE_KEY, encode_versioned(state).as_slice())],
 		&[]
 	)
 }
@@ -414,7 +429,7 @@ pub(crate) fn update_consensus_changes<H, N, F, R>(
 	N: Encode + Clone,
 	F: FnOnce(&[(&'static [u8], &[u8])]) -> R,
 {
-	write_aux(&[(CONSENSUS_CHANGES_KEY, set.encode().as_slice())])
+	write_aux(&[(CONSENSUS_CHANGES_KEY, encode_versioned(set).as_slice())])
 }
 
 #[cfg(test)]
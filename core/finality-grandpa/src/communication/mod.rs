@@ -137,6 +137,10 @@ pub trait Network<Block: BlockT>: Clone + Send + 'static {
 	/// should make a best effort to fetch the block from any peers it is
 	/// connected to (NOTE: this assumption will change in the future #3629).
 	fn set_sync_fork_request(&self, peers: Vec<network::PeerId>, hash: Block::Hash, number: NumberFor<Block>);
+
+	/// Notifies the sync service to request the justification of the given
+	/// block from the peers which have it.
+	fn request_justification(&self, hash: &Block::Hash, number: NumberFor<Block>);
 }
 
 /// Create a unique topic for a round and set-id combo.
@@ -228,6 +232,10 @@ impl<B, S, H> Network<B> for Arc<NetworkService<B, S, H>> where
 	fn set_sync_fork_request(&self, peers: Vec<network::PeerId>, hash: B::Hash, number: NumberFor<B>) {
 		NetworkService::set_sync_fork_request(self, peers, hash, number)
 	}
+
+	fn request_justification(&self, hash: &B::Hash, number: NumberFor<B>) {
+		NetworkService::request_justification(self, hash, number)
+	}
 }
 
 /// A stream used by NetworkBridge in its implementation of Network. Given a oneshot that eventually returns a channel
@@ -537,6 +545,11 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 	pub(crate) fn set_sync_fork_request(&self, peers: Vec<network::PeerId>, hash: B::Hash, number: NumberFor<B>) {
 		self.service.set_sync_fork_request(peers, hash, number)
 	}
+
+	/// Request the justification of the given block from the network.
+	pub(crate) fn request_justification(&self, hash: &B::Hash, number: NumberFor<B>) {
+		self.service.request_justification(hash, number)
+	}
 }
 
 fn incoming_global<B: BlockT, N: Network<B>>(
@@ -96,6 +96,9 @@ impl super::Network<Block> for TestNetwork {
 
 	/// Notify the sync service to try syncing the given chain.
 	fn set_sync_fork_request(&self, _peers: Vec<network::PeerId>, _hash: Hash, _number: NumberFor<Block>) {}
+
+	/// Request the justification of the given block.
+	fn request_justification(&self, _hash: &Hash, _number: NumberFor<Block>) {}
 }
 
 impl network_gossip::ValidatorContext<Block> for TestNetwork {
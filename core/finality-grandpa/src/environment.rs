@@ -28,7 +28,7 @@ use parking_lot::RwLock;
 use client::{
 	backend::Backend, apply_aux, BlockchainEvents, CallExecutor,
 	Client, error::Error as ClientError, utils::is_descendent_of,
-	blockchain::HeaderBackend, backend::Finalizer,
+	blockchain::HeaderBackend, backend::Finalizer, ExecutionStrategy,
 };
 use grandpa::{
 	BlockNumberOps, Equivocation, Error as GrandpaError, round::State as RoundState,
@@ -52,8 +52,12 @@ use crate::authorities::{AuthoritySet, SharedAuthoritySet};
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
 use crate::until_imported::UntilVoteTargetImported;
+use crate::voter_state::SharedVoterState;
 use crate::voting_rule::VotingRule;
-use fg_primitives::{AuthorityId, AuthoritySignature, SetId, RoundNumber};
+use fg_primitives::{
+	AuthorityId, AuthoritySignature, EquivocationProof, GrandpaApi, OpaqueKeyOwnershipProof,
+	RoundNumber, SetId, SignedVote, VoteStage,
+};
 
 type HistoricalVotes<Block> = grandpa::HistoricalVotes<
 	<Block as BlockT>::Hash,
@@ -380,6 +384,8 @@ pub(crate) struct Environment<B, E, Block: BlockT, N: Network<Block>, RA, SC, VR
 	pub(crate) set_id: SetId,
 	pub(crate) voter_set_state: SharedVoterSetState<Block>,
 	pub(crate) voting_rule: VR,
+	pub(crate) equivocation_reports: Option<futures03::channel::mpsc::UnboundedSender<Block::Extrinsic>>,
+	pub(crate) voter_state: SharedVoterState<Block>,
 }
 
 impl<B, E, Block: BlockT, N: Network<Block>, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR> {
@@ -598,15 +604,21 @@ where
 			has_voted,
 		);
 
+		self.voter_state.note_round(round);
+		let voter_state = self.voter_state.clone();
+
 		// schedule incoming messages from the network to be held until
-		// corresponding blocks are imported.
+		// corresponding blocks are imported, and track the votes handed
+		// over to the voter.
 		let incoming = Box::new(UntilVoteTargetImported::new(
 			self.client.import_notification_stream(),
 			self.network.clone(),
 			self.client.clone(),
 			incoming,
 			"round",
-		).map_err(Into::into));
+		)
+			.inspect(move |vote| voter_state.note_vote(round, vote))
+			.map_err(Into::into));
 
 		// schedule network message cleanup when sink drops.
 		let outgoing = Box::new(outgoing.sink_map_err(Into::into));
@@ -806,6 +818,8 @@ where
 			Ok(Some(set_state))
 		})?;
 
+		self.voter_state.note_completed(round, state);
+
 		Ok(())
 	}
 
@@ -843,7 +857,19 @@ where
 		equivocation: ::grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet; this could craft misbehavior reports of some kind.
+		let vote = |(vote, signature): (Prevote<Block>, AuthoritySignature)| SignedVote {
+			target_hash: vote.target_hash,
+			target_number: vote.target_number,
+			signature,
+		};
+		self.report_equivocation(EquivocationProof {
+			set_id: self.set_id,
+			round: equivocation.round_number,
+			stage: VoteStage::Prevote,
+			offender: equivocation.identity,
+			first: vote(equivocation.first),
+			second: vote(equivocation.second),
+		});
 	}
 
 	fn precommit_equivocation(
@@ -852,7 +878,86 @@ where
 		equivocation: Equivocation<Self::Id, Precommit<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet
+		let vote = |(vote, signature): (Precommit<Block>, AuthoritySignature)| SignedVote {
+			target_hash: vote.target_hash,
+			target_number: vote.target_number,
+			signature,
+		};
+		self.report_equivocation(EquivocationProof {
+			set_id: self.set_id,
+			round: equivocation.round_number,
+			stage: VoteStage::Precommit,
+			offender: equivocation.identity,
+			first: vote(equivocation.first),
+			second: vote(equivocation.second),
+		});
+	}
+}
+
+impl<B, E, Block: BlockT, N, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR>
+where
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher> + Send + Sync,
+	N: Network<Block>,
+{
+	/// Submit an unsigned extrinsic reporting the given equivocation to the runtime, through
+	/// the equivocation reports channel.
+	///
+	/// Nothing is reported if the runtime doesn't handle the reports, or if it can't prove that
+	/// the offender's key belongs to a validator.
+	fn report_equivocation(&self, equivocation: EquivocationProof<Block::Hash, NumberFor<Block>>) {
+		let reports = match self.equivocation_reports {
+			Some(ref reports) => reports,
+			None => return,
+		};
+
+		match self.equivocation_report_extrinsic(equivocation) {
+			Ok(Some(extrinsic)) => {
+				debug!(target: "afg", "Submitting equivocation report");
+				let _ = reports.unbounded_send(extrinsic);
+			},
+			Ok(None) => debug!(target: "afg", "Equivocation can't be reported to the runtime"),
+			Err(e) => warn!(target: "afg", "Error building equivocation report: {:?}", e),
+		}
+	}
+
+	fn equivocation_report_extrinsic(
+		&self,
+		equivocation: EquivocationProof<Block::Hash, NumberFor<Block>>,
+	) -> Result<Option<Block::Extrinsic>, ClientError> {
+		let at = BlockId::Hash(self.client.info().chain.best_hash);
+		if !self.client.runtime_version_at(&at)?.has_api_with::<dyn GrandpaApi<Block>, _>(|v| v >= 3) {
+			return Ok(None);
+		}
+
+		let call = |method, data: Vec<u8>| self.client.executor().call(
+			&at,
+			method,
+			&data,
+			ExecutionStrategy::NativeElseWasm,
+			None,
+		);
+		let decode_error = |e: codec::Error| ClientError::CallResultDecode(
+			"failed to decode the result of a GRANDPA runtime API call",
+			e,
+		);
+
+		let key_owner_proof = call(
+			"GrandpaApi_generate_key_ownership_proof",
+			(equivocation.set_id, &equivocation.offender).encode(),
+		)?;
+		let key_owner_proof = match Option::<OpaqueKeyOwnershipProof>::decode(&mut &key_owner_proof[..])
+			.map_err(decode_error)?
+		{
+			Some(proof) => proof,
+			None => return Ok(None),
+		};
+
+		let extrinsic = call(
+			"GrandpaApi_report_equivocation_unsigned_extrinsic",
+			(equivocation, key_owner_proof).encode(),
+		)?;
+		Decode::decode(&mut &extrinsic[..]).map_err(decode_error)
 	}
 }
 
@@ -151,6 +151,27 @@ impl<B, Block: BlockT<Hash=H256>> FinalityProofProvider<B, Block>
 	) -> Self {
 		FinalityProofProvider { backend, authority_provider }
 	}
+
+	/// Prove finality of the best possible block in the range: (begin; end].
+	///
+	/// The first justification of the proof is signed by the authority set with the given id.
+	/// Returns None if there are no finalized blocks unknown to the caller.
+	pub fn prove_finality(
+		&self,
+		begin: Block::Hash,
+		end: Block::Hash,
+		authorities_set_id: u64,
+	) -> ClientResult<Option<Vec<u8>>> where
+		NumberFor<Block>: BlockNumberOps,
+	{
+		prove_finality::<_, _, GrandpaJustification<Block>>(
+			&*self.backend.blockchain(),
+			&*self.authority_provider,
+			authorities_set_id,
+			begin,
+			end,
+		)
+	}
 }
 
 impl<B, Block> network::FinalityProofProvider<Block> for FinalityProofProvider<B, Block>
@@ -170,15 +191,37 @@ impl<B, Block> network::FinalityProofProvider<Block> for FinalityProofProvider<B
 				ClientError::Backend(format!("Invalid finality proof request"))
 			})?;
 		match request {
-			FinalityProofRequest::Original(request) => prove_finality::<_, _, GrandpaJustification<Block>>(
-				&*self.backend.blockchain(),
-				&*self.authority_provider,
-				request.authorities_set_id,
+			FinalityProofRequest::Original(request) => FinalityProofProvider::prove_finality(
+				self,
 				request.last_finalized,
 				for_block,
+				request.authorities_set_id,
 			),
 		}
 	}
+
+	fn prove_finality_since(
+		&self,
+		last_finalized: Block::Hash,
+		for_block: Block::Hash,
+	) -> Result<Option<Vec<u8>>, ClientError> {
+		let set_id = match crate::aux_schema::load_authority_set_id::<Block, _>(&*self.backend)? {
+			Some(set_id) => set_id,
+			None => return Ok(None),
+		};
+
+		// the block has just been finalized, so its justification is signed either by the
+		// current set, or by the previous one if the block enacts the current set.
+		let previous_set_id = set_id.checked_sub(1);
+		for set_id in iter::once(set_id).chain(previous_set_id) {
+			let proof = FinalityProofProvider::prove_finality(self, last_finalized, for_block, set_id)?;
+			if proof.is_some() {
+				return Ok(proof);
+			}
+		}
+
+		Ok(None)
+	}
 }
 
 /// The effects of block finality.
@@ -0,0 +1,172 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Requests of the justifications of blocks enacting authority set changes.
+//!
+//! A block enacting a standard authority set change must be finalized with a justification
+//! of the current set before the next set can pick up finalization. The justification is
+//! requested when the block is imported, but the request is lost if the node restarts before
+//! getting it, in which case the node would be stuck behind the pending change. The blocks
+//! of the best chain which should have been finalized by now are thus checked again whenever
+//! a new best block is imported, and their justifications requested from the network.
+
+use std::sync::Arc;
+
+use client::{
+	BlockchainEvents, CallExecutor, Client, backend::Backend, error::Error as ClientError,
+	utils::is_descendent_of,
+};
+use futures::prelude::*;
+use futures03::{StreamExt as _, TryStreamExt as _};
+use grandpa::BlockNumberOps;
+use log::{debug, warn};
+use primitives::{Blake2Hasher, H256};
+use sr_primitives::generic::BlockId;
+use sr_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+
+use crate::authorities::{DelayKind, SharedAuthoritySet};
+use crate::communication::{Network, NetworkBridge};
+
+/// How far the best block must be past a block enacting an authority set change before the
+/// justification of the latter is considered missing. This leaves GRANDPA time to finalize
+/// the block on its own.
+const JUSTIFICATION_REQUEST_DELAY: u32 = 32;
+
+/// Something that one can ask to request the justification of a block.
+pub(crate) trait JustificationRequester<Block: BlockT> {
+	/// Request the justification of the given block from the network.
+	fn request_justification(&self, hash: &Block::Hash, number: NumberFor<Block>);
+}
+
+impl<Block, N> JustificationRequester<Block> for NetworkBridge<Block, N> where
+	Block: BlockT,
+	N: Network<Block>,
+{
+	fn request_justification(&self, hash: &Block::Hash, number: NumberFor<Block>) {
+		NetworkBridge::request_justification(self, hash, number)
+	}
+}
+
+/// Blocks of the best chain which enact a pending standard change of the authority set and
+/// which aren't finalized, although the best block is well past them.
+pub(crate) fn missing_justifications<B, E, Block: BlockT<Hash=H256>, RA>(
+	client: &Client<B, E, Block, RA>,
+	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+) -> Result<Vec<(Block::Hash, NumberFor<Block>)>, ClientError> where
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+	NumberFor<Block>: BlockNumberOps,
+{
+	let info = client.info().chain;
+	let changes = authority_set.inner().read().pending_changes()
+		.filter(|change| change.delay_kind == DelayKind::Finalized)
+		.map(|change| (change.canon_hash, change.effective_number()))
+		.filter(|(_, effective_number)|
+			*effective_number > info.finalized_number &&
+				*effective_number + JUSTIFICATION_REQUEST_DELAY.into() <= info.best_number
+		)
+		.collect::<Vec<_>>();
+
+	let is_descendent_of = is_descendent_of(client, None::<(H256, H256)>);
+	let mut missing = Vec::new();
+	for (canon_hash, effective_number) in changes {
+		let hash = match client.header(&BlockId::Number(effective_number))? {
+			Some(header) => header.hash(),
+			None => continue,
+		};
+
+		// the change only applies to the best chain if it was signaled on it.
+		if hash == canon_hash || is_descendent_of(&canon_hash, &hash)? {
+			missing.push((hash, effective_number));
+		}
+	}
+
+	Ok(missing)
+}
+
+/// Future requesting the missing justifications of blocks enacting authority set changes
+/// each time a new best block is imported.
+pub(crate) fn request_missing_justifications<B, E, Block: BlockT<Hash=H256>, RA, R>(
+	client: Arc<Client<B, E, Block, RA>>,
+	authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+	requester: R,
+) -> impl Future<Item=(), Error=()> where
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+	NumberFor<Block>: BlockNumberOps,
+	R: JustificationRequester<Block>,
+{
+	client.import_notification_stream()
+		.map(|notification| Ok::<_, ()>(notification))
+		.compat()
+		.filter(|notification| notification.is_new_best)
+		.for_each(move |_| {
+			match missing_justifications(&*client, &authority_set) {
+				Ok(missing) => for (hash, number) in missing {
+					debug!(target: "afg", "Requesting missing justification of block #{} ({:?})", number, hash);
+					requester.request_justification(&hash, number);
+				},
+				Err(e) => warn!(target: "afg", "Failed to look for missing justifications: {:?}", e),
+			}
+
+			Ok(())
+		})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use consensus_common::BlockOrigin;
+	use test_client::{self, prelude::*};
+	use crate::authorities::{AuthoritySet, PendingChange};
+
+	fn import_blocks(client: &TestClient, hashes: &mut Vec<H256>, n: usize) {
+		for _ in 0..n {
+			let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+			hashes.push(block.header().hash());
+			client.import(BlockOrigin::Own, block).unwrap();
+		}
+	}
+
+	#[test]
+	fn missing_justifications_of_enacting_blocks_are_found() {
+		let client = test_client::new();
+		let mut hashes = vec![client.info().chain.genesis_hash];
+		import_blocks(&client, &mut hashes, 10);
+
+		let mut authority_set = AuthoritySet::genesis(Vec::new());
+		authority_set.add_pending_change(
+			PendingChange {
+				next_authorities: Vec::new(),
+				delay: 5,
+				canon_height: 1,
+				canon_hash: hashes[1],
+				delay_kind: DelayKind::Finalized,
+			},
+			&is_descendent_of(&client, None::<(H256, H256)>),
+		).unwrap();
+		let authority_set = SharedAuthoritySet::from(authority_set);
+
+		// GRANDPA still has time to finalize the enacting block.
+		assert!(missing_justifications(&client, &authority_set).unwrap().is_empty());
+
+		import_blocks(&client, &mut hashes, 30);
+		assert_eq!(missing_justifications(&client, &authority_set).unwrap(), vec![(hashes[6], 6)]);
+
+		client.finalize_block(BlockId::Number(6), None).unwrap();
+		assert!(missing_justifications(&client, &authority_set).unwrap().is_empty());
+	}
+}
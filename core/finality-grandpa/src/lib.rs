@@ -87,9 +87,11 @@ mod environment;
 mod finality_proof;
 mod import;
 mod justification;
+mod justification_requests;
 mod light_import;
 mod observer;
 mod until_imported;
+mod voter_state;
 mod voting_rule;
 
 pub use communication::Network;
@@ -97,6 +99,7 @@ pub use finality_proof::FinalityProofProvider;
 pub use justification::GrandpaJustification;
 pub use light_import::light_block_import;
 pub use observer::run_grandpa_observer;
+pub use voter_state::{RoundVotes, SharedVoterState, VoterState};
 pub use voting_rule::{
 	BeforeBestBlock, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
 };
@@ -540,6 +543,12 @@ pub struct GrandpaParams<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X> {
 	pub telemetry_on_connect: Option<mpsc::UnboundedReceiver<()>>,
 	/// A voting rule used to potentially restrict target votes.
 	pub voting_rule: VR,
+	/// If supplied, the unsigned extrinsics reporting the equivocations observed by the voter
+	/// are sent through this channel, to be submitted to the transaction pool.
+	pub equivocation_reports: Option<futures03::channel::mpsc::UnboundedSender<Block::Extrinsic>>,
+	/// The state of the voter, kept up to date while it runs, e.g. to be reported
+	/// through the RPC.
+	pub shared_voter_state: SharedVoterState<Block>,
 }
 
 /// Run a GRANDPA voter as a task. Provide configuration and a link to a
@@ -567,6 +576,8 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 		on_exit,
 		telemetry_on_connect,
 		voting_rule,
+		equivocation_reports,
+		shared_voter_state,
 	} = grandpa_params;
 
 	let LinkHalf {
@@ -585,6 +596,12 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 
 	register_finality_tracker_inherent_data_provider(client.clone(), &inherent_data_providers)?;
 
+	let justification_requests = justification_requests::request_missing_justifications(
+		client.clone(),
+		persistent_data.authority_set.clone(),
+		network.clone(),
+	);
+
 	let conf = config.clone();
 	let telemetry_task = if let Some(telemetry_on_connect) = telemetry_on_connect {
 		let authorities = persistent_data.authority_set.clone();
@@ -621,6 +638,8 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 		voting_rule,
 		persistent_data,
 		voter_commands_rx,
+		equivocation_reports,
+		shared_voter_state,
 	);
 
 	let voter_work = voter_work
@@ -632,11 +651,17 @@ pub fn run_grandpa_voter<B, E, Block: BlockT<Hash=H256>, N, RA, SC, VR, X>(
 
 	let voter_work = network_startup.and_then(move |()| voter_work);
 
-	// Make sure that `telemetry_task` doesn't accidentally finish and kill grandpa.
+	// Make sure that `telemetry_task` and `justification_requests` don't accidentally
+	// finish and kill grandpa.
 	let telemetry_task = telemetry_task
 		.then(|_| futures::future::empty::<(), ()>());
+	let justification_requests = justification_requests
+		.then(|_| futures::future::empty::<(), ()>());
 
-	Ok(voter_work.select(on_exit).select2(telemetry_task).then(|_| Ok(())))
+	Ok(voter_work
+		.select(on_exit)
+		.select2(telemetry_task.join(justification_requests))
+		.then(|_| Ok(())))
 }
 
 /// Future that powers the voter.
@@ -667,6 +692,8 @@ where
 		voting_rule: VR,
 		persistent_data: PersistentData<Block>,
 		voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
+		equivocation_reports: Option<futures03::channel::mpsc::UnboundedSender<Block::Extrinsic>>,
+		voter_state: SharedVoterState<Block>,
 	) -> Self {
 
 		let voters = persistent_data.authority_set.current_authorities();
@@ -681,6 +708,8 @@ where
 			authority_set: persistent_data.authority_set.clone(),
 			consensus_changes: persistent_data.consensus_changes.clone(),
 			voter_set_state: persistent_data.set_state.clone(),
+			equivocation_reports,
+			voter_state,
 		});
 
 		let mut work = VoterWork {
@@ -741,6 +770,13 @@ where
 
 				let last_completed_round = completed_rounds.last();
 
+				self.env.voter_state.reset(
+					self.env.set_id,
+					&self.env.voters,
+					last_completed_round.number,
+					last_completed_round.state.clone(),
+				);
+
 				let voter = voter::Voter::new(
 					self.env.clone(),
 					(*self.env.voters).clone(),
@@ -752,8 +788,10 @@ where
 
 				self.voter = Box::new(voter);
 			},
-			VoterSetState::Paused { .. } =>
-				self.voter = Box::new(futures::empty()),
+			VoterSetState::Paused { .. } => {
+				self.env.voter_state.clear();
+				self.voter = Box::new(futures::empty());
+			},
 		};
 	}
 
@@ -798,6 +836,8 @@ where
 					consensus_changes: self.env.consensus_changes.clone(),
 					network: self.env.network.clone(),
 					voting_rule: self.env.voting_rule.clone(),
+					equivocation_reports: self.env.equivocation_reports.clone(),
+					voter_state: self.env.voter_state.clone(),
 				});
 
 				self.rebuild_voter();
@@ -40,7 +40,7 @@ use sr_primitives::generic::BlockId;
 use primitives::{H256, Blake2Hasher};
 
 use crate::GenesisAuthoritySetProvider;
-use crate::aux_schema::load_decode;
+use crate::aux_schema::{load_decode, encode_versioned};
 use crate::consensus_changes::ConsensusChanges;
 use crate::environment::canonical_at_height;
 use crate::finality_proof::{
@@ -493,7 +493,7 @@ fn load_aux_import_data<B, Block: BlockT<Hash=H256>>(
 			let genesis_authorities = genesis_authorities_provider.get()?;
 
 			let authority_set = LightAuthoritySet::genesis(genesis_authorities);
-			let encoded = authority_set.encode();
+			let encoded = encode_versioned(&authority_set);
 			aux_store.insert_aux(&[(LIGHT_AUTHORITY_SET_KEY, &encoded[..])], &[])?;
 
 			authority_set
@@ -505,7 +505,7 @@ fn load_aux_import_data<B, Block: BlockT<Hash=H256>>(
 		None => {
 			let consensus_changes = ConsensusChanges::<Block::Hash, NumberFor<Block>>::empty();
 
-			let encoded = authority_set.encode();
+			let encoded = encode_versioned(&authority_set);
 			aux_store.insert_aux(&[(LIGHT_CONSENSUS_CHANGES_KEY, &encoded[..])], &[])?;
 
 			consensus_changes
@@ -526,7 +526,7 @@ fn require_insert_aux<T: Encode, A: AuxStore>(
 	value: &T,
 	value_type: &str,
 ) -> Result<(), ConsensusError> {
-	let encoded = value.encode();
+	let encoded = encode_versioned(value);
 	let update_res = store.insert_aux(&[(key, &encoded[..])], &[]);
 	if let Err(error) = update_res {
 		return Err(on_post_finalization_error(error, value_type));
@@ -36,6 +36,7 @@ use crate::{
 use crate::authorities::SharedAuthoritySet;
 use crate::communication::NetworkBridge;
 use crate::consensus_changes::SharedConsensusChanges;
+use crate::justification_requests::request_missing_justifications;
 use fg_primitives::AuthorityId;
 
 struct ObserverChain<'a, Block: BlockT, B, E, RA>(&'a Client<B, E, Block, RA>);
@@ -178,6 +179,12 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 		on_exit.clone(),
 	);
 
+	let justification_requests = request_missing_justifications(
+		client.clone(),
+		persistent_data.authority_set.clone(),
+		network.clone(),
+	);
+
 	let observer_work = ObserverWork::new(
 		client,
 		network,
@@ -194,7 +201,15 @@ pub fn run_grandpa_observer<B, E, Block: BlockT<Hash=H256>, N, RA, SC>(
 
 	let observer_work = network_startup.and_then(move |()| observer_work);
 
-	Ok(observer_work.select(on_exit).map(|_| ()).map_err(|_| ()))
+	// Make sure that `justification_requests` doesn't accidentally finish and kill the observer.
+	let justification_requests = justification_requests
+		.then(|_| future::empty::<(), ()>());
+
+	Ok(observer_work
+		.select(on_exit)
+		.select2(justification_requests)
+		.map(|_| ())
+		.map_err(|_| ()))
 }
 
 /// Future that powers the observer.
@@ -39,7 +39,9 @@ use codec::Decode;
 use sr_primitives::traits::{ApiRef, ProvideRuntimeApi, Header as HeaderT};
 use sr_primitives::generic::{BlockId, DigestItem};
 use primitives::{NativeOrEncoded, ExecutionContext, crypto::Public};
-use fg_primitives::{GRANDPA_ENGINE_ID, AuthorityList, GrandpaApi};
+use fg_primitives::{
+	GRANDPA_ENGINE_ID, AuthorityList, EquivocationProof, GrandpaApi, OpaqueKeyOwnershipProof, SetId,
+};
 use state_machine::{backend::InMemory, prove_read, read_proof_check};
 
 use authorities::AuthoritySet;
@@ -96,6 +98,7 @@ impl TestNetFactory for GrandpaTestNet {
 		// the authority role ensures gossip hits all nodes here.
 		ProtocolConfig {
 			roles: Roles::AUTHORITY,
+			..Default::default()
 		}
 	}
 
@@ -274,6 +277,26 @@ impl GrandpaApi<Block> for RuntimeApi {
 	) -> Result<NativeOrEncoded<AuthorityList>> {
 		Ok(self.inner.genesis_authorities.clone()).map(NativeOrEncoded::Native)
 	}
+
+	fn GrandpaApi_generate_key_ownership_proof_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(SetId, AuthorityId)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<OpaqueKeyOwnershipProof>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
+
+	fn GrandpaApi_report_equivocation_unsigned_extrinsic_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(EquivocationProof<Hash, BlockNumber>, OpaqueKeyOwnershipProof)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<<Block as BlockT>::Extrinsic>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
 }
 
 impl GenesisAuthoritySetProvider<Block> for TestApi {
@@ -405,6 +428,8 @@ fn run_to_completion_with<F>(
 			on_exit: Exit,
 			telemetry_on_connect: None,
 			voting_rule: (),
+			equivocation_reports: None,
+			shared_voter_state: SharedVoterState::empty(),
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -539,6 +564,8 @@ fn finalize_3_voters_1_full_observer() {
 			on_exit: Exit,
 			telemetry_on_connect: None,
 			voting_rule: (),
+			equivocation_reports: None,
+			shared_voter_state: SharedVoterState::empty(),
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -702,6 +729,8 @@ fn transition_3_voters_twice_1_full_observer() {
 			on_exit: Exit,
 			telemetry_on_connect: None,
 			voting_rule: (),
+			equivocation_reports: None,
+			shared_voter_state: SharedVoterState::empty(),
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -1130,6 +1159,8 @@ fn voter_persists_its_votes() {
 							on_exit: Exit,
 							telemetry_on_connect: None,
 							voting_rule: VotingRulesBuilder::default().build(),
+							equivocation_reports: None,
+							shared_voter_state: SharedVoterState::empty(),
 						};
 
 						let voter = run_grandpa_voter(grandpa_params)
@@ -1466,6 +1497,8 @@ fn voter_catches_up_to_latest_round_when_behind() {
 			on_exit: Exit,
 			telemetry_on_connect: None,
 			voting_rule: (),
+			equivocation_reports: None,
+			shared_voter_state: SharedVoterState::empty(),
 		};
 
 		Box::new(run_grandpa_voter(grandpa_params).expect("all in order with client and network"))
@@ -1597,6 +1630,8 @@ fn grandpa_environment_respects_voting_rules() {
 			voters: Arc::new(authority_set.current_authorities()),
 			network,
 			voting_rule,
+			equivocation_reports: None,
+			voter_state: SharedVoterState::empty(),
 		}
 	};
 
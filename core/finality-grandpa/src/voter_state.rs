@@ -0,0 +1,198 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State of the rounds run by the GRANDPA voter.
+//!
+//! The state is made of the votes imported by the voter in the rounds it is currently
+//! running, and of the state of the last round it completed. It is shared with the outside
+//! world, e.g. the RPC, to diagnose finality stalls.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use grandpa::{round::State as RoundState, voter_set::VoterSet};
+use parking_lot::RwLock;
+use sr_primitives::traits::{Block as BlockT, NumberFor};
+
+use crate::SignedMessage;
+use fg_primitives::{AuthorityId, RoundNumber, SetId};
+
+/// The voters whose votes were imported at each stage of a round.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundVotes {
+	/// The voters which prevoted.
+	pub prevotes: BTreeSet<AuthorityId>,
+	/// The voters which precommitted.
+	pub precommits: BTreeSet<AuthorityId>,
+}
+
+/// State of a running voter.
+#[derive(Debug, Clone)]
+pub struct VoterState<Block: BlockT> {
+	/// The id of the authority set of the voter.
+	pub set_id: SetId,
+	/// The voters of the set, along with their weight.
+	pub voters: Vec<(AuthorityId, u64)>,
+	/// The weight of the votes needed to make progress in a round.
+	pub threshold: u64,
+	/// The number of the last completed round.
+	pub last_completed_round: RoundNumber,
+	/// The state of the last completed round, i.e. its prevote-GHOST, its estimate
+	/// and the block it finalized.
+	pub last_completed_state: RoundState<Block::Hash, NumberFor<Block>>,
+	/// The votes imported in the rounds currently run by the voter, by round number.
+	/// The last round is the best one, the others are still running in the background.
+	pub rounds: BTreeMap<RoundNumber, RoundVotes>,
+}
+
+/// The state of the voter, shared with the outside world.
+///
+/// It is empty while the voter isn't running, e.g. before it starts or while it is
+/// paused.
+#[derive(Clone)]
+pub struct SharedVoterState<Block: BlockT> {
+	inner: Arc<RwLock<Option<VoterState<Block>>>>,
+}
+
+impl<Block: BlockT> SharedVoterState<Block> {
+	/// Create an empty shared voter state, to be given to the voter.
+	pub fn empty() -> Self {
+		SharedVoterState { inner: Arc::new(RwLock::new(None)) }
+	}
+
+	/// Get the current state of the voter, if it is running.
+	pub fn voter_state(&self) -> Option<VoterState<Block>> {
+		self.inner.read().clone()
+	}
+
+	/// Start tracking a new voter of the given set.
+	pub(crate) fn reset(
+		&self,
+		set_id: SetId,
+		voters: &VoterSet<AuthorityId>,
+		last_completed_round: RoundNumber,
+		last_completed_state: RoundState<Block::Hash, NumberFor<Block>>,
+	) {
+		*self.inner.write() = Some(VoterState {
+			set_id,
+			voters: voters.voters().to_vec(),
+			threshold: voters.threshold(),
+			last_completed_round,
+			last_completed_state,
+			rounds: BTreeMap::new(),
+		});
+	}
+
+	/// Stop tracking the voter.
+	pub(crate) fn clear(&self) {
+		*self.inner.write() = None;
+	}
+
+	/// Note that the voter started running the given round.
+	pub(crate) fn note_round(&self, round: RoundNumber) {
+		if let Some(state) = self.inner.write().as_mut() {
+			state.rounds.entry(round).or_default();
+		}
+	}
+
+	/// Note a vote imported by the voter in the given round.
+	pub(crate) fn note_vote(&self, round: RoundNumber, vote: &SignedMessage<Block>) {
+		let mut inner = self.inner.write();
+		let votes = match inner.as_mut().and_then(|state| state.rounds.get_mut(&round)) {
+			Some(votes) => votes,
+			None => return,
+		};
+
+		match vote.message {
+			grandpa::Message::Prevote(_) => { votes.prevotes.insert(vote.id.clone()); },
+			grandpa::Message::Precommit(_) => { votes.precommits.insert(vote.id.clone()); },
+			grandpa::Message::PrimaryPropose(_) => {},
+		}
+	}
+
+	/// Note that the voter completed the given round, which keeps running in the
+	/// background. The votes of the older rounds are forgotten.
+	pub(crate) fn note_completed(
+		&self,
+		round: RoundNumber,
+		state: RoundState<Block::Hash, NumberFor<Block>>,
+	) {
+		if let Some(voter_state) = self.inner.write().as_mut() {
+			voter_state.last_completed_round = round;
+			voter_state.last_completed_state = state;
+			voter_state.rounds = voter_state.rounds.split_off(&round);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use keyring::Ed25519Keyring;
+	use primitives::H256;
+	use test_client::runtime::Block;
+
+	fn vote(message: crate::Message<Block>, keyring: Ed25519Keyring) -> SignedMessage<Block> {
+		SignedMessage::<Block> {
+			message,
+			signature: keyring.sign(b"vote").into(),
+			id: keyring.public().into(),
+		}
+	}
+
+	#[test]
+	fn votes_of_running_rounds_are_tracked() {
+		let voters: VoterSet<AuthorityId> = vec![
+			(Ed25519Keyring::Alice.public().into(), 1),
+			(Ed25519Keyring::Bob.public().into(), 1),
+		].into_iter().collect();
+		let genesis = RoundState::genesis((H256::zero(), 0));
+
+		let voter_state = SharedVoterState::<Block>::empty();
+		voter_state.note_round(1);
+		assert!(voter_state.voter_state().is_none());
+
+		voter_state.reset(0, &voters, 0, genesis.clone());
+		voter_state.note_round(1);
+		let prevote = grandpa::Prevote { target_hash: H256::zero(), target_number: 0 };
+		let precommit = grandpa::Precommit { target_hash: H256::zero(), target_number: 0 };
+		voter_state.note_vote(1, &vote(grandpa::Message::Prevote(prevote.clone()), Ed25519Keyring::Alice));
+		voter_state.note_vote(1, &vote(grandpa::Message::Prevote(prevote.clone()), Ed25519Keyring::Bob));
+		voter_state.note_vote(1, &vote(grandpa::Message::Precommit(precommit), Ed25519Keyring::Bob));
+		// votes of rounds which aren't running are ignored.
+		voter_state.note_vote(3, &vote(grandpa::Message::Prevote(prevote), Ed25519Keyring::Alice));
+
+		let state = voter_state.voter_state().unwrap();
+		assert_eq!(state.voters.len(), 2);
+		assert_eq!(state.rounds.len(), 1);
+		assert_eq!(state.rounds[&1].prevotes.len(), 2);
+		assert_eq!(
+			state.rounds[&1].precommits.iter().collect::<Vec<_>>(),
+			vec![&AuthorityId::from(Ed25519Keyring::Bob.public())],
+		);
+
+		voter_state.note_round(2);
+		voter_state.note_completed(1, genesis.clone());
+		voter_state.note_round(3);
+		voter_state.note_completed(2, genesis);
+		let state = voter_state.voter_state().unwrap();
+		assert_eq!(state.last_completed_round, 2);
+		assert_eq!(state.rounds.keys().collect::<Vec<_>>(), vec![&2, &3]);
+
+		voter_state.clear();
+		assert!(voter_state.voter_state().is_none());
+	}
+}
@@ -18,6 +18,8 @@
 
 #![warn(missing_docs)]
 
+pub mod threshold;
+
 use std::{collections::HashMap, path::PathBuf, fs::{self, File}, io::{self, Write}, sync::Arc};
 
 use primitives::{
@@ -28,6 +30,11 @@ use app_crypto::{AppKey, AppPublic, AppPair, ed25519, sr25519};
 
 use parking_lot::RwLock;
 
+use threshold::{GroupId, PartialSignature, ThresholdGroup};
+
+/// Directory of the threshold groups, within the directory of the store.
+const THRESHOLD_GROUPS_DIR: &str = "threshold";
+
 /// Keystore pointer
 pub type KeyStorePtr = Arc<RwLock<Store>>;
 
@@ -50,6 +57,21 @@ pub enum Error {
 	/// Keystore unavailable
 	#[display(fmt="Keystore unavailable")]
 	Unavailable,
+	/// Invalid threshold group
+	#[display(fmt="Invalid threshold group")]
+	InvalidThresholdGroup,
+	/// Unknown threshold group
+	#[display(fmt="Unknown threshold group")]
+	UnknownThresholdGroup,
+	/// Key isn't a member of the threshold group
+	#[display(fmt="Key is not a member of the threshold group")]
+	NotThresholdMember,
+	/// Invalid partial signature
+	#[display(fmt="Invalid partial signature")]
+	InvalidPartialSignature,
+	/// Not enough partial signatures to reach the threshold
+	#[display(fmt="Not enough partial signatures to reach the threshold")]
+	NotEnoughSignatures,
 }
 
 /// Keystore Result
@@ -207,12 +229,12 @@ impl Store {
 		self.key_pair_by_type::<Pair::Generic>(IsWrappedBy::from_ref(public), Pair::ID).map(Into::into)
 	}
 
-	/// Get public keys of all stored keys that match the given key type.
-	pub fn public_keys_by_type<TPublic: Public>(&self, key_type: KeyTypeId) -> Result<Vec<TPublic>> {
-		let mut public_keys: Vec<TPublic> = self.additional.keys()
+	/// Get the raw public keys of all stored keys that match the given key type.
+	pub fn raw_public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		let mut public_keys: Vec<Vec<u8>> = self.additional.keys()
 			.filter_map(|(ty, public)| {
 				if *ty == key_type {
-					Some(TPublic::from_slice(public))
+					Some(public.clone())
 				} else {
 					None
 				}
@@ -228,8 +250,7 @@ impl Store {
 				match hex::decode(name) {
 					Ok(ref hex) => {
 						if &hex[0..4] != &key_type.0 { continue	}
-						let public = TPublic::from_slice(&hex[4..]);
-						public_keys.push(public);
+						public_keys.push(hex[4..].to_vec());
 					}
 					_ => continue,
 				}
@@ -239,6 +260,12 @@ impl Store {
 		Ok(public_keys)
 	}
 
+	/// Get public keys of all stored keys that match the given key type.
+	pub fn public_keys_by_type<TPublic: Public>(&self, key_type: KeyTypeId) -> Result<Vec<TPublic>> {
+		self.raw_public_keys(key_type)
+			.map(|keys| keys.iter().map(|public| TPublic::from_slice(public)).collect())
+	}
+
 	/// Get public keys of all stored keys that match the key type.
 	///
 	/// This will just use the type of the public key (a list of which to be returned) in order
@@ -250,6 +277,111 @@ impl Store {
 			.map(|v| v.into_iter().map(Into::into).collect())
 	}
 
+	/// Checks if the private keys for all the given public keys and key types are in the store.
+	pub fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		public_keys.iter().all(|(public, key_type)|
+			self.additional.contains_key(&(*key_type, public.clone()))
+				|| self.key_file_path(public, *key_type).exists()
+		)
+	}
+
+	/// Remove the key of the given key type and public key, from memory and from the file system
+	/// store.
+	///
+	/// Returns `false` if the key wasn't in the store.
+	pub fn remove_key(&mut self, key_type: KeyTypeId, public: &[u8]) -> Result<bool> {
+		let ephemeral = self.additional.remove(&(key_type, public.to_vec())).is_some();
+		match fs::remove_file(self.key_file_path(public, key_type)) {
+			Ok(()) => Ok(true),
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(ephemeral),
+			Err(e) => Err(e.into()),
+		}
+	}
+
+	/// Insert the share of a threshold group, i.e. the key of one of its members.
+	///
+	/// Places the key and the group into the file system store.
+	pub fn insert_threshold_share<Pair: PairT>(
+		&self,
+		key_type: KeyTypeId,
+		group: &ThresholdGroup,
+		suri: &str,
+	) -> Result<Pair> {
+		let pair = Pair::from_string(
+			suri,
+			self.password.as_ref().map(|p| &***p)
+		).map_err(|_| Error::InvalidSeed)?;
+		if !group.is_member(pair.public().as_slice()) {
+			return Err(Error::NotThresholdMember)
+		}
+
+		fs::create_dir_all(self.path.join(THRESHOLD_GROUPS_DIR))?;
+		let mut file = File::create(self.threshold_group_path(&group.id(), key_type))?;
+		serde_json::to_writer(&file, group)?;
+		file.flush()?;
+		self.insert_unknown(key_type, suri, pair.public().as_slice())?;
+		Ok(pair)
+	}
+
+	/// Get the threshold group of the given key type with the given identifier.
+	pub fn threshold_group(&self, key_type: KeyTypeId, id: &GroupId) -> Result<ThresholdGroup> {
+		let file = match File::open(self.threshold_group_path(id, key_type)) {
+			Ok(file) => file,
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::UnknownThresholdGroup),
+			Err(e) => return Err(e.into()),
+		};
+		let group: ThresholdGroup = serde_json::from_reader(&file)?;
+		// re-create the group to check it.
+		ThresholdGroup::new(group.threshold(), group.members().to_vec())
+			.ok()
+			.filter(|checked| &checked.id() == id)
+			.ok_or(Error::InvalidThresholdGroup)
+	}
+
+	/// Get the identifiers of the threshold groups of the given key type whose share is stored.
+	pub fn threshold_groups(&self, key_type: KeyTypeId) -> Result<Vec<GroupId>> {
+		let dir = self.path.join(THRESHOLD_GROUPS_DIR);
+		if !dir.exists() {
+			return Ok(Vec::new())
+		}
+
+		let mut groups = Vec::new();
+		for entry in fs::read_dir(dir)? {
+			let path = entry?.path();
+			if let Some(Ok(name)) = path.file_name().and_then(|n| n.to_str()).map(hex::decode) {
+				if name.len() == 4 + 32 && name[0..4] == key_type.0 {
+					let mut id = GroupId::default();
+					id.copy_from_slice(&name[4..]);
+					groups.push(id);
+				}
+			}
+		}
+		Ok(groups)
+	}
+
+	/// Produce the partial signature of a message with the stored share of the given threshold
+	/// group.
+	pub fn threshold_partial_sign<Pair: PairT>(
+		&self,
+		key_type: KeyTypeId,
+		id: &GroupId,
+		message: &[u8],
+	) -> Result<PartialSignature> {
+		let group = self.threshold_group(key_type, id)?;
+		let member = group.members().iter()
+			.find(|member| self.has_keys(&[(member.to_vec(), key_type)]))
+			.ok_or(Error::NotThresholdMember)?;
+		let share = self.key_pair_by_type::<Pair>(&Pair::Public::from_slice(member), key_type)?;
+		threshold::partial_sign(&share, &group, message)
+	}
+
+	/// Returns the file path for the given threshold group and key type.
+	fn threshold_group_path(&self, id: &GroupId, key_type: KeyTypeId) -> PathBuf {
+		let mut buf = self.path.join(THRESHOLD_GROUPS_DIR);
+		buf.push(hex::encode(key_type.0) + hex::encode(id).as_str());
+		buf
+	}
+
 	/// Returns the file path for the given public key and key type.
 	fn key_file_path(&self, public: &[u8], key_type: KeyTypeId) -> PathBuf {
 		let mut buf = self.path.clone();
@@ -309,6 +441,18 @@ impl BareCryptoStore for Store {
 		Store::insert_unknown(self, key_type, suri, public).map_err(|_| ())
 	}
 
+	fn raw_public_keys(&self, id: KeyTypeId) -> std::result::Result<Vec<Vec<u8>>, ()> {
+		Store::raw_public_keys(self, id).map_err(|_| ())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		Store::has_keys(self, public_keys)
+	}
+
+	fn remove_key(&mut self, id: KeyTypeId, public: &[u8]) -> std::result::Result<bool, ()> {
+		Store::remove_key(self, id, public).map_err(|_| ())
+	}
+
 	fn password(&self) -> Option<&str> {
 		self.password.as_ref().map(|x| x.as_str())
 	}
@@ -318,7 +462,7 @@ impl BareCryptoStore for Store {
 mod tests {
 	use super::*;
 	use tempdir::TempDir;
-	use primitives::{testing::{SR25519}, crypto::{Ss58Codec}};
+	use primitives::{testing::{ED25519, SR25519}, crypto::{Ss58Codec}};
 
 	#[test]
 	fn basic_store() {
@@ -401,6 +545,60 @@ mod tests {
 		assert_eq!(public_keys, store_pubs);
 	}
 
+	#[test]
+	fn keys_are_checked_and_removed() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+
+		let stored = store.write().generate::<ed25519::AppPair>().unwrap().public();
+		let ephemeral = store.write().insert_ephemeral_from_seed::<sr25519::AppPair>("//Alice")
+			.unwrap()
+			.public();
+		let stored = (stored.to_raw_vec(), ed25519::AppPublic::ID);
+		let ephemeral = (ephemeral.to_raw_vec(), sr25519::AppPublic::ID);
+
+		assert!(store.read().has_keys(&[stored.clone(), ephemeral.clone()]));
+		assert!(!store.read().has_keys(&[(stored.0.clone(), sr25519::AppPublic::ID)]));
+		assert_eq!(store.read().raw_public_keys(ed25519::AppPublic::ID).unwrap(), vec![stored.0.clone()]);
+
+		assert!(store.write().remove_key(stored.1, &stored.0).unwrap());
+		assert!(store.write().remove_key(ephemeral.1, &ephemeral.0).unwrap());
+		assert!(!store.write().remove_key(stored.1, &stored.0).unwrap());
+		assert!(!store.read().has_keys(&[stored]));
+		assert!(!store.read().has_keys(&[ephemeral]));
+	}
+
+	#[test]
+	fn threshold_shares_sign_partially() {
+		let temp_dir = TempDir::new("keystore").unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+
+		let members = ["//Alice", "//Bob", "//Charlie"].iter()
+			.map(|suri| sr25519::Pair::from_string(suri, None).unwrap().public().to_raw_vec())
+			.collect();
+		let group = ThresholdGroup::new(2, members).unwrap();
+		assert!(store.read().insert_threshold_share::<sr25519::Pair>(SR25519, &group, "//Dave").is_err());
+		store.read().insert_threshold_share::<sr25519::Pair>(SR25519, &group, "//Bob").unwrap();
+
+		assert_eq!(store.read().threshold_groups(SR25519).unwrap(), vec![group.id()]);
+		assert_eq!(store.read().threshold_group(SR25519, &group.id()).unwrap(), group);
+		assert!(store.read().threshold_groups(ED25519).unwrap().is_empty());
+		// the group isn't mistaken for a key.
+		assert_eq!(store.read().raw_public_keys(SR25519).unwrap().len(), 1);
+
+		let message = b"message";
+		let bob = store.read().threshold_partial_sign::<sr25519::Pair>(SR25519, &group.id(), message).unwrap();
+		let alice = threshold::partial_sign(
+			&sr25519::Pair::from_string("//Alice", None).unwrap(),
+			&group,
+			message,
+		).unwrap();
+		let signature = threshold::aggregate::<sr25519::Pair>(&group, message, vec![alice, bob]).unwrap();
+		assert!(threshold::verify::<sr25519::Pair>(&group, message, &signature));
+
+		assert!(store.read().threshold_partial_sign::<sr25519::Pair>(ED25519, &group.id(), message).is_err());
+	}
+
 	#[test]
 	fn store_unknown_and_extract_it() {
 		let temp_dir = TempDir::new("keystore").unwrap();
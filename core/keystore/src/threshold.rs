@@ -0,0 +1,218 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Threshold signatures of groups of keys.
+//!
+//! A threshold group is made of `n` member keys, any `threshold` of which are needed to sign on
+//! behalf of the group. Each member key is a share of the group, held by a different host, so
+//! that no single host is able to sign alone.
+//!
+//! Each host produces a partial signature of a message with its share, and the partial signatures
+//! of enough members are aggregated into a signature of the group. The aggregated signature is
+//! made of the partial signatures themselves, so it grows with the threshold.
+
+use std::collections::BTreeMap;
+use primitives::{blake2_256, crypto::{Pair as PairT, Public}};
+use serde::{Deserialize, Serialize};
+use crate::{Error, Result};
+
+/// Identifier of a threshold group, the hash of its threshold and members.
+pub type GroupId = [u8; 32];
+
+/// A group of keys, any `threshold` of which are needed to sign on behalf of the group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdGroup {
+	threshold: u32,
+	/// Raw public keys of the members, sorted.
+	members: Vec<Vec<u8>>,
+}
+
+impl ThresholdGroup {
+	/// Create the group of the given raw public keys.
+	///
+	/// Fails if the threshold is zero or greater than the number of members, or if a member is
+	/// given twice.
+	pub fn new(threshold: u32, mut members: Vec<Vec<u8>>) -> Result<Self> {
+		members.sort();
+		let group = ThresholdGroup { threshold, members };
+		if group.is_valid() {
+			Ok(group)
+		} else {
+			Err(Error::InvalidThresholdGroup)
+		}
+	}
+
+	/// Number of partial signatures needed to sign on behalf of the group.
+	pub fn threshold(&self) -> u32 {
+		self.threshold
+	}
+
+	/// Raw public keys of the members, sorted.
+	pub fn members(&self) -> &[Vec<u8>] {
+		&self.members
+	}
+
+	/// Is the given raw public key a member of the group?
+	pub fn is_member(&self, public: &[u8]) -> bool {
+		self.members.binary_search_by(|member| member[..].cmp(public)).is_ok()
+	}
+
+	/// Identifier of the group.
+	pub fn id(&self) -> GroupId {
+		let mut data = self.threshold.to_le_bytes().to_vec();
+		for member in &self.members {
+			data.extend_from_slice(&(member.len() as u32).to_le_bytes());
+			data.extend_from_slice(member);
+		}
+		blake2_256(&data)
+	}
+
+	fn is_valid(&self) -> bool {
+		self.threshold > 0 && self.threshold as usize <= self.members.len() &&
+			self.members.windows(2).all(|pair| pair[0] < pair[1])
+	}
+}
+
+/// Signature of a message by a member of a threshold group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+	/// Raw public key of the member.
+	pub signer: Vec<u8>,
+	/// Raw signature of the message by the member.
+	pub signature: Vec<u8>,
+}
+
+/// Signature of a message on behalf of a threshold group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThresholdSignature {
+	/// Identifier of the group.
+	pub group: GroupId,
+	/// Partial signatures of exactly `threshold` distinct members, ordered by signer.
+	pub signatures: Vec<PartialSignature>,
+}
+
+/// Produce the partial signature of a message with the share of the given member.
+pub fn partial_sign<Pair: PairT>(share: &Pair, group: &ThresholdGroup, message: &[u8]) -> Result<PartialSignature> {
+	let signer = share.public().to_raw_vec();
+	if !group.is_member(&signer) {
+		return Err(Error::NotThresholdMember)
+	}
+	Ok(PartialSignature { signer, signature: share.sign(message).as_ref().to_vec() })
+}
+
+/// Aggregate the partial signatures of a message into a signature of the group.
+///
+/// Partial signatures which don't verify, or of keys that aren't members of the group, are
+/// refused. Fails if there are fewer than `threshold` distinct signers, and only keeps the
+/// signatures of `threshold` of them otherwise.
+pub fn aggregate<Pair: PairT>(
+	group: &ThresholdGroup,
+	message: &[u8],
+	partials: impl IntoIterator<Item = PartialSignature>,
+) -> Result<ThresholdSignature> {
+	let mut signatures = BTreeMap::new();
+	for partial in partials {
+		if !group.is_member(&partial.signer) ||
+			!Pair::verify_weak(&partial.signature, message, &partial.signer)
+		{
+			return Err(Error::InvalidPartialSignature)
+		}
+		signatures.insert(partial.signer.clone(), partial);
+	}
+
+	if signatures.len() < group.threshold as usize {
+		return Err(Error::NotEnoughSignatures)
+	}
+	Ok(ThresholdSignature {
+		group: group.id(),
+		signatures: signatures.into_iter().map(|(_, partial)| partial).take(group.threshold as usize).collect(),
+	})
+}
+
+/// Verify a signature of a message on behalf of the given group.
+pub fn verify<Pair: PairT>(group: &ThresholdGroup, message: &[u8], signature: &ThresholdSignature) -> bool {
+	signature.group == group.id() &&
+		signature.signatures.len() == group.threshold as usize &&
+		// ordered by signer, so the signers are distinct.
+		signature.signatures.windows(2).all(|pair| pair[0].signer < pair[1].signer) &&
+		signature.signatures.iter().all(|partial|
+			group.is_member(&partial.signer) &&
+				Pair::verify_weak(&partial.signature, message, &partial.signer)
+		)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use app_crypto::sr25519;
+
+	fn shares() -> (Vec<sr25519::Pair>, ThresholdGroup) {
+		let shares = ["//Alice", "//Bob", "//Charlie"].iter()
+			.map(|suri| sr25519::Pair::from_string(suri, None).unwrap())
+			.collect::<Vec<_>>();
+		let group = ThresholdGroup::new(2, shares.iter().map(|share| share.public().to_raw_vec()).collect())
+			.unwrap();
+		(shares, group)
+	}
+
+	#[test]
+	fn groups_are_checked() {
+		assert!(ThresholdGroup::new(0, vec![vec![1]]).is_err());
+		assert!(ThresholdGroup::new(2, vec![vec![1], vec![1]]).is_err());
+		let group = ThresholdGroup::new(2, vec![vec![2], vec![1]]).unwrap();
+		assert_eq!(group.members(), &[vec![1], vec![2]]);
+		assert_eq!(group.id(), ThresholdGroup::new(2, vec![vec![1], vec![2]]).unwrap().id());
+		assert_ne!(group.id(), ThresholdGroup::new(1, vec![vec![1], vec![2]]).unwrap().id());
+	}
+
+	#[test]
+	fn threshold_signatures_are_aggregated_and_verified() {
+		let (shares, group) = shares();
+		let message = b"message";
+		let alice = partial_sign(&shares[0], &group, message).unwrap();
+		let bob = partial_sign(&shares[1], &group, message).unwrap();
+		let charlie = partial_sign(&shares[2], &group, message).unwrap();
+
+		assert!(matches_error(
+			aggregate::<sr25519::Pair>(&group, message, vec![alice.clone(), alice.clone()]),
+			Error::NotEnoughSignatures,
+		));
+		let signature = aggregate::<sr25519::Pair>(&group, message, vec![charlie, alice.clone(), bob]).unwrap();
+		assert_eq!(signature.signatures.len(), 2);
+		assert!(verify::<sr25519::Pair>(&group, message, &signature));
+		assert!(!verify::<sr25519::Pair>(&group, b"other message", &signature));
+
+		let mut forged = signature.clone();
+		forged.signatures = vec![alice.clone(), alice.clone()];
+		assert!(!verify::<sr25519::Pair>(&group, message, &forged));
+
+		let outsider = sr25519::Pair::from_string("//Dave", None).unwrap();
+		assert!(partial_sign(&outsider, &group, message).is_err());
+		let mut invalid = alice;
+		invalid.signature = outsider.sign(message).as_ref().to_vec();
+		assert!(matches_error(
+			aggregate::<sr25519::Pair>(&group, message, vec![invalid]),
+			Error::InvalidPartialSignature,
+		));
+	}
+
+	fn matches_error<T>(result: Result<T>, expected: Error) -> bool {
+		match result {
+			Err(error) => error.to_string() == expected.to_string(),
+			Ok(_) => false,
+		}
+	}
+}
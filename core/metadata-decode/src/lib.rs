@@ -0,0 +1,470 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of runtime events, calls and storage values driven by the runtime metadata.
+//!
+//! The [`Decoder`] reads the names of modules, calls, events and storage items, along with the
+//! names of their types, from the metadata of a runtime. Values are decoded into dynamically typed
+//! [`Value`]s that can be serialized, e.g. to JSON. This makes it possible to inspect the data of
+//! any runtime without a hardcoded decoder.
+//!
+//! The metadata only contains the names of types. Types that are not built into the decoder must
+//! be defined in a [`TypeRegistry`].
+
+#![warn(missing_docs)]
+
+mod types;
+mod value;
+
+pub use types::{TypeDef, TypeName, TypeRegistry};
+pub use value::Value;
+
+use codec::{Compact, Decode};
+use metadata::{
+	DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed, StorageEntryModifier,
+	StorageEntryType,
+};
+use serde::Serialize;
+
+/// Maximal nesting of types, protecting against recursive type definitions.
+const MAX_DEPTH: u32 = 64;
+
+/// Decoding error.
+#[derive(Debug, derive_more::Display)]
+pub enum Error {
+	/// The data doesn't match the expected type.
+	#[display(fmt="Decoding failed: {:?}", _0)]
+	Codec(codec::Error),
+	/// The metadata is invalid.
+	#[display(fmt="Invalid metadata: {}", _0)]
+	InvalidMetadata(String),
+	/// The version of the metadata is not supported.
+	#[display(fmt="Unsupported metadata version")]
+	UnsupportedVersion,
+	/// A type name couldn't be parsed.
+	#[display(fmt="Invalid type name `{}`", _0)]
+	InvalidTypeName(String),
+	/// A type is neither built into the decoder nor defined in the registry.
+	#[display(fmt="Unknown type `{}`", _0)]
+	UnknownType(String),
+	/// An enum variant index is out of range.
+	#[display(fmt="Invalid variant {} of `{}`", index, ty)]
+	InvalidVariant {
+		/// The name of the enum.
+		ty: String,
+		/// The decoded index.
+		index: u8,
+	},
+	/// No module with the given name has storage.
+	#[display(fmt="Unknown module `{}`", _0)]
+	UnknownModule(String),
+	/// The module has no storage item with the given name.
+	#[display(fmt="Unknown storage item `{}`", _0)]
+	UnknownStorage(String),
+	/// The module or call index is out of range.
+	#[display(fmt="Unknown call {} of module {}", _1, _0)]
+	UnknownCall(u8, u8),
+	/// The module or event index is out of range.
+	#[display(fmt="Unknown event {} of module {}", _1, _0)]
+	UnknownEvent(u8, u8),
+	/// Types are nested too deeply.
+	#[display(fmt="Types are nested too deeply")]
+	TooDeep,
+	/// Some bytes were left after decoding.
+	#[display(fmt="{} bytes left after decoding", _0)]
+	TrailingBytes(usize),
+}
+
+impl std::error::Error for Error {}
+
+impl From<codec::Error> for Error {
+	fn from(err: codec::Error) -> Error {
+		Error::Codec(err)
+	}
+}
+
+/// Decoding result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A decoded event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+	/// The name of the module emitting the event.
+	pub module: String,
+	/// The name of the event.
+	pub name: String,
+	/// The arguments of the event.
+	pub args: Vec<Value>,
+}
+
+/// A decoded event record, as stored by the system module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EventRecord {
+	/// The phase of the block the event was emitted in.
+	pub phase: Value,
+	/// The event itself.
+	pub event: Event,
+	/// The topics of the event.
+	pub topics: Vec<Value>,
+}
+
+/// A decoded call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Call {
+	/// The name of the module the call is dispatched to.
+	pub module: String,
+	/// The name of the function.
+	pub name: String,
+	/// The names and values of the arguments.
+	pub args: Vec<(String, Value)>,
+}
+
+impl From<Call> for Value {
+	fn from(call: Call) -> Value {
+		Value::Composite(vec![
+			("module".into(), Value::Text(call.module)),
+			("call".into(), Value::Text(call.name)),
+			("args".into(), Value::Composite(call.args)),
+		])
+	}
+}
+
+struct Function {
+	name: String,
+	args: Vec<(String, TypeName)>,
+}
+
+struct EventDef {
+	name: String,
+	args: Vec<TypeName>,
+}
+
+struct StorageEntry {
+	name: String,
+	optional: bool,
+	/// Type of the key of linked maps, whose values are stored along with their linkage.
+	linked_key: Option<TypeName>,
+	value: TypeName,
+	default: Vec<u8>,
+}
+
+struct Module {
+	name: String,
+	storage: Vec<StorageEntry>,
+	calls: Option<Vec<Function>>,
+	events: Option<Vec<EventDef>>,
+}
+
+/// Decoder of the events, calls and storage values of a runtime.
+pub struct Decoder {
+	modules: Vec<Module>,
+	registry: TypeRegistry,
+}
+
+fn decoded<B, O>(value: DecodeDifferent<B, O>) -> Result<O> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value),
+		DecodeDifferent::Encode(_) => Err(Error::InvalidMetadata("metadata was not decoded".into())),
+	}
+}
+
+impl Decoder {
+	/// Create a decoder from the encoded metadata of a runtime, as returned by `Metadata_metadata`.
+	pub fn new(metadata: &[u8], registry: TypeRegistry) -> Result<Self> {
+		let metadata = RuntimeMetadataPrefixed::decode(&mut &metadata[..])?;
+		let metadata = match metadata.1 {
+			RuntimeMetadata::V8(metadata) => metadata,
+			_ => return Err(Error::UnsupportedVersion),
+		};
+
+		let modules = decoded(metadata.modules)?.into_iter().map(|module| {
+			let storage = match module.storage {
+				Some(storage) => decoded(decoded(storage)?.entries)?.into_iter().map(|entry| {
+					let (linked_key, value) = match entry.ty {
+						StorageEntryType::Plain(value) => (None, value),
+						StorageEntryType::Map { key, value, is_linked, .. } =>
+							(if is_linked { Some(key) } else { None }, value),
+						StorageEntryType::DoubleMap { value, .. } => (None, value),
+					};
+					Ok(StorageEntry {
+						name: decoded(entry.name)?,
+						optional: entry.modifier == StorageEntryModifier::Optional,
+						linked_key: match linked_key {
+							Some(key) => Some(TypeName::parse(&decoded(key)?)?),
+							None => None,
+						},
+						value: TypeName::parse(&decoded(value)?)?,
+						default: decoded(entry.default)?,
+					})
+				}).collect::<Result<_>>()?,
+				None => Vec::new(),
+			};
+
+			let calls = match module.calls {
+				Some(calls) => Some(decoded(calls)?.into_iter().map(|call| Ok(Function {
+					name: decoded(call.name)?,
+					args: decoded(call.arguments)?.into_iter().map(|arg| Ok((
+						decoded(arg.name)?,
+						TypeName::parse(&decoded(arg.ty)?)?,
+					))).collect::<Result<_>>()?,
+				})).collect::<Result<_>>()?),
+				None => None,
+			};
+
+			let events = match module.event {
+				Some(events) => Some(decoded(events)?.into_iter().map(|event| Ok(EventDef {
+					name: decoded(event.name)?,
+					args: decoded(event.arguments)?.iter()
+						.map(|arg| TypeName::parse(arg))
+						.collect::<Result<_>>()?,
+				})).collect::<Result<_>>()?),
+				None => None,
+			};
+
+			Ok(Module { name: decoded(module.name)?, storage, calls, events })
+		}).collect::<Result<_>>()?;
+
+		Ok(Decoder { modules, registry })
+	}
+
+	/// Decode a value of the type with the given name.
+	pub fn decode_value(&self, ty: &str, data: &[u8]) -> Result<Value> {
+		let ty = TypeName::parse(ty)?;
+		decode_all(data, |input| self.decode_type(&ty, input, 0))
+	}
+
+	/// Decode a call, as found in extrinsics.
+	pub fn decode_call(&self, data: &[u8]) -> Result<Call> {
+		decode_all(data, |input| self.decode_call_from(input, 0))
+	}
+
+	/// Decode an event.
+	pub fn decode_event(&self, data: &[u8]) -> Result<Event> {
+		decode_all(data, |input| self.decode_event_from(input, 0))
+	}
+
+	/// Decode the events of a block, as stored by the system module.
+	pub fn decode_events(&self, data: &[u8]) -> Result<Vec<EventRecord>> {
+		decode_all(data, |input| {
+			let len = <Compact<u32>>::decode(input)?.0;
+			(0..len).map(|_| Ok(EventRecord {
+				phase: self.decode_type(&TypeName::Path("Phase".into(), Vec::new()), input, 0)?,
+				event: self.decode_event_from(input, 0)?,
+				topics: match self.decode_type(&TypeName::parse("Vec<Hash>")?, input, 0)? {
+					Value::Sequence(topics) => topics,
+					_ => unreachable!("vectors of non-bytes are decoded to sequences; qed"),
+				},
+			})).collect()
+		})
+	}
+
+	/// Decode the value of the storage item `item` of `module`.
+	///
+	/// `data` is the raw value found in storage, if any. If there's none, the default value of the
+	/// item is returned, or `None` if the item is optional.
+	pub fn decode_storage(&self, module: &str, item: &str, data: Option<&[u8]>) -> Result<Option<Value>> {
+		let module = self.modules.iter()
+			.find(|m| m.name == module)
+			.ok_or_else(|| Error::UnknownModule(module.into()))?;
+		let entry = module.storage.iter()
+			.find(|e| e.name == item)
+			.ok_or_else(|| Error::UnknownStorage(item.into()))?;
+
+		let data = match data {
+			Some(data) => data,
+			None if entry.optional => return Ok(None),
+			None => &entry.default[..],
+		};
+
+		decode_all(data, |input| {
+			let value = self.decode_type(&entry.value, input, 0)?;
+			if let Some(key) = &entry.linked_key {
+				// Skip the linkage: the previous and the next keys.
+				let linkage_key = TypeName::Path("Option".into(), vec![key.clone()]);
+				self.decode_type(&linkage_key, input, 0)?;
+				self.decode_type(&linkage_key, input, 0)?;
+			}
+			Ok(Some(value))
+		})
+	}
+
+	fn decode_call_from(&self, input: &mut &[u8], depth: u32) -> Result<Call> {
+		let module_index = u8::decode(input)?;
+		let call_index = u8::decode(input)?;
+		let (module, function) = self.modules.iter()
+			.filter_map(|m| m.calls.as_ref().map(|calls| (m, calls)))
+			.nth(module_index as usize)
+			.and_then(|(m, calls)| calls.get(call_index as usize).map(|f| (m, f)))
+			.ok_or(Error::UnknownCall(module_index, call_index))?;
+
+		Ok(Call {
+			module: module.name.clone(),
+			name: function.name.clone(),
+			args: function.args.iter()
+				.map(|(name, ty)| Ok((name.clone(), self.decode_type(ty, input, depth)?)))
+				.collect::<Result<_>>()?,
+		})
+	}
+
+	fn decode_event_from(&self, input: &mut &[u8], depth: u32) -> Result<Event> {
+		let module_index = u8::decode(input)?;
+		let event_index = u8::decode(input)?;
+		let (module, event) = self.modules.iter()
+			.filter_map(|m| m.events.as_ref().map(|events| (m, events)))
+			.nth(module_index as usize)
+			.and_then(|(m, events)| events.get(event_index as usize).map(|e| (m, e)))
+			.ok_or(Error::UnknownEvent(module_index, event_index))?;
+
+		Ok(Event {
+			module: module.name.clone(),
+			name: event.name.clone(),
+			args: event.args.iter()
+				.map(|ty| self.decode_type(ty, input, depth))
+				.collect::<Result<_>>()?,
+		})
+	}
+
+	fn decode_type(&self, ty: &TypeName, input: &mut &[u8], depth: u32) -> Result<Value> {
+		if depth > MAX_DEPTH {
+			return Err(Error::TooDeep);
+		}
+		let depth = depth + 1;
+
+		match ty {
+			TypeName::Tuple(types) if types.is_empty() => Ok(Value::Unit),
+			TypeName::Tuple(types) => types.iter()
+				.map(|ty| self.decode_type(ty, input, depth))
+				.collect::<Result<_>>()
+				.map(Value::Sequence),
+			TypeName::Array(inner, len) if is_u8(inner) => {
+				let mut bytes = vec![0; *len];
+				codec::Input::read(input, &mut bytes)?;
+				Ok(Value::Bytes(bytes))
+			},
+			TypeName::Array(inner, len) => (0..*len)
+				.map(|_| self.decode_type(inner, input, depth))
+				.collect::<Result<_>>()
+				.map(Value::Sequence),
+			TypeName::Path(name, params) => self.decode_path(name, params, input, depth),
+		}
+	}
+
+	fn decode_path(
+		&self,
+		name: &str,
+		params: &[TypeName],
+		input: &mut &[u8],
+		depth: u32,
+	) -> Result<Value> {
+		let param = |index: usize| params.get(index).ok_or_else(|| Error::InvalidTypeName(name.into()));
+		let decode_seq = |ty: &TypeName, input: &mut &[u8]| -> Result<Value> {
+			let len = <Compact<u32>>::decode(input)?.0;
+			(0..len)
+				.map(|_| self.decode_type(ty, input, depth))
+				.collect::<Result<_>>()
+				.map(Value::Sequence)
+		};
+
+		Ok(match name {
+			"bool" => Value::Bool(bool::decode(input)?),
+			"u8" => Value::UInt(u8::decode(input)?.into()),
+			"u16" => Value::UInt(u16::decode(input)?.into()),
+			"u32" => Value::UInt(u32::decode(input)?.into()),
+			"u64" => Value::UInt(u64::decode(input)?.into()),
+			"u128" => Value::UInt(u128::decode(input)?),
+			"i8" => Value::Int(i8::decode(input)?.into()),
+			"i16" => Value::Int(i16::decode(input)?.into()),
+			"i32" => Value::Int(i32::decode(input)?.into()),
+			"i64" => Value::Int(i64::decode(input)?.into()),
+			"i128" => Value::Int(i128::decode(input)?),
+			"Compact" => Value::UInt(<Compact<u128>>::decode(input)?.0),
+			"String" | "Text" => Value::Text(String::decode(input)?),
+			"PhantomData" => Value::Unit,
+			"Box" | "Rc" | "Arc" => self.decode_type(param(0)?, input, depth)?,
+			"Vec" | "VecDeque" | "BTreeSet" if is_u8(param(0)?) => Value::Bytes(Vec::<u8>::decode(input)?),
+			"Vec" | "VecDeque" | "BTreeSet" => decode_seq(param(0)?, input)?,
+			"BTreeMap" | "HashMap" =>
+				decode_seq(&TypeName::Tuple(vec![param(0)?.clone(), param(1)?.clone()]), input)?,
+			"Option" if param(0)? == &TypeName::Path("bool".into(), Vec::new()) =>
+				match u8::decode(input)? {
+					0 => Value::Variant("None".into(), Vec::new()),
+					1 => Value::Variant("Some".into(), vec![Value::Bool(true)]),
+					2 => Value::Variant("Some".into(), vec![Value::Bool(false)]),
+					index => return Err(Error::InvalidVariant { ty: name.into(), index }),
+				},
+			"Option" => match u8::decode(input)? {
+				0 => Value::Variant("None".into(), Vec::new()),
+				1 => Value::Variant("Some".into(), vec![self.decode_type(param(0)?, input, depth)?]),
+				index => return Err(Error::InvalidVariant { ty: name.into(), index }),
+			},
+			"Result" => match u8::decode(input)? {
+				0 => Value::Variant("Ok".into(), vec![self.decode_type(param(0)?, input, depth)?]),
+				1 => Value::Variant("Err".into(), vec![self.decode_type(param(1)?, input, depth)?]),
+				index => return Err(Error::InvalidVariant { ty: name.into(), index }),
+			},
+			"Call" => self.decode_call_from(input, depth)?.into(),
+			"Address" => decode_address(input)?,
+			_ => match self.registry.get(name) {
+				Some(TypeDef::Alias(target)) => self.decode_type(&TypeName::parse(target)?, input, depth)?,
+				Some(TypeDef::Struct(fields)) => Value::Composite(fields.iter()
+					.map(|(field, ty)| Ok((field.clone(), self.decode_type(&TypeName::parse(ty)?, input, depth)?)))
+					.collect::<Result<_>>()?),
+				Some(TypeDef::Enum(variants)) => {
+					let index = u8::decode(input)?;
+					let (variant, fields) = variants.get(index as usize)
+						.ok_or_else(|| Error::InvalidVariant { ty: name.into(), index })?;
+					Value::Variant(variant.clone(), fields.iter()
+						.map(|ty| self.decode_type(&TypeName::parse(ty)?, input, depth))
+						.collect::<Result<_>>()?)
+				},
+				None => return Err(Error::UnknownType(name.into())),
+			},
+		})
+	}
+}
+
+fn is_u8(ty: &TypeName) -> bool {
+	match ty {
+		TypeName::Path(name, _) => name == "u8",
+		_ => false,
+	}
+}
+
+/// Run `f` on `data`, making sure all of it is consumed.
+fn decode_all<T>(mut data: &[u8], f: impl FnOnce(&mut &[u8]) -> Result<T>) -> Result<T> {
+	let value = f(&mut data)?;
+	if data.is_empty() {
+		Ok(value)
+	} else {
+		Err(Error::TrailingBytes(data.len()))
+	}
+}
+
+/// Decode an address of the indices module, assuming 32 bytes account ids.
+fn decode_address(input: &mut &[u8]) -> Result<Value> {
+	let index = |value: u128| Value::Variant("Index".into(), vec![Value::UInt(value)]);
+	Ok(match u8::decode(input)? {
+		255 => Value::Variant("Id".into(), vec![Value::Bytes(<[u8; 32]>::decode(input)?.to_vec())]),
+		252 => index(u16::decode(input)?.into()),
+		253 => index(u32::decode(input)?.into()),
+		254 => index(u64::decode(input)?.into()),
+		byte if byte < 0xf0 => index(byte.into()),
+		byte => return Err(Error::InvalidVariant { ty: "Address".into(), index: byte }),
+	})
+}
+
+#[cfg(test)]
+mod tests;
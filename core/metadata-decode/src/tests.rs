@@ -0,0 +1,245 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use codec::Encode;
+use metadata::{
+	EventMetadata, FunctionArgumentMetadata, FunctionMetadata, ModuleMetadata, RuntimeMetadataV8,
+	StorageEntryMetadata, StorageHasher, StorageMetadata,
+};
+
+fn d<B, O>(value: O) -> DecodeDifferent<B, O> {
+	DecodeDifferent::Decoded(value)
+}
+
+fn module(
+	name: &str,
+	storage: Vec<StorageEntryMetadata>,
+	calls: Option<Vec<FunctionMetadata>>,
+	event: Option<Vec<EventMetadata>>,
+) -> ModuleMetadata {
+	ModuleMetadata {
+		name: d(name.into()),
+		storage: Some(d(StorageMetadata { prefix: d(name.into()), entries: d(storage) })),
+		calls: calls.map(d),
+		event: event.map(d),
+		constants: d(vec![]),
+		errors: d(vec![]),
+	}
+}
+
+fn call(name: &str, args: &[(&str, &str)]) -> FunctionMetadata {
+	FunctionMetadata {
+		name: d(name.into()),
+		arguments: d(args.iter().map(|(name, ty)| FunctionArgumentMetadata {
+			name: d((*name).into()),
+			ty: d((*ty).into()),
+		}).collect()),
+		documentation: d(vec![]),
+	}
+}
+
+fn event(name: &str, args: &[&str]) -> EventMetadata {
+	EventMetadata {
+		name: d(name.into()),
+		arguments: d(args.iter().map(|ty| (*ty).into()).collect()),
+		documentation: d(vec![]),
+	}
+}
+
+fn decoder() -> Decoder {
+	let modules = vec![
+		module(
+			"System",
+			vec![StorageEntryMetadata {
+				name: d("Number".into()),
+				modifier: StorageEntryModifier::Default,
+				ty: StorageEntryType::Plain(d("T::BlockNumber".into())),
+				default: d(7u32.encode()),
+				documentation: d(vec![]),
+			}],
+			None,
+			Some(vec![event("ExtrinsicSuccess", &[])]),
+		),
+		module(
+			"Balances",
+			vec![StorageEntryMetadata {
+				name: d("FreeBalance".into()),
+				modifier: StorageEntryModifier::Optional,
+				ty: StorageEntryType::Map {
+					hasher: StorageHasher::Blake2_256,
+					key: d("T::AccountId".into()),
+					value: d("T::Balance".into()),
+					is_linked: false,
+				},
+				default: d(vec![]),
+				documentation: d(vec![]),
+			}],
+			Some(vec![
+				call("transfer", &[
+					("dest", "<T::Lookup as StaticLookup>::Source"),
+					("value", "Compact<T::Balance>"),
+				]),
+			]),
+			Some(vec![event("Transfer", &["AccountId", "AccountId", "Balance", "Balance"])]),
+		),
+		module("Sudo", vec![], Some(vec![call("sudo", &[("proposal", "Box<T::Proposal>")])]), None),
+	];
+	let metadata: RuntimeMetadataPrefixed = RuntimeMetadataV8 { modules: d(modules) }.into();
+	Decoder::new(&metadata.encode(), TypeRegistry::default()).unwrap()
+}
+
+fn transfer() -> (Vec<u8>, Call) {
+	let mut data = vec![0, 0];
+	data.extend((255u8, [1u8; 32]).encode());
+	data.extend(Compact(100u128).encode());
+	let call = Call {
+		module: "Balances".into(),
+		name: "transfer".into(),
+		args: vec![
+			("dest".into(), Value::Variant("Id".into(), vec![Value::Bytes(vec![1; 32])])),
+			("value".into(), Value::UInt(100)),
+		],
+	};
+	(data, call)
+}
+
+#[test]
+fn decodes_calls() {
+	let decoder = decoder();
+	let (data, transfer) = transfer();
+	assert_eq!(decoder.decode_call(&data).unwrap(), transfer);
+
+	let mut sudo = vec![1, 0];
+	sudo.extend(&data);
+	assert_eq!(decoder.decode_call(&sudo).unwrap(), Call {
+		module: "Sudo".into(),
+		name: "sudo".into(),
+		args: vec![("proposal".into(), transfer.into())],
+	});
+
+	assert_matches!(decoder.decode_call(&[2, 0]), Err(Error::UnknownCall(2, 0)));
+	let mut trailing = data.clone();
+	trailing.push(0);
+	assert_matches!(decoder.decode_call(&trailing), Err(Error::TrailingBytes(1)));
+}
+
+#[test]
+fn decodes_events() {
+	let decoder = decoder();
+	let data = [
+		Compact(2u32).encode(),
+		(0u8, 0u32, (0u8, 0u8), Vec::<[u8; 32]>::new()).encode(),
+		(1u8, (1u8, 0u8, [1u8; 32], [2u8; 32], 5u128, 1u128), vec![[3u8; 32]]).encode(),
+	].concat();
+
+	let events = decoder.decode_events(&data).unwrap();
+	assert_eq!(events, vec![
+		EventRecord {
+			phase: Value::Variant("ApplyExtrinsic".into(), vec![Value::UInt(0)]),
+			event: Event { module: "System".into(), name: "ExtrinsicSuccess".into(), args: vec![] },
+			topics: vec![],
+		},
+		EventRecord {
+			phase: Value::Variant("Finalization".into(), vec![]),
+			event: Event {
+				module: "Balances".into(),
+				name: "Transfer".into(),
+				args: vec![
+					Value::Bytes(vec![1; 32]),
+					Value::Bytes(vec![2; 32]),
+					Value::UInt(5),
+					Value::UInt(1),
+				],
+			},
+			topics: vec![Value::Bytes(vec![3; 32])],
+		},
+	]);
+}
+
+#[test]
+fn decodes_storage() {
+	let decoder = decoder();
+	assert_eq!(decoder.decode_storage("System", "Number", None).unwrap(), Some(Value::UInt(7)));
+	assert_eq!(
+		decoder.decode_storage("System", "Number", Some(&9u32.encode())).unwrap(),
+		Some(Value::UInt(9)),
+	);
+	assert_eq!(decoder.decode_storage("Balances", "FreeBalance", None).unwrap(), None);
+	assert_eq!(
+		decoder.decode_storage("Balances", "FreeBalance", Some(&10u128.encode())).unwrap(),
+		Some(Value::UInt(10)),
+	);
+	assert_matches!(
+		decoder.decode_storage("Balances", "Locks", None),
+		Err(Error::UnknownStorage(_))
+	);
+}
+
+#[test]
+fn decodes_values() {
+	let decoder = decoder();
+	assert_eq!(
+		decoder.decode_value("Option<bool>", &[2]).unwrap(),
+		Value::Variant("Some".into(), vec![Value::Bool(false)]),
+	);
+	assert_eq!(
+		decoder.decode_value("Vec<(u8, Vec<u8>)>", &vec![(1u8, vec![2u8])].encode()).unwrap(),
+		Value::Sequence(vec![Value::Sequence(vec![Value::UInt(1), Value::Bytes(vec![2])])]),
+	);
+	assert_eq!(
+		decoder.decode_value("DispatchError", &(Some(3u8), 4u8).encode()).unwrap(),
+		Value::Composite(vec![
+			("module".into(), Value::Variant("Some".into(), vec![Value::UInt(3)])),
+			("error".into(), Value::UInt(4)),
+		]),
+	);
+	assert_matches!(decoder.decode_value("Unknown", &[]), Err(Error::UnknownType(_)));
+}
+
+#[test]
+fn decodes_srml_types() {
+	let decoder = decoder();
+	assert_eq!(
+		decoder.decode_value("Option<Timepoint<T::BlockNumber>>", &Some((5u32, 2u32)).encode()).unwrap(),
+		Value::Variant("Some".into(), vec![Value::Composite(vec![
+			("height".into(), Value::UInt(5)),
+			("index".into(), Value::UInt(2)),
+		])]),
+	);
+	assert_eq!(
+		decoder.decode_value("ValidatorPrefs<BalanceOf<T>>", &Compact(10u128).encode()).unwrap(),
+		Value::Composite(vec![("validator_payment".into(), Value::UInt(10))]),
+	);
+	assert_eq!(
+		decoder.decode_value("Judgement<BalanceOf<T>>", &(1u8, 3u128).encode()).unwrap(),
+		Value::Variant("FeePaid".into(), vec![Value::UInt(3)]),
+	);
+	let heartbeat = (7u32, (vec![1u8], vec![vec![2u8]]), 3u32, 0u32).encode();
+	assert_eq!(
+		decoder.decode_value("Heartbeat<T::BlockNumber>", &heartbeat).unwrap(),
+		Value::Composite(vec![
+			("block_number".into(), Value::UInt(7)),
+			("network_state".into(), Value::Composite(vec![
+				("peer_id".into(), Value::Bytes(vec![1])),
+				("external_addresses".into(), Value::Sequence(vec![Value::Bytes(vec![2])])),
+			])),
+			("session_index".into(), Value::UInt(3)),
+			("authority_index".into(), Value::UInt(0)),
+		]),
+	);
+}
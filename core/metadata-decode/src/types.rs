@@ -0,0 +1,341 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Type names as found in the metadata, and definitions of the types they refer to.
+
+use std::collections::HashMap;
+use crate::Error;
+
+/// A parsed type name.
+///
+/// Paths are reduced to their last segment, so `T::AccountId` and
+/// `<T as Trait>::AccountId` are both named `AccountId`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeName {
+	/// A named type along with its generic parameters, e.g. `Vec<u8>`.
+	Path(String, Vec<TypeName>),
+	/// A tuple, e.g. `(u32, u64)`. The unit type is the empty tuple.
+	Tuple(Vec<TypeName>),
+	/// A fixed size array, e.g. `[u8; 32]`.
+	Array(Box<TypeName>, usize),
+}
+
+impl TypeName {
+	/// Parse a type name as found in the metadata.
+	pub fn parse(name: &str) -> Result<Self, Error> {
+		let mut parser = Parser { input: name, pos: 0 };
+		let ty = parser.parse_type()?;
+		parser.skip_whitespace();
+		if parser.pos == name.len() {
+			Ok(ty)
+		} else {
+			Err(Error::InvalidTypeName(name.into()))
+		}
+	}
+}
+
+struct Parser<'a> {
+	input: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn error(&self) -> Error {
+		Error::InvalidTypeName(self.input.into())
+	}
+
+	fn rest(&self) -> &'a str {
+		&self.input[self.pos..]
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_start().len();
+	}
+
+	/// Consume `token` if the input continues with it.
+	fn eat(&mut self, token: &str) -> bool {
+		self.skip_whitespace();
+		if self.rest().starts_with(token) {
+			self.pos += token.len();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect(&mut self, token: &str) -> Result<(), Error> {
+		if self.eat(token) {
+			Ok(())
+		} else {
+			Err(self.error())
+		}
+	}
+
+	fn ident(&mut self) -> Result<&'a str, Error> {
+		self.skip_whitespace();
+		let rest = self.rest();
+		let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+		if len == 0 {
+			return Err(self.error());
+		}
+		self.pos += len;
+		Ok(&rest[..len])
+	}
+
+	/// Parse a comma separated list of types, up to and including `end`.
+	fn type_list(&mut self, end: &str) -> Result<Vec<TypeName>, Error> {
+		let mut types = Vec::new();
+		while !self.eat(end) {
+			types.push(self.parse_type()?);
+			if !self.eat(",") {
+				self.expect(end)?;
+				break;
+			}
+		}
+		Ok(types)
+	}
+
+	fn parse_type(&mut self) -> Result<TypeName, Error> {
+		if self.eat("(") {
+			return Ok(TypeName::Tuple(self.type_list(")")?));
+		}
+
+		if self.eat("[") {
+			let ty = self.parse_type()?;
+			if self.eat("]") {
+				// Slices are encoded like vectors.
+				return Ok(TypeName::Path("Vec".into(), vec![ty]));
+			}
+			self.expect(";")?;
+			let len = self.ident()?.parse().map_err(|_| self.error())?;
+			self.expect("]")?;
+			return Ok(TypeName::Array(Box::new(ty), len));
+		}
+
+		if self.eat("&") {
+			if self.eat("'") {
+				self.ident()?;
+			}
+			return self.parse_type();
+		}
+
+		if self.eat("<") {
+			// Qualified path, e.g. `<T as Trait>::Balance`.
+			self.parse_type()?;
+			self.expect("as")?;
+			self.parse_type()?;
+			self.expect(">")?;
+			self.expect("::")?;
+		}
+
+		self.parse_path()
+	}
+
+	fn parse_path(&mut self) -> Result<TypeName, Error> {
+		loop {
+			let name = self.ident()?;
+			let params = if self.eat("<") { self.type_list(">")? } else { Vec::new() };
+			if !self.eat("::") {
+				return Ok(TypeName::Path(name.into(), params));
+			}
+		}
+	}
+}
+
+/// Definition of a type that isn't built into the decoder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeDef {
+	/// The type is encoded like another type.
+	Alias(String),
+	/// A struct, with the names and types of its fields.
+	Struct(Vec<(String, String)>),
+	/// An enum, with the names and the field types of its variants.
+	Enum(Vec<(String, Vec<String>)>),
+}
+
+/// Definitions of the types that can't be decoded using the metadata alone.
+///
+/// The metadata only carries names of types. Apart from the types built into the decoder (integers,
+/// `Vec`, `Option`, tuples, ...), every type needs to be defined in the registry. The default
+/// registry contains the types used by the SRML modules.
+#[derive(Debug, Clone)]
+pub struct TypeRegistry {
+	types: HashMap<String, TypeDef>,
+}
+
+impl TypeRegistry {
+	/// Create a registry without any type.
+	pub fn empty() -> Self {
+		TypeRegistry { types: HashMap::new() }
+	}
+
+	/// Define the type with the given name, replacing any previous definition.
+	pub fn register(&mut self, name: impl Into<String>, def: TypeDef) -> &mut Self {
+		self.types.insert(name.into(), def);
+		self
+	}
+
+	/// Define the type with the given name as an alias of `target`.
+	pub fn alias(&mut self, name: impl Into<String>, target: impl Into<String>) -> &mut Self {
+		self.register(name, TypeDef::Alias(target.into()))
+	}
+
+	/// Returns the definition of the type with the given name.
+	pub fn get(&self, name: &str) -> Option<&TypeDef> {
+		self.types.get(name)
+	}
+}
+
+impl Default for TypeRegistry {
+	fn default() -> Self {
+		let mut registry = Self::empty();
+		for hash in &[
+			"Hash", "H256", "AccountId", "AuthorityId", "SessionKey", "CodeHash", "CallHash", "StorageKey",
+		] {
+			registry.alias(*hash, "[u8; 32]");
+		}
+		for index in &[
+			"BlockNumber", "Index", "AccountIndex", "SessionIndex", "EraIndex", "PropIndex",
+			"ReferendumIndex", "ProposalIndex", "MemberCount", "VoteIndex", "Weight",
+			"Perbill", "Permill", "AuthIndex", "RegistrarIndex", "BountyIndex",
+		] {
+			registry.alias(*index, "u32");
+		}
+		registry
+			.alias("Percent", "u8")
+			.alias("Priority", "u8")
+			.alias("H512", "[u8; 64]")
+			.alias("Signature", "H512")
+			.alias("Balance", "u128")
+			.alias("BalanceOf", "Balance")
+			.alias("Moment", "u64")
+			.alias("Gas", "u64")
+			.alias("AuthorityWeight", "u64")
+			.alias("Source", "Address")
+			.alias("LookupSource", "Address")
+			.alias("Proposal", "Call")
+			.alias("Key", "Vec<u8>")
+			.alias("KeyValue", "(Vec<u8>, Vec<u8>)")
+			.alias("OpaquePeerId", "Vec<u8>")
+			.alias("OpaqueMultiaddr", "Vec<u8>")
+			.alias("Period", "(BlockNumber, u32)")
+			// The aye bit and the conviction are packed into a single byte.
+			.alias("Vote", "u8")
+			.register("DispatchError", TypeDef::Struct(vec![
+				("module".into(), "Option<u8>".into()),
+				("error".into(), "u8".into()),
+			]))
+			.register("Phase", TypeDef::Enum(vec![
+				("ApplyExtrinsic".into(), vec!["u32".into()]),
+				("Finalization".into(), vec![]),
+			]))
+			.register("VoteThreshold", TypeDef::Enum(vec![
+				("SuperMajorityApprove".into(), vec![]),
+				("SuperMajorityAgainst".into(), vec![]),
+				("SimpleMajority".into(), vec![]),
+			]))
+			.register("Conviction", TypeDef::Enum(
+				["None", "Locked1x", "Locked2x", "Locked3x", "Locked4x", "Locked5x", "Locked6x"].iter()
+					.map(|variant| (variant.to_string(), vec![]))
+					.collect()
+			))
+			.register("RewardDestination", TypeDef::Enum(vec![
+				("Staked".into(), vec![]),
+				("Stash".into(), vec![]),
+				("Controller".into(), vec![]),
+			]))
+			.register("ValidatorPrefs", TypeDef::Struct(vec![
+				("validator_payment".into(), "Compact<Balance>".into()),
+			]))
+			.register("Judgement", TypeDef::Enum(vec![
+				("Unknown".into(), vec![]),
+				("FeePaid".into(), vec!["Balance".into()]),
+				("Reasonable".into(), vec![]),
+				("KnownGood".into(), vec![]),
+				("OutOfDate".into(), vec![]),
+				("LowQuality".into(), vec![]),
+				("Erroneous".into(), vec![]),
+			]))
+			.register("Timepoint", TypeDef::Struct(vec![
+				("height".into(), "BlockNumber".into()),
+				("index".into(), "u32".into()),
+			]))
+			.register("VestingInfo", TypeDef::Struct(vec![
+				("locked".into(), "Balance".into()),
+				("per_block".into(), "Balance".into()),
+				("starting_block".into(), "BlockNumber".into()),
+			]))
+			.register("ChangesTrieConfiguration", TypeDef::Struct(vec![
+				("digest_interval".into(), "u32".into()),
+				("digest_levels".into(), "u32".into()),
+			]))
+			.register("OpaqueNetworkState", TypeDef::Struct(vec![
+				("peer_id".into(), "OpaquePeerId".into()),
+				("external_addresses".into(), "Vec<OpaqueMultiaddr>".into()),
+			]))
+			.register("Heartbeat", TypeDef::Struct(vec![
+				("block_number".into(), "BlockNumber".into()),
+				("network_state".into(), "OpaqueNetworkState".into()),
+				("session_index".into(), "SessionIndex".into()),
+				("authority_index".into(), "AuthIndex".into()),
+			]));
+		registry
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn path(name: &str, params: Vec<TypeName>) -> TypeName {
+		TypeName::Path(name.into(), params)
+	}
+
+	#[test]
+	fn parses_type_names() {
+		assert_eq!(TypeName::parse("u32").unwrap(), path("u32", vec![]));
+		assert_eq!(TypeName::parse("T::AccountId").unwrap(), path("AccountId", vec![]));
+		assert_eq!(
+			TypeName::parse("<T as Trait<I>>::Balance").unwrap(),
+			path("Balance", vec![]),
+		);
+		assert_eq!(
+			TypeName::parse("<T::Lookup as StaticLookup>::Source").unwrap(),
+			path("Source", vec![]),
+		);
+		assert_eq!(
+			TypeName::parse("Vec<(T::AccountId, Compact<BalanceOf<T>>)>").unwrap(),
+			path("Vec", vec![TypeName::Tuple(vec![
+				path("AccountId", vec![]),
+				path("Compact", vec![path("BalanceOf", vec![path("T", vec![])])]),
+			])]),
+		);
+		assert_eq!(
+			TypeName::parse("[u8; 32]").unwrap(),
+			TypeName::Array(Box::new(path("u8", vec![])), 32),
+		);
+		assert_eq!(TypeName::parse("()").unwrap(), TypeName::Tuple(vec![]));
+		assert_eq!(TypeName::parse("&'static [u8]").unwrap(), path("Vec", vec![path("u8", vec![])]));
+	}
+
+	#[test]
+	fn rejects_invalid_type_names() {
+		assert!(TypeName::parse("Vec<u8").is_err());
+		assert!(TypeName::parse("[u8; x]").is_err());
+		assert!(TypeName::parse("u8 u16").is_err());
+	}
+}
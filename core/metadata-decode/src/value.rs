@@ -0,0 +1,104 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dynamically typed decoded values.
+
+use primitives::hexdisplay::HexDisplay;
+use serde::ser::{Serialize, Serializer, SerializeMap, SerializeSeq};
+
+/// A decoded value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+	/// The unit value, e.g. `()` or `PhantomData`.
+	Unit,
+	/// A boolean.
+	Bool(bool),
+	/// An unsigned integer, including compact ones.
+	UInt(u128),
+	/// A signed integer.
+	Int(i128),
+	/// Raw bytes, e.g. `Vec<u8>` or `[u8; 32]`.
+	Bytes(Vec<u8>),
+	/// UTF-8 text.
+	Text(String),
+	/// A sequence of values, e.g. a `Vec`, an array or a tuple.
+	Sequence(Vec<Value>),
+	/// A struct with named fields.
+	Composite(Vec<(String, Value)>),
+	/// A variant of an enum, e.g. `Option` or `Result`, along with its fields.
+	Variant(String, Vec<Value>),
+}
+
+/// Serializes integers that don't fit into JSON numbers as strings, and bytes as hex strings.
+impl Serialize for Value {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self {
+			Value::Unit => serializer.serialize_unit(),
+			Value::Bool(b) => serializer.serialize_bool(*b),
+			Value::UInt(n) if *n <= u64::max_value() as u128 => serializer.serialize_u64(*n as u64),
+			Value::UInt(n) => serializer.serialize_str(&n.to_string()),
+			Value::Int(n) if *n >= i64::min_value() as i128 && *n <= i64::max_value() as i128 =>
+				serializer.serialize_i64(*n as i64),
+			Value::Int(n) => serializer.serialize_str(&n.to_string()),
+			Value::Bytes(bytes) => serializer.serialize_str(&format!("0x{}", HexDisplay::from(bytes))),
+			Value::Text(text) => serializer.serialize_str(text),
+			Value::Sequence(values) => {
+				let mut seq = serializer.serialize_seq(Some(values.len()))?;
+				for value in values {
+					seq.serialize_element(value)?;
+				}
+				seq.end()
+			},
+			Value::Composite(fields) => {
+				let mut map = serializer.serialize_map(Some(fields.len()))?;
+				for (name, value) in fields {
+					map.serialize_entry(name, value)?;
+				}
+				map.end()
+			},
+			Value::Variant(name, fields) => {
+				let mut map = serializer.serialize_map(Some(1))?;
+				match fields.len() {
+					0 => map.serialize_entry(name, &())?,
+					1 => map.serialize_entry(name, &fields[0])?,
+					_ => map.serialize_entry(name, fields)?,
+				}
+				map.end()
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn values_serialize_to_json() {
+		let value = Value::Composite(vec![
+			("small".into(), Value::UInt(5)),
+			("big".into(), Value::UInt(u128::max_value())),
+			("bytes".into(), Value::Bytes(vec![0xde, 0xad])),
+			("none".into(), Value::Variant("None".into(), vec![])),
+			("some".into(), Value::Variant("Some".into(), vec![Value::Bool(true)])),
+		]);
+
+		assert_eq!(
+			serde_json::to_string(&value).unwrap(),
+			r#"{"small":5,"big":"340282366920938463463374607431768211455","bytes":"0xdead","none":{"None":null},"some":{"Some":true}}"#,
+		);
+	}
+}
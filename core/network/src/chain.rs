@@ -82,6 +82,18 @@ pub trait Client<Block: BlockT>: Send + Sync {
 pub trait FinalityProofProvider<Block: BlockT>: Send + Sync {
 	/// Prove finality of the block.
 	fn prove_finality(&self, for_block: Block::Hash, request: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+	/// Prove finality of the block to a caller that only knows the finality of `last_finalized`.
+	///
+	/// Unlike `prove_finality`, the provider picks the engine-specific request parameters on its
+	/// own. Returns `None` if it can't.
+	fn prove_finality_since(
+		&self,
+		_last_finalized: Block::Hash,
+		_for_block: Block::Hash,
+	) -> Result<Option<Vec<u8>>, Error> {
+		Ok(None)
+	}
 }
 
 impl<Block: BlockT> FinalityProofProvider<Block> for () {
@@ -32,7 +32,7 @@ use libp2p::identity::{Keypair, ed25519};
 use libp2p::wasm_ext;
 use libp2p::{PeerId, Multiaddr, multiaddr};
 use core::{fmt, iter};
-use std::{error::Error, fs, io::{self, Write}, net::Ipv4Addr, path::{Path, PathBuf}, sync::Arc};
+use std::{error::Error, fs, io::{self, Write}, net::Ipv4Addr, path::{Path, PathBuf}, sync::Arc, time::Duration};
 use zeroize::Zeroize;
 
 /// Network initialization parameters.
@@ -40,6 +40,9 @@ pub struct Params<B: BlockT, S, H: ExHashT> {
 	/// Assigned roles for our node (full, light, ...).
 	pub roles: Roles,
 
+	/// How the chain is synced. Light clients sync in `SyncMode::Light`, and only them.
+	pub sync_mode: SyncMode,
+
 	/// Network layer configuration.
 	pub network_config: NetworkConfiguration,
 
@@ -129,6 +132,62 @@ impl codec::Decode for Roles {
 	}
 }
 
+/// How the node syncs the chain, which determines what it downloads, verifies and stores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+	/// Download, execute and store all the blocks.
+	Full,
+	/// Download and verify the headers and justifications only, storing no state.
+	Light,
+	/// Download and verify the headers and justifications up to a recent finalized block,
+	/// download the state at that block, and download and execute the blocks from there.
+	///
+	/// Not supported yet.
+	Fast,
+	/// Download the proofs of finality of the authority set changes up to a recent finalized
+	/// block, download the state at that block, and execute the blocks from there.
+	///
+	/// Not supported yet.
+	Warp,
+}
+
+impl SyncMode {
+	/// The sync mode of a node with the given roles, when it isn't configured explicitly.
+	pub fn for_roles(roles: Roles) -> Self {
+		if roles.is_light() {
+			SyncMode::Light
+		} else {
+			SyncMode::Full
+		}
+	}
+
+	/// Does this mode keep the headers only?
+	pub fn is_light(&self) -> bool {
+		*self == SyncMode::Light
+	}
+
+	/// Does this mode download the bodies of the blocks it catches up with?
+	pub fn downloads_bodies(&self) -> bool {
+		match self {
+			SyncMode::Full => true,
+			SyncMode::Light | SyncMode::Fast | SyncMode::Warp => false,
+		}
+	}
+
+	/// Does this mode download the state of a recent block instead of executing all the blocks?
+	pub fn downloads_state(&self) -> bool {
+		match self {
+			SyncMode::Fast | SyncMode::Warp => true,
+			SyncMode::Full | SyncMode::Light => false,
+		}
+	}
+
+	/// Is this mode implemented?
+	pub fn is_supported(&self) -> bool {
+		!self.downloads_state()
+	}
+}
+
 /// Finality proof request builder.
 pub trait FinalityProofRequestBuilder<B: BlockT>: Send {
 	/// Build data blob, associated with the request.
@@ -261,6 +320,26 @@ pub struct NetworkConfiguration {
 	pub node_name: String,
 	/// Configuration for the transport layer.
 	pub transport: TransportConfig,
+	/// Maximal size of the digest items sent in block announcements.
+	///
+	/// Larger items, e.g. large seals, are omitted from the announcements sent to the peers that
+	/// support it, which download the complete header if they need it. `None` always sends the
+	/// complete header.
+	pub max_announced_digest_item_size: Option<usize>,
+	/// Maximal delay before propagating transactions to the peers that aren't authorities.
+	///
+	/// Transactions are propagated at once to the peers found by the authority discovery and to
+	/// the peers with the authority role, and to the other peers after a random delay, so that
+	/// they reach the block authors sooner. `None` propagates transactions to all peers at once.
+	pub non_authority_propagation_delay: Option<Duration>,
+	/// Maximal number of downloaded blocks handed over to the import queue at once during sync.
+	/// `None` if default.
+	pub sync_import_batch_size: Option<usize>,
+	/// Maximal number of blocks in the import queue during sync. Downloaded blocks are kept until
+	/// the queue drains below, bounding the memory used by the queue. `None` if default.
+	pub sync_max_queued_blocks: Option<usize>,
+	/// How to sync the chain. `None` to pick the mode matching the roles of the node.
+	pub sync_mode: Option<SyncMode>,
 }
 
 impl Default for NetworkConfiguration {
@@ -282,6 +361,11 @@ impl Default for NetworkConfiguration {
 				enable_mdns: false,
 				wasm_external_transport: None,
 			},
+			max_announced_digest_item_size: None,
+			non_authority_propagation_delay: None,
+			sync_import_batch_size: None,
+			sync_max_queued_blocks: None,
+			sync_mode: None,
 		}
 	}
 }
@@ -521,6 +605,15 @@ mod tests {
 		assert!(secret_bytes(&kp1) == secret_bytes(&kp2));
 	}
 
+	#[test]
+	fn sync_mode_follows_roles() {
+		assert_eq!(SyncMode::for_roles(Roles::LIGHT), SyncMode::Light);
+		assert_eq!(SyncMode::for_roles(Roles::FULL), SyncMode::Full);
+		assert_eq!(SyncMode::for_roles(Roles::AUTHORITY), SyncMode::Full);
+		assert!(SyncMode::Full.is_supported() && SyncMode::Light.is_supported());
+		assert!(!SyncMode::Fast.is_supported() && !SyncMode::Warp.is_supported());
+	}
+
 	#[test]
 	fn test_secret_new() {
 		let kp1 = NodeKeyConfig::Ed25519(Secret::New).into_keypair().unwrap();
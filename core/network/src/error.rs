@@ -17,6 +17,7 @@
 //! Substrate network possible errors.
 
 use client;
+use crate::config::SyncMode;
 
 use libp2p::{PeerId, Multiaddr};
 
@@ -47,6 +48,14 @@ pub enum Error {
 		/// The second peer id that was found for the bootnode.
 		second_id: PeerId,
 	},
+	/// The sync mode can't be used by this node.
+	#[display(fmt = "Sync mode {:?} can't be used: {}", mode, reason)]
+	UnsupportedSyncMode {
+		/// The configured sync mode.
+		mode: SyncMode,
+		/// Why the mode can't be used.
+		reason: &'static str,
+	},
 }
 
 // Make `Debug` use the `Display` implementation.
@@ -62,6 +71,7 @@ impl std::error::Error for Error {
 			Error::Io(ref err) => Some(err),
 			Error::Client(ref err) => Some(err),
 			Error::DuplicateBootnode { .. } => None,
+			Error::UnsupportedSyncMode { .. } => None,
 		}
 	}
 }
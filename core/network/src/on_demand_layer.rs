@@ -25,7 +25,7 @@ use parking_lot::Mutex;
 use client::error::Error as ClientError;
 use client::light::fetcher::{Fetcher, FetchChecker, RemoteHeaderRequest,
 	RemoteCallRequest, RemoteReadRequest, RemoteChangesRequest,
-	RemoteReadChildRequest, RemoteBodyRequest};
+	RemoteReadChildRequest, RemoteBodyRequest, RemoteBlockProofRequest, BlockProof};
 use sr_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 
 /// Implements the `Fetcher` trait of the client. Makes it possible for the light client to perform
@@ -89,6 +89,7 @@ impl<B> Fetcher<B> for OnDemand<B> where
 	type RemoteCallResult = Compat01As03<RemoteResponse<Vec<u8>>>;
 	type RemoteChangesResult = Compat01As03<RemoteResponse<Vec<(NumberFor<B>, u32)>>>;
 	type RemoteBodyResult = Compat01As03<RemoteResponse<Vec<B::Extrinsic>>>;
+	type RemoteBlockProofResult = Compat01As03<RemoteResponse<BlockProof<B::Header>>>;
 
 	fn remote_header(&self, request: RemoteHeaderRequest<B::Header>) -> Self::RemoteHeaderResult {
 		let (sender, receiver) = oneshot::channel();
@@ -128,6 +129,12 @@ impl<B> Fetcher<B> for OnDemand<B> where
 		let _ = self.requests_send.unbounded_send(RequestData::RemoteBody(request, sender));
 		RemoteResponse { receiver }.compat()
 	}
+
+	fn remote_block_proof(&self, request: RemoteBlockProofRequest<B::Header>) -> Self::RemoteBlockProofResult {
+		let (sender, receiver) = oneshot::channel();
+		let _ = self.requests_send.unbounded_send(RequestData::RemoteBlockProof(request, sender));
+		RemoteResponse { receiver }.compat()
+	}
 }
 
 /// Future for an on-demand remote call response.
@@ -32,22 +32,25 @@ use consensus::{
 use codec::{Decode, Encode};
 use sr_primitives::{generic::BlockId, ConsensusEngineId, Justification};
 use sr_primitives::traits::{
-	Block as BlockT, Header as HeaderT, NumberFor, One, Zero,
+	Block as BlockT, Header as HeaderT, Hash as HashT, NumberFor, One, Zero,
 	CheckedSub, SaturatedConversion
 };
-use message::{BlockAnnounce, BlockAttributes, Direction, FromBlock, Message, RequestId};
+use message::{
+	BlockAnnounce, BlockAttributes, CompactBlockAnnounce, Direction, FromBlock, Message, RequestId,
+};
 use message::generic::{Message as GenericMessage, ConsensusMessage};
 use consensus_gossip::{ConsensusGossip, MessageRecipient as GossipMessageRecipient};
 use light_dispatch::{LightDispatch, LightDispatchNetwork, RequestData};
 use specialization::NetworkSpecialization;
 use sync::{ChainSync, SyncState};
 use crate::service::{TransactionPool, ExHashT};
-use crate::config::{BoxFinalityProofRequestBuilder, Roles};
+use crate::config::{BoxFinalityProofRequestBuilder, Roles, SyncMode};
 use rustc_hex::ToHex;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use std::fmt::Write;
 use std::{cmp, num::NonZeroUsize, time};
+use rand::Rng;
 use log::{trace, debug, warn, error};
 use crate::chain::{Client, FinalityProofProvider};
 use client::light::fetcher::{FetchChecker, ChangesProof, StorageProof};
@@ -69,7 +72,7 @@ const TICK_TIMEOUT: time::Duration = time::Duration::from_millis(1100);
 const PROPAGATE_TIMEOUT: time::Duration = time::Duration::from_millis(2900);
 
 /// Current protocol version.
-pub(crate) const CURRENT_VERSION: u32 = 4;
+pub(crate) const CURRENT_VERSION: u32 = 6;
 /// Lowest version we support
 pub(crate) const MIN_VERSION: u32 = 3;
 
@@ -121,6 +124,10 @@ pub struct Protocol<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> {
 	finality_proof_provider: Option<Arc<dyn FinalityProofProvider<B>>>,
 	/// Handles opening the unique substream and sending and receiving raw messages.
 	behaviour: LegacyProto<Substream<StreamMuxerBox>>,
+	/// Peers found by the authority discovery.
+	authority_peers: HashSet<PeerId>,
+	/// Time after which extrinsics may be propagated to peers that aren't authorities.
+	extrinsics_release: HashMap<H, time::Instant>,
 }
 
 #[derive(Default)]
@@ -286,6 +293,22 @@ impl<'a, B: BlockT> LightDispatchNetwork<B> for LightDispatchIn<'a> {
 
 		self.behaviour.send_packet(who, message.encode())
 	}
+
+	fn send_block_proof_request(
+		&mut self,
+		who: &PeerId,
+		id: RequestId,
+		block: <B as BlockT>::Hash,
+		keys: Vec<Vec<u8>>,
+	) {
+		let message: Message<B> = message::generic::Message::RemoteBlockProofRequest(message::RemoteBlockProofRequest {
+			id,
+			block,
+			keys,
+		});
+
+		self.behaviour.send_packet(who, message.encode())
+	}
 }
 
 /// Context for a network-specific handler.
@@ -362,12 +385,33 @@ struct ContextData<B: BlockT, H: ExHashT> {
 pub struct ProtocolConfig {
 	/// Assigned roles.
 	pub roles: Roles,
+	/// How to sync the chain.
+	pub sync_mode: SyncMode,
+	/// Maximal size of the digest items sent in block announcements.
+	///
+	/// Larger items are omitted from the announcements sent to the peers that support it.
+	pub max_announced_digest_item_size: Option<usize>,
+	/// Maximal delay before propagating extrinsics to the peers that aren't authorities.
+	///
+	/// Extrinsics are propagated to the authority peers at once, and to the other peers after a
+	/// random delay. `None` propagates extrinsics to all peers at once.
+	pub non_authority_propagation_delay: Option<time::Duration>,
+	/// Maximal number of downloaded blocks handed over to the import queue at once.
+	pub import_batch_size: usize,
+	/// Maximal number of blocks in the import queue. Downloaded blocks are kept until the queue
+	/// has room for them.
+	pub max_queued_blocks: usize,
 }
 
 impl Default for ProtocolConfig {
 	fn default() -> ProtocolConfig {
 		ProtocolConfig {
 			roles: Roles::FULL,
+			sync_mode: SyncMode::Full,
+			max_announced_digest_item_size: None,
+			non_authority_propagation_delay: None,
+			import_batch_size: sync::IMPORT_BATCH_SIZE,
+			max_queued_blocks: sync::MAX_IMPORTING_BLOCKS,
 		}
 	}
 }
@@ -388,11 +432,13 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 	) -> error::Result<(Protocol<B, S, H>, peerset::PeersetHandle)> {
 		let info = chain.info();
 		let sync = ChainSync::new(
-			config.roles,
+			config.sync_mode,
 			chain.clone(),
 			&info,
 			finality_proof_request_builder,
 			block_announce_validator,
+			config.import_batch_size,
+			config.max_queued_blocks,
 		);
 		let (peerset, peerset_handle) = peerset::Peerset::from_config(peerset_config);
 		let versions = &((MIN_VERSION as u8)..=(CURRENT_VERSION as u8)).collect::<Vec<u8>>();
@@ -417,6 +463,8 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			finality_proof_provider,
 			peerset_handle: peerset_handle.clone(),
 			behaviour,
+			authority_peers: HashSet::new(),
+			extrinsics_release: HashMap::new(),
 		};
 
 		Ok((protocol, peerset_handle))
@@ -570,6 +618,11 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				self.update_peer_info(&who);
 				return outcome;
 			},
+			GenericMessage::CompactBlockAnnounce(announce) => {
+				let outcome = self.on_compact_block_announce(who.clone(), announce);
+				self.update_peer_info(&who);
+				return outcome;
+			},
 			GenericMessage::Transactions(m) =>
 				self.on_extrinsics(who, m),
 			GenericMessage::RemoteCallRequest(request) => self.on_remote_call_request(who, request),
@@ -593,6 +646,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				return self.on_finality_proof_response(who, response),
 			GenericMessage::RemoteReadChildRequest(request) =>
 				self.on_remote_read_child_request(who, request),
+			GenericMessage::RemoteBlockProofRequest(request) =>
+				self.on_remote_block_proof_request(who, request),
+			GenericMessage::RemoteBlockProofResponse(response) =>
+				self.on_remote_block_proof_response(who, response),
 			GenericMessage::Consensus(msg) => {
 				if self.context_data.peers.get(&who).map_or(false, |peer| peer.info.protocol_version > 2) {
 					self.consensus_gossip.on_incoming(
@@ -977,7 +1034,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		self.light_dispatch.on_connect(LightDispatchIn {
 			behaviour: &mut self.behaviour,
 			peerset: self.peerset_handle.clone(),
-		}, who.clone(), status.roles, status.best_number);
+		}, who.clone(), status.roles, status.version, status.best_number);
 		match self.sync.new_peer(who.clone(), info) {
 			Ok(None) => (),
 			Ok(Some(req)) => self.send_request(&who, GenericMessage::BlockRequest(req)),
@@ -1041,6 +1098,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		}
 
 		let extrinsics = self.transaction_pool.transactions();
+		let released = self.released_extrinsics(&extrinsics);
 		let mut propagated_to = HashMap::new();
 		for (who, peer) in self.context_data.peers.iter_mut() {
 			// never send extrinsics to the light node
@@ -1048,8 +1106,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				continue;
 			}
 
+			let is_authority = peer.info.roles.is_authority() || self.authority_peers.contains(who);
 			let (hashes, to_send): (Vec<_>, Vec<_>) = extrinsics
 				.iter()
+				.filter(|&(ref hash, _)| is_authority || released.as_ref().map_or(true, |r| r.contains(hash)))
 				.filter(|&(ref hash, _)| peer.known_extrinsics.insert(hash.clone()))
 				.cloned()
 				.unzip();
@@ -1074,6 +1134,30 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		self.transaction_pool.on_broadcasted(propagated_to);
 	}
 
+	/// Returns the extrinsics that may be propagated to the peers that aren't authorities, or
+	/// `None` if all extrinsics may be.
+	///
+	/// Each extrinsic is released after a random delay from the first time it is propagated, and
+	/// is then sent with the next propagation.
+	fn released_extrinsics(&mut self, extrinsics: &[(H, B::Extrinsic)]) -> Option<HashSet<H>> {
+		let max_delay = match self.config.non_authority_propagation_delay {
+			Some(max_delay) => max_delay,
+			None => return None,
+		};
+
+		Some(release_extrinsics(
+			&mut self.extrinsics_release,
+			extrinsics.iter().map(|(hash, _)| hash),
+			max_delay,
+			time::Instant::now(),
+		))
+	}
+
+	/// Set the peers found by the authority discovery, which extrinsics are propagated to first.
+	pub fn set_authority_peers(&mut self, peers: HashSet<PeerId>) {
+		self.authority_peers = peers;
+	}
+
 	/// Make sure an important block is propagated to peers.
 	///
 	/// In chain-based consensus, we often need to make sure non-best forks are
@@ -1103,12 +1187,25 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 
 	fn send_announcement(&mut self, header: &B::Header, data: Vec<u8>, is_best: bool, force: bool) {
 		let hash = header.hash();
+		let compact = self.config.max_announced_digest_item_size
+			.and_then(|max_size| compact_header::<B>(header, max_size));
 
 		for (who, ref mut peer) in self.context_data.peers.iter_mut() {
 			trace!(target: "sync", "Announcing block {:?} to {}", hash, who);
 			let inserted = peer.known_blocks.insert(hash);
-			if inserted || force {
-				let message: Message<B> = GenericMessage::BlockAnnounce(message::BlockAnnounce {
+			if !inserted && !force {
+				continue
+			}
+			let message: Message<B> = match &compact {
+				Some((header, omitted)) if peer.info.protocol_version >= 5 =>
+					GenericMessage::CompactBlockAnnounce(message::generic::CompactBlockAnnounce {
+						header: header.clone(),
+						hash,
+						omitted: omitted.clone(),
+						state: if is_best { message::BlockState::Best } else { message::BlockState::Normal },
+						data: data.clone(),
+					}),
+				_ => GenericMessage::BlockAnnounce(message::BlockAnnounce {
 					header: header.clone(),
 					state: if peer.info.protocol_version >= 4  {
 						if is_best {
@@ -1124,15 +1221,15 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 					} else {
 						None
 					},
-				});
+				}),
+			};
 
-				send_message::<B> (
-					&mut self.behaviour,
-					&mut self.context_data.stats,
-					&who,
-					message,
-				)
-			}
+			send_message::<B> (
+				&mut self.behaviour,
+				&mut self.context_data.stats,
+				&who,
+				message,
+			)
 		}
 	}
 
@@ -1154,6 +1251,37 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 
 	fn on_block_announce(&mut self, who: PeerId, announce: BlockAnnounce<B::Header>) -> CustomMessageOutcome<B> {
 		let hash = announce.header.hash();
+		self.handle_block_announce(who, hash, announce, true)
+	}
+
+	fn on_compact_block_announce(&mut self, who: PeerId, announce: CompactBlockAnnounce<B>) -> CustomMessageOutcome<B> {
+		trace!(
+			target: "sync",
+			"Compact announcement of {} from {}, {} digest items omitted",
+			announce.hash,
+			who,
+			announce.omitted.len(),
+		);
+		let hash = announce.hash;
+		let announce = BlockAnnounce {
+			header: announce.header,
+			state: Some(announce.state),
+			data: Some(announce.data),
+		};
+		self.handle_block_announce(who, hash, announce, false)
+	}
+
+	/// Handles an announcement of the block `hash`.
+	///
+	/// `complete` is false if digest items have been omitted from the announced header, in which
+	/// case the header must be downloaded before being imported.
+	fn handle_block_announce(
+		&mut self,
+		who: PeerId,
+		hash: B::Hash,
+		announce: BlockAnnounce<B::Header>,
+		complete: bool,
+	) -> CustomMessageOutcome<B> {
 		if let Some(ref mut peer) = self.context_data.peers.get_mut(&who) {
 			peer.known_blocks.insert(hash.clone());
 		}
@@ -1177,6 +1305,12 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				// 2) parent block is already imported and not pruned.
 				return CustomMessageOutcome::None
 			}
+			sync::OnBlockAnnounce::ImportHeader if !complete => {
+				// the header can't be imported without the omitted digest items, download it.
+				let number = *announce.header.number();
+				self.sync.set_sync_fork_request(vec![who], &hash, number);
+				return CustomMessageOutcome::None
+			}
 			sync::OnBlockAnnounce::ImportHeader => () // We proceed with the import.
 		}
 
@@ -1504,6 +1638,58 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		}, who, response);
 	}
 
+	fn on_remote_block_proof_request(
+		&mut self,
+		who: PeerId,
+		request: message::RemoteBlockProofRequest<B::Hash>,
+	) {
+		trace!(target: "sync", "Remote block proof request {} from {} ({} keys at {})",
+			request.id, who, request.keys.len(), request.block);
+		let id = BlockId::Hash(request.block);
+		let chain = &self.context_data.chain;
+		let response = chain.header(&id).and_then(|header| match header {
+			Some(header) => Ok((
+				Some(header),
+				chain.justification(&id)?,
+				chain.read_proof(&request.block, &request.keys)?,
+			)),
+			None => Ok((None, None, StorageProof::empty())),
+		});
+		let (header, justification, proof) = match response {
+			Ok(response) => response,
+			Err(error) => {
+				trace!(target: "sync", "Remote block proof request {} from {} ({}) failed with: {}",
+					request.id,
+					who,
+					request.block,
+					error
+				);
+				(None, None, StorageProof::empty())
+			}
+		};
+		self.send_message(
+			&who,
+			GenericMessage::RemoteBlockProofResponse(message::RemoteBlockProofResponse {
+				id: request.id,
+				header,
+				justification,
+				proof,
+			}),
+		);
+	}
+
+	fn on_remote_block_proof_response(
+		&mut self,
+		who: PeerId,
+		response: message::RemoteBlockProofResponse<B::Header>,
+	) {
+		trace!(target: "sync", "Remote block proof response {} from {}", response.id, who);
+		self.light_dispatch.on_remote_block_proof_response(LightDispatchIn {
+			behaviour: &mut self.behaviour,
+			peerset: self.peerset_handle.clone(),
+		}, who, response);
+	}
+
 	fn on_remote_changes_request(
 		&mut self,
 		who: PeerId,
@@ -1701,6 +1887,33 @@ fn send_message<B: BlockT>(
 	behaviour.send_packet(who, encoded);
 }
 
+/// Omits the digest items of `header` whose encoding is larger than `max_item_size`.
+///
+/// Returns the header without these items and the position and hash of each omitted item, or
+/// `None` if no item is omitted.
+fn compact_header<B: BlockT>(
+	header: &B::Header,
+	max_item_size: usize,
+) -> Option<(B::Header, Vec<(u32, B::Hash)>)> {
+	let mut compact = header.clone();
+	let mut omitted = Vec::new();
+	let mut index = 0;
+	compact.digest_mut().logs.retain(|item| {
+		let keep = item.using_encoded(|encoded| encoded.len()) <= max_item_size;
+		if !keep {
+			omitted.push((index, <<B::Header as HeaderT>::Hashing as HashT>::hash_of(item)));
+		}
+		index += 1;
+		keep
+	});
+
+	if omitted.is_empty() {
+		None
+	} else {
+		Some((compact, omitted))
+	}
+}
+
 impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> NetworkBehaviour for
 Protocol<B, S, H> {
 	type ProtocolsHandler = <LegacyProto<Substream<StreamMuxerBox>> as NetworkBehaviour>::ProtocolsHandler;
@@ -1774,6 +1987,13 @@ Protocol<B, S, H> {
 				GenericMessage::FinalityProofRequest(r))
 		}
 
+		if let Some((origin, blocks)) = self.sync.take_ready_orphans() {
+			return Async::Ready(NetworkBehaviourAction::GenerateEvent(CustomMessageOutcome::BlockImport(origin, blocks)))
+		}
+		if let Some((origin, blocks)) = self.sync.take_downloaded_blocks() {
+			return Async::Ready(NetworkBehaviourAction::GenerateEvent(CustomMessageOutcome::BlockImport(origin, blocks)))
+		}
+
 		let event = match self.behaviour.poll(params) {
 			Async::NotReady => return Async::NotReady,
 			Async::Ready(NetworkBehaviourAction::GenerateEvent(ev)) => ev,
@@ -1861,3 +2081,81 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Drop for Protocol<B, S,
 		debug!(target: "sync", "Network stats:\n{}", self.format_stats());
 	}
 }
+
+/// Returns the extrinsics among `extrinsics` whose release time has passed at `now`.
+///
+/// Extrinsics seen for the first time are given a random release time of at most `max_delay`
+/// after `now`. The release times of the extrinsics not in `extrinsics` are forgotten.
+fn release_extrinsics<'a, H: ExHashT>(
+	release: &mut HashMap<H, time::Instant>,
+	extrinsics: impl Iterator<Item = &'a H>,
+	max_delay: time::Duration,
+	now: time::Instant,
+) -> HashSet<H> {
+	let extrinsics = extrinsics.collect::<HashSet<_>>();
+	release.retain(|hash, _| extrinsics.contains(hash));
+
+	let mut rng = rand::thread_rng();
+	extrinsics.into_iter()
+		.filter(|hash| {
+			let at = *release.entry((*hash).clone()).or_insert_with(|| {
+				now + time::Duration::from_millis(rng.gen_range(0, max_delay.as_millis() as u64 + 1))
+			});
+			at <= now
+		})
+		.cloned()
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{compact_header, release_extrinsics};
+	use std::collections::HashMap;
+	use std::time::{Duration, Instant};
+	use primitives::H256;
+	use sr_primitives::generic::DigestItem;
+	use sr_primitives::testing::{Block as RawBlock, ExtrinsicWrapper, Header, Digest};
+	use sr_primitives::traits::{BlakeTwo256, Hash, Header as HeaderT};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	#[test]
+	fn large_digest_items_are_omitted() {
+		let seal = DigestItem::Seal(*b"test", vec![0; 1024]);
+		let pre_digest = DigestItem::PreRuntime(*b"test", vec![1; 16]);
+		let header = Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			H256::random(),
+			Digest { logs: vec![pre_digest.clone(), seal.clone()] },
+		);
+
+		let (compact, omitted) = compact_header::<Block>(&header, 64).unwrap();
+		assert_eq!(compact.digest().logs, vec![pre_digest]);
+		assert_eq!(compact.parent_hash(), header.parent_hash());
+		assert_eq!(omitted, vec![(1, BlakeTwo256::hash_of(&seal))]);
+
+		assert!(compact_header::<Block>(&header, 2048).is_none());
+	}
+
+	#[test]
+	fn extrinsics_are_released_after_the_delay() {
+		let mut release = HashMap::new();
+		let now = Instant::now();
+		let hour = Duration::from_secs(3600);
+
+		assert!(release_extrinsics(&mut release, [1u64, 2].iter(), hour, now).is_empty());
+		assert_eq!(release.len(), 2);
+
+		// the release time of extrinsics is kept until they leave the pool.
+		let released = release_extrinsics(&mut release, [1u64, 3].iter(), hour, now + hour);
+		assert!(released.contains(&1));
+		assert!(!released.contains(&2));
+		assert!(!release.contains_key(&2));
+
+		let released = release_extrinsics(&mut release, [4u64].iter(), Duration::from_secs(0), now);
+		assert_eq!(released.into_iter().collect::<Vec<_>>(), vec![4]);
+		assert_eq!(release.len(), 1);
+	}
+}
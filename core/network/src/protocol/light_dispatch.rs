@@ -28,10 +28,11 @@ use linked_hash_map::{Entry, LinkedHashMap};
 use client::error::Error as ClientError;
 use client::light::fetcher::{FetchChecker, RemoteHeaderRequest,
 	RemoteCallRequest, RemoteReadRequest, RemoteChangesRequest, ChangesProof,
-	RemoteReadChildRequest, RemoteBodyRequest, StorageProof};
+	RemoteReadChildRequest, RemoteBodyRequest, RemoteBlockProofRequest, BlockProof, StorageProof};
 use crate::message::{self, BlockAttributes, Direction, FromBlock, RequestId};
 use libp2p::PeerId;
 use crate::config::Roles;
+use sr_primitives::Justification;
 use sr_primitives::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 
 /// Remote request timeout.
@@ -40,6 +41,8 @@ const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
 const RETRY_COUNT: usize = 1;
 /// Reputation change for a peer when a request timed out.
 const TIMEOUT_REPUTATION_CHANGE: i32 = -(1 << 8);
+/// Lowest protocol version of the peers able to answer block proof requests.
+const BLOCK_PROOF_MIN_VERSION: u32 = 6;
 
 /// Trait used by the `LightDispatch` service to communicate messages back to the network.
 pub trait LightDispatchNetwork<B: BlockT> {
@@ -105,6 +108,15 @@ pub trait LightDispatchNetwork<B: BlockT> {
 		direction: Direction,
 		max: Option<u32>
 	);
+
+	/// Send to `who` a block proof request.
+	fn send_block_proof_request(
+		&mut self,
+		who: &PeerId,
+		id: RequestId,
+		block: <B as BlockT>::Hash,
+		keys: Vec<Vec<u8>>,
+	);
 }
 
 /// Light client requests service. Dispatches requests to appropriate peers.
@@ -123,6 +135,8 @@ pub struct LightDispatch<B: BlockT> {
 	idle_peers: VecDeque<PeerId>,
 	/// Best known block for each node in `active_peers` and `idle_peers`.
 	best_blocks: HashMap<PeerId, NumberFor<B>>,
+	/// Protocol version of each node in `active_peers` and `idle_peers`.
+	protocol_versions: HashMap<PeerId, u32>,
 }
 
 struct Request<Block: BlockT> {
@@ -154,6 +168,10 @@ pub(crate) enum RequestData<Block: BlockT> {
 		RemoteChangesRequest<Block::Header>,
 		OneShotSender<Result<Vec<(NumberFor<Block>, u32)>, ClientError>>
 	),
+	RemoteBlockProof(
+		RemoteBlockProofRequest<Block::Header>,
+		OneShotSender<Result<BlockProof<Block::Header>, ClientError>>
+	),
 }
 
 enum Accept<Block: BlockT> {
@@ -218,6 +236,16 @@ impl<Block: BlockT> FetchChecker<Block> for AlwaysBadChecker {
 	) -> Result<Vec<Block::Extrinsic>, ClientError> {
 		Err(ClientError::Msg("AlwaysBadChecker".into()))
 	}
+
+	fn check_block_proof(
+		&self,
+		_request: &RemoteBlockProofRequest<Block::Header>,
+		_header: Option<Block::Header>,
+		_justification: Option<Justification>,
+		_remote_proof: StorageProof,
+	) -> Result<BlockProof<Block::Header>, ClientError> {
+		Err(ClientError::Msg("AlwaysBadChecker".into()))
+	}
 }
 
 impl<B: BlockT> LightDispatch<B> where
@@ -232,6 +260,7 @@ impl<B: BlockT> LightDispatch<B> where
 			active_peers: LinkedHashMap::new(),
 			idle_peers: VecDeque::new(),
 			best_blocks: HashMap::new(),
+			protocol_versions: HashMap::new(),
 		}
 	}
 
@@ -314,6 +343,7 @@ impl<B: BlockT> LightDispatch<B> where
 		network: impl LightDispatchNetwork<B>,
 		peer: PeerId,
 		role: Roles,
+		protocol_version: u32,
 		best_number: NumberFor<B>
 	) {
 		if !role.is_full() {
@@ -321,6 +351,7 @@ impl<B: BlockT> LightDispatch<B> where
 		}
 
 		self.idle_peers.push_back(peer.clone());
+		self.protocol_versions.insert(peer.clone(), protocol_version);
 		self.best_blocks.insert(peer, best_number);
 
 		self.dispatch(network);
@@ -499,6 +530,31 @@ impl<B: BlockT> LightDispatch<B> where
 		})
 	}
 
+	/// Handles a remote block proof response message from on the network.
+	pub fn on_remote_block_proof_response(
+		&mut self,
+		network: impl LightDispatchNetwork<B>,
+		peer: PeerId,
+		response: message::RemoteBlockProofResponse<B::Header>
+	) {
+		self.accept_response("block proof", network, peer, response.id, |request, checker| match request.data {
+			RequestData::RemoteBlockProof(request, sender) => match checker.check_block_proof(
+				&request,
+				response.header,
+				response.justification,
+				response.proof,
+			) {
+				Ok(response) => {
+					// we do not bother if receiver has been dropped already
+					let _ = sender.send(Ok(response));
+					Accept::Ok
+				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteBlockProof(request, sender)),
+			},
+			data => Accept::Unexpected(data),
+		})
+	}
+
 	pub fn is_light_response(&self, peer: &PeerId, request_id: message::RequestId) -> bool {
 		self.active_peers.get(&peer).map_or(false, |r| r.id == request_id)
 	}
@@ -521,6 +577,7 @@ impl<B: BlockT> LightDispatch<B> where
 	/// Puts back the active request that this node was performing into `pending_requests`.
 	fn remove_peer(&mut self, peer: PeerId) {
 		self.best_blocks.remove(&peer);
+		self.protocol_versions.remove(&peer);
 
 		if let Some(request) = self.active_peers.remove(&peer) {
 			self.pending_requests.push_front(request);
@@ -556,7 +613,9 @@ impl<B: BlockT> LightDispatch<B> where
 					.expect("entries are inserted into best_blocks when peer is connected;
 						entries are removed from best_blocks when peer is disconnected;
 						peer is in idle_peers and thus connected; qed");
-				request.required_block() <= *peer_best_block
+				let peer_protocol_version = self.protocol_versions.get(&peer).cloned().unwrap_or(0);
+				request.required_block() <= *peer_best_block &&
+					request.required_protocol_version() <= peer_protocol_version
 			};
 
 			if !can_be_processed_by_peer {
@@ -597,6 +656,16 @@ impl<Block: BlockT> Request<Block> {
 			RequestData::RemoteCall(ref data, _) => *data.header.number(),
 			RequestData::RemoteChanges(ref data, _) => data.max_block.0,
 			RequestData::RemoteBody(ref data, _) => *data.header.number(),
+			RequestData::RemoteBlockProof(ref data, _) => data.number,
+		}
+	}
+
+	/// Returns the lowest protocol version that the remote needs to support in order to be able
+	/// to fulfill this request.
+	fn required_protocol_version(&self) -> u32 {
+		match self.data {
+			RequestData::RemoteBlockProof(..) => BLOCK_PROOF_MIN_VERSION,
+			_ => 0,
 		}
 	}
 
@@ -652,6 +721,13 @@ impl<Block: BlockT> Request<Block> {
 					message::Direction::Ascending,
 					Some(1)
 				),
+			RequestData::RemoteBlockProof(ref data, _) =>
+				out.send_block_proof_request(
+					peer,
+					self.id,
+					data.block,
+					data.keys.clone(),
+				),
 		}
 	}
 }
@@ -666,6 +742,7 @@ impl<Block: BlockT> RequestData<Block> {
 			RequestData::RemoteReadChild(_, sender) => { let _ = sender.send(Err(error)); },
 			RequestData::RemoteChanges(_, sender) => { let _ = sender.send(Err(error)); },
 			RequestData::RemoteBody(_, sender) => { let _ = sender.send(Err(error)); },
+			RequestData::RemoteBlockProof(_, sender) => { let _ = sender.send(Err(error)); },
 		}
 	}
 }
@@ -676,12 +753,14 @@ pub mod tests {
 	use std::sync::Arc;
 	use std::time::Instant;
 	use futures::{Future, sync::oneshot};
+	use sr_primitives::Justification;
 	use sr_primitives::traits::{Block as BlockT, NumberFor, Header as HeaderT};
 	use client::{error::{Error as ClientError, Result as ClientResult}};
 	use client::light::fetcher::{FetchChecker, RemoteHeaderRequest,
 		ChangesProof, RemoteCallRequest, RemoteReadRequest,
-		RemoteReadChildRequest, RemoteChangesRequest, RemoteBodyRequest};
+		RemoteReadChildRequest, RemoteChangesRequest, RemoteBodyRequest, RemoteBlockProofRequest, BlockProof};
 	use crate::config::Roles;
+	use crate::protocol::CURRENT_VERSION;
 	use crate::message::{self, BlockAttributes, Direction, FromBlock, RequestId};
 	use libp2p::PeerId;
 	use super::{REQUEST_TIMEOUT, LightDispatch, LightDispatchNetwork, RequestData, StorageProof};
@@ -762,6 +841,23 @@ pub mod tests {
 				false => Err(ClientError::Backend("Test error".into())),
 			}
 		}
+
+		fn check_block_proof(
+			&self,
+			request: &RemoteBlockProofRequest<Header>,
+			header: Option<Header>,
+			justification: Option<Justification>,
+			_: StorageProof,
+		) -> ClientResult<BlockProof<Header>> {
+			match self.ok {
+				true if header.is_some() => Ok(BlockProof {
+					header: header.unwrap(),
+					justification,
+					values: request.keys.iter().cloned().map(|k| (k, Some(vec![42]))).collect(),
+				}),
+				_ => Err(ClientError::Backend("Test error".into())),
+			}
+		}
 	}
 
 	fn dummy(ok: bool) -> LightDispatch<Block> {
@@ -813,6 +909,7 @@ pub mod tests {
 			_: <B as BlockT>::Hash, _: <B as BlockT>::Hash, _: Option<Vec<u8>>, _: Vec<u8>) {}
 		fn send_body_request(&mut self, _: &PeerId, _: RequestId, _: BlockAttributes, _: FromBlock<<B as BlockT>::Hash,
 			<<B as BlockT>::Header as HeaderT>::Number>, _: Option<B::Hash>, _: Direction, _: Option<u32>) {}
+		fn send_block_proof_request(&mut self, _: &PeerId, _: RequestId, _: <B as BlockT>::Hash, _: Vec<Vec<u8>>) {}
 	}
 
 	fn assert_disconnected_peer(dummy: &DummyNetwork) {
@@ -826,9 +923,9 @@ pub mod tests {
 		let peer0 = PeerId::random();
 		let peer1 = PeerId::random();
 		let peer2 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0, Roles::LIGHT, 1000);
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 2000);
-		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::AUTHORITY, 3000);
+		light_dispatch.on_connect(&mut network_interface, peer0, Roles::LIGHT, CURRENT_VERSION, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 2000);
+		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::AUTHORITY, CURRENT_VERSION, 3000);
 		assert_eq!(vec![peer1.clone(), peer2.clone()], light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>());
 		assert_eq!(light_dispatch.best_blocks.get(&peer1), Some(&2000));
 		assert_eq!(light_dispatch.best_blocks.get(&peer2), Some(&3000));
@@ -840,7 +937,7 @@ pub mod tests {
 
 		let mut network_interface = DummyNetwork::default();
 		let mut light_dispatch = dummy(true);
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 100);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 100);
 		assert_eq!(1, total_peers(&light_dispatch));
 		assert!(!light_dispatch.best_blocks.is_empty());
 
@@ -855,8 +952,8 @@ pub mod tests {
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
 		let peer1 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 		assert_eq!(vec![peer0.clone(), peer1.clone()], light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>());
 		assert!(light_dispatch.active_peers.is_empty());
 
@@ -882,7 +979,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let peer0 = PeerId::random();
 		let mut network_interface = DummyNetwork::default();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteCall(RemoteCallRequest {
 			block: Default::default(),
@@ -909,7 +1006,7 @@ pub mod tests {
 			retry_count: Some(1),
 		}, oneshot::channel().0));
 
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 		receive_call_response(&mut network_interface, &mut light_dispatch, peer0.clone(), 0);
 		assert_disconnected_peer(&network_interface);
 		assert_eq!(light_dispatch.pending_requests.len(), 1);
@@ -920,7 +1017,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		receive_call_response(&mut network_interface, &mut light_dispatch, peer0, 0);
 		assert_disconnected_peer(&network_interface);
@@ -931,7 +1028,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(false);
 		let peer0 = PeerId::random();
 		let mut network_interface = DummyNetwork::default();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteCall(RemoteCallRequest {
 			block: Default::default(),
@@ -956,7 +1053,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(false);
 		let mut network_interface = DummyNetwork::default();
 		for i in 0..retry_count+1 {
-			light_dispatch.on_connect(&mut network_interface, peer_ids[i].clone(), Roles::FULL, 1000);
+			light_dispatch.on_connect(&mut network_interface, peer_ids[i].clone(), Roles::FULL, CURRENT_VERSION, 1000);
 		}
 
 		let (tx, mut response) = oneshot::channel();
@@ -981,7 +1078,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		let (tx, response) = oneshot::channel();
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteCall(RemoteCallRequest {
@@ -1001,7 +1098,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		let (tx, response) = oneshot::channel();
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteRead(RemoteReadRequest {
@@ -1023,7 +1120,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		let (tx, response) = oneshot::channel();
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteReadChild(RemoteReadChildRequest {
@@ -1047,7 +1144,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		let (tx, response) = oneshot::channel();
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteHeader(RemoteHeaderRequest {
@@ -1078,7 +1175,7 @@ pub mod tests {
 		let mut light_dispatch = dummy(true);
 		let mut network_interface = DummyNetwork::default();
 		let peer0 = PeerId::random();
-		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, 1000);
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
 
 		let (tx, response) = oneshot::channel();
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteChanges(RemoteChangesRequest {
@@ -1102,6 +1199,57 @@ pub mod tests {
 		assert_eq!(response.wait().unwrap().unwrap(), vec![(100, 2)]);
 	}
 
+	#[test]
+	fn receives_remote_block_proof_response() {
+		let mut light_dispatch = dummy(true);
+		let mut network_interface = DummyNetwork::default();
+		let peer0 = PeerId::random();
+		light_dispatch.on_connect(&mut network_interface, peer0.clone(), Roles::FULL, CURRENT_VERSION, 1000);
+
+		let (tx, response) = oneshot::channel();
+		light_dispatch.add_request(&mut network_interface, RequestData::RemoteBlockProof(RemoteBlockProofRequest {
+			block: dummy_header().hash(),
+			number: 0,
+			keys: vec![b":key".to_vec()],
+			retry_count: None,
+		}, tx));
+
+		light_dispatch.on_remote_block_proof_response(&mut network_interface, peer0.clone(),
+			message::RemoteBlockProofResponse {
+				id: 0,
+				header: Some(dummy_header()),
+				justification: Some(vec![1]),
+				proof: StorageProof::empty(),
+		});
+		let mut proof = response.wait().unwrap().unwrap();
+		assert_eq!(proof.header, dummy_header());
+		assert_eq!(proof.justification, Some(vec![1]));
+		assert_eq!(proof.values.remove(b":key".as_ref()).unwrap(), Some(vec![42]));
+	}
+
+	#[test]
+	fn does_not_send_block_proof_request_to_outdated_peer() {
+		let mut light_dispatch = dummy(true);
+		let mut network_interface = DummyNetwork::default();
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 5, 1000);
+
+		light_dispatch.add_request(&mut network_interface, RequestData::RemoteBlockProof(RemoteBlockProofRequest {
+			block: Default::default(),
+			number: 100,
+			keys: vec![],
+			retry_count: None,
+		}, oneshot::channel().0));
+		assert_eq!(light_dispatch.pending_requests.len(), 1);
+		assert!(light_dispatch.active_peers.is_empty());
+
+		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::FULL, CURRENT_VERSION, 1000);
+		assert!(light_dispatch.pending_requests.is_empty());
+		assert_eq!(vec![peer2], light_dispatch.active_peers.keys().cloned().collect::<Vec<_>>());
+		assert_eq!(vec![peer1], light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>());
+	}
+
 	#[test]
 	fn does_not_sends_request_to_peer_who_has_no_required_block() {
 		let mut light_dispatch = dummy(true);
@@ -1109,7 +1257,7 @@ pub mod tests {
 		let peer1 = PeerId::random();
 		let peer2 = PeerId::random();
 
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 100);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 100);
 
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteHeader(RemoteHeaderRequest {
 			cht_root: Default::default(),
@@ -1127,7 +1275,7 @@ pub mod tests {
 			retry_count: None,
 		}, oneshot::channel().0));
 
-		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::FULL, 150);
+		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::FULL, CURRENT_VERSION, 150);
 
 		assert_eq!(vec![peer1.clone(), peer2.clone()], light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>());
 		assert_eq!(light_dispatch.pending_requests.len(), 3);
@@ -1174,9 +1322,9 @@ pub mod tests {
 			retry_count: None,
 		}, oneshot::channel().0));
 
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 200);
-		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::FULL, 200);
-		light_dispatch.on_connect(&mut network_interface, peer3.clone(), Roles::FULL, 250);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 200);
+		light_dispatch.on_connect(&mut network_interface, peer2.clone(), Roles::FULL, CURRENT_VERSION, 200);
+		light_dispatch.on_connect(&mut network_interface, peer3.clone(), Roles::FULL, CURRENT_VERSION, 250);
 
 		assert_eq!(vec![peer1.clone(), peer2.clone()], light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>());
 		assert_eq!(light_dispatch.pending_requests.len(), 1);
@@ -1199,7 +1347,7 @@ pub mod tests {
 			retry_count: None,
 		}, oneshot::channel().0));
 
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 250);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 250);
 
 		assert!(light_dispatch.idle_peers.iter().cloned().collect::<Vec<_>>().is_empty());
 		assert_eq!(light_dispatch.pending_requests.len(), 1);
@@ -1212,7 +1360,7 @@ pub mod tests {
 		let peer1 = PeerId::random();
 
 		let header = dummy_header();
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 250);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 250);
 
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteBody(RemoteBodyRequest {
 			header: header.clone(),
@@ -1249,7 +1397,7 @@ pub mod tests {
 		let peer1 = PeerId::random();
 
 		let header = dummy_header();
-		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, 250);
+		light_dispatch.on_connect(&mut network_interface, peer1.clone(), Roles::FULL, CURRENT_VERSION, 250);
 
 		light_dispatch.add_request(&mut network_interface, RequestData::RemoteBody(RemoteBodyRequest {
 			header: header.clone(),
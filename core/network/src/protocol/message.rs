@@ -20,11 +20,12 @@ use bitflags::bitflags;
 use sr_primitives::{ConsensusEngineId, traits::{Block as BlockT, Header as HeaderT}};
 use codec::{Encode, Decode, Input, Output, Error};
 pub use self::generic::{
-	BlockAnnounce, RemoteCallRequest, RemoteReadRequest,
+	BlockAnnounce, CompactBlockAnnounce, RemoteCallRequest, RemoteReadRequest,
 	RemoteHeaderRequest, RemoteHeaderResponse,
 	RemoteChangesRequest, RemoteChangesResponse,
 	FinalityProofRequest, FinalityProofResponse,
 	FromBlock, RemoteReadChildRequest,
+	RemoteBlockProofRequest, RemoteBlockProofResponse,
 };
 use client::light::fetcher::StorageProof;
 
@@ -65,6 +66,12 @@ pub type BlockResponse<B> = generic::BlockResponse<
 	<B as BlockT>::Extrinsic,
 >;
 
+/// Type alias for using the CompactBlockAnnounce type using block type parameters.
+pub type CompactBlockAnnounce<B> = generic::CompactBlockAnnounce<
+	<B as BlockT>::Header,
+	<B as BlockT>::Hash,
+>;
+
 /// A set of transactions.
 pub type Transactions<E> = Vec<E>;
 
@@ -217,6 +224,12 @@ pub mod generic {
 		FinalityProofRequest(FinalityProofRequest<Hash>),
 		/// Finality proof reponse.
 		FinalityProofResponse(FinalityProofResponse<Hash>),
+		/// Block announce with large digest items omitted.
+		CompactBlockAnnounce(CompactBlockAnnounce<Header, Hash>),
+		/// Remote block proof request.
+		RemoteBlockProofRequest(RemoteBlockProofRequest<Hash>),
+		/// Remote block proof response.
+		RemoteBlockProofResponse(RemoteBlockProofResponse<Header>),
 		/// Chain-specific message.
 		#[codec(index = "255")]
 		ChainSpecific(Vec<u8>),
@@ -243,6 +256,9 @@ pub mod generic {
 				Message::RemoteReadChildRequest(_) => "RemoteReadChildRequest",
 				Message::FinalityProofRequest(_) => "FinalityProofRequest",
 				Message::FinalityProofResponse(_) => "FinalityProofResponse",
+				Message::CompactBlockAnnounce(_) => "CompactBlockAnnounce",
+				Message::RemoteBlockProofRequest(_) => "RemoteBlockProofRequest",
+				Message::RemoteBlockProofResponse(_) => "RemoteBlockProofResponse",
 				Message::ChainSpecific(_) => "ChainSpecific",
 			}
 		}
@@ -332,6 +348,25 @@ pub mod generic {
 		}
 	}
 
+	/// Announce a new block, with the large digest items of its header omitted.
+	///
+	/// Sent instead of `BlockAnnounce` to peers supporting protocol version 5 when the header has
+	/// large digest items, e.g. seals. The complete header is downloaded from the peer if needed.
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	pub struct CompactBlockAnnounce<H, Hash> {
+		/// Header of the block, without the omitted digest items.
+		pub header: H,
+		/// Hash of the complete header.
+		pub hash: Hash,
+		/// Positions of the omitted items in the digest of the complete header, with the hash of
+		/// each item.
+		pub omitted: Vec<(u32, Hash)>,
+		/// Block state.
+		pub state: BlockState,
+		/// Data associated with this block announcement, e.g. a candidate message.
+		pub data: Vec<u8>,
+	}
+
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	/// Remote call request.
 	pub struct RemoteCallRequest<H> {
@@ -389,6 +424,33 @@ pub mod generic {
 		pub proof: StorageProof,
 	}
 
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Remote block proof request.
+	///
+	/// Asks for the header of a block, its justification and a read proof of storage keys at
+	/// this block at once. Only sent to peers supporting protocol version 6.
+	pub struct RemoteBlockProofRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Hash of the block.
+		pub block: H,
+		/// Storage keys to read at the block.
+		pub keys: Vec<Vec<u8>>,
+	}
+
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Remote block proof response.
+	pub struct RemoteBlockProofResponse<Header> {
+		/// Id of a request this response was made for.
+		pub id: RequestId,
+		/// Header. None if the block is unknown.
+		pub header: Option<Header>,
+		/// Justification of the block, if any.
+		pub justification: Option<Justification>,
+		/// Read proof of the requested keys.
+		pub proof: StorageProof,
+	}
+
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	/// Remote changes request.
 	pub struct RemoteChangesRequest<H> {
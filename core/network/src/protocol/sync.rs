@@ -34,7 +34,7 @@ use consensus::{BlockOrigin, BlockStatus,
 	import_queue::{IncomingBlock, BlockImportResult, BlockImportError}
 };
 use crate::{
-	config::{Roles, BoxFinalityProofRequestBuilder},
+	config::{BoxFinalityProofRequestBuilder, SyncMode},
 	message::{self, generic::FinalityProofRequest, BlockAnnounce, BlockAttributes, BlockRequest, BlockResponse,
 	FinalityProofResponse},
 	protocol
@@ -43,21 +43,28 @@ use either::Either;
 use extra_requests::ExtraRequests;
 use libp2p::PeerId;
 use log::{debug, trace, warn, info, error};
+use orphans::{OrphanPool, MAX_ORPHAN_BLOCKS, ORPHAN_TTL};
 use sr_primitives::{
 	Justification,
 	generic::BlockId,
 	traits::{Block as BlockT, Header, NumberFor, Zero, One, CheckedSub, SaturatedConversion}
 };
-use std::{fmt, ops::Range, collections::{HashMap, HashSet, VecDeque}, sync::Arc};
+use std::{cmp, fmt, ops::Range, collections::{HashMap, HashSet, VecDeque}, sync::Arc};
+use throughput::PeerThroughput;
 
 mod blocks;
 mod extra_requests;
+mod orphans;
+mod throughput;
 
 /// Maximum blocks to request in a single packet.
 const MAX_BLOCKS_TO_REQUEST: usize = 128;
 
-/// Maximum blocks to store in the import queue.
-const MAX_IMPORTING_BLOCKS: usize = 2048;
+/// Default maximum blocks to store in the import queue.
+pub const MAX_IMPORTING_BLOCKS: usize = 2048;
+
+/// Default maximum downloaded blocks to hand over to the import queue at once.
+pub const IMPORT_BATCH_SIZE: usize = 256;
 
 /// We use a heuristic that with a high likelihood, by the time
 /// `MAJOR_SYNC_BLOCKS` have been imported we'll be on the same
@@ -106,10 +113,10 @@ pub struct ChainSync<B: BlockT> {
 	best_queued_number: NumberFor<B>,
 	/// The best block hash in our queue of blocks to import
 	best_queued_hash: B::Hash,
-	/// The role of this node, e.g. light or full
-	role: Roles,
+	/// How the chain is synced.
+	mode: SyncMode,
 	/// What block attributes we require for this node, usually derived from
-	/// what role we are, but could be customized
+	/// the sync mode, but could be customized
 	required_block_attributes: message::BlockAttributes,
 	/// Any extra finality proof requests.
 	extra_finality_proofs: ExtraRequests<B>,
@@ -127,7 +134,16 @@ pub struct ChainSync<B: BlockT> {
 	/// A flag that caches idle state with no pending requests.
 	is_idle: bool,
 	/// A type to check incoming block announcements.
-	block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>
+	block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
+	/// Downloaded blocks waiting for their parent to be imported.
+	orphans: OrphanPool<B>,
+	/// Orphan blocks whose parent has been imported, to be queued for import.
+	ready_orphans: Vec<IncomingBlock<B>>,
+	/// Maximum downloaded blocks to hand over to the import queue at once.
+	import_batch_size: usize,
+	/// Maximum blocks in the import queue. Downloaded blocks are kept in the block collection
+	/// until the queue has room for them, and no new blocks are requested while it is full.
+	max_queued_blocks: usize,
 }
 
 /// All the data we have about a Peer that we are trying to sync with
@@ -145,7 +161,9 @@ pub struct PeerSync<B: BlockT> {
 	pub state: PeerSyncState<B>,
 	/// A queue of blocks that this peer has announced to us, should only
 	/// contain `ANNOUNCE_HISTORY_SIZE` entries.
-	pub recently_announced: VecDeque<B::Hash>
+	pub recently_announced: VecDeque<B::Hash>,
+	/// The measured block download throughput of this peer.
+	pub throughput: PeerThroughput,
 }
 
 /// The sync status of a peer we are trying to sync with
@@ -215,6 +233,8 @@ pub struct Status<B: BlockT> {
 	pub num_peers: u32,
 	/// Number of blocks queued for import
 	pub queued_blocks: u32,
+	/// Number of downloaded blocks waiting for their parent to be imported.
+	pub orphan_blocks: u32,
 }
 
 /// A peer did not behave as expected and should be reported.
@@ -278,15 +298,17 @@ pub enum OnBlockFinalityProof<B: BlockT> {
 impl<B: BlockT> ChainSync<B> {
 	/// Create a new instance.
 	pub fn new(
-		role: Roles,
+		mode: SyncMode,
 		client: Arc<dyn crate::chain::Client<B>>,
 		info: &ClientInfo<B>,
 		request_builder: Option<BoxFinalityProofRequestBuilder<B>>,
-		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>
+		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
+		import_batch_size: usize,
+		max_queued_blocks: usize,
 	) -> Self {
 		let mut required_block_attributes = BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION;
 
-		if role.is_full() {
+		if mode.downloads_bodies() {
 			required_block_attributes |= BlockAttributes::BODY
 		}
 
@@ -298,7 +320,7 @@ impl<B: BlockT> ChainSync<B> {
 			best_queued_number: info.chain.best_number,
 			extra_finality_proofs: ExtraRequests::new(),
 			extra_justifications: ExtraRequests::new(),
-			role,
+			mode,
 			required_block_attributes,
 			queue_blocks: Default::default(),
 			best_importing_number: Zero::zero(),
@@ -306,6 +328,10 @@ impl<B: BlockT> ChainSync<B> {
 			fork_targets: Default::default(),
 			is_idle: false,
 			block_announce_validator,
+			orphans: OrphanPool::new(MAX_ORPHAN_BLOCKS, ORPHAN_TTL),
+			ready_orphans: Vec::new(),
+			import_batch_size,
+			max_queued_blocks,
 		}
 	}
 
@@ -337,6 +363,7 @@ impl<B: BlockT> ChainSync<B> {
 			best_seen_block: best_seen,
 			num_peers: self.peers.len() as u32,
 			queued_blocks: self.queue_blocks.len() as u32,
+			orphan_blocks: self.orphans.len() as u32,
 		}
 	}
 
@@ -377,7 +404,8 @@ impl<B: BlockT> ChainSync<B> {
 						best_hash: info.best_hash,
 						best_number: info.best_number,
 						state: PeerSyncState::Available,
-						recently_announced: Default::default()
+						recently_announced: Default::default(),
+						throughput: Default::default(),
 					});
 					return Ok(None)
 				}
@@ -391,6 +419,7 @@ impl<B: BlockT> ChainSync<B> {
 						best_number: info.best_number,
 						state: PeerSyncState::Available,
 						recently_announced: Default::default(),
+						throughput: Default::default(),
 					});
 					self.is_idle = false;
 					return Ok(None)
@@ -412,7 +441,8 @@ impl<B: BlockT> ChainSync<B> {
 						common_best,
 						AncestorSearchState::ExponentialBackoff(One::one())
 					),
-					recently_announced: Default::default()
+					recently_announced: Default::default(),
+					throughput: Default::default(),
 				});
 				self.is_idle = false;
 
@@ -426,6 +456,7 @@ impl<B: BlockT> ChainSync<B> {
 					best_number: info.best_number,
 					state: PeerSyncState::Available,
 					recently_announced: Default::default(),
+					throughput: Default::default(),
 				});
 				self.is_idle = false;
 				Ok(None)
@@ -554,11 +585,15 @@ impl<B: BlockT> ChainSync<B> {
 	}
 
 	/// Get an iterator over all block requests of all peers.
+	///
+	/// Peers are asked for blocks in order of their measured throughput, so that the fastest peers
+	/// get the most urgently needed ranges, and the size of each request is proportional to the
+	/// throughput of the peer.
 	pub fn block_requests(&mut self) -> impl Iterator<Item = (PeerId, BlockRequest<B>)> + '_ {
 		if self.is_idle {
 			return Either::Left(std::iter::empty())
 		}
-		if self.queue_blocks.len() > MAX_IMPORTING_BLOCKS {
+		if self.queue_blocks.len() > self.max_queued_blocks {
 			trace!(target: "sync", "Too many blocks in the queue.");
 			return Either::Left(std::iter::empty())
 		}
@@ -571,13 +606,23 @@ impl<B: BlockT> ChainSync<B> {
 		let best_queued = self.best_queued_number;
 		let client = &self.client;
 		let queue = &self.queue_blocks;
-		let iter = self.peers.iter_mut().filter_map(move |(id, peer)| {
+		let fastest = self.peers.values()
+			.filter_map(|peer| peer.throughput.blocks_per_second())
+			.fold(None, |fastest: Option<f64>, t| Some(fastest.map_or(t, |f| f.max(t))));
+		// Peers that weren't measured yet are tried first, so that they get measured.
+		let mut by_throughput: Vec<_> = self.peers.iter()
+			.map(|(id, peer)| (id.clone(), peer.throughput.blocks_per_second().unwrap_or(std::f64::INFINITY)))
+			.collect();
+		by_throughput.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(cmp::Ordering::Equal));
+		let peers = &mut self.peers;
+		let iter = by_throughput.into_iter().filter_map(move |(id, _)| {
+			let peer = peers.get_mut(&id)?;
 			if !peer.state.is_available() {
 				trace!(target: "sync", "Peer {} is busy", id);
 				return None
 			}
 			if let Some((hash, req)) = fork_sync_request(
-				id,
+				&id,
 				fork_targets,
 				best_queued,
 				last_finalized,
@@ -591,12 +636,13 @@ impl<B: BlockT> ChainSync<B> {
 				trace!(target: "sync", "Downloading fork {:?} from {}", hash, id);
 				peer.state = PeerSyncState::DownloadingStale(hash);
 				have_requests = true;
-				Some((id.clone(), req))
-			} else if let Some((range, req)) = peer_block_request(id, peer, blocks, attrs, major_sync) {
+				Some((id, req))
+			} else if let Some((range, req)) = peer_block_request(&id, peer, blocks, attrs, major_sync, fastest) {
 				peer.state = PeerSyncState::DownloadingNew(range.start);
+				peer.throughput.on_request();
 				trace!(target: "sync", "New block request for {}", id);
 				have_requests = true;
-				Some((id.clone(), req))
+				Some((id, req))
 			} else {
 				trace!(target: "sync", "No new block request for {}", id);
 				None
@@ -617,6 +663,7 @@ impl<B: BlockT> ChainSync<B> {
 	pub fn on_block_data
 		(&mut self, who: PeerId, request: BlockRequest<B>, response: BlockResponse<B>) -> Result<OnBlockData<B>, BadPeer>
 	{
+		let import_allowance = self.import_allowance();
 		let new_blocks: Vec<IncomingBlock<B>> =
 			if let Some(peer) = self.peers.get_mut(&who) {
 				let mut blocks = response.blocks;
@@ -627,22 +674,36 @@ impl<B: BlockT> ChainSync<B> {
 				self.is_idle = false;
 				match &mut peer.state {
 					PeerSyncState::DownloadingNew(start_block) => {
+						peer.throughput.on_response(blocks.len());
 						self.blocks.clear_peer_download(&who);
 						self.blocks.insert(*start_block, blocks, who);
 						peer.state = PeerSyncState::Available;
+						// Blocks conflicting with data downloaded from other peers are either part of a
+						// fork or bogus. Download them as a fork, and let the import decide.
+						for conflict in self.blocks.take_conflicts() {
+							debug!(
+								target: "sync",
+								"Block #{} from {} ({}) conflicts with {} from {:?}",
+								conflict.number,
+								conflict.rejected_origin,
+								conflict.rejected,
+								conflict.kept,
+								conflict.kept_origin,
+							);
+							self.fork_targets
+								.entry(conflict.rejected)
+								.or_insert_with(|| ForkTarget {
+									number: conflict.number,
+									parent_hash: None,
+									peers: Default::default(),
+								})
+								.peers.insert(conflict.rejected_origin);
+						}
 						self.blocks
-							.drain(self.best_queued_number + One::one())
+							.drain_batch(self.best_queued_number + One::one(), import_allowance)
 							.into_iter()
-							.map(|block_data| {
-								IncomingBlock {
-									hash: block_data.block.hash,
-									header: block_data.block.header,
-									body: block_data.block.body,
-									justification: block_data.block.justification,
-									origin: block_data.origin,
-									allow_missing_state: false,
-								}
-							}).collect()
+							.map(incoming_block)
+							.collect()
 					}
 					PeerSyncState::DownloadingStale(_) => {
 						peer.state = PeerSyncState::Available;
@@ -723,6 +784,8 @@ impl<B: BlockT> ChainSync<B> {
 				Vec::new()
 			};
 
+		let new_blocks = self.filter_orphans(&who, new_blocks);
+
 		let is_recent = new_blocks.first()
 			.map(|block| {
 				self.peers.iter().any(|(_, peer)| peer.recently_announced.contains(&block.hash))
@@ -855,8 +918,12 @@ impl<B: BlockT> ChainSync<B> {
 			}
 
 			match result {
-				Ok(BlockImportResult::ImportedKnown(_number)) => {}
+				Ok(BlockImportResult::ImportedKnown(_number)) => {
+					self.release_orphans(&hash);
+				}
 				Ok(BlockImportResult::ImportedUnknown(number, aux, who)) => {
+					self.release_orphans(&hash);
+
 					if aux.clear_justification_requests {
 						trace!(
 							target: "sync",
@@ -885,6 +952,7 @@ impl<B: BlockT> ChainSync<B> {
 					}
 				},
 				Err(BlockImportError::IncompleteHeader(who)) => {
+					self.orphans.remove_descendants(&hash);
 					if let Some(peer) = who {
 						info!("Peer sent block with incomplete header to import");
 						output.push(Err(BadPeer(peer, INCOMPLETE_HEADER_REPUTATION_CHANGE)));
@@ -892,6 +960,7 @@ impl<B: BlockT> ChainSync<B> {
 					}
 				},
 				Err(BlockImportError::VerificationFailed(who, e)) => {
+					self.orphans.remove_descendants(&hash);
 					if let Some(peer) = who {
 						info!("Verification failed from peer: {}", e);
 						output.push(Err(BadPeer(peer, VERIFICATION_FAIL_REPUTATION_CHANGE)));
@@ -899,6 +968,7 @@ impl<B: BlockT> ChainSync<B> {
 					}
 				},
 				Err(BlockImportError::BadBlock(who)) => {
+					self.orphans.remove_descendants(&hash);
 					if let Some(peer) = who {
 						info!("Bad block");
 						output.push(Err(BadPeer(peer, BAD_BLOCK_REPUTATION_CHANGE)));
@@ -958,6 +1028,115 @@ impl<B: BlockT> ChainSync<B> {
 		}
 	}
 
+	/// Returns the orphan blocks whose parent has been imported, to be queued for import.
+	///
+	/// Also drops the orphan blocks whose parent hasn't been imported in time.
+	pub fn take_ready_orphans(&mut self) -> Option<(BlockOrigin, Vec<IncomingBlock<B>>)> {
+		let expired = self.orphans.remove_expired();
+		if expired > 0 {
+			debug!(target: "sync", "Dropped {} expired orphan blocks", expired);
+		}
+		if self.ready_orphans.is_empty() {
+			return None
+		}
+
+		let blocks = std::mem::replace(&mut self.ready_orphans, Vec::new());
+		trace!(target: "sync", "Queueing {} orphan blocks with an imported parent", blocks.len());
+		if let Some((hash, number)) = blocks.iter()
+			.filter_map(|b| b.header.as_ref().map(|h| (b.hash, *h.number())))
+			.max_by_key(|&(_, number)| number)
+		{
+			self.on_block_queued(&hash, number);
+			self.best_importing_number = cmp::max(number, self.best_importing_number);
+		}
+		self.queue_blocks.extend(blocks.iter().map(|b| b.hash));
+		Some((BlockOrigin::NetworkInitialSync, blocks))
+	}
+
+	/// Returns the downloaded blocks that are ready to be imported and weren't handed over to the
+	/// import queue yet, because it was full.
+	pub fn take_downloaded_blocks(&mut self) -> Option<(BlockOrigin, Vec<IncomingBlock<B>>)> {
+		let import_allowance = self.import_allowance();
+		if import_allowance == 0 {
+			return None
+		}
+		let blocks = self.blocks
+			.drain_batch(self.best_queued_number + One::one(), import_allowance)
+			.into_iter()
+			.map(incoming_block)
+			.collect::<Vec<_>>();
+		let (hash, number) = blocks.last().and_then(|b| b.header.as_ref().map(|h| (b.hash, *h.number())))?;
+
+		trace!(target: "sync", "Queueing {} downloaded blocks", blocks.len());
+		self.on_block_queued(&hash, number);
+		self.best_importing_number = cmp::max(number, self.best_importing_number);
+		self.queue_blocks.extend(blocks.iter().map(|b| b.hash));
+		Some((BlockOrigin::NetworkInitialSync, blocks))
+	}
+
+	/// Number of downloaded blocks that may be handed over to the import queue now.
+	fn import_allowance(&self) -> usize {
+		cmp::min(self.import_batch_size, self.max_queued_blocks.saturating_sub(self.queue_blocks.len()))
+	}
+
+	/// Moves the downloaded blocks whose parent isn't known to the orphan pool, and returns the
+	/// other blocks.
+	///
+	/// The blocks are expected to form a chain, as in a block response. The missing ancestors are
+	/// requested from the peer that sent the blocks.
+	fn filter_orphans(&mut self, who: &PeerId, blocks: Vec<IncomingBlock<B>>) -> Vec<IncomingBlock<B>> {
+		let (parent_hash, number, allow_missing_state) = match blocks.first() {
+			Some(IncomingBlock { header: Some(header), allow_missing_state, .. }) =>
+				(*header.parent_hash(), *header.number(), *allow_missing_state),
+			_ => return blocks,
+		};
+		let parent_known = match self.block_status(&parent_hash) {
+			Ok(BlockStatus::Unknown) => false,
+			Ok(BlockStatus::InChainPruned) => allow_missing_state,
+			Ok(_) => true,
+			// let the import queue report the error.
+			Err(_) => true,
+		};
+		if parent_known || number.is_zero() {
+			return blocks
+		}
+
+		debug!(
+			target: "sync",
+			"Keeping {} blocks from {} with unknown parent {:?} as orphans",
+			blocks.len(),
+			who,
+			parent_hash,
+		);
+		let mut peers = HashSet::new();
+		peers.insert(who.clone());
+		for block in blocks {
+			// the orphans themselves don't need to be downloaded anymore, their ancestors do.
+			if let Some(target) = self.fork_targets.remove(&block.hash) {
+				peers.extend(target.peers);
+			}
+			self.orphans.insert(block);
+		}
+		self.fork_targets
+			.entry(parent_hash)
+			.or_insert_with(|| ForkTarget {
+				number: number - One::one(),
+				parent_hash: None,
+				peers: Default::default(),
+			})
+			.peers.extend(peers);
+		Vec::new()
+	}
+
+	/// Schedules the orphan descendants of an imported block for import.
+	fn release_orphans(&mut self, hash: &B::Hash) {
+		let released = self.orphans.take_descendants(hash);
+		if !released.is_empty() {
+			trace!(target: "sync", "Parent {:?} of {} orphan blocks imported", hash, released.len());
+			self.ready_orphans.extend(released);
+		}
+	}
+
 	/// Called when a block has been queued for import.
 	///
 	/// Updates our internal state for best queued block and then goes
@@ -1044,7 +1223,7 @@ impl<B: BlockT> ChainSync<B> {
 		self.is_idle = false;
 
 		// known block case
-		if known || self.is_already_downloading(&hash) {
+		if known || self.is_already_downloading(&hash) || self.orphans.contains(&hash) {
 			trace!(target: "sync", "Known block announce from {}: {}", who, hash);
 			if let Some(target) = self.fork_targets.get_mut(&hash) {
 				target.peers.insert(who);
@@ -1071,7 +1250,9 @@ impl<B: BlockT> ChainSync<B> {
 			return OnBlockAnnounce::Nothing
 		}
 
-		let requires_additional_data = !self.role.is_light() || !known_parent;
+		// Only light clients import the announced headers on their own, the other modes execute
+		// the blocks once in sync.
+		let requires_additional_data = !self.mode.is_light() || !known_parent;
 		if !requires_additional_data {
 			trace!(target: "sync", "Importing new header announced from {}: {} {:?}", who, hash, header);
 			return OnBlockAnnounce::ImportHeader
@@ -1147,6 +1328,18 @@ impl<B: BlockT> ChainSync<B> {
 	}
 }
 
+/// Converts a downloaded block to a block to import.
+fn incoming_block<B: BlockT>(block_data: blocks::BlockData<B>) -> IncomingBlock<B> {
+	IncomingBlock {
+		hash: block_data.block.hash,
+		header: block_data.block.header,
+		body: block_data.block.body,
+		justification: block_data.block.justification,
+		origin: block_data.origin,
+		allow_missing_state: false,
+	}
+}
+
 /// Request the ancestry for a block. Sends a request for header and justification for the given
 /// block number. Used during ancestry search.
 fn ancestry_request<B: BlockT>(block: NumberFor<B>) -> BlockRequest<B> {
@@ -1227,11 +1420,17 @@ fn peer_block_request<B: BlockT>(
 	blocks: &mut BlockCollection<B>,
 	attrs: &message::BlockAttributes,
 	major_sync: bool,
+	fastest: Option<f64>,
 ) -> Option<(Range<NumberFor<B>>, BlockRequest<B>)> {
 	let max_parallel = if major_sync { 1 } else { 3 };
+	let count = throughput::request_size(
+		peer.throughput.blocks_per_second(),
+		fastest,
+		MAX_BLOCKS_TO_REQUEST,
+	);
 	if let Some(range) = blocks.needed_blocks(
 		id.clone(),
-		MAX_BLOCKS_TO_REQUEST,
+		count,
 		peer.best_number,
 		peer.common_number,
 		max_parallel,
@@ -1290,3 +1489,48 @@ fn fork_sync_request<B: BlockT>(
 	}
 	None
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use consensus::block_validation::DefaultBlockAnnounceValidator;
+	use crate::config::Roles;
+	use primitives::H256;
+	use test_client::{DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt};
+
+	fn first_block_request(mode: SyncMode) -> message::BlockAttributes {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let info = client.info();
+		let mut sync = ChainSync::new(
+			mode,
+			client.clone(),
+			&info,
+			None,
+			Box::new(DefaultBlockAnnounceValidator::new(client)),
+			IMPORT_BATCH_SIZE,
+			MAX_IMPORTING_BLOCKS,
+		);
+
+		let peer = PeerId::random();
+		let info = protocol::PeerInfo {
+			roles: Roles::FULL,
+			protocol_version: 0,
+			best_hash: H256::random(),
+			best_number: 10,
+		};
+		assert!(sync.new_peer(peer.clone(), info).unwrap().is_none());
+
+		let (who, request) = sync.block_requests().next().expect("the peer is ahead of us");
+		assert_eq!(who, peer);
+		request.fields
+	}
+
+	#[test]
+	fn block_requests_follow_the_sync_mode() {
+		let chain = BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION;
+
+		assert_eq!(first_block_request(SyncMode::Full), chain | BlockAttributes::BODY);
+		assert_eq!(first_block_request(SyncMode::Fast), chain);
+		assert_eq!(first_block_request(SyncMode::Light), chain);
+	}
+}
@@ -19,9 +19,9 @@ use std::cmp;
 use std::ops::Range;
 use std::collections::{HashMap, BTreeMap};
 use std::collections::hash_map::Entry;
-use log::trace;
+use log::{debug, trace};
 use libp2p::PeerId;
-use sr_primitives::traits::{Block as BlockT, NumberFor, One};
+use sr_primitives::traits::{Block as BlockT, NumberFor, One, SaturatedConversion};
 use crate::message;
 
 /// Block data with origin.
@@ -33,6 +33,22 @@ pub struct BlockData<B: BlockT> {
 	pub origin: Option<PeerId>,
 }
 
+/// Block data for the same block number that was received from different peers with different
+/// hashes. Either a fork, or a peer sending us bogus data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockConflict<B: BlockT> {
+	/// Number of the block.
+	pub number: NumberFor<B>,
+	/// Hash of the block that is kept in the collection.
+	pub kept: B::Hash,
+	/// The peer the kept block was received from.
+	pub kept_origin: Option<PeerId>,
+	/// Hash of the block that was rejected.
+	pub rejected: B::Hash,
+	/// The peer the rejected block was received from.
+	pub rejected_origin: PeerId,
+}
+
 #[derive(Debug)]
 enum BlockRangeState<B: BlockT> {
 	Downloading {
@@ -57,6 +73,8 @@ pub struct BlockCollection<B: BlockT> {
 	/// Downloaded blocks.
 	blocks: BTreeMap<NumberFor<B>, BlockRangeState<B>>,
 	peer_requests: HashMap<PeerId, NumberFor<B>>,
+	/// Conflicting block data that has been received and not yet taken.
+	conflicts: Vec<BlockConflict<B>>,
 }
 
 impl<B: BlockT> BlockCollection<B> {
@@ -65,6 +83,7 @@ impl<B: BlockT> BlockCollection<B> {
 		BlockCollection {
 			blocks: BTreeMap::new(),
 			peer_requests: HashMap::new(),
+			conflicts: Vec::new(),
 		}
 	}
 
@@ -72,14 +91,31 @@ impl<B: BlockT> BlockCollection<B> {
 	pub fn clear(&mut self) {
 		self.blocks.clear();
 		self.peer_requests.clear();
+		self.conflicts.clear();
 	}
 
 	/// Insert a set of blocks into collection.
+	///
+	/// If the blocks overlap already downloaded blocks with different hashes, the downloaded blocks
+	/// are kept and the conflicts are recorded, to be retrieved with `take_conflicts`.
 	pub fn insert(&mut self, start: NumberFor<B>, blocks: Vec<message::BlockData<B>>, who: PeerId) {
 		if blocks.is_empty() {
 			return;
 		}
 
+		let conflicts = self.find_conflicts(start, &blocks, &who);
+		if !conflicts.is_empty() {
+			debug!(
+				target: "sync",
+				"Ignored block data from {} conflicting with {} downloaded blocks: {}",
+				who,
+				conflicts.len(),
+				start,
+			);
+			self.conflicts.extend(conflicts);
+			return;
+		}
+
 		match self.blocks.get(&start) {
 			Some(&BlockRangeState::Downloading { .. }) => {
 				trace!(target: "sync", "Inserting block data still marked as being downloaded: {}", start);
@@ -95,6 +131,48 @@ impl<B: BlockT> BlockCollection<B> {
 			.map(|b| BlockData { origin: Some(who.clone()), block: b }).collect()));
 	}
 
+	/// Take the conflicting block data recorded since the last call.
+	pub fn take_conflicts(&mut self) -> Vec<BlockConflict<B>> {
+		mem::replace(&mut self.conflicts, Vec::new())
+	}
+
+	/// Compare the hashes of `blocks` starting at `start` with the hashes of the downloaded blocks
+	/// with the same numbers.
+	fn find_conflicts(
+		&self,
+		start: NumberFor<B>,
+		blocks: &[message::BlockData<B>],
+		who: &PeerId,
+	) -> Vec<BlockConflict<B>> {
+		let end = start + (blocks.len() as u32).into();
+		let mut conflicts = Vec::new();
+		for (range_start, range) in self.blocks.range(..end) {
+			let downloaded = match range {
+				BlockRangeState::Complete(downloaded) if *range_start + range.len() > start => downloaded,
+				_ => continue,
+			};
+			for (number, data) in (0..).map(|i: u32| *range_start + i.into()).zip(downloaded) {
+				if number < start {
+					continue;
+				}
+				if number >= end {
+					break;
+				}
+				let block = &blocks[(number - start).saturated_into::<usize>()];
+				if block.hash != data.block.hash {
+					conflicts.push(BlockConflict {
+						number,
+						kept: data.block.hash,
+						kept_origin: data.origin.clone(),
+						rejected: block.hash,
+						rejected_origin: who.clone(),
+					});
+				}
+			}
+		}
+		conflicts
+	}
+
 	/// Returns a set of block hashes that require a header download. The returned set is marked as being downloaded.
 	pub fn needed_blocks(
 		&mut self,
@@ -152,17 +230,34 @@ impl<B: BlockT> BlockCollection<B> {
 
 	/// Get a valid chain of blocks ordered in descending order and ready for importing into blockchain.
 	pub fn drain(&mut self, from: NumberFor<B>) -> Vec<BlockData<B>> {
+		self.drain_batch(from, usize::max_value())
+	}
+
+	/// Get a valid chain of at most `max` blocks ordered in descending order and ready for
+	/// importing into blockchain.
+	///
+	/// The blocks beyond `max` are kept, to be drained later.
+	pub fn drain_batch(&mut self, from: NumberFor<B>, max: usize) -> Vec<BlockData<B>> {
 		let mut drained = Vec::new();
 		let mut ranges = Vec::new();
+		let mut remainder = None;
 		{
 			let mut prev = from;
 			for (start, range_data) in &mut self.blocks {
+				if drained.len() >= max {
+					break
+				}
 				match range_data {
 					&mut BlockRangeState::Complete(ref mut blocks) if *start <= prev => {
-							prev = *start + (blocks.len() as u32).into();
+							let take = cmp::min(blocks.len(), max - drained.len());
+							prev = *start + (take as u32).into();
+							let rest = blocks.split_off(take);
 							let mut blocks = mem::replace(blocks, Vec::new());
 							drained.append(&mut blocks);
 							ranges.push(*start);
+							if !rest.is_empty() {
+								remainder = Some((prev, rest));
+							}
 					},
 					_ => break,
 				}
@@ -171,6 +266,9 @@ impl<B: BlockT> BlockCollection<B> {
 		for r in ranges {
 			self.blocks.remove(&r);
 		}
+		if let Some((start, rest)) = remainder {
+			self.blocks.entry(start).or_insert(BlockRangeState::Complete(rest));
+		}
 		trace!(target: "sync", "Drained {} blocks", drained.len());
 		drained
 	}
@@ -202,7 +300,7 @@ impl<B: BlockT> BlockCollection<B> {
 
 #[cfg(test)]
 mod test {
-	use super::{BlockCollection, BlockData, BlockRangeState};
+	use super::{BlockCollection, BlockConflict, BlockData, BlockRangeState};
 	use crate::{message, PeerId};
 	use sr_primitives::testing::{Block as RawBlock, ExtrinsicWrapper};
 	use primitives::H256;
@@ -211,7 +309,8 @@ mod test {
 
 	fn is_empty(bc: &BlockCollection<Block>) -> bool {
 		bc.blocks.is_empty() &&
-		bc.peer_requests.is_empty()
+		bc.peer_requests.is_empty() &&
+		bc.conflicts.is_empty()
 	}
 
 	fn generate_blocks(n: usize) -> Vec<message::BlockData<Block>> {
@@ -283,6 +382,30 @@ mod test {
 			.map(|b| BlockData { block: b.clone(), origin: Some(peer1.clone()) }).collect::<Vec<_>>()[..]);
 	}
 
+	#[test]
+	fn drain_in_batches() {
+		let mut bc = BlockCollection::new();
+		let peer0 = PeerId::random();
+		let peer1 = PeerId::random();
+		let blocks = generate_blocks(40);
+		let data = |range: std::ops::Range<usize>, peer: &PeerId| blocks[range].iter()
+			.map(|b| BlockData { block: b.clone(), origin: Some(peer.clone()) })
+			.collect::<Vec<_>>();
+
+		bc.insert(1, blocks[1..21].to_vec(), peer0.clone());
+		bc.insert(21, blocks[21..31].to_vec(), peer1.clone());
+
+		assert_eq!(bc.drain_batch(1, 0), vec![]);
+		assert_eq!(bc.drain_batch(1, 15), data(1..16, &peer0));
+		// the rest of the range is drained from where the batch stopped.
+		assert_eq!(bc.drain_batch(1, 15), vec![]);
+		let drained = bc.drain_batch(16, 10);
+		assert_eq!(drained[..5], data(16..21, &peer0)[..]);
+		assert_eq!(drained[5..], data(21..26, &peer1)[..]);
+		assert_eq!(bc.drain(26), data(26..31, &peer1));
+		assert!(is_empty(&bc));
+	}
+
 	#[test]
 	fn large_gap() {
 		let mut bc: BlockCollection<Block> = BlockCollection::new();
@@ -297,4 +420,48 @@ mod test {
 		assert_eq!(bc.needed_blocks(peer0.clone(), 128, 10000, 000, 1), Some(1 .. 100));
 		assert_eq!(bc.needed_blocks(peer0.clone(), 128, 10000, 600, 1), Some(100 + 128 .. 100 + 128 + 128));
 	}
+
+	#[test]
+	fn conflicting_blocks_are_recorded() {
+		let mut bc = BlockCollection::new();
+		let peer0 = PeerId::random();
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+
+		let blocks = generate_blocks(20);
+		bc.insert(1, blocks[1..11].to_vec(), peer0.clone());
+
+		// Same data from another peer doesn't conflict.
+		bc.insert(6, blocks[6..16].to_vec(), peer1.clone());
+		assert!(bc.take_conflicts().is_empty());
+
+		// Different data for blocks #8 and #9.
+		let mut fork = blocks[8..12].to_vec();
+		fork[0].hash = H256::random();
+		fork[1].hash = H256::random();
+		bc.insert(8, fork.clone(), peer2.clone());
+		assert_eq!(bc.take_conflicts(), vec![
+			BlockConflict {
+				number: 8,
+				kept: blocks[8].hash,
+				kept_origin: Some(peer0.clone()),
+				rejected: fork[0].hash,
+				rejected_origin: peer2.clone(),
+			},
+			BlockConflict {
+				number: 9,
+				kept: blocks[9].hash,
+				kept_origin: Some(peer0.clone()),
+				rejected: fork[1].hash,
+				rejected_origin: peer2.clone(),
+			},
+		]);
+		assert!(bc.take_conflicts().is_empty());
+
+		// The conflicting data is not inserted.
+		let drained = bc.drain(1);
+		assert_eq!(drained[..10], blocks[1..11].iter()
+			.map(|b| BlockData { block: b.clone(), origin: Some(peer0.clone()) }).collect::<Vec<_>>()[..]);
+		assert!(drained.iter().all(|b| b.origin != Some(peer2.clone())));
+	}
 }
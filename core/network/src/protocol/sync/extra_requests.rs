@@ -489,7 +489,8 @@ mod tests {
 				best_hash: Hash::random(),
 				best_number: g.gen(),
 				state: ArbitraryPeerSyncState::arbitrary(g).0,
-				recently_announced: Default::default()
+				recently_announced: Default::default(),
+				throughput: Default::default(),
 			};
 			ArbitraryPeerSync(ps)
 		}
@@ -0,0 +1,238 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use consensus::import_queue::IncomingBlock;
+use log::trace;
+use sr_primitives::traits::{Block as BlockT, Header};
+
+/// Maximum number of orphan blocks kept at a time.
+pub const MAX_ORPHAN_BLOCKS: usize = 512;
+
+/// Time after which an orphan block whose parent hasn't been imported is dropped.
+pub const ORPHAN_TTL: Duration = Duration::from_secs(60);
+
+struct Orphan<B: BlockT> {
+	block: IncomingBlock<B>,
+	parent_hash: B::Hash,
+	received: Instant,
+}
+
+/// Downloaded blocks whose parent hasn't been imported yet.
+///
+/// The blocks are kept until their parent is imported, instead of being dropped and downloaded
+/// again. The pool is bounded, the oldest blocks being dropped first, and blocks whose parent
+/// isn't imported in time expire.
+pub struct OrphanPool<B: BlockT> {
+	/// Orphan blocks, by hash.
+	blocks: HashMap<B::Hash, Orphan<B>>,
+	/// Hashes of the orphan blocks, by parent hash.
+	children: HashMap<B::Hash, Vec<B::Hash>>,
+	/// Hashes of the orphan blocks, in insertion order.
+	order: VecDeque<B::Hash>,
+	max_blocks: usize,
+	ttl: Duration,
+}
+
+impl<B: BlockT> OrphanPool<B> {
+	/// Create a pool holding at most `max_blocks` blocks, for at most `ttl` each.
+	pub fn new(max_blocks: usize, ttl: Duration) -> Self {
+		OrphanPool {
+			blocks: HashMap::new(),
+			children: HashMap::new(),
+			order: VecDeque::new(),
+			max_blocks,
+			ttl,
+		}
+	}
+
+	/// Number of orphan blocks in the pool.
+	pub fn len(&self) -> usize {
+		self.blocks.len()
+	}
+
+	/// Is the given block in the pool?
+	pub fn contains(&self, hash: &B::Hash) -> bool {
+		self.blocks.contains_key(hash)
+	}
+
+	/// Add a block to the pool, dropping the oldest block if the pool is full.
+	///
+	/// Blocks without a header are ignored, as their parent is unknown.
+	pub fn insert(&mut self, block: IncomingBlock<B>) {
+		let parent_hash = match block.header.as_ref() {
+			Some(header) => *header.parent_hash(),
+			None => return,
+		};
+		if self.blocks.contains_key(&block.hash) || self.max_blocks == 0 {
+			return
+		}
+		while self.blocks.len() >= self.max_blocks {
+			match self.order.front().cloned() {
+				Some(oldest) => {
+					trace!(target: "sync", "Orphan pool is full, dropping {:?}", oldest);
+					self.remove(&oldest);
+				}
+				None => break,
+			}
+		}
+
+		let hash = block.hash;
+		self.children.entry(parent_hash).or_default().push(hash);
+		self.order.push_back(hash);
+		self.blocks.insert(hash, Orphan { block, parent_hash, received: Instant::now() });
+	}
+
+	/// Remove and return the descendants of the given block, parents before their children.
+	pub fn take_descendants(&mut self, hash: &B::Hash) -> Vec<IncomingBlock<B>> {
+		let mut descendants = Vec::new();
+		let mut pending = VecDeque::new();
+		pending.push_back(*hash);
+		while let Some(parent) = pending.pop_front() {
+			for child in self.children.remove(&parent).unwrap_or_default() {
+				if let Some(orphan) = self.blocks.remove(&child) {
+					self.order.retain(|h| h != &child);
+					descendants.push(orphan.block);
+					pending.push_back(child);
+				}
+			}
+		}
+		descendants
+	}
+
+	/// Drop the descendants of the given block, e.g. because the block is bad.
+	///
+	/// Returns the number of dropped blocks.
+	pub fn remove_descendants(&mut self, hash: &B::Hash) -> usize {
+		self.take_descendants(hash).len()
+	}
+
+	/// Drop the blocks that have been in the pool for longer than the time to live.
+	///
+	/// Returns the number of dropped blocks.
+	pub fn remove_expired(&mut self) -> usize {
+		let mut removed = 0;
+		while let Some(oldest) = self.order.front().cloned() {
+			let expired = self.blocks.get(&oldest).map_or(true, |orphan| orphan.received.elapsed() >= self.ttl);
+			if !expired {
+				break
+			}
+			trace!(target: "sync", "Orphan block {:?} expired", oldest);
+			self.remove(&oldest);
+			removed += 1;
+		}
+		removed
+	}
+
+	fn remove(&mut self, hash: &B::Hash) {
+		self.order.retain(|h| h != hash);
+		if let Some(orphan) = self.blocks.remove(hash) {
+			if let Some(siblings) = self.children.get_mut(&orphan.parent_hash) {
+				siblings.retain(|h| h != hash);
+				if siblings.is_empty() {
+					self.children.remove(&orphan.parent_hash);
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use primitives::H256;
+	use sr_primitives::testing::{Block as RawBlock, ExtrinsicWrapper, Header as TestHeader, Digest};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	fn block(number: u64, parent_hash: H256) -> IncomingBlock<Block> {
+		// the random state root makes sibling blocks distinct.
+		let header = TestHeader::new(number, Default::default(), H256::random(), parent_hash, Digest::default());
+		IncomingBlock {
+			hash: header.hash(),
+			header: Some(header),
+			body: None,
+			justification: None,
+			origin: None,
+			allow_missing_state: false,
+		}
+	}
+
+	#[test]
+	fn descendants_are_taken_parents_first() {
+		let mut pool = OrphanPool::new(MAX_ORPHAN_BLOCKS, ORPHAN_TTL);
+		let root = H256::random();
+		let b1 = block(1, root);
+		let b2 = block(2, b1.hash);
+		let b3 = block(3, b2.hash);
+		let fork = block(2, b1.hash);
+		let unrelated = block(1, H256::random());
+
+		pool.insert(b3.clone());
+		pool.insert(b2.clone());
+		pool.insert(unrelated.clone());
+		pool.insert(fork.clone());
+		pool.insert(b1.clone());
+		assert_eq!(pool.len(), 5);
+
+		let taken = pool.take_descendants(&root).into_iter().map(|b| b.hash).collect::<Vec<_>>();
+		assert_eq!(taken.len(), 4);
+		assert_eq!(taken[0], b1.hash);
+		assert_eq!(taken[3], b3.hash);
+		assert!(taken.contains(&b2.hash));
+		assert!(taken.contains(&fork.hash));
+
+		assert_eq!(pool.len(), 1);
+		assert!(pool.contains(&unrelated.hash));
+		assert!(pool.take_descendants(&root).is_empty());
+	}
+
+	#[test]
+	fn oldest_blocks_are_dropped_when_full() {
+		let mut pool = OrphanPool::new(2, ORPHAN_TTL);
+		let b1 = block(1, H256::random());
+		let b2 = block(1, H256::random());
+		let b3 = block(1, H256::random());
+
+		pool.insert(b1.clone());
+		pool.insert(b2.clone());
+		pool.insert(b3.clone());
+
+		assert_eq!(pool.len(), 2);
+		assert!(!pool.contains(&b1.hash));
+		assert!(pool.contains(&b2.hash));
+		assert!(pool.contains(&b3.hash));
+		assert_eq!(pool.remove_descendants(&b3.header.unwrap().parent_hash), 1);
+		assert_eq!(pool.len(), 1);
+	}
+
+	#[test]
+	fn blocks_expire() {
+		let mut pool = OrphanPool::new(MAX_ORPHAN_BLOCKS, Duration::from_secs(0));
+		let b1 = block(1, H256::random());
+		pool.insert(b1.clone());
+		pool.insert(block(2, b1.hash));
+
+		assert_eq!(pool.remove_expired(), 2);
+		assert_eq!(pool.len(), 0);
+
+		let mut pool = OrphanPool::new(MAX_ORPHAN_BLOCKS, ORPHAN_TTL);
+		pool.insert(block(1, H256::random()));
+		assert_eq!(pool.remove_expired(), 0);
+		assert_eq!(pool.len(), 1);
+	}
+}
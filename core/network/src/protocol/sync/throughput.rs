@@ -0,0 +1,114 @@
+// Copyright 2017-2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+/// Minimum blocks to request from a peer, regardless of its throughput.
+pub const MIN_BLOCKS_TO_REQUEST: usize = 8;
+
+/// Weight of the most recent measurement in the moving average of the throughput.
+const SMOOTHING_FACTOR: f64 = 0.3;
+
+/// Measured block download throughput of a peer.
+#[derive(Debug, Clone, Default)]
+pub struct PeerThroughput {
+	/// Moving average of the blocks per second received from the peer, if measured yet.
+	blocks_per_second: Option<f64>,
+	/// When the pending block request to the peer was sent.
+	request_started: Option<Instant>,
+}
+
+impl PeerThroughput {
+	/// Average number of blocks per second received from the peer, if known.
+	pub fn blocks_per_second(&self) -> Option<f64> {
+		self.blocks_per_second
+	}
+
+	/// Note that a block request has been sent to the peer.
+	pub fn on_request(&mut self) {
+		self.request_started = Some(Instant::now());
+	}
+
+	/// Note that the peer answered the pending block request with `blocks` blocks.
+	pub fn on_response(&mut self, blocks: usize) {
+		if let Some(started) = self.request_started.take() {
+			self.note_response(blocks, started.elapsed());
+		}
+	}
+
+	fn note_response(&mut self, blocks: usize, elapsed: Duration) {
+		// Guard against responses that arrive within the resolution of the clock.
+		let secs = elapsed.as_secs_f64().max(0.001);
+		let measured = blocks as f64 / secs;
+		self.blocks_per_second = Some(match self.blocks_per_second {
+			Some(average) => average + SMOOTHING_FACTOR * (measured - average),
+			None => measured,
+		});
+	}
+}
+
+/// Number of blocks to request from a peer with the given throughput.
+///
+/// The request size is proportional to the throughput of the peer relative to the fastest one, so
+/// that slow peers don't hold up large ranges of blocks. Peers whose throughput hasn't been
+/// measured yet get the full `max` blocks.
+pub fn request_size(peer: Option<f64>, fastest: Option<f64>, max: usize) -> usize {
+	match (peer, fastest) {
+		(Some(peer), Some(fastest)) if fastest > 0.0 => {
+			let size = (max as f64 * peer / fastest).round() as usize;
+			cmp::max(cmp::min(size, max), cmp::min(MIN_BLOCKS_TO_REQUEST, max))
+		},
+		_ => max,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn throughput_is_averaged() {
+		let mut throughput = PeerThroughput::default();
+		assert_eq!(throughput.blocks_per_second(), None);
+
+		throughput.note_response(100, Duration::from_secs(1));
+		assert_eq!(throughput.blocks_per_second(), Some(100.0));
+
+		throughput.note_response(0, Duration::from_secs(1));
+		assert_eq!(throughput.blocks_per_second(), Some(70.0));
+	}
+
+	#[test]
+	fn responses_without_request_are_ignored() {
+		let mut throughput = PeerThroughput::default();
+		throughput.on_response(100);
+		assert_eq!(throughput.blocks_per_second(), None);
+
+		throughput.on_request();
+		throughput.on_response(100);
+		assert!(throughput.blocks_per_second().is_some());
+	}
+
+	#[test]
+	fn request_size_is_proportional_to_throughput() {
+		assert_eq!(request_size(None, Some(100.0), 128), 128);
+		assert_eq!(request_size(Some(100.0), Some(100.0), 128), 128);
+		assert_eq!(request_size(Some(50.0), Some(100.0), 128), 64);
+		assert_eq!(request_size(Some(1.0), Some(100.0), 128), MIN_BLOCKS_TO_REQUEST);
+		assert_eq!(request_size(Some(0.0), Some(0.0), 128), 128);
+	}
+}
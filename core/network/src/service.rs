@@ -51,6 +51,11 @@ use crate::protocol::{event::Event, light_dispatch::{AlwaysBadChecker, RequestDa
 use crate::protocol::specialization::NetworkSpecialization;
 use crate::protocol::sync::SyncState;
 
+/// Priority group of the peers found by the authority discovery.
+///
+/// Extrinsics are propagated to the peers of this group first.
+pub const AUTHORITIES_PRIORITY_GROUP: &str = "authorities";
+
 /// Minimum Requirements for a Hash within Networking
 pub trait ExHashT: std::hash::Hash + Eq + std::fmt::Debug + Clone + Send + Sync + 'static {}
 
@@ -167,6 +172,17 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 				}
 			)?;
 
+		let sync_mode = params.sync_mode;
+		if !sync_mode.is_supported() {
+			return Err(Error::UnsupportedSyncMode { mode: sync_mode, reason: "not supported yet" })
+		}
+		if sync_mode.is_light() != params.roles.is_light() {
+			return Err(Error::UnsupportedSyncMode {
+				mode: sync_mode,
+				reason: "light clients sync in light mode, and only them",
+			})
+		}
+
 		// Initialize the reserved peers.
 		for reserved in params.network_config.reserved_nodes.iter() {
 			if let Ok((peer_id, addr)) = parse_str_addr(reserved) {
@@ -194,7 +210,16 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkWorker
 		let num_connected = Arc::new(AtomicUsize::new(0));
 		let is_major_syncing = Arc::new(AtomicBool::new(false));
 		let (protocol, peerset_handle) = Protocol::new(
-			protocol::ProtocolConfig { roles: params.roles },
+			protocol::ProtocolConfig {
+				roles: params.roles,
+				sync_mode,
+				max_announced_digest_item_size: params.network_config.max_announced_digest_item_size,
+				non_authority_propagation_delay: params.network_config.non_authority_propagation_delay,
+				import_batch_size: params.network_config.sync_import_batch_size
+					.unwrap_or(protocol::sync::IMPORT_BATCH_SIZE),
+				max_queued_blocks: params.network_config.sync_max_queued_blocks
+					.unwrap_or(protocol::sync::MAX_IMPORTING_BLOCKS),
+			},
 			params.chain,
 			params.on_demand.as_ref().map(|od| od.checker().clone())
 				.unwrap_or(Arc::new(AlwaysBadChecker)),
@@ -542,7 +567,12 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> NetworkServic
 			parse_addr(p).map_err(|e| format!("{:?}", e))
 		}).collect::<Result<Vec<(PeerId, Multiaddr)>, String>>()?;
 
-		let peer_ids = peers.iter().map(|(peer_id, _addr)| peer_id.clone()).collect();
+		let peer_ids: HashSet<PeerId> = peers.iter().map(|(peer_id, _addr)| peer_id.clone()).collect();
+		if group_id == AUTHORITIES_PRIORITY_GROUP {
+			let _ = self
+				.to_worker
+				.unbounded_send(ServerToWorkerMsg::SetAuthorityPeers(peer_ids.clone()));
+		}
 		self.peerset.set_priority_group(group_id, peer_ids);
 
 		for (peer_id, addr) in peers.into_iter() {
@@ -629,6 +659,7 @@ enum ServerToWorkerMsg<B: BlockT, S: NetworkSpecialization<B>> {
 	PutValue(record::Key, Vec<u8>),
 	AddKnownAddress(PeerId, Multiaddr),
 	SyncFork(Vec<PeerId>, B::Hash, NumberFor<B>),
+	SetAuthorityPeers(HashSet<PeerId>),
 }
 
 /// Main network worker. Must be polled in order for the network to advance.
@@ -709,6 +740,8 @@ impl<B: BlockT + 'static, S: NetworkSpecialization<B>, H: ExHashT> Stream for Ne
 					self.network_service.add_known_address(peer_id, addr),
 				ServerToWorkerMsg::SyncFork(peer_ids, hash, number) =>
 					self.network_service.user_protocol_mut().set_sync_fork_request(peer_ids, &hash, number),
+				ServerToWorkerMsg::SetAuthorityPeers(peer_ids) =>
+					self.network_service.user_protocol_mut().set_authority_peers(peer_ids),
 			}
 		}
 
@@ -19,6 +19,8 @@
 #[cfg(test)]
 mod block_import;
 #[cfg(test)]
+mod soak;
+#[cfg(test)]
 mod sync;
 
 use std::collections::HashMap;
@@ -48,8 +50,8 @@ use consensus::{BlockOrigin, ForkChoiceStrategy, BlockImportParams, BlockCheckPa
 use futures::prelude::*;
 use futures03::{StreamExt as _, TryStreamExt as _};
 use crate::{NetworkWorker, NetworkService, ReportHandle, config::ProtocolId};
-use crate::config::{NetworkConfiguration, TransportConfig, BoxFinalityProofRequestBuilder};
-use libp2p::PeerId;
+use crate::config::{NetworkConfiguration, SyncMode, TransportConfig, BoxFinalityProofRequestBuilder};
+use libp2p::{Multiaddr, PeerId};
 use parking_lot::Mutex;
 use primitives::H256;
 use crate::protocol::{Context, ProtocolConfig};
@@ -220,6 +222,7 @@ pub struct Peer<D, S: NetworkSpecialization<Block>> {
 	select_chain: Option<LongestChain<test_client::Backend, Block>>,
 	backend: Option<Arc<test_client::Backend>>,
 	network: NetworkWorker<Block, S, <Block as BlockT>::Hash>,
+	listen_addr: Multiaddr,
 	imported_blocks_stream: Box<dyn Stream<Item = BlockImportNotification<Block>, Error = ()> + Send>,
 	finality_notification_stream: Box<dyn Stream<Item = FinalityNotification<Block>, Error = ()> + Send>,
 }
@@ -230,6 +233,11 @@ impl<D, S: NetworkSpecialization<Block>> Peer<D, S> {
 		self.network.service().local_peer_id()
 	}
 
+	/// Returns the address of this peer, including its peer ID.
+	pub fn address(&self) -> String {
+		format!("{}/p2p/{}", self.listen_addr, self.id().to_base58())
+	}
+
 	/// Returns true if we're major syncing.
 	pub fn is_major_syncing(&self) -> bool {
 		self.network.service().is_major_syncing()
@@ -375,6 +383,27 @@ impl<D, S: NetworkSpecialization<Block>> Peer<D, S> {
 		}
 	}
 
+	/// Polls the network of this peer and forwards the client notifications to it.
+	fn poll(&mut self) {
+		trace!(target: "sync", "-- Polling {}", self.id());
+		self.network.poll().unwrap();
+		trace!(target: "sync", "-- Polling complete {}", self.id());
+
+		// We poll `imported_blocks_stream`.
+		while let Ok(Async::Ready(Some(notification))) = self.imported_blocks_stream.poll() {
+			self.network.on_block_imported(notification.hash, notification.header, Vec::new(), true);
+		}
+
+		// We poll `finality_notification_stream`, but we only take the last event.
+		let mut last = None;
+		while let Ok(Async::Ready(Some(item))) = self.finality_notification_stream.poll() {
+			last = Some(item);
+		}
+		if let Some(notification) = last {
+			self.network.on_block_finalized(notification.hash, notification.header);
+		}
+	}
+
 	/// Count the total number of imported blocks.
 	pub fn blocks_count(&self) -> u64 {
 		self.backend.as_ref().map(
@@ -562,6 +591,7 @@ pub trait TestNetFactory: Sized {
 
 		let network = NetworkWorker::new(crate::config::Params {
 			roles: config.roles,
+			sync_mode: SyncMode::for_roles(config.roles),
 			network_config: NetworkConfiguration {
 				listen_addresses: vec![listen_addr.clone()],
 				transport: TransportConfig::MemoryOnly,
@@ -598,6 +628,7 @@ pub trait TestNetFactory: Sized {
 				block_import: Box::new(block_import),
 				verifier,
 				network,
+				listen_addr,
 			});
 		});
 	}
@@ -636,6 +667,7 @@ pub trait TestNetFactory: Sized {
 
 		let network = NetworkWorker::new(crate::config::Params {
 			roles: config.roles,
+			sync_mode: SyncMode::for_roles(config.roles),
 			network_config: NetworkConfiguration {
 				listen_addresses: vec![listen_addr.clone()],
 				transport: TransportConfig::MemoryOnly,
@@ -672,6 +704,7 @@ pub trait TestNetFactory: Sized {
 				imported_blocks_stream,
 				finality_notification_stream,
 				network,
+				listen_addr,
 			});
 		});
 	}
@@ -708,23 +741,7 @@ pub trait TestNetFactory: Sized {
 	fn poll(&mut self) {
 		self.mut_peers(|peers| {
 			for peer in peers {
-				trace!(target: "sync", "-- Polling {}", peer.id());
-				peer.network.poll().unwrap();
-				trace!(target: "sync", "-- Polling complete {}", peer.id());
-
-				// We poll `imported_blocks_stream`.
-				while let Ok(Async::Ready(Some(notification))) = peer.imported_blocks_stream.poll() {
-					peer.network.on_block_imported(notification.hash, notification.header, Vec::new(), true);
-				}
-
-				// We poll `finality_notification_stream`, but we only take the last event.
-				let mut last = None;
-				while let Ok(Async::Ready(Some(item))) = peer.finality_notification_stream.poll() {
-					last = Some(item);
-				}
-				if let Some(notification) = last {
-					peer.network.on_block_finalized(notification.hash, notification.header);
-				}
+				peer.poll();
 			}
 		});
 	}
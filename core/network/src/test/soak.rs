@@ -0,0 +1,349 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Soak tests of sync and finality.
+//!
+//! A soak runs a network of in-process full nodes over many blocks, authored by random nodes,
+//! while randomly delaying the nodes, partitioning the network and restarting nodes. At regular
+//! checkpoints the network is healed, and all the nodes must converge to the same best block
+//! without reverting any of the blocks finalized at the previous checkpoints.
+//!
+//! Restarts are simulated by disconnecting a node from all its peers for a while, the node keeping
+//! its database.
+//!
+//! The long soak is ignored by default, run it with:
+//!
+//! ```text
+//! cargo test -p substrate-network long_soak -- --ignored
+//! ```
+//!
+//! The random events of a soak are driven by a seed, which is logged and part of the failure
+//! messages. Set `SOAK_SEED` to replay the events of a run.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use consensus::BlockOrigin;
+use log::info;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use tokio::runtime::current_thread;
+use super::*;
+
+/// Time after which a healed network that hasn't converged is considered stuck on tied forks.
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time after which a healed network that hasn't converged fails the soak.
+const CONVERGENCE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Parameters of a soak.
+#[derive(Debug, Clone)]
+struct SoakConfig {
+	/// Number of nodes.
+	peers: usize,
+	/// Number of authoring rounds.
+	rounds: u64,
+	/// Seed of the random events.
+	seed: u64,
+	/// Maximal number of steps during which a node isn't polled.
+	max_latency: u32,
+	/// Maximal number of steps between two authoring rounds.
+	max_steps_per_round: u32,
+	/// Probability, at each round, that the network is split in two.
+	partition_probability: f64,
+	/// Probability, at each round, that a partitioned network is healed.
+	heal_probability: f64,
+	/// Probability, at each round, that a node is restarted.
+	restart_probability: f64,
+	/// Maximal number of rounds during which a restarted node is offline.
+	max_downtime: u64,
+	/// Number of rounds between two checkpoints.
+	checkpoint_interval: u64,
+	/// Distance from the best block of the block finalized at a checkpoint.
+	finality_depth: u64,
+}
+
+impl Default for SoakConfig {
+	fn default() -> Self {
+		SoakConfig {
+			peers: 4,
+			rounds: 100,
+			seed: std::env::var("SOAK_SEED").ok()
+				.and_then(|seed| seed.parse().ok())
+				.unwrap_or_else(rand::random),
+			max_latency: 3,
+			max_steps_per_round: 20,
+			partition_probability: 0.05,
+			heal_probability: 0.1,
+			restart_probability: 0.03,
+			max_downtime: 20,
+			checkpoint_interval: 50,
+			finality_depth: 5,
+		}
+	}
+}
+
+struct Soak {
+	config: SoakConfig,
+	net: TestNet,
+	runtime: current_thread::Runtime,
+	rng: StdRng,
+	/// Number of steps before each node is polled again.
+	delays: Vec<u32>,
+	/// Round until which each restarted node is offline.
+	offline_until: Vec<Option<u64>>,
+	/// Side of each node, if the network is partitioned.
+	partition: Option<Vec<bool>>,
+	/// Reserved peers of each node, the nodes only connect to their reserved peers.
+	reserved: Vec<HashSet<usize>>,
+	/// Blocks finalized at the previous checkpoints.
+	finalized: Vec<(u64, H256)>,
+}
+
+impl Soak {
+	fn new(config: SoakConfig) -> Self {
+		let net = TestNet::new(config.peers);
+		for peer in net.peers() {
+			peer.network_service().deny_unreserved_peers();
+		}
+
+		Soak {
+			net,
+			runtime: current_thread::Runtime::new().unwrap(),
+			rng: StdRng::seed_from_u64(config.seed),
+			delays: vec![0; config.peers],
+			offline_until: vec![None; config.peers],
+			partition: None,
+			reserved: vec![HashSet::new(); config.peers],
+			finalized: Vec::new(),
+			config,
+		}
+	}
+
+	fn run(mut self) {
+		info!(target: "soak", "Starting soak with {:?}", self.config);
+		self.apply_topology();
+
+		for round in 1..=self.config.rounds {
+			self.random_events(round);
+
+			let online = (0..self.config.peers)
+				.filter(|&i| self.offline_until[i].is_none())
+				.collect::<Vec<_>>();
+			let author = online[self.rng.gen_range(0, online.len())];
+			self.author_block(author);
+
+			let steps = self.rng.gen_range(1, self.config.max_steps_per_round + 1);
+			self.run_steps(steps);
+
+			if round % self.config.checkpoint_interval == 0 {
+				self.checkpoint(round);
+			}
+		}
+		self.checkpoint(self.config.rounds);
+	}
+
+	/// Partitions and heals the network, and restarts nodes.
+	fn random_events(&mut self, round: u64) {
+		for offline_until in self.offline_until.iter_mut() {
+			if offline_until.map_or(false, |until| until <= round) {
+				*offline_until = None;
+			}
+		}
+
+		let peers = self.config.peers;
+		if self.partition.is_some() {
+			if self.rng.gen_bool(self.config.heal_probability) {
+				info!(target: "soak", "Round {}: healing the partition", round);
+				self.partition = None;
+			}
+		} else if self.rng.gen_bool(self.config.partition_probability) {
+			let mut sides = (0..peers).map(|_| self.rng.gen::<bool>()).collect::<Vec<_>>();
+			if sides.iter().all(|side| *side == sides[0]) {
+				let i = self.rng.gen_range(0, peers);
+				sides[i] = !sides[i];
+			}
+			info!(target: "soak", "Round {}: partitioning the network in {:?}", round, sides);
+			self.partition = Some(sides);
+		}
+
+		let online = (0..peers).filter(|&i| self.offline_until[i].is_none()).collect::<Vec<_>>();
+		if online.len() > 1 && self.rng.gen_bool(self.config.restart_probability) {
+			let node = online[self.rng.gen_range(0, online.len())];
+			let until = round + self.rng.gen_range(1, self.config.max_downtime + 1);
+			info!(target: "soak", "Round {}: restarting node {} until round {}", round, node, until);
+			self.offline_until[node] = Some(until);
+		}
+
+		self.apply_topology();
+	}
+
+	/// Returns true if the two nodes are allowed to connect.
+	fn can_connect(&self, a: usize, b: usize) -> bool {
+		a != b &&
+			self.offline_until[a].is_none() &&
+			self.offline_until[b].is_none() &&
+			self.partition.as_ref().map_or(true, |sides| sides[a] == sides[b])
+	}
+
+	/// Updates the reserved peers of the nodes to match the partitions and the offline nodes.
+	fn apply_topology(&mut self) {
+		let peers = self.config.peers;
+		for i in 0..peers {
+			let wanted = (0..peers).filter(|&j| self.can_connect(i, j)).collect::<HashSet<_>>();
+			let service = self.net.peers()[i].network_service();
+			for &j in self.reserved[i].difference(&wanted) {
+				service.remove_reserved_peer(self.net.peers()[j].id());
+			}
+			for &j in wanted.difference(&self.reserved[i]) {
+				service.add_reserved_peer(self.net.peers()[j].address())
+					.expect("addresses of the test peers are valid; qed");
+			}
+			self.reserved[i] = wanted;
+		}
+	}
+
+	/// Authors a block on top of the best block of the given node.
+	fn author_block(&mut self, author: usize) {
+		// the random data makes the blocks of different nodes at the same height distinct.
+		let data = self.rng.gen::<[u8; 32]>().to_vec();
+		self.net.peer(author).generate_blocks(1, BlockOrigin::Own, |mut builder| {
+			builder.push(Extrinsic::IncludeData(data.clone())).unwrap();
+			builder.bake().unwrap()
+		});
+	}
+
+	/// Polls the network for the given number of steps, skipping the delayed nodes.
+	fn run_steps(&mut self, steps: u32) {
+		let Soak { net, runtime, rng, delays, config, .. } = self;
+		let mut remaining = steps;
+		runtime.block_on(futures::future::poll_fn::<(), (), _>(|| {
+			net.mut_peers(|peers| {
+				for (peer, delay) in peers.iter_mut().zip(delays.iter_mut()) {
+					if *delay == 0 {
+						peer.poll();
+						*delay = rng.gen_range(0, config.max_latency + 1);
+					} else {
+						*delay -= 1;
+					}
+				}
+			});
+
+			remaining -= 1;
+			if remaining == 0 {
+				return Ok(Async::Ready(()))
+			}
+			futures::task::current().notify();
+			Ok(Async::NotReady)
+		})).unwrap();
+	}
+
+	/// Polls the network until all the nodes have the same best block, or the timeout expires.
+	///
+	/// Returns true if the nodes are in sync.
+	fn sync(&mut self, timeout: Duration) -> bool {
+		let Soak { net, runtime, .. } = self;
+		let deadline = Instant::now() + timeout;
+		runtime.block_on(futures::future::poll_fn::<bool, (), _>(|| {
+			if let Async::Ready(()) = net.poll_until_sync() {
+				return Ok(Async::Ready(true))
+			}
+			if Instant::now() >= deadline {
+				return Ok(Async::Ready(false))
+			}
+			futures::task::current().notify();
+			Ok(Async::NotReady)
+		})).unwrap()
+	}
+
+	/// Heals the network, checks that the nodes converge without reverting finalized blocks, and
+	/// finalizes a new block on all of them.
+	fn checkpoint(&mut self, round: u64) {
+		let seed = self.config.seed;
+		info!(target: "soak", "Round {}: checkpoint", round);
+		self.partition = None;
+		for offline_until in self.offline_until.iter_mut() {
+			*offline_until = None;
+		}
+		self.apply_topology();
+
+		let started = Instant::now();
+		while !self.sync(STALL_TIMEOUT) {
+			assert!(
+				started.elapsed() < CONVERGENCE_TIMEOUT,
+				"Nodes haven't converged at round {} (seed {})", round, seed,
+			);
+			// nodes don't switch between forks of the same length, extend one of the longest.
+			let author = (0..self.config.peers)
+				.max_by_key(|&i| self.net.peers()[i].client().info().chain.best_number)
+				.expect("there is at least one node; qed");
+			self.author_block(author);
+		}
+
+		let peers = self.net.peers();
+		let best = peers[0].client().info().chain;
+		for (i, peer) in peers.iter().enumerate() {
+			assert_eq!(
+				peer.client().info().chain.best_hash, best.best_hash,
+				"Node {} has a different best block at round {} (seed {})", i, round, seed,
+			);
+			for &(number, hash) in &self.finalized {
+				let canon = peer.client().header(&BlockId::Number(number)).unwrap().map(|h| h.hash());
+				assert_eq!(
+					canon, Some(hash),
+					"Node {} reverted the finalized block #{} at round {} (seed {})", i, number, round, seed,
+				);
+			}
+		}
+
+		if best.best_number <= self.config.finality_depth {
+			return
+		}
+		let number = best.best_number - self.config.finality_depth;
+		if self.finalized.last().map_or(false, |&(finalized, _)| finalized >= number) {
+			return
+		}
+		let hash = peers[0].client().header(&BlockId::Number(number)).unwrap()
+			.expect("the best chain contains all the blocks up to the best one; qed")
+			.hash();
+		for peer in peers {
+			peer.client().finalize_block(BlockId::Hash(hash), None, true).unwrap();
+			assert_eq!(peer.client().info().chain.finalized_hash, hash);
+		}
+		self.finalized.push((number, hash));
+	}
+}
+
+#[test]
+fn short_soak() {
+	let _ = ::env_logger::try_init();
+	Soak::new(SoakConfig {
+		rounds: 60,
+		checkpoint_interval: 20,
+		..SoakConfig::default()
+	}).run();
+}
+
+#[test]
+#[ignore]
+fn long_soak() {
+	let _ = ::env_logger::try_init();
+	Soak::new(SoakConfig {
+		peers: 8,
+		rounds: 5000,
+		checkpoint_interval: 250,
+		finality_depth: 20,
+		..SoakConfig::default()
+	}).run();
+}
@@ -124,6 +124,18 @@ impl crate::traits::BareCryptoStore for KeyStore {
 		Ok(())
 	}
 
+	fn raw_public_keys(&self, id: KeyTypeId) -> Result<Vec<Vec<u8>>, ()> {
+		Ok(self.keys.get(&id).map(|keys| keys.keys().cloned().collect()).unwrap_or_default())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		public_keys.iter().all(|(k, t)| self.keys.get(t).and_then(|s| s.get(k)).is_some())
+	}
+
+	fn remove_key(&mut self, id: KeyTypeId, public: &[u8]) -> Result<bool, ()> {
+		Ok(self.keys.get_mut(&id).and_then(|keys| keys.remove(public)).is_some())
+	}
+
 	fn password(&self) -> Option<&str> {
 		None
 	}
@@ -63,6 +63,21 @@ pub trait BareCryptoStore: Send + Sync {
 	/// `Err` if there's some sort of weird filesystem error, but should generally be `Ok`.
 	fn insert_unknown(&mut self, _key_type: KeyTypeId, _suri: &str, _public: &[u8]) -> Result<(), ()>;
 
+	/// Returns the raw public keys of all the keys of the given key type.
+	///
+	/// `Err` if there's some sort of weird filesystem error, but should generally be `Ok`.
+	fn raw_public_keys(&self, id: KeyTypeId) -> Result<Vec<Vec<u8>>, ()>;
+
+	/// Checks if the private keys for the given public key and key type combinations exist.
+	///
+	/// Returns `true` iff all private keys could be found.
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool;
+
+	/// Remove the key of the given key type and public key.
+	///
+	/// Returns `false` if the key wasn't in the store.
+	fn remove_key(&mut self, id: KeyTypeId, public: &[u8]) -> Result<bool, ()>;
+
 	/// Get the password for this store.
 	fn password(&self) -> Option<&str>;
 }
@@ -18,31 +18,38 @@
 
 #![warn(missing_docs)]
 
+mod middleware;
+
 use std::io;
-use jsonrpc_core::IoHandlerExtension;
+use jsonrpc_core::{IoHandlerExtension, MetaIoHandler};
 use log::error;
 use pubsub::PubSubMetadata;
 
-/// Maximal payload accepted by RPC servers.
-const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
+/// Default maximal payload accepted by RPC servers.
+pub const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
 
 /// Default maximum number of connections for WS RPC servers.
 const WS_MAX_CONNECTIONS: usize = 100;
 
 /// The RPC IoHandler containing all requested APIs.
-pub type RpcHandler<T> = pubsub::PubSubHandler<T>;
+pub type RpcHandler<T> = pubsub::PubSubHandler<T, RpcMiddleware>;
 
 pub use self::inner::*;
+pub use self::middleware::{RpcMiddleware, RpcPolicy, DenyUnsafe, UNSAFE_METHODS, is_unsafe};
 
-/// Construct rpc `IoHandler`
+/// Construct rpc `IoHandler`, whose calls go through the given middleware.
 pub fn rpc_handler<M: PubSubMetadata>(
-	extension: impl IoHandlerExtension<M>
+	extension: impl IoHandlerExtension<M>,
+	middleware: RpcMiddleware,
 ) -> RpcHandler<M> {
-	let mut io = pubsub::PubSubHandler::default();
+	let mut io = pubsub::PubSubHandler::new(MetaIoHandler::with_middleware(middleware.clone()));
 	extension.augment(&mut io);
 
 	// add an endpoint to list all available methods.
-	let mut methods = io.iter().map(|x| x.0.clone()).collect::<Vec<String>>();
+	let mut methods = io.iter()
+		.map(|x| x.0.clone())
+		.filter(|method| middleware.is_allowed(method))
+		.collect::<Vec<String>>();
 	io.add_method("rpc_methods", {
 		methods.sort();
 		let methods = serde_json::to_value(&methods)
@@ -64,13 +71,18 @@ mod inner {
 	pub type HttpServer = http::Server;
 	/// Type alias for ws server
 	pub type WsServer = ws::Server;
+	/// Type alias for ipc server
+	pub type IpcServer = ipc::Server;
 
 	/// Start HTTP server listening on given address.
 	///
+	/// Requests larger than `max_payload` bytes, or [`MAX_PAYLOAD`] if `None`, are refused.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
 	pub fn start_http<M: pubsub::PubSubMetadata + Default>(
 		addr: &std::net::SocketAddr,
 		cors: Option<&Vec<String>>,
+		max_payload: Option<usize>,
 		io: RpcHandler<M>,
 	) -> io::Result<http::Server> {
 		http::ServerBuilder::new(io)
@@ -83,21 +95,24 @@ mod inner {
 				http::RestApi::Unsecure
 			})
 			.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
-			.max_request_body_size(MAX_PAYLOAD)
+			.max_request_body_size(max_payload.unwrap_or(MAX_PAYLOAD))
 			.start_http(addr)
 	}
 
 	/// Start WS server listening on given address.
 	///
+	/// Messages larger than `max_payload` bytes, or [`MAX_PAYLOAD`] if `None`, are refused.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
 	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
 		addr: &std::net::SocketAddr,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
+		max_payload: Option<usize>,
 		io: RpcHandler<M>,
 	) -> io::Result<ws::Server> {
 		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
-			.max_payload(MAX_PAYLOAD)
+			.max_payload(max_payload.unwrap_or(MAX_PAYLOAD))
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 			.allowed_origins(map_cors(cors))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -112,6 +127,18 @@ mod inner {
 			})
 	}
 
+	/// Start IPC server listening on the Unix socket, or the named pipe on Windows, at the given
+	/// path.
+	///
+	/// **Note**: Only available if `not(target_os = "unknown")`.
+	pub fn start_ipc<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>>(
+		path: &str,
+		io: RpcHandler<M>,
+	) -> io::Result<ipc::Server> {
+		ipc::ServerBuilder::with_meta_extractor(io, |context: &ipc::RequestContext| context.sender.clone().into())
+			.start(path)
+	}
+
 	fn map_cors<T: for<'a> From<&'a str>>(
 		cors: Option<&Vec<String>>
 	) -> http::DomainsValidation<T> {
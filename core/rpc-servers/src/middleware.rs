@@ -0,0 +1,348 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Policies restricting the methods served by the RPC servers.
+//!
+//! Operators exposing an RPC interface publicly can restrict it to a subset of the methods, deny the
+//! unsafe ones, limit the rate of the calls to the expensive ones, and bound the size of the batches
+//! of calls and of the responses, so that a single client can't keep the RPC threads busy with a
+//! huge batch.
+//!
+//! The policy is enforced by a middleware of the RPC handlers. Clones of the middleware share their
+//! rate limits, so that the limits apply to the calls received by all the servers.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use jsonrpc_core::{
+	futures::{Future, future::{self, Either, FutureResult}},
+	middleware, Call, Error, ErrorCode, Metadata, Middleware, Output, Request, Response, Version,
+};
+use parking_lot::Mutex;
+
+/// Error code of the calls to methods that aren't allowed.
+const METHOD_NOT_ALLOWED: i64 = -32010;
+
+/// Error code of the calls exceeding the rate limit of their method.
+const RATE_LIMIT_EXCEEDED: i64 = -32011;
+
+/// Error code of the batches with too many calls.
+const BATCH_TOO_LARGE: i64 = -32012;
+
+/// Error code of the calls whose response is too large.
+const RESPONSE_TOO_LARGE: i64 = -32013;
+
+/// Length of the windows the calls are counted over.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Methods that should only be served to trusted clients.
+///
+/// They alter the keystore, the transaction pool, the configuration or the chain of the node, reveal
+/// its keys or its network topology, or are expensive enough to be abused, e.g. because the size of
+/// their response isn't bounded. All the other methods are safe to expose publicly.
+pub const UNSAFE_METHODS: &[&str] = &[
+	// keystore
+	"author_insertKey",
+	"author_rotateKeys",
+	"author_removeKeys",
+	"author_hasKey",
+	"author_hasSessionKeys",
+	"author_listKeys",
+	// transaction pool
+	"author_removeExtrinsic",
+	"author_removeSenderExtrinsics",
+	// node
+	"system_networkState",
+	"system_peers",
+	"system_reloadConfig",
+	// manual sealing
+	"engine_createBlock",
+	"engine_finalizeBlock",
+	// unbounded responses
+	"state_getKeys",
+	"state_getChildKeys",
+	"childstate_getKeys",
+	"state_queryStorage",
+	"state_getReadProof",
+	"state_getPrefixReadProof",
+	// debugging
+	"state_callWithAllocationStats",
+	"state_getBlockStorageDiff",
+	"state_traceBlock",
+];
+
+/// Is the given method unsafe, i.e. one of the [`UNSAFE_METHODS`]?
+pub fn is_unsafe(method: &str) -> bool {
+	UNSAFE_METHODS.contains(&method)
+}
+
+/// Whether the [`UNSAFE_METHODS`] are denied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenyUnsafe {
+	/// Deny the unsafe methods, even if they are allowed.
+	Yes,
+	/// Serve the unsafe methods if they are allowed.
+	No,
+}
+
+impl Default for DenyUnsafe {
+	fn default() -> Self {
+		DenyUnsafe::No
+	}
+}
+
+impl DenyUnsafe {
+	/// Is the given method denied?
+	pub fn denies(self, method: &str) -> bool {
+		self == DenyUnsafe::Yes && is_unsafe(method)
+	}
+}
+
+/// Methods served by the RPC servers, the rate they are served at, and the size of the batches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpcPolicy {
+	/// Methods allowed. All the methods that aren't denied if `None`.
+	pub allowed_methods: Option<HashSet<String>>,
+	/// Methods denied, even if they are allowed.
+	pub denied_methods: HashSet<String>,
+	/// Whether the [`UNSAFE_METHODS`] are denied, even if they are allowed.
+	pub deny_unsafe: DenyUnsafe,
+	/// Maximum number of calls per second to a method, across all clients.
+	pub rate_limits: HashMap<String, u32>,
+	/// Maximum number of calls in a batch. Unlimited if `None`.
+	pub max_batch_size: Option<usize>,
+	/// Maximum size in bytes of the response to a call. Unlimited if `None`.
+	///
+	/// The responses of the calls of a batch are limited individually.
+	pub max_response_size: Option<usize>,
+}
+
+impl RpcPolicy {
+	/// Is the given method allowed by the policy?
+	pub fn is_allowed(&self, method: &str) -> bool {
+		!self.denied_methods.contains(method) &&
+			!self.deny_unsafe.denies(method) &&
+			self.allowed_methods.as_ref().map_or(true, |allowed| allowed.contains(method))
+	}
+}
+
+/// Calls to a rate limited method in the current window.
+struct Window {
+	start: Instant,
+	calls: u32,
+}
+
+/// Middleware of the RPC handlers enforcing an [`RpcPolicy`].
+#[derive(Clone, Default)]
+pub struct RpcMiddleware {
+	policy: Arc<RpcPolicy>,
+	windows: Arc<Mutex<HashMap<String, Window>>>,
+}
+
+impl RpcMiddleware {
+	/// Create a middleware enforcing the given policy.
+	pub fn new(policy: RpcPolicy) -> Self {
+		RpcMiddleware {
+			policy: Arc::new(policy),
+			windows: Default::default(),
+		}
+	}
+
+	/// Is the given method allowed by the policy?
+	pub fn is_allowed(&self, method: &str) -> bool {
+		self.policy.is_allowed(method)
+	}
+
+	/// Check a call to the given method against the policy, counting it if it is rate limited.
+	fn check(&self, method: &str, now: Instant) -> Result<(), Error> {
+		if !self.policy.is_allowed(method) {
+			return Err(Error {
+				code: ErrorCode::ServerError(METHOD_NOT_ALLOWED),
+				message: format!("Method {} is not allowed on this RPC interface", method),
+				data: None,
+			})
+		}
+
+		if let Some(limit) = self.policy.rate_limits.get(method) {
+			let mut windows = self.windows.lock();
+			let window = windows.entry(method.to_owned()).or_insert(Window { start: now, calls: 0 });
+			if now.duration_since(window.start) >= RATE_LIMIT_WINDOW {
+				*window = Window { start: now, calls: 0 };
+			}
+			if window.calls >= *limit {
+				return Err(Error {
+					code: ErrorCode::ServerError(RATE_LIMIT_EXCEEDED),
+					message: format!("Rate limit of method {} exceeded, retry later", method),
+					data: None,
+				})
+			}
+			window.calls += 1;
+		}
+		Ok(())
+	}
+
+	/// Replace the output by an error if it is larger than the maximum response size.
+	fn limit_output(&self, output: Output) -> Output {
+		let max = match self.policy.max_response_size {
+			Some(max) => max,
+			None => return output,
+		};
+		let size = serde_json::to_vec(&output).map(|encoded| encoded.len()).unwrap_or(0);
+		if size <= max {
+			return output
+		}
+		let error = Error {
+			code: ErrorCode::ServerError(RESPONSE_TOO_LARGE),
+			message: format!("Response of {} bytes exceeds the limit of {} bytes", size, max),
+			data: None,
+		};
+		Output::from(Err(error), output.id().clone(), output.version())
+	}
+}
+
+impl<M: Metadata> Middleware<M> for RpcMiddleware {
+	type Future = middleware::NoopFuture;
+	type CallFuture = FutureResult<Option<Output>, ()>;
+
+	fn on_request<F, X>(&self, request: Request, meta: M, next: F) -> Either<Self::Future, X> where
+		F: FnOnce(Request, M) -> X + Send,
+		X: Future<Item = Option<Response>, Error = ()> + Send + 'static,
+	{
+		if let (Request::Batch(calls), Some(max)) = (&request, self.policy.max_batch_size) {
+			if calls.len() > max {
+				let error = Error {
+					code: ErrorCode::ServerError(BATCH_TOO_LARGE),
+					message: format!("Batch of {} calls exceeds the limit of {} calls", calls.len(), max),
+					data: None,
+				};
+				return Either::A(Box::new(future::ok(Some(Response::from(error, Some(Version::V2))))))
+			}
+		}
+		if self.policy.max_response_size.is_none() {
+			return Either::B(next(request, meta))
+		}
+
+		let this = self.clone();
+		Either::A(Box::new(next(request, meta).map(move |response| response.map(|response| match response {
+			Response::Single(output) => Response::Single(this.limit_output(output)),
+			Response::Batch(outputs) => Response::Batch(
+				outputs.into_iter().map(|output| this.limit_output(output)).collect()
+			),
+		}))))
+	}
+
+	fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X> where
+		F: FnOnce(Call, M) -> X + Send,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		let now = Instant::now();
+		match &call {
+			Call::MethodCall(method_call) => if let Err(error) = self.check(&method_call.method, now) {
+				let output = Output::from(Err(error), method_call.id.clone(), method_call.jsonrpc);
+				return Either::A(future::ok(Some(output)))
+			},
+			// notifications are dropped silently, as they have no response.
+			Call::Notification(notification) => if self.check(&notification.method, now).is_err() {
+				return Either::A(future::ok(None))
+			},
+			Call::Invalid { .. } => {},
+		}
+		Either::B(next(call, meta))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use jsonrpc_core::{MetaIoHandler, Value};
+
+	fn policy() -> RpcPolicy {
+		RpcPolicy {
+			allowed_methods: Some(vec!["system_health".into(), "state_call".into()].into_iter().collect()),
+			denied_methods: vec!["state_call".into()].into_iter().collect(),
+			rate_limits: vec![("system_health".into(), 2)].into_iter().collect(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn methods_are_allowed_and_denied() {
+		let policy = policy();
+		assert!(policy.is_allowed("system_health"));
+		assert!(!policy.is_allowed("state_call"));
+		assert!(!policy.is_allowed("author_rotateKeys"));
+		assert!(RpcPolicy::default().is_allowed("author_rotateKeys"));
+		let deny_unsafe = RpcPolicy { deny_unsafe: DenyUnsafe::Yes, ..Default::default() };
+		assert!(!deny_unsafe.is_allowed("author_removeExtrinsic"));
+		assert!(!deny_unsafe.is_allowed("system_networkState"));
+		assert!(!deny_unsafe.is_allowed("state_getKeys"));
+		assert!(deny_unsafe.is_allowed("state_getKeysPaged"));
+		assert!(deny_unsafe.is_allowed("author_submitExtrinsic"));
+		assert!(deny_unsafe.is_allowed("system_health"));
+
+		let middleware = RpcMiddleware::new(policy);
+		let error = middleware.check("state_call", Instant::now()).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(METHOD_NOT_ALLOWED));
+	}
+
+	#[test]
+	fn calls_are_rate_limited() {
+		let middleware = RpcMiddleware::new(policy());
+		let shared = middleware.clone();
+		let start = Instant::now();
+
+		assert!(middleware.check("system_health", start).is_ok());
+		assert!(shared.check("system_health", start).is_ok());
+		let error = middleware.check("system_health", start + Duration::from_millis(500)).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(RATE_LIMIT_EXCEEDED));
+
+		// the next window starts anew.
+		assert!(shared.check("system_health", start + RATE_LIMIT_WINDOW).is_ok());
+	}
+
+	#[test]
+	fn batches_and_responses_are_limited() {
+		let policy = RpcPolicy {
+			max_batch_size: Some(2),
+			max_response_size: Some(64),
+			..Default::default()
+		};
+		let mut io = MetaIoHandler::<(), _>::with_middleware(RpcMiddleware::new(policy));
+		io.add_method("small", |_| Ok(Value::String("small".into())));
+		io.add_method("large", |_| Ok(Value::String("large".repeat(100))));
+
+		let call = |method: &str, id: u32| format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":{}}}"#, method, id);
+		let batch = |calls: &[String]| format!("[{}]", calls.join(","));
+
+		assert_eq!(
+			io.handle_request_sync(&call("small", 1), ()).unwrap(),
+			r#"{"jsonrpc":"2.0","result":"small","id":1}"#,
+		);
+		let response = io.handle_request_sync(&call("large", 2), ()).unwrap();
+		assert!(response.contains(&RESPONSE_TOO_LARGE.to_string()));
+		assert!(response.contains(r#""id":2"#));
+
+		// only the outputs that are too large are replaced.
+		let response = io.handle_request_sync(&batch(&[call("small", 1), call("large", 2)]), ()).unwrap();
+		assert!(response.contains(r#""result":"small""#));
+		assert!(response.contains(&RESPONSE_TOO_LARGE.to_string()));
+
+		let response = io.handle_request_sync(&batch(&[call("small", 1), call("small", 2), call("small", 3)]), ()).unwrap();
+		assert!(response.contains(&BATCH_TOO_LARGE.to_string()));
+		assert!(!response.contains(r#""result""#));
+	}
+}
@@ -16,7 +16,7 @@
 
 //! Authoring RPC module errors.
 
-use crate::errors;
+use crate::errors::ClientError;
 use jsonrpc_core as rpc;
 
 /// Author RPC Result type.
@@ -30,7 +30,7 @@ pub type FutureResult<T> = Box<dyn rpc::futures::Future<Item = T, Error = Error>
 pub enum Error {
 	/// Client error.
 	#[display(fmt="Client error: {}", _0)]
-	Client(Box<dyn std::error::Error + Send>),
+	Client(ClientError),
 	/// Transaction pool error,
 	#[display(fmt="Transaction pool error: {}", _0)]
 	Pool(txpool::error::Error),
@@ -52,12 +52,18 @@ pub enum Error {
 	/// Some random issue with the key store. Shouldn't happen.
 	#[display(fmt="The key store is unavailable")]
 	KeyStoreUnavailable,
+	/// Invalid session keys encoding.
+	#[display(fmt="Session keys are not encoded correctly")]
+	InvalidSessionKeys,
+	/// Removing keys through the RPC isn't enabled.
+	#[display(fmt="Removing keys is not enabled on this node")]
+	KeyRemovalDisabled,
 }
 
 impl std::error::Error for Error {
 	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match self {
-			Error::Client(ref err) => Some(&**err),
+			Error::Client(ref err) => Some(err),
 			Error::Pool(ref err) => Some(err),
 			Error::Verification(ref err) => Some(&**err),
 			_ => None,
@@ -71,6 +77,16 @@ const BASE_ERROR: i64 = 1000;
 const BAD_FORMAT: i64 = BASE_ERROR + 1;
 /// Error during transaction verification in runtime.
 const VERIFICATION_ERROR: i64 = BASE_ERROR + 2;
+/// The seed phrase or SURI is invalid.
+const BAD_SEED_PHRASE: i64 = BASE_ERROR + 3;
+/// The key type ID has an invalid format.
+const BAD_KEY_TYPE: i64 = BASE_ERROR + 4;
+/// The key store is unavailable.
+const KEY_STORE_UNAVAILABLE: i64 = BASE_ERROR + 5;
+/// The session keys are not encoded correctly.
+const INVALID_SESSION_KEYS: i64 = BASE_ERROR + 6;
+/// Removing keys isn't enabled.
+const KEY_REMOVAL_DISABLED: i64 = BASE_ERROR + 7;
 
 /// Pool rejected the transaction as invalid
 const POOL_INVALID_TX: i64 = BASE_ERROR + 10;
@@ -88,6 +104,12 @@ const POOL_CYCLE_DETECTED: i64 = POOL_INVALID_TX + 5;
 const POOL_IMMEDIATELY_DROPPED: i64 = POOL_INVALID_TX + 6;
 /// The key type crypto is not known.
 const UNSUPPORTED_KEY_TYPE: i64 = POOL_INVALID_TX + 7;
+/// The transaction does not provide any tags, so the pool can't identify it.
+const POOL_NO_TAGS_PROVIDED: i64 = POOL_INVALID_TX + 8;
+/// The block the transaction was validated against is invalid.
+const POOL_INVALID_BLOCK_ID: i64 = POOL_INVALID_TX + 9;
+/// The sender already has too many transactions in the pool.
+const POOL_TOO_MANY_FROM_SENDER: i64 = POOL_INVALID_TX + 10;
 
 impl From<Error> for rpc::Error {
 	fn from(e: Error) -> Self {
@@ -129,6 +151,11 @@ impl From<Error> for rpc::Error {
 				message: format!("Priority is too low: ({} vs {})", old, new),
 				data: Some("The transaction has too low priority to replace another transaction already in the pool.".into()),
 			},
+			Error::Pool(PoolError::TooManyFromSender { limit }) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(POOL_TOO_MANY_FROM_SENDER),
+				message: "Too Many Transactions From Sender".into(),
+				data: Some(format!("The sender can't have more than {} transactions in the pool.", limit).into()),
+			},
 			Error::Pool(PoolError::CycleDetected) => rpc::Error {
 				code: rpc::ErrorCode::ServerError(POOL_CYCLE_DETECTED),
 				message: "Cycle Detected".into(),
@@ -147,7 +174,42 @@ impl From<Error> for rpc::Error {
 					request to insert the key successfully.".into()
 				),
 			},
-			e => errors::internal(e),
+			Error::Pool(PoolError::NoTagsProvided) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(POOL_NO_TAGS_PROVIDED),
+				message: "No Tags Provided".into(),
+				data: Some("The transaction does not provide any tags, so the pool can't identify it.".into()),
+			},
+			Error::Pool(PoolError::InvalidBlockId(e)) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(POOL_INVALID_BLOCK_ID),
+				message: "Invalid Block Id".into(),
+				data: Some(e.into()),
+			},
+			Error::BadSeedPhrase => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BAD_SEED_PHRASE),
+				message: "Invalid seed phrase/SURI".into(),
+				data: None,
+			},
+			Error::BadKeyType => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BAD_KEY_TYPE),
+				message: "Invalid key type ID format".into(),
+				data: Some("The key type ID should be of length four.".into()),
+			},
+			Error::KeyStoreUnavailable => rpc::Error {
+				code: rpc::ErrorCode::ServerError(KEY_STORE_UNAVAILABLE),
+				message: "The key store is unavailable".into(),
+				data: None,
+			},
+			Error::InvalidSessionKeys => rpc::Error {
+				code: rpc::ErrorCode::ServerError(INVALID_SESSION_KEYS),
+				message: "Session keys are not encoded correctly".into(),
+				data: None,
+			},
+			Error::KeyRemovalDisabled => rpc::Error {
+				code: rpc::ErrorCode::ServerError(KEY_REMOVAL_DISABLED),
+				message: "Removing keys is not enabled on this node".into(),
+				data: Some("Start the node with `--rpc-allow-key-removal` to enable it.".into()),
+			},
+			Error::Client(e) => e.into(),
 		}
 	}
 }
@@ -40,6 +40,8 @@ pub trait AuthorApi<Hash, BlockHash> {
 	fn submit_extrinsic(&self, extrinsic: Bytes) -> FutureResult<Hash>;
 
 	/// Insert a key into the keystore.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "author_insertKey")]
 	fn insert_key(&self,
 		key_type: String,
@@ -48,20 +50,69 @@ pub trait AuthorApi<Hash, BlockHash> {
 	) -> Result<()>;
 
 	/// Generate new session keys and returns the corresponding public keys.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "author_rotateKeys")]
 	fn rotate_keys(&self) -> Result<Bytes>;
 
+	/// Checks if the keystore has private keys for the given session public keys.
+	///
+	/// `session_keys` is the SCALE encoded session keys object from the runtime, as returned by
+	/// `author_rotateKeys`.
+	///
+	/// Returns `true` iff all private keys could be found.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "author_hasSessionKeys")]
+	fn has_session_keys(&self, session_keys: Bytes) -> Result<bool>;
+
+	/// Checks if the keystore has the private key for the given public key and key type.
+	///
+	/// Returns `true` if a private key could be found. This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "author_hasKey")]
+	fn has_key(&self, public_key: Bytes, key_type: String) -> Result<bool>;
+
+	/// Returns the public keys of the given key type in the keystore.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "author_listKeys")]
+	fn list_keys(&self, key_type: String) -> Result<Vec<Bytes>>;
+
+	/// Removes the private keys of the given session public keys from the keystore.
+	///
+	/// `session_keys` is the SCALE encoded session keys object from the runtime. Only available
+	/// on nodes started with `--rpc-allow-key-removal`. This method is unsafe, see `--rpc-methods`.
+	///
+	/// Returns the public keys that were removed.
+	#[rpc(name = "author_removeKeys")]
+	fn remove_keys(&self, session_keys: Bytes) -> Result<Vec<Bytes>>;
+
 	/// Returns all pending extrinsics, potentially grouped by sender.
 	#[rpc(name = "author_pendingExtrinsics")]
 	fn pending_extrinsics(&self) -> Result<Vec<Bytes>>;
 
 	/// Remove given extrinsic from the pool and temporarily ban it to prevent reimporting.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "author_removeExtrinsic")]
 	fn remove_extrinsic(&self,
 		bytes_or_hash: Vec<hash::ExtrinsicOrHash<Hash>>
 	) -> Result<Vec<Hash>>;
 
+	/// Remove all the extrinsics of the given SCALE encoded sender from the pool and temporarily
+	/// ban them to prevent reimporting.
+	///
+	/// Nothing is removed if the pool can't tell the senders of the extrinsics. This method is
+	/// unsafe, see `--rpc-methods`.
+	///
+	/// Returns the hashes of the removed extrinsics.
+	#[rpc(name = "author_removeSenderExtrinsics")]
+	fn remove_sender_extrinsics(&self, sender: Bytes) -> Result<Vec<Hash>>;
+
 	/// Submit an extrinsic to watch.
+	///
+	/// Every change of the status of the extrinsic is notified, from its entrance in the pool to
+	/// its finalization or its removal from the pool.
 	#[pubsub(
 		subscription = "author_extrinsicUpdate",
 		subscribe,
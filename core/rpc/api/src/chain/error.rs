@@ -17,7 +17,7 @@
 
 //! Error helpers for Chain RPC module.
 
-use crate::errors;
+use crate::errors::ClientError;
 use jsonrpc_core as rpc;
 
 /// Chain RPC Result type.
@@ -31,7 +31,7 @@ pub type FutureResult<T> = Box<dyn rpc::futures::Future<Item = T, Error = Error>
 pub enum Error {
 	/// Client error.
 	#[display(fmt="Client error: {}", _0)]
-	Client(Box<dyn std::error::Error + Send>),
+	Client(ClientError),
 	/// Other error type.
 	Other(String),
 }
@@ -39,7 +39,7 @@ pub enum Error {
 impl std::error::Error for Error {
 	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match self {
-			Error::Client(ref err) => Some(&**err),
+			Error::Client(ref err) => Some(err),
 			_ => None,
 		}
 	}
@@ -56,7 +56,7 @@ impl From<Error> for rpc::Error {
 				message,
 				data: None,
 			},
-			e => errors::internal(e),
+			Error::Client(e) => e.into(),
 		}
 	}
 }
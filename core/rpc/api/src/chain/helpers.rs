@@ -0,0 +1,86 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate blockchain API helpers.
+
+use primitives::Bytes;
+use serde::{Serialize, Deserialize};
+
+/// Aggregate statistics over a range of blocks of the canonical chain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainStats<Number> {
+	/// First block of the range.
+	pub from: Number,
+	/// Last block of the range.
+	pub to: Number,
+	/// Number of blocks of the range for which statistics were collected.
+	pub block_count: u32,
+	/// Total number of extrinsics in these blocks.
+	pub total_extrinsics: u64,
+	/// Average size of the extrinsics of these blocks, relative to the maximal block size.
+	pub average_fullness: f64,
+	/// Number of reorgs observed at blocks of the range.
+	pub reorg_count: u32,
+}
+
+/// Execution metrics of a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionMetrics<Number, Hash> {
+	/// Number of the block.
+	pub number: Number,
+	/// Hash of the block.
+	pub hash: Hash,
+	/// Wall time taken by the execution, in microseconds.
+	pub execution_time_us: u64,
+	/// Number of storage keys read.
+	pub storage_reads: u64,
+	/// Total size of the storage values read.
+	pub storage_read_bytes: u64,
+	/// Number of storage keys written.
+	pub storage_writes: u64,
+	/// Wasm fuel consumed by the execution, if metered.
+	pub fuel: Option<u64>,
+	/// Encoded size of the storage proof of the execution, if estimated.
+	pub proof_size: Option<u64>,
+}
+
+/// A finalized header, along with the proof of its finality.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinalizedHeader<Header> {
+	/// The finalized header.
+	pub header: Header,
+	/// The proof of the finality of the block, if the node could produce one.
+	///
+	/// Nodes with a finality proof provider send the proof it produces for a caller knowing the
+	/// finality of the parent block, which may finalize a descendant of the block. Other nodes
+	/// send the justification they stored for the block, if any.
+	pub justification: Option<Bytes>,
+}
+
+/// An extrinsic along with its location in the chain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedExtrinsic<Hash> {
+	/// Hash of the block that includes the extrinsic.
+	pub block_hash: Hash,
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// The SCALE encoded extrinsic.
+	pub extrinsic: Bytes,
+}
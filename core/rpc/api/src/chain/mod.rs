@@ -17,6 +17,7 @@
 //! Substrate blockchain API.
 
 pub mod error;
+pub mod helpers;
 
 use jsonrpc_core::Result as RpcResult;
 use jsonrpc_core::futures::Future;
@@ -24,6 +25,7 @@ use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use rpc_primitives::number;
 use self::error::{FutureResult, Result};
+use self::helpers::{ChainStats, ExecutionMetrics, FinalizedHeader, IndexedExtrinsic};
 
 pub use self::gen_client::Client as ChainClient;
 
@@ -51,6 +53,29 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	#[rpc(name = "chain_getFinalizedHead", alias("chain_getFinalisedHead"))]
 	fn finalized_head(&self) -> Result<Hash>;
 
+	/// Get aggregate statistics over the canon chain blocks numbered `from` to `to`, inclusive.
+	///
+	/// By default `to` is the best block. Only available if the node collects chain statistics.
+	#[rpc(name = "chain_getStats")]
+	fn stats(
+		&self,
+		from: number::NumberOrHex<Number>,
+		to: Option<number::NumberOrHex<Number>>,
+	) -> Result<ChainStats<Number>>;
+
+	/// Get the execution metrics of the `count` most recent blocks of the canon chain.
+	///
+	/// By default only the metrics of the best block are returned. Blocks whose metrics were not
+	/// recorded are skipped.
+	#[rpc(name = "chain_getExecutionMetrics")]
+	fn execution_metrics(&self, count: Option<u32>) -> Result<Vec<ExecutionMetrics<Number, Hash>>>;
+
+	/// Get the extrinsic with the given hash, along with the block that includes it.
+	///
+	/// Only available if the node maintains an index of extrinsics.
+	#[rpc(name = "chain_getExtrinsic")]
+	fn extrinsic(&self, hash: Hash) -> Result<Option<IndexedExtrinsic<Hash>>>;
+
 	/// New head subscription
 	#[pubsub(
 		subscription = "chain_newHead",
@@ -86,4 +111,29 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 		alias("chain_unsubscribeFinalisedHeads")
 	)]
 	fn unsubscribe_finalized_heads(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
+
+	/// Finalized head subscription, whose notifications carry the proof of the finality of the
+	/// blocks.
+	#[pubsub(
+		subscription = "chain_finalizedHeadWithJustification",
+		subscribe,
+		name = "chain_subscribeFinalizedHeadsWithJustification"
+	)]
+	fn subscribe_finalized_heads_with_justification(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<FinalizedHeader<Header>>,
+	);
+
+	/// Unsubscribe from finalized head with justification subscription.
+	#[pubsub(
+		subscription = "chain_finalizedHeadWithJustification",
+		unsubscribe,
+		name = "chain_unsubscribeFinalizedHeadsWithJustification"
+	)]
+	fn unsubscribe_finalized_heads_with_justification(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool>;
 }
@@ -0,0 +1,84 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Substrate child state API.
+//!
+//! Queries of the child tries of the state, with the child storage key passed explicitly.
+
+use jsonrpc_derive::rpc;
+use primitives::storage::{StorageKey, StorageData};
+use crate::state::error::FutureResult;
+
+pub use self::gen_client::Client as ChildStateClient;
+
+/// Substrate child state API
+#[rpc]
+pub trait ChildStateApi<Hash> {
+	/// RPC Metadata
+	type Metadata;
+
+	/// Returns the keys with prefix from a child storage, leave empty to get all the keys.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "childstate_getKeys")]
+	fn storage_keys(
+		&self,
+		child_storage_key: StorageKey,
+		prefix: StorageKey,
+		hash: Option<Hash>,
+	) -> FutureResult<Vec<StorageKey>>;
+
+	/// Returns at most `count` keys with prefix from a child storage, starting after `start_key`.
+	///
+	/// At most `state::MAX_KEYS_PAGE_SIZE` keys are returned by a single call.
+	#[rpc(name = "childstate_getKeysPaged")]
+	fn storage_keys_paged(
+		&self,
+		child_storage_key: StorageKey,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		hash: Option<Hash>,
+	) -> FutureResult<Vec<StorageKey>>;
+
+	/// Returns a child storage entry at a specific block's state.
+	#[rpc(name = "childstate_getStorage")]
+	fn storage(
+		&self,
+		child_storage_key: StorageKey,
+		key: StorageKey,
+		hash: Option<Hash>,
+	) -> FutureResult<Option<StorageData>>;
+
+	/// Returns the hash of a child storage entry at a block's state.
+	#[rpc(name = "childstate_getStorageHash")]
+	fn storage_hash(
+		&self,
+		child_storage_key: StorageKey,
+		key: StorageKey,
+		hash: Option<Hash>,
+	) -> FutureResult<Option<Hash>>;
+
+	/// Returns the size of a child storage entry at a block's state.
+	#[rpc(name = "childstate_getStorageSize")]
+	fn storage_size(
+		&self,
+		child_storage_key: StorageKey,
+		key: StorageKey,
+		hash: Option<Hash>,
+	) -> FutureResult<Option<u64>>;
+}
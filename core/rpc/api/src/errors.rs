@@ -14,13 +14,121 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use log::warn;
-
-pub fn internal<E: ::std::fmt::Debug>(e: E) -> jsonrpc_core::Error {
-	warn!("Unknown error: {:?}", e);
-	jsonrpc_core::Error {
-		code: jsonrpc_core::ErrorCode::InternalError,
-		message: "Unknown error occured".into(),
-		data: Some(format!("{:?}", e).into()),
+//! Error codes of the RPC API.
+//!
+//! All errors returned by the RPC methods have a code from a stable code space, so that API
+//! consumers can tell errors apart without parsing the error messages:
+//!
+//! | Codes       | Errors                                              |
+//! |-------------|-----------------------------------------------------|
+//! | 1 - 999     | Errors of the RPCs of runtime modules               |
+//! | 1000 - 1009 | `author` errors                                     |
+//! | 1010 - 1029 | Transaction pool errors                             |
+//! | 2000 - 2999 | `system` errors                                     |
+//! | 3000 - 3999 | `chain` errors                                      |
+//! | 4000 - 4999 | `state` and `childstate` errors                     |
+//! | 5000 - 5999 | Client errors, shared by all of the above           |
+//!
+//! Client errors carry their [`ClientErrorKind`] in the `data` field of the error, as
+//! `{ "kind": "unknownBlock" }`.
+
+use jsonrpc_core as rpc;
+use serde::{Serialize, Deserialize};
+
+/// Base code for all client errors.
+pub const CLIENT_BASE_ERROR: i64 = 5000;
+
+/// The kind of a client error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClientErrorKind {
+	/// Any other error. Code 5000.
+	Other,
+	/// The requested block is not known. Code 5001.
+	UnknownBlock,
+	/// The state of the requested block has been pruned. Code 5002.
+	StatePruned,
+	/// The request can't be served by a light client. Code 5003.
+	NotAvailableOnLightClient,
+	/// A call into the runtime failed. Code 5004.
+	Execution,
+	/// A consensus error. Code 5005.
+	Consensus,
+	/// An extrinsic could not be applied. Code 5006.
+	InvalidExtrinsic,
+	/// Data could not be fetched from a remote node. Code 5007.
+	RemoteFetchFailed,
+	/// The request is not supported by the node, or its configuration. Code 5008.
+	Unsupported,
+}
+
+impl ClientErrorKind {
+	/// The RPC error code of this kind of error.
+	pub fn code(&self) -> i64 {
+		CLIENT_BASE_ERROR + match self {
+			ClientErrorKind::Other => 0,
+			ClientErrorKind::UnknownBlock => 1,
+			ClientErrorKind::StatePruned => 2,
+			ClientErrorKind::NotAvailableOnLightClient => 3,
+			ClientErrorKind::Execution => 4,
+			ClientErrorKind::Consensus => 5,
+			ClientErrorKind::InvalidExtrinsic => 6,
+			ClientErrorKind::RemoteFetchFailed => 7,
+			ClientErrorKind::Unsupported => 8,
+		}
+	}
+}
+
+/// An error of the client, along with its kind.
+#[derive(Debug)]
+pub struct ClientError {
+	kind: ClientErrorKind,
+	error: Box<dyn std::error::Error + Send>,
+}
+
+impl ClientError {
+	/// Create a new client error of the given kind.
+	pub fn new(kind: ClientErrorKind, error: Box<dyn std::error::Error + Send>) -> Self {
+		ClientError { kind, error }
+	}
+
+	/// The kind of the error.
+	pub fn kind(&self) -> ClientErrorKind {
+		self.kind
+	}
+}
+
+impl std::fmt::Display for ClientError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.error)
+	}
+}
+
+impl std::error::Error for ClientError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&*self.error)
+	}
+}
+
+impl From<ClientError> for rpc::Error {
+	fn from(e: ClientError) -> Self {
+		rpc::Error {
+			code: rpc::ErrorCode::ServerError(e.kind.code()),
+			message: format!("Client error: {}", e.error),
+			data: Some(serde_json::json!({ "kind": e.kind })),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn client_errors_have_kind_in_data() {
+		let error = ClientError::new(ClientErrorKind::StatePruned, Box::new(std::fmt::Error));
+		let error: rpc::Error = error.into();
+		assert_eq!(error.code, rpc::ErrorCode::ServerError(5002));
+		assert_eq!(error.data, Some(serde_json::json!({ "kind": "statePruned" })));
 	}
 }
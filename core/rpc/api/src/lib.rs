@@ -20,7 +20,6 @@
 
 #![warn(missing_docs)]
 
-mod errors;
 mod helpers;
 mod subscriptions;
 
@@ -30,5 +29,7 @@ pub use helpers::Receiver;
 
 pub mod author;
 pub mod chain;
+pub mod child_state;
+pub mod errors;
 pub mod state;
 pub mod system;
@@ -16,7 +16,7 @@
 
 //! State RPC errors.
 
-use crate::errors;
+use crate::errors::ClientError;
 use jsonrpc_core as rpc;
 
 /// State RPC Result type.
@@ -30,7 +30,7 @@ pub type FutureResult<T> = Box<dyn rpc::futures::Future<Item = T, Error = Error>
 pub enum Error {
 	/// Client error.
 	#[display(fmt="Client error: {}", _0)]
-	Client(Box<dyn std::error::Error + Send>),
+	Client(ClientError),
 	/// Provided block range couldn't be resolved to a list of blocks.
 	#[display(fmt = "Cannot resolve a block range ['{:?}' ... '{:?}]. {}", from, to, details)]
 	InvalidBlockRange {
@@ -41,12 +41,20 @@ pub enum Error {
 		/// Details of the error message.
 		details: String,
 	},
+	/// Requested more items than allowed in a single call.
+	#[display(fmt = "Count exceeded: {} > {}", value, max)]
+	InvalidCount {
+		/// Provided value.
+		value: u32,
+		/// Maximal allowed value.
+		max: u32,
+	},
 }
 
 impl std::error::Error for Error {
 	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match self {
-			Error::Client(ref err) => Some(&**err),
+			Error::Client(ref err) => Some(err),
 			_ => None,
 		}
 	}
@@ -63,7 +71,12 @@ impl From<Error> for rpc::Error {
 				message: format!("{}", e),
 				data: None,
 			},
-			e => errors::internal(e),
+			Error::InvalidCount { .. } => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: None,
+			},
+			Error::Client(e) => e.into(),
 		}
 	}
 }
@@ -0,0 +1,123 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate state API helpers.
+
+use primitives::{Bytes, storage::{StorageKey, StorageData}};
+use serde::{Serialize, Deserialize};
+
+/// Statistics of the Wasm heap allocator collected during a runtime call.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllocationStats {
+	/// Number of bytes still allocated when the call returned.
+	pub bytes_allocated: u32,
+	/// Highest number of bytes allocated at the same time (heap high-water mark).
+	pub bytes_allocated_peak: u32,
+	/// Total number of bytes handed out by the allocator.
+	pub bytes_allocated_sum: u64,
+	/// Number of allocations performed.
+	pub allocation_count: u32,
+	/// Size of the heap available to the allocator.
+	pub heap_size: u32,
+}
+
+/// Result of a runtime call along with the allocator statistics of the call.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallWithAllocationStats {
+	/// The encoded result of the call.
+	pub result: Bytes,
+	/// Allocator statistics of the call.
+	///
+	/// `None` if the call was not executed in Wasm.
+	pub allocation_stats: Option<AllocationStats>,
+}
+
+/// Kind of a storage access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageAccessKind {
+	/// The value was read from the state.
+	Read,
+	/// The value was set or removed.
+	Write,
+}
+
+/// An access to a storage key during the execution of a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageAccess {
+	/// The child storage the key belongs to, `None` for the top-level storage.
+	pub child_storage_key: Option<StorageKey>,
+	/// The accessed key.
+	pub key: StorageKey,
+	/// Kind of the access.
+	pub kind: StorageAccessKind,
+	/// Size of the value read or written, `None` if there is no value.
+	pub value_size: Option<u32>,
+}
+
+/// Trace of a step of the execution of a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceSpan {
+	/// Name of the step: `initializeBlock`, `applyExtrinsic` or `finalizeBlock`.
+	pub name: String,
+	/// Index of the applied extrinsic in the block body, for `applyExtrinsic` steps.
+	pub extrinsic_index: Option<u32>,
+	/// Wall time taken by the step, in microseconds.
+	pub duration_us: u64,
+	/// Storage accesses of the step: the reads, in the order they were made, followed by the
+	/// writes.
+	pub storage: Vec<StorageAccess>,
+}
+
+/// Trace of the execution of a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// Hash of the traced block.
+	pub block_hash: Hash,
+	/// Wall time taken by the whole execution, in microseconds.
+	pub duration_us: u64,
+	/// The steps of the execution, in order.
+	pub spans: Vec<TraceSpan>,
+}
+
+/// Proof of the storage entries of a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadProof<Hash> {
+	/// Hash of the block the proof was generated at.
+	pub at: Hash,
+	/// Trie nodes of the proof, checked against the state root of the block.
+	pub proof: Vec<Bytes>,
+}
+
+/// Change of the value of a storage key by the execution of a block.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDiffEntry {
+	/// The child storage the key belongs to, `None` for the top-level storage.
+	pub child_storage_key: Option<StorageKey>,
+	/// The changed key.
+	pub key: StorageKey,
+	/// Value before the execution of the block, `None` if there was no value.
+	pub old_value: Option<StorageData>,
+	/// Value after the execution of the block, `None` if the value was removed.
+	pub new_value: Option<StorageData>,
+}
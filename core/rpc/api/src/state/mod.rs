@@ -17,6 +17,7 @@
 //! Substrate state API.
 
 pub mod error;
+pub mod helpers;
 
 use jsonrpc_core::Result as RpcResult;
 use jsonrpc_core::futures::Future;
@@ -27,8 +28,15 @@ use primitives::storage::{StorageKey, StorageData, StorageChangeSet};
 use runtime_version::RuntimeVersion;
 use self::error::FutureResult;
 
+pub use self::helpers::{
+	AllocationStats, CallWithAllocationStats, BlockTrace, TraceSpan, StorageAccess, StorageAccessKind,
+	StorageDiffEntry, ReadProof,
+};
 pub use self::gen_client::Client as StateClient;
 
+/// Maximal number of keys returned by a single `state_getKeysPaged` call.
+pub const MAX_KEYS_PAGE_SIZE: u32 = 1000;
+
 /// Substrate state API
 #[rpc]
 pub trait StateApi<Hash> {
@@ -39,10 +47,38 @@ pub trait StateApi<Hash> {
 	#[rpc(name = "state_call", alias("state_callAt"))]
 	fn call(&self, name: String, bytes: Bytes, hash: Option<Hash>) -> FutureResult<Bytes>;
 
+	/// Call a contract at a block's state in Wasm and return the result along with the
+	/// statistics of the Wasm heap allocator.
+	///
+	/// Meant for debugging memory usage of the runtime. This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "state_callWithAllocationStats")]
+	fn call_with_allocation_stats(
+		&self,
+		name: String,
+		bytes: Bytes,
+		hash: Option<Hash>
+	) -> FutureResult<CallWithAllocationStats>;
+
 	/// Returns the keys with prefix, leave empty to get all the keys
+	///
+	/// The number of returned keys isn't bounded, prefer `state_getKeysPaged` for large maps. This
+	/// method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "state_getKeys")]
 	fn storage_keys(&self, prefix: StorageKey, hash: Option<Hash>) -> FutureResult<Vec<StorageKey>>;
 
+	/// Returns at most `count` keys with prefix, starting after `start_key`.
+	///
+	/// Leave the prefix empty to get all the keys. At most `MAX_KEYS_PAGE_SIZE` keys are returned
+	/// by a single call.
+	#[rpc(name = "state_getKeysPaged", alias("state_getKeysPagedAt"))]
+	fn storage_keys_paged(
+		&self,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		hash: Option<Hash>,
+	) -> FutureResult<Vec<StorageKey>>;
+
 	/// Returns a storage entry at a specific block's state.
 	#[rpc(name = "state_getStorage", alias("state_getStorageAt"))]
 	fn storage(&self, key: StorageKey, hash: Option<Hash>) -> FutureResult<Option<StorageData>>;
@@ -56,6 +92,8 @@ pub trait StateApi<Hash> {
 	fn storage_size(&self, key: StorageKey, hash: Option<Hash>) -> FutureResult<Option<u64>>;
 
 	/// Returns the keys with prefix from a child storage, leave empty to get all the keys
+	///
+	/// This method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "state_getChildKeys")]
 	fn child_storage_keys(
 		&self,
@@ -103,6 +141,8 @@ pub trait StateApi<Hash> {
 	///
 	/// NOTE This first returned result contains the initial state of storage for all keys.
 	/// Subsequent values in the vector represent changes to the previous state (diffs).
+	///
+	/// This method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "state_queryStorage")]
 	fn query_storage(
 		&self,
@@ -111,6 +151,37 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Execute a block again and trace the duration and the storage accesses of its
+	/// initialization, of each of its extrinsics and of its finalization.
+	///
+	/// Meant for profiling the runtime. The block is executed on top of the state of its parent,
+	/// which must not have been pruned. This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "state_traceBlock")]
+	fn trace_block(&self, block: Hash) -> FutureResult<BlockTrace<Hash>>;
+
+	/// Execute a block again and return the changes of the storage made by the block, with the
+	/// value of each changed key before and after the block.
+	///
+	/// Meant for debugging, e.g. to find out what a governance call changed. The block is
+	/// executed on top of the state of its parent, which must not have been pruned. This method is
+	/// unsafe, see `--rpc-methods`.
+	#[rpc(name = "state_getBlockStorageDiff")]
+	fn block_storage_diff(&self, block: Hash) -> FutureResult<Vec<StorageDiffEntry>>;
+
+	/// Returns proof of the storage entries at a specific block's state.
+	///
+	/// The size of the proof isn't bounded. This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "state_getReadProof")]
+	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> FutureResult<ReadProof<Hash>>;
+
+	/// Returns proof of all the storage entries with a key starting with `prefix` at a specific
+	/// block's state.
+	///
+	/// Unlike `state_getReadProof`, the proof also shows that no other key has the prefix. The size
+	/// of the proof isn't bounded. This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "state_getPrefixReadProof")]
+	fn prefix_read_proof(&self, prefix: StorageKey, hash: Option<Hash>) -> FutureResult<ReadProof<Hash>>;
+
 	/// New runtime version subscription
 	#[pubsub(
 		subscription = "state_runtimeVersion",
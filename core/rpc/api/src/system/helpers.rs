@@ -16,7 +16,7 @@
 
 //! Substrate system API helpers.
 
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
 use serde::{Serialize, Deserialize};
 use serde_json::{Value, map::Map};
 
@@ -87,6 +87,18 @@ pub enum NodeRole {
 	UnknownRole(u8)
 }
 
+/// Outcome of a configuration reload.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReloadReport {
+	/// Options that were applied to the running node.
+	pub applied: Vec<String>,
+	/// Options that can only be changed by restarting the node.
+	pub restart_required: Vec<String>,
+	/// Options that couldn't be applied, with the reason why.
+	pub failed: BTreeMap<String, String>,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -24,7 +24,7 @@ use jsonrpc_derive::rpc;
 
 use self::error::Result;
 
-pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo, NodeRole};
+pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo, NodeRole, ReloadReport};
 pub use self::gen_client::Client as SystemClient;
 
 /// Substrate system RPC API
@@ -55,12 +55,14 @@ pub trait SystemApi<Hash, Number> {
 	fn system_health(&self) -> Receiver<Health>;
 
 	/// Returns currently connected peers
+	///
+	/// This method is unsafe, see `--rpc-methods`.
 	#[rpc(name = "system_peers", returns = "Vec<PeerInfo<Hash, Number>>")]
 	fn system_peers(&self) -> Receiver<Vec<PeerInfo<Hash, Number>>>;
 
 	/// Returns current state of the network.
 	///
-	/// **Warning**: This API is not stable.
+	/// **Warning**: This API is not stable. This method is unsafe, see `--rpc-methods`.
 	// TODO: make this stable and move structs https://github.com/paritytech/substrate/issues/1890
 	#[rpc(name = "system_networkState", returns = "jsonrpc_core::Value")]
 	fn system_network_state(&self) -> Receiver<jsonrpc_core::Value>;
@@ -68,4 +70,13 @@ pub trait SystemApi<Hash, Number> {
 	/// Returns the roles the node is running as.
 	#[rpc(name = "system_nodeRoles", returns = "Vec<NodeRole>")]
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>>;
+
+	/// Change options of the running node.
+	///
+	/// The options are given by name of command line option, e.g. `{"log": "sync=trace"}`. Returns
+	/// which of them were applied, and which of them require restarting the node.
+	///
+	/// This method is unsafe, see `--rpc-methods`.
+	#[rpc(name = "system_reloadConfig", returns = "ReloadReport")]
+	fn system_reload_config(&self, options: Properties) -> Receiver<ReloadReport>;
 }
@@ -32,9 +32,10 @@ use futures03::{StreamExt as _, compat::Compat, future::ready};
 use api::Subscriptions;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use codec::{Encode, Decode};
-use primitives::{Bytes, Blake2Hasher, H256, traits::BareCryptoStorePtr};
+use primitives::{Bytes, Blake2Hasher, H256, crypto::KeyTypeId, traits::BareCryptoStorePtr};
 use sr_primitives::{generic, traits::{self, ProvideRuntimeApi}};
 use transaction_pool::{
+	Journal,
 	txpool::{
 		ChainApi as PoolChainApi,
 		BlockHash,
@@ -60,6 +61,10 @@ pub struct Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'static {
 	subscriptions: Subscriptions,
 	/// The key store.
 	keystore: BareCryptoStorePtr,
+	/// Whether keys may be removed from the key store.
+	allow_key_removal: bool,
+	/// Journal recording the submitted transactions.
+	journal: Option<Arc<Journal>>,
 }
 
 impl<B, E, P, RA> Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'static {
@@ -69,14 +74,56 @@ impl<B, E, P, RA> Author<B, E, P, RA> where P: PoolChainApi + Sync + Send + 'sta
 		pool: Arc<Pool<P>>,
 		subscriptions: Subscriptions,
 		keystore: BareCryptoStorePtr,
+	) -> Self {
+		Self::new_with_key_removal(client, pool, subscriptions, keystore, false)
+	}
+
+	/// Create new instance of Authoring API, allowing `author_removeKeys` if `allow_key_removal`
+	/// is set.
+	pub fn new_with_key_removal(
+		client: Arc<Client<B, E, <P as PoolChainApi>::Block, RA>>,
+		pool: Arc<Pool<P>>,
+		subscriptions: Subscriptions,
+		keystore: BareCryptoStorePtr,
+		allow_key_removal: bool,
 	) -> Self {
 		Author {
 			client,
 			pool,
 			subscriptions,
 			keystore,
+			allow_key_removal,
+			journal: None,
 		}
 	}
+
+	/// Record the transactions submitted through this API in the given journal, so that they
+	/// survive a restart of the node.
+	pub fn with_journal(mut self, journal: Arc<Journal>) -> Self {
+		self.journal = Some(journal);
+		self
+	}
+}
+
+impl<B, E, P, RA> Author<B, E, P, RA> where
+	B: client::backend::Backend<<P as PoolChainApi>::Block, Blake2Hasher> + Send + Sync + 'static,
+	E: client::CallExecutor<<P as PoolChainApi>::Block, Blake2Hasher> + Send + Sync + 'static,
+	P: PoolChainApi + Sync + Send + 'static,
+	P::Block: traits::Block<Hash=H256>,
+	Client<B, E, P::Block, RA>: ProvideRuntimeApi,
+	<Client<B, E, P::Block, RA> as ProvideRuntimeApi>::Api: SessionKeys<P::Block>,
+{
+	/// Decode the given session keys into raw public keys and key types, with the runtime at the
+	/// best block.
+	fn decode_session_keys(&self, session_keys: Bytes) -> Result<Vec<(Vec<u8>, KeyTypeId)>> {
+		let best_block_hash = self.client.info().chain.best_hash;
+		self.client.runtime_api().decode_session_keys(
+			&generic::BlockId::Hash(best_block_hash),
+			session_keys.to_vec(),
+		)
+			.map_err(|e| Error::Client(crate::errors::client_error(e)))?
+			.ok_or_else(|| Error::InvalidSessionKeys)
+	}
 }
 
 impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> where
@@ -109,7 +156,39 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 		self.client.runtime_api().generate_session_keys(
 			&generic::BlockId::Hash(best_block_hash),
 			None,
-		).map(Into::into).map_err(|e| Error::Client(Box::new(e)))
+		).map(Into::into).map_err(|e| Error::Client(crate::errors::client_error(e)))
+	}
+
+	fn has_session_keys(&self, session_keys: Bytes) -> Result<bool> {
+		let keys = self.decode_session_keys(session_keys)?;
+		Ok(self.keystore.read().has_keys(&keys))
+	}
+
+	fn has_key(&self, public_key: Bytes, key_type: String) -> Result<bool> {
+		let key_type = key_type.as_str().try_into().map_err(|_| Error::BadKeyType)?;
+		Ok(self.keystore.read().has_keys(&[(public_key.to_vec(), key_type)]))
+	}
+
+	fn list_keys(&self, key_type: String) -> Result<Vec<Bytes>> {
+		let key_type = key_type.as_str().try_into().map_err(|_| Error::BadKeyType)?;
+		self.keystore.read().raw_public_keys(key_type)
+			.map(|keys| keys.into_iter().map(Into::into).collect())
+			.map_err(|_| Error::KeyStoreUnavailable)
+	}
+
+	fn remove_keys(&self, session_keys: Bytes) -> Result<Vec<Bytes>> {
+		if !self.allow_key_removal {
+			return Err(Error::KeyRemovalDisabled)
+		}
+		let keys = self.decode_session_keys(session_keys)?;
+		let mut keystore = self.keystore.write();
+		let mut removed = Vec::new();
+		for (public, key_type) in keys {
+			if keystore.remove_key(key_type, &public).map_err(|_| Error::KeyStoreUnavailable)? {
+				removed.push(public.into());
+			}
+		}
+		Ok(removed)
 	}
 
 	fn submit_extrinsic(&self, ext: Bytes) -> FutureResult<ExHash<P>> {
@@ -118,8 +197,13 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 			Err(err) => return Box::new(result(Err(err.into()))),
 		};
 		let best_block_hash = self.client.info().chain.best_hash;
+		let journal = self.journal.clone();
 		Box::new(self.pool
 			.submit_one(&generic::BlockId::hash(best_block_hash), xt)
+			.map_ok(move |hash| {
+				journal_transaction(journal.as_ref(), ext.to_vec());
+				hash
+			})
 			.compat()
 			.map_err(|e| e.into_pool_error()
 				.map(Into::into)
@@ -152,6 +236,15 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 		)
 	}
 
+	fn remove_sender_extrinsics(&self, sender: Bytes) -> Result<Vec<ExHash<P>>> {
+		Ok(
+			self.pool.remove_sender(&sender)
+				.into_iter()
+				.map(|tx| tx.hash.clone())
+				.collect()
+		)
+	}
+
 	fn watch_extrinsic(&self,
 		_metadata: Self::Metadata,
 		subscriber: Subscriber<Status<ExHash<P>, BlockHash<P>>>,
@@ -161,9 +254,15 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 			let best_block_hash = self.client.info().chain.best_hash;
 			let dxt = <<P as PoolChainApi>::Block as traits::Block>::Extrinsic::decode(&mut &xt[..])
 				.map_err(error::Error::from)?;
+			let journal = self.journal.clone();
+			let encoded = xt.to_vec();
 			Ok(
 				self.pool
 					.submit_and_watch(&generic::BlockId::hash(best_block_hash), dxt)
+					.map_ok(move |watcher| {
+						journal_transaction(journal.as_ref(), encoded);
+						watcher
+					})
 					.map_err(|e| e.into_pool_error()
 						.map(error::Error::from)
 						.unwrap_or_else(|e| error::Error::Verification(Box::new(e)).into())
@@ -205,3 +304,12 @@ impl<B, E, P, RA> AuthorApi<ExHash<P>, BlockHash<P>> for Author<B, E, P, RA> whe
 		Ok(self.subscriptions.cancel(id))
 	}
 }
+
+/// Record a successfully submitted transaction in the journal, if any.
+fn journal_transaction(journal: Option<&Arc<Journal>>, encoded: Vec<u8>) {
+	if let Some(journal) = journal {
+		if let Err(e) = journal.insert(encoded) {
+			warn!("Failed to record the transaction in the journal: {:?}", e);
+		}
+	}
+}
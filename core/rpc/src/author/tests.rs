@@ -16,12 +16,12 @@
 
 use super::*;
 
-use std::sync::Arc;
+use std::{mem, sync::Arc};
 use assert_matches::assert_matches;
 use codec::Encode;
 use primitives::{
 	H256, blake2_256, hexdisplay::HexDisplay, testing::{ED25519, SR25519, KeyStore}, traits::BareCryptoStorePtr, ed25519,
-	crypto::Pair,
+	crypto::{Pair, Public},
 };
 use rpc::futures::Stream as _;
 use test_client::{
@@ -72,6 +72,7 @@ impl TestSetup {
 			pool: self.pool.clone(),
 			subscriptions: Subscriptions::new(Arc::new(self.runtime.executor())),
 			keystore: self.keystore.clone(),
+			allow_key_removal: false,
 		}
 	}
 }
@@ -193,6 +194,32 @@ fn should_remove_extrinsics() {
  	assert_eq!(removed.len(), 3);
 }
 
+#[test]
+fn should_remove_sender_extrinsics() {
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let ex1 = uxt(AccountKeyring::Alice, 0);
+	let hash1 = p.submit_extrinsic(ex1.encode().into()).wait().unwrap();
+	let ex2 = uxt(AccountKeyring::Alice, 1);
+	let hash2 = p.submit_extrinsic(ex2.encode().into()).wait().unwrap();
+	let ex3 = uxt(AccountKeyring::Bob, 0);
+	let hash3 = p.submit_extrinsic(ex3.encode().into()).wait().unwrap();
+	assert_eq!(setup.pool.status().ready, 3);
+
+	let alice: test_client::runtime::AccountId = AccountKeyring::Alice.into();
+	let mut removed = p.remove_sender_extrinsics(alice.encode().into()).unwrap();
+	removed.sort();
+	let mut expected = vec![hash1, hash2];
+	expected.sort();
+
+	assert_eq!(removed, expected);
+	assert_eq!(setup.pool.status().ready, 1);
+	assert_eq!(setup.pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash3]);
+	// the removed extrinsics are banned for a while
+	assert!(p.submit_extrinsic(ex1.encode().into()).wait().is_err());
+}
+
 #[test]
 fn should_insert_key() {
 	let setup = TestSetup::default();
@@ -235,3 +262,79 @@ fn should_rotate_keys() {
 	assert_eq!(session_keys.ed25519, ed25519_key_pair.public().into());
 	assert_eq!(session_keys.sr25519, sr25519_key_pair.public().into());
 }
+
+#[test]
+fn author_has_session_keys() {
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let non_existent_public_keys = TestSetup::default()
+		.author()
+		.rotate_keys()
+		.expect("Rotates the keys");
+
+	let public_keys = p.rotate_keys().expect("Rotates the keys");
+	let test_vectors = vec![
+		(public_keys, Ok(true)),
+		(vec![1, 2, 3].into(), Err(Error::InvalidSessionKeys)),
+		(non_existent_public_keys, Ok(false)),
+	];
+
+	for (keys, result) in test_vectors {
+		assert_eq!(
+			result.map_err(|e| mem::discriminant(&e)),
+			p.has_session_keys(keys).map_err(|e| mem::discriminant(&e)),
+		);
+	}
+}
+
+#[test]
+fn author_has_key() {
+	let setup = TestSetup::default();
+	let p = setup.author();
+
+	let suri = "//Alice";
+	let alice_key_pair = ed25519::Pair::from_string(suri, None).expect("Generates keypair");
+	p.insert_key(
+		String::from_utf8(ED25519.0.to_vec()).expect("Keytype is a valid string"),
+		suri.to_string(),
+		alice_key_pair.public().0.to_vec().into(),
+	).expect("Insert key");
+	let bob_key_pair = ed25519::Pair::from_string("//Bob", None).expect("Generates keypair");
+
+	let test_vectors = vec![
+		(alice_key_pair.public().to_raw_vec().into(), ED25519, Ok(true)),
+		(alice_key_pair.public().to_raw_vec().into(), SR25519, Ok(false)),
+		(bob_key_pair.public().to_raw_vec().into(), ED25519, Ok(false)),
+	];
+
+	for (key, key_type, result) in test_vectors {
+		assert_eq!(
+			result.map_err(|e: Error| mem::discriminant(&e)),
+			p.has_key(
+				key,
+				String::from_utf8(key_type.0.to_vec()).expect("Keytype is a valid string"),
+			).map_err(|e| mem::discriminant(&e)),
+		);
+	}
+
+	assert_eq!(
+		p.list_keys(String::from_utf8(ED25519.0.to_vec()).expect("Keytype is a valid string")).unwrap(),
+		vec![Bytes::from(alice_key_pair.public().to_raw_vec())],
+	);
+	assert_matches!(p.has_key(Bytes(Vec::new()), "bad".into()), Err(Error::BadKeyType));
+}
+
+#[test]
+fn author_remove_keys_is_guarded() {
+	let setup = TestSetup::default();
+	let public_keys = setup.author().rotate_keys().expect("Rotates the keys");
+
+	assert_matches!(setup.author().remove_keys(public_keys.clone()), Err(Error::KeyRemovalDisabled));
+	assert!(setup.author().has_session_keys(public_keys.clone()).unwrap());
+
+	let p = Author { allow_key_removal: true, ..setup.author() };
+	assert_eq!(p.remove_keys(public_keys.clone()).unwrap().len(), 2);
+	assert!(!p.has_session_keys(public_keys.clone()).unwrap());
+	assert!(p.remove_keys(public_keys).unwrap().is_empty());
+}
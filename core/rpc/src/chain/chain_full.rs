@@ -20,14 +20,14 @@ use std::sync::Arc;
 use rpc::futures::future::result;
 
 use api::Subscriptions;
-use client::{backend::Backend, CallExecutor, Client};
+use client::{backend::Backend, stats::StatsCollector, CallExecutor, Client};
 use primitives::{H256, Blake2Hasher};
 use sr_primitives::{
 	generic::{BlockId, SignedBlock},
 	traits::{Block as BlockT},
 };
 
-use super::{ChainBackend, client_err, error::FutureResult};
+use super::{ChainBackend, FinalityProofSource, client_err, error::FutureResult};
 
 /// Blockchain API backend for full nodes. Reads all the data from local database.
 pub struct FullChain<B, E, Block: BlockT, RA> {
@@ -35,6 +35,10 @@ pub struct FullChain<B, E, Block: BlockT, RA> {
 	client: Arc<Client<B, E, Block, RA>>,
 	/// Current subscriptions.
 	subscriptions: Subscriptions,
+	/// Collector of chain statistics, if enabled.
+	stats: Option<Arc<StatsCollector<Block>>>,
+	/// Source of the proofs of finality of the finalized heads, if any.
+	finality_proofs: Option<Arc<dyn FinalityProofSource<Block>>>,
 }
 
 impl<B, E, Block: BlockT, RA> FullChain<B, E, Block, RA> {
@@ -43,8 +47,25 @@ impl<B, E, Block: BlockT, RA> FullChain<B, E, Block, RA> {
 		Self {
 			client,
 			subscriptions,
+			stats: None,
+			finality_proofs: None,
 		}
 	}
+
+	/// Serve chain statistics from the given collector.
+	pub fn with_stats(mut self, stats: Option<Arc<StatsCollector<Block>>>) -> Self {
+		self.stats = stats;
+		self
+	}
+
+	/// Prove the finality of the finalized heads with the given source.
+	pub fn with_finality_proofs(
+		mut self,
+		finality_proofs: Option<Arc<dyn FinalityProofSource<Block>>>,
+	) -> Self {
+		self.finality_proofs = finality_proofs;
+		self
+	}
 }
 
 impl<B, E, Block, RA> ChainBackend<B, E, Block, RA> for FullChain<B, E, Block, RA> where
@@ -61,6 +82,14 @@ impl<B, E, Block, RA> ChainBackend<B, E, Block, RA> for FullChain<B, E, Block, R
 		&self.subscriptions
 	}
 
+	fn stats_collector(&self) -> Option<&Arc<StatsCollector<Block>>> {
+		self.stats.as_ref()
+	}
+
+	fn finality_proofs(&self) -> Option<&Arc<dyn FinalityProofSource<Block>>> {
+		self.finality_proofs.as_ref()
+	}
+
 	fn header(&self, hash: Option<Block::Hash>) -> FutureResult<Option<Block::Header>> {
 		Box::new(result(self.client
 			.header(&BlockId::Hash(self.unwrap_or_best(hash)))
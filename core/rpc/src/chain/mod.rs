@@ -23,6 +23,7 @@ mod chain_light;
 mod tests;
 
 use std::sync::Arc;
+use codec::Encode;
 use futures03::{future, StreamExt as _, TryStreamExt as _};
 use log::warn;
 use rpc::{
@@ -34,19 +35,34 @@ use api::Subscriptions;
 use client::{
 	self, Client, BlockchainEvents,
 	light::{fetcher::Fetcher, blockchain::RemoteBlockchain},
+	stats::StatsCollector,
 };
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use primitives::{H256, Blake2Hasher};
 use rpc_primitives::number;
 use sr_primitives::{
 	generic::{BlockId, SignedBlock},
-	traits::{Block as BlockT, Header, NumberFor},
+	traits::{Block as BlockT, Header, NumberFor, One, Zero},
 };
 
 use self::error::{Result, Error, FutureResult};
+use self::helpers::{ChainStats, ExecutionMetrics, FinalizedHeader, IndexedExtrinsic};
 
 pub use api::chain::*;
 
+/// Maximal number of blocks whose execution metrics are returned by a single call.
+const MAX_EXECUTION_METRICS_BLOCKS: u32 = 4096;
+
+/// Source of the finality proofs sent to the finalized heads subscribers.
+pub trait FinalityProofSource<Block: BlockT>: Send + Sync {
+	/// Prove the finality of `block` to a subscriber that knows the finality of `last_finalized`.
+	fn prove_finality(
+		&self,
+		last_finalized: Block::Hash,
+		block: Block::Hash,
+	) -> client::error::Result<Option<Vec<u8>>>;
+}
+
 /// Blockchain backend API
 trait ChainBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 	where
@@ -95,6 +111,86 @@ trait ChainBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 		Ok(self.client().info().chain.finalized_hash)
 	}
 
+	/// Get the collector of chain statistics, if any.
+	fn stats_collector(&self) -> Option<&Arc<StatsCollector<Block>>> {
+		None
+	}
+
+	/// Get the source of the proofs of finality of the finalized heads, if any.
+	fn finality_proofs(&self) -> Option<&Arc<dyn FinalityProofSource<Block>>> {
+		None
+	}
+
+	/// Get aggregate statistics over a range of blocks of the canon chain.
+	fn stats(
+		&self,
+		from: number::NumberOrHex<NumberFor<Block>>,
+		to: Option<number::NumberOrHex<NumberFor<Block>>>,
+	) -> Result<ChainStats<NumberFor<Block>>> {
+		let collector = self.stats_collector()
+			.ok_or_else(|| Error::Other("Chain statistics are not collected by this node".into()))?;
+		let from = from.to_number()?;
+		let to = match to {
+			Some(to) => to.to_number()?,
+			None => self.client().info().chain.best_number,
+		};
+
+		let client = self.client();
+		let stats = collector.stats(from, to, |number| client.block_hash(number).ok().and_then(|hash| hash));
+		Ok(ChainStats {
+			from,
+			to,
+			block_count: stats.block_count,
+			total_extrinsics: stats.total_extrinsics,
+			average_fullness: stats.average_fullness,
+			reorg_count: stats.reorgs,
+		})
+	}
+
+	/// Get the execution metrics of the most recent blocks of the canon chain.
+	fn execution_metrics(
+		&self,
+		count: Option<u32>,
+	) -> Result<Vec<ExecutionMetrics<NumberFor<Block>, Block::Hash>>> {
+		let count = count.unwrap_or(1).min(MAX_EXECUTION_METRICS_BLOCKS);
+		let mut number = self.client().info().chain.best_number;
+		let mut metrics = Vec::new();
+		for _ in 0..count {
+			let id = BlockId::Number(number);
+			if let (Some(hash), Some(block_metrics)) = (
+				self.client().block_hash(number).map_err(client_err)?,
+				self.client().execution_metrics(&id).map_err(client_err)?,
+			) {
+				metrics.push(ExecutionMetrics {
+					number,
+					hash,
+					execution_time_us: block_metrics.execution_time_us,
+					storage_reads: block_metrics.storage_reads,
+					storage_read_bytes: block_metrics.storage_read_bytes,
+					storage_writes: block_metrics.storage_writes,
+					fuel: block_metrics.fuel,
+					proof_size: block_metrics.proof_size,
+				});
+			}
+			if number.is_zero() {
+				break;
+			}
+			number -= One::one();
+		}
+		Ok(metrics)
+	}
+
+	/// Get the extrinsic with the given hash, along with the block that includes it.
+	fn extrinsic(&self, hash: Block::Hash) -> Result<Option<IndexedExtrinsic<Block::Hash>>> {
+		Ok(self.client().extrinsic(&hash)
+			.map_err(client_err)?
+			.map(|(block_hash, index, extrinsic)| IndexedExtrinsic {
+				block_hash,
+				index,
+				extrinsic: extrinsic.encode().into(),
+			}))
+	}
+
 	/// New head subscription
 	fn subscribe_new_heads(
 		&self,
@@ -147,6 +243,66 @@ trait ChainBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 	) -> RpcResult<bool> {
 		Ok(self.subscriptions().cancel(id))
 	}
+
+	/// Finalized head subscription, with the proof of the finality of the blocks.
+	///
+	/// The proofs come from the finality proof source, and prove the finality of each block to
+	/// a subscriber that knows the finality of its parent. Without a source, the justification
+	/// stored for the block is sent instead.
+	fn subscribe_finalized_heads_with_justification(
+		&self,
+		_metadata: crate::metadata::Metadata,
+		subscriber: Subscriber<FinalizedHeader<Block::Header>>,
+	) {
+		let client = self.client().clone();
+		let finality_proofs = self.finality_proofs().cloned();
+		let with_justification = move |header: Block::Header| -> Result<FinalizedHeader<Block::Header>> {
+			let justification = match finality_proofs {
+				Some(ref finality_proofs) if !header.number().is_zero() => finality_proofs
+					.prove_finality(*header.parent_hash(), header.hash())
+					.map_err(client_err)?,
+				Some(_) => None,
+				None => client.justification(&BlockId::Hash(header.hash())).map_err(client_err)?,
+			};
+			Ok(FinalizedHeader { header, justification: justification.map(Into::into) })
+		};
+
+		self.subscriptions().add(subscriber, |sink| {
+			// send current finalized head right at the start.
+			let header = self.client().header(&BlockId::Hash(self.client().info().chain.finalized_hash))
+				.map_err(client_err)
+				.and_then(|header| {
+					header.ok_or_else(|| "Finalized header missing.".to_owned().into())
+				})
+				.and_then(&with_justification)
+				.map_err(Into::into);
+
+			// send further subscriptions
+			let stream = self.client().finality_notification_stream()
+				.map(move |notification| Ok::<_, ()>(with_justification(notification.header)))
+				.compat()
+				.map(|res| res.map_err(Into::into))
+				.map_err(|e| warn!("Block notification stream error: {:?}", e));
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(
+					stream::iter_result(vec![Ok(header)])
+						.chain(stream)
+				)
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	/// Unsubscribe from finalized head with justification subscription.
+	fn unsubscribe_finalized_heads_with_justification(
+		&self,
+		_metadata: Option<crate::metadata::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions().cancel(id))
+	}
 }
 
 /// Create new state API that works on full node.
@@ -159,9 +315,30 @@ pub fn new_full<B, E, Block: BlockT, RA>(
 		B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
 		E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static + Clone,
 		RA: Send + Sync + 'static,
+{
+	new_full_with_stats(client, subscriptions, None, None)
+}
+
+/// Create new state API that works on full node, serving chain statistics from `stats` and
+/// the proofs of finality of the finalized heads from `finality_proofs`.
+pub fn new_full_with_stats<B, E, Block: BlockT, RA>(
+	client: Arc<Client<B, E, Block, RA>>,
+	subscriptions: Subscriptions,
+	stats: Option<Arc<StatsCollector<Block>>>,
+	finality_proofs: Option<Arc<dyn FinalityProofSource<Block>>>,
+) -> Chain<B, E, Block, RA>
+	where
+		Block: BlockT<Hash=H256> + 'static,
+		B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+		E: client::CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static + Clone,
+		RA: Send + Sync + 'static,
 {
 	Chain {
-		backend: Box::new(self::chain_full::FullChain::new(client, subscriptions)),
+		backend: Box::new(
+			self::chain_full::FullChain::new(client, subscriptions)
+				.with_stats(stats)
+				.with_finality_proofs(finality_proofs)
+		),
 	}
 }
 
@@ -219,6 +396,25 @@ impl<B, E, Block, RA> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Sig
 		self.backend.finalized_head()
 	}
 
+	fn stats(
+		&self,
+		from: number::NumberOrHex<NumberFor<Block>>,
+		to: Option<number::NumberOrHex<NumberFor<Block>>>,
+	) -> Result<ChainStats<NumberFor<Block>>> {
+		self.backend.stats(from, to)
+	}
+
+	fn execution_metrics(
+		&self,
+		count: Option<u32>,
+	) -> Result<Vec<ExecutionMetrics<NumberFor<Block>, Block::Hash>>> {
+		self.backend.execution_metrics(count)
+	}
+
+	fn extrinsic(&self, hash: Block::Hash) -> Result<Option<IndexedExtrinsic<Block::Hash>>> {
+		self.backend.extrinsic(hash)
+	}
+
 	fn subscribe_new_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<Block::Header>) {
 		self.backend.subscribe_new_heads(metadata, subscriber)
 	}
@@ -234,6 +430,22 @@ impl<B, E, Block, RA> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Sig
 	fn unsubscribe_finalized_heads(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
 		self.backend.unsubscribe_finalized_heads(metadata, id)
 	}
+
+	fn subscribe_finalized_heads_with_justification(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<FinalizedHeader<Block::Header>>,
+	) {
+		self.backend.subscribe_finalized_heads_with_justification(metadata, subscriber)
+	}
+
+	fn unsubscribe_finalized_heads_with_justification(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		self.backend.unsubscribe_finalized_heads_with_justification(metadata, id)
+	}
 }
 
 /// Subscribe to new headers.
@@ -278,5 +490,5 @@ fn subscribe_headers<B, E, Block, RA, F, G, S, ERR>(
 }
 
 fn client_err(err: client::error::Error) -> Error {
-	Error::Client(Box::new(err))
+	Error::Client(crate::errors::client_error(err))
 }
@@ -182,6 +182,74 @@ fn should_return_finalized_hash() {
 	);
 }
 
+#[test]
+fn should_return_stats() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+
+	let client = Arc::new(test_client::new());
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote.clone())));
+	assert_matches!(api.stats(0u64.into(), None), Err(Error::Other(_)));
+
+	let collector = Arc::new(StatsCollector::new(Default::default()));
+	let api = new_full_with_stats(client.clone(), Subscriptions::new(Arc::new(remote)), Some(collector.clone()), None);
+
+	let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+	client.import(BlockOrigin::Own, block.clone()).unwrap();
+	collector.note_imported(1, block.hash(), 2, 1024, false);
+
+	assert_matches!(
+		api.stats(0u64.into(), None),
+		Ok(ChainStats { from: 0, to: 1, block_count: 1, total_extrinsics: 2, reorg_count: 0, .. })
+	);
+	assert_matches!(
+		api.stats(2u64.into(), Some(5u64.into())),
+		Ok(ChainStats { block_count: 0, .. })
+	);
+}
+
+#[test]
+fn should_return_execution_metrics() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+
+	let mut client = test_client::new();
+	client.set_execution_metrics(Some(client::execution_metrics::ExecutionMetricsConfig {
+		keep_blocks: 16,
+		meter_fuel: false,
+		proof_size: false,
+	}));
+	let client = Arc::new(client);
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+
+	// the genesis block is not executed.
+	assert_matches!(api.execution_metrics(None), Ok(ref metrics) if metrics.is_empty());
+
+	for _ in 0..2 {
+		let block = client.new_block(Default::default()).unwrap().bake().unwrap();
+		client.import(BlockOrigin::Own, block).unwrap();
+	}
+
+	assert_matches!(
+		api.execution_metrics(Some(10)),
+		Ok(ref metrics) if metrics.iter().map(|m| m.number).collect::<Vec<_>>() == vec![2, 1]
+	);
+}
+
+#[test]
+fn should_require_extrinsic_index() {
+	let core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+
+	let client = Arc::new(test_client::new());
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+
+	assert_matches!(
+		api.extrinsic(H256::from_low_u64_be(1)),
+		Err(Error::Client(ref e)) if e.to_string().contains("--index-extrinsics")
+	);
+}
+
 #[test]
 fn should_notify_about_latest_block() {
 	let mut core = ::tokio::runtime::Runtime::new().unwrap();
@@ -240,3 +308,80 @@ fn should_notify_about_finalized_block() {
 	// no more notifications on this channel
 	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
 }
+
+#[test]
+fn should_notify_about_finalized_block_with_justification() {
+	let mut core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let client = Arc::new(test_client::new());
+		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+
+		api.subscribe_finalized_heads_with_justification(Default::default(), subscriber);
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+
+		let builder = client.new_block(Default::default()).unwrap();
+		client.import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		client.finalize_block(BlockId::number(1), Some(vec![1, 2, 3])).unwrap();
+	}
+
+	// assert initial head sent, the genesis block has no justification.
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	assert!(notification.unwrap().contains(r#""justification":null"#));
+	// assert notification sent to transport
+	let (notification, next) = core.block_on(next.into_future()).unwrap();
+	assert!(notification.unwrap().contains(r#""justification":"0x010203""#));
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
+
+#[test]
+fn should_notify_about_finalized_block_with_finality_proof() {
+	struct TestFinalityProofs;
+
+	impl FinalityProofSource<Block> for TestFinalityProofs {
+		fn prove_finality(
+			&self,
+			_last_finalized: H256,
+			_block: H256,
+		) -> client::error::Result<Option<Vec<u8>>> {
+			Ok(Some(vec![4, 5, 6]))
+		}
+	}
+
+	let mut core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let client = Arc::new(test_client::new());
+		let api = new_full_with_stats(
+			client.clone(),
+			Subscriptions::new(Arc::new(remote)),
+			None,
+			Some(Arc::new(TestFinalityProofs)),
+		);
+
+		api.subscribe_finalized_heads_with_justification(Default::default(), subscriber);
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+
+		let builder = client.new_block(Default::default()).unwrap();
+		client.import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+		client.finalize_block(BlockId::number(1), Some(vec![1, 2, 3])).unwrap();
+	}
+
+	// assert initial head sent, the finality of the genesis block isn't proven.
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	assert!(notification.unwrap().contains(r#""justification":null"#));
+	// assert the proof comes from the source rather than the stored justification.
+	let (notification, next) = core.block_on(next.into_future()).unwrap();
+	assert!(notification.unwrap().contains(r#""justification":"0x040506""#));
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
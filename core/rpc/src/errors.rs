@@ -0,0 +1,58 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Conversion of client errors into RPC errors.
+
+use api::errors::{ClientError, ClientErrorKind};
+use client::error::Error;
+
+/// Wrap a client error along with its kind.
+pub(crate) fn client_error(err: Error) -> ClientError {
+	ClientError::new(kind_of(&err), Box::new(err))
+}
+
+fn kind_of(err: &Error) -> ClientErrorKind {
+	match err {
+		Error::UnknownBlock(_) => ClientErrorKind::UnknownBlock,
+		Error::StatePruned(_) => ClientErrorKind::StatePruned,
+		Error::NotAvailableOnLightClient => ClientErrorKind::NotAvailableOnLightClient,
+		Error::Execution(_)
+			| Error::CallResultDecode(..)
+			| Error::RuntimeParamConversion(_)
+			| Error::VersionInvalid => ClientErrorKind::Execution,
+		Error::Consensus(_) => ClientErrorKind::Consensus,
+		Error::ApplyExtrinsicFailed(_) => ClientErrorKind::InvalidExtrinsic,
+		Error::InvalidCHTProof
+			| Error::RemoteFetchCancelled
+			| Error::RemoteFetchFailed => ClientErrorKind::RemoteFetchFailed,
+		Error::ExtrinsicIndexDisabled
+			| Error::ChangesTriesNotSupported => ClientErrorKind::Unsupported,
+		Error::Blockchain(err) => kind_of(err),
+		_ => ClientErrorKind::Other,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn nested_errors_are_classified() {
+		let err = Error::from_blockchain(Box::new(Error::StatePruned("#1".into())));
+		assert_eq!(client_error(err).kind(), ClientErrorKind::StatePruned);
+		assert_eq!(client_error(Error::Backend("io".into())).kind(), ClientErrorKind::Other);
+	}
+}
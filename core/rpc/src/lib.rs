@@ -20,6 +20,7 @@
 
 #![warn(missing_docs)]
 
+mod errors;
 mod metadata;
 
 pub use api::Subscriptions;
@@ -47,6 +47,7 @@ use sr_primitives::{
 use self::error::{Error, FutureResult};
 
 pub use api::state::*;
+pub use api::child_state::{ChildStateApi, ChildStateClient};
 
 /// State backend API.
 pub trait StateBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
@@ -64,6 +65,14 @@ pub trait StateBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 		call_data: Bytes,
 	) -> FutureResult<Bytes>;
 
+	/// Call runtime method at given block in Wasm and collect the allocator statistics of the call.
+	fn call_with_allocation_stats(
+		&self,
+		block: Option<Block::Hash>,
+		method: String,
+		call_data: Bytes,
+	) -> FutureResult<CallWithAllocationStats>;
+
 	/// Returns the keys with prefix, leave empty to get all the keys.
 	fn storage_keys(
 		&self,
@@ -71,6 +80,15 @@ pub trait StateBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 		prefix: StorageKey,
 	) -> FutureResult<Vec<StorageKey>>;
 
+	/// Returns at most `count` keys with prefix, starting after `start_key`.
+	fn storage_keys_paged(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>>;
+
 	/// Returns a storage entry at a specific block's state.
 	fn storage(
 		&self,
@@ -103,6 +121,16 @@ pub trait StateBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 		prefix: StorageKey,
 	) -> FutureResult<Vec<StorageKey>>;
 
+	/// Returns at most `count` keys with prefix from a child storage, starting after `start_key`.
+	fn child_storage_keys_paged(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>>;
+
 	/// Returns a child storage entry at a specific block's state.
 	fn child_storage(
 		&self,
@@ -147,6 +175,27 @@ pub trait StateBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 		keys: Vec<StorageKey>,
 	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>>;
 
+	/// Execute a block again and trace its execution.
+	fn trace_block(&self, block: Block::Hash) -> FutureResult<BlockTrace<Block::Hash>>;
+
+	/// Execute a block again and return the changes of the storage it made.
+	fn block_storage_diff(&self, block: Block::Hash) -> FutureResult<Vec<StorageDiffEntry>>;
+
+	/// Returns proof of the storage entries at a specific block's state.
+	fn read_proof(
+		&self,
+		block: Option<Block::Hash>,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>>;
+
+	/// Returns proof of all the storage entries with a key starting with `prefix` at a specific
+	/// block's state.
+	fn prefix_read_proof(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: StorageKey,
+	) -> FutureResult<ReadProof<Block::Hash>>;
+
 	/// New runtime version subscription
 	fn subscribe_runtime_version(
 		&self,
@@ -189,9 +238,30 @@ pub fn new_full<B, E, Block: BlockT, RA>(
 		RA: Send + Sync + 'static,
 		Client<B, E, Block, RA>: ProvideRuntimeApi,
 		<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: Metadata<Block>,
+{
+	new_full_with_fuel_limit(client, subscriptions, None)
+}
+
+/// Create new state API that works on full node, with runtime calls limited to `fuel_limit`.
+///
+/// Runtime calls exceeding the limit fail instead of keeping the node busy.
+pub fn new_full_with_fuel_limit<B, E, Block: BlockT, RA>(
+	client: Arc<Client<B, E, Block, RA>>,
+	subscriptions: Subscriptions,
+	fuel_limit: Option<u64>,
+) -> State<B, E, Block, RA>
+	where
+		Block: BlockT<Hash=H256> + 'static,
+		B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+		E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static + Clone,
+		RA: Send + Sync + 'static,
+		Client<B, E, Block, RA>: ProvideRuntimeApi,
+		<Client<B, E, Block, RA> as ProvideRuntimeApi>::Api: Metadata<Block>,
 {
 	State {
-		backend: Box::new(self::state_full::FullState::new(client, subscriptions)),
+		backend: Arc::new(
+			self::state_full::FullState::new(client, subscriptions).with_fuel_limit(fuel_limit)
+		),
 	}
 }
 
@@ -210,7 +280,7 @@ pub fn new_light<B, E, Block: BlockT, RA, F: Fetcher<Block>>(
 		F: Send + Sync + 'static,
 {
 	State {
-		backend: Box::new(self::state_light::LightState::new(
+		backend: Arc::new(self::state_light::LightState::new(
 			client,
 			subscriptions,
 			remote_blockchain,
@@ -221,7 +291,16 @@ pub fn new_light<B, E, Block: BlockT, RA, F: Fetcher<Block>>(
 
 /// State API with subscriptions support.
 pub struct State<B, E, Block, RA> {
-	backend: Box<dyn StateBackend<B, E, Block, RA>>,
+	backend: Arc<dyn StateBackend<B, E, Block, RA>>,
+}
+
+impl<B, E, Block, RA> State<B, E, Block, RA> {
+	/// Create the child state API sharing the backend of this state API.
+	pub fn child_state(&self) -> ChildState<B, E, Block, RA> {
+		ChildState {
+			backend: self.backend.clone(),
+		}
+	}
 }
 
 impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA>
@@ -237,6 +316,15 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA>
 		self.backend.call(block, method, data)
 	}
 
+	fn call_with_allocation_stats(
+		&self,
+		method: String,
+		data: Bytes,
+		block: Option<Block::Hash>,
+	) -> FutureResult<CallWithAllocationStats> {
+		self.backend.call_with_allocation_stats(block, method, data)
+	}
+
 	fn storage_keys(
 		&self,
 		key_prefix: StorageKey,
@@ -245,6 +333,19 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA>
 		self.backend.storage_keys(block, key_prefix)
 	}
 
+	fn storage_keys_paged(
+		&self,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		block: Option<Block::Hash>,
+	) -> FutureResult<Vec<StorageKey>> {
+		if let Err(e) = check_page_size(count) {
+			return Box::new(rpc::futures::future::err(e));
+		}
+		self.backend.storage_keys_paged(block, prefix, count, start_key)
+	}
+
 	fn storage(&self, key: StorageKey, block: Option<Block::Hash>) -> FutureResult<Option<StorageData>> {
 		self.backend.storage(block, key)
 	}
@@ -306,6 +407,26 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA>
 		self.backend.query_storage(from, to, keys)
 	}
 
+	fn trace_block(&self, block: Block::Hash) -> FutureResult<BlockTrace<Block::Hash>> {
+		self.backend.trace_block(block)
+	}
+
+	fn block_storage_diff(&self, block: Block::Hash) -> FutureResult<Vec<StorageDiffEntry>> {
+		self.backend.block_storage_diff(block)
+	}
+
+	fn read_proof(&self, keys: Vec<StorageKey>, block: Option<Block::Hash>) -> FutureResult<ReadProof<Block::Hash>> {
+		self.backend.read_proof(block, keys)
+	}
+
+	fn prefix_read_proof(
+		&self,
+		prefix: StorageKey,
+		block: Option<Block::Hash>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		self.backend.prefix_read_proof(block, prefix)
+	}
+
 	fn subscribe_storage(
 		&self,
 		meta: Self::Metadata,
@@ -336,6 +457,83 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA>
 	}
 }
 
+/// Child state API, queries the child tries of the state.
+pub struct ChildState<B, E, Block, RA> {
+	backend: Arc<dyn StateBackend<B, E, Block, RA>>,
+}
+
+impl<B, E, Block, RA> ChildStateApi<Block::Hash> for ChildState<B, E, Block, RA>
+	where
+		Block: BlockT<Hash=H256> + 'static,
+		B: client::backend::Backend<Block, Blake2Hasher> + Send + Sync + 'static,
+		E: CallExecutor<Block, Blake2Hasher> + Send + Sync + 'static + Clone,
+		RA: Send + Sync + 'static,
+{
+	type Metadata = crate::metadata::Metadata;
+
+	fn storage_keys(
+		&self,
+		child_storage_key: StorageKey,
+		key_prefix: StorageKey,
+		block: Option<Block::Hash>,
+	) -> FutureResult<Vec<StorageKey>> {
+		self.backend.child_storage_keys(block, child_storage_key, key_prefix)
+	}
+
+	fn storage_keys_paged(
+		&self,
+		child_storage_key: StorageKey,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		block: Option<Block::Hash>,
+	) -> FutureResult<Vec<StorageKey>> {
+		if let Err(e) = check_page_size(count) {
+			return Box::new(rpc::futures::future::err(e));
+		}
+		self.backend.child_storage_keys_paged(block, child_storage_key, prefix, count, start_key)
+	}
+
+	fn storage(
+		&self,
+		child_storage_key: StorageKey,
+		key: StorageKey,
+		block: Option<Block::Hash>,
+	) -> FutureResult<Option<StorageData>> {
+		self.backend.child_storage(block, child_storage_key, key)
+	}
+
+	fn storage_hash(
+		&self,
+		child_storage_key: StorageKey,
+		key: StorageKey,
+		block: Option<Block::Hash>,
+	) -> FutureResult<Option<Block::Hash>> {
+		self.backend.child_storage_hash(block, child_storage_key, key)
+	}
+
+	fn storage_size(
+		&self,
+		child_storage_key: StorageKey,
+		key: StorageKey,
+		block: Option<Block::Hash>,
+	) -> FutureResult<Option<u64>> {
+		self.backend.child_storage_size(block, child_storage_key, key)
+	}
+}
+
+/// Check that the number of keys requested in a single call doesn't exceed the maximum.
+fn check_page_size(count: u32) -> Result<(), Error> {
+	if count > MAX_KEYS_PAGE_SIZE {
+		Err(Error::InvalidCount {
+			value: count,
+			max: MAX_KEYS_PAGE_SIZE,
+		})
+	} else {
+		Ok(())
+	}
+}
+
 fn client_err(err: client::error::Error) -> Error {
-	Error::Client(Box::new(err))
+	Error::Client(crate::errors::client_error(err))
 }
@@ -30,19 +30,23 @@ use rpc::{
 use api::Subscriptions;
 use client::{
 	Client, CallExecutor, BlockchainEvents, runtime_api::Metadata,
-	backend::Backend, error::Result as ClientResult,
+	backend::Backend, error::Result as ClientResult, block_trace,
 };
 use primitives::{
 	H256, Blake2Hasher, Bytes, storage::{well_known_keys, StorageKey, StorageData, StorageChangeSet},
 };
 use runtime_version::RuntimeVersion;
-use state_machine::ExecutionStrategy;
+use state_machine::{ExecutionStrategy, StorageProof};
 use sr_primitives::{
 	generic::BlockId,
 	traits::{Block as BlockT, Header, NumberFor, ProvideRuntimeApi, SaturatedConversion},
 };
 
-use super::{StateBackend, error::{FutureResult, Error, Result}, client_err};
+use super::{
+	StateBackend, AllocationStats, CallWithAllocationStats,
+	BlockTrace, TraceSpan, StorageAccess, StorageAccessKind, StorageDiffEntry, ReadProof,
+	error::{FutureResult, Error, Result}, client_err,
+};
 
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
@@ -62,6 +66,8 @@ struct QueryStorageRange<Block: BlockT> {
 pub struct FullState<B, E, Block: BlockT, RA> {
 	client: Arc<Client<B, E, Block, RA>>,
 	subscriptions: Subscriptions,
+	/// Fuel available to runtime calls made through the API, `None` if they are unlimited.
+	fuel_limit: Option<u64>,
 }
 
 impl<B, E, Block: BlockT, RA> FullState<B, E, Block, RA>
@@ -72,7 +78,37 @@ impl<B, E, Block: BlockT, RA> FullState<B, E, Block, RA>
 {
 	/// Create new state API backend for full nodes.
 	pub fn new(client: Arc<Client<B, E, Block, RA>>, subscriptions: Subscriptions) -> Self {
-		Self { client, subscriptions }
+		Self { client, subscriptions, fuel_limit: None }
+	}
+
+	/// Limit the fuel available to runtime calls made through the API.
+	pub fn with_fuel_limit(mut self, fuel_limit: Option<u64>) -> Self {
+		self.fuel_limit = fuel_limit;
+		self
+	}
+
+	/// Call a runtime method at the given block, within the fuel limit of the API.
+	///
+	/// Metered calls are always executed in Wasm, whatever `strategy` is.
+	fn execute_call(
+		&self,
+		block: Block::Hash,
+		method: &str,
+		call_data: &[u8],
+		strategy: ExecutionStrategy,
+	) -> ClientResult<Vec<u8>> {
+		let call = || self.client.executor().call(
+			&BlockId::Hash(block),
+			method,
+			call_data,
+			strategy,
+			None,
+		);
+		match self.fuel_limit {
+			// The call is executed synchronously on this thread.
+			Some(fuel) => substrate_executor::with_fuel_limit(fuel, call),
+			None => call(),
+		}
 	}
 
 	/// Returns given block hash or best block hash if None is passed.
@@ -237,20 +273,45 @@ impl<B, E, Block, RA> StateBackend<B, E, Block, RA> for FullState<B, E, Block, R
 		method: String,
 		call_data: Bytes,
 	) -> FutureResult<Bytes> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| self.execute_call(
+					block,
+					&method,
+					&*call_data,
+					ExecutionStrategy::NativeElseWasm,
+				))
+				.map(Into::into)
+				.map_err(client_err)))
+	}
+
+	fn call_with_allocation_stats(
+		&self,
+		block: Option<Block::Hash>,
+		method: String,
+		call_data: Bytes,
+	) -> FutureResult<CallWithAllocationStats> {
 		Box::new(result(
 			self.block_or_best(block)
 				.and_then(|block|
-					self
-					.client
-					.executor()
-					.call(
-						&BlockId::Hash(block),
+					self.execute_call(
+						block,
 						&method,
 						&*call_data,
-						ExecutionStrategy::NativeElseWasm,
-						None,
+						ExecutionStrategy::AlwaysWasm,
 					)
-					.map(Into::into))
+					.map(|result| CallWithAllocationStats {
+						result: result.into(),
+						// The call above is executed synchronously on this thread.
+						allocation_stats: substrate_executor::take_last_allocation_stats()
+							.map(|stats| AllocationStats {
+								bytes_allocated: stats.bytes_allocated,
+								bytes_allocated_peak: stats.bytes_allocated_peak,
+								bytes_allocated_sum: stats.bytes_allocated_sum,
+								allocation_count: stats.allocation_count,
+								heap_size: stats.heap_size,
+							}),
+					}))
 				.map_err(client_err)))
 	}
 
@@ -265,6 +326,24 @@ impl<B, E, Block, RA> StateBackend<B, E, Block, RA> for FullState<B, E, Block, R
 				.map_err(client_err)))
 	}
 
+	fn storage_keys_paged(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| self.client.storage_keys_paged(
+					&BlockId::Hash(block),
+					prefix.as_ref(),
+					start_key.as_ref(),
+					count as usize,
+				))
+				.map_err(client_err)))
+	}
+
 	fn storage(
 		&self,
 		block: Option<Block::Hash>,
@@ -299,6 +378,26 @@ impl<B, E, Block, RA> StateBackend<B, E, Block, RA> for FullState<B, E, Block, R
 				.map_err(client_err)))
 	}
 
+	fn child_storage_keys_paged(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| self.client.child_storage_keys_paged(
+					&BlockId::Hash(block),
+					&child_storage_key,
+					prefix.as_ref(),
+					start_key.as_ref(),
+					count as usize,
+				))
+				.map_err(client_err)))
+	}
+
 	fn child_storage(
 		&self,
 		block: Option<Block::Hash>,
@@ -354,6 +453,49 @@ impl<B, E, Block, RA> StateBackend<B, E, Block, RA> for FullState<B, E, Block, R
 		Box::new(result(call_fn()))
 	}
 
+	fn trace_block(&self, block: Block::Hash) -> FutureResult<BlockTrace<Block::Hash>> {
+		Box::new(result(
+			self.client.trace_block(&BlockId::Hash(block))
+				.map(block_trace_to_rpc)
+				.map_err(client_err)))
+	}
+
+	fn block_storage_diff(&self, block: Block::Hash) -> FutureResult<Vec<StorageDiffEntry>> {
+		Box::new(result(
+			self.client.block_storage_diff(&BlockId::Hash(block))
+				.map(|diff| diff.into_iter().map(|change| StorageDiffEntry {
+					child_storage_key: change.child_storage_key.map(StorageKey),
+					key: StorageKey(change.key),
+					old_value: change.old_value.map(StorageData),
+					new_value: change.new_value.map(StorageData),
+				}).collect())
+				.map_err(client_err)))
+	}
+
+	fn read_proof(
+		&self,
+		block: Option<Block::Hash>,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| self.client.read_proof(&BlockId::Hash(block), keys.iter().map(|key| &key.0[..]))
+					.map(|proof| read_proof_to_rpc(block, proof)))
+				.map_err(client_err)))
+	}
+
+	fn prefix_read_proof(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: StorageKey,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| self.client.prefix_read_proof(&BlockId::Hash(block), &prefix.0)
+					.map(|proof| read_proof_to_rpc(block, proof)))
+				.map_err(client_err)))
+	}
+
 	fn subscribe_runtime_version(
 		&self,
 		_meta: crate::metadata::Metadata,
@@ -507,3 +649,40 @@ fn invalid_block_range<H: Header>(from: Option<&H>, to: Option<&H>, reason: Stri
 		details: reason,
 	}
 }
+
+/// Converts a storage proof generated at `block` to its RPC representation.
+fn read_proof_to_rpc<Hash>(block: Hash, proof: StorageProof) -> ReadProof<Hash> {
+	ReadProof {
+		at: block,
+		proof: proof.iter_nodes().map(Into::into).collect(),
+	}
+}
+
+/// Converts the trace of a block to its RPC representation.
+fn block_trace_to_rpc<Hash>(trace: block_trace::BlockTrace<Hash>) -> BlockTrace<Hash> {
+	BlockTrace {
+		block_hash: trace.hash,
+		duration_us: trace.duration.as_micros() as u64,
+		spans: trace.spans.into_iter().map(|span| {
+			let (name, extrinsic_index) = match span.target {
+				block_trace::TraceTarget::Initialization => ("initializeBlock", None),
+				block_trace::TraceTarget::Extrinsic(index) => ("applyExtrinsic", Some(index)),
+				block_trace::TraceTarget::Finalization => ("finalizeBlock", None),
+			};
+			TraceSpan {
+				name: name.into(),
+				extrinsic_index,
+				duration_us: span.duration.as_micros() as u64,
+				storage: span.storage.into_iter().map(|access| StorageAccess {
+					child_storage_key: access.child_storage_key.map(StorageKey),
+					key: StorageKey(access.key),
+					kind: match access.kind {
+						block_trace::StorageAccessKind::Read => StorageAccessKind::Read,
+						block_trace::StorageAccessKind::Write => StorageAccessKind::Write,
+					},
+					value_size: access.value_size,
+				}).collect(),
+			}
+		}).collect(),
+	}
+}
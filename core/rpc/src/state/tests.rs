@@ -70,6 +70,31 @@ fn should_return_storage() {
 
 }
 
+#[test]
+fn should_return_keys_paged() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let mut builder = TestClientBuilder::new();
+	for i in 0..5u8 {
+		builder = builder.add_extra_storage(vec![b':', b'p', i], vec![i]);
+	}
+	let client = Arc::new(builder.build());
+	let genesis_hash = client.genesis_hash();
+	let api = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+	let prefix = Some(StorageKey(b":p".to_vec()));
+
+	let first = api.storage_keys_paged(prefix.clone(), 3, None, Some(genesis_hash)).wait().unwrap();
+	assert_eq!(first, (0..3u8).map(|i| StorageKey(vec![b':', b'p', i])).collect::<Vec<_>>());
+
+	let second = api.storage_keys_paged(prefix.clone(), 3, first.last().cloned(), None)
+		.wait().unwrap();
+	assert_eq!(second, (3..5u8).map(|i| StorageKey(vec![b':', b'p', i])).collect::<Vec<_>>());
+
+	assert_matches!(
+		api.storage_keys_paged(prefix, MAX_KEYS_PAGE_SIZE + 1, None, None).wait(),
+		Err(Error::InvalidCount { .. })
+	);
+}
+
 #[test]
 fn should_return_child_storage() {
 	let core = tokio::runtime::Runtime::new().unwrap();
@@ -97,6 +122,55 @@ fn should_return_child_storage() {
 	);
 }
 
+#[test]
+fn should_return_child_state() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::TestClientBuilder::new()
+		.add_child_storage("test", "key1", vec![42_u8])
+		.add_child_storage("test", "key2", vec![43_u8, 44])
+		.add_child_storage("test", "other", vec![45_u8])
+		.build());
+	let genesis_hash = client.genesis_hash();
+	let child_state = new_full(client, Subscriptions::new(Arc::new(core.executor()))).child_state();
+	let child_key = StorageKey(well_known_keys::CHILD_STORAGE_KEY_PREFIX.iter().chain(b"test").cloned().collect());
+	let key = StorageKey(b"key2".to_vec());
+
+	assert_matches!(
+		ChildStateApi::storage(&child_state, child_key.clone(), key.clone(), Some(genesis_hash)).wait(),
+		Ok(Some(StorageData(ref d))) if d == &[43, 44]
+	);
+	assert_matches!(
+		ChildStateApi::storage_hash(&child_state, child_key.clone(), key.clone(), None)
+			.wait().map(|x| x.is_some()),
+		Ok(true)
+	);
+	assert_matches!(
+		ChildStateApi::storage_size(&child_state, child_key.clone(), key.clone(), None).wait(),
+		Ok(Some(2))
+	);
+	assert_eq!(
+		ChildStateApi::storage_keys(&child_state, child_key.clone(), StorageKey(b"key".to_vec()), None)
+			.wait().unwrap(),
+		vec![StorageKey(b"key1".to_vec()), StorageKey(b"key2".to_vec())],
+	);
+	assert_eq!(
+		ChildStateApi::storage_keys_paged(
+			&child_state,
+			child_key.clone(),
+			None,
+			2,
+			Some(StorageKey(b"key1".to_vec())),
+			None,
+		).wait().unwrap(),
+		vec![StorageKey(b"key2".to_vec()), StorageKey(b"other".to_vec())],
+	);
+	assert_matches!(
+		ChildStateApi::storage_keys_paged(&child_state, child_key, None, MAX_KEYS_PAGE_SIZE + 1, None, None)
+			.wait(),
+		Err(Error::InvalidCount { .. })
+	);
+}
+
 #[test]
 fn should_call_contract() {
 	let core = tokio::runtime::Runtime::new().unwrap();
@@ -110,6 +184,150 @@ fn should_call_contract() {
 	)
 }
 
+#[test]
+fn should_call_with_allocation_stats() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+
+	let call = client.call_with_allocation_stats(
+		"Core_version".into(),
+		Bytes(Vec::new()),
+		Some(genesis_hash).into(),
+	).wait().unwrap();
+
+	let stats = call.allocation_stats.unwrap();
+	assert!(stats.allocation_count > 0);
+	assert!(stats.bytes_allocated_peak > 0);
+	assert!(stats.bytes_allocated_peak <= stats.heap_size);
+}
+
+#[test]
+fn should_trace_block() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(core.executor())));
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(vec![1], Some(vec![1, 2, 3])).unwrap();
+	let block = builder.bake().unwrap();
+	let hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let trace = api.trace_block(hash).wait().unwrap();
+	assert_eq!(trace.block_hash, hash);
+	assert_eq!(
+		trace.spans.iter().map(|span| (span.name.as_str(), span.extrinsic_index)).collect::<Vec<_>>(),
+		vec![("initializeBlock", None), ("applyExtrinsic", Some(0)), ("finalizeBlock", None)],
+	);
+	assert!(trace.spans[1].storage.contains(&StorageAccess {
+		child_storage_key: None,
+		key: StorageKey(vec![1]),
+		kind: StorageAccessKind::Write,
+		value_size: Some(3),
+	}));
+
+	assert_matches!(
+		api.trace_block(H256::repeat_byte(1)).wait(),
+		Err(Error::Client(_))
+	);
+}
+
+#[test]
+fn should_return_block_storage_diff() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(core.executor())));
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(vec![1], Some(vec![1, 2, 3])).unwrap();
+	let block = builder.bake().unwrap();
+	let hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let diff = api.block_storage_diff(hash).wait().unwrap();
+	assert!(diff.contains(&StorageDiffEntry {
+		child_storage_key: None,
+		key: StorageKey(vec![1]),
+		old_value: None,
+		new_value: Some(StorageData(vec![1, 2, 3])),
+	}));
+
+	assert_matches!(
+		api.block_storage_diff(H256::repeat_byte(1)).wait(),
+		Err(Error::Client(_))
+	);
+}
+
+#[test]
+fn should_return_read_proofs() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(core.executor())));
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(b":proof:a".to_vec(), Some(vec![1])).unwrap();
+	builder.push_storage_change(b":proof:b".to_vec(), Some(vec![2])).unwrap();
+	let block = builder.bake().unwrap();
+	let hash = block.header.hash();
+	let state_root = block.header.state_root;
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let into_storage_proof = |proof: ReadProof<H256>| {
+		assert_eq!(proof.at, hash);
+		state_machine::StorageProof::new(proof.proof.into_iter().map(|node| node.0).collect())
+	};
+
+	let proof = api.read_proof(vec![StorageKey(b":proof:a".to_vec())], None).wait().unwrap();
+	let values = state_machine::read_proof_check::<Blake2Hasher, _>(
+		state_root,
+		into_storage_proof(proof),
+		&[b":proof:a"],
+	).unwrap();
+	assert_eq!(values.get(&b":proof:a"[..]), Some(&Some(vec![1])));
+
+	let proof = api.prefix_read_proof(StorageKey(b":proof:".to_vec()), Some(hash)).wait().unwrap();
+	let values = state_machine::read_prefix_proof_check::<Blake2Hasher>(
+		state_root,
+		into_storage_proof(proof),
+		b":proof:",
+	).unwrap();
+	assert_eq!(values, vec![(b":proof:a".to_vec(), vec![1]), (b":proof:b".to_vec(), vec![2])]);
+}
+
+#[test]
+fn should_limit_fuel_of_calls() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let subscriptions = Subscriptions::new(Arc::new(core.executor()));
+
+	let call = |api: &State<_, _, _, _>| api.call(
+		"Core_version".into(),
+		Bytes(Vec::new()),
+		Some(genesis_hash).into(),
+	).wait();
+
+	// The runtime is instantiated by a call with a tiny limit, which must only fail the call
+	// itself and not the runtime.
+	let limited = new_full_with_fuel_limit(client.clone(), subscriptions.clone(), Some(1));
+	for _ in 0..2 {
+		let err = call(&limited).unwrap_err();
+		assert!(format!("{:?}", err).contains("OutOfFuel"), "unexpected error: {:?}", err);
+	}
+
+	let generous = new_full_with_fuel_limit(
+		client.clone(),
+		subscriptions.clone(),
+		Some(u64::max_value()),
+	);
+	assert!(call(&generous).is_ok());
+
+	let unlimited = new_full_with_fuel_limit(client, subscriptions, None);
+	assert!(call(&unlimited).is_ok());
+}
+
 #[test]
 fn should_notify_about_storage_changes() {
 	let mut core = tokio::runtime::Runtime::new().unwrap();
@@ -282,7 +500,7 @@ fn should_return_runtime_version() {
 		\"specVersion\":1,\"implVersion\":1,\"apis\":[[\"0xdf6acb689907609b\",2],\
 		[\"0x37e397fc7c91f5e4\",1],[\"0xd2bc9897eed08f15\",1],[\"0x40fe3ad401f8959a\",3],\
 		[\"0xc6e9a76309f39b09\",1],[\"0xdd718d5cc53262d4\",1],[\"0xcbca25e39f142387\",1],\
-		[\"0xf78b278be53f454c\",1],[\"0xab3c0572291feb8b\",1],[\"0xbc9d89904f5b923f\",1]]}";
+		[\"0xf78b278be53f454c\",1],[\"0xab3c0572291feb8b\",2],[\"0xbc9d89904f5b923f\",1]]}";
 
 	let runtime_version = api.runtime_version(None.into()).wait().unwrap();
 	let serialized = serde_json::to_string(&runtime_version).unwrap();
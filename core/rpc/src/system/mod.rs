@@ -25,7 +25,7 @@ use sr_primitives::traits::{self, Header as HeaderT};
 use self::error::Result;
 
 pub use api::system::*;
-pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo, NodeRole};
+pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo, NodeRole, ReloadReport};
 pub use self::gen_client::Client as SystemClient;
 
 /// System API implementation
@@ -43,7 +43,9 @@ pub enum Request<B: traits::Block> {
 	/// Must return the state of the network.
 	NetworkState(oneshot::Sender<rpc::Value>),
 	/// Must return the node role.
-	NodeRoles(oneshot::Sender<Vec<NodeRole>>)
+	NodeRoles(oneshot::Sender<Vec<NodeRole>>),
+	/// Must apply the given options and report on the outcome.
+	ReloadConfig(Properties, oneshot::Sender<ReloadReport>),
 }
 
 impl<B: traits::Block> System<B> {
@@ -102,4 +104,10 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 		let _ = self.send_back.unbounded_send(Request::NodeRoles(tx));
 		Receiver(Compat::new(rx))
 	}
+
+	fn system_reload_config(&self, options: Properties) -> Receiver<ReloadReport> {
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::ReloadConfig(options, tx));
+		Receiver(Compat::new(rx))
+	}
 }
@@ -83,6 +83,16 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				}
+				Request::ReloadConfig(options, sender) => {
+					let mut report = ReloadReport::default();
+					for option in options.keys() {
+						match &option[..] {
+							"log" => report.applied.push(option.clone()),
+							_ => report.restart_required.push(option.clone()),
+						}
+					}
+					let _ = sender.send(report);
+				}
 			};
 
 			future::ready(())
@@ -231,4 +241,20 @@ fn system_node_roles() {
 		wait_receiver(api(None).system_node_roles()),
 		vec![NodeRole::Authority]
 	);
-}
\ No newline at end of file
+}
+
+#[test]
+fn system_reload_config_works() {
+	let mut options = Properties::new();
+	options.insert("log".into(), "sync=trace".into());
+	options.insert("port".into(), 30334.into());
+
+	assert_eq!(
+		wait_receiver(api(None).system_reload_config(options)),
+		ReloadReport {
+			applied: vec!["log".into()],
+			restart_required: vec!["port".into()],
+			failed: Default::default(),
+		}
+	);
+}
@@ -15,12 +15,14 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::{Service, NetworkStatus, NetworkState, error::{self, Error}, DEFAULT_PROTOCOL_ID};
-use crate::{SpawnTaskHandle, start_rpc_servers, build_network_future, TransactionPoolAdapter};
+use crate::{start_rpc_servers, build_network_future, TransactionPoolAdapter};
+use crate::task_manager::TaskManager;
 use crate::status_sinks;
-use crate::config::{Configuration, DatabaseConfig};
+use crate::config::{Configuration, DatabaseConfig, GenesisMismatchBehavior, PruningMode};
 use client::{
 	BlockchainEvents, Client, runtime_api,
 	backend::RemoteBackend, light::blockchain::RemoteBlockchain,
+	stats::StatsCollector,
 };
 use chain_spec::{RuntimeGenesis, Extension};
 use codec::{Decode, Encode, IoReader};
@@ -37,14 +39,18 @@ use log::{info, warn};
 use network::{FinalityProofProvider, OnDemand, NetworkService, NetworkStateInfo, DhtEvent};
 use network::{config::BoxFinalityProofRequestBuilder, specialization::NetworkSpecialization};
 use parking_lot::{Mutex, RwLock};
-use primitives::{Blake2Hasher, H256, Hasher};
+use primitives::{Blake2Hasher, H256, Hasher, storage::{StorageKey, well_known_keys}};
 use rpc;
+use sr_primitives::{StorageOverlay, ChildrenStorageOverlay};
 use sr_primitives::generic::BlockId;
 use sr_primitives::traits::{
 	Block as BlockT, Extrinsic, ProvideRuntimeApi, NumberFor, One, Zero, Header, SaturatedConversion
 };
 use substrate_executor::{NativeExecutor, NativeExecutionDispatch};
-use std::{io::{Read, Write, Seek}, marker::PhantomData, sync::Arc, sync::atomic::AtomicBool};
+use std::{
+	io::{Read, Write, Seek}, marker::PhantomData, path::{Path, PathBuf},
+	sync::Arc,
+};
 use sysinfo::{get_current_pid, ProcessExt, System, SystemExt};
 use tel::{telemetry, SUBSTRATE_INFO};
 use transaction_pool::txpool::{self, ChainApi, Pool as TransactionPool};
@@ -104,6 +110,17 @@ type TFullCallExecutor<TBl, TExecDisp> = client::LocalCallExecutor<
 	NativeExecutor<TExecDisp>,
 >;
 
+/// Full client type keeping its chain in memory.
+type TInMemoryClient<TBl, TRtApi, TExecDisp> = Client<
+	TInMemoryBackend<TBl>,
+	client::LocalCallExecutor<TInMemoryBackend<TBl>, NativeExecutor<TExecDisp>>,
+	TBl,
+	TRtApi,
+>;
+
+/// In-memory full client backend type.
+type TInMemoryBackend<TBl> = client::in_mem::Backend<TBl, Blake2Hasher>;
+
 /// Light client type.
 type TLightClient<TBl, TRtApi, TExecDisp> = Client<
 	TLightBackend<TBl>,
@@ -171,23 +188,44 @@ where TGen: RuntimeGenesis, TCSExt: Extension {
 			.cloned()
 			.unwrap_or_default();
 
-		let (client, backend) = {
+		let (mut client, backend) = {
 			let db_config = client_db::DatabaseSettings {
 				state_cache_size: config.state_cache_size,
 				state_cache_child_ratio:
 					config.state_cache_child_ratio.map(|v| (v, 100)),
 				pruning: config.pruning.clone(),
+				blocks_pruning: config.blocks_pruning,
+				index_extrinsics: config.index_extrinsics,
 				source: match &config.database {
-					DatabaseConfig::Path { path, cache_size } =>
+					DatabaseConfig::Path { path, cache_size, rocksdb } => {
+						let cache_size = cache_size.clone().map(|u| u as usize);
 						client_db::DatabaseSettingsSrc::Path {
-							path: path.clone(),
-							cache_size: cache_size.clone().map(|u| u as usize),
+							path: check_database_genesis::<TBl, _, _, _>(
+								&config,
+								path,
+								|path| client_db::DatabaseSettingsSrc::Path {
+									path,
+									cache_size,
+									rocksdb: rocksdb.clone(),
+								},
+							)?,
+							cache_size,
+							rocksdb: rocksdb.clone(),
+						}
+					},
+					DatabaseConfig::ParityDb { path } =>
+						client_db::DatabaseSettingsSrc::ParityDb {
+							path: check_database_genesis::<TBl, _, _, _>(
+								&config,
+								path,
+								|path| client_db::DatabaseSettingsSrc::ParityDb { path },
+							)?,
 						},
 					DatabaseConfig::Custom(db) =>
 						client_db::DatabaseSettingsSrc::Custom(db.clone()),
 				},
 			};
-			
+
 			client_db::new_client(
 				db_config,
 				executor,
@@ -198,6 +236,83 @@ where TGen: RuntimeGenesis, TCSExt: Extension {
 			)?
 		};
 
+		client.set_execution_metrics(config.execution_metrics.clone());
+		let client = Arc::new(client);
+
+		Ok(ServiceBuilder {
+			config,
+			client,
+			backend,
+			keystore,
+			fetcher: None,
+			select_chain: None,
+			import_queue: (),
+			finality_proof_request_builder: None,
+			finality_proof_provider: None,
+			network_protocol: (),
+			transaction_pool: Arc::new(()),
+			rpc_extensions: Default::default(),
+			remote_backend: None,
+			dht_event_tx: None,
+			marker: PhantomData,
+		})
+	}
+
+	/// Start the service builder of a full node keeping its chain in memory.
+	///
+	/// The database configuration is ignored, but the pruning mode is honored. Meant for
+	/// integration tests, which then run the same code paths as a full node without RocksDB.
+	pub fn new_test<TBl: BlockT<Hash=H256>, TRtApi, TExecDisp: NativeExecutionDispatch>(
+		config: Configuration<TCfg, TGen, TCSExt>
+	) -> Result<ServiceBuilder<
+		TBl,
+		TRtApi,
+		TCfg,
+		TGen,
+		TCSExt,
+		TInMemoryClient<TBl, TRtApi, TExecDisp>,
+		Arc<OnDemand<TBl>>,
+		(),
+		(),
+		BoxFinalityProofRequestBuilder<TBl>,
+		Arc<dyn FinalityProofProvider<TBl>>,
+		(),
+		(),
+		(),
+		TInMemoryBackend<TBl>,
+	>, Error> {
+		let keystore = Keystore::open(
+			config.keystore_path.clone().ok_or("No basepath configured")?,
+			config.keystore_password.clone()
+		)?;
+
+		let executor = NativeExecutor::<TExecDisp>::new(
+			config.wasm_method,
+			config.default_heap_pages,
+		);
+
+		let fork_blocks = config.chain_spec
+			.extensions()
+			.get::<client::ForkBlocks<TBl>>()
+			.cloned()
+			.unwrap_or_default();
+
+		let backend = Arc::new(match &config.pruning {
+			PruningMode::Constrained(constraints) =>
+				client::in_mem::Backend::new_with_pruning(constraints.max_blocks.unwrap_or(0)),
+			PruningMode::ArchiveCanonical =>
+				client::in_mem::Backend::new_with_pruning(std::u32::MAX),
+			PruningMode::ArchiveAll => client::in_mem::Backend::new(),
+		});
+		let mut client = Client::new(
+			backend.clone(),
+			client::LocalCallExecutor::new(backend.clone(), executor, Some(keystore.clone())),
+			&config.chain_spec,
+			fork_blocks,
+			config.execution_strategies.clone(),
+		)?;
+
+		client.set_execution_metrics(config.execution_metrics.clone());
 		let client = Arc::new(client);
 
 		Ok(ServiceBuilder {
@@ -255,12 +370,17 @@ where TGen: RuntimeGenesis, TCSExt: Extension {
 				state_cache_child_ratio:
 					config.state_cache_child_ratio.map(|v| (v, 100)),
 				pruning: config.pruning.clone(),
+				blocks_pruning: config.blocks_pruning,
+				index_extrinsics: config.index_extrinsics,
 				source: match &config.database {
-					DatabaseConfig::Path { path, cache_size } =>
+					DatabaseConfig::Path { path, cache_size, rocksdb } =>
 						client_db::DatabaseSettingsSrc::Path {
 							path: path.clone(),
 							cache_size: cache_size.clone().map(|u| u as usize),
+							rocksdb: rocksdb.clone(),
 						},
+					DatabaseConfig::ParityDb { path } =>
+						client_db::DatabaseSettingsSrc::ParityDb { path: path.clone() },
 					DatabaseConfig::Custom(db) =>
 						client_db::DatabaseSettingsSrc::Custom(db.clone()),
 				},
@@ -298,6 +418,83 @@ where TGen: RuntimeGenesis, TCSExt: Extension {
 	}
 }
 
+/// Checks that the genesis block of the full node database at `path` matches the genesis block
+/// built from the chain specification, applying the configured `GenesisMismatchBehavior` if not.
+///
+/// `source` describes how to open the database at a given path.
+///
+/// Returns the path of the database that should be opened.
+fn check_database_genesis<TBl, TCfg, TGen, TCSExt>(
+	config: &Configuration<TCfg, TGen, TCSExt>,
+	path: &Path,
+	source: impl Fn(PathBuf) -> client_db::DatabaseSettingsSrc,
+) -> Result<PathBuf, Error> where
+	TBl: BlockT<Hash=H256>,
+	TGen: RuntimeGenesis,
+	TCSExt: Extension,
+{
+	let read_genesis_hash = |path: &Path| -> Result<Option<H256>, Error> {
+		if !path.exists() {
+			return Ok(None);
+		}
+
+		let settings = client_db::DatabaseSettings {
+			state_cache_size: config.state_cache_size,
+			state_cache_child_ratio: config.state_cache_child_ratio.map(|v| (v, 100)),
+			pruning: config.pruning.clone(),
+			blocks_pruning: config.blocks_pruning,
+			index_extrinsics: config.index_extrinsics,
+			source: source(path.to_path_buf()),
+		};
+		Ok(client_db::read_genesis_hash::<TBl>(&settings)?)
+	};
+
+	let expected = client::genesis::genesis_hash::<TBl, _>(&config.chain_spec)?;
+	let namespaced_path = {
+		let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+		name.push(format!("-{:x}", expected));
+		path.with_file_name(name)
+	};
+
+	if config.genesis_mismatch == GenesisMismatchBehavior::Namespace && namespaced_path.exists() {
+		// The database was already namespaced for this genesis by a previous run.
+		return Ok(namespaced_path);
+	}
+
+	let found = match read_genesis_hash(path)? {
+		Some(found) if found != expected => found,
+		_ => return Ok(path.to_path_buf()),
+	};
+
+	match config.genesis_mismatch {
+		GenesisMismatchBehavior::Refuse => Err(Error::GenesisMismatch {
+			database: format!("{:?}", found),
+			chain_spec: format!("{:?}", expected),
+		}),
+		GenesisMismatchBehavior::Purge => {
+			warn!(
+				"Genesis block of the database ({:?}) doesn't match the chain specification ({:?}). \
+				Purging database at {}.",
+				found,
+				expected,
+				path.display(),
+			);
+			std::fs::remove_dir_all(path)?;
+			Ok(path.to_path_buf())
+		},
+		GenesisMismatchBehavior::Namespace => {
+			warn!(
+				"Genesis block of the database ({:?}) doesn't match the chain specification ({:?}). \
+				Using database at {}.",
+				found,
+				expected,
+				namespaced_path.display(),
+			);
+			Ok(namespaced_path)
+		},
+	}
+}
+
 impl<TBl, TRtApi, TCfg, TGen, TCSExt, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TNetP, TExPool, TRpc, Backend>
 	ServiceBuilder<TBl, TRtApi, TCfg, TGen, TCSExt, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 		TNetP, TExPool, TRpc, Backend> {
@@ -629,6 +826,8 @@ impl<TBl, TRtApi, TCfg, TGen, TCSExt, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TNet
 /// components to the builder.
 pub trait ServiceBuilderImport {
 	/// Starts the process of importing blocks.
+	///
+	/// The returned future fails if a block can't be read or imported.
 	fn import_blocks(
 		self,
 		exit: impl Future<Item=(),Error=()> + Send + 'static,
@@ -651,6 +850,22 @@ pub trait ServiceBuilderExport {
 		to: Option<NumberFor<Self::Block>>,
 		json: bool
 	) -> Result<(), Error>;
+
+	/// Returns the whole state of the given block, or of the best block if `None`, split into
+	/// the top-level storage and the child storages.
+	fn export_raw_state(
+		&self,
+		block: Option<BlockId<Self::Block>>,
+	) -> Result<(StorageOverlay, ChildrenStorageOverlay), Error>;
+
+	/// Writes a snapshot of the state of the given block, or of the best block if `None`, in
+	/// chunks of at most `chunk_size` entries. Returns the number of chunks written.
+	fn export_snapshot(
+		&self,
+		block: Option<BlockId<Self::Block>>,
+		chunk_size: usize,
+		output: impl Write,
+	) -> Result<u32, Error>;
 }
 
 /// Implemented on `ServiceBuilder`. Allows reverting the chain once you have given all the
@@ -660,9 +875,14 @@ pub trait ServiceBuilderRevert {
 	type Block: BlockT;
 
 	/// Performs a revert of `blocks` bocks.
+	///
+	/// Finalized blocks are only reverted if `force_finalized` is set. With `dry_run`, the blocks
+	/// that would be reverted are reported and the database is left untouched.
 	fn revert_chain(
 		&self,
-		blocks: NumberFor<Self::Block>
+		blocks: NumberFor<Self::Block>,
+		force_finalized: bool,
+		dry_run: bool,
 	) -> Result<(), Error>;
 }
 
@@ -713,6 +933,49 @@ where
 		let client = &self.client;
 		export_blocks!(client, exit, output, from, to, json)
 	}
+
+	fn export_raw_state(
+		&self,
+		block: Option<BlockId<TBl>>,
+	) -> Result<(StorageOverlay, ChildrenStorageOverlay), Error> {
+		let client = &self.client;
+		let block = block.unwrap_or_else(|| BlockId::Hash(client.info().chain.best_hash));
+		let empty_key = StorageKey(Vec::new());
+		let mut top = StorageOverlay::default();
+		let mut children = ChildrenStorageOverlay::default();
+
+		info!("Exporting raw state at {}", block);
+		for key in client.storage_keys(&block, &empty_key)? {
+			if well_known_keys::is_child_storage_key(&key.0) {
+				// The root of a child trie is rebuilt from its content when the genesis is
+				// built, so only the content is exported.
+				let mut child = StorageOverlay::default();
+				for child_key in client.child_storage_keys(&block, &key, &empty_key)? {
+					if let Some(value) = client.child_storage(&block, &key, &child_key)? {
+						child.insert(child_key.0, value.0);
+					}
+				}
+				children.insert(key.0, child);
+			} else if let Some(value) = client.storage(&block, &key)? {
+				top.insert(key.0, value.0);
+			}
+		}
+
+		Ok((top, children))
+	}
+
+	fn export_snapshot(
+		&self,
+		block: Option<BlockId<TBl>>,
+		chunk_size: usize,
+		mut output: impl Write,
+	) -> Result<u32, Error> {
+		let client = &self.client;
+		let block = block.unwrap_or_else(|| BlockId::Hash(client.info().chain.best_hash));
+
+		info!("Exporting state snapshot at {}", block);
+		Ok(client.export_state(&block, chunk_size, &mut output)?)
+	}
 }
 
 impl<TBl, TRtApi, TCfg, TGen, TCSExt, TBackend, TExec, TFchr, TSc, TImpQu, TFprb, TFpp, TNetP, TExPool, TRpc>
@@ -727,10 +990,12 @@ where
 
 	fn revert_chain(
 		&self,
-		blocks: NumberFor<TBl>
+		blocks: NumberFor<TBl>,
+		force_finalized: bool,
+		dry_run: bool,
 	) -> Result<(), Error> {
 		let client = &self.client;
-		revert_chain!(client, blocks)
+		revert_chain!(client, blocks, force_finalized, dry_run)
 	}
 }
 
@@ -808,11 +1073,10 @@ ServiceBuilder<
 			config.dev_key_seed.clone().map(|s| vec![s]).unwrap_or_default()
 		)?;
 
-		let (signal, exit) = exit_future::signal();
-
-		// List of asynchronous tasks to spawn. We collect them, then spawn them all at once.
-		let (to_spawn_tx, to_spawn_rx) =
-			mpsc::unbounded::<Box<dyn Future<Item = (), Error = ()> + Send>>();
+		// Asynchronous tasks are collected by the task manager, then spawned all at once when the
+		// service is first polled.
+		let task_manager = TaskManager::new();
+		let spawn_handle = task_manager.spawn_handle();
 
 		let import_queue = Box::new(import_queue);
 		let chain_info = client.info().chain;
@@ -830,7 +1094,7 @@ ServiceBuilder<
 			imports_external_transactions: !config.roles.is_light(),
 			pool: transaction_pool.clone(),
 			client: client.clone(),
-			executor: Arc::new(SpawnTaskHandle { sender: to_spawn_tx.clone(), on_exit: exit.clone() }),
+			executor: Arc::new(spawn_handle.clone()),
 		});
 
 		let protocol_id = {
@@ -849,8 +1113,14 @@ ServiceBuilder<
 		let block_announce_validator =
 			Box::new(consensus_common::block_validation::DefaultBlockAnnounceValidator::new(client.clone()));
 
+		let rpc_finality_proofs = finality_proof_provider.clone().map(|provider|
+			Arc::new(crate::FinalityProofSourceAdapter(provider)) as Arc<dyn rpc::chain::FinalityProofSource<TBl>>
+		);
+
 		let network_params = network::config::Params {
 			roles: config.roles,
+			sync_mode: config.network.sync_mode
+				.unwrap_or_else(|| network::config::SyncMode::for_roles(config.roles)),
 			network_config: config.network.clone(),
 			chain: client.clone(),
 			finality_proof_provider,
@@ -880,14 +1150,48 @@ ServiceBuilder<
 			_ => None,
 		};
 
+		// Light clients validate transactions through the network, so they don't keep a journal.
+		let journal = match (config.transaction_journal.as_ref(), remote_backend.is_none()) {
+			(Some(path), true) => match transaction_pool::Journal::open(path.clone()) {
+				Ok(journal) => Some(Arc::new(journal)),
+				Err(e) => {
+					warn!("Failed to open the transaction journal {}: {:?}", path.display(), e);
+					None
+				},
+			},
+			_ => None,
+		};
+		if let Some(journal) = journal.as_ref() {
+			let transactions = journal.transactions().into_iter()
+				.filter_map(|encoded| Decode::decode(&mut &encoded[..]).ok())
+				.collect::<Vec<_>>();
+			if !transactions.is_empty() {
+				info!("Resubmitting {} transactions from the journal", transactions.len());
+				if let Err(e) = futures03::executor::block_on(
+					transaction_pool.submit_at(&BlockId::hash(chain_info.best_hash), transactions, false)
+				) {
+					warn!("Error resubmitting transactions from the journal: {:?}", e);
+				}
+			}
+			prune_transaction_journal(journal, &transaction_pool);
+		}
+
+		// Light clients don't have the block bodies needed to collect chain statistics.
+		let chain_stats = match config.chain_stats.clone() {
+			Some(stats_config) if remote_backend.is_none() => Some(Arc::new(StatsCollector::new(stats_config))),
+			_ => None,
+		};
+
 		{
 			// block notifications
 			let txpool = Arc::downgrade(&transaction_pool);
 			let wclient = Arc::downgrade(&client);
 			let offchain = offchain_workers.as_ref().map(Arc::downgrade);
-			let to_spawn_tx_ = to_spawn_tx.clone();
+			let spawn_handle_ = spawn_handle.clone();
 			let network_state_info: Arc<dyn NetworkStateInfo + Send + Sync> = network.clone();
 			let is_validator = config.roles.is_authority();
+			let chain_stats = chain_stats.clone();
+			let journal = journal.clone();
 
 			let events = client.import_notification_stream()
 				.map(|v| Ok::<_, ()>(v)).compat()
@@ -895,6 +1199,20 @@ ServiceBuilder<
 					let number = *notification.header.number();
 					let txpool = txpool.upgrade();
 
+					if let (Some(chain_stats), Some(client)) = (chain_stats.as_ref(), wclient.upgrade()) {
+						match client.body(&BlockId::hash(notification.hash)) {
+							Ok(Some(body)) => chain_stats.note_imported(
+								number,
+								notification.hash,
+								body.len(),
+								body.iter().map(Encode::encoded_size).sum(),
+								notification.is_new_best && !notification.retracted.is_empty(),
+							),
+							Ok(None) => {},
+							Err(e) => warn!("Failed to read body of imported block {}: {:?}", notification.hash, e),
+						}
+					}
+
 					if let (Some(txpool), Some(client)) = (txpool.as_ref(), wclient.upgrade()) {
 						let future = maintain_transaction_pool(
 							&BlockId::hash(notification.hash),
@@ -902,21 +1220,67 @@ ServiceBuilder<
 							&*txpool,
 							&notification.retracted,
 						).map_err(|e| warn!("Pool error processing new block: {:?}", e))?;
-						let _ = to_spawn_tx_.unbounded_send(future);
+						let future: Box<dyn Future<Item = (), Error = ()> + Send> = match journal.clone() {
+							Some(journal) => {
+								let txpool = txpool.clone();
+								Box::new(future.then(move |result| {
+									prune_transaction_journal(&journal, &txpool);
+									result
+								}))
+							},
+							None => future,
+						};
+						spawn_handle_.spawn("txpool-maintain", future);
 					}
 
 					let offchain = offchain.as_ref().and_then(|o| o.upgrade());
 					if let (Some(txpool), Some(offchain)) = (txpool, offchain) {
 						let future = offchain.on_block_imported(&number, &txpool, network_state_info.clone(), is_validator)
 							.map(|()| Ok(()));
-						let _ = to_spawn_tx_.unbounded_send(Box::new(Compat::new(future)));
+						spawn_handle_.spawn("offchain-on-block", Compat::new(future));
 					}
 
 					Ok(())
-				})
-				.select(exit.clone())
-				.then(|_| Ok(()));
-			let _ = to_spawn_tx.unbounded_send(Box::new(events));
+				});
+			spawn_handle.spawn("import-notifications", events);
+		}
+
+		{
+			// let the watchers of the transactions know when their block gets finalized
+			let txpool = Arc::downgrade(&transaction_pool);
+			let events = client.finality_notification_stream()
+				.map(|v| Ok::<_, ()>(v)).compat()
+				.for_each(move |notification| {
+					if let Some(txpool) = txpool.upgrade() {
+						txpool.on_block_finalized(notification.hash, *notification.header.number());
+					}
+					Ok(())
+				});
+			spawn_handle.spawn("finality-notifications", events);
+		}
+
+		if config.transaction_pool.revalidation_batch > 0 {
+			// revalidate the transactions in the pool against the best block
+			let txpool = Arc::downgrade(&transaction_pool);
+			let wclient = Arc::downgrade(&client);
+			let batch = config.transaction_pool.revalidation_batch;
+			let events = tokio_timer::Interval::new_interval(config.transaction_pool.revalidation_interval)
+				.map_err(|e| warn!("Transaction pool revalidation timer failed: {:?}", e))
+				.for_each(move |_| -> Box<dyn Future<Item = (), Error = ()> + Send> {
+					match (txpool.upgrade(), wclient.upgrade()) {
+						(Some(txpool), Some(client)) => {
+							let best = BlockId::hash(client.info().chain.best_hash);
+							Box::new(txpool.revalidate(&best, batch)
+								.map(|result| Ok::<_, ()>(result.unwrap_or_else(|e|
+									warn!("Error revalidating transactions: {:?}", e)
+								)))
+								.boxed()
+								.compat())
+						},
+						_ => Box::new(futures::future::ok::<_, ()>(())),
+					}
+				});
+			spawn_handle.spawn("txpool-revalidation", events);
 		}
 
 		{
@@ -935,11 +1299,9 @@ ServiceBuilder<
 						"future" => status.future
 					);
 					Ok(())
-				})
-				.select(exit.clone())
-				.then(|_| Ok(()));
+				});
 
-			let _ = to_spawn_tx.unbounded_send(Box::new(events));
+			spawn_handle.spawn("txpool-notifications", events);
 		}
 
 		// Periodically notify the telemetry.
@@ -949,6 +1311,7 @@ ServiceBuilder<
 		let self_pid = get_current_pid().ok();
 		let (state_tx, state_rx) = mpsc::unbounded::<(NetworkStatus<_>, NetworkState)>();
 		network_status_sinks.lock().push(std::time::Duration::from_millis(5000), state_tx);
+		let mut last_submitted = None;
 		let tel_task = state_rx.for_each(move |(net_status, _)| {
 			let info = client_.info();
 			let best_number = info.chain.best_number.saturated_into::<u64>();
@@ -989,9 +1352,33 @@ ServiceBuilder<
 				"used_state_cache_size" => used_state_cache_size,
 			);
 
+			// The telemetry stands in for a metrics registry, which the node doesn't have.
+			let txpool_metrics = transaction_pool_.metrics();
+			let now = std::time::Instant::now();
+			let submitted_per_sec = match last_submitted.replace((now, txpool_metrics.submitted)) {
+				Some((at, submitted)) => {
+					let elapsed = now.duration_since(at).as_millis().max(1) as u64;
+					txpool_metrics.submitted.saturating_sub(submitted) * 1000 / elapsed
+				},
+				None => 0,
+			};
+			telemetry!(
+				SUBSTRATE_INFO;
+				"txpool.metrics";
+				"ready" => txpool_metrics.ready,
+				"future" => txpool_metrics.future,
+				"submitted" => txpool_metrics.submitted,
+				"submitted_per_sec" => submitted_per_sec,
+				"validations" => txpool_metrics.validations,
+				"banned" => txpool_metrics.banned,
+				"included" => txpool_metrics.included,
+				"average_inclusion_latency_ms" => txpool_metrics.average_inclusion_latency()
+					.map_or(0, |latency| latency.as_millis() as u64),
+			);
+
 			Ok(())
-		}).select(exit.clone()).then(|_| Ok(()));
-		let _ = to_spawn_tx.unbounded_send(Box::new(tel_task));
+		});
+		spawn_handle.spawn("telemetry-periodic-send", tel_task);
 
 		// Periodically send the network state to the telemetry.
 		let (netstat_tx, netstat_rx) = mpsc::unbounded::<(NetworkStatus<_>, NetworkState)>();
@@ -1003,12 +1390,23 @@ ServiceBuilder<
 				"state" => network_state,
 			);
 			Ok(())
-		}).select(exit.clone()).then(|_| Ok(()));
-		let _ = to_spawn_tx.unbounded_send(Box::new(tel_task_2));
+		});
+		spawn_handle.spawn("telemetry-periodic-network-state", tel_task_2);
+
+		// Notify the hooks of the events of the node.
+		#[cfg(not(target_os = "unknown"))]
+		{
+			if config.event_hooks.is_enabled() {
+				let (hooks_tx, hooks_rx) = mpsc::unbounded::<(NetworkStatus<_>, NetworkState)>();
+				network_status_sinks.lock().push(std::time::Duration::from_millis(5000), hooks_tx);
+				let hooks_task = crate::event_hooks::watch(client.clone(), config.event_hooks.clone(), hooks_rx);
+				spawn_handle.spawn("event-hooks", hooks_task);
+			}
+		}
 
 		// RPC
 		let (system_rpc_tx, system_rpc_rx) = futures03::channel::mpsc::unbounded();
-		let gen_handler = || {
+		let gen_handler = |middleware: rpc_servers::RpcMiddleware, allow_key_removal: bool| {
 			use rpc::{chain, state, author, system};
 
 			let system_info = rpc::system::SystemInfo {
@@ -1018,10 +1416,7 @@ ServiceBuilder<
 				properties: config.chain_spec.properties().clone(),
 			};
 
-			let subscriptions = rpc::Subscriptions::new(Arc::new(SpawnTaskHandle {
-				sender: to_spawn_tx.clone(),
-				on_exit: exit.clone()
-			}));
+			let subscriptions = rpc::Subscriptions::new(Arc::new(spawn_handle.clone()));
 
 			let (chain, state) = if let (Some(remote_backend), Some(on_demand)) =
 				(remote_backend.as_ref(), on_demand.as_ref()) {
@@ -1042,43 +1437,64 @@ ServiceBuilder<
 
 			} else {
 				// Full nodes
-				let chain = rpc::chain::new_full(client.clone(), subscriptions.clone());
-				let state = rpc::state::new_full(client.clone(), subscriptions.clone());
+				let chain = rpc::chain::new_full_with_stats(
+					client.clone(),
+					subscriptions.clone(),
+					chain_stats.clone(),
+					rpc_finality_proofs.clone(),
+				);
+				let state = rpc::state::new_full_with_fuel_limit(
+					client.clone(),
+					subscriptions.clone(),
+					config.rpc_call_fuel_limit,
+				);
 				(chain, state)
 			};
 
-			let author = rpc::author::Author::new(
+			let child_state = state.child_state();
+			let author = rpc::author::Author::new_with_key_removal(
 				client.clone(),
 				transaction_pool.clone(),
 				subscriptions,
 				keystore.clone(),
+				allow_key_removal,
 			);
+			let author = match journal.clone() {
+				Some(journal) => author.with_journal(journal),
+				None => author,
+			};
 			let system = system::System::new(system_info, system_rpc_tx.clone());
 
 			rpc_servers::rpc_handler((
 				state::StateApi::to_delegate(state),
+				state::ChildStateApi::to_delegate(child_state),
 				chain::ChainApi::to_delegate(chain),
 				author::AuthorApi::to_delegate(author),
 				system::SystemApi::to_delegate(system),
 				rpc_extensions.clone(),
-			))
+			), middleware)
 		};
-		let rpc_handlers = gen_handler();
-		let rpc = start_rpc_servers(&config, gen_handler)?;
-
-
-		let _ = to_spawn_tx.unbounded_send(Box::new(build_network_future(
+		// the policy only applies to the servers, not to the in-memory queries of the embedder.
+		let rpc_handlers = gen_handler(rpc_servers::RpcMiddleware::default(), config.rpc_allow_key_removal);
+		let rpc_middleware = rpc_servers::RpcMiddleware::new(config.rpc_policy.clone());
+		let rpc = start_rpc_servers(&config, |ipc| if ipc {
+			gen_handler(rpc_servers::RpcMiddleware::default(), true)
+		} else {
+			gen_handler(rpc_middleware.clone(), config.rpc_allow_key_removal)
+		})?;
+
+
+		// The network is stopped last on shutdown, together with the consensus tasks relying on it.
+		spawn_handle.spawn_essential("network-worker", build_network_future(
 			config.roles,
 			network_mut,
 			client.clone(),
 			network_status_sinks.clone(),
 			system_rpc_rx,
+			config.config_reloader.clone(),
 			has_bootnodes,
 			dht_event_tx,
-		)
-			.map_err(|_| ())
-			.select(exit.clone())
-			.then(|_| Ok(()))));
+		).map_err(|_| ()));
 
 		let telemetry_connection_sinks: Arc<Mutex<Vec<mpsc::UnboundedSender<()>>>> = Default::default();
 
@@ -1117,24 +1533,54 @@ ServiceBuilder<
 					});
 					Ok(())
 				});
-			let _ = to_spawn_tx.unbounded_send(Box::new(future
-				.select(exit.clone())
-				.then(|_| Ok(()))));
+			spawn_handle.spawn("telemetry-worker", future);
 			telemetry
 		});
 
+		// Options that can be changed without restarting the node.
+		{
+			let network = Arc::downgrade(&network);
+			let reserved_nodes = Mutex::new(config.network.reserved_nodes.clone());
+			config.config_reloader.register("reserved-nodes", move |value| {
+				let network = network.upgrade().ok_or("Network is shut down")?;
+				let new_nodes = string_list(value)?;
+				let mut reserved_nodes = reserved_nodes.lock();
+				for node in reserved_nodes.iter().filter(|node| !new_nodes.contains(node)) {
+					let (peer_id, _) = network::config::parse_str_addr(node)
+						.map_err(|e| format!("Invalid reserved node {}: {:?}", node, e))?;
+					network.remove_reserved_peer(peer_id);
+				}
+				for node in new_nodes.iter().filter(|node| !reserved_nodes.contains(node)) {
+					network.add_reserved_peer(node.clone())
+						.map_err(|e| format!("Invalid reserved node {}: {}", node, e))?;
+				}
+				*reserved_nodes = new_nodes;
+				Ok(())
+			});
+		}
+		if let Some(telemetry) = telemetry.clone() {
+			let default_endpoints = config.chain_spec.telemetry_endpoints().clone();
+			config.config_reloader.register("telemetry-url", move |value| {
+				let endpoints = match value {
+					serde_json::Value::Null => default_endpoints.clone()
+						.unwrap_or_else(|| tel::TelemetryEndpoints::new(Vec::new())),
+					value => tel::TelemetryEndpoints::new(string_list(value)?
+						.iter()
+						.map(|endpoint| parse_telemetry_endpoint(endpoint))
+						.collect::<Result<_, _>>()?),
+				};
+				telemetry.set_endpoints(endpoints);
+				Ok(())
+			});
+		}
+
 		Ok(Service {
 			client,
 			network,
 			network_status_sinks,
 			select_chain,
 			transaction_pool,
-			exit,
-			signal: Some(signal),
-			essential_failed: Arc::new(AtomicBool::new(false)),
-			to_spawn_tx,
-			to_spawn_rx,
-			to_poll: Vec::new(),
+			task_manager,
 			rpc_handlers,
 			_rpc: rpc,
 			_telemetry: telemetry,
@@ -1146,6 +1592,33 @@ ServiceBuilder<
 	}
 }
 
+/// Reads an option given once or several times, as a list of strings.
+fn string_list(value: &serde_json::Value) -> Result<Vec<String>, String> {
+	let values = match value {
+		serde_json::Value::Null => return Ok(Vec::new()),
+		serde_json::Value::Array(values) => &values[..],
+		value => std::slice::from_ref(value),
+	};
+	values.iter()
+		.map(|value| value.as_str()
+			.map(Into::into)
+			.ok_or_else(|| format!("Expected a string, got {}", value))
+		)
+		.collect()
+}
+
+/// Parses a telemetry endpoint given as `URL VERBOSITY`, the verbosity defaulting to 0.
+fn parse_telemetry_endpoint(endpoint: &str) -> Result<(String, u8), String> {
+	match endpoint.find(' ') {
+		None => Ok((endpoint.into(), 0)),
+		Some(pos) => {
+			let verbosity = endpoint[pos + 1..].parse()
+				.map_err(|e| format!("Invalid telemetry verbosity in {}: {}", endpoint, e))?;
+			Ok((endpoint[..pos].into(), verbosity))
+		},
+	}
+}
+
 pub(crate) fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	id: &BlockId<Block>,
 	client: &Arc<Client<Backend, Executor, Block, Api>>,
@@ -1161,6 +1634,9 @@ pub(crate) fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	Api: 'static,
 {
 	// Put transactions from retracted blocks back into the pool.
+	for hash in retracted {
+		transaction_pool.on_block_retracted(hash.clone());
+	}
 	let client_copy = client.clone();
 	let retracted_transactions = retracted.to_vec().into_iter()
 		.filter_map(move |hash| client_copy.block(&BlockId::hash(hash)).ok().unwrap_or(None))
@@ -1198,6 +1674,21 @@ pub(crate) fn maintain_transaction_pool<Api, Backend, Block, Executor, PoolApi>(
 	})
 }
 
+/// Drops the transactions which are no longer in the pool from the journal, i.e. the ones
+/// which got included or turned out invalid.
+fn prune_transaction_journal<PoolApi: ChainApi>(
+	journal: &transaction_pool::Journal,
+	transaction_pool: &TransactionPool<PoolApi>,
+) {
+	let result = journal.retain(|encoded| match Decode::decode(&mut &encoded[..]) {
+		Ok(xt) => transaction_pool.by_hash(&[transaction_pool.hash_of(&xt)]).pop().map_or(false, |tx| tx.is_some()),
+		Err(_) => false,
+	});
+	if let Err(e) = result {
+		warn!("Failed to update the transaction journal: {:?}", e);
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
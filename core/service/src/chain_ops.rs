@@ -16,8 +16,11 @@
 
 //! Chain utilities.
 
-use crate::error;
+use std::io::Read;
+use crate::{error, config::{Configuration, DatabaseConfig}};
 use chain_spec::{ChainSpec, RuntimeGenesis, Extension};
+use primitives::H256;
+use sr_primitives::traits::Block as BlockT;
 
 /// Defines the logic for an operation exporting blocks within a range.
 #[macro_export]
@@ -132,6 +135,8 @@ macro_rules! import_blocks {
 		.map_err(|e| format!("Error reading file: {}", e))?;
 	info!("Importing {} blocks", count);
 	let mut block_count = 0;
+	let mut queued: u64 = 0;
+	let mut read_error = false;
 	for b in 0 .. count {
 		if exit_recv.try_recv().is_ok() {
 			break;
@@ -159,9 +164,11 @@ macro_rules! import_blocks {
 						allow_missing_state: false,
 					}
 				]);
+				queued += 1;
 			}
 			Err(e) => {
 				warn!("Error reading block data at {}: {}", b, e);
+				read_error = true;
 				break;
 			}
 		}
@@ -188,7 +195,7 @@ macro_rules! import_blocks {
 				"Stopping after #{} blocks because of an error",
 				link.imported_blocks,
 			);
-			return Ok(Async::Ready(()));
+			return Err(());
 		}
 		if link.imported_blocks / 1000 != blocks_before / 1000 {
 			info!(
@@ -197,9 +204,13 @@ macro_rules! import_blocks {
 				count - link.imported_blocks
 			);
 		}
-		if link.imported_blocks >= count {
+		if link.imported_blocks >= queued {
 			info!("Imported {} blocks. Best: #{}", block_count, $client.info().chain.best_number);
-			Ok(Async::Ready(()))
+			if read_error {
+				Err(())
+			} else {
+				Ok(Async::Ready(()))
+			}
 		} else {
 			Ok(Async::NotReady)
 		}
@@ -210,14 +221,58 @@ macro_rules! import_blocks {
 /// Revert the chain some number of blocks.
 #[macro_export]
 macro_rules! revert_chain {
-($client:ident, $blocks:ident) => {{
-	let reverted = $client.revert($blocks)?;
+($client:ident, $blocks:ident, $force_finalized:ident, $dry_run:ident) => {{
+	if $dry_run {
+		let plan = $client.plan_revert($blocks, $force_finalized)?;
+		let info = $client.info().chain;
+		match (plan.first(), plan.last()) {
+			(Some(first), Some(last)) => {
+				info!("Dry run: {} blocks would be reverted.", plan.len());
+				for block in &plan {
+					info!(
+						"  #{} ({}){}: state changes reverted from the {} journal",
+						block.number,
+						block.hash,
+						if block.finalized { " finalized" } else { "" },
+						if block.canonical { "pruning" } else { "non-canonical" },
+					);
+				}
+				info!("Leaves removed: {}, added: {}", first.hash, last.parent_hash);
+				let finalized = if plan.iter().any(|block| block.finalized) {
+					(last.number - One::one(), last.parent_hash)
+				} else {
+					(info.finalized_number, info.finalized_hash)
+				};
+				info!(
+					"New best: #{} ({}), finalized: #{} ({})",
+					last.number - One::one(),
+					last.parent_hash,
+					finalized.0,
+					finalized.1,
+				);
+			},
+			_ => info!("Dry run: there aren't any blocks to revert."),
+		}
+		return Ok(());
+	}
+
+	let reverted = $client.revert($blocks, $force_finalized)?;
 	let info = $client.info().chain;
 
-	if reverted.is_zero() {
-		info!("There aren't any non-finalized blocks to revert.");
+	if reverted.is_empty() {
+		if $force_finalized {
+			info!("There aren't any blocks with available parent state to revert, the finalized \
+				blocks changing the consensus authorities can't be reverted.");
+		} else {
+			info!("There aren't any non-finalized blocks to revert.");
+		}
 	} else {
-		info!("Reverted {} blocks. Best: #{} ({})", reverted, info.best_number, info.best_hash);
+		let finalized = reverted.iter().filter(|block| block.finalized).count();
+		if finalized > 0 {
+			warn!("Reverted {} finalized blocks. Finalized: #{} ({})",
+				finalized, info.finalized_number, info.finalized_hash);
+		}
+		info!("Reverted {} blocks. Best: #{} ({})", reverted.len(), info.best_number, info.best_hash);
 	}
 	Ok(())
 }}
@@ -230,3 +285,47 @@ pub fn build_spec<G, E>(spec: ChainSpec<G, E>, raw: bool) -> error::Result<Strin
 {
 	Ok(spec.to_json(raw)?)
 }
+
+/// Initialize the empty database of a full node with a state snapshot read from `input`, instead of
+/// the genesis block.
+///
+/// The snapshot must belong to the chain of the specification. If the import fails, the database
+/// must be removed before trying again. Returns the header of the snapshot block, which becomes the
+/// best and finalized block of the database.
+pub fn import_snapshot<B, C, G, E>(
+	config: &Configuration<C, G, E>,
+	input: impl Read,
+) -> error::Result<B::Header> where
+	B: BlockT<Hash=H256>,
+	G: RuntimeGenesis,
+	E: Extension,
+{
+	let genesis_hash = client::genesis::genesis_hash::<B, _>(&config.chain_spec)?;
+	let settings = client_db::DatabaseSettings {
+		state_cache_size: config.state_cache_size,
+		state_cache_child_ratio: config.state_cache_child_ratio.map(|v| (v, 100)),
+		pruning: config.pruning.clone(),
+		blocks_pruning: config.blocks_pruning,
+		index_extrinsics: config.index_extrinsics,
+		source: database_source(&config.database),
+	};
+	Ok(client_db::import_snapshot::<B>(settings, genesis_hash, input)?)
+}
+
+/// Remove the bodies of all but the `keep` most recently finalized blocks from the database of a
+/// full node. Returns the number of removed bodies.
+pub fn purge_block_bodies(database: &DatabaseConfig, keep: u32) -> error::Result<u64> {
+	Ok(client_db::purge_block_bodies(database_source(database), keep)?)
+}
+
+fn database_source(database: &DatabaseConfig) -> client_db::DatabaseSettingsSrc {
+	match database {
+		DatabaseConfig::Path { path, cache_size, rocksdb } => client_db::DatabaseSettingsSrc::Path {
+			path: path.clone(),
+			cache_size: cache_size.map(|u| u as usize),
+			rocksdb: rocksdb.clone(),
+		},
+		DatabaseConfig::ParityDb { path } => client_db::DatabaseSettingsSrc::ParityDb { path: path.clone() },
+		DatabaseConfig::Custom(db) => client_db::DatabaseSettingsSrc::Custom(db.clone()),
+	}
+}
@@ -17,11 +17,16 @@
 //! Service configuration.
 
 pub use client::ExecutionStrategies;
-pub use client_db::{kvdb::KeyValueDB, PruningMode};
+pub use client::execution_metrics::ExecutionMetricsConfig;
+pub use client::stats::StatsConfig;
+pub use client_db::{kvdb::KeyValueDB, PruningMode, RocksDbSettings, CompactionStyle};
 pub use network::config::{ExtTransport, NetworkConfiguration, Roles};
+pub use rpc_servers::{RpcPolicy, DenyUnsafe};
 pub use substrate_executor::WasmExecutionMethod;
 
-use std::{path::PathBuf, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, net::SocketAddr, sync::Arc, time::Duration};
+use parking_lot::Mutex;
+use rpc::system::{Properties, ReloadReport};
 use transaction_pool;
 use chain_spec::{ChainSpec, RuntimeGenesis, Extension, NoExtension};
 use primitives::crypto::Protected;
@@ -41,6 +46,8 @@ pub struct Configuration<C, G, E = NoExtension> {
 	pub roles: Roles,
 	/// Extrinsic pool configuration.
 	pub transaction_pool: transaction_pool::txpool::Options,
+	/// Path to the journal of the transactions submitted through the local node, if any.
+	pub transaction_journal: Option<PathBuf>,
 	/// Network configuration.
 	pub network: NetworkConfiguration,
 	/// Path to the base configuration directory.
@@ -49,12 +56,18 @@ pub struct Configuration<C, G, E = NoExtension> {
 	pub keystore_path: Option<PathBuf>,
 	/// Configuration for the database.
 	pub database: DatabaseConfig,
+	/// What to do when the genesis block of an existing database doesn't match the chain spec.
+	pub genesis_mismatch: GenesisMismatchBehavior,
 	/// Size of internal state cache in Bytes
 	pub state_cache_size: usize,
 	/// Size in percent of cache size dedicated to child tries
 	pub state_cache_child_ratio: Option<usize>,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Number of finalized blocks to keep the bodies and justifications of. `None` keeps all of them.
+	pub blocks_pruning: Option<u32>,
+	/// Maintain an index of extrinsics by hash in the database.
+	pub index_extrinsics: bool,
 	/// Chain configuration.
 	pub chain_spec: ChainSpec<G, E>,
 	/// Custom configuration.
@@ -65,14 +78,32 @@ pub struct Configuration<C, G, E = NoExtension> {
 	pub wasm_method: WasmExecutionMethod,
 	/// Execution strategies.
 	pub execution_strategies: ExecutionStrategies,
+	/// Record the execution metrics of imported blocks. `None` if disabled.
+	pub execution_metrics: Option<ExecutionMetricsConfig>,
 	/// RPC over HTTP binding address. `None` if disabled.
 	pub rpc_http: Option<SocketAddr>,
 	/// RPC over Websockets binding address. `None` if disabled.
 	pub rpc_ws: Option<SocketAddr>,
+	/// RPC over IPC socket path. `None` if disabled.
+	///
+	/// The IPC server serves the full API, regardless of `rpc_policy` and `rpc_allow_key_removal`.
+	pub rpc_ipc: Option<String>,
 	/// Maximum number of connections for WebSockets RPC server. `None` if default.
 	pub rpc_ws_max_connections: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
+	/// Fuel available to runtime calls made through RPC. `None` if unlimited.
+	pub rpc_call_fuel_limit: Option<u64>,
+	/// Allow removing keys from the keystore through the `author_removeKeys` RPC method.
+	pub rpc_allow_key_removal: bool,
+	/// Methods served by the HTTP & WS servers, their rate limits, and the size of the batches.
+	pub rpc_policy: RpcPolicy,
+	/// Maximum size in bytes of the requests to the HTTP & WS servers. `None` if default.
+	pub rpc_max_payload: Option<usize>,
+	/// Collect statistics of imported blocks, served through `chain_getStats`. `None` if disabled.
+	pub chain_stats: Option<StatsConfig>,
+	/// Hooks notified of the events of the node.
+	pub event_hooks: EventHooksConfig,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
@@ -98,6 +129,50 @@ pub struct Configuration<C, G, E = NoExtension> {
 	///
 	/// Should only be set when `node` is running development mode.
 	pub dev_key_seed: Option<String>,
+	/// Options that can be changed while the node is running.
+	pub config_reloader: Arc<ConfigReloader>,
+}
+
+/// Handler applying a new value of an option to the running node.
+///
+/// The value is `Null` when the option is unset, in which case its default should be restored.
+pub type ReloadHandler = Box<dyn Fn(&serde_json::Value) -> Result<(), String> + Send + Sync>;
+
+/// Applies the options that can be changed without restarting the node.
+///
+/// Options are named after their command line option, e.g. `log` or `reserved-nodes`. Handlers are
+/// registered by the parts of the node owning the options, and are called on `system_reloadConfig`
+/// or when the command line interface reloads its configuration file.
+#[derive(Default)]
+pub struct ConfigReloader {
+	handlers: Mutex<HashMap<String, ReloadHandler>>,
+}
+
+impl ConfigReloader {
+	/// Registers the handler of an option, replacing its previous handler if any.
+	pub fn register(
+		&self,
+		option: &str,
+		handler: impl Fn(&serde_json::Value) -> Result<(), String> + Send + Sync + 'static,
+	) {
+		self.handlers.lock().insert(option.into(), Box::new(handler));
+	}
+
+	/// Applies the given options, reporting which of them require restarting the node.
+	pub fn reload(&self, options: &Properties) -> ReloadReport {
+		let handlers = self.handlers.lock();
+		let mut report = ReloadReport::default();
+		for (option, value) in options {
+			match handlers.get(option) {
+				Some(handler) => match handler(value) {
+					Ok(()) => report.applied.push(option.clone()),
+					Err(err) => { report.failed.insert(option.clone(), err); },
+				},
+				None => report.restart_required.push(option.clone()),
+			}
+		}
+		report
+	}
 }
 
 /// Configuration of the database of the client.
@@ -109,12 +184,62 @@ pub enum DatabaseConfig {
 		path: PathBuf,
 		/// Cache Size for internal database in MiB
 		cache_size: Option<u32>,
+		/// Tuning of the RocksDB database.
+		rocksdb: RocksDbSettings,
+	},
+
+	/// Parity-db database at a specific path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
 	},
 
 	/// A custom implementation of an already-open database.
 	Custom(Arc<dyn KeyValueDB>),
 }
 
+/// Behavior of a full node when the genesis block of an existing database doesn't match the
+/// genesis block built from the chain specification.
+///
+/// This commonly happens on development chains when the runtime or the genesis config changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenesisMismatchBehavior {
+	/// Refuse to start.
+	Refuse,
+	/// Remove the existing database and initialize a new one from the chain specification.
+	Purge,
+	/// Keep the existing database untouched and use a separate database whose path is suffixed
+	/// with the hash of the new genesis block.
+	Namespace,
+}
+
+impl Default for GenesisMismatchBehavior {
+	fn default() -> Self {
+		GenesisMismatchBehavior::Refuse
+	}
+}
+
+/// Hooks notified of the events of the node, e.g. to raise alerts.
+#[derive(Debug, Clone, Default)]
+pub struct EventHooksConfig {
+	/// Shell command run for each event.
+	pub command: Option<String>,
+	/// URL each event is posted to as JSON.
+	pub webhook: Option<String>,
+	/// Report when no block was finalized for this long. Not reported if `None`.
+	pub finality_stall: Option<Duration>,
+	/// Report when the best block is this many blocks behind the best block seen on the network.
+	/// Not reported if `None`.
+	pub max_blocks_behind: Option<u32>,
+}
+
+impl EventHooksConfig {
+	/// Are there hooks to notify?
+	pub fn is_enabled(&self) -> bool {
+		self.command.is_some() || self.webhook.is_some()
+	}
+}
+
 impl<C, G, E> Configuration<C, G, E> where
 	C: Default,
 	G: RuntimeGenesis,
@@ -131,22 +256,35 @@ impl<C, G, E> Configuration<C, G, E> where
 			name: Default::default(),
 			roles: Roles::FULL,
 			transaction_pool: Default::default(),
+			transaction_journal: None,
 			network: Default::default(),
 			keystore_path: config_dir.map(|c| c.join("keystore")),
 			database: DatabaseConfig::Path {
 				path: Default::default(),
 				cache_size: Default::default(),
+				rocksdb: Default::default(),
 			},
+			genesis_mismatch: Default::default(),
 			state_cache_size: Default::default(),
 			state_cache_child_ratio: Default::default(),
 			custom: Default::default(),
 			pruning: PruningMode::default(),
+			blocks_pruning: None,
+			index_extrinsics: false,
 			wasm_method: WasmExecutionMethod::Interpreted,
 			execution_strategies: Default::default(),
+			execution_metrics: None,
 			rpc_http: None,
 			rpc_ws: None,
+			rpc_ipc: None,
 			rpc_ws_max_connections: None,
 			rpc_cors: Some(vec![]),
+			rpc_call_fuel_limit: None,
+			rpc_allow_key_removal: false,
+			rpc_policy: Default::default(),
+			rpc_max_payload: None,
+			chain_stats: None,
+			event_hooks: Default::default(),
 			telemetry_endpoints: None,
 			telemetry_external_transport: None,
 			default_heap_pages: None,
@@ -156,6 +294,7 @@ impl<C, G, E> Configuration<C, G, E> where
 			disable_grandpa: false,
 			keystore_password: None,
 			dev_key_seed: None,
+			config_reloader: Default::default(),
 		};
 		configuration.network.boot_nodes = configuration.chain_spec.boot_nodes().to_vec();
 
@@ -201,3 +340,28 @@ pub fn full_version_from_strs(impl_version: &str, impl_commit: &str) -> String {
 	let commit_dash = if impl_commit.is_empty() { "" } else { "-" };
 	format!("{}{}{}-{}", impl_version, commit_dash, impl_commit, platform())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reload_reports_outcome_of_each_option() {
+		let reloader = ConfigReloader::default();
+		reloader.register("log", |value| match value {
+			serde_json::Value::String(_) => Ok(()),
+			_ => Err("expected a string".into()),
+		});
+		reloader.register("reserved-nodes", |_| Ok(()));
+
+		let mut options = Properties::new();
+		options.insert("log".into(), 5.into());
+		options.insert("port".into(), 30334.into());
+		options.insert("reserved-nodes".into(), serde_json::Value::Null);
+
+		let report = reloader.reload(&options);
+		assert_eq!(report.applied, vec!["reserved-nodes".to_string()]);
+		assert_eq!(report.restart_required, vec!["port".to_string()]);
+		assert_eq!(report.failed.get("log").map(|s| &s[..]), Some("expected a string"));
+	}
+}
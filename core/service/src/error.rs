@@ -37,6 +37,19 @@ pub enum Error {
 	Network(network::error::Error),
 	/// Keystore error.
 	Keystore(keystore::Error),
+	/// Genesis block of the database doesn't match the chain specification.
+	#[display(
+		fmt="Genesis block of the database ({}) doesn't match the chain specification ({}). \
+			Use `--on-genesis-mismatch` to purge or namespace the database.",
+		database,
+		chain_spec,
+	)]
+	GenesisMismatch {
+		/// Hash of the genesis block in the database.
+		database: String,
+		/// Hash of the genesis block built from the chain specification.
+		chain_spec: String,
+	},
 	/// Best chain selection strategy is missing.
 	#[display(fmt="Best chain selection strategy (SelectChain) is not provided.")]
 	SelectChainRequired,
@@ -0,0 +1,351 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hooks notifying external processes of noteworthy events of the node.
+//!
+//! The status of the node is checked every time the network status is refreshed. Events are
+//! reported once when their condition starts to hold, and again only after it stopped holding in
+//! the meantime. Each event is passed as JSON to a shell command, through the `SUBSTRATE_EVENT`
+//! and `SUBSTRATE_EVENT_JSON` environment variables, and posted to a webhook.
+
+use std::{process::Command, sync::Arc, thread, time::{Duration, Instant}};
+use client::{Client, CallExecutor, backend::Backend};
+use futures::{prelude::*, sync::mpsc};
+use log::{debug, warn};
+use network::NetworkState;
+use primitives::{Blake2Hasher, H256};
+use serde::Serialize;
+use sr_primitives::{generic::BlockId, traits::{Block as BlockT, NumberFor, Saturating, SimpleArithmetic}};
+use crate::{NetworkStatus, config::EventHooksConfig};
+
+/// Time after which a webhook request is abandoned.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An event reported to the hooks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<Number, Hash> {
+	/// No block was finalized for longer than the configured time.
+	FinalityStalled {
+		finalized_number: Number,
+		finalized_hash: Hash,
+		stalled_for_secs: u64,
+	},
+	/// A block enacting a new version of the runtime became the best block.
+	RuntimeUpgraded {
+		block_number: Number,
+		block_hash: Hash,
+		spec_name: String,
+		spec_version: u32,
+	},
+	/// The best block fell behind the best block seen on the network.
+	FellBehind {
+		best_number: Number,
+		best_seen_block: Number,
+	},
+}
+
+impl<Number, Hash> Event<Number, Hash> {
+	/// Name of the kind of the event, as found in the `event` field of its JSON encoding.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Event::FinalityStalled { .. } => "finality-stalled",
+			Event::RuntimeUpgraded { .. } => "runtime-upgraded",
+			Event::FellBehind { .. } => "fell-behind",
+		}
+	}
+}
+
+/// Status of the node the events are detected from.
+pub struct NodeStatus<Number, Hash> {
+	pub best_number: Number,
+	pub best_hash: Hash,
+	pub finalized_number: Number,
+	pub finalized_hash: Hash,
+	/// Best block seen on the network, if any.
+	pub best_seen_block: Option<Number>,
+	/// Name and version of the runtime at the best block, if known.
+	pub runtime: Option<(String, u32)>,
+}
+
+/// Detects the events from the successive statuses of the node.
+struct EventDetector<Number> {
+	finality_stall: Option<Duration>,
+	max_blocks_behind: Option<u32>,
+	/// Last finalized block number, and when it was first observed.
+	last_finalized: Option<(Number, Instant)>,
+	finality_stall_reported: bool,
+	fell_behind_reported: bool,
+	spec_version: Option<u32>,
+}
+
+impl<Number: SimpleArithmetic + Copy> EventDetector<Number> {
+	fn new(config: &EventHooksConfig) -> Self {
+		EventDetector {
+			finality_stall: config.finality_stall,
+			max_blocks_behind: config.max_blocks_behind,
+			last_finalized: None,
+			finality_stall_reported: false,
+			fell_behind_reported: false,
+			spec_version: None,
+		}
+	}
+
+	fn detect<Hash: Clone>(&mut self, status: &NodeStatus<Number, Hash>, now: Instant) -> Vec<Event<Number, Hash>> {
+		let mut events = Vec::new();
+
+		match self.last_finalized {
+			Some((number, since)) if number == status.finalized_number => {
+				let stalled_for = now.duration_since(since);
+				if self.finality_stall.map_or(false, |stall| stalled_for >= stall) && !self.finality_stall_reported {
+					self.finality_stall_reported = true;
+					events.push(Event::FinalityStalled {
+						finalized_number: status.finalized_number,
+						finalized_hash: status.finalized_hash.clone(),
+						stalled_for_secs: stalled_for.as_secs(),
+					});
+				}
+			},
+			_ => {
+				self.last_finalized = Some((status.finalized_number, now));
+				self.finality_stall_reported = false;
+			},
+		}
+
+		if let Some((spec_name, spec_version)) = &status.runtime {
+			// the first version observed is the one the node started with.
+			if self.spec_version.map_or(false, |known| known != *spec_version) {
+				events.push(Event::RuntimeUpgraded {
+					block_number: status.best_number,
+					block_hash: status.best_hash.clone(),
+					spec_name: spec_name.clone(),
+					spec_version: *spec_version,
+				});
+			}
+			self.spec_version = Some(*spec_version);
+		}
+
+		let behind = match (self.max_blocks_behind, status.best_seen_block) {
+			(Some(max), Some(best_seen)) if best_seen.saturating_sub(status.best_number) >= Number::from(max) =>
+				Some(best_seen),
+			_ => None,
+		};
+		match behind {
+			Some(best_seen_block) => if !self.fell_behind_reported {
+				self.fell_behind_reported = true;
+				events.push(Event::FellBehind { best_number: status.best_number, best_seen_block });
+			},
+			None => self.fell_behind_reported = false,
+		}
+
+		events
+	}
+}
+
+/// Run the configured shell command for the given event, without waiting for it to complete.
+fn run_command(command: &str, event: &str, payload: &str) {
+	let mut process = if cfg!(windows) {
+		let mut process = Command::new("cmd");
+		process.arg("/C");
+		process
+	} else {
+		let mut process = Command::new("sh");
+		process.arg("-c");
+		process
+	};
+	process.arg(command)
+		.env("SUBSTRATE_EVENT", event)
+		.env("SUBSTRATE_EVENT_JSON", payload);
+
+	match process.spawn() {
+		Ok(mut child) => {
+			// reap the process once it exits.
+			let _ = thread::Builder::new()
+				.name("event-hook".into())
+				.spawn(move || child.wait());
+		},
+		Err(e) => warn!(target: "service", "Failed to run event hook command: {}", e),
+	}
+}
+
+/// Post the given event payload to the webhook.
+fn post_webhook(
+	client: &hyper::Client<hyper_tls::HttpsConnector<hyper::client::HttpConnector>, hyper::Body>,
+	url: &hyper::Uri,
+	payload: String,
+) -> impl Future<Item = (), Error = ()> {
+	let request = hyper::Request::post(url.clone())
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(hyper::Body::from(payload))
+		.expect("the URL is valid and the header is well-formed; qed");
+
+	tokio_timer::Timeout::new(client.request(request), WEBHOOK_TIMEOUT)
+		.then(|result| {
+			match result {
+				Ok(response) => if !response.status().is_success() {
+					warn!(target: "service", "Event webhook responded with status {}", response.status());
+				},
+				Err(e) => warn!(target: "service", "Failed to post event to webhook: {:?}", e),
+			}
+			Ok(())
+		})
+}
+
+/// Watch the node for events and report them to the configured hooks.
+///
+/// The node is checked each time a network status is received.
+pub fn watch<B, E, Block, RA>(
+	client: Arc<Client<B, E, Block, RA>>,
+	config: EventHooksConfig,
+	statuses: mpsc::UnboundedReceiver<(NetworkStatus<Block>, NetworkState)>,
+) -> impl Future<Item = (), Error = ()> where
+	Block: BlockT<Hash=H256>,
+	B: Backend<Block, Blake2Hasher>,
+	E: CallExecutor<Block, Blake2Hasher>,
+{
+	let webhook = config.webhook.as_ref().and_then(|url| {
+		let url = url.parse::<hyper::Uri>()
+			.map_err(|e| warn!(target: "service", "Invalid event webhook URL {}: {}", url, e))
+			.ok()?;
+		let connector = hyper_tls::HttpsConnector::new(1)
+			.map_err(|e| warn!(target: "service", "Unable to initialize TLS client for event webhook: {:?}", e))
+			.ok()?;
+		Some((hyper::Client::builder().build(connector), url))
+	});
+	let mut detector = EventDetector::<NumberFor<Block>>::new(&config);
+
+	statuses.for_each(move |(net_status, _)| {
+		let info = client.info().chain;
+		let runtime = client.runtime_version_at(&BlockId::Hash(info.best_hash))
+			.ok()
+			.map(|version| (version.spec_name.to_string(), version.spec_version));
+		let status = NodeStatus {
+			best_number: info.best_number,
+			best_hash: info.best_hash,
+			finalized_number: info.finalized_number,
+			finalized_hash: info.finalized_hash,
+			best_seen_block: net_status.best_seen_block,
+			runtime,
+		};
+
+		let mut requests = Vec::new();
+		for event in detector.detect(&status, Instant::now()) {
+			let payload = match serde_json::to_string(&event) {
+				Ok(payload) => payload,
+				Err(e) => {
+					warn!(target: "service", "Failed to encode event {}: {}", event.name(), e);
+					continue
+				},
+			};
+			debug!(target: "service", "Reporting event {}", payload);
+
+			if let Some(command) = &config.command {
+				run_command(command, event.name(), &payload);
+			}
+			if let Some((client, url)) = &webhook {
+				requests.push(post_webhook(client, url, payload));
+			}
+		}
+		futures::future::join_all(requests).map(|_| ())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn status(best: u64, finalized: u64, best_seen: Option<u64>, spec_version: u32) -> NodeStatus<u64, u64> {
+		NodeStatus {
+			best_number: best,
+			best_hash: best,
+			finalized_number: finalized,
+			finalized_hash: finalized,
+			best_seen_block: best_seen,
+			runtime: Some(("test".into(), spec_version)),
+		}
+	}
+
+	fn detector() -> EventDetector<u64> {
+		EventDetector::new(&EventHooksConfig {
+			command: Some("true".into()),
+			webhook: None,
+			finality_stall: Some(Duration::from_secs(60)),
+			max_blocks_behind: Some(10),
+		})
+	}
+
+	#[test]
+	fn finality_stall_is_reported_once() {
+		let mut detector = detector();
+		let start = Instant::now();
+
+		assert!(detector.detect(&status(5, 2, None, 1), start).is_empty());
+		assert!(detector.detect(&status(6, 2, None, 1), start + Duration::from_secs(30)).is_empty());
+		assert_eq!(
+			detector.detect(&status(7, 2, None, 1), start + Duration::from_secs(61)),
+			vec![Event::FinalityStalled { finalized_number: 2, finalized_hash: 2, stalled_for_secs: 61 }],
+		);
+		assert!(detector.detect(&status(8, 2, None, 1), start + Duration::from_secs(120)).is_empty());
+
+		// finality resumed, then stalled again.
+		assert!(detector.detect(&status(8, 3, None, 1), start + Duration::from_secs(121)).is_empty());
+		assert_eq!(detector.detect(&status(9, 3, None, 1), start + Duration::from_secs(200)).len(), 1);
+	}
+
+	#[test]
+	fn runtime_upgrades_are_reported() {
+		let mut detector = detector();
+		let now = Instant::now();
+
+		assert!(detector.detect(&status(1, 1, None, 1), now).is_empty());
+		assert!(detector.detect(&status(2, 2, None, 1), now).is_empty());
+		assert_eq!(
+			detector.detect(&status(3, 3, None, 2), now),
+			vec![Event::RuntimeUpgraded {
+				block_number: 3,
+				block_hash: 3,
+				spec_name: "test".into(),
+				spec_version: 2,
+			}],
+		);
+		assert!(detector.detect(&status(4, 4, None, 2), now).is_empty());
+	}
+
+	#[test]
+	fn falling_behind_is_reported_once() {
+		let mut detector = detector();
+		let now = Instant::now();
+
+		assert!(detector.detect(&status(10, 10, Some(19), 1), now).is_empty());
+		assert_eq!(
+			detector.detect(&status(10, 10, Some(20), 1), now),
+			vec![Event::FellBehind { best_number: 10, best_seen_block: 20 }],
+		);
+		assert!(detector.detect(&status(11, 11, Some(30), 1), now).is_empty());
+		assert!(detector.detect(&status(29, 29, Some(30), 1), now).is_empty());
+		assert_eq!(detector.detect(&status(29, 29, Some(40), 1), now).len(), 1);
+	}
+
+	#[test]
+	fn events_are_encoded_with_their_name() {
+		let event = Event::<u64, u64>::FellBehind { best_number: 1, best_seen_block: 20 };
+		assert_eq!(
+			serde_json::to_string(&event).unwrap(),
+			r#"{"event":"fell-behind","best_number":1,"best_seen_block":20}"#,
+		);
+		assert_eq!(event.name(), "fell-behind");
+	}
+}
@@ -25,19 +25,20 @@ pub mod chain_ops;
 pub mod error;
 
 mod builder;
+#[cfg(not(target_os = "unknown"))]
+mod event_hooks;
 mod status_sinks;
+mod task_manager;
 
 use std::io;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use futures::sync::mpsc;
 use parking_lot::Mutex;
 
 use client::{runtime_api::BlockT, Client};
-use exit_future::Signal;
 use futures::prelude::*;
 use futures03::{
 	future::{ready, FutureExt as _, TryFutureExt as _},
@@ -47,7 +48,7 @@ use network::{
 	NetworkService, NetworkState, specialization::NetworkSpecialization,
 	Event, DhtEvent, PeerId, ReportHandle,
 };
-use log::{log, warn, debug, error, Level};
+use log::{log, warn, debug, Level};
 use codec::{Encode, Decode};
 use primitives::{Blake2Hasher, H256};
 use sr_primitives::generic::BlockId;
@@ -55,12 +56,14 @@ use sr_primitives::traits::NumberFor;
 
 pub use self::error::Error;
 pub use self::builder::{ServiceBuilder, ServiceBuilderExport, ServiceBuilderImport, ServiceBuilderRevert};
+pub use self::task_manager::SpawnTaskHandle;
 pub use config::{Configuration, Roles, PruningMode};
 pub use chain_spec::{ChainSpec, Properties, RuntimeGenesis, Extension as ChainSpecExtension};
 pub use transaction_pool::txpool::{
 	self, Pool as TransactionPool, Options as TransactionPoolOptions, ChainApi, IntoPoolError
 };
 pub use client::FinalityNotifications;
+pub use client_db::migrate_to_parity_db;
 pub use rpc::Metadata as RpcMetadata;
 #[doc(hidden)]
 pub use std::{ops::Deref, result::Result, sync::Arc};
@@ -80,22 +83,9 @@ pub struct Service<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> {
 	/// For each element, every time the `Interval` fires we push an element on the sender.
 	network_status_sinks: Arc<Mutex<status_sinks::StatusSinks<(TNetStatus, NetworkState)>>>,
 	transaction_pool: Arc<TTxPool>,
-	/// A future that resolves when the service has exited, this is useful to
-	/// make sure any internally spawned futures stop when the service does.
-	exit: exit_future::Exit,
-	/// A signal that makes the exit future above resolve, fired on service drop.
-	signal: Option<Signal>,
-	/// Set to `true` when a spawned essential task has failed. The next time
-	/// the service future is polled it should complete with an error.
-	essential_failed: Arc<AtomicBool>,
-	/// Sender for futures that must be spawned as background tasks.
-	to_spawn_tx: mpsc::UnboundedSender<Box<dyn Future<Item = (), Error = ()> + Send>>,
-	/// Receiver for futures that must be spawned as background tasks.
-	to_spawn_rx: mpsc::UnboundedReceiver<Box<dyn Future<Item = (), Error = ()> + Send>>,
-	/// List of futures to poll from `poll`.
-	/// If spawning a background task is not possible, we instead push the task into this `Vec`.
-	/// The elements must then be polled manually.
-	to_poll: Vec<Box<dyn Future<Item = (), Error = ()> + Send>>,
+	/// Spawns the background tasks of the service and stops them, in order, when the service
+	/// is dropped.
+	task_manager: task_manager::TaskManager,
 	rpc_handlers: rpc_servers::RpcHandler<rpc::Metadata>,
 	_rpc: Box<dyn std::any::Any + Send + Sync>,
 	_telemetry: Option<tel::Telemetry>,
@@ -108,28 +98,6 @@ pub struct Service<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> {
 /// Alias for a an implementation of `futures::future::Executor`.
 pub type TaskExecutor = Arc<dyn Executor<Box<dyn Future<Item = (), Error = ()> + Send>> + Send + Sync>;
 
-/// An handle for spawning tasks in the service.
-#[derive(Clone)]
-pub struct SpawnTaskHandle {
-	sender: mpsc::UnboundedSender<Box<dyn Future<Item = (), Error = ()> + Send>>,
-	on_exit: exit_future::Exit,
-}
-
-impl Executor<Box<dyn Future<Item = (), Error = ()> + Send>> for SpawnTaskHandle {
-	fn execute(
-		&self,
-		future: Box<dyn Future<Item = (), Error = ()> + Send>,
-	) -> Result<(), futures::future::ExecuteError<Box<dyn Future<Item = (), Error = ()> + Send>>> {
-		let future = Box::new(future.select(self.on_exit.clone()).then(|_| Ok(())));
-		if let Err(err) = self.sender.unbounded_send(future) {
-			let kind = futures::future::ExecuteErrorKind::Shutdown;
-			Err(futures::future::ExecuteError::new(kind, err.into_inner()))
-		} else {
-			Ok(())
-		}
-	}
-}
-
 /// Abstraction over a Substrate service.
 pub trait AbstractService: 'static + Future<Item = (), Error = Error> +
 	Executor<Box<dyn Future<Item = (), Error = ()> + Send>> + Send {
@@ -154,13 +122,13 @@ pub trait AbstractService: 'static + Future<Item = (), Error = Error> +
 	/// return a shared instance of Telemetry (if enabled)
 	fn telemetry(&self) -> Option<tel::Telemetry>;
 
-	/// Spawns a task in the background that runs the future passed as parameter.
-	fn spawn_task(&self, task: impl Future<Item = (), Error = ()> + Send + 'static);
+	/// Spawns a task named `name` in the background that runs the future passed as parameter.
+	fn spawn_task(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static);
 
-	/// Spawns a task in the background that runs the future passed as
+	/// Spawns a task named `name` in the background that runs the future passed as
 	/// parameter. The given task is considered essential, i.e. if it errors we
-	/// trigger a service exit.
-	fn spawn_essential_task(&self, task: impl Future<Item = (), Error = ()> + Send + 'static);
+	/// trigger a service exit. Essential tasks are the last ones stopped on shutdown.
+	fn spawn_essential_task(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static);
 
 	/// Returns a handle for spawning tasks.
 	fn spawn_task_handle(&self) -> SpawnTaskHandle;
@@ -233,27 +201,16 @@ where
 		self.keystore.clone()
 	}
 
-	fn spawn_task(&self, task: impl Future<Item = (), Error = ()> + Send + 'static) {
-		let task = task.select(self.on_exit()).then(|_| Ok(()));
-		let _ = self.to_spawn_tx.unbounded_send(Box::new(task));
+	fn spawn_task(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static) {
+		self.task_manager.spawn_handle().spawn(name, task)
 	}
 
-	fn spawn_essential_task(&self, task: impl Future<Item = (), Error = ()> + Send + 'static) {
-		let essential_failed = self.essential_failed.clone();
-		let essential_task = task.map_err(move |_| {
-			error!("Essential task failed. Shutting down service.");
-			essential_failed.store(true, Ordering::Relaxed);
-		});
-		let task = essential_task.select(self.on_exit()).then(|_| Ok(()));
-
-		let _ = self.to_spawn_tx.unbounded_send(Box::new(task));
+	fn spawn_essential_task(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static) {
+		self.task_manager.spawn_handle().spawn_essential(name, task)
 	}
 
 	fn spawn_task_handle(&self) -> SpawnTaskHandle {
-		SpawnTaskHandle {
-			sender: self.to_spawn_tx.clone(),
-			on_exit: self.on_exit(),
-		}
+		self.task_manager.spawn_handle()
 	}
 
 	fn rpc_query(&self, mem: &RpcSession, request: &str) -> Box<dyn Future<Item = Option<String>, Error = ()> + Send> {
@@ -283,7 +240,7 @@ where
 	}
 
 	fn on_exit(&self) -> exit_future::Exit {
-		self.exit.clone()
+		self.task_manager.on_exit()
 	}
 }
 
@@ -294,29 +251,8 @@ impl<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> Future for
 	type Error = Error;
 
 	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-		if self.essential_failed.load(Ordering::Relaxed) {
-			return Err(Error::Other("Essential task failed.".into()));
-		}
-
-		while let Ok(Async::Ready(Some(task_to_spawn))) = self.to_spawn_rx.poll() {
-			let executor = tokio_executor::DefaultExecutor::current();
-			if let Err(err) = executor.execute(task_to_spawn) {
-				debug!(
-					target: "service",
-					"Failed to spawn background task: {:?}; falling back to manual polling",
-					err
-				);
-				self.to_poll.push(err.into_future());
-			}
-		}
-
-		// Polling all the `to_poll` futures.
-		while let Some(pos) = self.to_poll.iter_mut().position(|t| t.poll().map(|t| t.is_ready()).unwrap_or(true)) {
-			let _ = self.to_poll.remove(pos);
-		}
-
-		// The service future never ends.
-		Ok(Async::NotReady)
+		// The service future never ends, unless an essential task fails.
+		self.task_manager.poll()
 	}
 }
 
@@ -327,12 +263,7 @@ impl<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> Executor<Box<dyn Future<Item
 		&self,
 		future: Box<dyn Future<Item = (), Error = ()> + Send>
 	) -> Result<(), futures::future::ExecuteError<Box<dyn Future<Item = (), Error = ()> + Send>>> {
-		if let Err(err) = self.to_spawn_tx.unbounded_send(future) {
-			let kind = futures::future::ExecuteErrorKind::Shutdown;
-			Err(futures::future::ExecuteError::new(kind, err.into_inner()))
-		} else {
-			Ok(())
-		}
+		self.task_manager.spawn_handle().execute(future)
 	}
 }
 
@@ -350,6 +281,7 @@ fn build_network_future<
 	client: Arc<C>,
 	status_sinks: Arc<Mutex<status_sinks::StatusSinks<(NetworkStatus<B>, NetworkState)>>>,
 	rpc_rx: futures03::channel::mpsc::UnboundedReceiver<rpc::system::Request<B>>,
+	config_reloader: Arc<config::ConfigReloader>,
 	should_have_peers: bool,
 	dht_event_tx: Option<mpsc::Sender<DhtEvent>>,
 ) -> impl Future<Item = (), Error = ()> {
@@ -420,6 +352,9 @@ fn build_network_future<
 
 					let _ = sender.send(node_roles);
 				}
+				rpc::system::Request::ReloadConfig(options, sender) => {
+					let _ = sender.send(config_reloader.reload(&options));
+				}
 			};
 		}
 
@@ -492,15 +427,16 @@ impl<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> Drop for
 {
 	fn drop(&mut self) {
 		debug!(target: "service", "Substrate service shutdown");
-		if let Some(signal) = self.signal.take() {
-			signal.fire();
-		}
+		self.task_manager.terminate();
 	}
 }
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
+///
+/// `gen_handler` is given `true` for the handlers of the IPC server, only reachable by local
+/// processes, which serve the full API.
 #[cfg(not(target_os = "unknown"))]
-fn start_rpc_servers<C, G, E, H: FnMut() -> rpc_servers::RpcHandler<rpc::Metadata>>(
+fn start_rpc_servers<C, G, E, H: FnMut(bool) -> rpc_servers::RpcHandler<rpc::Metadata>>(
 	config: &Configuration<C, G, E>,
 	mut gen_handler: H
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
@@ -525,7 +461,12 @@ fn start_rpc_servers<C, G, E, H: FnMut() -> rpc_servers::RpcHandler<rpc::Metadat
 	Ok(Box::new((
 		maybe_start_server(
 			config.rpc_http,
-			|address| rpc_servers::start_http(address, config.rpc_cors.as_ref(), gen_handler()),
+			|address| rpc_servers::start_http(
+				address,
+				config.rpc_cors.as_ref(),
+				config.rpc_max_payload,
+				gen_handler(false),
+			),
 		)?,
 		maybe_start_server(
 			config.rpc_ws,
@@ -533,15 +474,20 @@ fn start_rpc_servers<C, G, E, H: FnMut() -> rpc_servers::RpcHandler<rpc::Metadat
 				address,
 				config.rpc_ws_max_connections,
 				config.rpc_cors.as_ref(),
-				gen_handler(),
+				config.rpc_max_payload,
+				gen_handler(false),
 			),
 		)?.map(Mutex::new),
+		config.rpc_ipc.as_ref()
+			.map(|path| rpc_servers::start_ipc(path, gen_handler(true)))
+			.transpose()?
+			.map(Mutex::new),
 	)))
 }
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(target_os = "unknown")]
-fn start_rpc_servers<C, G, E, H: FnMut() -> rpc_servers::RpcHandler<rpc::Metadata>>(
+fn start_rpc_servers<C, G, E, H: FnMut(bool) -> rpc_servers::RpcHandler<rpc::Metadata>>(
 	_: &Configuration<C, G, E>,
 	_: H
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
@@ -663,6 +609,20 @@ where
 	}
 }
 
+/// Finality proof provider adapter, proving the finality of the finalized heads to the RPC
+/// subscribers.
+struct FinalityProofSourceAdapter<B: BlockT>(Arc<dyn FinalityProofProvider<B>>);
+
+impl<B: BlockT> rpc::chain::FinalityProofSource<B> for FinalityProofSourceAdapter<B> {
+	fn prove_finality(
+		&self,
+		last_finalized: B::Hash,
+		block: B::Hash,
+	) -> client::error::Result<Option<Vec<u8>>> {
+		self.0.prove_finality_since(last_finalized, block)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -700,4 +660,114 @@ mod tests {
 		// this should not panic
 		let _ = transactions[0].1.transfer();
 	}
+
+	#[test]
+	fn every_rpc_method_is_either_safe_or_unsafe() {
+		use rpc::{author::AuthorApi, chain::ChainApi, state::{ChildStateApi, StateApi}, system::SystemApi};
+
+		// methods reviewed as safe to serve publicly. New methods must be added either here or to
+		// the unsafe methods of the RPC servers.
+		const SAFE_METHODS: &[&str] = &[
+			"author_pendingExtrinsics",
+			"author_submitAndWatchExtrinsic",
+			"author_submitExtrinsic",
+			"author_unwatchExtrinsic",
+			"chain_getBlock",
+			"chain_getBlockHash",
+			"chain_getExecutionMetrics",
+			"chain_getExtrinsic",
+			"chain_getFinalisedHead",
+			"chain_getFinalizedHead",
+			"chain_getHead",
+			"chain_getHeader",
+			"chain_getRuntimeVersion",
+			"chain_getStats",
+			"chain_subscribeFinalisedHeads",
+			"chain_subscribeFinalizedHeads",
+			"chain_subscribeFinalizedHeadsWithJustification",
+			"chain_subscribeNewHead",
+			"chain_subscribeNewHeads",
+			"chain_subscribeRuntimeVersion",
+			"chain_unsubscribeFinalisedHeads",
+			"chain_unsubscribeFinalizedHeads",
+			"chain_unsubscribeFinalizedHeadsWithJustification",
+			"chain_unsubscribeNewHead",
+			"chain_unsubscribeNewHeads",
+			"chain_unsubscribeRuntimeVersion",
+			"childstate_getKeysPaged",
+			"childstate_getStorage",
+			"childstate_getStorageHash",
+			"childstate_getStorageSize",
+			"rpc_methods",
+			"state_call",
+			"state_callAt",
+			"state_getChildStorage",
+			"state_getChildStorageHash",
+			"state_getChildStorageSize",
+			"state_getKeysPaged",
+			"state_getKeysPagedAt",
+			"state_getMetadata",
+			"state_getRuntimeVersion",
+			"state_getStorage",
+			"state_getStorageAt",
+			"state_getStorageHash",
+			"state_getStorageHashAt",
+			"state_getStorageSize",
+			"state_getStorageSizeAt",
+			"state_subscribeRuntimeVersion",
+			"state_subscribeStorage",
+			"state_unsubscribeRuntimeVersion",
+			"state_unsubscribeStorage",
+			"subscribe_newHead",
+			"system_chain",
+			"system_health",
+			"system_name",
+			"system_nodeRoles",
+			"system_properties",
+			"system_version",
+			"unsubscribe_newHead",
+		];
+
+		let runtime = tokio::runtime::Runtime::new().unwrap();
+		let subscriptions = rpc::Subscriptions::new(Arc::new(runtime.executor()));
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(TransactionPool::new(
+			Default::default(),
+			transaction_pool::FullChainApi::new(client.clone())
+		));
+		let state = rpc::state::new_full(client.clone(), subscriptions.clone());
+		let child_state = state.child_state();
+		let chain = rpc::chain::new_full(client.clone(), subscriptions.clone());
+		let author = rpc::author::Author::new(
+			client,
+			pool,
+			subscriptions,
+			primitives::testing::KeyStore::new(),
+		);
+		let system_info = rpc::system::SystemInfo {
+			chain_name: "test".into(),
+			impl_name: "test".into(),
+			impl_version: "0.0.0".into(),
+			properties: Default::default(),
+		};
+		let system = rpc::system::System::<substrate_test_runtime_client::runtime::Block>::new(
+			system_info,
+			futures03::channel::mpsc::unbounded().0,
+		);
+
+		let io: rpc_servers::RpcHandler<rpc::Metadata> = rpc_servers::rpc_handler((
+			StateApi::to_delegate(state),
+			ChildStateApi::to_delegate(child_state),
+			ChainApi::to_delegate(chain),
+			AuthorApi::to_delegate(author),
+			SystemApi::to_delegate(system),
+		), Default::default());
+		for (method, _) in io.iter() {
+			assert!(
+				SAFE_METHODS.contains(&method.as_str()) != rpc_servers::is_unsafe(method),
+				"RPC method {} must be either safe or unsafe",
+				method,
+			);
+		}
+	}
 }
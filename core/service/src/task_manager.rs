@@ -0,0 +1,341 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! Keeps track of the background tasks spawned by the service.
+//!
+//! Every task has a name, used in logs, and is either essential or not. The failure of an
+//! essential task brings the whole service down. On shutdown, the non-essential tasks are stopped
+//! first and the essential ones afterwards, each stage getting a limited amount of time to finish.
+
+use std::{collections::HashMap, sync::Arc, thread, time::{Duration, Instant}};
+use exit_future::{Exit, Signal};
+use futures::{prelude::*, future::Executor, sync::mpsc};
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+use tokio_executor::Executor as _;
+
+use crate::error::Error;
+
+/// A task that can be spawned by the service.
+type Task = Box<dyn Future<Item = (), Error = ()> + Send>;
+
+/// The number of running tasks, by name and kind.
+type RunningTasks = Arc<Mutex<HashMap<(&'static str, TaskKind), usize>>>;
+
+/// How long each stage of the shutdown waits for its tasks to stop.
+const SHUTDOWN_STAGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Name given to the tasks spawned through the `Executor` interface.
+const UNNAMED_TASK: &str = "unnamed";
+
+/// Whether a task is essential to the service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum TaskKind {
+	/// A task whose failure is only logged. Stopped first on shutdown.
+	NonEssential,
+	/// A task whose failure shuts the service down. Stopped last on shutdown.
+	Essential,
+}
+
+/// Keeps a task counted as running for as long as it's alive.
+struct RunningGuard {
+	running: RunningTasks,
+	key: (&'static str, TaskKind),
+}
+
+impl RunningGuard {
+	fn new(running: RunningTasks, key: (&'static str, TaskKind)) -> Self {
+		*running.lock().entry(key).or_insert(0) += 1;
+		RunningGuard { running, key }
+	}
+}
+
+impl Drop for RunningGuard {
+	fn drop(&mut self) {
+		let mut running = self.running.lock();
+		if let Some(count) = running.get_mut(&self.key) {
+			*count -= 1;
+			if *count == 0 {
+				running.remove(&self.key);
+			}
+		}
+	}
+}
+
+/// An handle for spawning tasks in the service.
+#[derive(Clone)]
+pub struct SpawnTaskHandle {
+	sender: mpsc::UnboundedSender<(TaskKind, Task)>,
+	essential_failed: mpsc::UnboundedSender<&'static str>,
+	running: RunningTasks,
+	non_essential_exit: Exit,
+	essential_exit: Exit,
+}
+
+impl SpawnTaskHandle {
+	/// Spawns a non-essential task named `name`.
+	pub fn spawn(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static) {
+		let _ = self.sender.unbounded_send((TaskKind::NonEssential, self.wrap(name, TaskKind::NonEssential, task)));
+	}
+
+	/// Spawns an essential task named `name`. If it fails, the service shuts down.
+	pub fn spawn_essential(&self, name: &'static str, task: impl Future<Item = (), Error = ()> + Send + 'static) {
+		let _ = self.sender.unbounded_send((TaskKind::Essential, self.wrap(name, TaskKind::Essential, task)));
+	}
+
+	/// Wraps `task` so that it's counted as running, stops at its stage of the shutdown and
+	/// reports its failure.
+	fn wrap(
+		&self,
+		name: &'static str,
+		kind: TaskKind,
+		task: impl Future<Item = (), Error = ()> + Send + 'static,
+	) -> Task {
+		let (task, exit): (Task, _) = match kind {
+			TaskKind::NonEssential => (
+				Box::new(task.map_err(move |_| warn!(target: "service", "Task {} failed", name))),
+				self.non_essential_exit.clone(),
+			),
+			TaskKind::Essential => {
+				let essential_failed = self.essential_failed.clone();
+				let task = task.map_err(move |_| {
+					error!("Essential task {} failed. Shutting down service.", name);
+					let _ = essential_failed.unbounded_send(name);
+				});
+				(Box::new(task), self.essential_exit.clone())
+			},
+		};
+
+		// Only count the task once it's actually running, so that the tasks that never got
+		// spawned don't hold up the shutdown.
+		let running = self.running.clone();
+		Box::new(futures::future::lazy(move || {
+			let guard = RunningGuard::new(running, (name, kind));
+			task.select(exit).then(move |result| {
+				// Drop whatever is left of the task before it stops counting as running.
+				drop(result);
+				debug!(target: "service", "Task {} stopped", name);
+				drop(guard);
+				Ok(())
+			})
+		}))
+	}
+}
+
+impl Executor<Task> for SpawnTaskHandle {
+	fn execute(&self, future: Task) -> Result<(), futures::future::ExecuteError<Task>> {
+		let task = self.wrap(UNNAMED_TASK, TaskKind::NonEssential, future);
+		if let Err(err) = self.sender.unbounded_send((TaskKind::NonEssential, task)) {
+			let kind = futures::future::ExecuteErrorKind::Shutdown;
+			Err(futures::future::ExecuteError::new(kind, err.into_inner().1))
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// Spawns the tasks of the service on the current executor and stops them on shutdown.
+pub(crate) struct TaskManager {
+	handle: SpawnTaskHandle,
+	to_spawn_rx: mpsc::UnboundedReceiver<(TaskKind, Task)>,
+	essential_failed_rx: mpsc::UnboundedReceiver<&'static str>,
+	/// List of tasks to poll from `poll`.
+	/// If spawning a task on the executor is not possible, we instead push it into this `Vec`.
+	/// The elements must then be polled manually.
+	to_poll: Vec<(TaskKind, Task)>,
+	non_essential_signal: Option<Signal>,
+	essential_signal: Option<Signal>,
+}
+
+impl TaskManager {
+	/// Creates a new task manager with no tasks.
+	pub(crate) fn new() -> Self {
+		let (sender, to_spawn_rx) = mpsc::unbounded();
+		let (essential_failed, essential_failed_rx) = mpsc::unbounded();
+		let (non_essential_signal, non_essential_exit) = exit_future::signal();
+		let (essential_signal, essential_exit) = exit_future::signal();
+
+		TaskManager {
+			handle: SpawnTaskHandle {
+				sender,
+				essential_failed,
+				running: Default::default(),
+				non_essential_exit,
+				essential_exit,
+			},
+			to_spawn_rx,
+			essential_failed_rx,
+			to_poll: Vec::new(),
+			non_essential_signal: Some(non_essential_signal),
+			essential_signal: Some(essential_signal),
+		}
+	}
+
+	/// Returns a handle for spawning tasks.
+	pub(crate) fn spawn_handle(&self) -> SpawnTaskHandle {
+		self.handle.clone()
+	}
+
+	/// Returns a future that resolves once the service has stopped all its tasks.
+	pub(crate) fn on_exit(&self) -> Exit {
+		self.handle.essential_exit.clone()
+	}
+
+	/// Spawns the tasks submitted since the last call and polls the ones that couldn't be spawned.
+	///
+	/// Never completes successfully, but fails as soon as an essential task has failed.
+	pub(crate) fn poll(&mut self) -> Poll<(), Error> {
+		if let Ok(Async::Ready(Some(name))) = self.essential_failed_rx.poll() {
+			return Err(Error::Other(format!("Essential task {} failed.", name)));
+		}
+
+		while let Ok(Async::Ready(Some((kind, task)))) = self.to_spawn_rx.poll() {
+			let executor = tokio_executor::DefaultExecutor::current();
+			if let Err(err) = executor.execute(task) {
+				debug!(
+					target: "service",
+					"Failed to spawn background task: {:?}; falling back to manual polling",
+					err
+				);
+				self.to_poll.push((kind, err.into_future()));
+			}
+		}
+
+		// Polling all the `to_poll` futures.
+		while let Some(pos) = self.to_poll.iter_mut()
+			.position(|(_, t)| t.poll().map(|t| t.is_ready()).unwrap_or(true))
+		{
+			let _ = self.to_poll.remove(pos);
+		}
+
+		Ok(Async::NotReady)
+	}
+
+	/// Stops all the tasks: the non-essential ones first, then the essential ones.
+	///
+	/// Each stage waits for its tasks to stop for at most `SHUTDOWN_STAGE_TIMEOUT`, unless called
+	/// from an executor thread, where blocking could stall the very tasks we wait for. Does
+	/// nothing if the tasks were already stopped.
+	pub(crate) fn terminate(&mut self) {
+		let can_wait = tokio_executor::DefaultExecutor::current().status().is_err();
+		let stages = vec![
+			(TaskKind::NonEssential, self.non_essential_signal.take()),
+			(TaskKind::Essential, self.essential_signal.take()),
+		];
+
+		for (kind, signal) in stages {
+			let signal = match signal {
+				Some(signal) => signal,
+				None => continue,
+			};
+
+			// Manually polled tasks can't make progress any more, drop them right away.
+			self.to_poll.retain(|(k, _)| *k != kind);
+			signal.fire();
+
+			if can_wait {
+				self.wait_for(kind);
+			}
+		}
+	}
+
+	/// Blocks until all the tasks of the given kind have stopped, or the stage times out.
+	fn wait_for(&self, kind: TaskKind) {
+		let deadline = Instant::now() + SHUTDOWN_STAGE_TIMEOUT;
+		loop {
+			let remaining = self.handle.running.lock().keys()
+				.filter(|(_, k)| *k == kind)
+				.map(|(name, _)| *name)
+				.collect::<Vec<_>>();
+			if remaining.is_empty() {
+				return;
+			}
+			if Instant::now() >= deadline {
+				warn!(
+					target: "service",
+					"Timed out waiting for the {:?} tasks {} to stop",
+					kind,
+					remaining.join(", "),
+				);
+				return;
+			}
+			thread::sleep(Duration::from_millis(10));
+		}
+	}
+}
+
+impl Drop for TaskManager {
+	fn drop(&mut self) {
+		self.terminate();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Records its name in the shared list when dropped.
+	struct DropRecorder(Arc<Mutex<Vec<&'static str>>>, &'static str);
+
+	impl Drop for DropRecorder {
+		fn drop(&mut self) {
+			self.0.lock().push(self.1);
+		}
+	}
+
+	/// A task that never completes and records when it's stopped.
+	fn endless_task(order: &Arc<Mutex<Vec<&'static str>>>, name: &'static str) -> impl Future<Item = (), Error = ()> {
+		let recorder = DropRecorder(order.clone(), name);
+		futures::future::empty().map(move |()| drop(recorder))
+	}
+
+	#[test]
+	fn essential_task_failure_fails_the_manager() {
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let mut task_manager = TaskManager::new();
+		task_manager.spawn_handle().spawn("harmless", futures::future::err(()));
+		task_manager.spawn_handle().spawn_essential("worker", futures::future::err(()));
+
+		let result = runtime.block_on(futures::future::poll_fn(move ||
+			task_manager.poll().map_err(|e| e.to_string())
+		));
+		assert_eq!(result, Err("Essential task worker failed.".to_string()));
+	}
+
+	#[test]
+	fn tasks_are_stopped_in_order() {
+		let mut runtime = tokio::runtime::Runtime::new().unwrap();
+		let order = Arc::new(Mutex::new(Vec::new()));
+		let task_manager = TaskManager::new();
+		let handle = task_manager.spawn_handle();
+		handle.spawn_essential("essential", endless_task(&order, "essential"));
+		handle.spawn("non-essential", endless_task(&order, "non-essential"));
+
+		let mut task_manager = runtime.block_on(futures::future::lazy(move || {
+			let _ = task_manager.poll();
+			Ok::<_, ()>(task_manager)
+		})).unwrap();
+
+		let deadline = Instant::now() + Duration::from_secs(5);
+		while handle.running.lock().len() < 2 {
+			assert!(Instant::now() < deadline, "tasks should start running");
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		task_manager.terminate();
+		assert_eq!(*order.lock(), vec!["non-essential", "essential"]);
+		assert!(handle.running.lock().is_empty());
+	}
+}
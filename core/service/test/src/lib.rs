@@ -160,6 +160,11 @@ fn node_config<G, E: Clone> (
 			enable_mdns: false,
 			wasm_external_transport: None,
 		},
+		max_announced_digest_item_size: None,
+		non_authority_propagation_delay: None,
+		sync_import_batch_size: None,
+		sync_max_queued_blocks: None,
+		sync_mode: None,
 	};
 
 	Configuration {
@@ -168,26 +173,39 @@ fn node_config<G, E: Clone> (
 		impl_commit: "",
 		roles: role,
 		transaction_pool: Default::default(),
+		transaction_journal: None,
 		network: network_config,
 		keystore_path: Some(root.join("key")),
 		keystore_password: None,
 		config_dir: Some(root.clone()),
 		database: DatabaseConfig::Path {
 			path: root.join("db"),
-			cache_size: None
+			cache_size: None,
+			rocksdb: Default::default(),
 		},
+		genesis_mismatch: Default::default(),
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
 		pruning: Default::default(),
+		blocks_pruning: None,
+		index_extrinsics: false,
 		chain_spec: (*spec).clone(),
 		custom: Default::default(),
 		name: format!("Node {}", index),
 		wasm_method: service::config::WasmExecutionMethod::Interpreted,
 		execution_strategies: Default::default(),
+		execution_metrics: None,
 		rpc_http: None,
 		rpc_ws: None,
+		rpc_ipc: None,
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
+		rpc_call_fuel_limit: None,
+		rpc_allow_key_removal: false,
+		rpc_policy: Default::default(),
+		rpc_max_payload: None,
+		event_hooks: Default::default(),
+		chain_stats: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
 		default_heap_pages: None,
@@ -196,6 +214,7 @@ fn node_config<G, E: Clone> (
 		force_authoring: false,
 		disable_grandpa: false,
 		dev_key_seed: key_seed,
+		config_reloader: Default::default(),
 	}
 }
 
@@ -41,6 +41,9 @@ mod utils;
 /// `RUNTIME_API_VERSIONS` is generated. This constant should be used to instantiate the `apis`
 /// field of `RuntimeVersion`.
 ///
+/// An implementation can be put behind a `#[cfg(...)]` attribute, which then also applies to the
+/// functions exported for it and to its entry in `RUNTIME_API_VERSIONS`.
+///
 /// # Example
 ///
 /// ```rust
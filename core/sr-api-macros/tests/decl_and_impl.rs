@@ -42,6 +42,10 @@ decl_runtime_apis! {
 		#[changed_in(2)]
 		fn same_name() -> String;
 	}
+
+	pub trait ApiDisabledByCfg {
+		fn disabled();
+	}
 }
 
 impl_runtime_apis! {
@@ -67,6 +71,11 @@ impl_runtime_apis! {
 		fn same_name() {}
 	}
 
+	#[cfg(any())]
+	impl self::ApiDisabledByCfg<Block> for Runtime {
+		fn disabled() {}
+	}
+
 	impl runtime_api::Core<Block> for Runtime {
 		fn version() -> runtime_api::RuntimeVersion {
 			unimplemented!()
@@ -119,3 +128,8 @@ fn check_runtime_api_versions() {
 	check_runtime_api_versions_contains::<dyn ApiWithCustomVersion<Block>>();
 	check_runtime_api_versions_contains::<dyn runtime_api::Core<Block>>();
 }
+
+#[test]
+fn apis_disabled_by_cfg_are_not_implemented() {
+	assert!(RUNTIME_API_VERSIONS.iter().all(|v| v.0 != ApiDisabledByCfg::<Block>::ID));
+}
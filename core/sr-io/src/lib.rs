@@ -107,6 +107,12 @@ export_api! {
 		/// Set the storage of some particular key to Some value.
 		fn set_storage(key: &[u8], value: &[u8]);
 
+		/// Append the SCALE-encoded `value` to the encoded `Vec` stored at `key`.
+		///
+		/// The stored items are not decoded, which makes appending to a long `Vec` cheap. A
+		/// missing or invalid value is replaced by a `Vec` containing only `value`.
+		fn storage_append(key: &[u8], value: &[u8]);
+
 		/// Set the child storage of some particular key to Some value.
 		fn set_child_storage(storage_key: &[u8], key: &[u8], value: &[u8]);
 
@@ -119,6 +125,14 @@ export_api! {
 		/// Clear an entire child storage.
 		fn kill_child_storage(storage_key: &[u8]);
 
+		/// Clear at most `limit` entries of a child storage.
+		///
+		/// Lets a runtime delete a large child storage across multiple blocks. Returns
+		/// whether the deletion completed and how many keys were removed by this call. The keys
+		/// removed by a previous call in the same block are counted again, so it should be called
+		/// at most once per block for a given child storage.
+		fn kill_child_storage_limited(storage_key: &[u8], limit: u32) -> (bool, u32);
+
 		/// Check whether a given `key` exists in storage.
 		fn exists_storage(key: &[u8]) -> bool;
 
@@ -131,6 +145,23 @@ export_api! {
 		/// Clear the child storage entries with a key that starts with the given prefix.
 		fn clear_child_prefix(storage_key: &[u8], prefix: &[u8]);
 
+		/// Start a storage transaction nested within the open ones.
+		///
+		/// The storage changes made until the matching `storage_rollback_transaction` are
+		/// reverted by it, while `storage_commit_transaction` keeps them in the enclosing
+		/// transaction.
+		fn storage_start_transaction();
+
+		/// Revert the storage changes made within the innermost open transaction, and close it.
+		///
+		/// Returns `Err(())` if there is no open transaction.
+		fn storage_rollback_transaction() -> Result<(), ()>;
+
+		/// Keep the storage changes made within the innermost open transaction, and close it.
+		///
+		/// Returns `Err(())` if there is no open transaction.
+		fn storage_commit_transaction() -> Result<(), ()>;
+
 		/// "Commit" all existing operations and compute the resultant storage root.
 		fn storage_root() -> [u8; 32];
 
@@ -372,10 +403,23 @@ export_api! {
 	}
 }
 
+export_api! {
+	pub(crate) trait BenchmarkingApi {
+		/// Get the current time of the host in nanoseconds since the UNIX epoch.
+		fn benchmark_current_time() -> u64;
+
+		/// Commit all storage changes made so far, so that `benchmark_wipe_db` keeps them.
+		fn benchmark_commit_db();
+
+		/// Discard all storage changes made since the last `benchmark_commit_db`.
+		fn benchmark_wipe_db();
+	}
+}
+
 /// API trait that should cover all other APIs.
 ///
 /// Implement this to make sure you implement all APIs.
-trait Api: StorageApi + OtherApi + CryptoApi + HashingApi + OffchainApi {}
+trait Api: StorageApi + OtherApi + CryptoApi + HashingApi + OffchainApi + BenchmarkingApi {}
 
 mod imp {
 	use super::*;
@@ -74,6 +74,12 @@ impl StorageApi for () {
 		);
 	}
 
+	fn storage_append(key: &[u8], value: &[u8]) {
+		with_externalities(|ext|
+			ext.storage_append(key.to_vec(), value.to_vec())
+		);
+	}
+
 	fn read_child_storage(
 		storage_key: &[u8],
 		key: &[u8],
@@ -120,6 +126,13 @@ impl StorageApi for () {
 		});
 	}
 
+	fn kill_child_storage_limited(storage_key: &[u8], limit: u32) -> (bool, u32) {
+		with_externalities(|ext| {
+			let storage_key = child_storage_key_or_panic(storage_key);
+			ext.kill_child_storage_limited(storage_key, limit)
+		}).expect("kill_child_storage_limited cannot be called outside of an Externalities-provided environment.")
+	}
+
 	fn exists_storage(key: &[u8]) -> bool {
 		with_externalities(|ext|
 			ext.exists_storage(key)
@@ -144,6 +157,21 @@ impl StorageApi for () {
 		});
 	}
 
+	fn storage_start_transaction() {
+		with_externalities(|ext| ext.storage_start_transaction())
+			.expect("storage_start_transaction cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn storage_rollback_transaction() -> Result<(), ()> {
+		with_externalities(|ext| ext.storage_rollback_transaction())
+			.expect("storage_rollback_transaction cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn storage_commit_transaction() -> Result<(), ()> {
+		with_externalities(|ext| ext.storage_commit_transaction())
+			.expect("storage_commit_transaction cannot be called outside of an Externalities-provided environment.")
+	}
+
 	fn storage_root() -> [u8; 32] {
 		with_externalities(|ext|
 			ext.storage_root()
@@ -464,6 +492,25 @@ impl OffchainApi for () {
 	}
 }
 
+impl BenchmarkingApi for () {
+	fn benchmark_current_time() -> u64 {
+		std::time::SystemTime::now()
+			.duration_since(std::time::UNIX_EPOCH)
+			.map(|d| d.as_nanos() as u64)
+			.unwrap_or(0)
+	}
+
+	fn benchmark_commit_db() {
+		with_externalities(|ext| ext.commit())
+			.expect("`benchmark_commit_db` cannot be called outside of an Externalities-provided environment.")
+	}
+
+	fn benchmark_wipe_db() {
+		with_externalities(|ext| ext.wipe())
+			.expect("`benchmark_wipe_db` cannot be called outside of an Externalities-provided environment.")
+	}
+}
+
 impl Api for () {}
 
 /// A set of key value pairs for storage.
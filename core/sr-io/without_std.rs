@@ -171,6 +171,8 @@ pub mod ext {
 
 		/// Set value for key in storage.
 		fn ext_set_storage(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
+		/// Append the encoded value to the encoded `Vec` stored at key.
+		fn ext_storage_append(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
 		/// Remove key and value from storage.
 		fn ext_clear_storage(key_data: *const u8, key_len: u32);
 		/// Checks if the given key exists in the storage.
@@ -189,6 +191,22 @@ pub mod ext {
 			prefix_data: *const u8,
 			prefix_len: u32,
 		);
+		/// Start a storage transaction nested within the open ones.
+		fn ext_storage_start_transaction();
+		/// Revert the storage changes made within the innermost open transaction, and close it.
+		///
+		/// # Returns
+		///
+		/// - `0` if the transaction was rolled back.
+		/// - `1` if there is no open transaction.
+		fn ext_storage_rollback_transaction() -> u32;
+		/// Keep the storage changes made within the innermost open transaction, and close it.
+		///
+		/// # Returns
+		///
+		/// - `0` if the transaction was committed.
+		/// - `1` if there is no open transaction.
+		fn ext_storage_commit_transaction() -> u32;
 		/// Gets the value of the given key from storage.
 		///
 		/// The host allocates the memory for storing the value.
@@ -273,6 +291,21 @@ pub mod ext {
 		fn ext_kill_child_storage(storage_key_data: *const u8, storage_key_len: u32);
 		/// A child storage function.
 		///
+		/// Removes at most `limit` entries of the child storage and writes the number of
+		/// removed keys into `removed_out`.
+		///
+		/// # Returns
+		///
+		/// - `1` if the child storage has been emptied completely.
+		/// - `0` otherwise.
+		fn ext_kill_child_storage_limited(
+			storage_key_data: *const u8,
+			storage_key_len: u32,
+			limit: u32,
+			removed_out: *mut u32,
+		) -> u32;
+		/// A child storage function.
+		///
 		/// See [`ext_get_allocated_storage`] for details.
 		///
 		/// A child storage is used e.g. by a contract.
@@ -580,6 +613,13 @@ pub mod ext {
 			buffer_len: u32,
 			deadline: u64,
 		) -> u32;
+
+		/// Current time of the host in nanoseconds since the UNIX epoch.
+		fn ext_benchmark_current_time() -> u64;
+		/// Commit all storage changes made so far.
+		fn ext_benchmark_commit_db();
+		/// Discard all storage changes made since the last commit.
+		fn ext_benchmark_wipe_db();
 	}
 }
 
@@ -646,6 +686,15 @@ impl StorageApi for () {
 		}
 	}
 
+	fn storage_append(key: &[u8], value: &[u8]) {
+		unsafe {
+			ext_storage_append.get()(
+				key.as_ptr(), key.len() as u32,
+				value.as_ptr(), value.len() as u32
+			);
+		}
+	}
+
 	fn set_child_storage(storage_key: &[u8], key: &[u8], value: &[u8]) {
 		unsafe {
 			ext_set_child_storage.get()(
@@ -708,6 +757,36 @@ impl StorageApi for () {
 		}
 	}
 
+	fn storage_start_transaction() {
+		unsafe {
+			ext_storage_start_transaction.get()()
+		}
+	}
+
+	fn storage_rollback_transaction() -> Result<(), ()> {
+		let ret = unsafe {
+			ext_storage_rollback_transaction.get()()
+		};
+
+		if ret == 0 {
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+
+	fn storage_commit_transaction() -> Result<(), ()> {
+		let ret = unsafe {
+			ext_storage_commit_transaction.get()()
+		};
+
+		if ret == 0 {
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+
 	fn kill_child_storage(storage_key: &[u8]) {
 		unsafe {
 			ext_kill_child_storage.get()(
@@ -717,6 +796,19 @@ impl StorageApi for () {
 		}
 	}
 
+	fn kill_child_storage_limited(storage_key: &[u8], limit: u32) -> (bool, u32) {
+		let mut removed = 0u32;
+		let complete = unsafe {
+			ext_kill_child_storage_limited.get()(
+				storage_key.as_ptr(),
+				storage_key.len() as u32,
+				limit,
+				&mut removed,
+			) != 0
+		};
+		(complete, removed)
+	}
+
 	fn storage_root() -> [u8; 32] {
 		let mut result: [u8; 32] = Default::default();
 		unsafe {
@@ -1238,4 +1330,24 @@ unsafe fn from_raw_parts(ptr: *mut u8, len: u32) -> Option<Vec<u8>> {
 	}
 }
 
+impl BenchmarkingApi for () {
+	fn benchmark_current_time() -> u64 {
+		unsafe {
+			ext_benchmark_current_time.get()()
+		}
+	}
+
+	fn benchmark_commit_db() {
+		unsafe {
+			ext_benchmark_commit_db.get()()
+		}
+	}
+
+	fn benchmark_wipe_db() {
+		unsafe {
+			ext_benchmark_wipe_db.get()()
+		}
+	}
+}
+
 impl Api for () {}
@@ -23,7 +23,7 @@ use rstd::prelude::*;
 
 use crate::ConsensusEngineId;
 use crate::codec::{Decode, Encode, Input, Error};
-use primitives::RuntimeDebug;
+use primitives::{ChangesTrieConfiguration, RuntimeDebug};
 
 /// Generic header digest.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
@@ -97,10 +97,42 @@ pub enum DigestItem<Hash> {
 	/// by runtimes.
 	Seal(ConsensusEngineId, Vec<u8>),
 
+	/// Digest item that contains signal from changes tries manager to the
+	/// native code.
+	ChangesTrieSignal(ChangesTrieSignal),
+
 	/// Some other thing. Unsupported and experimental.
 	Other(Vec<u8>),
 }
 
+/// Available changes trie signals.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub enum ChangesTrieSignal {
+	/// New changes trie configuration is enacted, starting from **next block**.
+	///
+	/// The block that emits this signal will contain changes trie (CT) that covers
+	/// blocks range [BEGIN; current block], where BEGIN is (order matters):
+	/// - LAST_TOP_LEVEL_DIGEST_BLOCK+1 if top level digest CT has ever been created
+	///   using current configuration AND the last top level digest CT has been created
+	///   at block LAST_TOP_LEVEL_DIGEST_BLOCK;
+	/// - LAST_CONFIGURATION_CHANGE_BLOCK+1 if there has been CT configuration change
+	///   before and the last configuration change happened at block
+	///   LAST_CONFIGURATION_CHANGE_BLOCK;
+	/// - 1 otherwise.
+	///
+	/// `None` disables changes tries.
+	NewConfiguration(Option<ChangesTrieConfiguration>),
+}
+
+impl ChangesTrieSignal {
+	/// Try to cast this signal to NewConfiguration.
+	pub fn as_new_configuration(&self) -> Option<&Option<ChangesTrieConfiguration>> {
+		match self {
+			ChangesTrieSignal::NewConfiguration(config) => Some(config),
+		}
+	}
+}
+
 #[cfg(feature = "std")]
 impl<Hash: Encode> serde::Serialize for DigestItem<Hash> {
 	fn serialize<S>(&self, seq: S) -> Result<S::Ok, S::Error> where S: serde::Serializer {
@@ -141,6 +173,9 @@ pub enum DigestItemRef<'a, Hash: 'a> {
 	/// Put a Seal on it. This is only used by native code, and is never seen
 	/// by runtimes.
 	Seal(&'a ConsensusEngineId, &'a Vec<u8>),
+	/// Digest item that contains signal from changes tries manager to the
+	/// native code.
+	ChangesTrieSignal(&'a ChangesTrieSignal),
 	/// Any 'non-system' digest item, opaque to the native code.
 	Other(&'a Vec<u8>),
 }
@@ -156,6 +191,7 @@ pub enum DigestItemType {
 	PreRuntime = 6,
 	Consensus = 4,
 	Seal = 5,
+	ChangesTrieSignal = 7,
 	Other = 0,
 }
 
@@ -181,6 +217,7 @@ impl<Hash> DigestItem<Hash> {
 			DigestItem::PreRuntime(ref v, ref s) => DigestItemRef::PreRuntime(v, s),
 			DigestItem::Consensus(ref v, ref s) => DigestItemRef::Consensus(v, s),
 			DigestItem::Seal(ref v, ref s) => DigestItemRef::Seal(v, s),
+			DigestItem::ChangesTrieSignal(ref s) => DigestItemRef::ChangesTrieSignal(s),
 			DigestItem::Other(ref v) => DigestItemRef::Other(v),
 		}
 	}
@@ -205,6 +242,11 @@ impl<Hash> DigestItem<Hash> {
 		self.dref().as_seal()
 	}
 
+	/// Returns `Some` if the entry is the `ChangesTrieSignal` entry.
+	pub fn as_changes_trie_signal(&self) -> Option<&ChangesTrieSignal> {
+		self.dref().as_changes_trie_signal()
+	}
+
 	/// Returns Some if `self` is a `DigestItem::Other`.
 	pub fn as_other(&self) -> Option<&[u8]> {
 		match *self {
@@ -253,6 +295,9 @@ impl<Hash: Decode> Decode for DigestItem<Hash> {
 				let vals: (ConsensusEngineId, Vec<u8>) = Decode::decode(input)?;
 				Ok(DigestItem::Seal(vals.0, vals.1))
 			},
+			DigestItemType::ChangesTrieSignal => Ok(DigestItem::ChangesTrieSignal(
+				Decode::decode(input)?,
+			)),
 			DigestItemType::Other => Ok(DigestItem::Other(
 				Decode::decode(input)?,
 			)),
@@ -293,6 +338,14 @@ impl<'a, Hash> DigestItemRef<'a, Hash> {
 		}
 	}
 
+	/// Cast this digest item into `ChangesTrieSignal`.
+	pub fn as_changes_trie_signal(&self) -> Option<&'a ChangesTrieSignal> {
+		match *self {
+			DigestItemRef::ChangesTrieSignal(ref changes_trie_signal) => Some(changes_trie_signal),
+			_ => None,
+		}
+	}
+
 	/// Cast this digest item into `PreRuntime`
 	pub fn as_other(&self) -> Option<&'a [u8]> {
 		match *self {
@@ -342,6 +395,10 @@ impl<'a, Hash: Encode> Encode for DigestItemRef<'a, Hash> {
 				DigestItemType::PreRuntime.encode_to(&mut v);
 				(val, data).encode_to(&mut v);
 			},
+			DigestItemRef::ChangesTrieSignal(changes_trie_signal) => {
+				DigestItemType::ChangesTrieSignal.encode_to(&mut v);
+				changes_trie_signal.encode_to(&mut v);
+			},
 			DigestItemRef::Other(val) => {
 				DigestItemType::Other.encode_to(&mut v);
 				val.encode_to(&mut v);
@@ -373,4 +430,18 @@ mod tests {
 			r#"{"logs":["0x0204000000","0x000c010203","0x05746573740c010203"]}"#
 		);
 	}
+
+	#[test]
+	fn changes_trie_signal_roundtrips() {
+		let item = DigestItem::<u32>::ChangesTrieSignal(ChangesTrieSignal::NewConfiguration(
+			Some(ChangesTrieConfiguration::new(4, 2)),
+		));
+		let encoded = item.encode();
+		assert_eq!(encoded, vec![7, 0, 1, 4, 0, 0, 0, 2, 0, 0, 0]);
+		assert_eq!(DigestItem::<u32>::decode(&mut &encoded[..]).unwrap(), item);
+		assert_eq!(
+			item.as_changes_trie_signal(),
+			Some(&ChangesTrieSignal::NewConfiguration(Some(ChangesTrieConfiguration::new(4, 2)))),
+		);
+	}
 }
@@ -33,7 +33,7 @@ pub use self::checked_extrinsic::CheckedExtrinsic;
 pub use self::header::Header;
 pub use self::block::{Block, SignedBlock, BlockId};
 pub use self::digest::{
-	Digest, DigestItem, DigestItemRef, OpaqueDigestItemId
+	Digest, DigestItem, DigestItemRef, OpaqueDigestItemId, ChangesTrieSignal,
 };
 
 use crate::codec::Encode;
@@ -1164,6 +1164,37 @@ macro_rules! impl_opaque_keys {
 				};
 				$crate::codec::Encode::encode(&keys)
 			}
+
+			/// Converts `Self` into a `Vec` of `(raw public key, KeyTypeId)`.
+			pub fn into_raw_public_keys(
+				self,
+			) -> $crate::rstd::vec::Vec<($crate::rstd::vec::Vec<u8>, $crate::KeyTypeId)> {
+				let mut keys = $crate::rstd::vec::Vec::new();
+				$(
+					keys.push((
+						$crate::rstd::convert::AsRef::<[u8]>::as_ref(&self.$field).to_vec(),
+						<
+							<
+								$type as $crate::BoundToRuntimeAppPublic
+							>::Public as $crate::RuntimeAppPublic
+						>::ID,
+					));
+				)*
+
+				keys
+			}
+
+			/// Decode `Self` from the given `encoded` slice and convert `Self` into the raw public
+			/// keys (see [`Self::into_raw_public_keys`]).
+			///
+			/// Returns `None` when the decoding failed, otherwise `Some(_)`.
+			pub fn decode_into_raw_public_keys(
+				encoded: &[u8],
+			) -> Option<$crate::rstd::vec::Vec<($crate::rstd::vec::Vec<u8>, $crate::KeyTypeId)>> {
+				<Self as $crate::codec::Decode>::decode(&mut &encoded[..])
+					.ok()
+					.map(|s| s.into_raw_public_keys())
+			}
 		}
 
 		impl $crate::traits::OpaqueKeys for $name {
@@ -23,6 +23,13 @@ use crate::RuntimeDebug;
 /// Priority for a transaction. Additive. Higher is better.
 pub type TransactionPriority = u64;
 
+/// Priority of operational transactions, like heartbeats or misbehavior reports.
+///
+/// The transaction pool and block authorship recognize operational transactions by this priority,
+/// so it is reserved to them: adding the priorities of other transactions in
+/// `ValidTransaction::combine_with` stops right below it.
+pub const OPERATIONAL_PRIORITY: TransactionPriority = TransactionPriority::max_value();
+
 /// Minimum number of blocks a transaction will remain valid for.
 /// `TransactionLongevity::max_value()` means "forever".
 pub type TransactionLongevity = u64;
@@ -180,6 +187,9 @@ pub struct ValidTransaction {
 	///
 	/// Priority determines the ordering of two transactions that have all
 	/// their dependencies (required tags) satisfied.
+	///
+	/// `OPERATIONAL_PRIORITY` marks operational transactions. The pool evicts them last, and
+	/// block authorship keeps including them once the block is full of normal transactions.
 	pub priority: TransactionPriority,
 	/// Transaction dependencies
 	///
@@ -222,9 +232,16 @@ impl ValidTransaction {
 	/// Combine two instances into one, as a best effort. This will take the superset of each of the
 	/// `provides` and `requires` tags, it will sum the priorities, take the minimum longevity and
 	/// the logic *And* of the propagate flags.
+	///
+	/// The sum of the priorities only reaches `OPERATIONAL_PRIORITY` if one of them is operational.
 	pub fn combine_with(mut self, mut other: ValidTransaction) -> Self {
+		let priority = if self.priority == OPERATIONAL_PRIORITY || other.priority == OPERATIONAL_PRIORITY {
+			OPERATIONAL_PRIORITY
+		} else {
+			self.priority.saturating_add(other.priority).min(OPERATIONAL_PRIORITY - 1)
+		};
 		ValidTransaction {
-			priority: self.priority.saturating_add(other.priority),
+			priority,
 			requires: { self.requires.append(&mut other.requires); self.requires },
 			provides: { self.provides.append(&mut other.provides); self.provides },
 			longevity: self.longevity.min(other.longevity),
@@ -256,4 +273,23 @@ mod tests {
 		// decode back
 		assert_eq!(TransactionValidity::decode(&mut &*encoded), Ok(v));
 	}
+
+	#[test]
+	fn should_reserve_the_operational_priority() {
+		let with_priority = |priority| ValidTransaction { priority, ..Default::default() };
+
+		assert_eq!(with_priority(2).combine_with(with_priority(3)).priority, 5);
+		assert_eq!(
+			with_priority(OPERATIONAL_PRIORITY - 1).combine_with(with_priority(1)).priority,
+			OPERATIONAL_PRIORITY - 1,
+		);
+		assert_eq!(
+			with_priority(OPERATIONAL_PRIORITY).combine_with(with_priority(1)).priority,
+			OPERATIONAL_PRIORITY,
+		);
+		assert_eq!(
+			with_priority(0).combine_with(with_priority(OPERATIONAL_PRIORITY)).priority,
+			OPERATIONAL_PRIORITY,
+		);
+	}
 }
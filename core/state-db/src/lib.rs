@@ -331,6 +331,32 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 		}
 	}
 
+	/// Revert the canonicalization of the last canonicalized block, making its parent the last
+	/// canonicalized block. The non-canonical blocks must have been reverted first, and the state
+	/// of the parent must not have been pruned.
+	/// Returns a database commit or `None` if not possible.
+	/// For archive an empty commit set is returned.
+	pub fn revert_canonical_one(&mut self, hash: &BlockHash, parent: (BlockHash, u64)) -> Option<CommitSet<Key>> {
+		match self.mode {
+			PruningMode::ArchiveAll => {
+				Some(CommitSet::default())
+			},
+			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+				if !self.non_canonical.can_revert_canonical(hash) || self.is_pruned(&parent.0, parent.1) {
+					return None;
+				}
+				let mut commit = match &mut self.pruning {
+					Some(pruning) => pruning.revert_last(hash)?,
+					None => CommitSet::default(),
+				};
+				let canonical = self.non_canonical.revert_canonical(hash, parent)
+					.expect("can_revert_canonical is checked above; qed");
+				commit.meta.inserted.extend(canonical.meta.inserted);
+				Some(commit)
+			},
+		}
+	}
+
 	pub fn pin(&mut self, hash: &BlockHash) -> Result<(), PinError> {
 		match self.mode {
 			PruningMode::ArchiveAll => Ok(()),
@@ -447,6 +473,15 @@ impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 		self.db.write().revert_one()
 	}
 
+	/// Revert the canonicalization of the last canonicalized block, making `parent` the last
+	/// canonicalized block. Only possible once all the non-canonical blocks are reverted, and if
+	/// the state of the parent isn't pruned.
+	/// Returns a database commit or `None` if not possible.
+	/// For archive an empty commit set is returned.
+	pub fn revert_canonical_one(&self, hash: &BlockHash, parent: (BlockHash, u64)) -> Option<CommitSet<Key>> {
+		self.db.write().revert_canonical_one(hash, parent)
+	}
+
 	/// Returns last finalized block number.
 	pub fn best_canonical(&self) -> Option<u64> {
 		return self.db.read().best_canonical()
@@ -576,6 +611,35 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[21, 3, 922, 93, 94])));
 	}
 
+	#[test]
+	fn revert_canonical_blocks() {
+		let (mut db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(2),
+			max_mem: None,
+		}));
+		let (h1, h21, h3) = (H256::from_low_u64_be(1), H256::from_low_u64_be(21), H256::from_low_u64_be(3));
+
+		// the non-canonical blocks must be reverted first.
+		assert!(sdb.revert_canonical_one(&h3, (h21, 2)).is_none());
+		db.commit(&sdb.revert_one().unwrap());
+		assert!(sdb.revert_one().is_none());
+
+		db.commit(&sdb.revert_canonical_one(&h3, (h21, 2)).unwrap());
+		assert_eq!(sdb.best_canonical(), Some(2));
+		assert!(!sdb.is_pruned(&h21, 2));
+		// the state of block 1 is pruned.
+		assert!(sdb.revert_canonical_one(&h21, (h1, 1)).is_none());
+
+		// blocks can be imported on top of the new last canonical block.
+		db.commit(
+			&sdb.insert_block::<io::Error>(&H256::from_low_u64_be(5), 3, &h21, make_changeset(&[5], &[]))
+				.unwrap(),
+		);
+		sdb.apply_pending();
+		assert_eq!(sdb.best_canonical(), Some(2));
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
+	}
+
 	#[test]
 	fn prune_window_2() {
 		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
@@ -378,6 +378,27 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 			&& !self.pending_canonicalizations.contains(hash)
 	}
 
+	/// Revert the canonicalization of the last canonicalized block, which must be the given block,
+	/// making `parent` the last canonicalized block. Only possible when there are no non-canonical
+	/// blocks. Returns a commit set that updates the journal or `None` if not possible.
+	pub fn revert_canonical(&mut self, hash: &BlockHash, parent: (BlockHash, u64)) -> Option<CommitSet<Key>> {
+		if !self.can_revert_canonical(hash) {
+			return None;
+		}
+		let mut commit = CommitSet::default();
+		commit.meta.inserted.push((to_meta_key(LAST_CANONICAL, &()), parent.encode()));
+		self.last_canonicalized = Some(parent);
+		Some(commit)
+	}
+
+	/// Can the canonicalization of the given block be reverted?
+	pub fn can_revert_canonical(&self, hash: &BlockHash) -> bool {
+		self.levels.is_empty() &&
+			self.pending_canonicalizations.is_empty() &&
+			self.pending_insertions.is_empty() &&
+			self.last_canonicalized.as_ref().map_or(false, |(h, n)| h == hash && *n > 0)
+	}
+
 	/// Revert a single level. Returns commit set that deletes the journal or `None` if not possible.
 	pub fn revert_one(&mut self) -> Option<CommitSet<Key>> {
 		self.levels.pop_back().map(|level| {
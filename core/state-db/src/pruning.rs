@@ -153,6 +153,28 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 		}
 	}
 
+	/// Remove the last block of the window, which must be the given block and must not be pruned.
+	/// The keys it deleted are kept in the DB. Returns a commit removing its journal record or
+	/// `None` if not possible.
+	///
+	/// The keys the block re-inserted aren't put back in the death rows of the previous blocks, so
+	/// they will never be pruned.
+	pub fn revert_last(&mut self, hash: &BlockHash) -> Option<CommitSet<Key>> {
+		if self.death_rows.len() <= self.pending_prunings ||
+			self.death_rows.back().map_or(true, |row| row.hash != *hash)
+		{
+			return None;
+		}
+		let reverted = self.death_rows.pop_back().expect("death_rows is not empty; qed");
+		trace!(target: "state-db", "Reverting {:?} ({} deleted)", reverted.hash, reverted.deleted.len());
+		for k in reverted.deleted.iter() {
+			self.death_index.remove(k);
+		}
+		let mut commit = CommitSet::default();
+		commit.meta.deleted.push(reverted.journal_key);
+		Some(commit)
+	}
+
 	/// Add a change set to the window. Creates a journal record and pushes it to `commit`
 	pub fn note_canonical(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) {
 		trace!(target: "state-db", "Adding to pruning window: {:?} ({} inserted, {} deleted)", hash, commit.data.inserted.len(), commit.data.deleted.len());
@@ -263,6 +285,35 @@ mod tests {
 		assert_eq!(pruning.pending_number, 1);
 	}
 
+	#[test]
+	fn revert_last() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db).unwrap();
+		let (h1, h2) = (H256::random(), H256::random());
+		let mut commit = make_commit(&[4], &[1]);
+		pruning.note_canonical(&h1, &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[5], &[2]);
+		pruning.note_canonical(&h2, &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		assert!(pruning.revert_last(&h1).is_none());
+		let commit = pruning.revert_last(&h2).unwrap();
+		db.commit(&commit);
+		assert!(!pruning.have_block(&h2));
+		assert!(pruning.have_block(&h1));
+		check_journal(&pruning, &db);
+
+		// the keys deleted by the reverted block are kept.
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[2, 3, 4, 5])));
+		assert!(pruning.revert_last(&h1).is_none());
+	}
+
 	#[test]
 	fn prune_two() {
 		let mut db = make_db(&[1, 2, 3]);
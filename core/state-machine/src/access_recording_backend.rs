@@ -0,0 +1,231 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State backend wrapper recording the storage accesses.
+
+use std::mem;
+use hash_db::Hasher;
+use parking_lot::Mutex;
+use crate::backend::Backend;
+use crate::overlayed_changes::OverlayedChanges;
+
+/// Kind of a storage access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageAccessKind {
+	/// The value was read from the state.
+	Read,
+	/// The value was set or removed.
+	Write,
+}
+
+/// An access to a storage key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageAccess {
+	/// The child storage the key belongs to, `None` for the top-level storage.
+	pub child_storage_key: Option<Vec<u8>>,
+	/// The accessed key.
+	pub key: Vec<u8>,
+	/// Kind of the access.
+	pub kind: StorageAccessKind,
+	/// Size of the value read or written, `None` if there is no value.
+	pub value_size: Option<u32>,
+}
+
+impl StorageAccess {
+	/// Returns the writes of the prospective changes of the given overlay.
+	///
+	/// The writes are returned in no particular order.
+	pub fn prospective_writes(overlay: &OverlayedChanges) -> Vec<StorageAccess> {
+		let top = overlay.prospective.top.iter()
+			.map(|(key, value)| (None, key, value));
+		let children = overlay.prospective.children.iter()
+			.flat_map(|(storage_key, map)| map.iter().map(move |(key, value)| (Some(storage_key), key, value)));
+		top.chain(children)
+			.map(|(child_storage_key, key, value)| StorageAccess {
+				child_storage_key: child_storage_key.cloned(),
+				key: key.clone(),
+				kind: StorageAccessKind::Write,
+				value_size: value.value.as_ref().map(|value| value.len() as u32),
+			})
+			.collect()
+	}
+}
+
+/// Wraps a state backend and records the storage reads made through it.
+///
+/// Only direct reads of keys are recorded, iteration over keys isn't. Reads served by the
+/// overlayed changes don't reach the backend, and aren't recorded either.
+#[derive(Debug)]
+pub struct AccessRecordingBackend<'a, S> {
+	backend: &'a S,
+	accesses: Mutex<Vec<StorageAccess>>,
+}
+
+impl<'a, S> AccessRecordingBackend<'a, S> {
+	/// Wrap the given backend.
+	pub fn new(backend: &'a S) -> Self {
+		AccessRecordingBackend {
+			backend,
+			accesses: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// Returns the reads recorded since the last call, in the order they were made.
+	pub fn take_accesses(&self) -> Vec<StorageAccess> {
+		mem::replace(&mut *self.accesses.lock(), Vec::new())
+	}
+
+	fn record<E>(
+		&self,
+		child_storage_key: Option<&[u8]>,
+		key: &[u8],
+		value: Result<Option<Vec<u8>>, E>,
+	) -> Result<Option<Vec<u8>>, E> {
+		if let Ok(value) = &value {
+			self.accesses.lock().push(StorageAccess {
+				child_storage_key: child_storage_key.map(|k| k.to_vec()),
+				key: key.to_vec(),
+				kind: StorageAccessKind::Read,
+				value_size: value.as_ref().map(|value| value.len() as u32),
+			});
+		}
+		value
+	}
+}
+
+impl<'a, S: Backend<H>, H: Hasher> Backend<H> for AccessRecordingBackend<'a, S> {
+	type Error = S::Error;
+	type Transaction = S::Transaction;
+	type TrieBackendStorage = S::TrieBackendStorage;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.record(None, key, self.backend.storage(key))
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.record(Some(storage_key), key, self.backend.child_storage(storage_key, key))
+	}
+
+	fn for_keys_in_child_storage<F: FnMut(&[u8])>(&self, storage_key: &[u8], f: F) {
+		self.backend.for_keys_in_child_storage(storage_key, f)
+	}
+
+	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
+		self.backend.for_keys_with_prefix(prefix, f)
+	}
+
+	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], f: F) {
+		self.backend.for_key_values_with_prefix(prefix, f)
+	}
+
+	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(&self, storage_key: &[u8], prefix: &[u8], f: F) {
+		self.backend.for_child_keys_with_prefix(storage_key, prefix, f)
+	}
+
+	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
+	where
+		I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+		H::Out: Ord,
+	{
+		self.backend.storage_root(delta)
+	}
+
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> (Vec<u8>, bool, Self::Transaction)
+	where
+		I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+		H::Out: Ord,
+	{
+		self.backend.child_storage_root(storage_key, delta)
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.backend.pairs()
+	}
+
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		self.backend.keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		self.backend.child_keys_paged(child_storage_key, prefix, start_key, count)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::Blake2Hasher;
+	use crate::backend::InMemory;
+
+	#[test]
+	fn records_reads() {
+		let backend: InMemory<Blake2Hasher> = vec![(b"key".to_vec(), b"value".to_vec())]
+			.into_iter()
+			.collect::<std::collections::HashMap<_, _>>()
+			.into();
+		let recording = AccessRecordingBackend::new(&backend);
+
+		assert_eq!(recording.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(recording.storage(b"missing").unwrap(), None);
+		assert_eq!(recording.take_accesses(), vec![
+			StorageAccess {
+				child_storage_key: None,
+				key: b"key".to_vec(),
+				kind: StorageAccessKind::Read,
+				value_size: Some(5),
+			},
+			StorageAccess {
+				child_storage_key: None,
+				key: b"missing".to_vec(),
+				kind: StorageAccessKind::Read,
+				value_size: None,
+			},
+		]);
+		assert!(recording.take_accesses().is_empty());
+	}
+
+	#[test]
+	fn prospective_writes_are_listed() {
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(b"committed".to_vec(), Some(vec![1]));
+		overlay.commit_prospective();
+		overlay.set_storage(b"key".to_vec(), Some(vec![1, 2, 3]));
+		overlay.set_storage(b"removed".to_vec(), None);
+
+		let mut writes = StorageAccess::prospective_writes(&overlay);
+		writes.sort_by(|a, b| a.key.cmp(&b.key));
+		assert_eq!(writes, vec![
+			StorageAccess {
+				child_storage_key: None,
+				key: b"key".to_vec(),
+				kind: StorageAccessKind::Write,
+				value_size: Some(3),
+			},
+			StorageAccess {
+				child_storage_key: None,
+				key: b"removed".to_vec(),
+				kind: StorageAccessKind::Write,
+				value_size: None,
+			},
+		]);
+	}
+}
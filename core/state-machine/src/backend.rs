@@ -117,6 +117,36 @@ pub trait Backend<H: Hasher>: std::fmt::Debug {
 		all
 	}
 
+	/// Get at most `count` keys with given prefix, in lexicographic order, strictly after
+	/// `start_key` if given.
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		let mut keys: Vec<_> = self.keys(prefix)
+			.into_iter()
+			.filter(|key| start_key.map_or(true, |start| &key[..] > start))
+			.collect();
+		keys.sort();
+		keys.truncate(count);
+		keys
+	}
+
+	/// Get at most `count` keys of child storage with given prefix, in lexicographic order, strictly
+	/// after `start_key` if given.
+	fn child_keys_paged(
+		&self,
+		child_storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		let mut keys: Vec<_> = self.child_keys(child_storage_key, prefix)
+			.into_iter()
+			.filter(|key| start_key.map_or(true, |start| &key[..] > start))
+			.collect();
+		keys.sort();
+		keys.truncate(count);
+		keys
+	}
+
 	/// Try convert into trie backend.
 	fn as_trie_backend(&mut self) -> Option<&TrieBackend<Self::TrieBackendStorage, H>> {
 		None
@@ -202,6 +232,20 @@ impl<'a, T: Backend<H>, H: Hasher> Backend<H> for &'a T {
 		(*self).pairs()
 	}
 
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		(*self).keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		(*self).child_keys_paged(child_storage_key, prefix, start_key, count)
+	}
+
 	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], f: F) {
 		(*self).for_key_values_with_prefix(prefix, f);
 	}
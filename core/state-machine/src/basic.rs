@@ -17,7 +17,7 @@
 //! Basic implementation for Externalities.
 
 use std::{collections::HashMap, any::{TypeId, Any}, iter::FromIterator};
-use crate::backend::{Backend, InMemory};
+use crate::{backend::{Backend, InMemory}, ext::StorageAppend};
 use hash_db::Hasher;
 use trie::{TrieConfiguration, default_child_trie_root};
 use trie::trie_types::Layout;
@@ -32,6 +32,8 @@ use log::warn;
 pub struct BasicExternalities {
 	top: HashMap<Vec<u8>, Vec<u8>>,
 	children: HashMap<Vec<u8>, HashMap<Vec<u8>, Vec<u8>>>,
+	/// Storage at the start of each open transaction, innermost last.
+	transactions: Vec<(HashMap<Vec<u8>, Vec<u8>>, HashMap<Vec<u8>, HashMap<Vec<u8>, Vec<u8>>>)>,
 }
 
 impl BasicExternalities {
@@ -43,6 +45,7 @@ impl BasicExternalities {
 		BasicExternalities {
 			top,
 			children,
+			transactions: Vec::new(),
 		}
 	}
 
@@ -83,6 +86,7 @@ impl From<HashMap<Vec<u8>, Vec<u8>>> for BasicExternalities {
 		BasicExternalities {
 			top: hashmap,
 			children: Default::default(),
+			transactions: Vec::new(),
 		}
 	}
 }
@@ -132,6 +136,15 @@ impl Externalities for BasicExternalities {
 		}
 	}
 
+	fn storage_append(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		if is_child_storage_key(&key) {
+			warn!(target: "trie", "Refuse to append to child storage key via main storage");
+			return;
+		}
+
+		StorageAppend::new(self.top.entry(key).or_default()).append(&value);
+	}
+
 	fn place_child_storage(
 		&mut self,
 		storage_key: ChildStorageKey,
@@ -150,6 +163,24 @@ impl Externalities for BasicExternalities {
 		self.children.remove(storage_key.as_ref());
 	}
 
+	fn kill_child_storage_limited(&mut self, storage_key: ChildStorageKey, limit: u32) -> (bool, u32) {
+		let (complete, removed) = match self.children.get_mut(storage_key.as_ref()) {
+			Some(child) => {
+				let keys: Vec<_> = child.keys().take(limit as usize).cloned().collect();
+				for key in &keys {
+					child.remove(key);
+				}
+				(child.is_empty(), keys.len() as u32)
+			},
+			None => (true, 0),
+		};
+
+		if complete {
+			self.children.remove(storage_key.as_ref());
+		}
+		(complete, removed)
+	}
+
 	fn clear_prefix(&mut self, prefix: &[u8]) {
 		if is_child_storage_key(prefix) {
 			warn!(
@@ -205,6 +236,25 @@ impl Externalities for BasicExternalities {
 	fn storage_changes_root(&mut self, _parent: H256) -> Result<Option<H256>, ()> {
 		Ok(None)
 	}
+
+	fn commit(&mut self) {}
+
+	fn wipe(&mut self) {}
+
+	fn storage_start_transaction(&mut self) {
+		self.transactions.push((self.top.clone(), self.children.clone()));
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		let (top, children) = self.transactions.pop().ok_or(())?;
+		self.top = top;
+		self.children = children;
+		Ok(())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.transactions.pop().map(|_| ()).ok_or(())
+	}
 }
 
 impl externalities::ExtensionStore for BasicExternalities {
@@ -269,6 +319,29 @@ mod tests {
 		assert_eq!(ext.child_storage(child(), b"doe"), None);
 	}
 
+	#[test]
+	fn kill_child_storage_limited_works() {
+		let child_storage = b":child_storage:default:test".to_vec();
+
+		let mut ext = BasicExternalities::new(
+			Default::default(),
+			map![
+				child_storage.clone() => map![
+					b"doe".to_vec() => b"reindeer".to_vec(),
+					b"dog".to_vec() => b"puppy".to_vec(),
+					b"hello".to_vec() => b"world".to_vec()
+				]
+			]
+		);
+
+		let child = || ChildStorageKey::from_vec(child_storage.clone()).unwrap();
+
+		assert_eq!(ext.kill_child_storage_limited(child(), 2), (false, 2));
+		assert_eq!(ext.kill_child_storage_limited(child(), 2), (true, 1));
+		assert_eq!(ext.kill_child_storage_limited(child(), 2), (true, 0));
+		assert!(ext.into_storages().1.is_empty());
+	}
+
 	#[test]
 	fn basic_externalities_is_empty() {
 		// Make sure no values are set by default in `BasicExternalities`.
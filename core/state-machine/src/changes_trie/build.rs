@@ -453,6 +453,7 @@ mod test {
 				].into_iter().collect(),
 			},
 			changes_trie_config: Some(config.clone()),
+			..Default::default()
 		};
 
 		(backend, storage, changes, config)
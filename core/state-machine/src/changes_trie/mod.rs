@@ -71,7 +71,7 @@ use hash_db::{Hasher, Prefix};
 use crate::backend::Backend;
 use num_traits::{One, Zero};
 use codec::{Decode, Encode};
-use primitives;
+use primitives::{self, storage::well_known_keys};
 use crate::changes_trie::build::prepare_input;
 use crate::changes_trie::build_cache::{IncompleteCachedBuildData, IncompleteCacheAction};
 use crate::overlayed_changes::OverlayedChanges;
@@ -141,6 +141,9 @@ pub trait Storage<H: Hasher, Number: BlockNumber>: RootsStorage<H, Number> {
 		root: &H::Out,
 		functor: &mut dyn FnMut(&HashMap<Option<Vec<u8>>, HashSet<Vec<u8>>>),
 	) -> bool;
+	/// Get the zero block of the configuration that is active at the child of the given block,
+	/// i.e. the number of the block at which this configuration has been set.
+	fn configuration_zero(&self, parent: &AnchorBlockId<H::Out, Number>) -> Result<Number, String>;
 	/// Get a trie node.
 	fn get(&self, key: &H::Out, prefix: Prefix) -> Result<Option<DBValue>, String>;
 }
@@ -188,17 +191,25 @@ pub fn build_changes_trie<'a, B: Backend<H>, S: Storage<H, Number>, H: Hasher, N
 		_ => return Ok(None),
 	};
 
-	// FIXME: remove this in https://github.com/paritytech/substrate/pull/3201
-	let config = ConfigurationRange {
-		config,
-		zero: Zero::zero(),
-		end: None,
-	};
-
 	// build_anchor error should not be considered fatal
 	let parent = storage.build_anchor(parent_hash).map_err(|_| ())?;
 	let block = parent.number.clone() + One::one();
 
+	// the configuration that is active at the beginning of the block is used to build its
+	// changes trie. If the runtime has changed the configuration within this block, then the
+	// block is the last one where the current configuration is active
+	let zero = storage.configuration_zero(&parent).map_err(|_| ())?;
+	let config_changed = match changes.storage(well_known_keys::CHANGES_TRIE_CONFIG) {
+		Some(Some(new_config)) => new_config != &config.encode()[..],
+		Some(None) => true,
+		None => false,
+	};
+	let config = ConfigurationRange {
+		config,
+		zero,
+		end: if config_changed { Some(block.clone()) } else { None },
+	};
+
 	// storage errors are considered fatal (similar to situations when runtime fetches values from storage)
 	let (input_pairs, child_input_pairs, digest_input_blocks) = prepare_input::<B, H, Number>(
 		backend,
@@ -302,6 +313,8 @@ fn prepare_cached_build_data<Number: BlockNumber>(
 
 #[cfg(test)]
 mod tests {
+	use primitives::Blake2Hasher;
+	use crate::backend::InMemory;
 	use super::*;
 
 	#[test]
@@ -333,4 +346,42 @@ mod tests {
 		let config_range = ConfigurationRange { zero: 0, end: Some(4u32), config: &config };
 		assert_eq!(prepare_cached_build_data(config_range.clone(), 4u32), IncompleteCacheAction::Clear);
 	}
+
+	#[test]
+	fn configuration_ends_at_block_where_it_has_been_changed() {
+		let build_cache_action = |new_config: Option<Option<Configuration>>| {
+			let backend = InMemory::<Blake2Hasher>::default();
+			let storage = InMemoryStorage::<Blake2Hasher, u64>::with_blocks(vec![(8, Default::default())]);
+			storage.note_configuration_change(4);
+			let mut changes = OverlayedChanges::default();
+			changes.changes_trie_config = Some(Configuration { digest_interval: 4, digest_levels: 2 });
+			if let Some(new_config) = new_config {
+				changes.set_storage(
+					well_known_keys::CHANGES_TRIE_CONFIG.to_vec(),
+					new_config.map(|config| config.encode()),
+				);
+			}
+
+			build_changes_trie::<_, _, Blake2Hasher, u64>(&backend, Some(&storage), &changes, Default::default())
+				.unwrap().unwrap().2
+		};
+
+		// configuration has been set at block#4 => block#9 is not a digest block
+		// => build data is cached
+		assert!(match build_cache_action(None) {
+			CacheAction::CacheBuildData(_) => true,
+			CacheAction::Clear => false,
+		});
+		// the same configuration is stored again => nothing changes
+		assert!(match build_cache_action(Some(Some(Configuration { digest_interval: 4, digest_levels: 2 }))) {
+			CacheAction::CacheBuildData(_) => true,
+			CacheAction::Clear => false,
+		});
+		// configuration has been changed or disabled => block#9 is the last block of configuration
+		assert_eq!(
+			build_cache_action(Some(Some(Configuration { digest_interval: 8, digest_levels: 1 }))),
+			CacheAction::Clear,
+		);
+		assert_eq!(build_cache_action(Some(None)), CacheAction::Clear);
+	}
 }
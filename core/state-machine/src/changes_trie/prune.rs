@@ -44,25 +44,17 @@ pub fn oldest_non_pruned_trie<Number: BlockNumber>(
 	}
 }
 
-/// Prune obsolete changes tries. Pruning happens at the same block, where highest
-/// level digest is created. Pruning guarantees to save changes tries for last
-/// `min_blocks_to_keep` blocks. We only prune changes tries at `max_digest_interval`
-/// ranges.
-/// Returns MemoryDB that contains all deleted changes tries nodes.
+/// Prune changes tries of all blocks in given range (inclusive from both ends).
+/// The range is selected by the caller, which knows what configurations have been
+/// active at these blocks and how many changes tries must be kept.
+/// Calls `remove_trie_node` for every node of every pruned changes trie.
 pub fn prune<S: Storage<H, Number>, H: Hasher, Number: BlockNumber, F: FnMut(H::Out)>(
-	config: &Configuration,
 	storage: &S,
-	min_blocks_to_keep: Number,
+	first: Number,
+	last: Number,
 	current_block: &AnchorBlockId<H::Out, Number>,
 	mut remove_trie_node: F,
 ) {
-
-	// select range for pruning
-	let (first, last) = match pruning_range(config, min_blocks_to_keep, current_block.number.clone()) {
-		Some((first, last)) => (first, last),
-		None => return,
-	};
-
 	// delete changes trie for every block in range
 	// FIXME: limit `max_digest_interval` so that this cycle won't involve huge ranges
 	let mut block = first;
@@ -215,13 +207,13 @@ mod tests {
 	}
 
 	fn prune_by_collect<S: Storage<H, u64>, H: Hasher>(
-		config: &Configuration,
 		storage: &S,
-		min_blocks_to_keep: u64,
+		first: u64,
+		last: u64,
 		current_block: u64,
 	) -> HashSet<H::Out> {
 		let mut pruned_trie_nodes = HashSet::new();
-		prune(config, storage, min_blocks_to_keep, &AnchorBlockId { hash: Default::default(), number: current_block },
+		prune(storage, first, last, &AnchorBlockId { hash: Default::default(), number: current_block },
 			|node| { pruned_trie_nodes.insert(node); });
 		pruned_trie_nodes
 	}
@@ -253,47 +245,21 @@ mod tests {
 			storage
 		}
 
-		// l1-digest is created every 2 blocks
-		// l2-digest is created every 4 blocks
-		// we do not want to keep any additional changes tries
-		// => only one l2-digest is saved AND it is pruned once next is created
-		let config = Configuration { digest_interval: 2, digest_levels: 2 };
+		// when there are no changes tries in the range => nothing is pruned
 		let storage = prepare_storage();
-		assert!(prune_by_collect(&config, &storage, 0, 69).is_empty());
-		assert!(prune_by_collect(&config, &storage, 0, 70).is_empty());
-		assert!(prune_by_collect(&config, &storage, 0, 71).is_empty());
-		let non_empty = prune_by_collect(&config, &storage, 0, 72);
-		assert!(!non_empty.is_empty());
-		storage.remove_from_storage(&non_empty);
-		assert!(storage.into_mdb().drain().is_empty());
+		assert!(prune_by_collect(&storage, 20, 30, 90).is_empty());
+		assert!(!storage.into_mdb().drain().is_empty());
 
-		// l1-digest is created every 2 blocks
-		// l2-digest is created every 4 blocks
-		// we want keep 1 additional changes tries
-		let config = Configuration { digest_interval: 2, digest_levels: 2 };
+		// when range covers some of the changes tries => only these are pruned
 		let storage = prepare_storage();
-		assert!(prune_by_collect(&config, &storage, 8, 69).is_empty());
-		assert!(prune_by_collect(&config, &storage, 8, 70).is_empty());
-		assert!(prune_by_collect(&config, &storage, 8, 71).is_empty());
-		assert!(prune_by_collect(&config, &storage, 8, 72).is_empty());
-		assert!(prune_by_collect(&config, &storage, 8, 73).is_empty());
-		assert!(prune_by_collect(&config, &storage, 8, 74).is_empty());
-		assert!(prune_by_collect(&config, &storage, 8, 75).is_empty());
-		let non_empty = prune_by_collect(&config, &storage, 8, 76);
+		let non_empty = prune_by_collect(&storage, 60, 66, 90);
 		assert!(!non_empty.is_empty());
 		storage.remove_from_storage(&non_empty);
-		assert!(storage.into_mdb().drain().is_empty());
+		assert!(!storage.into_mdb().drain().is_empty());
 
-		// l1-digest is created every 2 blocks
-		// we want keep 2 additional changes tries
-		let config = Configuration { digest_interval: 2, digest_levels: 1 };
+		// when range covers all changes tries (including child tries) => everything is pruned
 		let storage = prepare_storage();
-		assert!(prune_by_collect(&config, &storage, 4, 69).is_empty());
-		let non_empty = prune_by_collect(&config, &storage, 4, 70);
-		assert!(!non_empty.is_empty());
-		storage.remove_from_storage(&non_empty);
-		assert!(prune_by_collect(&config, &storage, 4, 71).is_empty());
-		let non_empty = prune_by_collect(&config, &storage, 4, 72);
+		let non_empty = prune_by_collect(&storage, 60, 70, 90);
 		assert!(!non_empty.is_empty());
 		storage.remove_from_storage(&non_empty);
 		assert!(storage.into_mdb().drain().is_empty());
@@ -16,8 +16,9 @@
 
 //! Changes trie storage utilities.
 
-use std::collections::{BTreeMap, HashSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashSet, HashMap};
 use hash_db::{Hasher, Prefix, EMPTY_PREFIX};
+use num_traits::Zero;
 use trie::DBValue;
 use trie::MemoryDB;
 use parking_lot::RwLock;
@@ -44,6 +45,7 @@ pub struct TrieBackendAdapter<'a, H: Hasher, Number: BlockNumber> {
 struct InMemoryStorageData<H: Hasher, Number: BlockNumber> {
 	roots: BTreeMap<Number, H::Out>,
 	mdb: MemoryDB<H>,
+	configuration_zeros: BTreeSet<Number>,
 }
 
 impl<H: Hasher, Number: BlockNumber> InMemoryStorage<H, Number> {
@@ -53,6 +55,7 @@ impl<H: Hasher, Number: BlockNumber> InMemoryStorage<H, Number> {
 			data: RwLock::new(InMemoryStorageData {
 				roots: BTreeMap::new(),
 				mdb,
+				configuration_zeros: BTreeSet::new(),
 			}),
 			cache: BuildCache::new(),
 		}
@@ -85,6 +88,7 @@ impl<H: Hasher, Number: BlockNumber> InMemoryStorage<H, Number> {
 			data: RwLock::new(InMemoryStorageData {
 				roots: blocks.into_iter().collect(),
 				mdb: MemoryDB::default(),
+				configuration_zeros: BTreeSet::new(),
 			}),
 			cache: BuildCache::new(),
 		}
@@ -127,6 +131,7 @@ impl<H: Hasher, Number: BlockNumber> InMemoryStorage<H, Number> {
 			data: RwLock::new(InMemoryStorageData {
 				roots,
 				mdb,
+				configuration_zeros: BTreeSet::new(),
 			}),
 			cache: BuildCache::new(),
 		}
@@ -156,6 +161,11 @@ impl<H: Hasher, Number: BlockNumber> InMemoryStorage<H, Number> {
 		data.roots.insert(block, changes_trie_root);
 		data.mdb.consolidate(trie);
 	}
+
+	/// Note that the changes trie configuration has been changed at given block.
+	pub fn note_configuration_change(&self, block: Number) {
+		self.data.write().configuration_zeros.insert(block);
+	}
 }
 
 impl<H: Hasher, Number: BlockNumber> RootsStorage<H, Number> for InMemoryStorage<H, Number> {
@@ -184,6 +194,13 @@ impl<H: Hasher, Number: BlockNumber> Storage<H, Number> for InMemoryStorage<H, N
 		self.cache.with_changed_keys(root, functor)
 	}
 
+	fn configuration_zero(&self, parent: &AnchorBlockId<H::Out, Number>) -> Result<Number, String> {
+		Ok(self.data.read().configuration_zeros.range(..=parent.number.clone())
+			.next_back()
+			.cloned()
+			.unwrap_or_else(Zero::zero))
+	}
+
 	fn get(&self, key: &H::Out, prefix: Prefix) -> Result<Option<DBValue>, String> {
 		MemoryDB::<H>::get(&self.data.read().mdb, key, prefix)
 	}
@@ -0,0 +1,369 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! Externalities wrapper tracking the storage accesses of the benchmarked calls, used to calibrate
+//! the weights of database reads and writes.
+
+use std::{any::{Any, TypeId}, cell::RefCell, collections::{BTreeMap, HashSet}, mem};
+use hash_db::Hasher;
+use primitives::{storage::ChildStorageKey, traits::Externalities, hash::H256};
+use crate::proving_backend::storage_proof_size;
+use crate::trie_backend::TrieBackend;
+use crate::trie_backend_essence::TrieBackendStorage;
+
+/// A storage key, as `(child storage key, key)`.
+pub type TrackedKey = (Option<Vec<u8>>, Vec<u8>);
+
+/// A storage change, as `(child storage key, key, value)`.
+pub type TrackedChange = (Option<Vec<u8>>, Vec<u8>, Option<Vec<u8>>);
+
+/// The distinct storage accesses made by a single call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbAccessStats {
+	/// Keys read by the call, in the order of the first read. Keys written by the call before
+	/// they were read don't reach the database, and aren't included.
+	pub reads: Vec<TrackedKey>,
+	/// Keys written by the call, in the order of the first write. Removing a child storage or
+	/// the keys with a prefix is counted as a single write of the child storage key or prefix.
+	pub writes: Vec<TrackedKey>,
+	/// Total size of the values read.
+	pub read_bytes: u64,
+	/// Total size of the values written.
+	pub written_bytes: u64,
+	/// Changes committed by the setup of the call, before the call. The call runs on the state
+	/// with the setups of all the calls so far applied, in order. The keys of the state removed
+	/// along with a child storage or a prefix aren't included.
+	pub setup: Vec<TrackedChange>,
+}
+
+impl DbAccessStats {
+	/// Returns the encoded size of the storage proof of the keys read by the call.
+	pub fn proof_size<S, H>(&self, backend: &TrieBackend<S, H>) -> Result<u64, String>
+	where
+		S: TrieBackendStorage<H>,
+		H: Hasher,
+	{
+		storage_proof_size(backend, self.reads.iter().cloned())
+	}
+}
+
+#[derive(Default)]
+struct CallTracker {
+	stats: DbAccessStats,
+	read: HashSet<TrackedKey>,
+	written: HashSet<TrackedKey>,
+}
+
+impl CallTracker {
+	fn read(&mut self, child_storage_key: Option<&[u8]>, key: &[u8], value_size: Option<usize>) {
+		let key = (child_storage_key.map(|k| k.to_vec()), key.to_vec());
+		if self.written.contains(&key) || !self.read.insert(key.clone()) {
+			return;
+		}
+		self.stats.read_bytes += value_size.unwrap_or(0) as u64;
+		self.stats.reads.push(key);
+	}
+
+	fn write(&mut self, child_storage_key: Option<&[u8]>, key: &[u8], value_size: Option<usize>) {
+		let key = (child_storage_key.map(|k| k.to_vec()), key.to_vec());
+		self.stats.written_bytes += value_size.unwrap_or(0) as u64;
+		if self.written.insert(key.clone()) {
+			self.stats.writes.push(key);
+		}
+	}
+}
+
+/// Wraps externalities and tracks the distinct storage keys read and written through them.
+///
+/// The accesses are split into calls the way the benchmarks run them: `Externalities::commit`
+/// starts a new call, dropping the accesses made by the setup code but keeping its changes, and
+/// `Externalities::wipe` ends it.
+pub struct DbAccessTrackingExt<'a, E> {
+	ext: &'a mut E,
+	current: RefCell<CallTracker>,
+	calls: Vec<DbAccessStats>,
+	/// Changes made since the last commit or wipe.
+	pending: BTreeMap<TrackedKey, Option<Vec<u8>>>,
+}
+
+impl<'a, E: Externalities> DbAccessTrackingExt<'a, E> {
+	/// Wrap the given externalities.
+	pub fn new(ext: &'a mut E) -> Self {
+		DbAccessTrackingExt {
+			ext,
+			current: RefCell::new(CallTracker::default()),
+			calls: Vec::new(),
+			pending: BTreeMap::new(),
+		}
+	}
+
+	/// Returns the accesses of every call that has ended so far, in the order of the calls.
+	pub fn take_calls(&mut self) -> Vec<DbAccessStats> {
+		mem::replace(&mut self.calls, Vec::new())
+	}
+
+	fn track_read(&self, child_storage_key: Option<&[u8]>, key: &[u8], value_size: Option<usize>) {
+		self.current.borrow_mut().read(child_storage_key, key, value_size)
+	}
+
+	fn track_write(&self, child_storage_key: Option<&[u8]>, key: &[u8], value_size: Option<usize>) {
+		self.current.borrow_mut().write(child_storage_key, key, value_size)
+	}
+
+	fn track_change(&mut self, child_storage_key: Option<&[u8]>, key: &[u8], value: Option<Vec<u8>>) {
+		self.pending.insert((child_storage_key.map(|k| k.to_vec()), key.to_vec()), value);
+	}
+
+	/// Remove the pending changes of the keys with the given prefix.
+	fn clear_changes(&mut self, child_storage_key: Option<&[u8]>, prefix: &[u8]) {
+		for ((storage_key, key), value) in self.pending.iter_mut() {
+			if storage_key.as_ref().map(|k| &k[..]) == child_storage_key && key.starts_with(prefix) {
+				*value = None;
+			}
+		}
+	}
+}
+
+impl<'a, E: Externalities> Externalities for DbAccessTrackingExt<'a, E> {
+	fn storage(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let value = self.ext.storage(key);
+		self.track_read(None, key, value.as_ref().map(Vec::len));
+		value
+	}
+
+	fn storage_hash(&self, key: &[u8]) -> Option<H256> {
+		self.track_read(None, key, None);
+		self.ext.storage_hash(key)
+	}
+
+	fn child_storage_hash(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<H256> {
+		self.track_read(Some(storage_key.as_ref()), key, None);
+		self.ext.child_storage_hash(storage_key, key)
+	}
+
+	fn original_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let value = self.ext.original_storage(key);
+		self.track_read(None, key, value.as_ref().map(Vec::len));
+		value
+	}
+
+	fn original_child_storage(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<Vec<u8>> {
+		self.track_read(Some(storage_key.as_ref()), key, None);
+		self.ext.original_child_storage(storage_key, key)
+	}
+
+	fn original_storage_hash(&self, key: &[u8]) -> Option<H256> {
+		self.track_read(None, key, None);
+		self.ext.original_storage_hash(key)
+	}
+
+	fn original_child_storage_hash(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<H256> {
+		self.track_read(Some(storage_key.as_ref()), key, None);
+		self.ext.original_child_storage_hash(storage_key, key)
+	}
+
+	fn child_storage(&self, storage_key: ChildStorageKey, key: &[u8]) -> Option<Vec<u8>> {
+		let child_storage_key = storage_key.as_ref().to_vec();
+		let value = self.ext.child_storage(storage_key, key);
+		self.track_read(Some(&child_storage_key), key, value.as_ref().map(Vec::len));
+		value
+	}
+
+	fn exists_storage(&self, key: &[u8]) -> bool {
+		self.track_read(None, key, None);
+		self.ext.exists_storage(key)
+	}
+
+	fn exists_child_storage(&self, storage_key: ChildStorageKey, key: &[u8]) -> bool {
+		self.track_read(Some(storage_key.as_ref()), key, None);
+		self.ext.exists_child_storage(storage_key, key)
+	}
+
+	fn kill_child_storage(&mut self, storage_key: ChildStorageKey) {
+		self.track_write(None, storage_key.as_ref(), None);
+		self.clear_changes(Some(storage_key.as_ref()), &[]);
+		self.ext.kill_child_storage(storage_key)
+	}
+
+	fn kill_child_storage_limited(&mut self, storage_key: ChildStorageKey, limit: u32) -> (bool, u32) {
+		self.track_write(None, storage_key.as_ref(), None);
+		self.ext.kill_child_storage_limited(storage_key, limit)
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8]) {
+		self.track_write(None, prefix, None);
+		self.clear_changes(None, prefix);
+		self.ext.clear_prefix(prefix)
+	}
+
+	fn clear_child_prefix(&mut self, storage_key: ChildStorageKey, prefix: &[u8]) {
+		self.track_write(Some(storage_key.as_ref()), prefix, None);
+		self.clear_changes(Some(storage_key.as_ref()), prefix);
+		self.ext.clear_child_prefix(storage_key, prefix)
+	}
+
+	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+		self.track_write(None, &key, value.as_ref().map(Vec::len));
+		self.track_change(None, &key, value.clone());
+		self.ext.place_storage(key, value)
+	}
+
+	fn storage_append(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		// the whole appended value is written to the database, not only the item.
+		self.ext.storage_append(key.clone(), value);
+		let appended = self.ext.storage(&key);
+		self.track_write(None, &key, appended.as_ref().map(Vec::len));
+		self.track_change(None, &key, appended);
+	}
+
+	fn place_child_storage(&mut self, storage_key: ChildStorageKey, key: Vec<u8>, value: Option<Vec<u8>>) {
+		self.track_write(Some(storage_key.as_ref()), &key, value.as_ref().map(Vec::len));
+		self.track_change(Some(storage_key.as_ref()), &key, value.clone());
+		self.ext.place_child_storage(storage_key, key, value)
+	}
+
+	fn chain_id(&self) -> u64 {
+		self.ext.chain_id()
+	}
+
+	fn storage_root(&mut self) -> H256 {
+		self.ext.storage_root()
+	}
+
+	fn child_storage_root(&mut self, storage_key: ChildStorageKey) -> Vec<u8> {
+		self.ext.child_storage_root(storage_key)
+	}
+
+	fn storage_changes_root(&mut self, parent: H256) -> Result<Option<H256>, ()> {
+		self.ext.storage_changes_root(parent)
+	}
+
+	fn commit(&mut self) {
+		let setup = mem::replace(&mut self.pending, BTreeMap::new())
+			.into_iter()
+			.map(|((child_storage_key, key), value)| (child_storage_key, key, value))
+			.collect();
+		let mut current = CallTracker::default();
+		current.stats.setup = setup;
+		*self.current.borrow_mut() = current;
+		self.ext.commit()
+	}
+
+	fn storage_start_transaction(&mut self) {
+		self.ext.storage_start_transaction()
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.ext.storage_rollback_transaction()
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.ext.storage_commit_transaction()
+	}
+
+	fn wipe(&mut self) {
+		let call = mem::replace(&mut *self.current.borrow_mut(), CallTracker::default());
+		self.calls.push(call.stats);
+		self.pending.clear();
+		self.ext.wipe()
+	}
+}
+
+impl<'a, E: Externalities> externalities::ExtensionStore for DbAccessTrackingExt<'a, E> {
+	fn extension_by_type_id(&mut self, type_id: TypeId) -> Option<&mut dyn Any> {
+		self.ext.extension_by_type_id(type_id)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::Blake2Hasher;
+	use crate::backend::InMemory;
+	use crate::{Ext, InMemoryChangesTrieStorage, OverlayedChanges};
+
+	#[test]
+	fn accesses_are_tracked_per_call() {
+		let backend: InMemory<Blake2Hasher> = vec![(b"key".to_vec(), b"value".to_vec())]
+			.into_iter()
+			.collect::<std::collections::HashMap<_, _>>()
+			.into();
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = Ext::<Blake2Hasher, u64, _, InMemoryChangesTrieStorage<Blake2Hasher, u64>>::new(
+			&mut overlay,
+			&backend,
+			None,
+			None,
+		);
+		let mut tracking = DbAccessTrackingExt::new(&mut ext);
+
+		// the setup isn't tracked
+		tracking.set_storage(b"setup".to_vec(), b"1".to_vec());
+		tracking.commit();
+
+		assert_eq!(tracking.storage(b"key"), Some(b"value".to_vec()));
+		assert_eq!(tracking.storage(b"key"), Some(b"value".to_vec()));
+		tracking.set_storage(b"written".to_vec(), b"22".to_vec());
+		tracking.set_storage(b"written".to_vec(), b"333".to_vec());
+		assert_eq!(tracking.storage(b"written"), Some(b"333".to_vec()));
+		tracking.wipe();
+
+		tracking.commit();
+		assert_eq!(tracking.storage(b"written"), None);
+		tracking.wipe();
+
+		assert_eq!(tracking.take_calls(), vec![
+			DbAccessStats {
+				reads: vec![(None, b"key".to_vec())],
+				writes: vec![(None, b"written".to_vec())],
+				read_bytes: 5,
+				written_bytes: 5,
+				setup: vec![(None, b"setup".to_vec(), Some(b"1".to_vec()))],
+			},
+			DbAccessStats {
+				reads: vec![(None, b"written".to_vec())],
+				writes: Vec::new(),
+				read_bytes: 0,
+				written_bytes: 0,
+				setup: Vec::new(),
+			},
+		]);
+		assert!(tracking.take_calls().is_empty());
+	}
+
+	#[test]
+	fn appends_write_the_appended_value() {
+		let backend: InMemory<Blake2Hasher> = vec![(b"list".to_vec(), vec![4, 1])]
+			.into_iter()
+			.collect::<std::collections::HashMap<_, _>>()
+			.into();
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = Ext::<Blake2Hasher, u64, _, InMemoryChangesTrieStorage<Blake2Hasher, u64>>::new(
+			&mut overlay,
+			&backend,
+			None,
+			None,
+		);
+		let mut tracking = DbAccessTrackingExt::new(&mut ext);
+
+		tracking.commit();
+		tracking.storage_append(b"list".to_vec(), vec![2]);
+		tracking.wipe();
+
+		let calls = tracking.take_calls();
+		assert_eq!(calls[0].writes, vec![(None, b"list".to_vec())]);
+		assert_eq!(calls[0].written_bytes, 3);
+	}
+}
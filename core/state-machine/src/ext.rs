@@ -31,11 +31,41 @@ use primitives::{
 use trie::{trie_types::Layout, MemoryDB, default_child_trie_root};
 use externalities::Extensions;
 
+use codec::{Compact, Decode, Encode};
 use std::{error, fmt, any::{Any, TypeId}};
 use log::{warn, trace};
 
 const EXT_NOT_ALLOWED_TO_FAIL: &str = "Externalities not allowed to fail within runtime";
 
+/// Appends items to a SCALE-encoded `Vec` without decoding it.
+pub(crate) struct StorageAppend<'a>(&'a mut Vec<u8>);
+
+impl<'a> StorageAppend<'a> {
+	/// Wrap the encoded `Vec`.
+	pub fn new(encoded: &'a mut Vec<u8>) -> Self {
+		StorageAppend(encoded)
+	}
+
+	/// Append the encoded `item`, re-encoding only the length prefix.
+	///
+	/// If the wrapped value doesn't start with a valid length, it is replaced by a `Vec`
+	/// containing only `item`.
+	pub fn append(self, item: &[u8]) {
+		let (len, prefix_len) = match Compact::<u32>::decode(&mut &self.0[..]) {
+			Ok(Compact(len)) if len < u32::max_value() => (len, Compact(len).encode().len()),
+			_ => (0, self.0.len()),
+		};
+
+		let new_prefix = Compact(len + 1).encode();
+		if new_prefix.len() == prefix_len {
+			self.0[..prefix_len].copy_from_slice(&new_prefix);
+		} else {
+			self.0.splice(..prefix_len, new_prefix);
+		}
+		self.0.extend_from_slice(item);
+	}
+}
+
 /// Errors that can occur when interacting with the externalities.
 #[derive(Debug, Copy, Clone)]
 pub enum Error<B, E> {
@@ -346,6 +376,26 @@ where
 		self.overlay.set_storage(key, value);
 	}
 
+	fn storage_append(&mut self, key: Vec<u8>, value: Vec<u8>) {
+		trace!(target: "state-trace", "{:04x}: Append {}={}",
+			self.id,
+			HexDisplay::from(&key),
+			HexDisplay::from(&value),
+		);
+		let _guard = panic_handler::AbortGuard::force_abort();
+		if is_child_storage_key(&key) {
+			warn!(target: "trie", "Refuse to directly append to child storage key");
+			return;
+		}
+
+		self.mark_dirty();
+		let backend = &self.backend;
+		let current = self.overlay.value_mut_or_insert_with(&key, ||
+			backend.storage(&key).expect(EXT_NOT_ALLOWED_TO_FAIL).unwrap_or_default()
+		);
+		StorageAppend::new(current).append(&value);
+	}
+
 	fn place_child_storage(
 		&mut self,
 		storage_key: ChildStorageKey,
@@ -378,6 +428,31 @@ where
 		});
 	}
 
+	fn kill_child_storage_limited(&mut self, storage_key: ChildStorageKey, limit: u32) -> (bool, u32) {
+		trace!(target: "state-trace", "{:04x}: KillChildLimited({}) {}",
+			self.id,
+			HexDisplay::from(&storage_key.as_ref()),
+			limit,
+		);
+		let _guard = panic_handler::AbortGuard::force_abort();
+
+		self.mark_dirty();
+		let storage_key = storage_key.as_ref();
+		// the keys written in the overlay are cleared without counting towards the limit.
+		self.overlay.clear_child_storage(storage_key);
+
+		// a single page of the backend is visited, with one more key telling whether any remain.
+		let limit = limit as usize;
+		let keys = self.backend.child_keys_paged(storage_key, &[], None, limit.saturating_add(1));
+		let complete = keys.len() <= limit;
+		let mut removed = 0;
+		for key in keys.into_iter().take(limit) {
+			self.overlay.set_child_storage(storage_key.to_vec(), key, None);
+			removed += 1;
+		}
+		(complete, removed)
+	}
+
 	fn clear_prefix(&mut self, prefix: &[u8]) {
 		trace!(target: "state-trace", "{:04x}: ClearPrefix {}",
 			self.id,
@@ -510,6 +585,38 @@ where
 		);
 		result
 	}
+
+	fn commit(&mut self) {
+		let _guard = panic_handler::AbortGuard::force_abort();
+		trace!(target: "state-trace", "{:04x}: Commit", self.id);
+		self.overlay.commit_prospective();
+	}
+
+	fn wipe(&mut self) {
+		let _guard = panic_handler::AbortGuard::force_abort();
+		trace!(target: "state-trace", "{:04x}: Wipe", self.id);
+		self.mark_dirty();
+		self.overlay.discard_prospective();
+	}
+
+	fn storage_start_transaction(&mut self) {
+		let _guard = panic_handler::AbortGuard::force_abort();
+		trace!(target: "state-trace", "{:04x}: StartTransaction", self.id);
+		self.overlay.start_transaction();
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		let _guard = panic_handler::AbortGuard::force_abort();
+		trace!(target: "state-trace", "{:04x}: RollbackTransaction", self.id);
+		self.mark_dirty();
+		self.overlay.rollback_transaction().map_err(|_| ())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		let _guard = panic_handler::AbortGuard::force_abort();
+		trace!(target: "state-trace", "{:04x}: CommitTransaction", self.id);
+		self.overlay.commit_transaction().map_err(|_| ())
+	}
 }
 
 impl<'a, H, B, T, N> externalities::ExtensionStore for Ext<'a, H, N, B, T>
@@ -558,6 +665,7 @@ mod tests {
 				digest_interval: 0,
 				digest_levels: 0,
 			}),
+			..Default::default()
 		}
 	}
 
@@ -603,4 +711,97 @@ mod tests {
 			Some(hex!("96f5aae4690e7302737b6f9b7f8567d5bbb9eac1c315f80101235a92d9ec27f4").into()),
 		);
 	}
+
+	#[test]
+	fn wipe_discards_changes_since_last_commit() {
+		let mut overlay = OverlayedChanges::default();
+		let backend = TestBackend::default();
+		let mut ext = TestExt::new(&mut overlay, &backend, None, None);
+
+		ext.set_storage(b"committed".to_vec(), b"1".to_vec());
+		ext.commit();
+		ext.set_storage(b"pending".to_vec(), b"2".to_vec());
+		ext.wipe();
+
+		assert_eq!(ext.storage(b"committed"), Some(b"1".to_vec()));
+		assert_eq!(ext.storage(b"pending"), None);
+	}
+
+	#[test]
+	fn storage_append_works() {
+		let backend: TestBackend = vec![
+			(None, b"events".to_vec(), Some(vec![1u32].encode())),
+		].into();
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = TestExt::new(&mut overlay, &backend, None, None);
+		let events = |ext: &TestExt| Vec::<u32>::decode(&mut &ext.storage(b"events").unwrap()[..]).unwrap();
+
+		ext.storage_append(b"events".to_vec(), 2u32.encode());
+		ext.commit();
+		ext.storage_append(b"events".to_vec(), 3u32.encode());
+		assert_eq!(events(&ext), vec![1, 2, 3]);
+
+		ext.wipe();
+		assert_eq!(events(&ext), vec![1, 2]);
+
+		ext.clear_storage(b"events");
+		ext.storage_append(b"events".to_vec(), 4u32.encode());
+		assert_eq!(events(&ext), vec![4]);
+
+		ext.storage_append(b"new".to_vec(), 5u32.encode());
+		assert_eq!(ext.storage(b"new"), Some(vec![5u32].encode()));
+	}
+
+	#[test]
+	fn storage_append_updates_length_prefix() {
+		let mut encoded = Vec::new();
+		let mut items = Vec::new();
+		for i in 0..100u16 {
+			StorageAppend::new(&mut encoded).append(&i.encode());
+			items.push(i);
+			assert_eq!(encoded, items.encode());
+		}
+
+		// a value that isn't a `Vec` is replaced.
+		let mut encoded = vec![0xff];
+		StorageAppend::new(&mut encoded).append(&1u16.encode());
+		assert_eq!(encoded, vec![1u16].encode());
+	}
+
+	#[test]
+	fn kill_child_storage_limited_works() {
+		let child_storage = b":child_storage:default:test".to_vec();
+		let child = || ChildStorageKey::from_vec(child_storage.clone()).unwrap();
+
+		let backend: TestBackend = vec![
+			(Some(child_storage.clone()), b"a".to_vec(), Some(b"1".to_vec())),
+			(Some(child_storage.clone()), b"b".to_vec(), Some(b"2".to_vec())),
+			(Some(child_storage.clone()), b"c".to_vec(), Some(b"3".to_vec())),
+		].into();
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = TestExt::new(&mut overlay, &backend, None, None);
+
+		ext.set_child_storage(child(), b"d".to_vec(), b"4".to_vec());
+
+		// the keys of the overlay don't count towards the limit.
+		assert_eq!(ext.kill_child_storage_limited(child(), 2), (false, 2));
+		assert_eq!(ext.child_storage(child(), b"d"), None);
+		assert_eq!(ext.child_storage(child(), b"a"), None);
+		assert_eq!(ext.child_storage(child(), b"b"), None);
+		assert_eq!(ext.child_storage(child(), b"c"), Some(b"3".to_vec()));
+
+		assert_eq!(ext.kill_child_storage_limited(child(), 3), (true, 3));
+		assert_eq!(ext.child_storage(child(), b"c"), None);
+		drop(ext);
+
+		// once the deletions are in the backend, the deletion completes.
+		let backend = backend.update(overlay.prospective.children.iter()
+			.flat_map(|(storage_key, map)| map.iter()
+				.map(move |(key, value)| (Some(storage_key.clone()), key.clone(), value.value.clone()))
+			)
+			.collect());
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = TestExt::new(&mut overlay, &backend, None, None);
+		assert_eq!(ext.kill_child_storage_limited(child(), 2), (true, 0));
+	}
 }
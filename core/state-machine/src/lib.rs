@@ -26,17 +26,20 @@ use primitives::{
 	storage::well_known_keys, NativeOrEncoded, NeverNativeValue, offchain::OffchainExt,
 	traits::{KeystoreExt, CodeExecutor}, hexdisplay::HexDisplay, hash::H256,
 };
-use overlayed_changes::OverlayedChangeSet;
+use overlayed_changes::ProspectiveChanges;
 use externalities::Extensions;
 
 pub mod backend;
+mod access_recording_backend;
 mod changes_trie;
+mod db_access_tracking_ext;
 mod error;
 mod ext;
 mod testing;
 mod basic;
 mod overlayed_changes;
 mod proving_backend;
+mod read_counting_backend;
 mod trie_backend;
 mod trie_backend_essence;
 
@@ -44,6 +47,8 @@ pub use trie::{trie_types::{Layout, TrieDBMut}, TrieMut, DBValue, MemoryDB};
 pub use testing::TestExternalities;
 pub use basic::BasicExternalities;
 pub use ext::Ext;
+pub use access_recording_backend::{AccessRecordingBackend, StorageAccess, StorageAccessKind};
+pub use db_access_tracking_ext::{DbAccessTrackingExt, DbAccessStats, TrackedKey, TrackedChange};
 pub use backend::Backend;
 pub use changes_trie::{
 	AnchorBlockId as ChangesTrieAnchorBlockId,
@@ -57,11 +62,13 @@ pub use changes_trie::{
 	prune as prune_changes_tries,
 	oldest_non_pruned_trie as oldest_non_pruned_changes_trie,
 };
-pub use overlayed_changes::OverlayedChanges;
+pub use overlayed_changes::{OverlayedChanges, NoOpenTransaction};
 pub use proving_backend::{
-	create_proof_check_backend, create_proof_check_backend_storage, merge_storage_proofs,
-	Recorder as ProofRecorder, ProvingBackend, StorageProof,
+	create_proof_check_backend, create_proof_check_backend_from_compact,
+	create_proof_check_backend_storage, merge_storage_proofs, storage_proof_size,
+	Recorder as ProofRecorder, ProvingBackend, StorageProof, CompactProof,
 };
+pub use read_counting_backend::{ReadCountingBackend, ReadStats};
 pub use trie_backend_essence::{TrieBackendStorage, Storage};
 pub use trie_backend::TrieBackend;
 pub use error::{Error, ExecutionError};
@@ -299,7 +306,7 @@ impl<'a, B, H, N, T, Exec> StateMachine<'a, B, H, N, T, Exec> where
 		&mut self,
 		compute_tx: bool,
 		mut native_call: Option<NC>,
-		orig_prospective: OverlayedChangeSet,
+		orig_prospective: ProspectiveChanges,
 		on_consensus_failure: Handler,
 	) -> (
 		CallResult<R, Exec::Error>,
@@ -320,7 +327,7 @@ impl<'a, B, H, N, T, Exec> StateMachine<'a, B, H, N, T, Exec> where
 		);
 
 		if was_native {
-			self.overlay.prospective = orig_prospective.clone();
+			self.overlay.restore_prospective(orig_prospective.clone());
 			let (wasm_result, _, wasm_storage_delta, wasm_changes_delta) = self.execute_aux(
 				compute_tx,
 				false,
@@ -344,7 +351,7 @@ impl<'a, B, H, N, T, Exec> StateMachine<'a, B, H, N, T, Exec> where
 		&mut self,
 		compute_tx: bool,
 		mut native_call: Option<NC>,
-		orig_prospective: OverlayedChangeSet,
+		orig_prospective: ProspectiveChanges,
 	) -> (
 		CallResult<R, Exec::Error>,
 		Option<(B::Transaction, H::Out)>,
@@ -362,7 +369,7 @@ impl<'a, B, H, N, T, Exec> StateMachine<'a, B, H, N, T, Exec> where
 		if !was_native || result.is_ok() {
 			(result, storage_delta, changes_delta)
 		} else {
-			self.overlay.prospective = orig_prospective.clone();
+			self.overlay.restore_prospective(orig_prospective.clone());
 			let (wasm_result, _, wasm_storage_delta, wasm_changes_delta) = self.execute_aux(
 				compute_tx,
 				false,
@@ -400,20 +407,16 @@ impl<'a, B, H, N, T, Exec> StateMachine<'a, B, H, N, T, Exec> where
 		// read changes trie configuration. The reason why we're doing it here instead of the
 		// `OverlayedChanges` constructor is that we need proofs for this read as a part of
 		// proof-of-execution on light clients. And the proof is recorded by the backend which
-		// is created after OverlayedChanges
-
-		let init_overlay = |overlay: &mut OverlayedChanges, final_check: bool, backend: &B| {
-			let changes_trie_config = try_read_overlay_value(
-				overlay,
-				backend,
-				well_known_keys::CHANGES_TRIE_CONFIG
-			)?;
-			set_changes_trie_config(overlay, changes_trie_config, final_check)
-		};
-		init_overlay(self.overlay, false, &self.backend)?;
+		// is created after OverlayedChanges.
+		// The configuration that is active at the beginning of the block is used - if the runtime
+		// changes it, the new configuration is only applied starting from the next block.
+		let changes_trie_config = self.backend
+			.storage(well_known_keys::CHANGES_TRIE_CONFIG)
+			.map_err(|err| Box::new(ExecutionError::Backend(format!("{}", err))) as Box<dyn Error>)?;
+		set_changes_trie_config(self.overlay, changes_trie_config)?;
 
 		let result = {
-			let orig_prospective = self.overlay.prospective.clone();
+			let orig_prospective = self.overlay.save_prospective();
 
 			let (result, storage_delta, changes_delta) = match manager {
 				ExecutionManager::Both(on_consensus_failure) => {
@@ -447,10 +450,6 @@ impl<'a, B, H, N, T, Exec> StateMachine<'a, B, H, N, T, Exec> where
 			result.map(move |out| (out, storage_delta, changes_delta))
 		};
 
-		if result.is_ok() {
-			init_overlay(self.overlay, true, self.backend)?;
-		}
-
 		result.map_err(|e| Box::new(e) as _)
 	}
 }
@@ -611,6 +610,40 @@ where
 	Ok(proving_backend.extract_proof())
 }
 
+/// Generate storage read proof of all the keys starting with `prefix`.
+pub fn prove_prefix_read<B, H>(
+	mut backend: B,
+	prefix: &[u8],
+) -> Result<StorageProof, Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	prove_prefix_read_on_trie_backend(trie_backend, prefix)
+}
+
+/// Generate storage read proof of all the keys starting with `prefix` on pre-created trie backend.
+pub fn prove_prefix_read_on_trie_backend<S, H>(
+	trie_backend: &TrieBackend<S, H>,
+	prefix: &[u8],
+) -> Result<StorageProof, Box<dyn Error>>
+where
+	S: trie_backend_essence::TrieBackendStorage<H>,
+	H: Hasher,
+	H::Out: Ord,
+{
+	let mut recorder = proving_backend::Recorder::new();
+	proving_backend::ProvingBackendEssence {
+		backend: trie_backend.essence(),
+		proof_recorder: &mut recorder,
+	}.record_keys_with_prefix(prefix).map_err(|e| Box::new(e) as Box<dyn Error>)?;
+
+	Ok(StorageProof::new(recorder.drain().into_iter().map(|record| record.data).collect()))
+}
+
 /// Generate storage read proof on pre-created trie backend.
 pub fn prove_child_read_on_trie_backend<S, H, I>(
 	trie_backend: &TrieBackend<S, H>,
@@ -654,6 +687,43 @@ where
 	Ok(result)
 }
 
+/// Check storage read proof in the compact form, generated by `prove_read` call and converted
+/// using `StorageProof::into_compact_proof`.
+pub fn read_compact_proof_check<H, I>(
+	root: H::Out,
+	proof: &CompactProof,
+	keys: I,
+) -> Result<HashMap<Vec<u8>, Option<Vec<u8>>>, Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Ord,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	read_proof_check::<H, I>(root, proof.to_storage_proof::<H>()?, keys)
+}
+
+/// Check storage read proof, generated by `prove_prefix_read` call.
+///
+/// Returns all the key/value pairs with a key starting with `prefix`, in order. Fails if the
+/// proof misses any of them.
+pub fn read_prefix_proof_check<H>(
+	root: H::Out,
+	proof: StorageProof,
+	prefix: &[u8],
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Ord,
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	trie::read_trie_prefix::<Layout<H>, _>(
+		proving_backend.backend_storage(),
+		proving_backend.root(),
+		prefix,
+	).map_err(|e| Box::new(format!("Trie lookup error: {}", e)) as Box<dyn Error>)
+}
+
 /// Check child storage read proof, generated by `prove_child_read` call.
 pub fn read_child_proof_check<H, I>(
 	root: H::Out,
@@ -705,12 +775,10 @@ where
 	proving_backend.child_storage(storage_key, key).map_err(|e| Box::new(e) as Box<dyn Error>)
 }
 
-/// Sets overlayed changes' changes trie configuration. Returns error if configuration
-/// differs from previous OR config decode has failed.
+/// Sets overlayed changes' changes trie configuration. Returns error if config decode has failed.
 fn set_changes_trie_config(
 	overlay: &mut OverlayedChanges,
 	config: Option<Vec<u8>>,
-	final_check: bool,
 ) -> Result<(), Box<dyn Error>> {
 	let config = match config {
 		Some(v) => Some(Decode::decode(&mut &v[..])
@@ -718,31 +786,10 @@ fn set_changes_trie_config(
 		None => None,
 	};
 
-	if final_check && overlay.changes_trie_config.is_some() != config.is_some() {
-		return Err(Box::new("Changes trie configuration change is not supported".to_owned()));
-	}
-
-	if let Some(config) = config {
-		if !overlay.set_changes_trie_config(config) {
-			return Err(Box::new("Changes trie configuration change is not supported".to_owned()));
-		}
-	}
+	overlay.set_changes_trie_config(config);
 	Ok(())
 }
 
-/// Reads storage value from overlay or from the backend.
-fn try_read_overlay_value<H, B>(
-	overlay: &OverlayedChanges,
-	backend: &B, key: &[u8],
-) -> Result<Option<Vec<u8>>, Box<dyn Error>> where H: Hasher, B: Backend<H> {
-	match overlay.storage(key).map(|x| x.map(|x| x.to_vec())) {
-		Some(value) => Ok(value),
-		None => backend
-			.storage(key)
-			.map_err(|err| Box::new(ExecutionError::Backend(format!("{}", err))) as Box<dyn Error>),
-	}
-}
-
 #[cfg(test)]
 mod tests {
 	use std::collections::HashMap;
@@ -1046,6 +1093,17 @@ mod tests {
 			vec![(b"value2".to_vec(), Some(vec![24]))],
 		);
 		assert_eq!(local_result2, false);
+		// check compact proof locally
+		let remote_compact_proof = remote_proof.clone().into_compact_proof::<Blake2Hasher>().unwrap();
+		let local_result3 = read_compact_proof_check::<Blake2Hasher, _>(
+			remote_root,
+			&remote_compact_proof,
+			&[b"value2"],
+		).unwrap();
+		assert_eq!(
+			local_result3.into_iter().collect::<Vec<_>>(),
+			vec![(b"value2".to_vec(), Some(vec![24]))],
+		);
 		// on child trie
 		let remote_backend = trie_backend::tests::test_trie();
 		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
@@ -1077,9 +1135,31 @@ mod tests {
 	}
 
 	#[test]
-	fn cannot_change_changes_trie_config() {
+	fn prove_prefix_read_and_proof_check_works() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let remote_proof = prove_prefix_read(remote_backend, b"value").unwrap();
+
+		let local_result = read_prefix_proof_check::<Blake2Hasher>(
+			remote_root,
+			remote_proof,
+			b"value",
+		).unwrap();
+		assert_eq!(
+			local_result,
+			vec![(b"value1".to_vec(), vec![42]), (b"value2".to_vec(), vec![24])],
+		);
+
+		// a proof of a single key doesn't prove the whole prefix.
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_proof = prove_read(remote_backend, &[b"value1"]).unwrap();
+		assert!(read_prefix_proof_check::<Blake2Hasher>(remote_root, remote_proof, b"value").is_err());
+	}
+
+	#[test]
+	fn changes_trie_config_can_be_changed() {
 		let backend = trie_backend::tests::test_trie();
-		let mut overlayed_changes = Default::default();
+		let mut overlayed_changes = OverlayedChanges::default();
 		let changes_trie_storage = InMemoryChangesTrieStorage::<Blake2Hasher, u64>::new();
 
 		let mut state_machine = StateMachine::new(
@@ -1098,13 +1178,14 @@ mod tests {
 			None,
 		);
 
-		assert!(state_machine.execute(ExecutionStrategy::NativeWhenPossible).is_err());
+		assert!(state_machine.execute(ExecutionStrategy::NativeWhenPossible).is_ok());
+		assert_changes_trie_config_changed(&overlayed_changes);
 	}
 
 	#[test]
-	fn cannot_change_changes_trie_config_with_native_else_wasm() {
+	fn changes_trie_config_can_be_changed_with_native_else_wasm() {
 		let backend = trie_backend::tests::test_trie();
-		let mut overlayed_changes = Default::default();
+		let mut overlayed_changes = OverlayedChanges::default();
 		let changes_trie_storage = InMemoryChangesTrieStorage::<Blake2Hasher, u64>::new();
 
 		let mut state_machine = StateMachine::new(
@@ -1123,6 +1204,16 @@ mod tests {
 			None,
 		);
 
-		assert!(state_machine.execute(ExecutionStrategy::NativeElseWasm).is_err());
+		assert!(state_machine.execute(ExecutionStrategy::NativeElseWasm).is_ok());
+		assert_changes_trie_config_changed(&overlayed_changes);
+	}
+
+	fn assert_changes_trie_config_changed(overlayed_changes: &OverlayedChanges) {
+		// the configuration of the current block is kept, but the new one is saved to the storage
+		assert!(overlayed_changes.changes_trie_config.is_none());
+		assert_eq!(
+			overlayed_changes.storage(well_known_keys::CHANGES_TRIE_CONFIG),
+			Some(Some(&ChangesTrieConfig { digest_interval: 777, digest_levels: 333 }.encode()[..])),
+		);
 	}
 }
@@ -27,12 +27,22 @@ use primitives::storage::well_known_keys::EXTRINSIC_INDEX;
 ///
 /// A transaction shares all prospective changes within an inner overlay
 /// that can be cleared.
+///
+/// Transactions can be nested within the prospective changes with [`start_transaction`]. The
+/// changes made within such a transaction are reverted by [`rollback_transaction`], or kept in
+/// the enclosing transaction by [`commit_transaction`].
+///
+/// [`start_transaction`]: #method.start_transaction
+/// [`rollback_transaction`]: #method.rollback_transaction
+/// [`commit_transaction`]: #method.commit_transaction
 #[derive(Debug, Default, Clone)]
 pub struct OverlayedChanges {
 	/// Changes that are not yet committed.
 	pub(crate) prospective: OverlayedChangeSet,
 	/// Committed changes.
 	pub(crate) committed: OverlayedChangeSet,
+	/// Open transactions, innermost last.
+	transactions: Vec<TransactionUndo>,
 	/// Changes trie configuration. None by default, but could be installed by the
 	/// runtime if it supports change tries.
 	pub(crate) changes_trie_config: Option<ChangesTrieConfig>,
@@ -69,6 +79,26 @@ impl FromIterator<(Vec<u8>, OverlayedValue)> for OverlayedChangeSet {
 	}
 }
 
+/// Values the prospective change set had before they were first changed within a transaction,
+/// `None` for keys that were not in the change set.
+#[derive(Debug, Default, Clone)]
+struct TransactionUndo {
+	top: HashMap<Vec<u8>, Option<OverlayedValue>>,
+	children: HashMap<Vec<u8>, HashMap<Vec<u8>, Option<OverlayedValue>>>,
+}
+
+/// The prospective changes along with the open transactions, saved to run an execution again from
+/// the same changes.
+#[derive(Debug, Clone)]
+pub(crate) struct ProspectiveChanges {
+	changes: OverlayedChangeSet,
+	transactions: Vec<TransactionUndo>,
+}
+
+/// Error returned when committing or rolling back a transaction while none is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoOpenTransaction;
+
 impl OverlayedChangeSet {
 	/// Whether the change set is empty.
 	pub fn is_empty(&self) -> bool {
@@ -90,18 +120,11 @@ impl OverlayedChanges {
 
 	/// Sets the changes trie configuration.
 	///
-	/// Returns false if configuration has been set already and we now trying
-	/// to install different configuration. This isn't supported now.
-	pub(crate) fn set_changes_trie_config(&mut self, config: ChangesTrieConfig) -> bool {
-		if let Some(ref old_config) = self.changes_trie_config {
-			// we do not support changes trie configuration' change now
-			if *old_config != config {
-				return false;
-			}
-		}
-
-		self.changes_trie_config = Some(config);
-		true
+	/// This is the configuration that is active at the beginning of the block and it is used
+	/// to build the changes trie of this block. Changes of the configuration that are made
+	/// by the runtime within the block only take effect starting from the next block.
+	pub(crate) fn set_changes_trie_config(&mut self, config: Option<ChangesTrieConfig>) {
+		self.changes_trie_config = config;
 	}
 
 	/// Returns a double-Option: None if the key is unknown (i.e. and the query should be refered
@@ -137,6 +160,7 @@ impl OverlayedChanges {
 	/// `None` can be used to delete a value specified by the given key.
 	pub(crate) fn set_storage(&mut self, key: Vec<u8>, val: Option<Vec<u8>>) {
 		let extrinsic_index = self.extrinsic_index();
+		self.save_for_rollback(None, &key);
 		let entry = self.prospective.top.entry(key).or_default();
 		entry.value = val;
 
@@ -146,11 +170,39 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Returns a mutable reference to the value of `key` in the prospective change set, marking the
+	/// value as changed.
+	///
+	/// If the prospective change set doesn't contain `key`, the value is copied from the committed
+	/// change set, or obtained from `init` if the key isn't changed at all. A deleted value is
+	/// turned into an empty one.
+	pub(crate) fn value_mut_or_insert_with(
+		&mut self,
+		key: &[u8],
+		init: impl FnOnce() -> Vec<u8>,
+	) -> &mut Vec<u8> {
+		let extrinsic_index = self.extrinsic_index();
+		self.save_for_rollback(None, key);
+		let committed = &self.committed.top;
+		let entry = self.prospective.top.entry(key.to_vec()).or_insert_with(|| OverlayedValue {
+			value: committed.get(key).map_or_else(|| Some(init()), |committed| committed.value.clone()),
+			extrinsics: None,
+		});
+
+		if let Some(extrinsic) = extrinsic_index {
+			entry.extrinsics.get_or_insert_with(Default::default)
+				.insert(extrinsic);
+		}
+
+		entry.value.get_or_insert_with(Vec::new)
+	}
+
 	/// Inserts the given key-value pair into the prospective child change set.
 	///
 	/// `None` can be used to delete a value specified by the given key.
 	pub(crate) fn set_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, val: Option<Vec<u8>>) {
 		let extrinsic_index = self.extrinsic_index();
+		self.save_for_rollback(Some(&storage_key), &key);
 		let map_entry = self.prospective.children.entry(storage_key).or_default();
 		let entry = map_entry.entry(key).or_default();
 		entry.value = val;
@@ -169,6 +221,7 @@ impl OverlayedChanges {
 	/// [`discard_prospective`]: #method.discard_prospective
 	pub(crate) fn clear_child_storage(&mut self, storage_key: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
+		self.save_prefix_for_rollback(Some(storage_key), &[]);
 		let map_entry = self.prospective.children.entry(storage_key.to_vec()).or_default();
 
 		map_entry.values_mut().for_each(|e| {
@@ -205,6 +258,7 @@ impl OverlayedChanges {
 	/// [`discard_prospective`]: #method.discard_prospective
 	pub(crate) fn clear_prefix(&mut self, prefix: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
+		self.save_prefix_for_rollback(None, prefix);
 
 		// Iterate over all prospective and mark all keys that share
 		// the given prefix as removed (None).
@@ -236,6 +290,7 @@ impl OverlayedChanges {
 
 	pub(crate) fn clear_child_prefix(&mut self, storage_key: &[u8], prefix: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
+		self.save_prefix_for_rollback(Some(storage_key), prefix);
 		let map_entry = self.prospective.children.entry(storage_key.to_vec()).or_default();
 
 		for (key, entry) in map_entry.iter_mut() {
@@ -266,13 +321,136 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Start a transaction nested within the prospective changes and the open transactions.
+	pub fn start_transaction(&mut self) {
+		self.transactions.push(Default::default());
+	}
+
+	/// Revert the changes made since the innermost open transaction was started, and close it.
+	pub fn rollback_transaction(&mut self) -> Result<(), NoOpenTransaction> {
+		let undo = self.transactions.pop().ok_or(NoOpenTransaction)?;
+
+		let restore = |changes: &mut HashMap<Vec<u8>, OverlayedValue>, key, value| match value {
+			Some(value) => { changes.insert(key, value); },
+			None => { changes.remove(&key); },
+		};
+		for (key, value) in undo.top {
+			restore(&mut self.prospective.top, key, value);
+		}
+		for (storage_key, child_undo) in undo.children {
+			let child = self.prospective.children.entry(storage_key.clone()).or_default();
+			for (key, value) in child_undo {
+				restore(child, key, value);
+			}
+			if child.is_empty() {
+				self.prospective.children.remove(&storage_key);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Keep the changes made since the innermost open transaction was started in the enclosing
+	/// transaction, and close it.
+	pub fn commit_transaction(&mut self) -> Result<(), NoOpenTransaction> {
+		let undo = self.transactions.pop().ok_or(NoOpenTransaction)?;
+
+		// the enclosing transaction must restore the values from before it changed them first.
+		if let Some(parent) = self.transactions.last_mut() {
+			for (key, value) in undo.top {
+				parent.top.entry(key).or_insert(value);
+			}
+			for (storage_key, child_undo) in undo.children {
+				let parent_child = parent.children.entry(storage_key).or_default();
+				for (key, value) in child_undo {
+					parent_child.entry(key).or_insert(value);
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Number of open transactions.
+	pub fn transaction_depth(&self) -> usize {
+		self.transactions.len()
+	}
+
+	/// Remember the prospective value of a key before it is changed within the innermost open
+	/// transaction.
+	fn save_for_rollback(&mut self, storage_key: Option<&[u8]>, key: &[u8]) {
+		let undo = match self.transactions.last_mut() {
+			Some(undo) => undo,
+			None => return,
+		};
+
+		let (undo, prospective) = match storage_key {
+			Some(storage_key) => (
+				undo.children.entry(storage_key.to_vec()).or_default(),
+				self.prospective.children.get(storage_key),
+			),
+			None => (&mut undo.top, Some(&self.prospective.top)),
+		};
+		if !undo.contains_key(key) {
+			undo.insert(key.to_vec(), prospective.and_then(|changes| changes.get(key)).cloned());
+		}
+	}
+
+	/// Remember the prospective values of all the keys with the given prefix that are known to
+	/// the overlay, before they are cleared within the innermost open transaction.
+	fn save_prefix_for_rollback(&mut self, storage_key: Option<&[u8]>, prefix: &[u8]) {
+		if self.transactions.is_empty() {
+			return;
+		}
+
+		let keys: BTreeSet<Vec<u8>> = {
+			let (prospective, committed) = match storage_key {
+				Some(storage_key) => (
+					self.prospective.children.get(storage_key),
+					self.committed.children.get(storage_key),
+				),
+				None => (Some(&self.prospective.top), Some(&self.committed.top)),
+			};
+			prospective.into_iter()
+				.chain(committed)
+				.flat_map(|changes| changes.keys())
+				.filter(|key| key.starts_with(prefix))
+				.cloned()
+				.collect()
+		};
+		for key in keys {
+			self.save_for_rollback(storage_key, &key);
+		}
+	}
+
+	/// Save the prospective changes and the open transactions.
+	pub(crate) fn save_prospective(&self) -> ProspectiveChanges {
+		ProspectiveChanges {
+			changes: self.prospective.clone(),
+			transactions: self.transactions.clone(),
+		}
+	}
+
+	/// Replace the prospective changes and the open transactions by the saved ones, discarding
+	/// the changes made and the transactions opened since they were saved.
+	pub(crate) fn restore_prospective(&mut self, saved: ProspectiveChanges) {
+		self.prospective = saved.changes;
+		self.transactions = saved.transactions;
+	}
+
 	/// Discard prospective changes to state.
+	///
+	/// The open transactions are discarded as well.
 	pub fn discard_prospective(&mut self) {
 		self.prospective.clear();
+		self.transactions.clear();
 	}
 
 	/// Commit prospective changes to state.
+	///
+	/// The open transactions are committed as well.
 	pub fn commit_prospective(&mut self) {
+		self.transactions.clear();
 		if self.committed.is_empty() {
 			::std::mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
@@ -393,6 +571,76 @@ mod tests {
 		assert!(overlayed.storage(&key).unwrap().is_none());
 	}
 
+	#[test]
+	fn nested_transactions_work() {
+		let mut overlayed = OverlayedChanges::default();
+		let child = b":child_storage:default:child".to_vec();
+
+		overlayed.set_storage(b"a".to_vec(), Some(vec![1]));
+		overlayed.commit_prospective();
+		overlayed.set_storage(b"b".to_vec(), Some(vec![2]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"a".to_vec(), Some(vec![10]));
+		overlayed.set_child_storage(child.clone(), b"c".to_vec(), Some(vec![3]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"b".to_vec(), Some(vec![20]));
+		overlayed.clear_prefix(b"a");
+		overlayed.set_storage(b"d".to_vec(), Some(vec![4]));
+		assert_eq!(overlayed.transaction_depth(), 2);
+		assert_eq!(overlayed.commit_transaction(), Ok(()));
+
+		overlayed.start_transaction();
+		overlayed.clear_child_storage(&child);
+		assert_eq!(overlayed.child_storage(&child, b"c"), Some(None));
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+
+		assert_eq!(overlayed.storage(b"a"), Some(None));
+		assert_eq!(overlayed.storage(b"b"), Some(Some(&[20][..])));
+		assert_eq!(overlayed.storage(b"d"), Some(Some(&[4][..])));
+		assert_eq!(overlayed.child_storage(&child, b"c"), Some(Some(&[3][..])));
+
+		// rolling back the outer transaction reverts the committed inner one as well.
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.transaction_depth(), 0);
+		assert_eq!(overlayed.storage(b"a"), Some(Some(&[1][..])));
+		assert_eq!(overlayed.storage(b"b"), Some(Some(&[2][..])));
+		assert_eq!(overlayed.storage(b"d"), None);
+		assert_eq!(overlayed.child_storage(&child, b"c"), None);
+		assert!(overlayed.prospective.children.is_empty());
+
+		assert_eq!(overlayed.rollback_transaction(), Err(NoOpenTransaction));
+		assert_eq!(overlayed.commit_transaction(), Err(NoOpenTransaction));
+	}
+
+	#[test]
+	fn commit_prospective_closes_transactions() {
+		let mut overlayed = OverlayedChanges::default();
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"a".to_vec(), Some(vec![1]));
+		overlayed.commit_prospective();
+
+		assert_eq!(overlayed.transaction_depth(), 0);
+		assert_eq!(overlayed.storage(b"a"), Some(Some(&[1][..])));
+	}
+
+	#[test]
+	fn restore_prospective_closes_the_transactions_opened_since_saved() {
+		let mut overlayed = OverlayedChanges::default();
+		overlayed.set_storage(b"a".to_vec(), Some(vec![1]));
+		let saved = overlayed.save_prospective();
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"a".to_vec(), Some(vec![2]));
+		overlayed.restore_prospective(saved);
+
+		assert_eq!(overlayed.transaction_depth(), 0);
+		assert_eq!(overlayed.storage(b"a"), Some(Some(&[1][..])));
+		assert_eq!(overlayed.rollback_transaction(), Err(NoOpenTransaction));
+	}
+
 	#[test]
 	fn overlayed_storage_root_works() {
 		let initial: HashMap<_, _> = vec![
@@ -431,12 +679,7 @@ mod tests {
 	fn changes_trie_configuration_is_saved() {
 		let mut overlay = OverlayedChanges::default();
 		assert!(overlay.changes_trie_config.is_none());
-		assert_eq!(
-			overlay.set_changes_trie_config(
-				ChangesTrieConfig { digest_interval: 4, digest_levels: 1, },
-			),
-			true,
-		);
+		overlay.set_changes_trie_config(Some(ChangesTrieConfig { digest_interval: 4, digest_levels: 1, }));
 		assert!(overlay.changes_trie_config.is_some());
 	}
 
@@ -444,14 +687,14 @@ mod tests {
 	fn changes_trie_configuration_is_saved_twice() {
 		let mut overlay = OverlayedChanges::default();
 		assert!(overlay.changes_trie_config.is_none());
-		assert_eq!(overlay.set_changes_trie_config(ChangesTrieConfig {
+		overlay.set_changes_trie_config(Some(ChangesTrieConfig {
 			digest_interval: 4, digest_levels: 1,
-		}), true);
+		}));
 		overlay.set_extrinsic_index(0);
 		overlay.set_storage(vec![1], Some(vec![2]));
-		assert_eq!(overlay.set_changes_trie_config(ChangesTrieConfig {
+		overlay.set_changes_trie_config(Some(ChangesTrieConfig {
 			digest_interval: 4, digest_levels: 1,
-		}), true);
+		}));
 		assert_eq!(
 			strip_extrinsic_index(&overlay.prospective.top),
 			vec![
@@ -461,22 +704,28 @@ mod tests {
 	}
 
 	#[test]
-	fn panics_when_trying_to_save_different_changes_trie_configuration() {
+	fn changes_trie_configuration_is_replaced() {
 		let mut overlay = OverlayedChanges::default();
-		assert_eq!(overlay.set_changes_trie_config(ChangesTrieConfig {
+		overlay.set_changes_trie_config(Some(ChangesTrieConfig {
 			digest_interval: 4, digest_levels: 1,
-		}), true);
-		assert_eq!(overlay.set_changes_trie_config(ChangesTrieConfig {
+		}));
+		overlay.set_changes_trie_config(Some(ChangesTrieConfig {
 			digest_interval: 2, digest_levels: 1,
-		}), false);
+		}));
+		assert_eq!(
+			overlay.changes_trie_config,
+			Some(ChangesTrieConfig { digest_interval: 2, digest_levels: 1 }),
+		);
+		overlay.set_changes_trie_config(None);
+		assert!(overlay.changes_trie_config.is_none());
 	}
 
 	#[test]
 	fn extrinsic_changes_are_collected() {
 		let mut overlay = OverlayedChanges::default();
-		let _ = overlay.set_changes_trie_config(ChangesTrieConfig {
+		overlay.set_changes_trie_config(Some(ChangesTrieConfig {
 			digest_interval: 4, digest_levels: 1,
-		});
+		}));
 
 		overlay.set_storage(vec![100], Some(vec![101]));
 
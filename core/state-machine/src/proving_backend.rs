@@ -16,13 +16,14 @@
 
 //! Proving state machine backend.
 
-use std::{cell::RefCell, collections::HashSet, rc::Rc};
-use codec::{Decode, Encode};
+use std::{cell::RefCell, collections::{HashMap, HashSet}, rc::Rc};
+use codec::{Compact, Decode, Encode};
 use log::debug;
 use hash_db::{Hasher, HashDB, EMPTY_PREFIX};
 use trie::{
 	MemoryDB, PrefixedMemoryDB, default_child_trie_root,
-	read_trie_value_with, read_child_trie_value_with, record_all_keys
+	read_trie_value_with, read_child_trie_value_with, record_all_keys, record_keys_with_prefix,
+	trie_keys_paged,
 };
 pub use trie::Recorder;
 pub use trie::trie_types::{Layout, TrieError};
@@ -68,6 +69,37 @@ impl StorageProof {
 	pub fn iter_nodes(self) -> StorageProofNodeIterator {
 		StorageProofNodeIterator::new(self)
 	}
+
+	/// Encode the proof in the compact form.
+	pub fn into_compact_proof<H: Hasher>(self) -> Result<CompactProof, Box<dyn Error>> {
+		let encoded_nodes = trie::encode_compact::<H>(self.trie_nodes)
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		Ok(CompactProof { encoded_nodes })
+	}
+}
+
+/// Storage proof in the compact form.
+///
+/// The hashes of trie nodes that could be recomputed from the proof itself are omitted, which
+/// makes the proof considerably smaller. It is converted back into the `StorageProof` before
+/// verification.
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct CompactProof {
+	encoded_nodes: Vec<Vec<u8>>,
+}
+
+impl CompactProof {
+	/// Returns compact-encoded trie nodes of the proof.
+	pub fn encoded_nodes(&self) -> &[Vec<u8>] {
+		&self.encoded_nodes
+	}
+
+	/// Decode the storage proof from the compact form.
+	pub fn to_storage_proof<H: Hasher>(&self) -> Result<StorageProof, Box<dyn Error>> {
+		let trie_nodes = trie::decode_compact::<H>(&self.encoded_nodes)
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		Ok(StorageProof { trie_nodes })
+	}
 }
 
 /// An iterator over trie nodes constructed from a storage proof. The nodes are not guaranteed to
@@ -160,6 +192,73 @@ impl<'a, S, H> ProvingBackendEssence<'a, S, H>
 		).map_err(map_e)
 	}
 
+	pub fn record_keys_with_prefix(&mut self, prefix: &[u8]) -> Result<(), String> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral::new(
+			self.backend.backend_storage(),
+			&mut read_overlay,
+		);
+
+		let map_e = |e| format!("Trie lookup error: {}", e);
+
+		record_keys_with_prefix::<Layout<H>, _>(
+			&eph,
+			self.backend.root(),
+			prefix,
+			&mut *self.proof_recorder
+		).map_err(map_e)
+	}
+
+	pub fn keys_paged(
+		&mut self,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, String> {
+		let root = *self.backend.root();
+		self.keys_paged_inner(&root, prefix, start_key, count)
+	}
+
+	pub fn child_keys_paged(
+		&mut self,
+		storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, String> {
+		let root_vec = self.storage(storage_key)?
+			.unwrap_or(default_child_trie_root::<Layout<H>>(storage_key));
+		let mut root = H::Out::default();
+		root.as_mut().copy_from_slice(&root_vec);
+
+		self.keys_paged_inner(&root, prefix, start_key, count)
+	}
+
+	fn keys_paged_inner(
+		&mut self,
+		root: &H::Out,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<Vec<u8>>, String> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral::new(
+			self.backend.backend_storage(),
+			&mut read_overlay,
+		);
+
+		let map_e = |e| format!("Trie lookup error: {}", e);
+
+		trie_keys_paged::<Layout<H>, _>(
+			&eph,
+			root,
+			prefix,
+			start_key,
+			count,
+			Some(&mut *self.proof_recorder),
+		).map_err(map_e)
+	}
+
 	pub fn record_all_keys(&mut self) {
 		let mut read_overlay = S::Overlay::default();
 		let eph = Ephemeral::new(
@@ -178,6 +277,41 @@ impl<'a, S, H> ProvingBackendEssence<'a, S, H>
 	}
 }
 
+/// Returns the encoded size of the storage proof of the given keys, as `(child storage key, key)`.
+///
+/// The trie nodes are looked up to measure them, but aren't kept.
+pub fn storage_proof_size<S, H, I>(backend: &TrieBackend<S, H>, keys: I) -> Result<u64, String>
+	where
+		S: TrieBackendStorage<H>,
+		H: Hasher,
+		I: IntoIterator<Item=(Option<Vec<u8>>, Vec<u8>)>,
+{
+	let mut recorder = Recorder::new();
+	{
+		let mut essence = ProvingBackendEssence {
+			backend: backend.essence(),
+			proof_recorder: &mut recorder,
+		};
+		for (storage_key, key) in keys {
+			match storage_key {
+				Some(storage_key) => essence.child_storage(&storage_key, &key)?,
+				None => essence.storage(&key)?,
+			};
+		}
+	}
+
+	// the recorder doesn't deduplicate the nodes shared by several lookups.
+	let node_sizes = recorder.drain()
+		.into_iter()
+		.map(|record| (record.hash, record.data.len()))
+		.collect::<HashMap<_, _>>();
+	let encoded_len = |len: usize| Compact(len as u32).encode().len() as u64 + len as u64;
+	Ok(node_sizes.values().fold(
+		Compact(node_sizes.len() as u32).encode().len() as u64,
+		|size, len| size + encoded_len(*len),
+	))
+}
+
 /// Patricia trie-based backend which also tracks all touched storage trie values.
 /// These can be sent to remote node and used as a proof of execution.
 pub struct ProvingBackend<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> {
@@ -277,6 +411,32 @@ impl<'a, S, H> Backend<H> for ProvingBackend<'a, S, H>
 		self.backend.child_keys(child_storage_key, prefix)
 	}
 
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		ProvingBackendEssence {
+			backend: self.backend.essence(),
+			proof_recorder: &mut *self.proof_recorder.try_borrow_mut()
+				.expect("only fails when already borrowed; keys_paged() is non-reentrant; qed"),
+		}.keys_paged(prefix, start_key, count)
+			.map_err(|e| debug!(target: "trie", "Error extracting trie keys: {}", e))
+			.unwrap_or_default()
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		ProvingBackendEssence {
+			backend: self.backend.essence(),
+			proof_recorder: &mut *self.proof_recorder.try_borrow_mut()
+				.expect("only fails when already borrowed; child_keys_paged() is non-reentrant; qed"),
+		}.child_keys_paged(child_storage_key, prefix, start_key, count)
+			.map_err(|e| debug!(target: "trie", "Error extracting trie keys: {}", e))
+			.unwrap_or_default()
+	}
+
 	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
@@ -309,6 +469,17 @@ where
 	}
 }
 
+/// Create proof check backend from the compact proof.
+pub fn create_proof_check_backend_from_compact<H>(
+	root: H::Out,
+	proof: &CompactProof,
+) -> Result<TrieBackend<MemoryDB<H>, H>, Box<dyn Error>>
+where
+	H: Hasher,
+{
+	create_proof_check_backend(root, proof.to_storage_proof::<H>()?)
+}
+
 /// Create in-memory storage of proof check backend.
 pub fn create_proof_check_backend_storage<H>(
 	proof: StorageProof,
@@ -395,6 +566,57 @@ mod tests {
 		assert_eq!(proof_check.storage(&[42]).unwrap().unwrap(), vec![42]);
 	}
 
+	#[test]
+	fn compact_proof_recorded_and_checked_with_child() {
+		let subtrie = ChildStorageKey::from_slice(b":child_storage:default:sub1").unwrap().into_owned();
+		let contents = (0..64).map(|i| (None, vec![i], Some(vec![i; 40])))
+			.chain((0..64).map(|i| (Some(subtrie.clone()), vec![i], Some(vec![i; 40]))))
+			.collect::<Vec<_>>();
+		let mut in_memory = InMemory::<Blake2Hasher>::default().update(contents);
+		let in_memory_root = in_memory.full_storage_root::<_, Vec<_>, _>(
+			::std::iter::empty(),
+			in_memory.child_storage_keys().map(|k|(k.to_vec(), Vec::new()))
+		).0;
+		let trie = in_memory.as_trie_backend().unwrap();
+
+		let proving = ProvingBackend::new(trie);
+		assert_eq!(proving.storage(&[42]).unwrap().unwrap(), vec![42; 40]);
+		assert_eq!(proving.child_storage(&subtrie[..], &[42]).unwrap().unwrap(), vec![42; 40]);
+		let proof = proving.extract_proof();
+
+		let compact_proof = proof.clone().into_compact_proof::<Blake2Hasher>().unwrap();
+		assert!(compact_proof.encode().len() < proof.encode().len());
+		let mut decoded_nodes = compact_proof.to_storage_proof::<Blake2Hasher>().unwrap()
+			.iter_nodes().collect::<Vec<_>>();
+		let mut nodes = proof.iter_nodes().collect::<Vec<_>>();
+		decoded_nodes.sort();
+		nodes.sort();
+		assert_eq!(decoded_nodes, nodes);
+
+		let proof_check = create_proof_check_backend_from_compact::<Blake2Hasher>(
+			in_memory_root.into(),
+			&compact_proof,
+		).unwrap();
+		assert_eq!(proof_check.storage(&[42]).unwrap().unwrap(), vec![42; 40]);
+		assert_eq!(proof_check.child_storage(&subtrie[..], &[42]).unwrap().unwrap(), vec![42; 40]);
+	}
+
+	#[test]
+	fn proof_size_matches_recorded_proof() {
+		let contents = (0..64).map(|i| (None, vec![i], Some(vec![i; 40]))).collect::<Vec<_>>();
+		let mut in_memory = InMemory::<Blake2Hasher>::default().update(contents);
+		let trie = in_memory.as_trie_backend().unwrap();
+
+		let keys = vec![vec![1], vec![42], vec![1], vec![100]];
+		let proving = ProvingBackend::new(trie);
+		keys.iter().for_each(|key| { proving.storage(key).unwrap(); });
+		let proof = merge_storage_proofs(vec![proving.extract_proof()]);
+
+		let size = storage_proof_size(trie, keys.into_iter().map(|key| (None, key))).unwrap();
+		assert_eq!(size, proof.encode().len() as u64);
+		assert_eq!(storage_proof_size(trie, std::iter::empty()).unwrap(), 1);
+	}
+
 	#[test]
 	fn proof_recorded_and_checked_with_child() {
 		let subtrie1 = ChildStorageKey::from_slice(b":child_storage:default:sub1").unwrap();
@@ -459,6 +681,18 @@ mod tests {
 			proof_check.child_storage(&own1[..], &[64]).unwrap().unwrap(),
 			vec![64]
 		);
+
+		// only the requested page of child keys is recorded.
+		let proving = ProvingBackend::new(trie);
+		let page = proving.child_keys_paged(&own1[..], &[], Some(&[40]), 3);
+		assert_eq!(page, vec![vec![41], vec![42], vec![43]]);
+
+		let proof = proving.extract_proof();
+		let proof_check = create_proof_check_backend::<Blake2Hasher>(
+			in_memory_root.into(),
+			proof
+		).unwrap();
+		assert_eq!(proof_check.child_keys_paged(&own1[..], &[], Some(&[40]), 3), page);
 	}
 
 }
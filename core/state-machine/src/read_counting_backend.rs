@@ -0,0 +1,188 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State backend wrapper counting storage reads.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use hash_db::Hasher;
+use parking_lot::Mutex;
+use crate::backend::Backend;
+
+/// Number of storage reads performed through a [`ReadCountingBackend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadStats {
+	/// Number of keys read.
+	pub reads: u64,
+	/// Total size of the values read.
+	pub bytes: u64,
+}
+
+/// Wraps a state backend and counts the storage reads made through it.
+///
+/// Only direct reads of keys are counted, iteration over keys isn't.
+#[derive(Debug)]
+pub struct ReadCountingBackend<'a, S> {
+	backend: &'a S,
+	reads: AtomicU64,
+	bytes: AtomicU64,
+	/// The distinct keys read, as `(child storage key, key)`, if recorded.
+	read_keys: Option<Mutex<HashSet<(Option<Vec<u8>>, Vec<u8>)>>>,
+}
+
+impl<'a, S> ReadCountingBackend<'a, S> {
+	/// Wrap the given backend.
+	pub fn new(backend: &'a S) -> Self {
+		ReadCountingBackend {
+			backend,
+			reads: AtomicU64::new(0),
+			bytes: AtomicU64::new(0),
+			read_keys: None,
+		}
+	}
+
+	/// Wrap the given backend, also recording the distinct keys read.
+	pub fn with_read_keys(backend: &'a S) -> Self {
+		ReadCountingBackend {
+			read_keys: Some(Mutex::new(HashSet::new())),
+			..Self::new(backend)
+		}
+	}
+
+	/// Returns the reads counted so far.
+	pub fn stats(&self) -> ReadStats {
+		ReadStats {
+			reads: self.reads.load(Ordering::Relaxed),
+			bytes: self.bytes.load(Ordering::Relaxed),
+		}
+	}
+
+	/// Returns the distinct keys read so far, as `(child storage key, key)`.
+	///
+	/// Empty unless the backend was created with [`ReadCountingBackend::with_read_keys`].
+	pub fn read_keys(&self) -> Vec<(Option<Vec<u8>>, Vec<u8>)> {
+		self.read_keys.as_ref()
+			.map(|keys| keys.lock().iter().cloned().collect())
+			.unwrap_or_default()
+	}
+
+	fn count<E>(
+		&self,
+		storage_key: Option<&[u8]>,
+		key: &[u8],
+		value: Result<Option<Vec<u8>>, E>,
+	) -> Result<Option<Vec<u8>>, E> {
+		if let Some(keys) = &self.read_keys {
+			keys.lock().insert((storage_key.map(|k| k.to_vec()), key.to_vec()));
+		}
+		self.reads.fetch_add(1, Ordering::Relaxed);
+		if let Ok(Some(value)) = &value {
+			self.bytes.fetch_add(value.len() as u64, Ordering::Relaxed);
+		}
+		value
+	}
+}
+
+impl<'a, S: Backend<H>, H: Hasher> Backend<H> for ReadCountingBackend<'a, S> {
+	type Error = S::Error;
+	type Transaction = S::Transaction;
+	type TrieBackendStorage = S::TrieBackendStorage;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.count(None, key, self.backend.storage(key))
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.count(Some(storage_key), key, self.backend.child_storage(storage_key, key))
+	}
+
+	fn for_keys_in_child_storage<F: FnMut(&[u8])>(&self, storage_key: &[u8], f: F) {
+		self.backend.for_keys_in_child_storage(storage_key, f)
+	}
+
+	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
+		self.backend.for_keys_with_prefix(prefix, f)
+	}
+
+	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], f: F) {
+		self.backend.for_key_values_with_prefix(prefix, f)
+	}
+
+	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(&self, storage_key: &[u8], prefix: &[u8], f: F) {
+		self.backend.for_child_keys_with_prefix(storage_key, prefix, f)
+	}
+
+	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
+	where
+		I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+		H::Out: Ord,
+	{
+		self.backend.storage_root(delta)
+	}
+
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> (Vec<u8>, bool, Self::Transaction)
+	where
+		I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+		H::Out: Ord,
+	{
+		self.backend.child_storage_root(storage_key, delta)
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.backend.pairs()
+	}
+
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		self.backend.keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		self.backend.child_keys_paged(child_storage_key, prefix, start_key, count)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::Blake2Hasher;
+	use crate::backend::InMemory;
+
+	#[test]
+	fn counts_reads() {
+		let backend: InMemory<Blake2Hasher> = vec![(b"key".to_vec(), b"value".to_vec())]
+			.into_iter()
+			.collect::<std::collections::HashMap<_, _>>()
+			.into();
+		let counting = ReadCountingBackend::new(&backend);
+
+		assert_eq!(counting.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(counting.storage(b"missing").unwrap(), None);
+		assert!(counting.exists_storage(b"key").unwrap());
+		assert_eq!(counting.stats(), ReadStats { reads: 3, bytes: 10 });
+		assert!(counting.read_keys().is_empty());
+
+		let counting = ReadCountingBackend::with_read_keys(&backend);
+		counting.storage(b"key").unwrap();
+		counting.storage(b"key").unwrap();
+		assert_eq!(counting.read_keys(), vec![(None, b"key".to_vec())]);
+	}
+}
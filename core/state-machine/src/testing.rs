@@ -72,7 +72,6 @@ impl<H: Hasher<Out=H256>, N: ChangesTrieBlockNumber> TestExternalities<H, N> {
 		super::set_changes_trie_config(
 			&mut overlay,
 			storage.0.get(&CHANGES_TRIE_CONFIG.to_vec()).cloned(),
-			false,
 		).expect("changes trie configuration is correct in test env; qed");
 
 		storage.0.insert(HEAP_PAGES.to_vec(), 8u64.encode());
@@ -138,6 +138,20 @@ impl<S: TrieBackendStorage<H>, H: Hasher> Backend<H> for TrieBackend<S, H> where
 		collect_all().map_err(|e| debug!(target: "trie", "Error extracting trie keys: {}", e)).unwrap_or_default()
 	}
 
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		self.essence.keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys_paged(
+		&self,
+		child_storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		self.essence.child_keys_paged(child_storage_key, prefix, start_key, count)
+	}
+
 	fn storage_root<I>(&self, delta: I) -> (H::Out, S::Overlay)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
@@ -295,4 +309,42 @@ pub mod tests {
 		expected.insert(b"value2".to_vec());
 		assert_eq!(seen, expected);
 	}
+
+	#[test]
+	fn keys_paged_works() {
+		let trie = test_trie();
+
+		assert_eq!(trie.keys_paged(b"value", None, 1), vec![b"value1".to_vec()]);
+		assert_eq!(trie.keys_paged(b"value", Some(b"value1"), 10), vec![b"value2".to_vec()]);
+		assert_eq!(trie.keys_paged(b"value", Some(b"value2"), 10), Vec::<Vec<u8>>::new());
+		// a start key before the prefix is ignored.
+		assert_eq!(trie.keys_paged(b"value", Some(b"key"), 10), vec![b"value1".to_vec(), b"value2".to_vec()]);
+
+		let all = trie.keys_paged(b"", None, usize::max_value());
+		let mut paged = Vec::new();
+		let mut start: Option<Vec<u8>> = None;
+		loop {
+			let page = trie.keys_paged(b"", start.as_ref().map(|k| &k[..]), 7);
+			match page.last() {
+				Some(last) => start = Some(last.clone()),
+				None => break,
+			}
+			paged.extend(page);
+		}
+		assert_eq!(paged, all);
+		assert_eq!(all.len(), trie.keys(b"").len());
+	}
+
+	#[test]
+	fn child_keys_paged_works() {
+		let trie = test_trie();
+		let child = b":child_storage:default:sub1";
+		assert_eq!(trie.child_keys_paged(child, b"value", None, 1), vec![b"value3".to_vec()]);
+		assert_eq!(trie.child_keys_paged(child, b"value", Some(b"value3"), 10), vec![b"value4".to_vec()]);
+		assert_eq!(trie.child_keys_paged(child, b"", Some(b"value4"), 10), Vec::<Vec<u8>>::new());
+		assert_eq!(
+			trie.child_keys_paged(b":child_storage:default:missing", b"", None, 10),
+			Vec::<Vec<u8>>::new(),
+		);
+	}
 }
@@ -23,7 +23,7 @@ use log::{debug, warn};
 use hash_db::{self, Hasher, EMPTY_PREFIX, Prefix};
 use trie::{Trie, MemoryDB, PrefixedMemoryDB, DBValue,
 	default_child_trie_root, read_trie_value, read_child_trie_value,
-	for_keys_in_child_trie};
+	for_keys_in_child_trie, trie_keys_paged};
 use trie::trie_types::{TrieDB, TrieError, Layout};
 use crate::backend::Consolidate;
 
@@ -133,6 +133,52 @@ impl<S: TrieBackendStorage<H>, H: Hasher> TrieBackendEssence<S, H> {
 		self.keys_values_with_prefix_inner(&root, prefix, |k, _v| f(k))
 	}
 
+	/// Returns at most `count` keys starting with `prefix`, in lexicographic order, strictly after
+	/// `start_key` if given.
+	pub fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Vec<Vec<u8>> {
+		self.keys_paged_inner(&self.root, prefix, start_key, count)
+	}
+
+	/// Returns at most `count` keys of child storage starting with `prefix`, in lexicographic order,
+	/// strictly after `start_key` if given.
+	pub fn child_keys_paged(
+		&self,
+		storage_key: &[u8],
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		let root_vec = match self.storage(storage_key) {
+			Ok(v) => v.unwrap_or(default_child_trie_root::<Layout<H>>(storage_key)),
+			Err(e) => {
+				debug!(target: "trie", "Error while iterating child storage: {}", e);
+				return Vec::new();
+			}
+		};
+		let mut root = H::Out::default();
+		root.as_mut().copy_from_slice(&root_vec);
+
+		self.keys_paged_inner(&root, prefix, start_key, count)
+	}
+
+	fn keys_paged_inner(
+		&self,
+		root: &H::Out,
+		prefix: &[u8],
+		start_key: Option<&[u8]>,
+		count: usize,
+	) -> Vec<Vec<u8>> {
+		let mut read_overlay = S::Overlay::default();
+		let eph = Ephemeral {
+			storage: &self.storage,
+			overlay: &mut read_overlay,
+		};
+
+		trie_keys_paged::<Layout<H>, _>(&eph, root, prefix, start_key, count, None)
+			.map_err(|e| debug!(target: "trie", "Error extracting trie keys: {}", e))
+			.unwrap_or_default()
+	}
+
 	/// Execute given closure for all keys starting with prefix.
 	pub fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], mut f: F) {
 		self.keys_values_with_prefix_inner(&self.root, prefix, |k, _v| f(k))
@@ -145,14 +145,7 @@ struct TelemetryDrain {
 /// Please be careful to not call this function twice in the same program. The `slog` crate
 /// doesn't provide any way of knowing whether a global logger has already been registered.
 pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
-	// Build the list of telemetry endpoints.
-	let mut endpoints = Vec::new();
-	for &(ref url, verbosity) in &config.endpoints.0 {
-		match url_to_multiaddr(url) {
-			Ok(addr) => endpoints.push((addr, verbosity)),
-			Err(err) => warn!(target: "telemetry", "Invalid telemetry URL {}: {}", url, err),
-		}
-	}
+	let endpoints = endpoints_to_multiaddrs(&config.endpoints);
 
 	let (sender, receiver) = mpsc::channel(16);
 	let guard = {
@@ -170,6 +163,17 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 	}
 }
 
+impl Telemetry {
+	/// Replaces the telemetry servers we talk to.
+	///
+	/// The connections to the previous servers are closed. The external transport given at
+	/// initialization, if any, isn't used for the new servers.
+	pub fn set_endpoints(&self, endpoints: TelemetryEndpoints) {
+		let endpoints = endpoints_to_multiaddrs(&endpoints);
+		self.inner.lock().worker = worker::TelemetryWorker::new(endpoints, None);
+	}
+}
+
 /// Event generated when polling the worker.
 #[derive(Debug)]
 pub enum TelemetryEvent {
@@ -256,6 +260,18 @@ impl slog::Drain for TelemetryDrain {
 	}
 }
 
+/// Builds the list of telemetry endpoints, skipping the invalid URLs.
+fn endpoints_to_multiaddrs(endpoints: &TelemetryEndpoints) -> Vec<(Multiaddr, u8)> {
+	let mut addrs = Vec::new();
+	for &(ref url, verbosity) in &endpoints.0 {
+		match url_to_multiaddr(url) {
+			Ok(addr) => addrs.push((addr, verbosity)),
+			Err(err) => warn!(target: "telemetry", "Invalid telemetry URL {}: {}", url, err),
+		}
+	}
+	addrs
+}
+
 /// Parses a WebSocket URL into a libp2p `Multiaddr`.
 fn url_to_multiaddr(url: &str) -> Result<Multiaddr, libp2p::multiaddr::Error> {
 	// First, assume that we have a `Multiaddr`.
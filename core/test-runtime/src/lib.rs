@@ -37,7 +37,7 @@ use substrate_client::{
 	impl_runtime_apis,
 };
 use sr_primitives::{
-	ApplyResult, create_runtime_str, Perbill, impl_opaque_keys,
+	ApplyResult, create_runtime_str, Perbill, impl_opaque_keys, KeyTypeId,
 	transaction_validity::{
 		TransactionValidity, ValidTransaction, TransactionValidityError, InvalidTransaction,
 	},
@@ -398,6 +398,11 @@ impl srml_babe::Trait for Runtime {
 	// are manually adding the digests. normally in this situation you'd use
 	// srml_babe::SameAuthoritiesForever.
 	type EpochChangeTrigger = srml_babe::ExternalTrigger;
+	// equivocations aren't reported in the test runtime.
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof = runtime_support::Void;
+	type KeyOwnerIdentification = ();
+	type ReportEquivocation = ();
 }
 
 /// Adds one to the given input and returns the final result.
@@ -493,6 +498,13 @@ cfg_if! {
 
 					system::validate_transaction(utx)
 				}
+
+				fn transaction_sender(utx: <Block as BlockT>::Extrinsic) -> Option<Vec<u8>> {
+					match utx {
+						Extrinsic::Transfer(transfer, _) => Some(transfer.from.encode()),
+						_ => None,
+					}
+				}
 			}
 
 			impl block_builder_api::BlockBuilder<Block> for Runtime {
@@ -621,6 +633,19 @@ cfg_if! {
 						secondary_slots: true,
 					}
 				}
+
+				fn generate_key_ownership_proof(
+					_authority_id: babe_primitives::AuthorityId,
+				) -> Option<babe_primitives::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: babe_primitives::EquivocationProof<<Block as BlockT>::Header>,
+					_key_owner_proof: babe_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<<Block as BlockT>::Extrinsic> {
+					None
+				}
 			}
 
 			impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -634,6 +659,10 @@ cfg_if! {
 				fn generate_session_keys(_: Option<Vec<u8>>) -> Vec<u8> {
 					SessionKeys::generate(None)
 				}
+
+				fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+					SessionKeys::decode_into_raw_public_keys(&encoded)
+				}
 			}
 
 			impl srml_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -678,6 +707,13 @@ cfg_if! {
 
 					system::validate_transaction(utx)
 				}
+
+				fn transaction_sender(utx: <Block as BlockT>::Extrinsic) -> Option<Vec<u8>> {
+					match utx {
+						Extrinsic::Transfer(transfer, _) => Some(transfer.from.encode()),
+						_ => None,
+					}
+				}
 			}
 
 			impl block_builder_api::BlockBuilder<Block> for Runtime {
@@ -837,6 +873,19 @@ cfg_if! {
 						secondary_slots: true,
 					}
 				}
+
+				fn generate_key_ownership_proof(
+					_authority_id: babe_primitives::AuthorityId,
+				) -> Option<babe_primitives::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: babe_primitives::EquivocationProof<<Block as BlockT>::Header>,
+					_key_owner_proof: babe_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<<Block as BlockT>::Extrinsic> {
+					None
+				}
 			}
 
 			impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -850,6 +899,10 @@ cfg_if! {
 				fn generate_session_keys(_: Option<Vec<u8>>) -> Vec<u8> {
 					SessionKeys::generate(None)
 				}
+
+				fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+					SessionKeys::decode_into_raw_public_keys(&encoded)
+				}
 			}
 
 			impl srml_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -19,10 +19,12 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	cmp::Ordering,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
+	time,
 };
 
 use log::{trace, debug, warn};
@@ -33,6 +35,7 @@ use sr_primitives::transaction_validity::{
 	TransactionTag as Tag,
 	TransactionLongevity as Longevity,
 	TransactionPriority as Priority,
+	OPERATIONAL_PRIORITY,
 };
 
 use crate::error;
@@ -109,6 +112,15 @@ impl<Hash, Extrinsic> Transaction<Hash, Extrinsic> {
 	pub fn is_propagateable(&self) -> bool {
 		self.propagate
 	}
+
+	/// Returns `true` if the transaction is operational.
+	///
+	/// The runtime gives the reserved `OPERATIONAL_PRIORITY` to operational transactions, like
+	/// heartbeats or misbehavior reports. They are only evicted from the pool when there are no
+	/// normal transactions left to evict.
+	pub fn is_operational(&self) -> bool {
+		self.priority == OPERATIONAL_PRIORITY
+	}
 }
 
 impl<Hash, Extrinsic> fmt::Debug for Transaction<Hash, Extrinsic> where
@@ -167,6 +179,13 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// Maximal number of transactions of a single sender.
+	max_per_sender: Option<usize>,
+	/// Hashes of the transactions of each sender, by encoded sender.
+	///
+	/// The hashes of the transactions that left the pool are removed lazily, see
+	/// `forget_removed_senders`.
+	senders: HashMap<Vec<u8>, HashSet<Hash>>,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for BasePool<Hash, Ex> {
@@ -176,6 +195,23 @@ impl<Hash: hash::Hash + Eq, Ex> Default for BasePool<Hash, Ex> {
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			max_per_sender: None,
+			senders: Default::default(),
+		}
+	}
+}
+
+impl<Hash: hash::Hash + Member + Serialize, Ex> BasePool<Hash, Ex> {
+	/// Creates an empty pool.
+	///
+	/// At most `max_per_sender` transactions of a single sender are accepted, see
+	/// `import_from`. A transaction replacing the ones providing the same tags needs a priority at least
+	/// `replacement_bump` percent higher than theirs.
+	pub fn new(max_per_sender: Option<usize>, replacement_bump: u32) -> Self {
+		BasePool {
+			ready: ReadyTransactions::new(replacement_bump),
+			max_per_sender,
+			..Default::default()
 		}
 	}
 }
@@ -191,11 +227,31 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 	pub fn import(
 		&mut self,
 		tx: Transaction<Hash, Ex>,
+	) -> error::Result<Imported<Hash, Ex>> {
+		self.import_from(tx, None)
+	}
+
+	/// Imports transaction of given sender to the pool.
+	///
+	/// The transaction is rejected if the sender already has `max_per_sender` transactions in
+	/// the pool, not counting the ones it would replace. The transactions of unknown senders
+	/// are not limited.
+	pub fn import_from(
+		&mut self,
+		tx: Transaction<Hash, Ex>,
+		sender: Option<Vec<u8>>,
 	) -> error::Result<Imported<Hash, Ex>> {
 		if self.future.contains(&tx.hash) || self.ready.contains(&tx.hash) {
 			return Err(error::Error::AlreadyImported(Box::new(tx.hash.clone())))
 		}
 
+		if let (Some(limit), Some(sender)) = (self.max_per_sender, sender.as_ref()) {
+			if self.count_sender_transactions(sender, &tx) >= limit {
+				debug!(target: "txpool", "[{:?}] Too many transactions from the sender", tx.hash);
+				return Err(error::Error::TooManyFromSender { limit })
+			}
+		}
+
 		let tx = WaitingTransaction::new(
 			tx,
 			self.ready.provided_tags(),
@@ -204,14 +260,19 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 		trace!(target: "txpool", "[{:?}] {:?}", tx.transaction.hash, tx);
 		debug!(target: "txpool", "[{:?}] Importing to {}", tx.transaction.hash, if tx.is_ready() { "ready" } else { "future" });
 
+		let hash = tx.transaction.hash.clone();
 		// If all tags are not satisfied import to future.
-		if !tx.is_ready() {
-			let hash = tx.transaction.hash.clone();
+		let imported = if !tx.is_ready() {
 			self.future.import(tx);
-			return Ok(Imported::Future { hash });
-		}
+			Imported::Future { hash: hash.clone() }
+		} else {
+			self.import_to_ready(tx)?
+		};
 
-		self.import_to_ready(tx)
+		if let Some(sender) = sender {
+			self.senders.entry(sender).or_insert_with(HashSet::new).insert(hash);
+		}
+		Ok(imported)
 	}
 
 	/// Imports transaction to ready queue.
@@ -279,6 +340,30 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 		})
 	}
 
+	/// Counts the transactions of `sender` in the pool, except the ones `tx` would replace.
+	fn count_sender_transactions(&mut self, sender: &[u8], tx: &Transaction<Hash, Ex>) -> usize {
+		let (ready, future) = (&self.ready, &self.future);
+		let hashes = match self.senders.get_mut(sender) {
+			Some(hashes) => hashes,
+			None => return 0,
+		};
+		hashes.retain(|hash| ready.contains(hash) || future.contains(hash));
+
+		let replaced = tx.provides.iter()
+			.filter_map(|tag| ready.provided_tags().get(tag))
+			.collect::<HashSet<_>>();
+		hashes.iter().filter(|hash| !replaced.contains(hash)).count()
+	}
+
+	/// Removes the hashes of the transactions that left the pool from the index of senders.
+	fn forget_removed_senders(&mut self) {
+		let (ready, future) = (&self.ready, &self.future);
+		self.senders.retain(|_, hashes| {
+			hashes.retain(|hash| ready.contains(hash) || future.contains(hash));
+			!hashes.is_empty()
+		});
+	}
+
 	/// Returns an iterator over ready transactions in the pool.
 	pub fn ready(&self) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
 		self.ready.get()
@@ -308,7 +393,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 	///
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
-	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
+	/// We use a simplified approach, see `eviction_order`, and remove the oldest transaction when
+	/// several are equally bad.
 	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
 
@@ -319,7 +405,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 					let transaction = &current.transaction;
 					match minimal {
 						None => Some(transaction.clone()),
-						Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
+						Some(ref tx) if eviction_order(&tx.transaction, &transaction.transaction)
+							.then(tx.insertion_id.cmp(&transaction.insertion_id)) == Ordering::Greater => {
 							Some(transaction.clone())
 						},
 						other => other,
@@ -339,7 +426,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 				.fold(|minimal, current| {
 					match minimal {
 						None => Some(current.clone()),
-						Some(ref tx) if tx.imported_at > current.imported_at => {
+						Some(ref tx) if eviction_order(&tx.transaction, &current.transaction)
+							.then(tx.imported_at.cmp(&current.imported_at)) == Ordering::Greater => {
 							Some(current.clone())
 						},
 						other => other,
@@ -356,6 +444,13 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 		removed
 	}
 
+	/// Removes the transactions waiting in the future queue since before `deadline`.
+	///
+	/// Returns a list of removed transactions.
+	pub fn remove_future_older_than(&mut self, deadline: time::Instant) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		self.future.remove_older_than(deadline)
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -405,6 +500,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: ::std::fmt::Debug> BasePool<Hash
 			}
 		}
 
+		self.forget_removed_senders();
+
 		PruneStatus {
 			pruned,
 			failed,
@@ -443,7 +540,23 @@ impl Status {
 	}
 }
 
+/// Order in which transactions are evicted from a full queue, the first ones first.
+///
+/// Normal transactions are evicted before operational ones, and the ones with the lowest priority
+/// per byte first, so that a few large transactions can't take the whole queue.
+fn eviction_order<Hash, Ex>(a: &Transaction<Hash, Ex>, b: &Transaction<Hash, Ex>) -> Ordering {
+	a.is_operational().cmp(&b.is_operational()).then_with(|| {
+		// compare `a.priority / a.bytes` with `b.priority / b.bytes`
+		let a_priority = u128::from(a.priority) * b.bytes.max(1) as u128;
+		let b_priority = u128::from(b.priority) * a.bytes.max(1) as u128;
+		a_priority.cmp(&b_priority)
+	})
+}
+
 /// Queue limits
+///
+/// When a queue exceeds either its count or its size, transactions are evicted from it until it
+/// fits again.
 #[derive(Debug, Clone)]
 pub struct Limit {
 	/// Maximal number of transactions in the queue.
@@ -491,6 +604,68 @@ mod tests {
 		assert_eq!(pool.ready.len(), 1);
 	}
 
+	#[test]
+	fn should_evict_normal_transactions_before_operational_ones() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, priority: u64| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires: vec![],
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+		};
+		pool.import(tx(1, OPERATIONAL_PRIORITY)).unwrap();
+		pool.import(tx(2, 5)).unwrap();
+		pool.import(tx(3, 5)).unwrap();
+		assert!(pool.ready().next().unwrap().is_operational());
+
+		// when
+		let limit = Limit { count: 1, total_bytes: 64 };
+		let removed = pool.enforce_limits(&limit, &limit);
+
+		// then
+		assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2, 3]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
+	#[test]
+	fn should_evict_transactions_with_lowest_priority_per_byte() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, priority: u64, bytes: usize| Transaction {
+			data: vec![hash as u8],
+			bytes,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires: vec![],
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+		};
+		pool.import(tx(1, 10, 1)).unwrap();
+		pool.import(tx(2, 100, 50)).unwrap();
+		pool.import(tx(3, 5, 1)).unwrap();
+
+		// when
+		let limit = Limit { count: 10, total_bytes: 10 };
+		let removed = pool.enforce_limits(&limit, &limit);
+
+		// then
+		assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+		assert_eq!(pool.status().ready_bytes, 2);
+
+		// when
+		let limit = Limit { count: 1, total_bytes: 10 };
+		let removed = pool.enforce_limits(&limit, &limit);
+
+		// then
+		assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+	}
+
 	#[test]
 	fn should_not_import_same_transaction_twice() {
 		// given
@@ -929,6 +1104,86 @@ mod tests {
 		assert_eq!(pool.ready().count(), 3);
 	}
 
+	#[test]
+	fn should_limit_transactions_per_sender() {
+		// given
+		let mut pool = BasePool::new(Some(2), 0);
+		let tx = |hash: u64, requires: Vec<Vec<u8>>, provides: Vec<u8>| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires,
+			provides: vec![provides],
+			propagate: true,
+		};
+		let alice = Some(b"alice".to_vec());
+		let bob = Some(b"bob".to_vec());
+		pool.import_from(tx(1, vec![], vec![1]), alice.clone()).unwrap();
+		pool.import_from(tx(2, vec![vec![1]], vec![2]), alice.clone()).unwrap();
+
+		// when
+		let ready = pool.import_from(tx(3, vec![vec![2]], vec![3]), alice.clone());
+		let replacing = pool.import_from(Transaction { priority: 6, ..tx(4, vec![], vec![1]) }, alice.clone());
+		let other = pool.import_from(tx(5, vec![], vec![20]), bob.clone());
+		let unknown = pool.import_from(tx(6, vec![], vec![30]), None);
+
+		// then the transactions it would replace are not counted
+		assert!(match ready { Err(error::Error::TooManyFromSender { limit: 2 }) => true, _ => false });
+		assert!(replacing.is_ok());
+		assert!(other.is_ok());
+		assert!(unknown.is_ok());
+
+		// given transactions of a sender with gaps between their nonces
+		pool.import_from(tx(10, vec![vec![109]], vec![110]), bob.clone()).unwrap();
+
+		// when
+		let gap = pool.import_from(tx(12, vec![vec![111]], vec![112]), bob.clone());
+
+		// then they are all counted
+		assert!(match gap { Err(error::Error::TooManyFromSender { limit: 2 }) => true, _ => false });
+		assert_eq!(pool.future.len(), 1);
+
+		// when a transaction of the sender leaves the pool
+		pool.remove_invalid(&[5]);
+
+		// then another one is accepted
+		assert!(pool.import_from(tx(12, vec![vec![111]], vec![112]), bob.clone()).is_ok());
+		assert_eq!(pool.future.len(), 2);
+	}
+
+	#[test]
+	fn should_require_priority_bump_to_replace() {
+		// given
+		let mut pool = BasePool::new(None, 10);
+		let tx = |hash: u64, priority: u64| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires: vec![],
+			provides: vec![vec![1]],
+			propagate: true,
+		};
+		pool.import(tx(1, 100)).unwrap();
+
+		// when
+		let too_low = pool.import(tx(2, 109));
+		let replaced = pool.import(tx(3, 110)).unwrap();
+
+		// then
+		assert!(match too_low { Err(error::Error::TooLowPriority { old: 100, new: 109 }) => true, _ => false });
+		assert_eq!(replaced, Imported::Ready {
+			hash: 3,
+			promoted: vec![],
+			failed: vec![],
+			removed: vec![Arc::new(tx(1, 100))],
+		});
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+	}
+
 	#[test]
 	fn transaction_debug() {
 		assert_eq!(
@@ -52,6 +52,12 @@ pub enum Error {
 		/// Transaction entering the pool.
 		new: Priority
 	},
+	/// The sender already has too many transactions in the pool.
+	#[display(fmt="Too many transactions from the sender (limit {})", limit)]
+	TooManyFromSender {
+		/// Maximal number of transactions of a single sender.
+		limit: usize,
+	},
 	/// Deps cycle etected and we couldn't import transaction.
 	#[display(fmt="Cycle Detected")]
 	CycleDetected,
@@ -215,6 +215,17 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		removed
 	}
 
+	/// Removes the transactions imported to the queue before `deadline`.
+	///
+	/// Returns a list of removed transactions.
+	pub fn remove_older_than(&mut self, deadline: time::Instant) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let hashes = self.waiting.values()
+			.filter(|waiting| waiting.imported_at < deadline)
+			.map(|waiting| waiting.transaction.hash.clone())
+			.collect::<Vec<_>>();
+		self.remove(&hashes)
+	}
+
 	/// Fold a list of future transactions to compute a single value.
 	pub fn fold<R, F: FnMut(Option<R>, &WaitingTransaction<Hash, Ex>) -> Option<R>>(&mut self, f: F) -> Option<R> {
 		self.waiting
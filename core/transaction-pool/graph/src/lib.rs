@@ -26,6 +26,7 @@
 
 mod future;
 mod listener;
+mod metrics;
 mod pool;
 mod ready;
 mod rotator;
@@ -37,6 +38,7 @@ pub mod watcher;
 
 pub use self::error::IntoPoolError;
 pub use self::base_pool::{Transaction, Status};
+pub use self::metrics::Metrics;
 pub use self::pool::{
 	Pool,
 	Options, ChainApi, EventStream, ExtrinsicFor,
@@ -26,18 +26,22 @@ use log::warn;
 
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, H2> {
-	watchers: HashMap<H, watcher::Sender<H, H2>>
+	watchers: HashMap<H, watcher::Sender<H, H2>>,
+	/// Watched extrinsics included in each block, along with the number of the block, waiting
+	/// for the block to be finalized.
+	finality_watchers: HashMap<H2, (u64, Vec<H>)>,
 }
 
 impl<H: hash::Hash + Eq, H2> Default for Listener<H, H2> {
 	fn default() -> Self {
 		Listener {
 			watchers: Default::default(),
+			finality_watchers: Default::default(),
 		}
 	}
 }
 
-impl<H: hash::Hash + traits::Member + Serialize, H2: Clone> Listener<H, H2> {
+impl<H: hash::Hash + traits::Member + Serialize, H2: hash::Hash + Eq + Clone> Listener<H, H2> {
 	fn fire<F>(&mut self, hash: &H, fun: F) where F: FnOnce(&mut watcher::Sender<H, H2>) {
 		let clean = if let Some(h) = self.watchers.get_mut(hash) {
 			fun(h);
@@ -91,8 +95,73 @@ impl<H: hash::Hash + traits::Member + Serialize, H2: Clone> Listener<H, H2> {
 		self.fire(tx, |watcher| watcher.invalid());
 	}
 
-	/// Transaction was pruned from the pool.
-	pub fn pruned(&mut self, header_hash: H2, tx: &H) {
-		self.fire(tx, |watcher| watcher.finalized(header_hash))
+	/// Transaction was pruned from the pool, because it got included in the given block.
+	pub fn pruned(&mut self, header_hash: H2, block_number: u64, tx: &H) {
+		self.fire(tx, |watcher| watcher.in_block(header_hash.clone()));
+		if self.watchers.contains_key(tx) {
+			self.finality_watchers.entry(header_hash)
+				.or_insert_with(|| (block_number, Vec::new()))
+				.1
+				.push(tx.clone());
+		}
+	}
+
+	/// The given block has been retracted, its transactions are no longer included.
+	pub fn retracted(&mut self, block_hash: H2) {
+		if let Some((_, hashes)) = self.finality_watchers.remove(&block_hash) {
+			for hash in hashes {
+				self.fire(&hash, |watcher| watcher.retracted(block_hash.clone()));
+			}
+		}
+		self.prune_unwatched();
+	}
+
+	/// The given block has been finalized, along with its transactions.
+	pub fn finalized(&mut self, block_hash: H2, block_number: u64) {
+		if let Some((_, hashes)) = self.finality_watchers.remove(&block_hash) {
+			for hash in hashes {
+				self.fire(&hash, |watcher| watcher.finalized(block_hash.clone()));
+			}
+		}
+
+		// the blocks of the other forks up to the finalized one won't ever be finalized
+		self.finality_watchers.retain(|_, (number, _)| *number > block_number);
+		self.prune_unwatched();
+	}
+
+	/// Forgets the extrinsics whose watchers have all unsubscribed.
+	fn prune_unwatched(&mut self) {
+		self.watchers.retain(|_, watcher| !watcher.is_done());
+		let watchers = &self.watchers;
+		self.finality_watchers.retain(|_, (_, hashes)| {
+			hashes.retain(|hash| watchers.contains_key(hash));
+			!hashes.is_empty()
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_forget_stale_and_unwatched_blocks_on_finalization() {
+		// given
+		let mut listener = Listener::<u64, u64>::default();
+		let _finalized = listener.create_watcher(1);
+		let unwatched = listener.create_watcher(2);
+		let _stale = listener.create_watcher(3);
+		listener.pruned(10, 1, &1);
+		listener.pruned(20, 2, &2);
+		listener.pruned(11, 1, &3);
+		assert_eq!(listener.finality_watchers.len(), 3);
+
+		// when
+		drop(unwatched);
+		listener.finalized(10, 1);
+
+		// then the fork at the finalized height and the unsubscribed watcher are forgotten
+		assert!(listener.finality_watchers.is_empty());
+		assert_eq!(listener.watchers.keys().collect::<Vec<_>>(), vec![&3]);
 	}
 }
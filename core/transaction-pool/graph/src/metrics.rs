@@ -0,0 +1,100 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Metrics of the transaction pool.
+//!
+//! The node doesn't have a Prometheus registry (yet), so these metrics are not exported
+//! through a metrics endpoint. Instead the service reports them periodically to the telemetry,
+//! as the `txpool.metrics` message, next to the other node metrics of `system.interval`.
+//! Once a metrics registry exists, `Pool::metrics` is the single place to read them from.
+
+use std::{
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
+
+use crate::base_pool::Status;
+
+/// Metrics of a transaction pool.
+///
+/// Counters are totals since the pool was created, rates are left to the consumers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+	/// Number of transactions in the ready queue.
+	pub ready: usize,
+	/// Number of transactions in the future queue.
+	pub future: usize,
+	/// Number of transactions submitted to the pool.
+	pub submitted: u64,
+	/// Number of transactions validated by the runtime.
+	pub validations: u64,
+	/// Number of transactions currently banned from the pool.
+	pub banned: usize,
+	/// Number of transactions of the pool which got included in a block.
+	pub included: u64,
+	/// Total time between the import of these transactions and their inclusion, in milliseconds.
+	pub inclusion_latency_ms: u64,
+}
+
+impl Metrics {
+	/// Average time between the import of a transaction and its inclusion in a block.
+	pub fn average_inclusion_latency(&self) -> Option<Duration> {
+		if self.included == 0 {
+			return None
+		}
+		Some(Duration::from_millis(self.inclusion_latency_ms / self.included))
+	}
+}
+
+/// Counters of the activity of a transaction pool.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+	submitted: AtomicU64,
+	validations: AtomicU64,
+	included: AtomicU64,
+	inclusion_latency_ms: AtomicU64,
+}
+
+impl Counters {
+	/// Note that transactions were submitted to the pool.
+	pub fn note_submitted(&self, count: usize) {
+		self.submitted.fetch_add(count as u64, Ordering::Relaxed);
+	}
+
+	/// Note that a transaction is being validated by the runtime.
+	pub fn note_validation(&self) {
+		self.validations.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Note that a transaction got included in a block, the given time after its import.
+	pub fn note_included(&self, latency: Duration) {
+		self.included.fetch_add(1, Ordering::Relaxed);
+		self.inclusion_latency_ms.fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+	}
+
+	/// Metrics of the pool given its status and the number of banned transactions.
+	pub fn metrics(&self, status: Status, banned: usize) -> Metrics {
+		Metrics {
+			ready: status.ready,
+			future: status.future,
+			submitted: self.submitted.load(Ordering::Relaxed),
+			validations: self.validations.load(Ordering::Relaxed),
+			banned,
+			included: self.included.load(Ordering::Relaxed),
+			inclusion_latency_ms: self.inclusion_latency_ms.load(Ordering::Relaxed),
+		}
+	}
+}
@@ -18,11 +18,14 @@ use std::{
 	hash,
 	collections::HashMap,
 	sync::Arc,
+	time::{Duration, Instant},
 };
 
 use crate::base_pool as base;
 use crate::error;
 use crate::watcher::Watcher;
+use crate::rotator::DEFAULT_BAN_TIME;
+use crate::metrics::Metrics;
 use serde::Serialize;
 
 use futures::{
@@ -33,7 +36,9 @@ use futures::{
 use sr_primitives::{
 	generic::BlockId,
 	traits::{self, SaturatedConversion},
-	transaction_validity::{TransactionValidity, TransactionTag as Tag, TransactionValidityError},
+	transaction_validity::{
+		TransactionValidity, TransactionTag as Tag, TransactionValidityError, InvalidTransaction,
+	},
 };
 use crate::validated_pool::{ValidatedPool, ValidatedTransaction};
 
@@ -83,6 +88,15 @@ pub trait ChainApi: Send + Sync {
 
 	/// Returns hash and encoding length of the extrinsic.
 	fn hash_and_length(&self, uxt: &ExtrinsicFor<Self>) -> (Self::Hash, usize);
+
+	/// Returns an encoded identifier of the sender of the extrinsic, if it's known.
+	///
+	/// Used to ban the senders of invalid transactions, see `Options::ban_senders`, to limit the
+	/// transactions of a sender, see `Options::max_per_sender`, and to find the transactions of a
+	/// sender in `Pool::remove_sender`.
+	fn sender_of(&self, _uxt: &ExtrinsicFor<Self>) -> Option<Vec<u8>> {
+		None
+	}
 }
 
 /// Pool configuration options.
@@ -92,6 +106,34 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Maximal number of transactions of a single sender.
+	///
+	/// Senders are only known if the `ChainApi` implements `sender_of`, the transactions of
+	/// unknown senders are not limited.
+	pub max_per_sender: Option<usize>,
+	/// Percentage by which the priority of a transaction has to exceed the priority of
+	/// the transactions it replaces.
+	pub replacement_bump: u32,
+	/// How long invalid transactions are banned from entering the pool again.
+	///
+	/// Banned transactions are rejected without being validated again.
+	pub ban_time: Duration,
+	/// Ban the senders of transactions which can't pay for their inclusion as well.
+	///
+	/// Such transactions are validly signed, so the sender can't be impersonated. Senders are
+	/// only known if the `ChainApi` implements `sender_of`, which the full chain api does
+	/// through the `TaggedTransactionQueue` runtime api.
+	pub ban_senders: bool,
+	/// Maximal number of transactions revalidated in the background at once.
+	///
+	/// Transactions are not revalidated in the background if zero.
+	pub revalidation_batch: usize,
+	/// Interval at which batches of transactions are revalidated in the background.
+	pub revalidation_interval: Duration,
+	/// Maximal time a transaction can wait in the future queue, e.g. because of a nonce gap.
+	///
+	/// Older transactions are dropped when the pool is maintained. Unlimited if `None`.
+	pub future_max_age: Option<Duration>,
 }
 
 impl Default for Options {
@@ -105,6 +147,13 @@ impl Default for Options {
 				count: 128,
 				total_bytes: 1 * 1024 * 1024,
 			},
+			max_per_sender: None,
+			replacement_bump: 0,
+			ban_time: DEFAULT_BAN_TIME,
+			ban_senders: false,
+			revalidation_batch: 64,
+			revalidation_interval: Duration::from_secs(10),
+			future_max_age: Some(Duration::from_secs(60 * 30)),
 		}
 	}
 }
@@ -128,6 +177,8 @@ impl<B: ChainApi> Pool<B> {
 	where
 		T: IntoIterator<Item=ExtrinsicFor<B>>
 	{
+		let xts = xts.into_iter().collect::<Vec<_>>();
+		self.validated_pool.counters().note_submitted(xts.len());
 		let validated_pool = self.validated_pool.clone();
 		self.verify(at, xts, force)
 			.map(move |validated_transactions| validated_transactions
@@ -158,6 +209,7 @@ impl<B: ChainApi> Pool<B> {
 			Err(err) => return Either::Left(ready(Err(err)))
 		};
 
+		self.validated_pool.counters().note_submitted(1);
 		let validated_pool = self.validated_pool.clone();
 		Either::Right(
 			self.verify_one(at, block_number, xt, false)
@@ -273,6 +325,25 @@ impl<B: ChainApi> Pool<B> {
 			)))
 	}
 
+	/// Revalidates up to `max` transactions of the pool at the given block, the ones validated
+	/// the longest time ago first.
+	///
+	/// Transactions which became invalid are removed from the pool, like the ones beyond
+	/// their longevity period.
+	pub fn revalidate(
+		&self,
+		at: &BlockId<B::Block>,
+		max: usize,
+	) -> impl Future<Output=Result<(), B::Error>> {
+		let to_revalidate = self.validated_pool.revalidation_batch(max);
+		let validated_pool = self.validated_pool.clone();
+		let at = at.clone();
+		self.verify(&at, to_revalidate, true)
+			.map(move |revalidated| revalidated.and_then(|revalidated|
+				validated_pool.on_revalidated(&at, revalidated)
+			))
+	}
+
 	/// Return an event stream of transactions imported to the pool.
 	pub fn import_notification_stream(&self) -> EventStream {
 		self.validated_pool.import_notification_stream()
@@ -288,16 +359,47 @@ impl<B: ChainApi> Pool<B> {
 		self.validated_pool.remove_invalid(hashes)
 	}
 
+	/// Remove all the transactions of the given sender from the pool, and temporarily ban them.
+	///
+	/// Senders are only known if the `ChainApi` implements `sender_of`.
+	pub fn remove_sender(&self, sender: &[u8]) -> Vec<TransactionFor<B>> {
+		self.validated_pool.remove_sender(sender)
+	}
+
 	/// Get an iterator for ready transactions ordered by priority
 	pub fn ready(&self) -> impl Iterator<Item=TransactionFor<B>> {
 		self.validated_pool.ready()
 	}
 
+	/// Returns the metrics of the pool.
+	///
+	/// These are reported to the telemetry by the service, see the `metrics` module.
+	pub fn metrics(&self) -> Metrics {
+		self.validated_pool.metrics()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> base::Status {
 		self.validated_pool.status()
 	}
 
+	/// Returns pool transactions given list of hashes, for both the ready and future queues.
+	pub fn by_hash(&self, hashes: &[ExHash<B>]) -> Vec<Option<TransactionFor<B>>> {
+		self.validated_pool.by_hash(hashes)
+	}
+
+	/// Invoked when the given block gets finalized.
+	///
+	/// The watchers of the blocks of other forks up to the given one are dropped.
+	pub fn on_block_finalized(&self, block_hash: BlockHash<B>, block_number: NumberFor<B>) {
+		self.validated_pool.on_block_finalized(block_hash, block_number)
+	}
+
+	/// Invoked when the given block gets retracted, before its transactions are resubmitted.
+	pub fn on_block_retracted(&self, block_hash: BlockHash<B>) {
+		self.validated_pool.on_block_retracted(block_hash)
+	}
+
 	/// Returns transaction hash
 	pub fn hash_of(&self, xt: &ExtrinsicFor<B>) -> ExHash<B> {
 		self.validated_pool.api().hash_and_length(xt).0
@@ -341,10 +443,19 @@ impl<B: ChainApi> Pool<B> {
 		force: bool,
 	) -> impl Future<Output=ValidatedTransactionFor<B>> {
 		let (hash, bytes) = self.validated_pool.api().hash_and_length(&xt);
-		if !force && self.validated_pool.is_banned(&hash) {
+		let sender = if self.validated_pool.options().ban_senders {
+			self.validated_pool.api().sender_of(&xt)
+		} else {
+			None
+		};
+		let is_banned = self.validated_pool.is_banned(&hash) ||
+			sender.as_ref().map_or(false, |sender| self.validated_pool.is_sender_banned(sender));
+		if !force && is_banned {
 			return Either::Left(ready(ValidatedTransaction::Invalid(hash, error::Error::TemporarilyBanned.into())))
 		}
 
+		let validated_pool = self.validated_pool.clone();
+		self.validated_pool.counters().note_validation();
 		Either::Right(self.validated_pool.api().validate_transaction(block_id, xt.clone())
 			.then(move |validation_result| ready(match validation_result {
 				Ok(validity) => match validity {
@@ -364,8 +475,12 @@ impl<B: ChainApi> Pool<B> {
 								.saturating_add(validity.longevity),
 						})
 					},
-					Err(TransactionValidityError::Invalid(e)) =>
-						ValidatedTransaction::Invalid(hash, error::Error::InvalidTransaction(e).into()),
+					Err(TransactionValidityError::Invalid(e)) => {
+						if let (InvalidTransaction::Payment, Some(sender)) = (&e, sender) {
+							validated_pool.ban_sender(&Instant::now(), sender);
+						}
+						ValidatedTransaction::Invalid(hash, error::Error::InvalidTransaction(e).into())
+					},
 					Err(TransactionValidityError::Unknown(e)) =>
 						ValidatedTransaction::Unknown(hash, error::Error::UnknownTransaction(e).into()),
 				},
@@ -399,10 +514,12 @@ mod tests {
 	use crate::watcher;
 
 	const INVALID_NONCE: u64 = 254;
+	const UNPAYABLE_NONCE: u64 = 253;
 
 	#[derive(Clone, Debug, Default)]
 	struct TestApi {
 		delay: Arc<Mutex<Option<std::sync::mpsc::Receiver<()>>>>,
+		validations: Arc<Mutex<usize>>,
 	}
 
 	impl ChainApi for TestApi {
@@ -417,6 +534,7 @@ mod tests {
 			at: &BlockId<Self::Block>,
 			uxt: ExtrinsicFor<Self>,
 		) -> Self::ValidationFuture {
+			*self.validations.lock() += 1;
 			let block_number = self.block_id_to_number(at).unwrap().unwrap();
 			let nonce = uxt.transfer().nonce;
 
@@ -430,7 +548,9 @@ mod tests {
 				}
 			}
 
-			futures::future::ready(if nonce < block_number {
+			futures::future::ready(if nonce == UNPAYABLE_NONCE {
+				Ok(InvalidTransaction::Payment.into())
+			} else if nonce < block_number {
 				Ok(InvalidTransaction::Stale.into())
 			} else {
 				Ok(Ok(ValidTransaction {
@@ -467,6 +587,11 @@ mod tests {
 				len
 			)
 		}
+
+		/// Use the account as the sender.
+		fn sender_of(&self, uxt: &ExtrinsicFor<Self>) -> Option<Vec<u8>> {
+			Some(uxt.transfer().from.encode())
+		}
 	}
 
 	fn uxt(transfer: Transfer) -> Extrinsic {
@@ -515,6 +640,108 @@ mod tests {
 		assert_matches!(res.unwrap_err(), error::Error::TemporarilyBanned);
 	}
 
+	#[test]
+	fn should_not_revalidate_banned_transactions() {
+		// given
+		let pool = pool();
+		let stale = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		let res = block_on(pool.submit_one(&BlockId::Number(1), stale.clone()));
+		assert_matches!(res.unwrap_err(), error::Error::InvalidTransaction(InvalidTransaction::Stale));
+		assert_eq!(*pool.validated_pool.api().validations.lock(), 1);
+
+		// when
+		let res = block_on(pool.submit_one(&BlockId::Number(1), stale));
+
+		// then
+		assert_matches!(res.unwrap_err(), error::Error::TemporarilyBanned);
+		assert_eq!(*pool.validated_pool.api().validations.lock(), 1);
+	}
+
+	#[test]
+	fn should_ban_senders_of_unpayable_transactions() {
+		// given
+		let pool = Pool::new(Options {
+			ban_senders: true,
+			..Default::default()
+		}, TestApi::default());
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		let res = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, UNPAYABLE_NONCE)));
+		assert_matches!(res.unwrap_err(), error::Error::InvalidTransaction(InvalidTransaction::Payment));
+
+		// then
+		let res = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0)));
+		assert_matches!(res.unwrap_err(), error::Error::TemporarilyBanned);
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3, 0))).unwrap();
+		assert_eq!(pool.status().ready, 1);
+	}
+
+	#[test]
+	fn should_limit_transactions_per_sender_with_nonce_gaps() {
+		// given
+		let pool = Pool::new(Options {
+			max_per_sender: Some(2),
+			..Default::default()
+		}, TestApi::default());
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 10))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 12))).unwrap();
+
+		// when
+		let res = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 14)));
+
+		// then
+		assert_matches!(res.unwrap_err(), error::Error::TooManyFromSender { limit: 2 });
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3, 14))).unwrap();
+		assert_eq!(pool.status().future, 3);
+	}
+
+	#[test]
+	fn should_revalidate_oldest_transactions_first() {
+		// given
+		let pool = pool();
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let hash0 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(3), transfer(3, 3))).unwrap();
+		assert_eq!(pool.status().ready, 2);
+
+		// when
+		block_on(pool.revalidate(&BlockId::Number(1), 1)).unwrap();
+
+		// then
+		assert_eq!(*pool.validated_pool.api().validations.lock(), 3);
+		assert_eq!(pool.ready().map(|v| v.hash).collect::<Vec<_>>(), vec![hash3]);
+		assert!(pool.validated_pool.rotator().is_banned(&hash0));
+
+		// when
+		block_on(pool.revalidate(&BlockId::Number(1), 1)).unwrap();
+
+		// then
+		assert_eq!(*pool.validated_pool.api().validations.lock(), 4);
+		assert_eq!(pool.ready().map(|v| v.hash).collect::<Vec<_>>(), vec![hash3]);
+	}
+
 	#[test]
 	fn should_notify_about_pool_events() {
 		let stream = {
@@ -591,6 +818,67 @@ mod tests {
 		assert!(pool.validated_pool.rotator().is_banned(&hash3));
 	}
 
+	#[test]
+	fn should_remove_and_ban_transactions_of_sender() {
+		// given
+		let pool = pool();
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let hash2 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 2))).unwrap();
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(5), transfer(3, 5))).unwrap();
+		assert_eq!(pool.status().ready, 2);
+		assert_eq!(pool.status().future, 1);
+
+		// when
+		let sender = AccountId::from_h256(H256::from_low_u64_be(1)).encode();
+		let removed = pool.remove_sender(&sender);
+
+		// then
+		assert_eq!(removed.len(), 2);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash3]);
+		assert_eq!(pool.status().future, 0);
+		assert!(pool.validated_pool.rotator().is_banned(&hash1));
+		assert!(pool.validated_pool.rotator().is_banned(&hash2));
+	}
+
+	#[test]
+	fn should_report_metrics() {
+		// given
+		let pool = pool();
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(1), transfer(3, 0))).unwrap_err();
+		assert_eq!(pool.metrics(), Metrics {
+			ready: 1,
+			future: 0,
+			submitted: 2,
+			validations: 2,
+			banned: 1,
+			included: 0,
+			inclusion_latency_ms: 0,
+		});
+
+		// when
+		block_on(pool.prune_tags(&BlockId::Number(1), vec![vec![0]], vec![hash1.clone()])).unwrap();
+
+		// then
+		let metrics = pool.metrics();
+		assert_eq!(metrics.ready, 0);
+		assert_eq!(metrics.banned, 2);
+		assert_eq!(metrics.included, 1);
+		assert!(metrics.average_inclusion_latency().is_some());
+	}
+
 	#[test]
 	fn should_ban_mined_transactions() {
 		// given
@@ -619,6 +907,7 @@ mod tests {
 		let pool = Pool::new(Options {
 			ready: limit.clone(),
 			future: limit.clone(),
+			..Default::default()
 		}, TestApi::default());
 
 		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
@@ -653,6 +942,7 @@ mod tests {
 		let pool = Pool::new(Options {
 			ready: limit.clone(),
 			future: limit.clone(),
+			..Default::default()
 		}, TestApi::default());
 
 		// when
@@ -708,9 +998,12 @@ mod tests {
 			assert_eq!(pool.status().ready, 0);
 			assert_eq!(pool.status().future, 0);
 
+			pool.on_block_finalized(H256::from_low_u64_be(2).into(), 2);
+
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(watcher::Status::Ready));
+			assert_eq!(stream.next(), Some(watcher::Status::InBlock(H256::from_low_u64_be(2).into())));
 			assert_eq!(stream.next(), Some(watcher::Status::Finalized(H256::from_low_u64_be(2).into())));
 			assert_eq!(stream.next(), None);
 		}
@@ -733,13 +1026,50 @@ mod tests {
 			assert_eq!(pool.status().ready, 0);
 			assert_eq!(pool.status().future, 0);
 
+			pool.on_block_finalized(H256::from_low_u64_be(2).into(), 2);
+
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(watcher::Status::Ready));
+			assert_eq!(stream.next(), Some(watcher::Status::InBlock(H256::from_low_u64_be(2).into())));
 			assert_eq!(stream.next(), Some(watcher::Status::Finalized(H256::from_low_u64_be(2).into())));
 			assert_eq!(stream.next(), None);
 		}
 
+		#[test]
+		fn should_trigger_retracted_and_ready_again() {
+			// given
+			let pool = pool();
+			let xt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			});
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), xt.clone())).unwrap();
+			block_on(pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]], vec![])).unwrap();
+			assert_eq!(pool.status().ready, 0);
+
+			// when
+			pool.on_block_retracted(H256::from_low_u64_be(2).into());
+			// the transaction got banned when pruned, so it has to be forced back in
+			block_on(pool.submit_at(&BlockId::Number(0), vec![xt], true)).unwrap();
+			block_on(pool.prune_tags(&BlockId::Number(3), vec![vec![0u8]], vec![])).unwrap();
+			// the retracted block doesn't matter anymore
+			pool.on_block_finalized(H256::from_low_u64_be(2).into(), 2);
+			pool.on_block_finalized(H256::from_low_u64_be(3).into(), 3);
+
+			// then
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(watcher::Status::Ready));
+			assert_eq!(stream.next(), Some(watcher::Status::InBlock(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), Some(watcher::Status::Retracted(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), Some(watcher::Status::Ready));
+			assert_eq!(stream.next(), Some(watcher::Status::InBlock(H256::from_low_u64_be(3).into())));
+			assert_eq!(stream.next(), Some(watcher::Status::Finalized(H256::from_low_u64_be(3).into())));
+			assert_eq!(stream.next(), None);
+		}
+
 		#[test]
 		fn should_trigger_future_and_ready_after_promoted() {
 			// given
@@ -828,6 +1158,7 @@ mod tests {
 			let pool = Pool::new(Options {
 				ready: limit.clone(),
 				future: limit.clone(),
+				..Default::default()
 			}, TestApi::default());
 
 			let xt = uxt(Transfer {
@@ -855,6 +1186,35 @@ mod tests {
 			assert_eq!(stream.next(), Some(watcher::Status::Dropped));
 		}
 
+		#[test]
+		fn should_trigger_dropped_when_waiting_too_long_in_future() {
+			// given
+			let pool = Pool::new(Options {
+				future_max_age: Some(Duration::from_millis(10)),
+				..Default::default()
+			}, TestApi::default());
+			let xt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			});
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), xt)).unwrap();
+			assert_eq!(pool.status().future, 1);
+
+			// when
+			pool.validated_pool.clear_stale(&BlockId::Number(0)).unwrap();
+			assert_eq!(pool.status().future, 1);
+			std::thread::sleep(Duration::from_millis(20));
+			pool.validated_pool.clear_stale(&BlockId::Number(0)).unwrap();
+
+			// then
+			assert_eq!(pool.status().future, 0);
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(watcher::Status::Future));
+			assert_eq!(stream.next(), Some(watcher::Status::Dropped));
+		}
+
 		#[test]
 		fn should_handle_pruning_in_the_middle_of_import() {
 			let _ = env_logger::try_init();
@@ -108,6 +108,8 @@ qed
 pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	/// Insertion id
 	insertion_id: u64,
+	/// Percentage by which a transaction has to exceed the priority of the transactions it replaces.
+	replacement_bump: u32,
 	/// tags that are provided by Ready transactions
 	provided_tags: HashMap<Tag, Hash>,
 	/// Transactions that are ready (i.e. don't have any requirements external to the pool)
@@ -120,6 +122,7 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 	fn default() -> Self {
 		ReadyTransactions {
 			insertion_id: Default::default(),
+			replacement_bump: 0,
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
@@ -128,6 +131,15 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Creates an empty queue, where a replacement transaction needs a priority at least
+	/// `replacement_bump` percent higher than the transactions it replaces.
+	pub fn new(replacement_bump: u32) -> Self {
+		ReadyTransactions {
+			replacement_bump,
+			..Default::default()
+		}
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -387,7 +399,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			let required = u128::from(old_priority) * (100 + u128::from(self.replacement_bump)) / 100;
+			if old_priority >= tx.priority || u128::from(tx.priority) < required {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -18,6 +18,8 @@
 //!
 //! Keeps only recent extrinsic and discard the ones kept for a significant amount of time.
 //! Discarded extrinsics are banned so that they don't get re-imported again.
+//! Senders of transactions can be banned as well, which rejects all their transactions for a
+//! while.
 
 use std::{
 	collections::HashMap,
@@ -32,6 +34,9 @@ use crate::base_pool::Transaction;
 /// Expected size of the banned extrinsics cache.
 const EXPECTED_SIZE: usize = 2048;
 
+/// Default duration of a ban.
+pub const DEFAULT_BAN_TIME: Duration = Duration::from_secs(60 * 30);
+
 /// Pool rotator is responsible to only keep fresh extrinsics in the pool.
 ///
 /// Extrinsics that occupy the pool for too long are culled and temporarily banned from entering
@@ -41,13 +46,23 @@ pub struct PoolRotator<Hash> {
 	ban_time: Duration,
 	/// Currently banned extrinsics.
 	banned_until: RwLock<HashMap<Hash, Instant>>,
+	/// Currently banned senders, by their encoded identifier.
+	banned_senders_until: RwLock<HashMap<Vec<u8>, Instant>>,
 }
 
 impl<Hash: hash::Hash + Eq> Default for PoolRotator<Hash> {
 	fn default() -> Self {
+		Self::new(DEFAULT_BAN_TIME)
+	}
+}
+
+impl<Hash: hash::Hash + Eq> PoolRotator<Hash> {
+	/// Create a rotator banning extrinsics for the given duration.
+	pub fn new(ban_time: Duration) -> Self {
 		PoolRotator {
-			ban_time: Duration::from_secs(60 * 30),
+			ban_time,
 			banned_until: Default::default(),
+			banned_senders_until: Default::default(),
 		}
 	}
 }
@@ -66,13 +81,24 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 			banned.insert(hash, *now + self.ban_time);
 		}
 
-		if banned.len() > 2 * EXPECTED_SIZE {
-			while banned.len() > EXPECTED_SIZE {
-				if let Some(key) = banned.keys().next().cloned() {
-					banned.remove(&key);
-				}
-			}
-		}
+		garbage_collect(&mut banned);
+	}
+
+	/// Returns the number of currently banned extrinsics.
+	pub fn banned_count(&self) -> usize {
+		self.banned_until.read().len()
+	}
+
+	/// Returns `true` if the sender is currently banned.
+	pub fn is_sender_banned(&self, sender: &[u8]) -> bool {
+		self.banned_senders_until.read().contains_key(sender)
+	}
+
+	/// Bans given sender.
+	pub fn ban_sender(&self, now: &Instant, sender: Vec<u8>) {
+		let mut banned = self.banned_senders_until.write();
+		banned.insert(sender, *now + self.ban_time);
+		garbage_collect(&mut banned);
 	}
 
 
@@ -90,9 +116,19 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 
 	/// Removes timed bans.
 	pub fn clear_timeouts(&self, now: &Instant) {
-		let mut banned = self.banned_until.write();
+		self.banned_until.write().retain(|_, &mut v| v >= *now);
+		self.banned_senders_until.write().retain(|_, &mut v| v >= *now);
+	}
+}
 
-		banned.retain(|_, &mut v| v >= *now);
+/// Drops bans arbitrarily when there are too many of them.
+fn garbage_collect<K: hash::Hash + Eq + Clone>(banned: &mut HashMap<K, Instant>) {
+	if banned.len() > 2 * EXPECTED_SIZE {
+		while banned.len() > EXPECTED_SIZE {
+			if let Some(key) = banned.keys().next().cloned() {
+				banned.remove(&key);
+			}
+		}
 	}
 }
 
@@ -104,10 +140,7 @@ mod tests {
 	type Ex = ();
 
 	fn rotator() -> PoolRotator<Hash> {
-		PoolRotator {
-			ban_time: Duration::from_millis(10),
-			..Default::default()
-		}
+		PoolRotator::new(Duration::from_millis(10))
 	}
 
 	fn tx() -> (Hash, Transaction<Hash, Ex>) {
@@ -173,6 +206,22 @@ mod tests {
 		assert!(!rotator.is_banned(&hash));
 	}
 
+	#[test]
+	fn should_ban_and_clear_senders() {
+		// given
+		let rotator = rotator();
+		let now = Instant::now();
+
+		// when
+		rotator.ban_sender(&now, vec![1]);
+
+		// then
+		assert!(rotator.is_sender_banned(&[1]));
+		assert!(!rotator.is_sender_banned(&[2]));
+		rotator.clear_timeouts(&(now + rotator.ban_time + rotator.ban_time));
+		assert!(!rotator.is_sender_banned(&[1]));
+	}
+
 	#[test]
 	fn should_garbage_collect() {
 		// given
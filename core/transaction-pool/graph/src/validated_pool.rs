@@ -23,6 +23,7 @@ use std::{
 use crate::base_pool as base;
 use crate::error;
 use crate::listener::Listener;
+use crate::metrics::{Counters, Metrics};
 use crate::rotator::PoolRotator;
 use crate::watcher::Watcher;
 use serde::Serialize;
@@ -37,7 +38,7 @@ use sr_primitives::{
 };
 
 use crate::base_pool::PruneStatus;
-use crate::pool::{EventStream, Options, ChainApi, BlockHash, ExHash, ExtrinsicFor, TransactionFor};
+use crate::pool::{EventStream, Options, ChainApi, BlockHash, ExHash, ExtrinsicFor, NumberFor, TransactionFor};
 
 /// Pre-validated transaction. Validated pool only accepts transactions wrapped in this enum.
 #[derive(Debug)]
@@ -70,18 +71,28 @@ pub(crate) struct ValidatedPool<B: ChainApi> {
 	>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<()>>>,
 	rotator: PoolRotator<ExHash<B>>,
+	/// When the transactions in the pool were last validated.
+	validated_at: Mutex<HashMap<ExHash<B>, time::Instant>>,
+	/// When the transactions in the pool were imported.
+	imported_at: Mutex<HashMap<ExHash<B>, time::Instant>>,
+	counters: Counters,
 }
 
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: B) -> Self {
+		let base_pool = base::BasePool::new(options.max_per_sender, options.replacement_bump);
+		let rotator = PoolRotator::new(options.ban_time);
 		ValidatedPool {
 			api,
 			options,
 			listener: Default::default(),
-			pool: Default::default(),
+			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
-			rotator: Default::default(),
+			rotator,
+			validated_at: Default::default(),
+			imported_at: Default::default(),
+			counters: Default::default(),
 		}
 	}
 
@@ -95,6 +106,16 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.rotator.is_banned(hash)
 	}
 
+	/// Bans given sender.
+	pub fn ban_sender(&self, now: &std::time::Instant, sender: Vec<u8>) {
+		self.rotator.ban_sender(now, sender)
+	}
+
+	/// Returns true if transactions of the given sender are currently banned from the pool.
+	pub fn is_sender_banned(&self, sender: &[u8]) -> bool {
+		self.rotator.is_sender_banned(sender)
+	}
+
 	/// Imports a bunch of pre-validated transactions to the pool.
 	pub fn submit<T>(&self, txs: T) -> Vec<Result<ExHash<B>, B::Error>> where
 		T: IntoIterator<Item=ValidatedTransactionFor<B>>
@@ -115,7 +136,14 @@ impl<B: ChainApi> ValidatedPool<B> {
 	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<ExHash<B>, B::Error> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
-				let imported = self.pool.write().import(tx)?;
+				let sender = match self.options.max_per_sender {
+					Some(_) => self.api.sender_of(&tx.data),
+					None => None,
+				};
+				let imported = self.pool.write().import_from(tx, sender)?;
+				let now = time::Instant::now();
+				self.validated_at.lock().insert(imported.hash().clone(), now);
+				self.imported_at.lock().entry(imported.hash().clone()).or_insert(now);
 
 				if let base::Imported::Ready { .. } = imported {
 					self.import_notification_sinks.lock().retain(|sink| sink.unbounded_send(()).is_ok());
@@ -251,9 +279,16 @@ impl<B: ChainApi> ValidatedPool<B> {
 		{
 			let header_hash = self.api.block_id_to_hash(at)?
 				.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?;
+			let block_number = self.api.block_id_to_number(at)?
+				.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?
+				.saturated_into::<u64>();
 			let mut listener = self.listener.write();
+			let mut imported_at = self.imported_at.lock();
 			for h in hashes {
-				listener.pruned(header_hash, &h);
+				if let Some(imported_at) = imported_at.remove(&h) {
+					self.counters.note_included(imported_at.elapsed());
+				}
+				listener.pruned(header_hash, block_number, &h);
 			}
 		}
 		// perform regular cleanup of old transactions in the pool
@@ -291,12 +326,88 @@ impl<B: ChainApi> ValidatedPool<B> {
 		// removing old transactions
 		self.remove_invalid(&to_remove);
 		self.remove_invalid(&futures_to_remove);
+		// dropping the transactions waiting for too long in the future queue
+		if let Some(deadline) = self.options.future_max_age.and_then(|max_age| now.checked_sub(max_age)) {
+			let expired = self.pool.write().remove_future_older_than(deadline);
+			let mut listener = self.listener.write();
+			for tx in &expired {
+				debug!(target: "txpool", "[{:?}] Dropped after waiting too long in the future queue", tx.hash);
+				listener.dropped(&tx.hash, None);
+			}
+		}
+		// forget about the import of transactions which left the pool
+		{
+			let pool = self.pool.read();
+			let in_pool = pool.ready().map(|tx| tx.hash.clone())
+				.chain(pool.futures().map(|tx| tx.hash.clone()))
+				.collect::<HashSet<_>>();
+			self.imported_at.lock().retain(|hash, _| in_pool.contains(hash));
+		}
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
 
 		Ok(())
 	}
 
+	/// Returns up to `max` extrinsics of the pool, the ones validated the longest time ago first.
+	pub fn revalidation_batch(&self, max: usize) -> Vec<ExtrinsicFor<B>> {
+		let pool = self.pool.read();
+		let mut validated_at = self.validated_at.lock();
+		let mut hashes = pool.ready()
+			.map(|tx| tx.hash.clone())
+			.chain(pool.futures().map(|tx| tx.hash.clone()))
+			.collect::<Vec<_>>();
+		// forget about the transactions which left the pool in the meantime
+		{
+			let in_pool = hashes.iter().collect::<HashSet<_>>();
+			validated_at.retain(|hash, _| in_pool.contains(hash));
+		}
+		hashes.sort_by_key(|hash| validated_at.get(hash).cloned());
+		hashes.truncate(max);
+
+		pool.by_hash(&hashes)
+			.into_iter()
+			.filter_map(|tx| tx.map(|tx| tx.data.clone()))
+			.collect()
+	}
+
+	/// Handles the result of the revalidation of transactions at the given block.
+	///
+	/// Transactions which turned out to be invalid are removed from the pool, together with the
+	/// ones beyond their longevity period. The others are kept as they are, even if their
+	/// validity changed, and go to the back of the revalidation queue.
+	pub fn on_revalidated(
+		&self,
+		at: &BlockId<B::Block>,
+		revalidated: Vec<ValidatedTransactionFor<B>>,
+	) -> Result<(), B::Error> {
+		let now = time::Instant::now();
+		let mut invalid = Vec::new();
+		{
+			let mut validated_at = self.validated_at.lock();
+			for tx in revalidated {
+				let hash = match tx {
+					ValidatedTransaction::Valid(tx) => tx.hash,
+					ValidatedTransaction::Invalid(hash, err) => match error::IntoPoolError::into_pool_error(err) {
+						Ok(error::Error::InvalidTransaction(_)) | Ok(error::Error::NoTagsProvided) => {
+							invalid.push(hash);
+							continue
+						},
+						// the runtime couldn't be called, try again later
+						_ => hash,
+					},
+					ValidatedTransaction::Unknown(hash, _) => hash,
+				};
+				validated_at.insert(hash, now);
+			}
+		}
+
+		if !invalid.is_empty() {
+			self.remove_invalid(&invalid);
+		}
+		self.clear_stale(at)
+	}
+
 	/// Get rotator reference.
 	#[cfg(test)]
 	pub fn rotator(&self) -> &PoolRotator<ExHash<B>> {
@@ -308,6 +419,21 @@ impl<B: ChainApi> ValidatedPool<B> {
 		&self.api
 	}
 
+	/// Get the counters of the pool activity.
+	pub fn counters(&self) -> &Counters {
+		&self.counters
+	}
+
+	/// Returns the metrics of the pool.
+	pub fn metrics(&self) -> Metrics {
+		self.counters.metrics(self.status(), self.rotator.banned_count())
+	}
+
+	/// Get the options the pool was created with.
+	pub fn options(&self) -> &Options {
+		&self.options
+	}
+
 	/// Return an event stream of transactions imported to the pool.
 	pub fn import_notification_stream(&self) -> EventStream {
 		let (sink, stream) = mpsc::unbounded();
@@ -339,6 +465,23 @@ impl<B: ChainApi> ValidatedPool<B> {
 		invalid
 	}
 
+	/// Removes all the transactions of the given sender from the pool, and temporarily bans them.
+	///
+	/// Senders are only known if the `ChainApi` implements `sender_of`.
+	pub fn remove_sender(&self, sender: &[u8]) -> Vec<TransactionFor<B>> {
+		let hashes = {
+			let pool = self.pool.read();
+			let is_sender = |data: &ExtrinsicFor<B>|
+				self.api.sender_of(data).as_ref().map(|s| &s[..]) == Some(sender);
+			pool.ready()
+				.filter(|tx| is_sender(&tx.data))
+				.map(|tx| tx.hash.clone())
+				.chain(pool.futures().filter(|tx| is_sender(&tx.data)).map(|tx| tx.hash.clone()))
+				.collect::<Vec<_>>()
+		};
+		self.remove_invalid(&hashes)
+	}
+
 	/// Get an iterator for ready transactions ordered by priority
 	pub fn ready(&self) -> impl Iterator<Item=TransactionFor<B>> {
 		self.pool.read().ready()
@@ -348,6 +491,21 @@ impl<B: ChainApi> ValidatedPool<B> {
 	pub fn status(&self) -> base::Status {
 		self.pool.read().status()
 	}
+
+	/// Returns pool transactions given list of hashes.
+	pub fn by_hash(&self, hashes: &[ExHash<B>]) -> Vec<Option<TransactionFor<B>>> {
+		self.pool.read().by_hash(hashes)
+	}
+
+	/// Notify the watchers of the transactions included in the given block that it got finalized.
+	pub fn on_block_finalized(&self, block_hash: BlockHash<B>, block_number: NumberFor<B>) {
+		self.listener.write().finalized(block_hash, block_number.saturated_into::<u64>())
+	}
+
+	/// Notify the watchers of the transactions included in the given block that it got retracted.
+	pub fn on_block_retracted(&self, block_hash: BlockHash<B>) {
+		self.listener.write().retracted(block_hash)
+	}
 }
 
 fn fire_events<H, H2, Ex>(
@@ -355,7 +513,7 @@ fn fire_events<H, H2, Ex>(
 	imported: &base::Imported<H, Ex>,
 ) where
 	H: hash::Hash + Eq + traits::Member + Serialize,
-	H2: Clone,
+	H2: hash::Hash + Eq + Clone,
 {
 	match *imported {
 		base::Imported::Ready { ref promoted, ref failed, ref removed, ref hash } => {
@@ -22,7 +22,12 @@ use futures::{
 };
 use serde::{Serialize, Deserialize};
 
-/// Possible extrinsic status events
+/// Possible extrinsic status events.
+///
+/// An extrinsic goes through `Future` and/or `Ready` and may be `Broadcast` several times while
+/// in the pool. It then leaves the pool when it gets included in a block (`InBlock`), after which
+/// it may be put back to the pool if the block is `Retracted`, or end up `Finalized`. Otherwise it
+/// leaves the pool because it got `Usurped`, `Dropped` or turned `Invalid`.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Status<H, H2> {
@@ -30,6 +35,10 @@ pub enum Status<H, H2> {
 	Future,
 	/// Extrinsic is part of the ready queue.
 	Ready,
+	/// Extrinsic has been included in block with given hash.
+	InBlock(H2),
+	/// The block this extrinsic was included in has been retracted.
+	Retracted(H2),
 	/// Extrinsic has been finalized in block with given hash.
 	Finalized(H2),
 	/// Some state change (perhaps another extrinsic was included) rendered this extrinsic invalid.
@@ -107,6 +116,16 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 		self.send(Status::Usurped(hash))
 	}
 
+	/// Extrinsic has been included in block with given hash.
+	pub fn in_block(&mut self, hash: H2) {
+		self.send(Status::InBlock(hash))
+	}
+
+	/// The block this extrinsic was included in has been retracted.
+	pub fn retracted(&mut self, hash: H2) {
+		self.send(Status::Retracted(hash))
+	}
+
 	/// Extrinsic has been finalized in block with given hash.
 	pub fn finalized(&mut self, hash: H2) {
 		self.send(Status::Finalized(hash));
@@ -130,10 +149,9 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 		self.send(Status::Broadcast(peers))
 	}
 
-
 	/// Returns true if the are no more listeners for this extrinsic or it was finalized.
 	pub fn is_done(&self) -> bool {
-		self.finalized || self.receivers.is_empty()
+		self.finalized || self.receivers.iter().all(|sender| sender.is_closed())
 	}
 
 	fn send(&mut self, status: Status<H, H2>) {
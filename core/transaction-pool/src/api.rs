@@ -20,7 +20,7 @@ use std::{
 	sync::Arc,
 	marker::PhantomData,
 };
-use client::{runtime_api::TaggedTransactionQueue, blockchain::HeaderBackend};
+use client::{runtime_api::{TaggedTransactionQueue, ApiExt}, blockchain::HeaderBackend};
 use codec::Encode;
 use txpool;
 use primitives::{
@@ -85,4 +85,18 @@ impl<T, Block> txpool::ChainApi for FullChainApi<T, Block> where
 			(Blake2Hasher::hash(x), x.len())
 		})
 	}
+
+	fn sender_of(&self, uxt: &txpool::ExtrinsicFor<Self>) -> Option<Vec<u8>> {
+		let at = BlockId::Hash(self.client.info().best_hash);
+		let runtime_api = self.client.runtime_api();
+		// Runtimes built before `transaction_sender` was added can't tell the sender.
+		let has_sender_api = runtime_api
+			.has_api_with::<dyn TaggedTransactionQueue<Block>, _>(&at, |v| v >= 2)
+			.unwrap_or(false);
+		if !has_sender_api {
+			return None;
+		}
+
+		runtime_api.transaction_sender(&at, uxt.clone()).ok().and_then(|sender| sender)
+	}
 }
@@ -0,0 +1,113 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Journal of the transactions submitted through the local node.
+//!
+//! The transaction pool only lives in memory, so the transactions which are still pending when
+//! the node stops would be lost. The locally submitted ones are kept in a small file instead,
+//! which is read back on startup to resubmit them to the pool. Transactions leave the journal
+//! once they are no longer in the pool, i.e. when they got included or turned out invalid.
+
+use std::{fs, io, path::PathBuf};
+use codec::{Decode, Encode};
+use log::warn;
+use parking_lot::Mutex;
+
+/// Journal of encoded transactions, persisted to a file.
+pub struct Journal {
+	path: PathBuf,
+	transactions: Mutex<Vec<Vec<u8>>>,
+}
+
+impl Journal {
+	/// Open the journal at the given path, reading the transactions it already contains.
+	///
+	/// A journal that can't be decoded is discarded.
+	pub fn open(path: PathBuf) -> io::Result<Self> {
+		let transactions = match fs::read(&path) {
+			Ok(data) => Decode::decode(&mut &data[..]).unwrap_or_else(|e| {
+				warn!("Discarding the corrupted transaction journal {}: {:?}", path.display(), e);
+				Vec::new()
+			}),
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+			Err(e) => return Err(e),
+		};
+
+		Ok(Journal { path, transactions: Mutex::new(transactions) })
+	}
+
+	/// Encoded transactions in the journal, in the order they were submitted.
+	pub fn transactions(&self) -> Vec<Vec<u8>> {
+		self.transactions.lock().clone()
+	}
+
+	/// Add an encoded transaction to the journal.
+	pub fn insert(&self, transaction: Vec<u8>) -> io::Result<()> {
+		let mut transactions = self.transactions.lock();
+		if transactions.contains(&transaction) {
+			return Ok(())
+		}
+		transactions.push(transaction);
+		self.write(&transactions)
+	}
+
+	/// Keep only the transactions for which `f` returns `true`.
+	pub fn retain(&self, mut f: impl FnMut(&[u8]) -> bool) -> io::Result<()> {
+		let mut transactions = self.transactions.lock();
+		let len = transactions.len();
+		transactions.retain(|transaction| f(transaction));
+		if transactions.len() == len {
+			return Ok(())
+		}
+		self.write(&transactions)
+	}
+
+	fn write(&self, transactions: &[Vec<u8>]) -> io::Result<()> {
+		if let Some(dir) = self.path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		// write to a temporary file first, so that the journal is never left half-written.
+		let tmp = self.path.with_extension("tmp");
+		fs::write(&tmp, transactions.encode())?;
+		fs::rename(&tmp, &self.path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn transactions_persist() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("transactions");
+
+		let journal = Journal::open(path.clone()).unwrap();
+		assert!(journal.transactions().is_empty());
+		journal.insert(vec![1]).unwrap();
+		journal.insert(vec![2]).unwrap();
+		journal.insert(vec![1]).unwrap();
+		journal.insert(vec![3]).unwrap();
+		journal.retain(|transaction| transaction != &[2][..]).unwrap();
+		drop(journal);
+
+		let journal = Journal::open(path.clone()).unwrap();
+		assert_eq!(journal.transactions(), vec![vec![1], vec![3]]);
+
+		fs::write(&path, b"garbage").unwrap();
+		assert!(Journal::open(path).unwrap().transactions().is_empty());
+	}
+}
@@ -20,10 +20,12 @@
 #![warn(unused_extern_crates)]
 
 mod api;
+mod journal;
 #[cfg(test)]
 mod tests;
 
 pub mod error;
 
 pub use api::FullChainApi;
+pub use journal::Journal;
 pub use txpool;
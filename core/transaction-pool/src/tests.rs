@@ -220,3 +220,14 @@ fn should_correctly_prune_transactions_providing_more_than_one_tag() {
 	assert_eq!(pool.status().ready, 0);
 	assert_eq!(pool.status().future, 2);
 }
+
+#[test]
+fn full_chain_api_should_tell_the_sender_of_transfers() {
+	use txpool::ChainApi;
+
+	let api = FullChainApi::new(std::sync::Arc::new(test_client::new()));
+
+	assert_eq!(api.sender_of(&uxt(Alice, 0)), Some(AccountId::from(Alice).encode()));
+	assert_eq!(api.sender_of(&uxt(Bob, 3)), Some(AccountId::from(Bob).encode()));
+	assert_eq!(api.sender_of(&Extrinsic::IncludeData(vec![42])), None);
+}
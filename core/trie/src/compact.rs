@@ -0,0 +1,202 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! Compact encoding of the sets of trie nodes (e.g. storage proofs).
+//!
+//! Every node of the set, except for the trie roots, is referenced by its hash from the parent
+//! node. The compact encoding lists nodes of every trie in the depth-first order, starting from
+//! its root, and replaces references to the nodes that are encoded right after the reference
+//! with empty references. Hashes of these nodes are recomputed when the set is decoded, so the
+//! encoded set is smaller by the size of the hash for every node that isn't a root.
+
+use std::collections::{HashMap, HashSet};
+use codec::Encode;
+use hash_db::Hasher;
+use trie_db::NodeCodec as NodeCodecT;
+use crate::error::Error;
+use crate::node_codec::{NodeCodec, split_branch_children};
+
+/// Encode the set of trie nodes in the compact form.
+///
+/// Every node from the set is encoded once, duplicates are removed.
+pub fn encode_compact<H: Hasher>(nodes: impl IntoIterator<Item=Vec<u8>>) -> Result<Vec<Vec<u8>>, Error> {
+	let mut index = HashMap::new();
+	let mut nodes_by_index = Vec::new();
+	for node in nodes {
+		let hash = H::hash(&node);
+		if !index.contains_key(&hash) {
+			index.insert(hash, nodes_by_index.len());
+			nodes_by_index.push((hash, node));
+		}
+	}
+
+	// every node that isn't referenced by other nodes is a root of some trie
+	let mut referenced = HashSet::new();
+	for (_, node) in &nodes_by_index {
+		if let Some((_, children)) = split_branch_children(node)? {
+			referenced.extend(children.iter()
+				.filter_map(|child| child.and_then(<NodeCodec<H> as NodeCodecT<H>>::try_decode_hash))
+				.filter(|child_hash| index.contains_key(child_hash)));
+		}
+	}
+
+	let mut encoded = Vec::with_capacity(nodes_by_index.len());
+	let mut visited = HashSet::new();
+	for (hash, _) in &nodes_by_index {
+		if !referenced.contains(hash) {
+			encode_node::<H>(hash, &nodes_by_index, &index, &mut visited, &mut encoded)?;
+		}
+	}
+	Ok(encoded)
+}
+
+/// Decode the set of trie nodes from the compact form.
+pub fn decode_compact<H: Hasher>(encoded: &[Vec<u8>]) -> Result<Vec<Vec<u8>>, Error> {
+	let mut nodes = Vec::with_capacity(encoded.len());
+	let mut position = 0;
+	while position < encoded.len() {
+		decode_node::<H>(encoded, &mut position, &mut nodes)?;
+	}
+	Ok(nodes)
+}
+
+/// Encode the node and all its children that haven't been visited yet.
+fn encode_node<H: Hasher>(
+	hash: &H::Out,
+	nodes: &[(H::Out, Vec<u8>)],
+	index: &HashMap<H::Out, usize>,
+	visited: &mut HashSet<H::Out>,
+	encoded: &mut Vec<Vec<u8>>,
+) -> Result<(), Error> {
+	visited.insert(*hash);
+
+	let node = &nodes[index[hash]].1;
+	let (prefix, children) = match split_branch_children(node)? {
+		Some(split) => split,
+		None => {
+			encoded.push(node.clone());
+			return Ok(());
+		},
+	};
+
+	// reserve the place for the node, so that it precedes its children
+	let position = encoded.len();
+	encoded.push(Vec::new());
+
+	let mut compact_node = prefix.to_vec();
+	for child in children.iter().filter_map(|child| *child) {
+		let child_hash = <NodeCodec<H> as NodeCodecT<H>>::try_decode_hash(child)
+			.filter(|child_hash| index.contains_key(child_hash) && !visited.contains(child_hash));
+		match child_hash {
+			Some(child_hash) => {
+				(&[][..] as &[u8]).encode_to(&mut compact_node);
+				encode_node::<H>(&child_hash, nodes, index, visited, encoded)?;
+			},
+			None => child.encode_to(&mut compact_node),
+		}
+	}
+	encoded[position] = compact_node;
+
+	Ok(())
+}
+
+/// Decode the node at given position and all its omitted children. Returns hash of the node.
+fn decode_node<H: Hasher>(
+	encoded: &[Vec<u8>],
+	position: &mut usize,
+	nodes: &mut Vec<Vec<u8>>,
+) -> Result<H::Out, Error> {
+	let compact_node = encoded.get(*position).ok_or(Error::BadFormat)?;
+	*position += 1;
+
+	let node = match split_branch_children(compact_node)? {
+		Some((prefix, children)) => {
+			let mut node = prefix.to_vec();
+			for child in children.iter().filter_map(|child| *child) {
+				if child.is_empty() {
+					decode_node::<H>(encoded, position, nodes)?.as_ref().encode_to(&mut node);
+				} else {
+					child.encode_to(&mut node);
+				}
+			}
+			node
+		},
+		None => compact_node.clone(),
+	};
+
+	let hash = H::hash(&node);
+	nodes.push(node);
+	Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::Blake2Hasher;
+	use trie_db::TrieMut;
+	use crate::{MemoryDB, TrieDBMut, Layout};
+
+	fn trie_nodes(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<Vec<u8>> {
+		let mut db = MemoryDB::<Blake2Hasher>::default();
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMut::<Layout<Blake2Hasher>>::new(&mut db, &mut root);
+			for (key, value) in pairs {
+				trie.insert(&key, &value).unwrap();
+			}
+		}
+		db.drain().into_iter().filter(|(_, (_, rc))| *rc > 0).map(|(_, (node, _))| node).collect()
+	}
+
+	fn sorted(mut nodes: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+		nodes.sort();
+		nodes
+	}
+
+	fn size(nodes: &[Vec<u8>]) -> usize {
+		nodes.iter().map(|node| node.len()).sum()
+	}
+
+	#[test]
+	fn compact_encoding_roundtrips() {
+		let nodes = trie_nodes((0u32..1000).map(|i| (i.encode(), (i, i).encode())).collect());
+		let encoded = encode_compact::<Blake2Hasher>(nodes.clone()).unwrap();
+		assert_eq!(encoded.len(), nodes.len());
+		// every node, except for the root, is referenced by hash
+		assert_eq!(size(&nodes) - size(&encoded), (nodes.len() - 1) * 32);
+		assert_eq!(sorted(decode_compact::<Blake2Hasher>(&encoded).unwrap()), sorted(nodes));
+	}
+
+	#[test]
+	fn compact_encoding_of_multiple_tries_roundtrips() {
+		let mut nodes = trie_nodes((0u32..100).map(|i| (i.encode(), (i, i).encode())).collect());
+		nodes.extend(trie_nodes((100u32..200).map(|i| (i.encode(), (i, i).encode())).collect()));
+		// duplicate nodes are only encoded once
+		nodes.extend(nodes.clone());
+
+		let encoded = encode_compact::<Blake2Hasher>(nodes.clone()).unwrap();
+		let mut expected = sorted(nodes);
+		expected.dedup();
+		assert_eq!(sorted(decode_compact::<Blake2Hasher>(&encoded).unwrap()), expected);
+	}
+
+	#[test]
+	fn incomplete_compact_encoding_is_rejected() {
+		let nodes = trie_nodes((0u32..100).map(|i| (i.encode(), (i, i).encode())).collect());
+		let mut encoded = encode_compact::<Blake2Hasher>(nodes).unwrap();
+		encoded.pop();
+		assert!(decode_compact::<Blake2Hasher>(&encoded).is_err());
+	}
+}
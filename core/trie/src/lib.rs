@@ -22,6 +22,8 @@ mod error;
 mod node_header;
 mod node_codec;
 mod trie_stream;
+#[cfg(feature = "std")]
+mod compact;
 
 use rstd::boxed::Box;
 use rstd::vec::Vec;
@@ -32,6 +34,9 @@ pub use error::Error;
 pub use trie_stream::TrieStream;
 /// The Substrate format implementation of `NodeCodec`.
 pub use node_codec::NodeCodec;
+/// Compact encoding of the sets of trie nodes.
+#[cfg(feature = "std")]
+pub use compact::{encode_compact, decode_compact};
 /// Various re-exports from the `trie-db` crate.
 pub use trie_db::{
 	Trie, TrieMut, DBValue, Recorder, CError, Query, TrieLayout, TrieConfiguration, nibble_ops,
@@ -264,6 +269,111 @@ pub fn record_all_keys<L: TrieConfiguration, DB>(
 	Ok(())
 }
 
+/// Read all the key/value pairs of the trie with a key starting with `prefix`, in order.
+///
+/// Unlike the iteration helpers of the state backends, a missing trie node is reported as an
+/// error instead of ending the iteration, which makes this usable on a partial trie built from
+/// a proof.
+pub fn read_trie_prefix<L: TrieConfiguration, DB>(
+	db: &DB,
+	root: &TrieHash<L>,
+	prefix: &[u8],
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Box<TrieError<L>>> where
+	DB: hash_db::HashDBRef<L::Hash, trie_db::DBValue>
+{
+	let trie = TrieDB::<L>::new(&*db, root)?;
+	let mut iter = trie.iter()?;
+	iter.seek(prefix)?;
+
+	let mut pairs = Vec::new();
+	for x in iter {
+		let (key, value) = x?;
+		if !key.starts_with(prefix) {
+			break;
+		}
+		pairs.push((key, value.to_vec()));
+	}
+
+	Ok(pairs)
+}
+
+/// Record the trie nodes needed to iterate over the keys starting with `prefix`.
+///
+/// Besides the nodes leading to these keys, the nodes leading to the first key following them
+/// are recorded, since iterating needs them to find out where the prefixed keys end.
+pub fn record_keys_with_prefix<L: TrieConfiguration, DB>(
+	db: &DB,
+	root: &TrieHash<L>,
+	prefix: &[u8],
+	recorder: &mut Recorder<TrieHash<L>>
+) -> Result<(), Box<TrieError<L>>> where
+	DB: hash_db::HashDBRef<L::Hash, trie_db::DBValue>
+{
+	let trie = TrieDB::<L>::new(&*db, root)?;
+	// nodes visited when seeking the prefix, even if no key starts with it.
+	trie.get_with(prefix, &mut *recorder)?;
+
+	let mut iter = trie.iter()?;
+	iter.seek(prefix)?;
+
+	for x in iter {
+		let (key, _) = x?;
+		trie.get_with(&key, &mut *recorder)?;
+		if !key.starts_with(prefix) {
+			break;
+		}
+	}
+
+	Ok(())
+}
+
+/// Returns at most `count` keys of the trie starting with `prefix`, in order, strictly after
+/// `start_key` if given.
+///
+/// Only the part of the trie holding the requested keys is visited. If a `recorder` is given, the
+/// trie nodes leading to the requested keys are recorded, as well as those leading to the key
+/// that follows them.
+pub fn trie_keys_paged<L: TrieConfiguration, DB>(
+	db: &DB,
+	root: &TrieHash<L>,
+	prefix: &[u8],
+	start_key: Option<&[u8]>,
+	count: usize,
+	mut recorder: Option<&mut Recorder<TrieHash<L>>>,
+) -> Result<Vec<Vec<u8>>, Box<TrieError<L>>> where
+	DB: hash_db::HashDBRef<L::Hash, trie_db::DBValue>
+{
+	let trie = TrieDB::<L>::new(&*db, root)?;
+	// keys are iterated in lexicographic order, so we can start right at the requested page.
+	let seek_key = match start_key {
+		Some(start_key) if start_key > prefix => start_key,
+		_ => prefix,
+	};
+	if let Some(recorder) = recorder.as_mut() {
+		trie.get_with(seek_key, &mut **recorder)?;
+	}
+
+	let mut iter = trie.iter()?;
+	iter.seek(seek_key)?;
+
+	let mut keys = Vec::new();
+	for x in iter {
+		let (key, _) = x?;
+		if let Some(recorder) = recorder.as_mut() {
+			trie.get_with(&key, &mut **recorder)?;
+		}
+		if keys.len() >= count || !key.starts_with(prefix) {
+			break;
+		}
+		if start_key.map_or(false, |start_key| &key[..] <= start_key) {
+			continue;
+		}
+		keys.push(key);
+	}
+
+	Ok(keys)
+}
+
 /// Read a value from the child trie.
 pub fn read_child_trie_value<L: TrieConfiguration, DB>(
 	_storage_key: &[u8],
@@ -616,4 +726,80 @@ mod tests {
 
 		assert_eq!(pairs, iter_pairs);
 	}
+
+	#[test]
+	fn recorded_prefix_can_be_read_back() {
+		let pairs = vec![
+			(b"aa".to_vec(), b"1".to_vec()),
+			(b"abc1".to_vec(), b"2".to_vec()),
+			(b"abc2".to_vec(), b"3".to_vec()),
+			(b"abd".to_vec(), b"4".to_vec()),
+			(b"b".to_vec(), b"5".to_vec()),
+		];
+
+		let mut mdb = MemoryDB::default();
+		let mut root = Default::default();
+		let _ = populate_trie::<Layout>(&mut mdb, &mut root, &pairs);
+
+		let read_back = |prefix: &[u8]| {
+			let mut recorder = Recorder::new();
+			record_keys_with_prefix::<Layout, _>(&mdb, &root, prefix, &mut recorder).unwrap();
+
+			let mut proof_db = MemoryDB::<Blake2Hasher>::default();
+			for record in recorder.drain() {
+				proof_db.insert(EMPTY_PREFIX, &record.data);
+			}
+			read_trie_prefix::<Layout, _>(&proof_db, &root, prefix).unwrap()
+		};
+
+		assert_eq!(read_back(b"abc"), pairs[1..3].to_vec());
+		assert_eq!(read_back(b"ab"), pairs[1..4].to_vec());
+		assert_eq!(read_back(b"c"), Vec::new());
+		assert_eq!(read_back(b""), pairs);
+	}
+
+	#[test]
+	fn recorded_key_pages_can_be_read_back() {
+		let pairs = vec![
+			(b"aa".to_vec(), b"1".to_vec()),
+			(b"abc1".to_vec(), b"2".to_vec()),
+			(b"abc2".to_vec(), b"3".to_vec()),
+			(b"abd".to_vec(), b"4".to_vec()),
+			(b"b".to_vec(), b"5".to_vec()),
+		];
+		let keys = |range: std::ops::Range<usize>| pairs[range].iter()
+			.map(|(key, _)| key.clone())
+			.collect::<Vec<_>>();
+
+		let mut mdb = MemoryDB::default();
+		let mut root = Default::default();
+		let _ = populate_trie::<Layout>(&mut mdb, &mut root, &pairs);
+
+		let page = |prefix: &[u8], start_key: Option<&[u8]>, count| {
+			let mut recorder = Recorder::new();
+			let keys = trie_keys_paged::<Layout, _>(
+				&mdb,
+				&root,
+				prefix,
+				start_key,
+				count,
+				Some(&mut recorder),
+			).unwrap();
+
+			let mut proof_db = MemoryDB::<Blake2Hasher>::default();
+			for record in recorder.drain() {
+				proof_db.insert(EMPTY_PREFIX, &record.data);
+			}
+			let checked = trie_keys_paged::<Layout, _>(&proof_db, &root, prefix, start_key, count, None)
+				.unwrap();
+			assert_eq!(keys, checked);
+			keys
+		};
+
+		assert_eq!(page(b"ab", None, 2), keys(1..3));
+		assert_eq!(page(b"ab", Some(b"abc2"), 2), keys(3..4));
+		assert_eq!(page(b"", Some(b"a"), 10), keys(0..5));
+		assert_eq!(page(b"", None, 0), Vec::<Vec<u8>>::new());
+		assert_eq!(page(b"c", None, 10), Vec::<Vec<u8>>::new());
+	}
 }
@@ -180,6 +180,38 @@ impl<H: Hasher> NodeCodecT<H> for NodeCodec<H> {
 
 // utils
 
+/// Split the encoded branch node into the part that precedes the children references (header,
+/// partial key, bitmap and value) and the encoded children references.
+///
+/// Returns `None` if the node isn't a branch.
+pub(crate) fn split_branch_children(data: &[u8]) -> Result<Option<(&[u8], [Option<&[u8]>; 16])>, Error> {
+	let input = &mut &*data;
+	let (has_value, nibble_count) = match NodeHeader::decode(input)? {
+		NodeHeader::Branch(has_value, nibble_count) => (has_value, nibble_count),
+		NodeHeader::Null | NodeHeader::Leaf(_) => return Ok(None),
+	};
+	take(
+		input,
+		(nibble_count + (nibble_ops::NIBBLE_PER_BYTE - 1)) / nibble_ops::NIBBLE_PER_BYTE,
+	).ok_or(Error::BadFormat)?;
+	let bitmap_slice = take(input, BITMAP_LENGTH).ok_or(Error::BadFormat)?;
+	let bitmap = Bitmap::decode(&bitmap_slice[..])?;
+	if has_value {
+		let count = <Compact<u32>>::decode(input)?.0 as usize;
+		take(input, count).ok_or(Error::BadFormat)?;
+	}
+	let prefix = &data[..data.len() - input.len()];
+
+	let mut children = [None; 16];
+	for i in 0..nibble_ops::NIBBLE_LENGTH {
+		if bitmap.value_at(i) {
+			let count = <Compact<u32>>::decode(input)?.0 as usize;
+			children[i] = Some(take(input, count).ok_or(Error::BadFormat)?);
+		}
+	}
+	Ok(Some((prefix, children)))
+}
+
 /// Encode and allocate node type header (type and size), and partial value.
 /// It uses an iterator over encoded partial bytes as input.
 fn partial_from_iterator_encode<I: Iterator<Item = u8>>(
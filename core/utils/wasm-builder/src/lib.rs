@@ -50,6 +50,9 @@
 //! This will include the generated WASM binary as two constants `WASM_BINARY` and `WASM_BINARY_BLOATY`.
 //! The former is a compact WASM binary and the latter is not compacted.
 //!
+//! The features of the project enabled for the main build, except `default` and `std`, are enabled for
+//! the WASM build as well, e.g. `runtime-benchmarks`.
+//!
 //! ## Environment variables
 //!
 //! By using environment variables, you can configure which WASM binaries are built and how:
@@ -244,6 +244,25 @@ fn create_wasm_workspace_project(wasm_workspace: &Path, cargo_manifest: &Path) {
 	).expect("WASM workspace `Cargo.toml` writing can not fail; qed");
 }
 
+/// Returns the features of the project that are enabled for the current build, and are forwarded
+/// to the wasm build, e.g. `runtime-benchmarks`. `default` and `std` are never forwarded.
+fn project_enabled_features(cargo_manifest: &Path) -> Vec<String> {
+	let manifest: Table = toml::from_str(
+		&fs::read_to_string(cargo_manifest).expect("Project `Cargo.toml` exists; qed")
+	).expect("Project `Cargo.toml` is a valid toml file; qed");
+
+	let mut features = manifest.get("features")
+		.and_then(|features| features.as_table())
+		.map(|features| features.keys().cloned().collect::<Vec<_>>())
+		.unwrap_or_default();
+	// Cargo tells the build script about the enabled features through `CARGO_FEATURE_*`.
+	features.retain(|feature| {
+		feature != "default" && feature != "std" &&
+			env::var(format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"))).is_ok()
+	});
+	features
+}
+
 /// Create the project used to build the wasm binary.
 ///
 /// # Returns
@@ -253,6 +272,10 @@ fn create_project(cargo_manifest: &Path, wasm_workspace: &Path) -> PathBuf {
 	let crate_path = cargo_manifest.parent().expect("Parent path exists; qed");
 	let wasm_binary = get_wasm_binary_name(cargo_manifest);
 	let project_folder = wasm_workspace.join(&crate_name);
+	let enabled_features = project_enabled_features(cargo_manifest).iter()
+		.map(|feature| format!("\"{}\"", feature))
+		.collect::<Vec<_>>()
+		.join(", ");
 
 	fs::create_dir_all(project_folder.join("src")).expect("Wasm project dir create can not fail; qed");
 
@@ -270,11 +293,12 @@ fn create_project(cargo_manifest: &Path, wasm_workspace: &Path) -> PathBuf {
 				crate-type = ["cdylib"]
 
 				[dependencies]
-				wasm_project = {{ package = "{crate_name}", path = "{crate_path}", default-features = false }}
+				wasm_project = {{ package = "{crate_name}", path = "{crate_path}", default-features = false, features = [ {features} ] }}
 			"#,
 			crate_name = crate_name,
 			crate_path = crate_path.display(),
 			wasm_binary = wasm_binary,
+			features = enabled_features,
 		)
 	);
 
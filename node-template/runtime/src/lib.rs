@@ -9,10 +9,11 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use rstd::prelude::*;
+use codec::Encode;
 use primitives::OpaqueMetadata;
 use sr_primitives::{
 	ApplyResult, transaction_validity::TransactionValidity, generic, create_runtime_str,
-	impl_opaque_keys, MultiSignature
+	impl_opaque_keys, MultiSignature, KeyTypeId,
 };
 use sr_primitives::traits::{
 	NumberFor, BlakeTwo256, Block as BlockT, StaticLookup, Verify, ConvertInto, IdentifyAccount
@@ -165,14 +166,23 @@ impl aura::Trait for Runtime {
 
 impl grandpa::Trait for Runtime {
 	type Event = Event;
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof = support::Void;
+	type KeyOwnerIdentification = ();
+	type ReportEquivocation = ();
+}
+
+parameter_types! {
+	pub const IndexDeposit: u128 = 500;
 }
 
 impl indices::Trait for Runtime {
-	/// The type for recording indexing into the account enumeration. If this ever overflows, there
-	/// will be problems!
+	/// The type for recording the indices of the accounts.
 	type AccountIndex = AccountIndex;
-	/// Use the standard means of resolving an index hint from an id.
-	type ResolveHint = indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
+	/// The currency in which the deposits for indices are reserved.
+	type Currency = Balances;
+	/// The deposit needed to claim an index.
+	type Deposit = IndexDeposit;
 	/// Determine whether an account is dead.
 	type IsDeadAccount = Balances;
 	/// The ubiquitous event type.
@@ -202,7 +212,7 @@ impl balances::Trait for Runtime {
 	/// What to do if an account's free balance gets zeroed.
 	type OnFreeBalanceZero = ();
 	/// What to do if a new account is created.
-	type OnNewAccount = Indices;
+	type OnNewAccount = ();
 	/// The ubiquitous event type.
 	type Event = Event;
 	type DustRemoval = ();
@@ -329,6 +339,12 @@ impl_runtime_apis! {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
 			Executive::validate_transaction(tx)
 		}
+
+		fn transaction_sender(tx: <Block as BlockT>::Extrinsic) -> Option<Vec<u8>> {
+			tx.signature
+				.and_then(|(address, _, _)| Indices::lookup_address(address))
+				.map(|who| who.encode())
+		}
 	}
 
 	impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -352,11 +368,30 @@ impl_runtime_apis! {
 			let seed = seed.as_ref().map(|s| rstd::str::from_utf8(&s).expect("Seed is an utf8 string"));
 			opaque::SessionKeys::generate(seed)
 		}
+
+		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+			opaque::SessionKeys::decode_into_raw_public_keys(&encoded)
+		}
 	}
 
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn generate_key_ownership_proof(
+			_set_id: fg_primitives::SetId,
+			_authority_id: fg_primitives::AuthorityId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			// equivocations aren't reported on this chain.
+			None
+		}
+
+		fn report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: fg_primitives::EquivocationProof<<Block as BlockT>::Hash, NumberFor<Block>>,
+			_key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<<Block as BlockT>::Extrinsic> {
+			None
+		}
 	}
 }
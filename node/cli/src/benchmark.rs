@@ -0,0 +1,356 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implementation of the `benchmark` subcommand, running the benchmarks of a runtime module in
+//! the wasm executor and deriving weight constants from the measurements.
+//!
+//! The storage accesses of every benchmarked call are tracked as well, and the cost of a
+//! database read and write is measured on this machine, so that the weights of the calls could
+//! account for their database accesses.
+//!
+//! The subcommand is only available when the node is built with the `runtime-benchmarks` feature.
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+use codec::{Decode, Encode};
+use node_executor::Executor;
+use node_primitives::BlockNumber;
+use primitives::{Blake2Hasher, NeverNativeValue, traits::CodeExecutor};
+use sr_primitives::BuildStorage;
+use srml_benchmarking::{BenchmarkParameter, BenchmarkResults};
+use state_machine::{
+	Backend, DbAccessStats, DbAccessTrackingExt, Ext, InMemoryChangesTrieStorage, MemoryDB,
+	OverlayedChanges, TrieBackend, backend::InMemory,
+};
+use structopt::StructOpt;
+use substrate_cli::{error, SharedParams, WasmExecutionMethodParam};
+use substrate_executor::NativeExecutor;
+use substrate_service::{ChainSpec, RuntimeGenesis};
+
+/// The `benchmark` command used to measure the dispatchables of a runtime module.
+#[derive(Debug, StructOpt, Clone)]
+pub struct BenchmarkCmd {
+	/// Select a module to benchmark, e.g. `balances`.
+	#[structopt(long = "module")]
+	pub module: String,
+
+	/// Select a dispatchable of the module to benchmark, e.g. `transfer`.
+	#[structopt(long = "extrinsic")]
+	pub extrinsic: String,
+
+	/// Number of steps across the range of each component.
+	#[structopt(long = "steps", default_value = "10")]
+	pub steps: u32,
+
+	/// Number of times each step is repeated.
+	#[structopt(long = "repeat", default_value = "1")]
+	pub repeat: u32,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	/// Method for executing Wasm runtime code.
+	#[structopt(
+		long = "wasm-execution",
+		value_name = "METHOD",
+		possible_values = &WasmExecutionMethodParam::enabled_variants(),
+		case_insensitive = true,
+		default_value = "Interpreted"
+	)]
+	pub wasm_method: WasmExecutionMethodParam,
+}
+
+/// Runs the `benchmark` command against the genesis state of the given chain spec.
+pub fn run<G: RuntimeGenesis, E>(cmd: &BenchmarkCmd, spec: &ChainSpec<G, E>) -> error::Result<()> {
+	let mut backend = InMemory::<Blake2Hasher>::from(spec.build_storage()?);
+	let executor = NativeExecutor::<Executor>::new(cmd.wasm_method.clone().into(), None);
+	let call_data = (cmd.module.as_bytes(), cmd.extrinsic.as_bytes(), cmd.steps, cmd.repeat).encode();
+	let mut overlay = OverlayedChanges::default();
+
+	let (result, accesses) = {
+		let mut ext = Ext::<
+			Blake2Hasher,
+			BlockNumber,
+			_,
+			InMemoryChangesTrieStorage<Blake2Hasher, BlockNumber>,
+		>::new(&mut overlay, &backend, None, None);
+		let mut ext = DbAccessTrackingExt::new(&mut ext);
+		let (result, _) = executor.call::<_, NeverNativeValue, fn() -> _>(
+			&mut ext,
+			"Benchmark_dispatch_benchmark",
+			&call_data,
+			false,
+			None,
+		);
+		let result = result.map_err(|e| format!("Error executing runtime benchmark: {}", e))?;
+		(result.into_encoded(), ext.take_calls())
+	};
+
+	let results = <Result<Vec<BenchmarkResults>, String>>::decode(&mut &result[..])
+		.map_err(|e| format!("Failed to decode benchmark results: {}", e))?
+		.map_err(|e| format!("Benchmark failed: {}", e))?;
+
+	// every call is measured on the state it ran on, with the changes of the setups committed.
+	let mut proof_sizes = Vec::with_capacity(accesses.len());
+	let mut times = DbAccessTimes::default();
+	for call in &accesses {
+		if !call.setup.is_empty() {
+			backend = backend.update(call.setup.clone());
+		}
+		let trie = backend.as_trie_backend()
+			.ok_or_else(|| "Failed to build the trie of the benchmarked state".to_string())?;
+		proof_sizes.push(call.proof_size(trie)?);
+		times.measure(trie, call);
+	}
+	let (read_weight, write_weight) = times.averages();
+
+	println!(
+		"Module: {:?}, Extrinsic: {:?}, Steps: {}, Repeat: {}",
+		cmd.module,
+		cmd.extrinsic,
+		cmd.steps,
+		cmd.repeat,
+	);
+	print!("{}", format_results(&results, &accesses, &proof_sizes));
+	println!("Weight (ns): {}", format_weight(&weight_formula(&results)));
+	println!("DB read (ns): {:.0}, DB write (ns): {:.0}", read_weight, write_weight);
+
+	Ok(())
+}
+
+/// Format the measurements as CSV, one line per run, along with the storage accesses of the run.
+fn format_results(results: &[BenchmarkResults], accesses: &[DbAccessStats], proof_sizes: &[u64]) -> String {
+	let mut output = String::new();
+	if let Some((components, _)) = results.first() {
+		for (name, _) in components {
+			output.push_str(&format!("{:?},", name));
+		}
+		output.push_str("time,reads,writes,proof_size\n");
+	}
+	for (((components, time), access), proof_size) in results.iter().zip(accesses).zip(proof_sizes) {
+		for (_, value) in components {
+			output.push_str(&format!("{},", value));
+		}
+		output.push_str(&format!(
+			"{},{},{},{}\n",
+			time,
+			access.reads.len(),
+			access.writes.len(),
+			proof_size,
+		));
+	}
+	output
+}
+
+/// Time spent repeating the storage accesses of the benchmarked calls directly on the state trie,
+/// to measure the cost of a database read and write on this machine.
+#[derive(Default)]
+struct DbAccessTimes {
+	reads: u64,
+	read_time: u128,
+	writes: u64,
+	write_time: u128,
+}
+
+impl DbAccessTimes {
+	/// Repeat the storage accesses of the call on the trie of the state it ran on.
+	///
+	/// Values of the keys read are looked up in the trie, and the trie root is recomputed with the
+	/// keys written.
+	fn measure(&mut self, trie: &TrieBackend<MemoryDB<Blake2Hasher>, Blake2Hasher>, call: &DbAccessStats) {
+		let start = Instant::now();
+		for (storage_key, key) in &call.reads {
+			let _ = match storage_key {
+				Some(storage_key) => trie.child_storage(storage_key, key),
+				None => trie.storage(key),
+			};
+		}
+		self.read_time += start.elapsed().as_nanos();
+		self.reads += call.reads.len() as u64;
+
+		let delta = call.writes.iter()
+			.filter(|(storage_key, _)| storage_key.is_none())
+			.map(|(_, key)| (key.clone(), Some(vec![0u8; value_size(call)])))
+			.collect::<Vec<_>>();
+		if delta.is_empty() {
+			return;
+		}
+		self.writes += delta.len() as u64;
+		let start = Instant::now();
+		let _ = trie.storage_root(delta);
+		self.write_time += start.elapsed().as_nanos();
+	}
+
+	/// Returns the average time in nanoseconds of a database read and write.
+	fn averages(&self) -> (f64, f64) {
+		let average = |time: u128, count: u64| if count == 0 { 0.0 } else { time as f64 / count as f64 };
+		(average(self.read_time, self.reads), average(self.write_time, self.writes))
+	}
+}
+
+/// The average size of the values written by the call.
+fn value_size(call: &DbAccessStats) -> usize {
+	if call.writes.is_empty() {
+		0
+	} else {
+		(call.written_bytes / call.writes.len() as u64) as usize
+	}
+}
+
+/// A weight formula of the form `base + Σ slope * component`, in nanoseconds.
+#[derive(Debug, PartialEq)]
+struct WeightFormula {
+	base: f64,
+	slopes: Vec<(BenchmarkParameter, f64)>,
+}
+
+/// Derive a linear weight formula from benchmark results.
+///
+/// Each component was stepped through its range while the other components were held at their
+/// highest value, so a least squares fit over those runs gives the slope of the component. Its
+/// intercept also contains the cost of the other components at their highest value, which is
+/// subtracted to get the base weight.
+fn weight_formula(results: &[BenchmarkResults]) -> WeightFormula {
+	let mut highest = BTreeMap::new();
+	for (components, _) in results {
+		for (index, (_, value)) in components.iter().enumerate() {
+			let entry = highest.entry(index).or_insert(*value);
+			*entry = (*entry).max(*value);
+		}
+	}
+
+	let names: Vec<BenchmarkParameter> = results.first()
+		.map(|(components, _)| components.iter().map(|(name, _)| *name).collect())
+		.unwrap_or_default();
+
+	if names.is_empty() {
+		let times: Vec<(f64, f64)> = results.iter().map(|(_, time)| (0.0, *time as f64)).collect();
+		return WeightFormula { base: linear_fit(&times).0, slopes: Vec::new() };
+	}
+
+	let fits: Vec<(f64, f64)> = (0..names.len())
+		.map(|index| {
+			let points: Vec<(f64, f64)> = results.iter()
+				.filter(|(components, _)| components.iter()
+					.enumerate()
+					.all(|(i, (_, value))| i == index || Some(value) == highest.get(&i))
+				)
+				.map(|(components, time)| (components[index].1 as f64, *time as f64))
+				.collect();
+			linear_fit(&points)
+		})
+		.collect();
+
+	let base = fits.iter()
+		.enumerate()
+		.map(|(index, (intercept, _))| {
+			intercept - fits.iter()
+				.enumerate()
+				.filter(|(i, _)| *i != index)
+				.map(|(i, (_, slope))| slope * highest[&i] as f64)
+				.sum::<f64>()
+		})
+		.sum::<f64>() / fits.len() as f64;
+
+	WeightFormula {
+		base,
+		slopes: names.into_iter().zip(fits.into_iter().map(|(_, slope)| slope)).collect(),
+	}
+}
+
+/// Least squares fit of `y = a + b * x` over the given points, returning `(a, b)`.
+fn linear_fit(points: &[(f64, f64)]) -> (f64, f64) {
+	if points.is_empty() {
+		return (0.0, 0.0);
+	}
+
+	let n = points.len() as f64;
+	let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+	let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+	let covariance = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+	let variance = points.iter().map(|(x, _)| (x - mean_x) * (x - mean_x)).sum::<f64>();
+
+	let slope = if variance == 0.0 { 0.0 } else { covariance / variance };
+	(mean_y - slope * mean_x, slope)
+}
+
+fn format_weight(formula: &WeightFormula) -> String {
+	let mut output = format!("{:.0}", formula.base.max(0.0));
+	for (name, slope) in &formula.slopes {
+		output.push_str(&format!(" + {:.0} * {:?}", slope.max(0.0), name));
+	}
+	output
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use srml_benchmarking::BenchmarkParameter::{a, b};
+
+	#[test]
+	fn linear_fit_works() {
+		assert_eq!(linear_fit(&[(1.0, 12.0), (2.0, 14.0), (3.0, 16.0)]), (10.0, 2.0));
+		assert_eq!(linear_fit(&[(5.0, 3.0), (5.0, 5.0)]), (4.0, 0.0));
+		assert_eq!(linear_fit(&[]), (0.0, 0.0));
+	}
+
+	#[test]
+	fn weight_formula_separates_components() {
+		// time = 100 + 3 * a + 5 * b
+		let run = |va: u32, vb: u32| (vec![(a, va), (b, vb)], (100 + 3 * va + 5 * vb) as u64);
+		let results = vec![run(0, 10), run(5, 10), run(10, 10), run(10, 0), run(10, 5)];
+
+		assert_eq!(
+			weight_formula(&results),
+			WeightFormula { base: 100.0, slopes: vec![(a, 3.0), (b, 5.0)] },
+		);
+		assert_eq!(format_weight(&weight_formula(&results)), "100 + 3 * a + 5 * b");
+	}
+
+	#[test]
+	fn weight_formula_without_components_is_the_mean() {
+		let results = vec![(Vec::new(), 90), (Vec::new(), 110)];
+
+		assert_eq!(weight_formula(&results), WeightFormula { base: 100.0, slopes: Vec::new() });
+	}
+
+	#[test]
+	fn format_results_prints_csv() {
+		let results = vec![(vec![(a, 1)], 10), (vec![(a, 2)], 20)];
+		let accesses = vec![
+			DbAccessStats { reads: vec![(None, vec![1])], ..Default::default() },
+			DbAccessStats { writes: vec![(None, vec![1]), (None, vec![2])], ..Default::default() },
+		];
+
+		assert_eq!(
+			format_results(&results, &accesses, &[100, 1]),
+			"a,time,reads,writes,proof_size\n1,10,1,0,100\n2,20,0,2,1\n",
+		);
+	}
+
+	#[test]
+	fn value_size_is_the_average() {
+		let call = DbAccessStats {
+			writes: vec![(None, vec![1]), (None, vec![2])],
+			written_bytes: 10,
+			..Default::default()
+		};
+
+		assert_eq!(value_size(&call), 5);
+		assert_eq!(value_size(&DbAccessStats::default()), 0);
+	}
+}
@@ -49,6 +49,21 @@ const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 pub struct Extensions {
 	/// Block numbers with known hashes.
 	pub fork_blocks: client::ForkBlocks<Block>,
+	/// GRANDPA settings of the chain. The defaults of the node are used if missing.
+	pub grandpa: Option<GrandpaSettings>,
+}
+
+/// GRANDPA settings of a chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrandpaSettings {
+	/// Duration of a gossip round, in milliseconds.
+	pub gossip_duration: u64,
+	/// Number of blocks between two justifications stored in the database.
+	pub justification_period: u32,
+	/// Whether voters vote on their best block right away, instead of staying
+	/// behind it to give the other voters time to import it.
+	pub instant_finality: bool,
 }
 
 /// Specialized `ChainSpec`.
@@ -174,6 +189,15 @@ pub fn get_authority_keys_from_seed(seed: &str) -> (AccountId, AccountId, Grandp
 	)
 }
 
+/// Seeds of the accounts endowed by default on development chains.
+const WELL_KNOWN_ACCOUNTS: &[&str] = &[
+	"Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie",
+	"Alice//stash", "Bob//stash", "Charlie//stash", "Dave//stash", "Eve//stash", "Ferdie//stash",
+];
+
+/// Balance of every endowed account, unless a preset says otherwise.
+const ENDOWMENT: Balance = 10_000_000 * DOLLARS;
+
 /// Helper function to create GenesisConfig for testing
 pub fn testnet_genesis(
 	initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId)>,
@@ -182,23 +206,32 @@ pub fn testnet_genesis(
 	enable_println: bool,
 ) -> GenesisConfig {
 	let endowed_accounts: Vec<AccountId> = endowed_accounts.unwrap_or_else(|| {
-		vec![
-			get_account_id_from_seed::<sr25519::Public>("Alice"),
-			get_account_id_from_seed::<sr25519::Public>("Bob"),
-			get_account_id_from_seed::<sr25519::Public>("Charlie"),
-			get_account_id_from_seed::<sr25519::Public>("Dave"),
-			get_account_id_from_seed::<sr25519::Public>("Eve"),
-			get_account_id_from_seed::<sr25519::Public>("Ferdie"),
-			get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
-			get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
-			get_account_id_from_seed::<sr25519::Public>("Charlie//stash"),
-			get_account_id_from_seed::<sr25519::Public>("Dave//stash"),
-			get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
-			get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
-		]
+		WELL_KNOWN_ACCOUNTS.iter()
+			.map(|seed| get_account_id_from_seed::<sr25519::Public>(seed))
+			.collect()
 	});
+	let validator_count = initial_authorities.len() as u32 * 2;
 
-	const ENDOWMENT: Balance = 10_000_000 * DOLLARS;
+	genesis(
+		initial_authorities,
+		root_key,
+		endowed_accounts,
+		ENDOWMENT,
+		validator_count,
+		None,
+		enable_println,
+	)
+}
+
+fn genesis(
+	initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId)>,
+	root_key: AccountId,
+	endowed_accounts: Vec<AccountId>,
+	endowment: Balance,
+	validator_count: u32,
+	epoch_duration: Option<u64>,
+	enable_println: bool,
+) -> GenesisConfig {
 	const STASH: Balance = 100 * DOLLARS;
 
 	GenesisConfig {
@@ -208,10 +241,9 @@ pub fn testnet_genesis(
 		}),
 		balances: Some(BalancesConfig {
 			balances: endowed_accounts.iter().cloned()
-				.map(|k| (k, ENDOWMENT))
+				.map(|k| (k, endowment))
 				.chain(initial_authorities.iter().map(|x| (x.0.clone(), STASH)))
 				.collect(),
-			vesting: vec![],
 		}),
 		indices: Some(IndicesConfig {
 			ids: endowed_accounts.iter().cloned()
@@ -225,7 +257,7 @@ pub fn testnet_genesis(
 		}),
 		staking: Some(StakingConfig {
 			current_era: 0,
-			validator_count: initial_authorities.len() as u32 * 2,
+			validator_count,
 			minimum_validator_count: initial_authorities.len() as u32,
 			stakers: initial_authorities.iter().map(|x| {
 				(x.0.clone(), x.1.clone(), STASH, StakerStatus::Validator)
@@ -255,6 +287,7 @@ pub fn testnet_genesis(
 		}),
 		babe: Some(BabeConfig {
 			authorities: vec![],
+			epoch_duration,
 		}),
 		im_online: Some(ImOnlineConfig {
 			keys: vec![],
@@ -264,32 +297,173 @@ pub fn testnet_genesis(
 		}),
 		membership_Instance1: Some(Default::default()),
 		treasury: Some(Default::default()),
+		vesting: Some(Default::default()),
 	}
 }
 
-fn development_config_genesis() -> GenesisConfig {
-	testnet_genesis(
-		vec![
-			get_authority_keys_from_seed("Alice"),
-		],
-		get_account_id_from_seed::<sr25519::Public>("Alice"),
-		None,
-		true,
-	)
+/// A named development chain preset.
+///
+/// All keys of a preset are derived from the well-known development phrase, so that
+/// variants of the development chain can be started with `--chain <id>` instead of
+/// hand-editing a chain spec. The pre-funded accounts can be replaced with `--dev-accounts`.
+#[derive(Debug, PartialEq)]
+pub struct DevPreset {
+	/// Identifier of the preset, used both for `--chain` and as the chain id.
+	pub id: &'static str,
+	/// Human readable name of the chain.
+	pub name: &'static str,
+	/// Seeds of the initial authorities. Stash, controller and session keys are derived from these.
+	pub authorities: &'static [&'static str],
+	/// Seeds of the accounts pre-funded at genesis, on top of the authority stashes.
+	pub endowed_accounts: &'static [&'static str],
+	/// Balance of every pre-funded account.
+	pub endowment: Balance,
+	/// Seed of the sudo key.
+	pub root: &'static str,
+	/// Ideal number of validators. Defaults to twice the number of authorities.
+	pub validator_count: Option<u32>,
+	/// Number of slots of a BABE epoch. Defaults to the one of the runtime.
+	pub epoch_duration: Option<u64>,
+	/// GRANDPA settings. Defaults to the ones of the node.
+	pub grandpa: Option<GrandpaSettings>,
+	/// Whether contracts are allowed to print to the node's console.
+	pub enable_println: bool,
+}
+
+/// All known development presets.
+pub const DEV_PRESETS: &[DevPreset] = &[
+	// single validator Alice
+	DevPreset {
+		id: "dev",
+		name: "Development",
+		authorities: &["Alice"],
+		endowed_accounts: WELL_KNOWN_ACCOUNTS,
+		endowment: ENDOWMENT,
+		root: "Alice",
+		validator_count: None,
+		epoch_duration: None,
+		grandpa: None,
+		enable_println: true,
+	},
+	// Alice, Bob and Charlie validating, with room for the other well-known accounts to join
+	DevPreset {
+		id: "dev-multi",
+		name: "Development (multi-validator)",
+		authorities: &["Alice", "Bob", "Charlie"],
+		endowed_accounts: WELL_KNOWN_ACCOUNTS,
+		endowment: ENDOWMENT,
+		root: "Alice",
+		validator_count: Some(6),
+		epoch_duration: None,
+		grandpa: None,
+		enable_println: true,
+	},
+	// all well-known accounts validating from genesis
+	DevPreset {
+		id: "dev-all",
+		name: "Development (all well-known validators)",
+		authorities: &["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"],
+		endowed_accounts: WELL_KNOWN_ACCOUNTS,
+		endowment: ENDOWMENT,
+		root: "Alice",
+		validator_count: None,
+		epoch_duration: None,
+		grandpa: None,
+		enable_println: true,
+	},
+	// single validator Alice, with only Alice funded and a fixed validator set
+	DevPreset {
+		id: "dev-minimal",
+		name: "Development (minimal)",
+		authorities: &["Alice"],
+		endowed_accounts: &["Alice"],
+		endowment: ENDOWMENT,
+		root: "Alice",
+		validator_count: Some(1),
+		epoch_duration: None,
+		grandpa: None,
+		enable_println: true,
+	},
+	// single validator Alice, with one minute epochs and every block finalized right away
+	DevPreset {
+		id: "dev-fast",
+		name: "Development (fast epochs and finality)",
+		authorities: &["Alice"],
+		endowed_accounts: WELL_KNOWN_ACCOUNTS,
+		endowment: ENDOWMENT,
+		root: "Alice",
+		validator_count: None,
+		epoch_duration: Some(20),
+		grandpa: Some(GrandpaSettings {
+			gossip_duration: 100,
+			justification_period: 1,
+			instant_finality: true,
+		}),
+		enable_println: true,
+	},
+];
+
+/// Returns the development preset with the given id, if any.
+pub fn dev_preset(id: &str) -> Option<&'static DevPreset> {
+	DEV_PRESETS.iter().find(|preset| preset.id == id)
+}
+
+impl DevPreset {
+	/// Build the genesis config described by this preset.
+	pub fn genesis(&self) -> GenesisConfig {
+		self.genesis_endowing(self.endowed_accounts)
+	}
+
+	/// Build the genesis config described by this preset, pre-funding the accounts with the given
+	/// seeds instead of the ones of the preset.
+	pub fn genesis_endowing<S: AsRef<str>>(&self, endowed_accounts: &[S]) -> GenesisConfig {
+		let initial_authorities: Vec<_> = self.authorities.iter()
+			.map(|seed| get_authority_keys_from_seed(seed))
+			.collect();
+		let endowed_accounts = endowed_accounts.iter()
+			.map(|seed| get_account_id_from_seed::<sr25519::Public>(seed.as_ref()))
+			.collect();
+		let validator_count = self.validator_count
+			.unwrap_or(initial_authorities.len() as u32 * 2);
+
+		genesis(
+			initial_authorities,
+			get_account_id_from_seed::<sr25519::Public>(self.root),
+			endowed_accounts,
+			self.endowment,
+			validator_count,
+			self.epoch_duration,
+			self.enable_println,
+		)
+	}
+
+	/// Build the chain spec described by this preset.
+	pub fn chain_spec(&'static self) -> ChainSpec {
+		self.chain_spec_endowing(self.endowed_accounts.iter().map(|seed| seed.to_string()).collect())
+	}
+
+	/// Build the chain spec described by this preset, pre-funding the accounts with the given
+	/// seeds instead of the ones of the preset.
+	pub fn chain_spec_endowing(&'static self, endowed_accounts: Vec<String>) -> ChainSpec {
+		ChainSpec::from_genesis(
+			self.name,
+			self.id,
+			move || self.genesis_endowing(&endowed_accounts),
+			vec![],
+			None,
+			None,
+			None,
+			Extensions {
+				fork_blocks: None,
+				grandpa: self.grandpa.clone(),
+			},
+		)
+	}
 }
 
 /// Development config (single validator Alice)
 pub fn development_config() -> ChainSpec {
-	ChainSpec::from_genesis(
-		"Development",
-		"dev",
-		development_config_genesis,
-		vec![],
-		None,
-		None,
-		None,
-		Default::default(),
-	)
+	dev_preset("dev").expect("`dev` preset is always defined; qed").chain_spec()
 }
 
 fn local_testnet_genesis() -> GenesisConfig {
@@ -364,6 +538,52 @@ pub(crate) mod tests {
 		)
 	}
 
+	#[test]
+	fn dev_presets_are_consistent() {
+		for (i, preset) in DEV_PRESETS.iter().enumerate() {
+			assert!(DEV_PRESETS[..i].iter().all(|other| other.id != preset.id));
+			assert_eq!(dev_preset(preset.id), Some(preset));
+
+			let genesis = preset.genesis();
+			let balances = genesis.balances.unwrap().balances;
+			let staking = genesis.staking.unwrap();
+			assert_eq!(staking.stakers.len(), preset.authorities.len());
+			assert!(staking.validator_count >= staking.minimum_validator_count);
+			for seed in preset.authorities {
+				let (stash, ..) = get_authority_keys_from_seed(seed);
+				assert!(balances.iter().any(|(who, _)| *who == stash));
+			}
+		}
+	}
+
+	#[test]
+	fn dev_preset_accounts_can_be_replaced() {
+		let preset = dev_preset("dev-fast").unwrap();
+		let endowed = |seed| get_account_id_from_seed::<sr25519::Public>(seed);
+
+		let genesis = preset.genesis_endowing(&["Ferdie"]);
+		let balances = genesis.balances.unwrap().balances;
+		assert!(balances.iter().any(|(who, _)| *who == endowed("Ferdie")));
+		assert!(balances.iter().all(|(who, _)| *who != endowed("Bob")));
+		assert_eq!(genesis.babe.unwrap().epoch_duration, Some(20));
+
+		let spec = preset.chain_spec_endowing(vec!["Ferdie".into()]);
+		assert_eq!(spec.extensions().grandpa, preset.grandpa);
+	}
+
+	#[test]
+	fn dev_preset_matches_testnet_genesis() {
+		let preset = dev_preset("dev").unwrap().genesis();
+		let expected = testnet_genesis(
+			vec![get_authority_keys_from_seed("Alice")],
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			None,
+			true,
+		);
+		assert_eq!(preset.balances.unwrap().balances, expected.balances.unwrap().balances);
+		assert_eq!(preset.staking.unwrap().validator_count, expected.staking.unwrap().validator_count);
+	}
+
 	#[test]
 	#[ignore]
 	fn test_connectivity() {
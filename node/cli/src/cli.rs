@@ -22,10 +22,14 @@ use substrate_service::{AbstractService, Roles as ServiceRoles, Configuration};
 use log::info;
 use structopt::{StructOpt, clap::App};
 use substrate_cli::{display_role, parse_and_prepare, AugmentClap, GetLogFilter, ParseAndPrepare};
-use crate::{service, ChainSpec, load_spec};
+use crate::{service, ChainSpec, load_spec, load_spec_endowing};
 use crate::factory_impl::FactoryState;
+pub use crate::inspect::{InspectCmd, InspectSubCmd};
+#[cfg(feature = "runtime-benchmarks")]
+pub use crate::benchmark::BenchmarkCmd;
 use transaction_factory::RuntimeAdapter;
-use client::ExecutionStrategies;
+use client::{ExecutionStrategies, runtime_api::Metadata};
+use sr_primitives::{generic::BlockId, traits::ProvideRuntimeApi};
 
 /// Custom subcommands.
 #[derive(Clone, Debug, StructOpt)]
@@ -37,6 +41,15 @@ pub enum CustomSubcommands {
 		Only supported for development or local testnet."
 	)]
 	Factory(FactoryCmd),
+
+	/// Decode blocks and extrinsics and print them in a human-readable form.
+	#[structopt(name = "inspect")]
+	Inspect(InspectCmd),
+
+	/// Benchmark the dispatchables of a runtime module and print the derived weights.
+	#[cfg(feature = "runtime-benchmarks")]
+	#[structopt(name = "benchmark")]
+	Benchmark(BenchmarkCmd),
 }
 
 impl GetLogFilter for CustomSubcommands {
@@ -45,6 +58,21 @@ impl GetLogFilter for CustomSubcommands {
 	}
 }
 
+/// Parameters of the `run` command specific to the node.
+#[derive(Clone, Debug, Default, StructOpt)]
+pub struct RunParams {
+	/// Seeds of the accounts pre-funded at genesis, replacing the ones of the development preset
+	/// given with `--chain`, e.g. `--dev-accounts Alice,Bob,Alice//stash`.
+	#[structopt(long = "dev-accounts", value_name = "SEEDS", use_delimiter = true)]
+	pub dev_accounts: Vec<String>,
+}
+
+impl AugmentClap for RunParams {
+	fn augment_clap<'a, 'b>(app: App<'a, 'b>) -> App<'a, 'b> {
+		RunParams::augment_clap(app)
+	}
+}
+
 /// The `factory` command used to generate transactions.
 /// Please note: this command currently only works on an empty database!
 #[derive(Debug, StructOpt, Clone)]
@@ -107,36 +135,49 @@ pub fn run<I, T, E>(args: I, exit: E, version: substrate_cli::VersionInfo) -> er
 {
 	type Config<A, B> = Configuration<(), A, B>;
 
-	match parse_and_prepare::<CustomSubcommands, NoCustom, _>(&version, "substrate-node", args) {
-		ParseAndPrepare::Run(cmd) => cmd.run(load_spec, exit,
-		|exit, _cli_args, _custom_args, config: Config<_, _>| {
-			info!("{}", version.name);
-			info!("  version {}", config.full_version());
-			info!("  by Parity Technologies, 2017-2019");
-			info!("Chain specification: {}", config.chain_spec.name());
-			info!("Node name: {}", config.name);
-			info!("Roles: {}", display_role(&config));
-			let runtime = RuntimeBuilder::new().name_prefix("main-tokio-").build()
-				.map_err(|e| format!("{:?}", e))?;
-			match config.roles {
-				ServiceRoles::LIGHT => run_until_exit(
-					runtime,
-					service::new_light(config)?,
-					exit
-				),
-				_ => run_until_exit(
-					runtime,
-					service::new_full(config)?,
-					exit
-				),
-			}
-		}),
-		ParseAndPrepare::BuildSpec(cmd) => cmd.run::<NoCustom, _, _, _>(load_spec),
+	match parse_and_prepare::<CustomSubcommands, RunParams, _>(&version, "substrate-node", args) {
+		ParseAndPrepare::Run(cmd) => {
+			let dev_accounts = cmd.custom_params().dev_accounts.clone();
+			cmd.run(move |id| load_spec_endowing(id, dev_accounts), exit,
+			|exit, _cli_args, _custom_args, config: Config<_, _>| {
+				info!("{}", version.name);
+				info!("  version {}", config.full_version());
+				info!("  by Parity Technologies, 2017-2019");
+				info!("Chain specification: {}", config.chain_spec.name());
+				info!("Node name: {}", config.name);
+				info!("Roles: {}", display_role(&config));
+				let runtime = RuntimeBuilder::new().name_prefix("main-tokio-").build()
+					.map_err(|e| format!("{:?}", e))?;
+				match config.roles {
+					ServiceRoles::LIGHT => run_until_exit(
+						runtime,
+						service::new_light(config)?,
+						exit
+					),
+					_ => run_until_exit(
+						runtime,
+						service::new_full(config)?,
+						exit
+					),
+				}
+			})
+		},
+		ParseAndPrepare::BuildSpec(cmd) => cmd.run_with_builder(|config: Config<_, _>|
+			Ok(new_full_start!(config).0), load_spec),
 		ParseAndPrepare::ExportBlocks(cmd) => cmd.run_with_builder(|config: Config<_, _>|
 			Ok(new_full_start!(config).0), load_spec, exit),
+		ParseAndPrepare::ExportState(cmd) => cmd.run_with_builder(|config: Config<_, _>|
+			Ok(new_full_start!(config).0), load_spec),
+		ParseAndPrepare::ExportSnapshot(cmd) => cmd.run_with_builder(|config: Config<_, _>|
+			Ok(new_full_start!(config).0), load_spec),
+		ParseAndPrepare::ImportSnapshot(cmd) => cmd.run::<node_primitives::Block, _, _, _>(load_spec),
 		ParseAndPrepare::ImportBlocks(cmd) => cmd.run_with_builder(|config: Config<_, _>|
 			Ok(new_full_start!(config).0), load_spec, exit),
-		ParseAndPrepare::PurgeChain(cmd) => cmd.run(load_spec),
+		ParseAndPrepare::PurgeChain(cmd) => cmd.run_with_builder(|config: Config<_, _>|
+			Ok(new_full_start!(config).0), load_spec, exit),
+		ParseAndPrepare::MigrateDb(cmd) => cmd.run(load_spec),
+		ParseAndPrepare::Key(cmd) => cmd.run(load_spec),
+		ParseAndPrepare::PrintConfig(cmd) => cmd.run(),
 		ParseAndPrepare::RevertChain(cmd) => cmd.run_with_builder(|config: Config<_, _>|
 			Ok(new_full_start!(config).0), load_spec),
 		ParseAndPrepare::CustomCommand(CustomSubcommands::Factory(cli_args)) => {
@@ -173,6 +214,34 @@ pub fn run<I, T, E>(args: I, exit: E, version: substrate_cli::VersionInfo) -> er
 
 			Ok(())
 		}
+		ParseAndPrepare::CustomCommand(CustomSubcommands::Inspect(cmd)) => {
+			let config: Config<_, _> = substrate_cli::create_config_with_db_path(
+				load_spec,
+				&cmd.shared_params,
+				&version,
+			)?;
+
+			let full_client = new_full_start!(config).0.client();
+			let blocks = full_client.clone();
+			crate::inspect::run(
+				&cmd.command,
+				move |id| Ok(blocks.block(&id)?.map(|signed| signed.block)),
+				move |at| {
+					let at = at.unwrap_or_else(|| BlockId::Hash(full_client.info().chain.best_hash));
+					Ok(full_client.runtime_api().metadata(&at)?.to_vec())
+				},
+			)
+		}
+		#[cfg(feature = "runtime-benchmarks")]
+		ParseAndPrepare::CustomCommand(CustomSubcommands::Benchmark(cmd)) => {
+			let config: Config<_, _> = substrate_cli::create_config_with_db_path(
+				load_spec,
+				&cmd.shared_params,
+				&version,
+			)?;
+
+			crate::benchmark::run(&cmd, &config.chain_spec)
+		}
 	}
 }
 
@@ -0,0 +1,357 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Implementation of the `inspect` subcommand, decoding blocks and extrinsics through the metadata
+//! of the runtime and printing them in a human-readable form.
+
+use std::io::Read;
+use codec::{Decode, Encode};
+use node_primitives::{Block, BlockNumber, Hash, Signature};
+use node_runtime::{Address, SignedExtra};
+use primitives::{H256, hexdisplay::HexDisplay};
+use sr_primitives::{generic::{self, BlockId}, traits::{Block as BlockT, Header as HeaderT}};
+use structopt::StructOpt;
+use substrate_cli::{error, SharedParams};
+use substrate_metadata_decode::{Decoder, TypeDef, TypeRegistry, Value};
+
+/// The `inspect` command used to print decoded chain data.
+#[derive(Debug, StructOpt, Clone)]
+pub struct InspectCmd {
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub command: InspectSubCmd,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// What to inspect.
+#[derive(Debug, StructOpt, Clone)]
+pub enum InspectSubCmd {
+	/// Decode a block and print out its details.
+	#[structopt(name = "block")]
+	Block {
+		/// Address of the block to print out.
+		///
+		/// Can be a block hash (`0x` prefixed) or number to read from the database, the
+		/// hex-encoded SCALE encoding of a block, or `-` to read the latter from stdin.
+		input: String,
+	},
+	/// Decode an extrinsic and print out its details.
+	#[structopt(name = "extrinsic")]
+	Extrinsic {
+		/// Address of the extrinsic to print out.
+		///
+		/// Can be a block address followed by the index of the extrinsic (`<hash|number>:<index>`)
+		/// to read from the database, the hex-encoded SCALE encoding of an extrinsic, or `-` to
+		/// read the latter from stdin.
+		input: String,
+	},
+}
+
+/// Runs the `inspect` command.
+///
+/// The database is only accessed through `load_block`, if the input refers to a block in it, and
+/// through `load_metadata`, which returns the encoded metadata of the runtime at the given block,
+/// or at the best block if `None`.
+pub fn run(
+	command: &InspectSubCmd,
+	load_block: impl FnOnce(BlockId<Block>) -> error::Result<Option<Block>>,
+	load_metadata: impl FnOnce(Option<BlockId<Block>>) -> error::Result<Vec<u8>>,
+) -> error::Result<()> {
+	let decoder = |metadata: Vec<u8>| Decoder::new(&metadata, type_registry())
+		.map_err(|e| format!("Unable to read the runtime metadata: {}", e));
+
+	match command {
+		InspectSubCmd::Block { input } => {
+			let block = match read_input(input)? {
+				Input::Bytes(bytes) => Block::decode(&mut &bytes[..])
+					.map_err(|e| format!("Unable to decode block: {}", e))?,
+				Input::Address(address) => {
+					let id = parse_block_id(&address)?;
+					load_block(id.clone())?.ok_or_else(|| format!("Block {} not found", id))?
+				},
+			};
+			let decoder = decoder(load_metadata(Some(runtime_of(&block)))?)?;
+			println!("{}", format_block(&decoder, &block)?);
+		},
+		InspectSubCmd::Extrinsic { input } => {
+			let (bytes, at) = match read_input(input)? {
+				Input::Bytes(bytes) => (bytes, None),
+				Input::Address(address) => {
+					let mut parts = address.splitn(2, ':');
+					let (block, index) = match (parts.next(), parts.next()) {
+						(Some(block), Some(index)) => (block, index),
+						_ => return Err(format!(
+							"Expected an extrinsic address as `<hash|number>:<index>`, got {}", address,
+						).into()),
+					};
+					let id = parse_block_id(block)?;
+					let index: usize = index.parse()
+						.map_err(|e| format!("Invalid extrinsic index {}: {}", index, e))?;
+					let block = load_block(id.clone())?.ok_or_else(|| format!("Block {} not found", id))?;
+					let opaque = block.extrinsics().get(index)
+						.ok_or_else(|| format!("Block {} has no extrinsic #{}", id, index))?;
+					(opaque.encode(), Some(runtime_of(&block)))
+				},
+			};
+			let decoder = decoder(load_metadata(at)?)?;
+			println!("{}", format_extrinsic(&decoder, &bytes)?);
+		},
+	}
+
+	Ok(())
+}
+
+/// Returns the block whose runtime executed the extrinsics of `block`, i.e. its parent, unless
+/// it's the genesis block.
+fn runtime_of(block: &Block) -> BlockId<Block> {
+	let header = block.header();
+	if *header.number() == 0 {
+		BlockId::Hash(header.hash())
+	} else {
+		BlockId::Hash(*header.parent_hash())
+	}
+}
+
+/// The input of the command, once read.
+enum Input {
+	/// The SCALE encoding of the inspected item.
+	Bytes(Vec<u8>),
+	/// The address of the inspected item in the database.
+	Address(String),
+}
+
+/// Reads the input from stdin if it's `-`, and tells hex-encoded items from addresses.
+fn read_input(input: &str) -> error::Result<Input> {
+	if input == "-" {
+		let mut buffer = String::new();
+		std::io::stdin().read_to_string(&mut buffer)?;
+		return Ok(Input::Bytes(decode_hex(&buffer)?));
+	}
+	let input = input.trim();
+
+	// Block hashes are also `0x` prefixed, but are too short to encode anything we inspect, and
+	// extrinsic addresses (`0x<hash>:<index>`) have an index, which hex can't contain.
+	let block = input.splitn(2, ':').next().unwrap_or(input);
+	let is_hash = block.len() == 2 + 2 * std::mem::size_of::<Hash>();
+	if input.starts_with("0x") && !is_hash && !input.contains(':') {
+		Ok(Input::Bytes(decode_hex(input)?))
+	} else {
+		Ok(Input::Address(input.to_owned()))
+	}
+}
+
+/// Parses a block given either as a `0x` prefixed hash or as a number.
+fn parse_block_id(block: &str) -> error::Result<BlockId<Block>> {
+	if block.starts_with("0x") {
+		let hash = block[2..].parse::<H256>()
+			.map_err(|e| format!("Invalid block hash {}: {:?}", block, e))?;
+		Ok(BlockId::Hash(hash))
+	} else {
+		let number = block.parse::<BlockNumber>()
+			.map_err(|e| format!("Invalid block number {}: {}", block, e))?;
+		Ok(BlockId::Number(number))
+	}
+}
+
+/// Decodes a hex string, optionally `0x` prefixed.
+fn decode_hex(input: &str) -> error::Result<Vec<u8>> {
+	let input = input.trim();
+	let input = if input.starts_with("0x") { &input[2..] } else { input };
+	if !input.is_ascii() || input.len() % 2 != 0 {
+		return Err(format!("Invalid hex input {}", input).into());
+	}
+
+	(0..input.len()).step_by(2)
+		.map(|i| u8::from_str_radix(&input[i..i + 2], 16)
+			.map_err(|e| format!("Invalid hex input {}: {}", input, e).into())
+		)
+		.collect()
+}
+
+/// The call of an extrinsic, left encoded to be decoded through the runtime metadata.
+struct EncodedCall(Vec<u8>);
+
+impl Decode for EncodedCall {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		// The call is the last field of an extrinsic, it takes the rest of the encoding.
+		let len = input.remaining_len()?.ok_or("Unknown length of the call")?;
+		let mut call = vec![0; len];
+		input.read(&mut call)?;
+		Ok(EncodedCall(call))
+	}
+}
+
+/// An extrinsic of the node's runtime, whose call is decoded through the runtime metadata.
+type Extrinsic = generic::UncheckedExtrinsic<Address, EncodedCall, Signature, SignedExtra>;
+
+/// Formats a block and all its extrinsics.
+fn format_block(decoder: &Decoder, block: &Block) -> error::Result<String> {
+	let header = block.header();
+	let mut out = format!(
+		"Block #{} ({})\n\
+		\tparent hash: {}\n\
+		\tstate root: {}\n\
+		\textrinsics root: {}\n\
+		\tdigest:\n",
+		header.number(),
+		header.hash(),
+		header.parent_hash(),
+		header.state_root(),
+		header.extrinsics_root(),
+	);
+	for log in header.digest().logs() {
+		out.push_str(&format!("\t\t{:?}\n", log));
+	}
+
+	out.push_str(&format!("\textrinsics ({}):\n", block.extrinsics().len()));
+	for (index, opaque) in block.extrinsics().iter().enumerate() {
+		let formatted = format_extrinsic(decoder, &opaque.encode())
+			.map_err(|e| format!("Extrinsic #{}: {}", index, e))?
+			.replace("\n", "\n\t\t\t");
+		out.push_str(&format!("\t\t#{}: {}\n", index, formatted));
+	}
+
+	Ok(out)
+}
+
+/// Decodes an extrinsic from its SCALE encoding and formats its signer and its call.
+fn format_extrinsic(decoder: &Decoder, bytes: &[u8]) -> error::Result<String> {
+	let extrinsic = Extrinsic::decode(&mut &bytes[..])
+		.map_err(|e| format!("Unable to decode extrinsic: {}", e))?;
+	// Print the raw call rather than failing if it uses a type the registry doesn't know.
+	let call = match decoder.decode_call(&extrinsic.function.0) {
+		Ok(call) => format_value(&call.into())?,
+		Err(e) => format!("0x{} (unable to decode: {})", HexDisplay::from(&extrinsic.function.0), e),
+	};
+
+	Ok(match &extrinsic.signature {
+		Some((signer, signature, extra)) => {
+			let signer = decoder.decode_value("Address", &signer.encode())
+				.map_err(|e| format!("Unable to decode signer: {}", e))?;
+			format!(
+				"signed extrinsic\n\
+				signer: {}\n\
+				signature: 0x{}\n\
+				extra: {:?}\n\
+				call: {}",
+				format_value(&signer)?,
+				HexDisplay::from(&signature.encode()),
+				extra,
+				call,
+			)
+		},
+		None => format!("unsigned extrinsic\ncall: {}", call),
+	})
+}
+
+/// Returns the SRML types, along with the types the node runtime defines itself.
+fn type_registry() -> TypeRegistry {
+	let mut registry = TypeRegistry::default();
+	registry
+		.register("Keys", TypeDef::Struct(vec![
+			("grandpa".into(), "[u8; 32]".into()),
+			("babe".into(), "[u8; 32]".into()),
+			("im_online".into(), "[u8; 32]".into()),
+		]))
+		.register("ProxyType", TypeDef::Enum(vec![
+			("Any".into(), vec![]),
+			("NonTransfer".into(), vec![]),
+			("Staking".into(), vec![]),
+			("Governance".into(), vec![]),
+		]));
+	registry
+}
+
+/// Formats a decoded value as pretty-printed JSON.
+fn format_value(value: &Value) -> error::Result<String> {
+	serde_json::to_string_pretty(value).map_err(|e| format!("Unable to format value: {}", e).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_decode_hex() {
+		assert_eq!(decode_hex("0x0aff").unwrap(), vec![0x0a, 0xff]);
+		assert_eq!(decode_hex(" 0aff\n").unwrap(), vec![0x0a, 0xff]);
+		assert!(decode_hex("0x0af").is_err());
+		assert!(decode_hex("0xzz").is_err());
+	}
+
+	#[test]
+	fn should_tell_addresses_from_encoded_items() {
+		let hash = format!("0x{}", "00".repeat(32));
+		match read_input(&hash).unwrap() {
+			Input::Address(address) => assert_eq!(address, hash),
+			Input::Bytes(_) => panic!("a block hash is an address"),
+		}
+		let extrinsic = format!("{}:1", hash);
+		match read_input(&extrinsic).unwrap() {
+			Input::Address(address) => assert_eq!(address, extrinsic),
+			Input::Bytes(_) => panic!("an extrinsic in a block given by hash is an address"),
+		}
+		match read_input("42:1").unwrap() {
+			Input::Address(address) => assert_eq!(address, "42:1"),
+			Input::Bytes(_) => panic!("a block number is an address"),
+		}
+		match read_input("0x0aff").unwrap() {
+			Input::Bytes(bytes) => assert_eq!(bytes, vec![0x0a, 0xff]),
+			Input::Address(_) => panic!("hex is an encoded item"),
+		}
+	}
+
+	#[test]
+	fn should_inspect_encoded_extrinsic() {
+		let decoder = Decoder::new(&node_runtime::Runtime::metadata().encode(), type_registry()).unwrap();
+		let extrinsic = node_runtime::UncheckedExtrinsic::new_unsigned(
+			node_runtime::Call::Timestamp(timestamp::Call::set(42))
+		);
+
+		let formatted = format_extrinsic(&decoder, &extrinsic.encode()).unwrap();
+
+		assert!(formatted.starts_with("unsigned extrinsic"));
+		assert!(formatted.contains(r#""module": "Timestamp""#));
+		assert!(formatted.contains(r#""call": "set""#));
+		assert!(formatted.contains(r#""now": 42"#));
+	}
+
+	#[test]
+	fn should_inspect_undecodable_call() {
+		let decoder = Decoder::new(&node_runtime::Runtime::metadata().encode(), type_registry()).unwrap();
+		// An unsigned extrinsic of version 4, calling a module that doesn't exist.
+		let extrinsic = vec![0x04u8, 0xff, 0x00];
+
+		let formatted = format_extrinsic(&decoder, &extrinsic.encode()).unwrap();
+
+		assert!(formatted.starts_with("unsigned extrinsic"));
+		assert!(formatted.contains("call: 0xff00 (unable to decode:"));
+	}
+
+	#[test]
+	fn should_know_the_node_types() {
+		let decoder = Decoder::new(&node_runtime::Runtime::metadata().encode(), type_registry()).unwrap();
+
+		assert!(decoder.decode_value("T::Keys", &node_runtime::SessionKeys::default().encode()).is_ok());
+		assert_eq!(
+			decoder.decode_value("T::ProxyType", &node_runtime::ProxyType::Staking.encode()).unwrap(),
+			Value::Variant("Staking".into(), vec![]),
+		);
+	}
+}
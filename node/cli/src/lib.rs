@@ -39,6 +39,10 @@ mod browser;
 mod cli;
 #[cfg(feature = "cli")]
 mod factory_impl;
+#[cfg(feature = "cli")]
+mod inspect;
+#[cfg(all(feature = "cli", feature = "runtime-benchmarks"))]
+mod benchmark;
 
 #[cfg(feature = "browser")]
 pub use browser::*;
@@ -56,6 +60,8 @@ pub enum ChainSpec {
 	FlamingFir,
 	/// Whatever the current runtime is with the "global testnet" defaults.
 	StagingTestnet,
+	/// Whatever the current runtime is, with one of the named development presets.
+	DevPreset(&'static chain_spec::DevPreset),
 }
 
 /// Get a chain config from a spec setting.
@@ -66,6 +72,7 @@ impl ChainSpec {
 			ChainSpec::Development => chain_spec::development_config(),
 			ChainSpec::LocalTestnet => chain_spec::local_testnet_config(),
 			ChainSpec::StagingTestnet => chain_spec::staging_testnet_config(),
+			ChainSpec::DevPreset(preset) => preset.chain_spec(),
 		})
 	}
 
@@ -75,7 +82,7 @@ impl ChainSpec {
 			"local" => Some(ChainSpec::LocalTestnet),
 			"" | "fir" | "flaming-fir" => Some(ChainSpec::FlamingFir),
 			"staging" => Some(ChainSpec::StagingTestnet),
-			_ => None,
+			id => chain_spec::dev_preset(id).map(ChainSpec::DevPreset),
 		}
 	}
 }
@@ -86,3 +93,22 @@ fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
 		None => None,
 	})
 }
+
+/// Like `load_spec`, but pre-funds the accounts with the given seeds instead of the ones of the
+/// development preset. Fails for chains that aren't development presets.
+#[cfg(feature = "cli")]
+fn load_spec_endowing(id: &str, endowed_accounts: Vec<String>) -> Result<Option<chain_spec::ChainSpec>, String> {
+	if endowed_accounts.is_empty() {
+		return load_spec(id);
+	}
+
+	let preset = match ChainSpec::from(id) {
+		Some(ChainSpec::Development) => chain_spec::dev_preset("dev"),
+		Some(ChainSpec::DevPreset(preset)) => Some(preset),
+		_ => None,
+	};
+	match preset {
+		Some(preset) => Ok(Some(preset.chain_spec_endowing(endowed_accounts))),
+		None => Err(format!("Only development presets accept --dev-accounts, got chain {}", id)),
+	}
+}
@@ -56,6 +56,8 @@ macro_rules! new_full_start {
 		type RpcExtension = jsonrpc_core::IoHandler<substrate_rpc::Metadata>;
 		let mut import_setup = None;
 		let inherent_data_providers = inherents::InherentDataProviders::new();
+		let shared_voter_state = grandpa::SharedVoterState::empty();
+		let rpc_setup = shared_voter_state.clone();
 
 		let builder = substrate_service::ServiceBuilder::new_full::<
 			node_primitives::Block, node_runtime::RuntimeApi, node_executor::Executor
@@ -83,6 +85,10 @@ macro_rules! new_full_start {
 					client.clone(),
 				)?;
 
+				// the equivocations detected by the import queue and by the GRANDPA voter are
+				// reported through the transaction pool.
+				let (equivocation_reports_tx, equivocation_reports_rx) = futures03::channel::mpsc::unbounded();
+
 				let import_queue = babe::import_queue(
 					babe_link.clone(),
 					block_import.clone(),
@@ -91,16 +97,26 @@ macro_rules! new_full_start {
 					client.clone(),
 					client,
 					inherent_data_providers.clone(),
+					Some(equivocation_reports_tx.clone()),
 				)?;
 
-				import_setup = Some((block_import, grandpa_link, babe_link));
+				import_setup = Some((
+					block_import,
+					grandpa_link,
+					babe_link,
+					equivocation_reports_tx,
+					equivocation_reports_rx,
+				));
 				Ok(import_queue)
 			})?
-			.with_rpc_extensions(|client, pool, _backend| -> RpcExtension {
-				node_rpc::create(client, pool)
+			.with_rpc_extensions(|client, pool, backend| -> RpcExtension {
+				let finality_proof_provider = Arc::new(
+					grandpa::FinalityProofProvider::new(backend, client.clone())
+				);
+				node_rpc::create_full(client, pool, finality_proof_provider, shared_voter_state.clone())
 			})?;
 
-		(builder, import_setup, inherent_data_providers)
+		(builder, import_setup, inherent_data_providers, rpc_setup)
 	}}
 }
 
@@ -111,18 +127,22 @@ macro_rules! new_full_start {
 macro_rules! new_full {
 	($config:expr, $with_startup_data: expr) => {{
 		use futures::sync::mpsc;
+		use futures03::{FutureExt, StreamExt, TryFutureExt};
 		use network::DhtEvent;
+		use sr_primitives::generic::BlockId;
 
 		let (
 			is_authority,
 			force_authoring,
 			name,
-			disable_grandpa
+			disable_grandpa,
+			grandpa_settings,
 		) = (
 			$config.roles.is_authority(),
 			$config.force_authoring,
 			$config.name.clone(),
-			$config.disable_grandpa
+			$config.disable_grandpa,
+			$config.chain_spec.extensions().grandpa.clone(),
 		);
 
 		// sentry nodes announce themselves as authorities to the network
@@ -130,7 +150,8 @@ macro_rules! new_full {
 		// never actively participate in any consensus process.
 		let participates_in_consensus = is_authority && !$config.sentry_mode;
 
-		let (builder, mut import_setup, inherent_data_providers) = new_full_start!($config);
+		let (builder, mut import_setup, inherent_data_providers, shared_voter_state) =
+			new_full_start!($config);
 
 		// Dht event channel from the network to the authority discovery module. Use bounded channel to ensure
 		// back-pressure. Authority discovery is triggering one event per authority within the current authority set.
@@ -146,15 +167,32 @@ macro_rules! new_full {
 			.with_dht_event_tx(dht_event_tx)?
 			.build()?;
 
-		let (block_import, grandpa_link, babe_link) = import_setup.take()
+		let (
+			block_import,
+			grandpa_link,
+			babe_link,
+			equivocation_reports_tx,
+			equivocation_reports,
+		) = import_setup.take()
 				.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
 		($with_startup_data)(&block_import, &babe_link);
 
+		let client = service.client();
+		let transaction_pool = service.transaction_pool();
+		let submit_equivocation_reports = equivocation_reports.for_each(move |report| {
+			let best_id = BlockId::Hash(client.info().chain.best_hash);
+			transaction_pool.submit_one(&best_id, report).map(|result| if let Err(e) = result {
+				log::warn!("Failed to submit an equivocation report: {:?}", e);
+			})
+		});
+		service.spawn_task("equivocation-reports", submit_equivocation_reports.unit_error().compat());
+
 		if participates_in_consensus {
 			let proposer = substrate_basic_authorship::ProposerFactory {
 				client: service.client(),
 				transaction_pool: service.transaction_pool(),
+				limits: Default::default(),
 			};
 
 			let client = service.client();
@@ -171,10 +209,11 @@ macro_rules! new_full {
 				inherent_data_providers: inherent_data_providers.clone(),
 				force_authoring,
 				babe_link,
+				backoff_authoring_blocks: babe::BackoffAuthoringOnFinalizedHeadLagging::default(),
 			};
 
 			let babe = babe::start_babe(babe_config)?;
-			service.spawn_essential_task(babe);
+			service.spawn_essential_task("babe-proposer", babe);
 		}
 
 		// if the node isn't actively participating in consensus then it doesn't
@@ -186,9 +225,10 @@ macro_rules! new_full {
 		};
 
 		let config = grandpa::Config {
-			// FIXME #1578 make this available through chainspec
-			gossip_duration: std::time::Duration::from_millis(333),
-			justification_period: 512,
+			gossip_duration: std::time::Duration::from_millis(
+				grandpa_settings.as_ref().map_or(333, |settings| settings.gossip_duration)
+			),
+			justification_period: grandpa_settings.as_ref().map_or(512, |settings| settings.justification_period),
 			name: Some(name),
 			observer_enabled: true,
 			keystore,
@@ -198,7 +238,7 @@ macro_rules! new_full {
 		match (is_authority, disable_grandpa) {
 			(false, false) => {
 				// start the lightweight GRANDPA observer
-				service.spawn_task(grandpa::run_grandpa_observer(
+				service.spawn_task("grandpa-observer", grandpa::run_grandpa_observer(
 					config,
 					grandpa_link,
 					service.network(),
@@ -214,11 +254,16 @@ macro_rules! new_full {
 					inherent_data_providers: inherent_data_providers.clone(),
 					on_exit: service.on_exit(),
 					telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
-					voting_rule: grandpa::VotingRulesBuilder::default().build(),
+					voting_rule: match grandpa_settings {
+						Some(ref settings) if settings.instant_finality => grandpa::VotingRulesBuilder::new(),
+						_ => grandpa::VotingRulesBuilder::default(),
+					}.build(),
+					equivocation_reports: Some(equivocation_reports_tx),
+					shared_voter_state,
 				};
 				// the GRANDPA voter task is considered infallible, i.e.
 				// if it fails we take down the service with it.
-				service.spawn_essential_task(grandpa::run_grandpa_voter(grandpa_config)?);
+				service.spawn_essential_task("grandpa-voter", grandpa::run_grandpa_voter(grandpa_config)?);
 			},
 			(_, true) => {
 				grandpa::setup_disabled_grandpa(
@@ -318,6 +363,7 @@ pub fn new_light<C: Send + Default + 'static>(config: NodeConfiguration<C>)
 				client.clone(),
 				client,
 				inherent_data_providers.clone(),
+				None,
 			)?;
 
 			Ok((import_queue, finality_proof_request_builder))
@@ -473,6 +519,7 @@ mod tests {
 				let mut proposer_factory = substrate_basic_authorship::ProposerFactory {
 					client: service.client(),
 					transaction_pool: service.transaction_pool(),
+					limits: Default::default(),
 				};
 
 				let mut digest = Digest::<H256>::default();
@@ -34,6 +34,7 @@ use std::sync::Arc;
 use node_primitives::{Block, AccountId, Index, Balance};
 use node_runtime::UncheckedExtrinsic;
 use sr_primitives::traits::ProvideRuntimeApi;
+use substrate_finality_grandpa_rpc::{ReportVoterState, RpcFinalityProofProvider};
 use transaction_pool::txpool::{ChainApi, Pool};
 
 /// Instantiate all RPC extensions.
@@ -63,3 +64,31 @@ pub fn create<C, P, M>(client: Arc<C>, pool: Arc<Pool<P>>) -> jsonrpc_core::IoHa
 	);
 	io
 }
+
+/// Instantiate all RPC extensions of a full node, which also serves GRANDPA finality proofs
+/// and the state of the local GRANDPA voter.
+pub fn create_full<C, P, M, F, V>(
+	client: Arc<C>,
+	pool: Arc<Pool<P>>,
+	finality_proof_provider: Arc<F>,
+	grandpa_voter_state: V,
+) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi,
+	C: client::blockchain::HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	C::Api: srml_system_rpc::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: srml_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance>,
+	C::Api: srml_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	P: ChainApi + Sync + Send + 'static,
+	M: jsonrpc_core::Metadata + Default,
+	F: RpcFinalityProofProvider<Block> + Send + Sync + 'static,
+	V: ReportVoterState<Block> + Send + Sync + 'static,
+{
+	use substrate_finality_grandpa_rpc::{Grandpa, GrandpaApi};
+
+	let mut io = create(client, pool);
+	io.extend_with(
+		GrandpaApi::to_delegate(Grandpa::new(finality_proof_provider, grandpa_voter_state))
+	);
+	io
+}
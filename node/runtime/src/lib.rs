@@ -21,8 +21,9 @@
 #![recursion_limit="256"]
 
 use rstd::prelude::*;
+use codec::{Encode, Decode};
 use support::{
-	construct_runtime, parameter_types, traits::{SplitTwoWays, Currency, Randomness}
+	construct_runtime, parameter_types, traits::{SplitTwoWays, Currency, Randomness, InstanceFilter}
 };
 use primitives::u32_trait::{_1, _2, _3, _4};
 use node_primitives::{
@@ -33,13 +34,13 @@ use client::{
 	block_builder::api::{self as block_builder_api, InherentData, CheckInherentsResult},
 	runtime_api as client_api, impl_runtime_apis
 };
-use sr_primitives::{Permill, Perbill, ApplyResult, impl_opaque_keys, generic, create_runtime_str};
+use sr_primitives::{Permill, Perbill, Percent, ApplyResult, impl_opaque_keys, generic, create_runtime_str, KeyTypeId, RuntimeDebug};
 use sr_primitives::curve::PiecewiseLinear;
 use sr_primitives::transaction_validity::TransactionValidity;
 use sr_primitives::weights::Weight;
 use sr_primitives::traits::{
 	self, BlakeTwo256, Block as BlockT, NumberFor, StaticLookup, SaturatedConversion,
-	OpaqueKeys,
+	OpaqueKeys, ConvertInto,
 };
 use version::RuntimeVersion;
 #[cfg(any(feature = "std", test))]
@@ -81,8 +82,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 193,
-	impl_version: 193,
+	spec_version: 216,
+	impl_version: 216,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -135,6 +136,127 @@ impl utility::Trait for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	// One storage item; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const MultisigDepositBase: Balance = 30 * CENTS;
+	// Additional storage item size of 32 bytes.
+	pub const MultisigDepositFactor: Balance = 5 * CENTS;
+	pub const MaxSignatories: u16 = 100;
+}
+
+impl multisig::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type MultisigDepositBase = MultisigDepositBase;
+	type MultisigDepositFactor = MultisigDepositFactor;
+	type MaxSignatories = MaxSignatories;
+}
+
+parameter_types! {
+	// One storage item; key size 32, value size 8.
+	pub const ProxyDepositBase: Balance = 20 * CENTS;
+	// Additional storage item size of 33 bytes.
+	pub const ProxyDepositFactor: Balance = 3 * CENTS;
+	pub const MaxProxies: u16 = 32;
+	pub const AnnouncementDepositBase: Balance = 20 * CENTS;
+	// Additional storage item size of 68 bytes.
+	pub const AnnouncementDepositFactor: Balance = 7 * CENTS;
+	pub const MaxPending: u32 = 32;
+}
+
+/// The type used to represent the kinds of proxying allowed.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+	/// Allowed to make any call.
+	Any,
+	/// Allowed to make any call but the balance transfers.
+	NonTransfer,
+	/// Allowed to make the staking calls.
+	Staking,
+	/// Allowed to make the governance calls.
+	Governance,
+}
+
+impl Default for ProxyType {
+	fn default() -> Self { ProxyType::Any }
+}
+
+/// Whether the call moves funds of its origin, either itself or through one of the calls it
+/// dispatches.
+///
+/// The calls dispatching other calls must be looked into, otherwise a `NonTransfer` proxy could
+/// e.g. wrap a transfer in a batch.
+fn is_transfer(c: &Call) -> bool {
+	match c {
+		Call::Balances(..) |
+		Call::Vesting(vesting::Call::vested_transfer(..)) |
+		Call::Indices(indices::Call::transfer(..)) => true,
+		Call::Contracts(contracts::Call::call(_, value, _, _)) => *value != 0,
+		Call::Contracts(contracts::Call::instantiate(endowment, _, _, _)) => *endowment != 0,
+		Call::Utility(utility::Call::batch(calls)) |
+		Call::Utility(utility::Call::batch_all(calls)) => calls.iter().any(is_transfer),
+		Call::Proxy(proxy::Call::proxy(_, _, call)) |
+		Call::Proxy(proxy::Call::proxy_announced(_, _, _, call)) |
+		Call::Multisig(multisig::Call::as_multi(_, _, _, call)) => is_transfer(call),
+		// the sudo key can do anything, including transfers of any account.
+		Call::Sudo(..) => true,
+		_ => false,
+	}
+}
+
+impl InstanceFilter<Call> for ProxyType {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			ProxyType::Any => true,
+			ProxyType::NonTransfer => !is_transfer(c),
+			ProxyType::Staking => match c {
+				Call::Staking(..) | Call::Session(..) => true,
+				_ => false,
+			},
+			ProxyType::Governance => match c {
+				Call::Democracy(..) | Call::Council(..) | Call::TechnicalCommittee(..) |
+				Call::Elections(..) | Call::Treasury(..) => true,
+				_ => false,
+			},
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		match (self, o) {
+			(x, y) if x == y => true,
+			(ProxyType::Any, _) => true,
+			(_, ProxyType::Any) => false,
+			(ProxyType::NonTransfer, _) => true,
+			_ => false,
+		}
+	}
+}
+
+impl proxy::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+	type AnnouncementDepositBase = AnnouncementDepositBase;
+	type AnnouncementDepositFactor = AnnouncementDepositFactor;
+	type MaxPending = MaxPending;
+}
+
+parameter_types! {
+	pub const MinVestedTransfer: Balance = 100 * DOLLARS;
+}
+
+impl vesting::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = MinVestedTransfer;
+}
+
 parameter_types! {
 	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
 	pub const ExpectedBlockTime: Moment = MILLISECS_PER_BLOCK;
@@ -144,12 +266,21 @@ impl babe::Trait for Runtime {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = babe::ExternalTrigger;
+	type KeyOwnerProofSystem = session::historical::Module<Self>;
+	type KeyOwnerProof = session::historical::Proof;
+	type KeyOwnerIdentification = session::historical::IdentificationTuple<Self>;
+	type ReportEquivocation = Offences;
+}
+
+parameter_types! {
+	pub const IndexDeposit: Balance = 1 * DOLLARS;
 }
 
 impl indices::Trait for Runtime {
 	type AccountIndex = AccountIndex;
+	type Currency = Balances;
+	type Deposit = IndexDeposit;
 	type IsDeadAccount = Balances;
-	type ResolveHint = indices::SimpleResolveHint<Self::AccountId, Self::AccountIndex>;
 	type Event = Event;
 }
 
@@ -162,7 +293,7 @@ parameter_types! {
 impl balances::Trait for Runtime {
 	type Balance = Balance;
 	type OnFreeBalanceZero = ((Staking, Contracts), Session);
-	type OnNewAccount = Indices;
+	type OnNewAccount = ();
 	type Event = Event;
 	type DustRemoval = ();
 	type TransferPayment = ();
@@ -222,7 +353,9 @@ parameter_types! {
 }
 
 impl session::Trait for Runtime {
-	type OnSessionEnding = Staking;
+	// keep the roots of the past sessions, to check the key ownership proofs of the
+	// equivocation reports made after the end of the session.
+	type OnSessionEnding = session::historical::NoteHistoricalRoot<Self, Staking>;
 	type SessionHandler = <SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
 	type ShouldEndSession = Babe;
 	type Event = Event;
@@ -252,6 +385,7 @@ srml_staking_reward_curve::build! {
 parameter_types! {
 	pub const SessionsPerEra: sr_staking_primitives::SessionIndex = 6;
 	pub const BondingDuration: staking::EraIndex = 24 * 28;
+	pub const SlashDeferDuration: staking::EraIndex = 24 * 7; // 1/4 the bonding duration.
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
 }
 
@@ -265,10 +399,24 @@ impl staking::Trait for Runtime {
 	type Reward = (); // rewards are minted from the void
 	type SessionsPerEra = SessionsPerEra;
 	type BondingDuration = BondingDuration;
+	type SlashDeferDuration = SlashDeferDuration;
+	/// A super-majority of the council can cancel the slash.
+	type SlashCancelOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
 	type SessionInterface = Self;
 	type RewardCurve = RewardCurve;
 }
 
+parameter_types! {
+	// 80% of the maximum block weight.
+	pub const MaximumSchedulerWeight: Weight = 800_000_000;
+}
+
+impl scheduler::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+}
+
 parameter_types! {
 	pub const LaunchPeriod: BlockNumber = 28 * 24 * 60 * MINUTES;
 	pub const VotingPeriod: BlockNumber = 28 * 24 * 60 * MINUTES;
@@ -303,6 +451,7 @@ impl democracy::Trait for Runtime {
 	// only do it once and it lasts only for the cooloff period.
 	type VetoOrigin = collective::EnsureMember<AccountId, TechnicalCollective>;
 	type CooloffPeriod = CooloffPeriod;
+	type Scheduler = Scheduler;
 }
 
 type CouncilCollective = collective::Instance1;
@@ -348,6 +497,7 @@ impl membership::Trait<membership::Instance1> for Runtime {
 	type RemoveOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
 	type SwapOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
 	type ResetOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+	type PrimeOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
 	type MembershipInitialized = TechnicalCommittee;
 	type MembershipChanged = TechnicalCommittee;
 }
@@ -357,6 +507,16 @@ parameter_types! {
 	pub const ProposalBondMinimum: Balance = 1 * DOLLARS;
 	pub const SpendPeriod: BlockNumber = 1 * DAYS;
 	pub const Burn: Permill = Permill::from_percent(50);
+	pub const TipCountdown: BlockNumber = 1 * DAYS;
+	pub const TipFindersFee: Percent = Percent::from_percent(20);
+	pub const TipReportDepositBase: Balance = 1 * DOLLARS;
+	pub const TipReportDepositPerByte: Balance = 1 * CENTS;
+	pub const MaximumReasonLength: u32 = 16384;
+	pub const BountyDepositBase: Balance = 1 * DOLLARS;
+	pub const BountyDepositPerByte: Balance = 1 * CENTS;
+	pub const BountyValueMinimum: Balance = 5 * DOLLARS;
+	pub const BountyCuratorDeposit: Permill = Permill::from_percent(50);
+	pub const BountyDepositPayoutDelay: BlockNumber = 1 * DAYS;
 }
 
 impl treasury::Trait for Runtime {
@@ -369,6 +529,19 @@ impl treasury::Trait for Runtime {
 	type ProposalBondMinimum = ProposalBondMinimum;
 	type SpendPeriod = SpendPeriod;
 	type Burn = Burn;
+	type Call = Call;
+	type Scheduler = Scheduler;
+	type Tippers = Council;
+	type TipCountdown = TipCountdown;
+	type TipFindersFee = TipFindersFee;
+	type TipReportDepositBase = TipReportDepositBase;
+	type TipReportDepositPerByte = TipReportDepositPerByte;
+	type MaximumReasonLength = MaximumReasonLength;
+	type BountyDepositBase = BountyDepositBase;
+	type BountyDepositPerByte = BountyDepositPerByte;
+	type BountyValueMinimum = BountyValueMinimum;
+	type BountyCuratorDeposit = BountyCuratorDeposit;
+	type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
 }
 
 parameter_types! {
@@ -410,6 +583,7 @@ impl contracts::Trait for Runtime {
 	type MaxDepth = contracts::DefaultMaxDepth;
 	type MaxValueSize = contracts::DefaultMaxValueSize;
 	type BlockGasLimit = contracts::DefaultBlockGasLimit;
+	type ChainExtension = ();
 }
 
 impl sudo::Trait for Runtime {
@@ -440,6 +614,10 @@ impl offences::Trait for Runtime {
 
 impl grandpa::Trait for Runtime {
 	type Event = Event;
+	type KeyOwnerProofSystem = session::historical::Module<Self>;
+	type KeyOwnerProof = session::historical::Proof;
+	type KeyOwnerIdentification = session::historical::IdentificationTuple<Self>;
+	type ReportEquivocation = Offences;
 }
 
 parameter_types! {
@@ -469,6 +647,28 @@ impl nicks::Trait for Runtime {
 	type MaxLength = MaxLength;
 }
 
+parameter_types! {
+	// Roughly 1 CENT per byte of on-chain data.
+	pub const BasicDeposit: Balance = 10 * DOLLARS;       // 258 bytes on-chain
+	pub const FieldDeposit: Balance = 250 * CENTS;        // 66 bytes on-chain
+	pub const SubAccountDeposit: Balance = 2 * DOLLARS;   // 53 bytes on-chain
+	pub const MaximumSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+}
+
+impl identity::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaximumSubAccounts = MaximumSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type Slashed = Treasury;
+	type ForceOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+	type RegistrarOrigin = collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+}
+
 impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtime {
 	type Public = <Signature as traits::Verify>::Signer;
 	type Signature = Signature;
@@ -507,7 +707,8 @@ construct_runtime!(
 	{
 		System: system::{Module, Call, Storage, Config, Event},
 		Utility: utility::{Module, Call, Event},
-		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+		Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+		Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp), ValidateUnsigned},
 		Timestamp: timestamp::{Module, Call, Storage, Inherent},
 		Authorship: authorship::{Module, Call, Storage, Inherent},
 		Indices: indices,
@@ -521,7 +722,7 @@ construct_runtime!(
 		Elections: elections_phragmen::{Module, Call, Storage, Event<T>},
 		TechnicalMembership: membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 		FinalityTracker: finality_tracker::{Module, Call, Inherent},
-		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		Grandpa: grandpa::{Module, Call, Storage, Config, Event, ValidateUnsigned},
 		Treasury: treasury::{Module, Call, Storage, Config, Event<T>},
 		Contracts: contracts,
 		Sudo: sudo,
@@ -529,6 +730,10 @@ construct_runtime!(
 		Offences: offences::{Module, Call, Storage, Event},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
 		Nicks: nicks::{Module, Call, Storage, Event<T>},
+		Identity: identity::{Module, Call, Storage, Event<T>},
+		Multisig: multisig::{Module, Call, Storage, Event<T>},
+		Proxy: proxy::{Module, Call, Storage, Event<T>},
+		Vesting: vesting::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -608,6 +813,12 @@ impl_runtime_apis! {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
 			Executive::validate_transaction(tx)
 		}
+
+		fn transaction_sender(tx: <Block as BlockT>::Extrinsic) -> Option<Vec<u8>> {
+			tx.signature
+				.and_then(|(address, _, _)| Indices::lookup_address(address))
+				.map(|who| who.encode())
+		}
 	}
 
 	impl offchain_primitives::OffchainWorkerApi<Block> for Runtime {
@@ -620,6 +831,21 @@ impl_runtime_apis! {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn generate_key_ownership_proof(
+			set_id: fg_primitives::SetId,
+			authority_id: fg_primitives::AuthorityId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			Grandpa::generate_key_ownership_proof(set_id, authority_id)
+		}
+
+		fn report_equivocation_unsigned_extrinsic(
+			equivocation_proof: fg_primitives::EquivocationProof<<Block as BlockT>::Hash, NumberFor<Block>>,
+			key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<<Block as BlockT>::Extrinsic> {
+			Grandpa::equivocation_report_call(equivocation_proof, key_owner_proof)
+				.map(|call| UncheckedExtrinsic::new_unsigned(call.into()))
+		}
 	}
 
 	impl babe_primitives::BabeApi<Block> for Runtime {
@@ -631,13 +857,27 @@ impl_runtime_apis! {
 			// <https://research.web3.foundation/en/latest/polkadot/BABE/Babe/#6-practical-results>
 			babe_primitives::BabeConfiguration {
 				slot_duration: Babe::slot_duration(),
-				epoch_length: EpochDuration::get(),
+				epoch_length: Babe::epoch_duration(),
 				c: PRIMARY_PROBABILITY,
 				genesis_authorities: Babe::authorities(),
 				randomness: Babe::randomness(),
 				secondary_slots: true,
 			}
 		}
+
+		fn generate_key_ownership_proof(
+			authority_id: babe_primitives::AuthorityId,
+		) -> Option<babe_primitives::OpaqueKeyOwnershipProof> {
+			Babe::generate_key_ownership_proof(authority_id)
+		}
+
+		fn report_equivocation_unsigned_extrinsic(
+			equivocation_proof: babe_primitives::EquivocationProof<<Block as BlockT>::Header>,
+			key_owner_proof: babe_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<<Block as BlockT>::Extrinsic> {
+			Babe::equivocation_report_call(equivocation_proof, key_owner_proof)
+				.map(|call| UncheckedExtrinsic::new_unsigned(call.into()))
+		}
 	}
 
 	impl system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -702,6 +942,29 @@ impl_runtime_apis! {
 				.expect("Seed is an utf8 string"));
 			SessionKeys::generate(seed)
 		}
+
+		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+			SessionKeys::decode_into_raw_public_keys(&encoded)
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl srml_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			extrinsic: Vec<u8>,
+			steps: u32,
+			repeat: u32,
+		) -> Result<Vec<srml_benchmarking::BenchmarkResults>, sr_primitives::RuntimeString> {
+			use srml_benchmarking::Benchmarking;
+
+			let result = match module.as_slice() {
+				b"balances" => Balances::run_benchmark(extrinsic, steps, repeat),
+				_ => Err("Benchmark not found for this module."),
+			};
+
+			result.map_err(|e| e.into())
+		}
 	}
 }
 #[cfg(test)]
@@ -724,4 +987,27 @@ mod tests {
 		let x = SubmitTransaction::default();
 		is_submit_signed_transaction(x);
 	}
+
+	#[test]
+	fn non_transfer_proxy_filters_wrapped_transfers() {
+		let transfer = Call::Balances(balances::Call::transfer(Default::default(), 1));
+		let remark = Call::System(system::Call::remark(vec![]));
+
+		assert!(!ProxyType::NonTransfer.filter(&transfer));
+		assert!(ProxyType::NonTransfer.filter(&remark));
+
+		let batch = Call::Utility(utility::Call::batch(vec![remark.clone(), transfer.clone()]));
+		assert!(!ProxyType::NonTransfer.filter(&batch));
+		let batch_all = Call::Utility(utility::Call::batch_all(vec![transfer.clone()]));
+		assert!(!ProxyType::NonTransfer.filter(&batch_all));
+		let nested = Call::Utility(utility::Call::batch(vec![
+			Call::Proxy(proxy::Call::proxy(Default::default(), None, Box::new(batch))),
+		]));
+		assert!(!ProxyType::NonTransfer.filter(&nested));
+		let multisig = Call::Multisig(multisig::Call::as_multi(2, vec![], None, Box::new(transfer)));
+		assert!(!ProxyType::NonTransfer.filter(&multisig));
+
+		let remarks = Call::Utility(utility::Call::batch(vec![remark.clone(), remark]));
+		assert!(ProxyType::NonTransfer.filter(&remarks));
+	}
 }
@@ -20,12 +20,14 @@
 //!
 //! ## Overview
 //!
-//! The Assets module provides functionality for asset management of fungible asset classes
-//! with a fixed supply, including:
+//! The Assets module provides functionality for asset management of fungible asset classes,
+//! including:
 //!
 //! * Asset Issuance
-//! * Asset Transfer
+//! * Asset Minting and Burning
+//! * Asset Transfer, direct or approved
 //! * Asset Destruction
+//! * Asset Metadata
 //!
 //! To use it in your runtime, you need to implement the assets [`Trait`](./trait.Trait.html).
 //!
@@ -33,8 +35,14 @@
 //!
 //! ### Terminology
 //!
-//! * **Asset issuance:** The creation of a new asset, whose total supply will belong to the
-//!   account that issues the asset.
+//! * **Asset issuance:** The creation of a new asset, whose initial supply will belong to the
+//!   account that issues the asset. Only the origins allowed by `CreateOrigin` may issue assets.
+//! * **Owner:** The account which issued an asset. It can set the issuer and the metadata of the
+//!   asset, and has a deposit reserved for it.
+//! * **Issuer:** The account which may mint and burn units of an asset.
+//! * **Approval:** An allowance for a delegate to transfer some units of an asset out of the
+//!   account of their holder.
+//! * **Metadata:** The name, ticker symbol and number of decimals of an asset.
 //! * **Asset transfer:** The action of transferring assets from one account to another.
 //! * **Asset destruction:** The process of an account removing its entire holding of an asset.
 //! * **Fungible asset:** An asset whose units are interchangeable.
@@ -58,6 +66,14 @@
 //! the function caller's account (`origin`) to a `target` account.
 //! * `destroy` - Destroys the entire holding of a fungible asset `id` associated with the account
 //! that called the function.
+//! * `mint` - Mints units of an asset into an account; only callable by the issuer.
+//! * `burn` - Burns units of an asset from an account; only callable by the issuer.
+//! * `set_issuer` - Changes the issuer of an asset; only callable by the owner.
+//! * `approve_transfer` - Allows a delegate to transfer some units out of the caller's account.
+//! * `cancel_approval` - Cancels the approval of a delegate.
+//! * `transfer_approved` - Transfers units out of an account that approved the caller.
+//! * `set_metadata` - Sets the metadata of an asset; only callable by the owner.
+//! * `clear_metadata` - Clears the metadata of an asset; only callable by the owner.
 //!
 //! Please refer to the [`Call`](./enum.Call.html) enum and its associated variants for documentation on each function.
 //!
@@ -69,6 +85,9 @@
 //!
 //! Please refer to the [`Module`](./struct.Module.html) struct for details on publicly available functions.
 //!
+//! The module also implements the `MultiAsset` trait, through which other modules may use the
+//! assets as currencies.
+//!
 //! ## Usage
 //!
 //! The following example shows how to use the Assets module in your runtime by exposing public functions to:
@@ -130,39 +149,123 @@
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use rstd::prelude::*;
+use codec::{Encode, Decode};
 use support::{Parameter, decl_module, decl_event, decl_storage, ensure};
-use sr_primitives::traits::{Member, SimpleArithmetic, Zero, StaticLookup};
+use support::traits::{Currency, ReservableCurrency, Get, MultiAsset};
+use sr_primitives::RuntimeDebug;
+use sr_primitives::traits::{
+	Member, SimpleArithmetic, Zero, StaticLookup, EnsureOrigin, MaybeSerializeDeserialize,
+	CheckedAdd, CheckedSub, Saturating,
+};
 use system::ensure_signed;
 use sr_primitives::traits::One;
 
+type DepositBalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
 /// The module configuration trait.
 pub trait Trait: system::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
 	/// The units in which we record balances.
-	type Balance: Member + Parameter + SimpleArithmetic + Default + Copy;
+	type Balance: Member + Parameter + SimpleArithmetic + Default + Copy + MaybeSerializeDeserialize;
 
 	/// The arithmetic type of asset identifier.
-	type AssetId: Parameter + SimpleArithmetic + Default + Copy;
+	type AssetId: Parameter + SimpleArithmetic + Default + Copy + MaybeSerializeDeserialize;
+
+	/// The currency in which the deposits are reserved.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The origin which may issue new assets, becoming their owner and issuer.
+	type CreateOrigin: EnsureOrigin<Self::Origin, Success=Self::AccountId>;
+
+	/// The amount reserved from the owner of an asset when it's issued.
+	type AssetDeposit: Get<DepositBalanceOf<Self>>;
+
+	/// The amount reserved from the owner of an asset when its metadata is set.
+	type MetadataDepositBase: Get<DepositBalanceOf<Self>>;
+
+	/// The additional amount reserved for each byte of the name and symbol of an asset.
+	type MetadataDepositPerByte: Get<DepositBalanceOf<Self>>;
+
+	/// The amount reserved from a holder when approving a transfer of their holding.
+	type ApprovalDeposit: Get<DepositBalanceOf<Self>>;
+
+	/// The maximum length of the name and of the symbol of an asset.
+	type StringLimit: Get<u32>;
+}
+
+/// The accounts in charge of an asset.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct AssetDetails<AccountId, DepositBalance> {
+	/// Can change the issuer and the metadata of the asset.
+	pub owner: AccountId,
+	/// Can mint and burn units of the asset.
+	pub issuer: AccountId,
+	/// The amount reserved from the owner for the asset.
+	pub deposit: DepositBalance,
+}
+
+/// An approval for a delegate to transfer some of a holder's asset.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct Approval<Balance, DepositBalance> {
+	/// The amount of the asset that the delegate may still transfer.
+	pub amount: Balance,
+	/// The amount reserved from the holder for the approval.
+	pub deposit: DepositBalance,
+}
+
+/// The human-readable description of an asset.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct AssetMetadata<DepositBalance> {
+	/// The amount reserved from the owner for the metadata.
+	pub deposit: DepositBalance,
+	/// The name of the asset.
+	pub name: Vec<u8>,
+	/// The ticker symbol of the asset.
+	pub symbol: Vec<u8>,
+	/// The number of decimals used to display the balances of the asset.
+	pub decimals: u8,
 }
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The amount reserved from the owner of an asset when it's issued.
+		const AssetDeposit: DepositBalanceOf<T> = T::AssetDeposit::get();
+
+		/// The amount reserved from the owner of an asset when its metadata is set.
+		const MetadataDepositBase: DepositBalanceOf<T> = T::MetadataDepositBase::get();
+
+		/// The additional amount reserved for each byte of the name and symbol of an asset.
+		const MetadataDepositPerByte: DepositBalanceOf<T> = T::MetadataDepositPerByte::get();
+
+		/// The amount reserved from a holder when approving a transfer of their holding.
+		const ApprovalDeposit: DepositBalanceOf<T> = T::ApprovalDeposit::get();
+
+		/// The maximum length of the name and of the symbol of an asset.
+		const StringLimit: u32 = T::StringLimit::get();
+
 		fn deposit_event() = default;
-		/// Issue a new class of fungible assets. There are, and will only ever be, `total`
-		/// such assets and they'll all belong to the `origin` initially. It will have an
+		/// Issue a new class of fungible assets, with `total` units belonging to the `origin`
+		/// initially. The `origin` must be allowed by `CreateOrigin` and becomes the owner and the
+		/// issuer of the asset; `AssetDeposit` is reserved from it. The asset will have an
 		/// identifier `AssetId` instance: this will be specified in the `Issued` event.
 		fn issue(origin, #[compact] total: T::Balance) {
-			let origin = ensure_signed(origin)?;
+			let owner = T::CreateOrigin::ensure_origin(origin)?;
+
+			let deposit = T::AssetDeposit::get();
+			T::Currency::reserve(&owner, deposit)
+				.map_err(|_| "not enough funds for the asset deposit")?;
 
 			let id = Self::next_asset_id();
 			<NextAssetId<T>>::mutate(|id| *id += One::one());
 
-			<Balances<T>>::insert((id, &origin), total);
+			<Details<T>>::insert(id, AssetDetails { owner: owner.clone(), issuer: owner.clone(), deposit });
+			<Balances<T>>::insert((id, &owner), total);
 			<TotalSupply<T>>::insert(id, total);
 
-			Self::deposit_event(RawEvent::Issued(id, origin, total));
+			Self::deposit_event(RawEvent::Issued(id, owner, total));
 		}
 
 		/// Move some assets from one holder to another.
@@ -172,15 +275,8 @@ decl_module! {
 			#[compact] amount: T::Balance
 		) {
 			let origin = ensure_signed(origin)?;
-			let origin_account = (id, origin.clone());
-			let origin_balance = <Balances<T>>::get(&origin_account);
 			let target = T::Lookup::lookup(target)?;
-			ensure!(!amount.is_zero(), "transfer amount should be non-zero");
-			ensure!(origin_balance >= amount, "origin account balance must be greater than or equal to the transfer amount");
-
-			Self::deposit_event(RawEvent::Transferred(id, origin, target.clone(), amount));
-			<Balances<T>>::insert(origin_account, origin_balance - amount);
-			<Balances<T>>::mutate((id, target), |balance| *balance += amount);
+			Self::do_transfer(id, &origin, &target, amount)?;
 		}
 
 		/// Destroy any assets of `id` owned by `origin`.
@@ -192,6 +288,183 @@ decl_module! {
 			<TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= balance);
 			Self::deposit_event(RawEvent::Destroyed(id, origin, balance));
 		}
+
+		/// Mint `amount` units of the asset `id` into the account of `beneficiary`.
+		///
+		/// The dispatch origin for this call must be the issuer of the asset.
+		fn mint(origin,
+			#[compact] id: T::AssetId,
+			beneficiary: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let beneficiary = T::Lookup::lookup(beneficiary)?;
+			let details = Self::details(id).ok_or("unknown asset")?;
+			ensure!(details.issuer == origin, "origin must be the issuer of the asset");
+
+			Self::do_mint(id, &beneficiary, amount)?;
+			Self::deposit_event(RawEvent::Minted(id, beneficiary, amount));
+		}
+
+		/// Burn up to `amount` units of the asset `id` from the account of `who`.
+		///
+		/// The dispatch origin for this call must be the issuer of the asset.
+		fn burn(origin,
+			#[compact] id: T::AssetId,
+			who: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let origin = ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+			let details = Self::details(id).ok_or("unknown asset")?;
+			ensure!(details.issuer == origin, "origin must be the issuer of the asset");
+
+			let burned = amount.min(<Balances<T>>::get((id, &who)));
+			ensure!(!burned.is_zero(), "nothing to burn");
+			<Balances<T>>::mutate((id, &who), |balance| *balance -= burned);
+			<TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= burned);
+			Self::deposit_event(RawEvent::Burned(id, who, burned));
+		}
+
+		/// Make `issuer` the account which may mint and burn units of the asset `id`.
+		///
+		/// The dispatch origin for this call must be the owner of the asset.
+		fn set_issuer(origin,
+			#[compact] id: T::AssetId,
+			issuer: <T::Lookup as StaticLookup>::Source
+		) {
+			let origin = ensure_signed(origin)?;
+			let issuer = T::Lookup::lookup(issuer)?;
+			let mut details = Self::details(id).ok_or("unknown asset")?;
+			ensure!(details.owner == origin, "origin must be the owner of the asset");
+
+			details.issuer = issuer.clone();
+			<Details<T>>::insert(id, details);
+			Self::deposit_event(RawEvent::IssuerChanged(id, issuer));
+		}
+
+		/// Allow `delegate` to transfer up to `amount` more units of the asset `id` out of the
+		/// account of the origin.
+		///
+		/// `ApprovalDeposit` is reserved from the origin, unless it already approved a transfer to
+		/// `delegate`.
+		fn approve_transfer(origin,
+			#[compact] id: T::AssetId,
+			delegate: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let owner = ensure_signed(origin)?;
+			let delegate = T::Lookup::lookup(delegate)?;
+			ensure!(<Details<T>>::exists(id), "unknown asset");
+
+			let key = (id, owner.clone(), delegate.clone());
+			let approval = match <Approvals<T>>::get(&key) {
+				Some(mut approval) => {
+					approval.amount = approval.amount.saturating_add(amount);
+					approval
+				}
+				None => {
+					let deposit = T::ApprovalDeposit::get();
+					T::Currency::reserve(&owner, deposit)
+						.map_err(|_| "not enough funds for the approval deposit")?;
+					Approval { amount, deposit }
+				}
+			};
+			<Approvals<T>>::insert(&key, approval);
+			Self::deposit_event(RawEvent::ApprovedTransfer(id, owner, delegate, amount));
+		}
+
+		/// Cancel the approval for `delegate` to transfer units of the asset `id` out of the
+		/// account of the origin, returning the approval deposit.
+		fn cancel_approval(origin,
+			#[compact] id: T::AssetId,
+			delegate: <T::Lookup as StaticLookup>::Source
+		) {
+			let owner = ensure_signed(origin)?;
+			let delegate = T::Lookup::lookup(delegate)?;
+			let approval = <Approvals<T>>::take((id, owner.clone(), delegate.clone()))
+				.ok_or("no approval to cancel")?;
+
+			T::Currency::unreserve(&owner, approval.deposit);
+			Self::deposit_event(RawEvent::ApprovalCancelled(id, owner, delegate));
+		}
+
+		/// Transfer `amount` units of the asset `id` from the account of `owner` to `destination`,
+		/// using an approval of `owner` for the origin.
+		///
+		/// Once the approved amount is used up, the approval is removed and its deposit returned.
+		fn transfer_approved(origin,
+			#[compact] id: T::AssetId,
+			owner: <T::Lookup as StaticLookup>::Source,
+			destination: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance
+		) {
+			let delegate = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+			let destination = T::Lookup::lookup(destination)?;
+
+			let key = (id, owner.clone(), delegate.clone());
+			let mut approval = <Approvals<T>>::get(&key).ok_or("transfer not approved")?;
+			approval.amount = approval.amount.checked_sub(&amount)
+				.ok_or("transfer amount exceeds the approved amount")?;
+
+			Self::do_transfer(id, &owner, &destination, amount)?;
+
+			if approval.amount.is_zero() {
+				T::Currency::unreserve(&owner, approval.deposit);
+				<Approvals<T>>::remove(&key);
+			} else {
+				<Approvals<T>>::insert(&key, approval);
+			}
+			Self::deposit_event(RawEvent::TransferredApproved(id, owner, delegate, destination, amount));
+		}
+
+		/// Set the name, ticker symbol and number of decimals of the asset `id`.
+		///
+		/// The dispatch origin for this call must be the owner of the asset. A deposit of
+		/// `MetadataDepositBase` plus `MetadataDepositPerByte` for each byte of `name` and `symbol`
+		/// is reserved from it, replacing the one of any previous metadata.
+		fn set_metadata(origin,
+			#[compact] id: T::AssetId,
+			name: Vec<u8>,
+			symbol: Vec<u8>,
+			decimals: u8
+		) {
+			let origin = ensure_signed(origin)?;
+			let limit = T::StringLimit::get() as usize;
+			ensure!(name.len() <= limit, "name too long");
+			ensure!(symbol.len() <= limit, "symbol too long");
+
+			let details = Self::details(id).ok_or("unknown asset")?;
+			ensure!(details.owner == origin, "origin must be the owner of the asset");
+
+			let old_deposit = <Metadata<T>>::get(id).map_or_else(Zero::zero, |m| m.deposit);
+			let bytes = (name.len() + symbol.len()) as u32;
+			let deposit = T::MetadataDepositBase::get()
+				+ T::MetadataDepositPerByte::get() * bytes.into();
+			if deposit > old_deposit {
+				T::Currency::reserve(&origin, deposit - old_deposit)
+					.map_err(|_| "not enough funds for the metadata deposit")?;
+			} else {
+				T::Currency::unreserve(&origin, old_deposit - deposit);
+			}
+
+			<Metadata<T>>::insert(id, AssetMetadata { deposit, name: name.clone(), symbol: symbol.clone(), decimals });
+			Self::deposit_event(RawEvent::MetadataSet(id, name, symbol, decimals));
+		}
+
+		/// Clear the metadata of the asset `id`, returning its deposit.
+		///
+		/// The dispatch origin for this call must be the owner of the asset.
+		fn clear_metadata(origin, #[compact] id: T::AssetId) {
+			let origin = ensure_signed(origin)?;
+			let details = Self::details(id).ok_or("unknown asset")?;
+			ensure!(details.owner == origin, "origin must be the owner of the asset");
+
+			let metadata = <Metadata<T>>::take(id).ok_or("no metadata to clear")?;
+			T::Currency::unreserve(&origin, metadata.deposit);
+			Self::deposit_event(RawEvent::MetadataCleared(id));
+		}
 	}
 }
 
@@ -207,6 +480,22 @@ decl_event!(
 		Transferred(AssetId, AccountId, AccountId, Balance),
 		/// Some assets were destroyed.
 		Destroyed(AssetId, AccountId, Balance),
+		/// Some assets were minted into an account by their issuer.
+		Minted(AssetId, AccountId, Balance),
+		/// Some assets were burned from an account by their issuer.
+		Burned(AssetId, AccountId, Balance),
+		/// The issuer of an asset was changed.
+		IssuerChanged(AssetId, AccountId),
+		/// A holder (first) approved a delegate (second) to transfer some more of their assets.
+		ApprovedTransfer(AssetId, AccountId, AccountId, Balance),
+		/// A holder (first) cancelled the approval of a delegate (second).
+		ApprovalCancelled(AssetId, AccountId, AccountId),
+		/// A delegate (second) transferred some assets of a holder (first) to a destination (third).
+		TransferredApproved(AssetId, AccountId, AccountId, AccountId, Balance),
+		/// The metadata of an asset was set: name, symbol and decimals.
+		MetadataSet(AssetId, Vec<u8>, Vec<u8>, u8),
+		/// The metadata of an asset was cleared.
+		MetadataCleared(AssetId),
 	}
 );
 
@@ -218,6 +507,13 @@ decl_storage! {
 		NextAssetId get(fn next_asset_id): T::AssetId;
 		/// The total unit supply of an asset.
 		TotalSupply: map T::AssetId => T::Balance;
+		/// The accounts in charge of an asset.
+		pub Details get(fn details): map T::AssetId => Option<AssetDetails<T::AccountId, DepositBalanceOf<T>>>;
+		/// The approvals of holders (second) for delegates (third) to transfer their assets.
+		pub Approvals get(fn approvals):
+			map (T::AssetId, T::AccountId, T::AccountId) => Option<Approval<T::Balance, DepositBalanceOf<T>>>;
+		/// The metadata of an asset.
+		pub Metadata get(fn metadata): map T::AssetId => Option<AssetMetadata<DepositBalanceOf<T>>>;
 	}
 }
 
@@ -234,6 +530,73 @@ impl<T: Trait> Module<T> {
 	pub fn total_supply(id: T::AssetId) -> T::Balance {
 		<TotalSupply<T>>::get(id)
 	}
+
+	// Private mutables
+
+	/// Move `amount` units of the asset `id` from `source` to `dest`.
+	fn do_transfer(
+		id: T::AssetId,
+		source: &T::AccountId,
+		dest: &T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), &'static str> {
+		let source_balance = <Balances<T>>::get((id, source));
+		ensure!(!amount.is_zero(), "transfer amount should be non-zero");
+		ensure!(source_balance >= amount, "origin account balance must be greater than or equal to the transfer amount");
+
+		Self::deposit_event(RawEvent::Transferred(id, source.clone(), dest.clone(), amount));
+		<Balances<T>>::insert((id, source), source_balance - amount);
+		<Balances<T>>::mutate((id, dest), |balance| *balance += amount);
+		Ok(())
+	}
+
+	/// Create `amount` units of the asset `id` into the account of `who`.
+	fn do_mint(id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> Result<(), &'static str> {
+		let total_supply = <TotalSupply<T>>::get(id).checked_add(&amount).ok_or("total supply overflow")?;
+		// the balance can't overflow as it's lower than the total supply.
+		<Balances<T>>::mutate((id, who), |balance| *balance += amount);
+		<TotalSupply<T>>::insert(id, total_supply);
+		Ok(())
+	}
+}
+
+impl<T: Trait> MultiAsset<T::AccountId> for Module<T> {
+	type AssetId = T::AssetId;
+	type Balance = T::Balance;
+
+	fn exists(id: T::AssetId) -> bool {
+		<Details<T>>::exists(id)
+	}
+
+	fn total_supply(id: T::AssetId) -> T::Balance {
+		<TotalSupply<T>>::get(id)
+	}
+
+	fn balance(id: T::AssetId, who: &T::AccountId) -> T::Balance {
+		<Balances<T>>::get((id, who))
+	}
+
+	fn transfer(
+		id: T::AssetId,
+		source: &T::AccountId,
+		dest: &T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), &'static str> {
+		Self::do_transfer(id, source, dest, amount)
+	}
+
+	fn deposit(id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> Result<(), &'static str> {
+		ensure!(<Details<T>>::exists(id), "unknown asset");
+		Self::do_mint(id, who, amount)
+	}
+
+	fn withdraw(id: T::AssetId, who: &T::AccountId, amount: T::Balance) -> Result<(), &'static str> {
+		let balance = <Balances<T>>::get((id, who));
+		ensure!(balance >= amount, "balance too low");
+		<Balances<T>>::insert((id, who), balance - amount);
+		<TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= amount);
+		Ok(())
+	}
 }
 
 #[cfg(test)]
@@ -278,17 +641,52 @@ mod tests {
 		type MaximumBlockLength = MaximumBlockLength;
 		type Version = ();
 	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const AssetDeposit: u64 = 1;
+		pub const MetadataDepositBase: u64 = 1;
+		pub const MetadataDepositPerByte: u64 = 1;
+		pub const ApprovalDeposit: u64 = 1;
+		pub const StringLimit: u32 = 8;
+	}
 	impl Trait for Test {
 		type Event = ();
 		type Balance = u64;
 		type AssetId = u32;
+		type Currency = balances::Module<Test>;
+		type CreateOrigin = system::EnsureSigned<u64>;
+		type AssetDeposit = AssetDeposit;
+		type MetadataDepositBase = MetadataDepositBase;
+		type MetadataDepositPerByte = MetadataDepositPerByte;
+		type ApprovalDeposit = ApprovalDeposit;
+		type StringLimit = StringLimit;
 	}
+	type Balances = balances::Module<Test>;
 	type Assets = Module<Test>;
 
 	// This function basically just builds a genesis storage key/value store according to
 	// our desired mockup.
 	fn new_test_ext() -> runtime_io::TestExternalities {
-		system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
 	}
 
 	#[test]
@@ -296,6 +694,130 @@ mod tests {
 		new_test_ext().execute_with(|| {
 			assert_ok!(Assets::issue(Origin::signed(1), 100));
 			assert_eq!(Assets::balance(0, 1), 100);
+			assert_eq!(Balances::reserved_balance(&1), 1);
+			assert_eq!(Assets::details(0), Some(AssetDetails { owner: 1, issuer: 1, deposit: 1 }));
+		});
+	}
+
+	#[test]
+	fn issuing_requires_the_asset_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(Assets::issue(Origin::signed(4), 100), "not enough funds for the asset deposit");
+		});
+	}
+
+	#[test]
+	fn minting_and_burning_should_work_for_the_issuer_only() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_noop!(Assets::mint(Origin::signed(2), 0, 2, 50), "origin must be the issuer of the asset");
+			assert_noop!(Assets::mint(Origin::signed(1), 1, 2, 50), "unknown asset");
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 2, 50));
+			assert_eq!(Assets::balance(0, 2), 50);
+			assert_eq!(Assets::total_supply(0), 150);
+
+			assert_noop!(Assets::burn(Origin::signed(2), 0, 2, 20), "origin must be the issuer of the asset");
+			assert_ok!(Assets::burn(Origin::signed(1), 0, 2, 20));
+			assert_eq!(Assets::balance(0, 2), 30);
+			// burning more than the balance burns the whole balance.
+			assert_ok!(Assets::burn(Origin::signed(1), 0, 2, 100));
+			assert_eq!(Assets::balance(0, 2), 0);
+			assert_eq!(Assets::total_supply(0), 100);
+			assert_noop!(Assets::burn(Origin::signed(1), 0, 2, 100), "nothing to burn");
+		});
+	}
+
+	#[test]
+	fn changing_the_issuer_should_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_noop!(Assets::set_issuer(Origin::signed(2), 0, 2), "origin must be the owner of the asset");
+			assert_ok!(Assets::set_issuer(Origin::signed(1), 0, 2));
+			assert_noop!(Assets::mint(Origin::signed(1), 0, 1, 50), "origin must be the issuer of the asset");
+			assert_ok!(Assets::mint(Origin::signed(2), 0, 1, 50));
+			assert_eq!(Assets::balance(0, 1), 150);
+		});
+	}
+
+	#[test]
+	fn approved_transfers_should_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_ok!(Assets::approve_transfer(Origin::signed(1), 0, 2, 30));
+			assert_ok!(Assets::approve_transfer(Origin::signed(1), 0, 2, 20));
+			// the deposit is only reserved once per delegate.
+			assert_eq!(Balances::reserved_balance(&1), 2);
+			assert_eq!(Assets::approvals((0, 1, 2)), Some(Approval { amount: 50, deposit: 1 }));
+
+			assert_noop!(Assets::transfer_approved(Origin::signed(3), 0, 1, 3, 10), "transfer not approved");
+			assert_noop!(
+				Assets::transfer_approved(Origin::signed(2), 0, 1, 3, 60),
+				"transfer amount exceeds the approved amount"
+			);
+			assert_ok!(Assets::transfer_approved(Origin::signed(2), 0, 1, 3, 40));
+			assert_eq!(Assets::balance(0, 1), 60);
+			assert_eq!(Assets::balance(0, 3), 40);
+			assert_ok!(Assets::transfer_approved(Origin::signed(2), 0, 1, 3, 10));
+			// the approval is used up and its deposit returned.
+			assert_eq!(Assets::approvals((0, 1, 2)), None);
+			assert_eq!(Balances::reserved_balance(&1), 1);
+		});
+	}
+
+	#[test]
+	fn cancelling_approvals_should_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_noop!(Assets::approve_transfer(Origin::signed(1), 1, 2, 30), "unknown asset");
+			assert_ok!(Assets::approve_transfer(Origin::signed(1), 0, 2, 30));
+			assert_noop!(Assets::cancel_approval(Origin::signed(1), 0, 3), "no approval to cancel");
+			assert_ok!(Assets::cancel_approval(Origin::signed(1), 0, 2));
+			assert_eq!(Balances::reserved_balance(&1), 1);
+			assert_noop!(Assets::transfer_approved(Origin::signed(2), 0, 1, 3, 10), "transfer not approved");
+		});
+	}
+
+	#[test]
+	fn metadata_should_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_noop!(
+				Assets::set_metadata(Origin::signed(2), 0, b"Token".to_vec(), b"TKN".to_vec(), 12),
+				"origin must be the owner of the asset"
+			);
+			assert_noop!(
+				Assets::set_metadata(Origin::signed(1), 0, b"A long token".to_vec(), b"TKN".to_vec(), 12),
+				"name too long"
+			);
+			assert_ok!(Assets::set_metadata(Origin::signed(1), 0, b"Token".to_vec(), b"TKN".to_vec(), 12));
+			assert_eq!(Balances::reserved_balance(&1), 1 + 1 + 8);
+			// a shorter metadata returns the difference of deposit.
+			assert_ok!(Assets::set_metadata(Origin::signed(1), 0, b"T".to_vec(), b"T".to_vec(), 12));
+			assert_eq!(Balances::reserved_balance(&1), 1 + 1 + 2);
+			assert_eq!(Assets::metadata(0), Some(AssetMetadata {
+				deposit: 3,
+				name: b"T".to_vec(),
+				symbol: b"T".to_vec(),
+				decimals: 12,
+			}));
+			assert_ok!(Assets::clear_metadata(Origin::signed(1), 0));
+			assert_eq!(Balances::reserved_balance(&1), 1);
+			assert_noop!(Assets::clear_metadata(Origin::signed(1), 0), "no metadata to clear");
+		});
+	}
+
+	#[test]
+	fn multi_asset_interface_should_work() {
+		new_test_ext().execute_with(|| {
+			assert!(!<Assets as MultiAsset<u64>>::exists(0));
+			assert_noop!(<Assets as MultiAsset<u64>>::deposit(0, &2, 10), "unknown asset");
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_ok!(<Assets as MultiAsset<u64>>::deposit(0, &2, 10));
+			assert_ok!(<Assets as MultiAsset<u64>>::transfer(0, &2, &3, 5));
+			assert_noop!(<Assets as MultiAsset<u64>>::withdraw(0, &3, 6), "balance too low");
+			assert_ok!(<Assets as MultiAsset<u64>>::withdraw(0, &3, 5));
+			assert_eq!(<Assets as MultiAsset<u64>>::balance(0, &2), 5);
+			assert_eq!(<Assets as MultiAsset<u64>>::total_supply(0), 105);
 		});
 	}
 
@@ -23,14 +23,21 @@
 pub use timestamp;
 
 use rstd::{result, prelude::*};
-use support::{decl_storage, decl_module, traits::FindAuthor, traits::Get};
+use support::{
+	decl_storage, decl_module, Parameter,
+	traits::{FindAuthor, Get, KeyOwnerProofSystem, GetSessionNumber, GetValidatorCount},
+};
 use timestamp::OnTimestampSet;
-use sr_primitives::{generic::DigestItem, ConsensusEngineId, Perbill};
+use sr_primitives::{generic::DigestItem, ConsensusEngineId, KeyTypeId, Perbill, RuntimeAppPublic};
 use sr_primitives::traits::{IsMember, SaturatedConversion, Saturating, RandomnessBeacon};
+use sr_primitives::transaction_validity::{
+	TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority, TransactionLongevity,
+};
 use sr_staking_primitives::{
 	SessionIndex,
-	offence::{Offence, Kind},
+	offence::{Offence, Kind, ReportOffence},
 };
+use system::ensure_none;
 #[cfg(feature = "std")]
 use timestamp::TimestampInherentData;
 use codec::{Encode, Decode};
@@ -39,9 +46,9 @@ use inherents::{RuntimeString, InherentIdentifier, InherentData, ProvideInherent
 use inherents::{InherentDataProviders, ProvideInherentData};
 use babe_primitives::{
 	BABE_ENGINE_ID, ConsensusLog, BabeAuthorityWeight, NextEpochDescriptor, RawBabePreDigest,
-	SlotNumber,
+	SlotNumber, OpaqueKeyOwnershipProof,
 };
-pub use babe_primitives::{AuthorityId, VRF_OUTPUT_LENGTH, PUBLIC_KEY_LENGTH};
+pub use babe_primitives::{AuthorityId, EquivocationProof, VRF_OUTPUT_LENGTH, PUBLIC_KEY_LENGTH};
 
 #[cfg(all(feature = "std", test))]
 mod tests;
@@ -122,7 +129,8 @@ impl ProvideInherentData for InherentDataProvider {
 }
 
 pub trait Trait: timestamp::Trait {
-	/// The amount of time, in slots, that each epoch should last.
+	/// The amount of time, in slots, that each epoch should last, unless the genesis config
+	/// says otherwise.
 	type EpochDuration: Get<SlotNumber>;
 
 	/// The expected average block time at which BABE should be creating
@@ -138,6 +146,28 @@ pub trait Trait: timestamp::Trait {
 	/// Typically, the `ExternalTrigger` type should be used. An internal trigger should only be used
 	/// when no other module is responsible for changing authority set.
 	type EpochChangeTrigger: EpochChangeTrigger;
+
+	/// The system proving that a key was owned by a validator of a given session,
+	/// which is needed to check the equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The proof of key ownership of `KeyOwnerProofSystem`.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The full identification of a key owner, as given by `KeyOwnerProofSystem`.
+	type KeyOwnerIdentification: Parameter;
+
+	/// Where the offences of the equivocating authorities are reported, e.g.
+	/// `srml-offences`.
+	type ReportEquivocation: ReportOffence<
+		Self::AccountId,
+		Self::KeyOwnerIdentification,
+		BabeEquivocationOffence<Self::KeyOwnerIdentification>,
+	>;
 }
 
 /// Trigger an epoch change, if any should take place.
@@ -225,10 +255,21 @@ decl_storage! {
 		/// Temporary value (cleared at block finalization) which is `Some`
 		/// if per-block initialization has already been called for current block.
 		Initialized get(fn initialized): Option<MaybeVrf>;
+
+		/// The number of slots of an epoch, if set at genesis instead of using
+		/// `T::EpochDuration`.
+		EpochDurationOverride: Option<SlotNumber>;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<(AuthorityId, BabeAuthorityWeight)>;
-		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+		config(epoch_duration): Option<SlotNumber>;
+		build(|config| {
+			Module::<T>::initialize_authorities(&config.authorities);
+			if let Some(epoch_duration) = config.epoch_duration {
+				assert!(epoch_duration > 0, "Epochs must last at least one slot");
+				EpochDurationOverride::put(epoch_duration);
+			}
+		})
 	}
 }
 
@@ -237,6 +278,9 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		/// The number of **slots** that an epoch takes. We couple sessions to
 		/// epochs, i.e. we start a new session once the new epoch begins.
+		///
+		/// This is only the default, the genesis config may override it.
+		/// See `Module::epoch_duration`.
 		const EpochDuration: u64 = T::EpochDuration::get();
 
 		/// The expected average block time at which BABE should be creating
@@ -262,6 +306,21 @@ decl_module! {
 				Self::deposit_vrf_output(&vrf_output);
 			}
 		}
+
+		/// Report an authority for authoring two different blocks at the same slot.
+		///
+		/// The report is an unsigned extrinsic, checked in `validate_unsigned`, which
+		/// block authors create from the equivocations observed by their node.
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Header>,
+			key_owner_proof: T::KeyOwnerProof
+		) {
+			ensure_none(origin)?;
+
+			let offence = Self::check_equivocation_report(equivocation_proof, key_owner_proof)?;
+			T::ReportEquivocation::report_offence(vec![], offence);
+		}
 	}
 }
 
@@ -314,12 +373,10 @@ impl<T: Trait> session::ShouldEndSession<T::BlockNumber> for Module<T> {
 	}
 }
 
-// TODO [slashing]: @marcio use this, remove the dead_code annotation.
 /// A BABE equivocation offence report.
 ///
 /// When a validator released two or more blocks at the same slot.
-#[allow(dead_code)]
-struct BabeEquivocationOffence<FullIdentification> {
+pub struct BabeEquivocationOffence<FullIdentification> {
 	/// A babe slot number in which this incident happened.
 	slot: u64,
 	/// The session index in which the incident happened.
@@ -369,6 +426,11 @@ impl<T: Trait> Module<T> {
 		<T as timestamp::Trait>::MinimumPeriod::get().saturating_mul(2.into())
 	}
 
+	/// The number of slots of an epoch.
+	pub fn epoch_duration() -> SlotNumber {
+		EpochDurationOverride::get().unwrap_or_else(T::EpochDuration::get)
+	}
+
 	/// Determine whether an epoch change should take place at this block.
 	/// Assumes that initialization has already taken place.
 	pub fn should_epoch_change(now: T::BlockNumber) -> bool {
@@ -382,7 +444,7 @@ impl<T: Trait> Module<T> {
 		// so we don't rotate the epoch.
 		now != sr_primitives::traits::One::one() && {
 			let diff = CurrentSlot::get().saturating_sub(Self::current_epoch_start());
-			diff >= T::EpochDuration::get()
+			diff >= Self::epoch_duration()
 		}
 	}
 
@@ -436,7 +498,7 @@ impl<T: Trait> Module<T> {
 	// give correct results after `do_initialize` of the first block
 	// in the chain (as its result is based off of `GenesisSlot`).
 	fn current_epoch_start() -> SlotNumber {
-		(EpochIndex::get() * T::EpochDuration::get()) + GenesisSlot::get()
+		(EpochIndex::get() * Self::epoch_duration()) + GenesisSlot::get()
 	}
 
 	fn deposit_consensus<U: Encode>(new: U) {
@@ -534,6 +596,48 @@ impl<T: Trait> Module<T> {
 		this_randomness
 	}
 
+	/// Generate a proof that the given authority is part of the current validator
+	/// set, to report its equivocations with `equivocation_report_call`.
+	pub fn generate_key_ownership_proof(authority_id: AuthorityId) -> Option<OpaqueKeyOwnershipProof> {
+		T::KeyOwnerProofSystem::prove((<AuthorityId as RuntimeAppPublic>::ID, authority_id))
+			.map(|proof| OpaqueKeyOwnershipProof::new(proof.encode()))
+	}
+
+	/// Create the call reporting the given equivocation, with a proof of key
+	/// ownership of the offender generated by `generate_key_ownership_proof`.
+	///
+	/// Returns `None` if the proof of key ownership can't be decoded.
+	pub fn equivocation_report_call(
+		equivocation_proof: EquivocationProof<T::Header>,
+		key_owner_proof: OpaqueKeyOwnershipProof,
+	) -> Option<Call<T>> {
+		key_owner_proof.decode().map(|key_owner_proof|
+			Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof)
+		)
+	}
+
+	/// Check an equivocation report, returning the offence it proves.
+	fn check_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Header>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> result::Result<BabeEquivocationOffence<T::KeyOwnerIdentification>, &'static str> {
+		let session_index = key_owner_proof.session();
+		let validator_set_count = key_owner_proof.validator_count();
+		let slot = equivocation_proof.slot_number;
+		let offender = equivocation_proof.offender.clone();
+
+		if !babe_primitives::check_equivocation_proof(equivocation_proof) {
+			return Err("Invalid equivocation proof")
+		}
+
+		let offender = T::KeyOwnerProofSystem::check_proof(
+			(<AuthorityId as RuntimeAppPublic>::ID, offender),
+			key_owner_proof,
+		).ok_or("Invalid key ownership proof")?;
+
+		Ok(BabeEquivocationOffence { slot, session_index, validator_set_count, offender })
+	}
+
 	fn initialize_authorities(authorities: &[(AuthorityId, BabeAuthorityWeight)]) {
 		if !authorities.is_empty() {
 			assert!(Authorities::get().is_empty(), "Authorities are already initialized!");
@@ -600,6 +704,33 @@ fn compute_randomness(
 	runtime_io::blake2_256(&s)
 }
 
+#[allow(deprecated)]
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof) = call {
+			let tag = (&equivocation_proof.offender, equivocation_proof.slot_number).encode();
+
+			if Self::check_equivocation_report(equivocation_proof.clone(), key_owner_proof.clone()).is_err() {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				// a single report per offender and slot.
+				provides: vec![tag],
+				longevity: TransactionLongevity::max_value(),
+				// the reports are only included by the authors which observed the equivocation.
+				propagate: false,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
 impl<T: Trait> ProvideInherent for Module<T> {
 	type Call = timestamp::Call<T>;
 	type Error = MakeFatalError<RuntimeString>;
@@ -95,12 +95,24 @@ impl Trait for Test {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = crate::ExternalTrigger;
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof = support::Void;
+	type KeyOwnerIdentification = ();
+	type ReportEquivocation = ();
 }
 
 pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> runtime_io::TestExternalities {
+	new_test_ext_with_epoch_duration(authorities, None)
+}
+
+pub fn new_test_ext_with_epoch_duration(
+	authorities: Vec<DummyValidatorId>,
+	epoch_duration: Option<u64>,
+) -> runtime_io::TestExternalities {
 	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 	GenesisConfig {
 		authorities: authorities.into_iter().map(|a| (UintAuthorityId(a).to_public_key(), 1)).collect(),
+		epoch_duration,
 	}.assimilate_storage::<Test>(&mut t).unwrap();
 	t.into()
 }
@@ -17,7 +17,7 @@
 //! Consensus extension module tests for BABE consensus.
 
 use super::*;
-use mock::{new_test_ext, Babe, Test};
+use mock::{new_test_ext, new_test_ext_with_epoch_duration, Babe, Test};
 use sr_primitives::{traits::OnFinalize, testing::{Digest, DigestItem}};
 use session::ShouldEndSession;
 
@@ -116,6 +116,24 @@ fn first_block_epoch_zero_start() {
 	})
 }
 
+#[test]
+fn epoch_duration_can_be_set_at_genesis() {
+	let epoch_changes = |epoch_duration| new_test_ext_with_epoch_duration(vec![0, 1], epoch_duration)
+		.execute_with(|| {
+			let pre_digest = make_pre_digest(0, 100, [1; 32], [0xff; 64]);
+			System::initialize(&1, &Default::default(), &Default::default(), &pre_digest);
+
+			(103u64..=110).find(|slot| {
+				CurrentSlot::put(*slot);
+				Babe::should_epoch_change(2)
+			})
+		});
+
+	assert_eq!(epoch_changes(None), Some(103));
+	assert_eq!(epoch_changes(Some(10)), Some(110));
+	assert_eq!(epoch_changes(Some(20)), None);
+}
+
 #[test]
 fn authority_index() {
 	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
@@ -124,3 +142,48 @@ fn authority_index() {
 			"Trivially invalid authorities are ignored")
 	})
 }
+
+#[test]
+fn equivocation_proofs_are_checked() {
+	use primitives::crypto::Pair;
+	use sr_primitives::{testing::Header, traits::Header as HeaderT};
+
+	let pair = babe_primitives::AuthorityPair::from_seed(&[1; 32]);
+	let make_header = |slot_number, extrinsics_root, signer: &babe_primitives::AuthorityPair| {
+		let mut header = Header::new(
+			1,
+			extrinsics_root,
+			Default::default(),
+			Default::default(),
+			make_pre_digest(0, slot_number, [1; 32], [0xff; 64]),
+		);
+		let signature = signer.sign(header.hash().as_ref());
+		header.digest_mut().push(DigestItem::Seal(BABE_ENGINE_ID, signature.encode()));
+		header
+	};
+	let proof = |first_header, second_header| EquivocationProof {
+		offender: pair.public(),
+		slot_number: 10,
+		first_header,
+		second_header,
+	};
+
+	let first_header = make_header(10, [1; 32].into(), &pair);
+	assert!(babe_primitives::check_equivocation_proof(
+		proof(first_header.clone(), make_header(10, [2; 32].into(), &pair)),
+	));
+
+	// the same header twice isn't an equivocation.
+	assert!(!babe_primitives::check_equivocation_proof(
+		proof(first_header.clone(), first_header.clone()),
+	));
+	// neither are headers at different slots.
+	assert!(!babe_primitives::check_equivocation_proof(
+		proof(first_header.clone(), make_header(11, [2; 32].into(), &pair)),
+	));
+	// nor headers sealed by someone else.
+	let other = babe_primitives::AuthorityPair::from_seed(&[2; 32]);
+	assert!(!babe_primitives::check_equivocation_proof(
+		proof(first_header, make_header(10, [2; 32].into(), &other)),
+	));
+}
@@ -0,0 +1,64 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Benchmarks for the balances module.
+
+use super::*;
+
+use system::RawOrigin;
+use srml_benchmarking::{benchmarks, account};
+
+const SEED: u32 = 0;
+const MAX_USER_INDEX: u32 = 1000;
+
+benchmarks! {
+	// Transfer to an account that does not exist yet.
+	transfer {
+		let u in 1 .. MAX_USER_INDEX;
+		let existential_deposit = T::ExistentialDeposit::get().max(1u32.into());
+		let caller: T::AccountId = account("caller", u, SEED);
+		let _ = <Module<T> as Currency<_>>::make_free_balance_be(
+			&caller,
+			existential_deposit.saturating_mul(100u32.into()),
+		);
+		let recipient: T::AccountId = account("recipient", u, SEED);
+		let recipient_lookup = T::Lookup::unlookup(recipient);
+		let amount = existential_deposit.saturating_mul(10u32.into());
+	}: transfer(RawOrigin::Signed(caller), recipient_lookup, amount)
+
+	// Transfer to an existing account without killing the sender.
+	transfer_keep_alive {
+		let u in 1 .. MAX_USER_INDEX;
+		let existential_deposit = T::ExistentialDeposit::get().max(1u32.into());
+		let balance = existential_deposit.saturating_mul(100u32.into());
+		let caller: T::AccountId = account("caller", u, SEED);
+		let _ = <Module<T> as Currency<_>>::make_free_balance_be(&caller, balance);
+		let recipient: T::AccountId = account("recipient", u, SEED);
+		let _ = <Module<T> as Currency<_>>::make_free_balance_be(&recipient, balance);
+		let recipient_lookup = T::Lookup::unlookup(recipient);
+		let amount = existential_deposit.saturating_mul(10u32.into());
+	}: transfer_keep_alive(RawOrigin::Signed(caller), recipient_lookup, amount)
+
+	// Set the balance of an existing account.
+	set_balance {
+		let u in 1 .. MAX_USER_INDEX;
+		let existential_deposit = T::ExistentialDeposit::get().max(1u32.into());
+		let balance = existential_deposit.saturating_mul(100u32.into());
+		let user: T::AccountId = account("user", u, SEED);
+		let _ = <Module<T> as Currency<_>>::make_free_balance_be(&user, balance);
+		let user_lookup = T::Lookup::unlookup(user);
+	}: set_balance(RawOrigin::Root, user_lookup, balance, balance)
+}
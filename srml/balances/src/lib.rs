@@ -55,8 +55,6 @@
 //! simply dropped, it should automatically maintain any book-keeping such as total issuance.)
 //! - **Lock:** A freeze on a specified amount of an account's free balance until a specified block number. Multiple
 //! locks always operate over the same funds, so they "overlay" rather than "stack".
-//! - **Vesting:** Similar to a lock, this is another, but independent, liquidity restriction that reduces linearly
-//! over time.
 //!
 //! ### Implementations
 //!
@@ -67,6 +65,9 @@
 //! fungible assets system.
 //! - [`ReservableCurrency`](../srml_support/traits/trait.ReservableCurrency.html):
 //! Functions for dealing with assets that can be reserved from an account.
+//! - [`NamedReservableCurrency`](../srml_support/traits/trait.NamedReservableCurrency.html):
+//! Functions for dealing with assets that are reserved under an identifier, independently of
+//! the other reserves of the account.
 //! - [`LockableCurrency`](../srml_support/traits/trait.LockableCurrency.html): Functions for
 //! dealing with accounts that allow liquidity restrictions.
 //! - [`Imbalance`](../srml_support/traits/trait.Imbalance.html): Functions for handling
@@ -82,10 +83,6 @@
 //! - `transfer` - Transfer some liquid free balance to another account.
 //! - `set_balance` - Set the balances of a given account. The origin of this call must be root.
 //!
-//! ### Public Functions
-//!
-//! - `vesting_balance` - Get the amount that is currently being vested and cannot be transferred out of this account.
-//!
 //! ## Usage
 //!
 //! The following examples show how to use the Balances module in your custom module.
@@ -155,7 +152,8 @@ use support::{
 	traits::{
 		UpdateBalanceOutcome, Currency, OnFreeBalanceZero, OnUnbalanced,
 		WithdrawReason, WithdrawReasons, LockIdentifier, LockableCurrency, ExistenceRequirement,
-		Imbalance, SignedImbalance, ReservableCurrency, Get,
+		Imbalance, SignedImbalance, ReservableCurrency, Get, ReserveIdentifier,
+		NamedReservableCurrency,
 	},
 	dispatch::Result,
 };
@@ -171,6 +169,8 @@ use system::{IsDeadAccount, OnNewAccount, ensure_signed, ensure_root};
 
 mod mock;
 mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 pub use self::imbalances::{PositiveImbalance, NegativeImbalance};
 
@@ -255,7 +255,7 @@ decl_event!(
 	}
 );
 
-/// Struct to encode the vesting schedule of an individual account.
+/// The vesting schedule of an account, from when vesting was part of this module.
 #[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
 pub struct VestingSchedule<Balance, BlockNumber> {
 	/// Locked amount at genesis.
@@ -291,6 +291,13 @@ pub struct BalanceLock<Balance, BlockNumber> {
 	pub reasons: WithdrawReasons,
 }
 
+/// A portion of the reserved balance of an account, reserved under an identifier.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ReserveData<Balance> {
+	pub id: ReserveIdentifier,
+	pub amount: Balance,
+}
+
 decl_storage! {
 	trait Store for Module<T: Trait<I>, I: Instance=DefaultInstance> as Balances {
 		/// The total units issued in the system.
@@ -298,32 +305,12 @@ decl_storage! {
 			config.balances.iter().fold(Zero::zero(), |acc: T::Balance, &(_, n)| acc + n)
 		}): T::Balance;
 
-		/// Information regarding the vesting of a given account.
-		pub Vesting get(fn vesting) build(|config: &GenesisConfig<T, I>| {
-			// Generate initial vesting configuration
-			// * who - Account which we are generating vesting configuration for
-			// * begin - Block when the account will start to vest
-			// * length - Number of blocks from `begin` until fully vested
-			// * liquid - Number of units which can be spent before vesting begins
-			config.vesting.iter().filter_map(|&(ref who, begin, length, liquid)| {
-				let length = <T::Balance as From<T::BlockNumber>>::from(length);
-
-				config.balances.iter()
-					.find(|&&(ref w, _)| w == who)
-					.map(|&(_, balance)| {
-						// Total genesis `balance` minus `liquid` equals funds locked for vesting
-						let locked = balance.saturating_sub(liquid);
-						// Number of units unlocked per block after `begin`
-						let per_block = locked / length.max(sr_primitives::traits::One::one());
-
-						(who.clone(), VestingSchedule {
-							locked: locked,
-							per_block: per_block,
-							starting_block: begin
-						})
-					})
-			}).collect::<Vec<_>>()
-		}): map T::AccountId => Option<VestingSchedule<T::Balance, T::BlockNumber>>;
+		/// Deprecated, the vesting schedules from when vesting was part of this module.
+		///
+		/// The vesting module moves the schedule of an account to its own storage, along with a
+		/// lock, the first time the account vests. Until then the schedule is still enforced here.
+		pub Vesting get(fn legacy_vesting):
+			map T::AccountId => Option<VestingSchedule<T::Balance, T::BlockNumber>>;
 
 		/// The 'free' balance of a given account.
 		///
@@ -355,11 +342,14 @@ decl_storage! {
 
 		/// Any liquidity locks on some account balances.
 		pub Locks get(fn locks): map T::AccountId => Vec<BalanceLock<T::Balance, T::BlockNumber>>;
+
+		/// The portions of the reserved balance of an account that are reserved under an identifier.
+		///
+		/// The rest of `ReservedBalance` is not reserved under any identifier.
+		pub Reserves get(fn reserves): map T::AccountId => Vec<ReserveData<T::Balance>>;
 	}
 	add_extra_genesis {
 		config(balances): Vec<(T::AccountId, T::Balance)>;
-		config(vesting): Vec<(T::AccountId, T::BlockNumber, T::BlockNumber, T::Balance)>;
-		// ^^ begin, length, amount liquid at genesis
 	}
 }
 
@@ -491,9 +481,9 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 
 	// PUBLIC IMMUTABLES
 
-	/// Get the amount that is currently being vested and cannot be transferred out of this account.
-	pub fn vesting_balance(who: &T::AccountId) -> T::Balance {
-		if let Some(v) = Self::vesting(who) {
+	/// Get the amount that is still locked by the deprecated vesting schedule of `who`.
+	pub fn legacy_vesting_balance(who: &T::AccountId) -> T::Balance {
+		if let Some(v) = Self::legacy_vesting(who) {
 			Self::free_balance(who)
 				.min(v.locked_at(<system::Module<T>>::block_number()))
 		} else {
@@ -586,6 +576,7 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 	/// Will maintain total issuance.
 	fn on_reserved_too_low(who: &T::AccountId) {
 		let dust = <ReservedBalance<T, I>>::take(who);
+		<Reserves<T, I>>::remove(who);
 
 		// underflow should never happen, but it if does, there's nothing to be done here.
 		if !dust.is_zero() {
@@ -596,6 +587,51 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 			Self::reap_account(who);
 		}
 	}
+
+	/// The portion of the reserved balance of `who` that isn't reserved under any identifier.
+	fn anonymous_reserved_balance(who: &T::AccountId) -> T::Balance {
+		let named = Self::reserves(who).iter()
+			.fold(T::Balance::zero(), |total, reserve| total.saturating_add(reserve.amount));
+		Self::reserved_balance(who).saturating_sub(named)
+	}
+
+	/// Deduct `value` from the named reserves of `who`, in the order they were made, so that they
+	/// stay within the reserved balance when it is slashed regardless of identifiers.
+	fn slash_named_reserves(who: &T::AccountId, mut value: T::Balance) {
+		if value.is_zero() {
+			return
+		}
+		let mut reserves = Self::reserves(who);
+		for reserve in reserves.iter_mut() {
+			let slash = cmp::min(reserve.amount, value);
+			reserve.amount -= slash;
+			value -= slash;
+		}
+		reserves.retain(|reserve| !reserve.amount.is_zero());
+
+		if reserves.is_empty() {
+			<Reserves<T, I>>::remove(who);
+		} else {
+			<Reserves<T, I>>::insert(who, reserves);
+		}
+	}
+
+	/// Set the amount reserved under `id` for `who`, removing the reserve if it is zero.
+	fn set_reserve_named(id: &ReserveIdentifier, who: &T::AccountId, amount: T::Balance) {
+		let mut reserves = Self::reserves(who);
+		match reserves.iter().position(|reserve| &reserve.id == id) {
+			Some(index) if amount.is_zero() => { reserves.remove(index); },
+			Some(index) => reserves[index].amount = amount,
+			None if !amount.is_zero() => reserves.push(ReserveData { id: *id, amount }),
+			None => (),
+		}
+
+		if reserves.is_empty() {
+			<Reserves<T, I>>::remove(who);
+		} else {
+			<Reserves<T, I>>::insert(who, reserves);
+		}
+	}
 }
 
 // wrapping these imbalances in a private module is necessary to ensure absolute privacy
@@ -848,7 +884,7 @@ where
 		new_balance: T::Balance,
 	) -> Result {
 		if reasons.intersects(WithdrawReason::Reserve | WithdrawReason::Transfer)
-			&& Self::vesting_balance(who) > new_balance
+			&& Self::legacy_vesting_balance(who) > new_balance
 		{
 			return Err("vesting balance too high to send value");
 		}
@@ -961,6 +997,9 @@ where
 		if !remaining_slash.is_zero() {
 			let reserved_balance = Self::reserved_balance(who);
 			let reserved_slash = cmp::min(reserved_balance, remaining_slash);
+			// the anonymous reserve is drawn from first, the rest comes out of the named reserves.
+			let named_slash = reserved_slash.saturating_sub(Self::anonymous_reserved_balance(who));
+			Self::slash_named_reserves(who, named_slash);
 			Self::set_reserved_balance(who, reserved_balance - reserved_slash);
 			(NegativeImbalance::new(free_slash + reserved_slash), remaining_slash - reserved_slash)
 		} else {
@@ -1067,7 +1106,7 @@ where
 
 	fn unreserve(who: &T::AccountId, value: Self::Balance) -> Self::Balance {
 		let b = Self::reserved_balance(who);
-		let actual = cmp::min(b, value);
+		let actual = cmp::min(Self::anonymous_reserved_balance(who), value);
 		Self::set_free_balance(who, Self::free_balance(who) + actual);
 		Self::set_reserved_balance(who, b - actual);
 		value - actual
@@ -1078,7 +1117,7 @@ where
 		value: Self::Balance
 	) -> (Self::NegativeImbalance, Self::Balance) {
 		let b = Self::reserved_balance(who);
-		let slash = cmp::min(b, value);
+		let slash = cmp::min(Self::anonymous_reserved_balance(who), value);
 		// underflow should never happen, but it if does, there's nothing to be done here.
 		Self::set_reserved_balance(who, b - slash);
 		(NegativeImbalance::new(slash), value - slash)
@@ -1093,13 +1132,88 @@ where
 			return Err("beneficiary account must pre-exist");
 		}
 		let b = Self::reserved_balance(slashed);
-		let slash = cmp::min(b, value);
+		let slash = cmp::min(Self::anonymous_reserved_balance(slashed), value);
 		Self::set_free_balance(beneficiary, Self::free_balance(beneficiary) + slash);
 		Self::set_reserved_balance(slashed, b - slash);
 		Ok(value - slash)
 	}
 }
 
+impl<T: Trait<I>, I: Instance> NamedReservableCurrency<T::AccountId> for Module<T, I>
+where
+	T::Balance: MaybeSerializeDeserialize + Debug
+{
+	fn reserved_balance_named(id: &ReserveIdentifier, who: &T::AccountId) -> Self::Balance {
+		Self::reserves(who).into_iter()
+			.find(|reserve| &reserve.id == id)
+			.map_or_else(Zero::zero, |reserve| reserve.amount)
+	}
+
+	fn reserve_named(
+		id: &ReserveIdentifier,
+		who: &T::AccountId,
+		value: Self::Balance,
+	) -> result::Result<(), &'static str> {
+		if value.is_zero() {
+			return Ok(())
+		}
+		<Self as ReservableCurrency<_>>::reserve(who, value)?;
+		let named = Self::reserved_balance_named(id, who);
+		Self::set_reserve_named(id, who, named + value);
+		Ok(())
+	}
+
+	fn unreserve_named(id: &ReserveIdentifier, who: &T::AccountId, value: Self::Balance) -> Self::Balance {
+		let named = Self::reserved_balance_named(id, who);
+		let b = Self::reserved_balance(who);
+		// the named reserve can't exceed the reserved balance, but be defensive about it.
+		let actual = cmp::min(cmp::min(named, b), value);
+		// update the named reserve first, as the whole reserve is killed if it gets too low.
+		Self::set_reserve_named(id, who, named - actual);
+		Self::set_free_balance(who, Self::free_balance(who) + actual);
+		Self::set_reserved_balance(who, b - actual);
+		value - actual
+	}
+
+	fn slash_reserved_named(
+		id: &ReserveIdentifier,
+		who: &T::AccountId,
+		value: Self::Balance,
+	) -> (Self::NegativeImbalance, Self::Balance) {
+		let named = Self::reserved_balance_named(id, who);
+		let b = Self::reserved_balance(who);
+		let slash = cmp::min(cmp::min(named, b), value);
+		Self::set_reserve_named(id, who, named - slash);
+		Self::set_reserved_balance(who, b - slash);
+		(NegativeImbalance::new(slash), value - slash)
+	}
+
+	fn repatriate_reserved_named(
+		id: &ReserveIdentifier,
+		slashed: &T::AccountId,
+		beneficiary: &T::AccountId,
+		value: Self::Balance,
+	) -> result::Result<Self::Balance, &'static str> {
+		if Self::total_balance(beneficiary).is_zero() {
+			return Err("beneficiary account must pre-exist");
+		}
+		let named = Self::reserved_balance_named(id, slashed);
+		let b = Self::reserved_balance(slashed);
+		let slash = cmp::min(cmp::min(named, b), value);
+		Self::set_reserve_named(id, slashed, named - slash);
+		Self::set_free_balance(beneficiary, Self::free_balance(beneficiary) + slash);
+		Self::set_reserved_balance(slashed, b - slash);
+		Ok(value - slash)
+	}
+
+	fn name_reserved(id: &ReserveIdentifier, who: &T::AccountId, value: Self::Balance) -> Self::Balance {
+		let actual = cmp::min(Self::anonymous_reserved_balance(who), value);
+		let named = Self::reserved_balance_named(id, who);
+		Self::set_reserve_named(id, who, named + actual);
+		value - actual
+	}
+}
+
 impl<T: Trait<I>, I: Instance> LockableCurrency<T::AccountId> for Module<T, I>
 where
 	T::Balance: MaybeSerializeDeserialize + Debug
@@ -24,7 +24,8 @@ use sr_primitives::traits::SignedExtension;
 use support::{
 	assert_noop, assert_ok, assert_err,
 	traits::{LockableCurrency, LockIdentifier, WithdrawReason, WithdrawReasons,
-	Currency, ReservableCurrency, ExistenceRequirement::AllowDeath}
+	Currency, ReservableCurrency, NamedReservableCurrency, ReserveIdentifier,
+	ExistenceRequirement::AllowDeath}
 };
 use transaction_payment::ChargeTransactionPayment;
 use system::RawOrigin;
@@ -33,6 +34,9 @@ const ID_1: LockIdentifier = *b"1       ";
 const ID_2: LockIdentifier = *b"2       ";
 const ID_3: LockIdentifier = *b"3       ";
 
+const RID_1: ReserveIdentifier = *b"1       ";
+const RID_2: ReserveIdentifier = *b"2       ";
+
 #[test]
 fn basic_locking_should_work() {
 	ExtBuilder::default().existential_deposit(1).monied(true).build().execute_with(|| {
@@ -583,180 +587,148 @@ fn transfer_overflow_isnt_exploitable() {
 }
 
 #[test]
-fn check_vesting_status() {
-	ExtBuilder::default()
-		.existential_deposit(256)
-		.monied(true)
-		.vesting(true)
-		.build()
-		.execute_with(|| {
-			assert_eq!(System::block_number(), 1);
-			let user1_free_balance = Balances::free_balance(&1);
-			let user2_free_balance = Balances::free_balance(&2);
-			let user12_free_balance = Balances::free_balance(&12);
-			assert_eq!(user1_free_balance, 256 * 10); // Account 1 has free balance
-			assert_eq!(user2_free_balance, 256 * 20); // Account 2 has free balance
-			assert_eq!(user12_free_balance, 256 * 10); // Account 12 has free balance
-			let user1_vesting_schedule = VestingSchedule {
-				locked: 256 * 5,
-				per_block: 128, // Vesting over 10 blocks
-				starting_block: 0,
-			};
-			let user2_vesting_schedule = VestingSchedule {
-				locked: 256 * 20,
-				per_block: 256, // Vesting over 20 blocks
-				starting_block: 10,
-			};
-			let user12_vesting_schedule = VestingSchedule {
-				locked: 256 * 5,
-				per_block: 64, // Vesting over 20 blocks
-				starting_block: 10,
-			};
-			assert_eq!(Balances::vesting(&1), Some(user1_vesting_schedule)); // Account 1 has a vesting schedule
-			assert_eq!(Balances::vesting(&2), Some(user2_vesting_schedule)); // Account 2 has a vesting schedule
-			assert_eq!(Balances::vesting(&12), Some(user12_vesting_schedule)); // Account 12 has a vesting schedule
-
-			// Account 1 has only 128 units vested from their illiquid 256 * 5 units at block 1
-			assert_eq!(Balances::vesting_balance(&1), 128 * 9);
-			// Account 2 has their full balance locked
-			assert_eq!(Balances::vesting_balance(&2), user2_free_balance);
-			// Account 12 has only their illiquid funds locked
-			assert_eq!(Balances::vesting_balance(&12), user12_free_balance - 256 * 5);
-
-			System::set_block_number(10);
-			assert_eq!(System::block_number(), 10);
-
-			// Account 1 has fully vested by block 10
-			assert_eq!(Balances::vesting_balance(&1), 0);
-			// Account 2 has started vesting by block 10
-			assert_eq!(Balances::vesting_balance(&2), user2_free_balance);
-			// Account 12 has started vesting by block 10
-			assert_eq!(Balances::vesting_balance(&12), user12_free_balance - 256 * 5);
-
-			System::set_block_number(30);
-			assert_eq!(System::block_number(), 30);
-
-			assert_eq!(Balances::vesting_balance(&1), 0); // Account 1 is still fully vested, and not negative
-			assert_eq!(Balances::vesting_balance(&2), 0); // Account 2 has fully vested by block 30
-			assert_eq!(Balances::vesting_balance(&12), 0); // Account 2 has fully vested by block 30
-
-		});
+fn burn_must_work() {
+	ExtBuilder::default().monied(true).build().execute_with(|| {
+		let init_total_issuance = Balances::total_issuance();
+		let imbalance = Balances::burn(10);
+		assert_eq!(Balances::total_issuance(), init_total_issuance - 10);
+		drop(imbalance);
+		assert_eq!(Balances::total_issuance(), init_total_issuance);
+	});
 }
 
 #[test]
-fn unvested_balance_should_not_transfer() {
-	ExtBuilder::default()
-		.existential_deposit(10)
-		.monied(true)
-		.vesting(true)
-		.build()
-		.execute_with(|| {
-			assert_eq!(System::block_number(), 1);
-			let user1_free_balance = Balances::free_balance(&1);
-			assert_eq!(user1_free_balance, 100); // Account 1 has free balance
-			// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
-			assert_eq!(Balances::vesting_balance(&1), 45);
-			assert_noop!(
-				Balances::transfer(Some(1).into(), 2, 56),
-				"vesting balance too high to send value",
-			); // Account 1 cannot send more than vested amount
-		});
+fn transfer_keep_alive_works() {
+	ExtBuilder::default().existential_deposit(1).build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 100);
+		assert_err!(
+			Balances::transfer_keep_alive(Some(1).into(), 2, 100),
+			"transfer would kill account"
+		);
+		assert_eq!(Balances::is_dead_account(&1), false);
+		assert_eq!(Balances::total_balance(&1), 100);
+		assert_eq!(Balances::total_balance(&2), 0);
+	});
 }
 
 #[test]
-fn vested_balance_should_transfer() {
-	ExtBuilder::default()
-		.existential_deposit(10)
-		.monied(true)
-		.vesting(true)
-		.build()
-		.execute_with(|| {
-			assert_eq!(System::block_number(), 1);
-			let user1_free_balance = Balances::free_balance(&1);
-			assert_eq!(user1_free_balance, 100); // Account 1 has free balance
-			// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
-			assert_eq!(Balances::vesting_balance(&1), 45);
-			assert_ok!(Balances::transfer(Some(1).into(), 2, 55));
-		});
+fn named_reserves_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 111);
+		assert_ok!(Balances::reserve_named(&RID_1, &1, 20));
+		assert_ok!(Balances::reserve_named(&RID_2, &1, 30));
+		assert_ok!(Balances::reserve(&1, 10));
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 20);
+		assert_eq!(Balances::reserved_balance_named(&RID_2, &1), 30);
+		assert_eq!(Balances::reserved_balance(&1), 60);
+		assert_eq!(Balances::free_balance(&1), 51);
+
+		// unreserving one identifier leaves the others untouched.
+		assert_eq!(Balances::unreserve_named(&RID_1, &1, 25), 5);
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 0);
+		assert_eq!(Balances::reserved_balance_named(&RID_2, &1), 30);
+		assert_eq!(Balances::reserved_balance(&1), 40);
+		assert_eq!(Balances::free_balance(&1), 71);
+		assert_eq!(Balances::reserves(&1), vec![ReserveData { id: RID_2, amount: 30 }]);
+
+		assert_noop!(Balances::reserve_named(&RID_1, &1, 100), "not enough free funds");
+	});
 }
 
 #[test]
-fn extra_balance_should_transfer() {
-	ExtBuilder::default()
-		.existential_deposit(10)
-		.monied(true)
-		.vesting(true)
-		.build()
-		.execute_with(|| {
-			assert_eq!(System::block_number(), 1);
-			assert_ok!(Balances::transfer(Some(3).into(), 1, 100));
-			assert_ok!(Balances::transfer(Some(3).into(), 2, 100));
+fn anonymous_unreserve_should_not_touch_named_reserves() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 111);
+		assert_ok!(Balances::reserve_named(&RID_1, &1, 50));
+		assert_ok!(Balances::reserve(&1, 10));
+
+		assert_eq!(Balances::unreserve(&1, 30), 20);
+		assert_eq!(Balances::slash_reserved(&1, 30).1, 30);
+		assert_eq!(Balances::repatriate_reserved(&1, &2, 30), Err("beneficiary account must pre-exist"));
+		assert_eq!(Balances::reserved_balance(&1), 50);
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 50);
+		assert_eq!(Balances::free_balance(&1), 61);
+	});
+}
 
-			let user1_free_balance = Balances::free_balance(&1);
-			assert_eq!(user1_free_balance, 200); // Account 1 has 100 more free balance than normal
+#[test]
+fn slashing_named_reserve_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 111);
+		assert_ok!(Balances::reserve_named(&RID_1, &1, 50));
+		assert_ok!(Balances::reserve(&1, 10));
 
-			let user2_free_balance = Balances::free_balance(&2);
-			assert_eq!(user2_free_balance, 300); // Account 2 has 100 more free balance than normal
+		assert_eq!(Balances::slash_reserved_named(&RID_1, &1, 60).1, 10);
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 0);
+		assert_eq!(Balances::reserved_balance(&1), 10);
+		assert_eq!(<TotalIssuance<Runtime>>::get(), 61);
+	});
+}
 
-			// Account 1 has only 5 units vested at block 1 (plus 150 unvested)
-			assert_eq!(Balances::vesting_balance(&1), 45);
-			assert_ok!(Balances::transfer(Some(1).into(), 3, 155)); // Account 1 can send extra units gained
+#[test]
+fn slashing_should_draw_from_named_reserves_last() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 111);
+		assert_ok!(Balances::reserve_named(&RID_1, &1, 30));
+		assert_ok!(Balances::reserve_named(&RID_2, &1, 30));
+		assert_ok!(Balances::reserve(&1, 10));
 
-			// Account 2 has no units vested at block 1, but gained 100
-			assert_eq!(Balances::vesting_balance(&2), 200);
-			assert_ok!(Balances::transfer(Some(2).into(), 3, 100)); // Account 2 can send extra units gained
-		});
+		// 41 free, then 10 anonymous, then 30 from the first named reserve and 9 from the second.
+		assert_eq!(Balances::slash(&1, 90).1, 0);
+		assert_eq!(Balances::free_balance(&1), 0);
+		assert_eq!(Balances::reserved_balance(&1), 21);
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 0);
+		assert_eq!(Balances::reserved_balance_named(&RID_2, &1), 21);
+		assert_eq!(Balances::reserves(&1), vec![ReserveData { id: RID_2, amount: 21 }]);
+	});
 }
 
 #[test]
-fn liquid_funds_should_transfer_with_delayed_vesting() {
-	ExtBuilder::default()
-		.existential_deposit(256)
-		.monied(true)
-		.vesting(true)
-		.build()
-		.execute_with(|| {
-			assert_eq!(System::block_number(), 1);
-			let user12_free_balance = Balances::free_balance(&12);
-
-			assert_eq!(user12_free_balance, 2560); // Account 12 has free balance
-			// Account 12 has liquid funds
-			assert_eq!(Balances::vesting_balance(&12), user12_free_balance - 256 * 5);
-
-			// Account 12 has delayed vesting
-			let user12_vesting_schedule = VestingSchedule {
-				locked: 256 * 5,
-				per_block: 64, // Vesting over 20 blocks
-				starting_block: 10,
-			};
-			assert_eq!(Balances::vesting(&12), Some(user12_vesting_schedule));
-
-			// Account 12 can still send liquid funds
-			assert_ok!(Balances::transfer(Some(12).into(), 3, 256 * 5));
-		});
+fn repatriating_named_reserve_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 110);
+		let _ = Balances::deposit_creating(&2, 1);
+		assert_ok!(Balances::reserve_named(&RID_1, &1, 50));
+		assert_ok!(Balances::reserve(&1, 10));
+
+		assert_eq!(Balances::repatriate_reserved_named(&RID_1, &1, &3, 20), Err("beneficiary account must pre-exist"));
+		assert_eq!(Balances::repatriate_reserved_named(&RID_1, &1, &2, 70), Ok(20));
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 0);
+		assert_eq!(Balances::reserved_balance(&1), 10);
+		assert_eq!(Balances::free_balance(&2), 51);
+	});
 }
 
 #[test]
-fn burn_must_work() {
-	ExtBuilder::default().monied(true).build().execute_with(|| {
-		let init_total_issuance = Balances::total_issuance();
-		let imbalance = Balances::burn(10);
-		assert_eq!(Balances::total_issuance(), init_total_issuance - 10);
-		drop(imbalance);
-		assert_eq!(Balances::total_issuance(), init_total_issuance);
+fn naming_anonymous_reserve_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 111);
+		assert_ok!(Balances::reserve(&1, 40));
+		assert_ok!(Balances::reserve_named(&RID_1, &1, 10));
+
+		assert_eq!(Balances::name_reserved(&RID_1, &1, 50), 10);
+		assert_eq!(Balances::reserved_balance_named(&RID_1, &1), 50);
+		assert_eq!(Balances::reserved_balance(&1), 50);
+		assert_eq!(Balances::unreserve(&1, 10), 10);
 	});
 }
 
 #[test]
-fn transfer_keep_alive_works() {
+#[cfg(feature = "runtime-benchmarks")]
+fn benchmarks_should_run() {
+	use srml_benchmarking::Benchmarking;
+
 	ExtBuilder::default().existential_deposit(1).build().execute_with(|| {
-		let _ = Balances::deposit_creating(&1, 100);
-		assert_err!(
-			Balances::transfer_keep_alive(Some(1).into(), 2, 100),
-			"transfer would kill account"
+		for extrinsic in &["transfer", "transfer_keep_alive", "set_balance"] {
+			let results = <Balances as Benchmarking<_>>::run_benchmark(extrinsic.as_bytes().to_vec(), 2, 2)
+				.unwrap();
+			let users: Vec<u32> = results.iter().map(|r| (r.0)[0].1).collect();
+			assert_eq!(users, vec![1, 1, 500, 500, 1000, 1000]);
+		}
+
+		assert_eq!(
+			<Balances as Benchmarking<_>>::run_benchmark(b"burn".to_vec(), 2, 2),
+			Err("Could not find extrinsic."),
 		);
-		assert_eq!(Balances::is_dead_account(&1), false);
-		assert_eq!(Balances::total_balance(&1), 100);
-		assert_eq!(Balances::total_balance(&2), 0);
 	});
 }
@@ -0,0 +1,280 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Benchmarking Module
+//!
+//! Tools for measuring the execution time of dispatchable calls, so that their weights can be
+//! derived from measurements instead of being guessed.
+//!
+//! A module declares its benchmarks with the [`benchmarks!`](macro.benchmarks.html) macro. Every
+//! benchmark names the dispatchable it measures, the components the cost of that dispatchable
+//! depends on, given as single letter parameters with an inclusive range, and the setup code that
+//! builds the call for a particular choice of component values:
+//!
+//! ```ignore
+//! benchmarks! {
+//! 	transfer {
+//! 		let u in 1 .. 1000;
+//! 		let caller: T::AccountId = account("caller", u, 0);
+//! 		let _ = <Module<T> as Currency<_>>::make_free_balance_be(&caller, 1_000_000u32.into());
+//! 		let recipient = T::Lookup::unlookup(account("recipient", u, 0));
+//! 	}: transfer(RawOrigin::Signed(caller), recipient, 100u32.into())
+//! }
+//! ```
+//!
+//! The macro implements [`Benchmarking`](trait.Benchmarking.html) for the module, which the
+//! runtime exposes through the [`Benchmark`](trait.Benchmark.html) runtime api.
+//!
+//! Every component is stepped through its range while the others are held at their maximum. For
+//! each step the setup code is run and committed, after which only the dispatch of the call is
+//! timed. Changes made by the dispatch are wiped before the next repetition, while the state
+//! built by the setup code is kept.
+//!
+//! Benchmarks must never be part of a production runtime: modules declare them, and runtimes
+//! expose them, behind a `runtime-benchmarks` feature, which also enables the host functions
+//! they need in the executor.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use sr_primitives::{RuntimeDebug, RuntimeString, DispatchError, traits::Dispatchable};
+
+#[doc(hidden)]
+pub use rstd::vec::Vec;
+
+mod tests;
+
+/// A component of a benchmark.
+///
+/// Components are named by a single letter, matching the parameter name used in
+/// [`benchmarks!`](macro.benchmarks.html).
+#[allow(non_camel_case_types)]
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum BenchmarkParameter {
+	a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p, q, r, s, t, u, v, w, x, y, z,
+}
+
+/// The values of the components used in one run of a benchmark, along with the time in
+/// nanoseconds the dispatch took.
+pub type BenchmarkResults = (Vec<(BenchmarkParameter, u32)>, u64);
+
+client::decl_runtime_apis! {
+	/// Runtime api for benchmarking the dispatchables of a runtime.
+	pub trait Benchmark {
+		/// Run the benchmark of `extrinsic` in `module`.
+		///
+		/// Every component is measured at `steps` points of its range, each of them `repeat`
+		/// times.
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			extrinsic: Vec<u8>,
+			steps: u32,
+			repeat: u32,
+		) -> Result<Vec<BenchmarkResults>, RuntimeString>;
+	}
+}
+
+/// A module whose dispatchables can be benchmarked.
+///
+/// Implemented by [`benchmarks!`](macro.benchmarks.html).
+pub trait Benchmarking<T> {
+	/// Run the benchmark of `extrinsic`, measuring every component at `steps` points of its range,
+	/// each of them `repeat` times.
+	fn run_benchmark(extrinsic: Vec<u8>, steps: u32, repeat: u32) -> Result<Vec<T>, &'static str>;
+}
+
+/// The setup of a single benchmark.
+///
+/// Implemented by [`benchmarks!`](macro.benchmarks.html).
+pub trait BenchmarkingSetup<Call, Origin> {
+	/// The components of the benchmark, along with their lowest and highest value.
+	fn components(&self) -> Vec<(BenchmarkParameter, u32, u32)>;
+
+	/// Prepare the state for the given component values and return the call to measure, along
+	/// with the origin to dispatch it with.
+	fn instance(&self, components: &[(BenchmarkParameter, u32)]) -> Result<(Call, Origin), &'static str>;
+}
+
+/// Derive an account id from a name, an index and a seed.
+///
+/// Useful to create distinct accounts in the setup of a benchmark.
+pub fn account<AccountId: Decode + Default>(name: &'static str, index: u32, seed: u32) -> AccountId {
+	let entropy = (name, index, seed).using_encoded(runtime_io::blake2_256);
+	AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+}
+
+/// The component values to run a benchmark with.
+///
+/// Each component is stepped from its lowest to its highest value in `steps` steps, while all
+/// other components are held at their highest value.
+fn component_values(
+	components: &[(BenchmarkParameter, u32, u32)],
+	steps: u32,
+) -> Vec<Vec<(BenchmarkParameter, u32)>> {
+	if components.is_empty() {
+		return vec![Vec::new()];
+	}
+
+	let steps = steps.max(1);
+	let mut values = Vec::new();
+	for (index, &(_, low, high)) in components.iter().enumerate() {
+		let range = u64::from(high.saturating_sub(low));
+		let mut points: Vec<u32> = (0..=steps)
+			.map(|s| low + (range * u64::from(s) / u64::from(steps)) as u32)
+			.collect();
+		points.dedup();
+
+		for value in points {
+			values.push(components.iter()
+				.enumerate()
+				.map(|(i, &(name, _, high))| (name, if i == index { value } else { high }))
+				.collect()
+			);
+		}
+	}
+	values
+}
+
+/// Run a benchmark.
+///
+/// Used by the implementation of [`Benchmarking`](trait.Benchmarking.html) generated by
+/// [`benchmarks!`](macro.benchmarks.html).
+pub fn run_benchmark<Call, Origin, S>(
+	selected: &S,
+	steps: u32,
+	repeat: u32,
+) -> Result<Vec<BenchmarkResults>, &'static str> where
+	S: BenchmarkingSetup<Call, Origin>,
+	Call: Dispatchable<Origin=Origin>,
+{
+	let mut results = Vec::new();
+	for components in component_values(&selected.components(), steps) {
+		for _ in 0..repeat {
+			let (call, origin) = selected.instance(&components)?;
+			runtime_io::benchmark_commit_db();
+
+			let start = runtime_io::benchmark_current_time();
+			let result = call.dispatch(origin);
+			let elapsed = runtime_io::benchmark_current_time().saturating_sub(start);
+
+			runtime_io::benchmark_wipe_db();
+			result.map_err(|e| {
+				let e: DispatchError = e.into();
+				e.message.unwrap_or("Benchmarked call failed.")
+			})?;
+			results.push((components.clone(), elapsed));
+		}
+	}
+	Ok(results)
+}
+
+/// Declare the benchmarks of a module.
+///
+/// Must be called in a scope where the module's `Trait`, `Module` and `Call` are available. Each
+/// benchmark starts with the components it depends on, declared as `let <letter> in <low> ..
+/// <high>;`, followed by the setup code, which can use the components. It ends with the name of
+/// the dispatchable to measure and its arguments, of which the first one is the origin:
+///
+/// ```ignore
+/// benchmarks! {
+/// 	set_name {
+/// 		let n in 1 .. 16;
+/// 		let caller: T::AccountId = account("caller", 0, 0);
+/// 	}: set_name(RawOrigin::Signed(caller), vec![0; n as usize])
+/// }
+/// ```
+///
+/// This implements [`Benchmarking`](trait.Benchmarking.html) for the module, selecting the
+/// benchmark by the name of the dispatchable.
+#[macro_export]
+macro_rules! benchmarks {
+	(
+		$(
+			$name:ident { $( $code:tt )* }: $dispatch:ident ( $origin:expr $( , $arg:expr )* $(,)? )
+		)*
+	) => {
+		#[allow(non_camel_case_types)]
+		enum SelectedBenchmark {
+			$( $name, )*
+		}
+
+		impl<T: Trait> $crate::BenchmarkingSetup<Call<T>, T::Origin> for SelectedBenchmark {
+			fn components(&self) -> $crate::Vec<($crate::BenchmarkParameter, u32, u32)> {
+				match self {
+					$( SelectedBenchmark::$name => $crate::benchmarks!(@components [] $( $code )*), )*
+				}
+			}
+
+			fn instance(&self, components: &[($crate::BenchmarkParameter, u32)])
+				-> Result<(Call<T>, T::Origin), &'static str>
+			{
+				match self {
+					$(
+						SelectedBenchmark::$name => $crate::benchmarks!(
+							@instance components { $dispatch ( $origin $( , $arg )* ) } [] $( $code )*
+						),
+					)*
+				}
+			}
+		}
+
+		impl<T: Trait> $crate::Benchmarking<$crate::BenchmarkResults> for Module<T> {
+			fn run_benchmark(extrinsic: $crate::Vec<u8>, steps: u32, repeat: u32)
+				-> Result<$crate::Vec<$crate::BenchmarkResults>, &'static str>
+			{
+				let selected = match &extrinsic[..] {
+					$( name if name == stringify!($name).as_bytes() => SelectedBenchmark::$name, )*
+					_ => return Err("Could not find extrinsic."),
+				};
+				$crate::run_benchmark::<Call<T>, T::Origin, _>(&selected, steps, repeat)
+			}
+		}
+	};
+	(@components [ $( $acc:tt )* ] let $param:ident in $low:tt .. $high:expr; $( $rest:tt )*) => {
+		$crate::benchmarks!(
+			@components [ $( $acc )* ($crate::BenchmarkParameter::$param, $low, $high), ] $( $rest )*
+		)
+	};
+	(@components [ $( $acc:tt )* ] $( $rest:tt )*) => {
+		{
+			let components: &[($crate::BenchmarkParameter, u32, u32)] = &[ $( $acc )* ];
+			components.to_vec()
+		}
+	};
+	(
+		@instance $components:ident { $( $call:tt )* } [ $( $params:ident )* ]
+		let $param:ident in $low:tt .. $high:expr; $( $rest:tt )*
+	) => {
+		$crate::benchmarks!(@instance $components { $( $call )* } [ $( $params )* $param ] $( $rest )*)
+	};
+	(
+		@instance $components:ident { $dispatch:ident ( $origin:expr $( , $arg:expr )* ) }
+		[ $( $params:ident )* ] $( $code:tt )*
+	) => {
+		{
+			$(
+				#[allow(unused_variables)]
+				let $params = $components.iter()
+					.find(|c| c.0 == $crate::BenchmarkParameter::$params)
+					.ok_or("Could not find component during benchmark preparation.")?
+					.1;
+			)*
+			$( $code )*
+			Ok((Call::<T>::$dispatch( $( $arg ),* ), $origin.into()))
+		}
+	};
+}
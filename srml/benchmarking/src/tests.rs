@@ -0,0 +1,104 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the benchmarking module.
+
+#![cfg(test)]
+
+use super::*;
+use sr_primitives::traits::DispatchResult;
+use runtime_io::TestExternalities;
+
+struct Call(u32);
+
+impl Dispatchable for Call {
+	type Origin = ();
+	type Trait = ();
+	type Error = &'static str;
+
+	fn dispatch(self, _origin: ()) -> DispatchResult<&'static str> {
+		if self.0 == 0 {
+			return Err("Zero is not allowed.");
+		}
+		runtime_io::set_storage(b":dispatched", &self.0.encode());
+		Ok(())
+	}
+}
+
+struct Setup(Vec<(BenchmarkParameter, u32, u32)>);
+
+impl BenchmarkingSetup<Call, ()> for Setup {
+	fn components(&self) -> Vec<(BenchmarkParameter, u32, u32)> {
+		self.0.clone()
+	}
+
+	fn instance(&self, components: &[(BenchmarkParameter, u32)]) -> Result<(Call, ()), &'static str> {
+		let value = components.iter().map(|c| c.1).sum::<u32>();
+		runtime_io::set_storage(b":setup", &value.encode());
+		Ok((Call(value), ()))
+	}
+}
+
+#[test]
+fn component_values_step_one_component_at_a_time() {
+	use BenchmarkParameter::{a, b};
+
+	assert_eq!(
+		component_values(&[(a, 1, 10), (b, 0, 2)], 2),
+		vec![
+			vec![(a, 1), (b, 2)],
+			vec![(a, 5), (b, 2)],
+			vec![(a, 10), (b, 2)],
+			vec![(a, 10), (b, 0)],
+			vec![(a, 10), (b, 1)],
+			vec![(a, 10), (b, 2)],
+		],
+	);
+	assert_eq!(component_values(&[(a, 3, 3)], 10), vec![vec![(a, 3)]]);
+	assert_eq!(component_values(&[], 10), vec![Vec::<(BenchmarkParameter, u32)>::new()]);
+}
+
+#[test]
+fn run_benchmark_keeps_setup_and_wipes_dispatch() {
+	let mut ext = TestExternalities::default();
+	ext.execute_with(|| {
+		let setup = Setup(vec![(BenchmarkParameter::x, 1, 4)]);
+		let results = run_benchmark(&setup, 3, 2).unwrap();
+
+		let values: Vec<u32> = results.iter().map(|r| (r.0)[0].1).collect();
+		assert_eq!(values, vec![1, 1, 2, 2, 3, 3, 4, 4]);
+		assert_eq!(runtime_io::storage(b":setup"), Some(4u32.encode()));
+		assert_eq!(runtime_io::storage(b":dispatched"), None);
+	});
+}
+
+#[test]
+fn run_benchmark_fails_with_failing_dispatch() {
+	let mut ext = TestExternalities::default();
+	ext.execute_with(|| {
+		let setup = Setup(vec![(BenchmarkParameter::x, 0, 1)]);
+		assert_eq!(run_benchmark(&setup, 1, 1), Err("Zero is not allowed."));
+	});
+}
+
+#[test]
+fn account_is_deterministic() {
+	let first: [u8; 32] = account("caller", 0, 0);
+	assert_eq!(first, account("caller", 0, 0));
+	assert!(first != account::<[u8; 32]>("caller", 1, 0));
+	assert!(first != account::<[u8; 32]>("recipient", 0, 0));
+
+}
@@ -30,7 +30,7 @@ use sr_primitives::traits::{Hash, EnsureOrigin};
 use sr_primitives::weights::SimpleDispatchInfo;
 use support::{
 	dispatch::{Dispatchable, Parameter}, codec::{Encode, Decode},
-	traits::{ChangeMembers, InitializeMembers}, decl_module, decl_event,
+	traits::{ChangeMembers, InitializeMembers, Contains, ContainsCount}, decl_module, decl_event,
 	decl_storage, ensure,
 };
 use system::{self, ensure_signed, ensure_root};
@@ -94,6 +94,9 @@ decl_storage! {
 		pub ProposalCount get(fn proposal_count): u32;
 		/// The current members of the collective. This is stored sorted (just by value).
 		pub Members get(fn members): Vec<T::AccountId>;
+		/// The prime member of the collective, as set by whatever manages its membership. It is
+		/// cleared whenever the membership changes.
+		pub Prime get(fn prime): Option<T::AccountId>;
 	}
 	add_extra_genesis {
 		config(phantom): rstd::marker::PhantomData<I>;
@@ -262,6 +265,18 @@ impl<T: Trait<I>, I: Instance> Module<T, I> {
 	}
 }
 
+impl<T: Trait<I>, I: Instance> Contains<T::AccountId> for Module<T, I> {
+	fn contains(who: &T::AccountId) -> bool {
+		Self::is_member(who)
+	}
+}
+
+impl<T: Trait<I>, I: Instance> ContainsCount<T::AccountId> for Module<T, I> {
+	fn count() -> usize {
+		Self::members().len()
+	}
+}
+
 impl<T: Trait<I>, I: Instance> ChangeMembers<T::AccountId> for Module<T, I> {
 	fn change_members_sorted(_incoming: &[T::AccountId], outgoing: &[T::AccountId], new: &[T::AccountId]) {
 		// remove accounts from all current voting in motions.
@@ -281,6 +296,11 @@ impl<T: Trait<I>, I: Instance> ChangeMembers<T::AccountId> for Module<T, I> {
 			);
 		}
 		<Members<T, I>>::put(new);
+		<Prime<T, I>>::kill();
+	}
+
+	fn set_prime(prime: Option<T::AccountId>) {
+		<Prime<T, I>>::set(prime);
 	}
 }
 
@@ -494,6 +514,16 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn prime_is_cleared_on_membership_change() {
+		make_ext().execute_with(|| {
+			Collective::set_prime(Some(2));
+			assert_eq!(Collective::prime(), Some(2));
+			Collective::change_members_sorted(&[4], &[1], &[2, 3, 4]);
+			assert_eq!(Collective::prime(), None);
+		});
+	}
+
 	#[test]
 	fn removal_of_old_voters_votes_works_with_set_members() {
 		make_ext().execute_with(|| {
@@ -0,0 +1,123 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+//! A mechanism for runtime authors to augment the functionality of contracts.
+//!
+//! A runtime can expose functions of its own to contracts, e.g. to give them access to other
+//! modules, by implementing [`ChainExtension`] and setting it as `Trait::ChainExtension`.
+//! Contracts call into the extension through the `ext_chain_extension` host function, passing the
+//! id of the function to call and an input buffer. The output of the function is placed into the
+//! scratch buffer and its status code is returned to the contract.
+//!
+//! Function ids are part of the interface contracts are compiled against: an extension must never
+//! change the behavior of an existing function id, but introduce a new one instead.
+
+use crate::{Trait, Gas, GasMeter};
+use crate::gas::{Token, GasMeterResult};
+use rstd::prelude::*;
+use codec::Decode;
+
+/// A set of functions a runtime exposes to contracts.
+pub trait ChainExtension<T: Trait> {
+	/// Call the function `func_id` of the extension on behalf of the contract executing in `env`.
+	///
+	/// Returns the status code handed back to the contract. Returning an error traps the
+	/// contract.
+	///
+	/// The function is responsible for charging gas for the work it does, using
+	/// [`Environment::charge_gas`], before doing it.
+	///
+	/// The function may write to the storage directly, bypassing the overlay of the contract.
+	/// Each contract call runs in a storage transaction, so these changes are reverted if the
+	/// calling contract, or any of its callers, fails.
+	fn call(func_id: u32, env: Environment<T>) -> Result<u32, &'static str>;
+}
+
+/// No chain extension. Calling into it traps the contract.
+impl<T: Trait> ChainExtension<T> for () {
+	fn call(_func_id: u32, _env: Environment<T>) -> Result<u32, &'static str> {
+		Err("no chain extension is configured")
+	}
+}
+
+/// The gas charged by a chain extension.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+#[derive(Copy, Clone)]
+struct ChainExtensionToken(Gas);
+
+impl<T: Trait> Token<T> for ChainExtensionToken {
+	type Metadata = ();
+
+	fn calculate_amount(&self, _metadata: &()) -> Gas {
+		self.0
+	}
+}
+
+/// The environment of a contract calling into a chain extension.
+pub struct Environment<'a, T: Trait> {
+	address: &'a T::AccountId,
+	caller: &'a T::AccountId,
+	input: Vec<u8>,
+	output: &'a mut Vec<u8>,
+	gas_meter: &'a mut GasMeter<T>,
+}
+
+impl<'a, T: Trait> Environment<'a, T> {
+	pub(crate) fn new(
+		address: &'a T::AccountId,
+		caller: &'a T::AccountId,
+		input: Vec<u8>,
+		output: &'a mut Vec<u8>,
+		gas_meter: &'a mut GasMeter<T>,
+	) -> Self {
+		Environment { address, caller, input, output, gas_meter }
+	}
+
+	/// The address of the contract calling into the extension.
+	pub fn address(&self) -> &T::AccountId {
+		self.address
+	}
+
+	/// The caller of the contract calling into the extension.
+	pub fn caller(&self) -> &T::AccountId {
+		self.caller
+	}
+
+	/// The input passed by the contract.
+	pub fn input(&self) -> &[u8] {
+		&self.input
+	}
+
+	/// Decode the input passed by the contract as `D`.
+	pub fn read_as<D: Decode>(&self) -> Result<D, &'static str> {
+		D::decode(&mut &self.input[..]).map_err(|_| "chain extension input cannot be decoded")
+	}
+
+	/// Charge the given amount of gas to the contract.
+	///
+	/// Returns an error if the contract doesn't have enough gas left.
+	pub fn charge_gas(&mut self, amount: Gas) -> Result<(), &'static str> {
+		match self.gas_meter.charge(&(), ChainExtensionToken(amount)) {
+			GasMeterResult::Proceed => Ok(()),
+			GasMeterResult::OutOfGas => Err("not enough gas to call the chain extension"),
+		}
+	}
+
+	/// Set the output returned to the contract through the scratch buffer.
+	pub fn write_output(&mut self, data: &[u8]) {
+		self.output.clear();
+		self.output.extend_from_slice(data);
+	}
+}
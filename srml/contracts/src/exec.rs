@@ -22,7 +22,10 @@ use crate::rent;
 
 use rstd::prelude::*;
 use sr_primitives::traits::{Bounded, CheckedAdd, CheckedSub, Zero};
-use support::traits::{WithdrawReason, Currency, Time, Randomness};
+use support::{
+	storage::{with_transaction, TransactionOutcome},
+	traits::{WithdrawReason, Currency, Time, Randomness},
+};
 
 pub type AccountIdOf<T> = <T as system::Trait>::AccountId;
 pub type CallOf<T> = <T as Trait>::Call;
@@ -511,15 +514,24 @@ where
 		}
 	}
 
+	/// Run `func` in a nested context, keeping its changes only if it succeeds.
+	///
+	/// The nested execution runs in a storage transaction, so that the changes made directly to
+	/// the storage, e.g. by the chain extension, are reverted along with the overlay.
 	fn with_nested_context<F>(&mut self, dest: T::AccountId, trie_id: Option<TrieId>, func: F)
 		-> ExecResult
 		where F: FnOnce(&mut ExecutionContext<T, V, L>) -> ExecResult
 	{
-		let (output, change_set, deferred) = {
+		let (output, change_set, deferred) = with_transaction(|| {
 			let mut nested = self.nested(dest, trie_id);
-			let output = func(&mut nested)?;
-			(output, nested.overlay.into_change_set(), nested.deferred)
-		};
+			let result = func(&mut nested)
+				.map(|output| (output, nested.overlay.into_change_set(), nested.deferred));
+			if result.as_ref().map_or(false, |(output, _, _)| output.is_success()) {
+				TransactionOutcome::Commit(result)
+			} else {
+				TransactionOutcome::Rollback(result)
+			}
+		})?;
 
 		if output.is_success() {
 			self.overlay.commit(change_set);
@@ -1233,6 +1245,36 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn storage_changes_are_reverted_on_failure() {
+		// Changes made directly to the storage, e.g. by the chain extension, are kept only if
+		// the contract succeeds.
+		let vm = MockVm::new();
+		let mut loader = MockLoader::empty();
+		let failing_ch = loader.insert(|_| {
+			support::storage::unhashed::put(b":failing", &true);
+			Ok(ExecReturnValue { status: 1, data: Vec::new() })
+		});
+		let success_ch = loader.insert(|_| {
+			support::storage::unhashed::put(b":success", &true);
+			exec_success()
+		});
+
+		ExtBuilder::default().build().execute_with(|| {
+			let cfg = Config::preload();
+			let mut ctx = ExecutionContext::top_level(ALICE, &cfg, &vm, &loader);
+			ctx.overlay.instantiate_contract(&BOB, failing_ch).unwrap();
+			ctx.overlay.instantiate_contract(&CHARLIE, success_ch).unwrap();
+
+			let mut gas_meter = GasMeter::<Test>::with_limit(10000, 1);
+			assert!(!ctx.call(BOB, 0, &mut gas_meter, vec![]).unwrap().is_success());
+			assert!(ctx.call(CHARLIE, 0, &mut gas_meter, vec![]).unwrap().is_success());
+
+			assert_eq!(support::storage::unhashed::get::<bool>(b":failing"), None);
+			assert_eq!(support::storage::unhashed::get::<bool>(b":success"), Some(true));
+		});
+	}
+
 	#[test]
 	fn input_data_to_call() {
 		let vm = MockVm::new();
@@ -96,6 +96,8 @@ mod exec;
 mod wasm;
 mod rent;
 
+pub mod chain_extension;
+
 #[cfg(test)]
 mod tests;
 
@@ -416,6 +418,10 @@ pub trait Trait: system::Trait {
 
 	/// The maximum amount of gas that could be expended per block.
 	type BlockGasLimit: Get<Gas>;
+
+	/// Functions the runtime exposes to contracts through `ext_chain_extension`. Use `()` if
+	/// there are none.
+	type ChainExtension: chain_extension::ChainExtension<Self>;
 }
 
 /// Simple contract address determiner.
@@ -23,6 +23,7 @@ use crate::{
 	BalanceOf, ComputeDispatchFee, ContractAddressFor, ContractInfo, ContractInfoOf, GenesisConfig,
 	Module, RawAliveContractInfo, RawEvent, Trait, TrieId, TrieIdFromParentCounter, Schedule,
 	TrieIdGenerator, CheckBlockGasLimit, account_db::{AccountDb, DirectAccountDb, OverlayAccountDb},
+	chain_extension::{ChainExtension, Environment},
 };
 use assert_matches::assert_matches;
 use hex_literal::*;
@@ -176,6 +177,7 @@ impl Trait for Test {
 	type MaxDepth = MaxDepth;
 	type MaxValueSize = MaxValueSize;
 	type BlockGasLimit = BlockGasLimit;
+	type ChainExtension = TestExtension;
 }
 
 type Balances = balances::Module<Test>;
@@ -211,6 +213,24 @@ impl TrieIdGenerator<u64> for DummyTrieIdGenerator {
 	}
 }
 
+/// A chain extension whose only function, `0`, outputs the caller followed by the input and
+/// returns the length of the input.
+pub struct TestExtension;
+impl ChainExtension<Test> for TestExtension {
+	fn call(func_id: u32, mut env: Environment<Test>) -> Result<u32, &'static str> {
+		match func_id {
+			0 => {
+				env.charge_gas(100)?;
+				let mut output = env.caller().encode();
+				output.extend_from_slice(env.input());
+				env.write_output(&output);
+				Ok(env.input().len() as u32)
+			}
+			_ => Err("unknown chain extension function"),
+		}
+	}
+}
+
 pub struct DummyComputeDispatchFee;
 impl ComputeDispatchFee<Call, u64> for DummyComputeDispatchFee {
 	fn compute_dispatch_fee(call: &Call) -> u64 {
@@ -273,7 +293,6 @@ impl ExtBuilder {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test> {
 			balances: vec![],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		GenesisConfig::<Test> {
 			current_schedule: Schedule {
@@ -1634,4 +1634,90 @@ mod tests {
 		assert_eq!(output, ExecReturnValue { status: 17, data: hex!("5566778899").to_vec() });
 		assert!(!output.is_success());
 	}
+
+	const CODE_CHAIN_EXTENSION: &str = r#"
+(module
+	(import "env" "ext_chain_extension" (func $ext_chain_extension (param i32 i32 i32) (result i32)))
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	(func (export "call")
+		;; Call the function 0 of the extension with the input "\01\02\03".
+		;; The test extension returns the length of the input.
+		(call $assert
+			(i32.eq
+				(call $ext_chain_extension
+					(i32.const 0)	;; Function id.
+					(i32.const 8)	;; Pointer to the input.
+					(i32.const 3)	;; Length of the input.
+				)
+				(i32.const 3)
+			)
+		)
+
+		;; The output is the caller (8 bytes) followed by the input.
+		(call $assert
+			(i32.eq
+				(call $ext_scratch_size)
+				(i32.const 11)
+			)
+		)
+
+		;; Return the output.
+		(call $ext_scratch_read
+			(i32.const 16)		;; Pointer in memory to the place where to copy.
+			(i32.const 0)		;; Offset from the start of the scratch buffer.
+			(i32.const 11)		;; Count of bytes to copy.
+		)
+		(call $ext_return
+			(i32.const 16)
+			(i32.const 11)
+		)
+	)
+
+	(func (export "deploy"))
+
+	(data (i32.const 8) "\01\02\03")
+)
+"#;
+
+	#[test]
+	fn chain_extension() {
+		let mut gas_meter = GasMeter::with_limit(50_000, 1);
+		let output = execute(
+			CODE_CHAIN_EXTENSION,
+			vec![],
+			MockExt::default(),
+			&mut gas_meter,
+		).unwrap();
+
+		let mut expected = 42u64.encode();
+		expected.extend_from_slice(&[1, 2, 3]);
+		assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: expected });
+	}
+
+	#[test]
+	fn chain_extension_traps_when_out_of_gas() {
+		// The gas limit can't cover the execution on top of the 100 gas charged by the test
+		// extension.
+		let output = execute(
+			CODE_CHAIN_EXTENSION,
+			vec![],
+			MockExt::default(),
+			&mut GasMeter::with_limit(100, 1),
+		);
+
+		assert_matches!(output, Err(ExecError { reason: "during execution", buffer: _ }));
+	}
 }
@@ -17,6 +17,7 @@
 //! Environment definition of the wasm smart-contract runtime.
 
 use crate::{Schedule, Trait, CodeHash, ComputeDispatchFee, BalanceOf};
+use crate::chain_extension::{ChainExtension, Environment};
 use crate::exec::{
 	Ext, ExecResult, ExecError, ExecReturnValue, StorageKey, TopicOf, STATUS_SUCCESS,
 };
@@ -835,6 +836,30 @@ define_env!(Env, <E: Ext>,
 		ctx.ext.block_number().encode_to(&mut ctx.scratch_buf);
 		Ok(())
 	},
+
+	// Call a function of the chain extension configured by the runtime.
+	//
+	// - func_id: the id of the function of the extension to call.
+	// - input_ptr: a pointer to the buffer passed as input to the function.
+	// - input_len: length of the input buffer.
+	//
+	// The output of the function is stored into the scratch buffer, and its status code is
+	// returned. Traps if the function fails, e.g. because it ran out of gas or because no chain
+	// extension is configured.
+	ext_chain_extension(ctx, func_id: u32, input_ptr: u32, input_len: u32) -> u32 => {
+		let input = read_sandbox_memory(ctx, input_ptr, input_len)?;
+		ctx.scratch_buf.clear();
+
+		let env = Environment::new(
+			ctx.ext.address(),
+			ctx.ext.caller(),
+			input,
+			&mut ctx.scratch_buf,
+			&mut *ctx.gas_meter,
+		);
+		<<E as Ext>::T as Trait>::ChainExtension::call(func_id, env)
+			.map_err(|_| sandbox::HostError)
+	},
 );
 
 /// Finds duplicates in a given vector.
@@ -22,7 +22,7 @@ use rstd::prelude::*;
 use rstd::{result, convert::TryFrom};
 use sr_primitives::{
 	RuntimeDebug,
-	traits::{Zero, Bounded, CheckedMul, CheckedDiv, EnsureOrigin, Hash, Dispatchable},
+	traits::{Zero, One, Bounded, CheckedMul, CheckedDiv, EnsureOrigin, Hash, Dispatchable},
 	weights::SimpleDispatchInfo,
 };
 use codec::{Ref, Encode, Decode, Input, Output, Error};
@@ -31,7 +31,7 @@ use support::{
 	Parameter,
 	traits::{
 		Currency, ReservableCurrency, LockableCurrency, WithdrawReason, LockIdentifier, Get,
-		OnFreeBalanceZero
+		OnFreeBalanceZero, schedule,
 	}
 };
 use support::dispatch::Result;
@@ -42,6 +42,9 @@ pub use vote_threshold::{Approved, VoteThreshold};
 
 const DEMOCRACY_ID: LockIdentifier = *b"democrac";
 
+/// The number of blocks of the deprecated `DispatchQueue` moved to the scheduler in each block.
+const DISPATCH_QUEUE_MIGRATION_STEP: u32 = 256;
+
 /// A proposal index.
 pub type PropIndex = u32;
 
@@ -176,7 +179,7 @@ impl Decode for Vote {
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 pub trait Trait: system::Trait + Sized {
-	type Proposal: Parameter + Dispatchable<Origin=Self::Origin>;
+	type Proposal: Parameter + Dispatchable<Origin=Self::Origin> + From<Call<Self>>;
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
 	/// Currency type for this module.
@@ -227,6 +230,9 @@ pub trait Trait: system::Trait + Sized {
 
 	/// Period in blocks where an external proposal may not be re-submitted after being vetoed.
 	type CooloffPeriod: Get<Self::BlockNumber>;
+
+	/// The scheduler through which the enactment of approved proposals is delayed.
+	type Scheduler: schedule::Named<Self::BlockNumber, Self::Proposal>;
 }
 
 /// Info regarding an ongoing referendum.
@@ -270,9 +276,16 @@ decl_storage! {
 		/// Information concerning any given referendum.
 		pub ReferendumInfoOf get(fn referendum_info):
 			map ReferendumIndex => Option<(ReferendumInfo<T::BlockNumber, T::Proposal>)>;
-		/// Queue of successful referenda to be dispatched.
-		pub DispatchQueue get(fn dispatch_queue):
-			map T::BlockNumber => Vec<Option<(T::Proposal, ReferendumIndex)>>;
+		/// Deprecated, the queue of successful referenda to be dispatched, from before their
+		/// enactment was scheduled through `T::Scheduler`.
+		///
+		/// It can't be iterated, so it's moved to the scheduler a few blocks at a time from the
+		/// upgrade up to `EnactmentPeriod` blocks later. Any proposal left in it is still enacted.
+		pub DispatchQueue: map T::BlockNumber => Vec<Option<(T::Proposal, ReferendumIndex)>>;
+		/// The next block of `DispatchQueue` to move to the scheduler, and the last one.
+		pub DispatchQueueMigration: Option<(T::BlockNumber, T::BlockNumber)>;
+		/// True once the migration of `DispatchQueue` started.
+		pub DidMigrate: bool;
 
 		/// Get the voters for the current proposal.
 		pub VotersFor get(fn voters_for): map ReferendumIndex => Vec<T::AccountId>;
@@ -542,24 +555,26 @@ decl_module! {
 
 		/// Cancel a proposal queued for enactment.
 		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
-		fn cancel_queued(
-			origin,
-			#[compact] when: T::BlockNumber,
-			#[compact] which: u32,
-			#[compact] what: ReferendumIndex
-		) {
+		fn cancel_queued(origin, #[compact] which: ReferendumIndex) {
 			ensure_root(origin)?;
-			let which = which as usize;
-			let mut items = <DispatchQueue<T>>::get(when);
-			if items.get(which).and_then(Option::as_ref).map_or(false, |x| x.1 == what) {
-				items[which] = None;
-				<DispatchQueue<T>>::insert(when, items);
-			} else {
-				Err("proposal not found")?
-			}
+			T::Scheduler::cancel_named((DEMOCRACY_ID, which).encode())
+				.map_err(|_| "proposal not found")?;
+		}
+
+		/// Enact a proposal from a referendum. For now we just make the weight be the maximum.
+		#[weight = SimpleDispatchInfo::MaxOperational]
+		fn enact_proposal(origin, proposal: Box<T::Proposal>, index: ReferendumIndex) {
+			ensure_root(origin)?;
+			Self::do_enact_proposal(*proposal, index);
 		}
 
 		fn on_initialize(n: T::BlockNumber) {
+			if !DidMigrate::exists() {
+				DidMigrate::put(true);
+				<DispatchQueueMigration<T>>::put((n + One::one(), n + T::EnactmentPeriod::get()));
+			}
+			Self::migrate_dispatch_queue();
+
 			if let Err(e) = Self::end_block(n) {
 				sr_primitives::print(e);
 			}
@@ -827,7 +842,7 @@ impl<T: Trait> Module<T> {
 	}
 
 	/// Enact a proposal from a referendum.
-	fn enact_proposal(proposal: T::Proposal, index: ReferendumIndex) {
+	fn do_enact_proposal(proposal: T::Proposal, index: ReferendumIndex) {
 		let ok = proposal.dispatch(system::RawOrigin::Root.into()).is_ok();
 		Self::deposit_event(RawEvent::Executed(index, ok));
 	}
@@ -924,12 +939,15 @@ impl<T: Trait> Module<T> {
 		if approved {
 			Self::deposit_event(RawEvent::Passed(index));
 			if info.delay.is_zero() {
-				Self::enact_proposal(info.proposal, index);
-			} else {
-				<DispatchQueue<T>>::append_or_insert(
-					now + info.delay,
-					&[Some((info.proposal, index))][..]
-				);
+				Self::do_enact_proposal(info.proposal, index);
+			} else if T::Scheduler::schedule_named(
+				(DEMOCRACY_ID, index).encode(),
+				now + info.delay,
+				None,
+				schedule::HARD_DEADLINE,
+				Call::enact_proposal(Box::new(info.proposal), index).into(),
+			).is_err() {
+				sr_primitives::print("LOGIC ERROR: bake_referendum/schedule_named failed");
 			}
 		} else {
 			Self::deposit_event(RawEvent::NotPassed(index));
@@ -954,11 +972,46 @@ impl<T: Trait> Module<T> {
 			Self::bake_referendum(now, index, info)?;
 		}
 
+		// enact the proposals of the deprecated queue which weren't moved to the scheduler.
 		for (proposal, index) in <DispatchQueue<T>>::take(now).into_iter().filter_map(|x| x) {
-			Self::enact_proposal(proposal, index);
+			Self::do_enact_proposal(proposal, index);
 		}
 		Ok(())
 	}
+
+	/// Move the next `DISPATCH_QUEUE_MIGRATION_STEP` blocks of `DispatchQueue` to the scheduler.
+	///
+	/// The migration starts at the block following the upgrade, and moves more than one block
+	/// per block, so the queue of the current block is never moved.
+	fn migrate_dispatch_queue() {
+		let (mut next, last) = match <DispatchQueueMigration<T>>::get() {
+			Some(migration) => migration,
+			None => return,
+		};
+		let end = last.min(next + DISPATCH_QUEUE_MIGRATION_STEP.into());
+
+		while next <= end {
+			for (proposal, index) in <DispatchQueue<T>>::take(next).into_iter().filter_map(|x| x) {
+				if T::Scheduler::schedule_named(
+					(DEMOCRACY_ID, index).encode(),
+					next,
+					None,
+					schedule::HARD_DEADLINE,
+					Call::enact_proposal(Box::new(proposal.clone()), index).into(),
+				).is_err() {
+					// keep it in the deprecated queue, it's enacted from there.
+					<DispatchQueue<T>>::append_or_insert(next, &[Some((proposal, index))][..]);
+				}
+			}
+			next += One::one();
+		}
+
+		if next > last {
+			<DispatchQueueMigration<T>>::kill();
+		} else {
+			<DispatchQueueMigration<T>>::put((next, last));
+		}
+	}
 }
 
 impl<T: Trait> OnFreeBalanceZero<T::AccountId> for Module<T> {
@@ -972,10 +1025,12 @@ mod tests {
 	use super::*;
 	use support::{
 		impl_outer_origin, impl_outer_dispatch, assert_noop, assert_ok, parameter_types,
-		traits::Contains
+		StorageMap, StorageValue, traits::Contains
 	};
 	use primitives::H256;
-	use sr_primitives::{traits::{BlakeTwo256, IdentityLookup, Bounded}, testing::Header, Perbill};
+	use sr_primitives::{
+		traits::{BlakeTwo256, IdentityLookup, Bounded, OnInitialize}, testing::Header, Perbill,
+	};
 	use balances::BalanceLock;
 	use system::EnsureSignedBy;
 
@@ -992,6 +1047,7 @@ mod tests {
 		pub enum Call for Test where origin: Origin {
 			balances::Balances,
 			democracy::Democracy,
+			scheduler::Scheduler,
 		}
 	}
 
@@ -1037,6 +1093,14 @@ mod tests {
 		type TransferFee = TransferFee;
 		type CreationFee = CreationFee;
 	}
+	parameter_types! {
+		pub const MaximumWeight: u32 = 1_000_000;
+	}
+	impl scheduler::Trait for Test {
+		type Event = ();
+		type Call = Call;
+		type MaximumWeight = MaximumWeight;
+	}
 	parameter_types! {
 		pub const LaunchPeriod: u64 = 2;
 		pub const VotingPeriod: u64 = 2;
@@ -1072,13 +1136,13 @@ mod tests {
 		type CancellationOrigin = EnsureSignedBy<Four, u64>;
 		type VetoOrigin = EnsureSignedBy<OneToFive, u64>;
 		type CooloffPeriod = CooloffPeriod;
+		type Scheduler = Scheduler;
 	}
 
 	fn new_test_ext() -> runtime_io::TestExternalities {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test>{
 			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		GenesisConfig::default().assimilate_storage(&mut t).unwrap();
 		runtime_io::TestExternalities::new(t)
@@ -1086,6 +1150,7 @@ mod tests {
 
 	type System = system::Module<Test>;
 	type Balances = balances::Module<Test>;
+	type Scheduler = scheduler::Module<Test>;
 	type Democracy = Module<Test>;
 
 	#[test]
@@ -1110,6 +1175,7 @@ mod tests {
 	}
 
 	fn next_block() {
+		Scheduler::on_initialize(System::block_number());
 		assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 		System::set_block_number(System::block_number() + 1);
 	}
@@ -1490,9 +1556,7 @@ mod tests {
 			fast_forward_to(3);
 
 			assert!(Democracy::referendum_info(0).is_none());
-			assert_eq!(Democracy::dispatch_queue(4), vec![
-				Some((set_balance_proposal(2), 0))
-			]);
+			assert_eq!(Scheduler::agenda(4).len(), 1);
 
 			// referendum passes and wait another two blocks for enactment.
 			fast_forward_to(5);
@@ -1514,14 +1578,40 @@ mod tests {
 
 			fast_forward_to(3);
 
-			assert_eq!(Democracy::dispatch_queue(4), vec![
-				Some((set_balance_proposal(2), 0))
-			]);
+			assert!(Scheduler::agenda(4)[0].is_some());
+
+			assert_noop!(Democracy::cancel_queued(Origin::ROOT, 1), "proposal not found");
+			assert_ok!(Democracy::cancel_queued(Origin::ROOT, 0));
+			assert!(Scheduler::agenda(4)[0].is_none());
+
+			fast_forward_to(5);
+			assert_eq!(Balances::free_balance(&42), 0);
+		});
+	}
+
+	#[test]
+	fn dispatch_queue_should_be_migrated_to_the_scheduler() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			<DispatchQueue<Test>>::insert(2, vec![Some((set_balance_proposal(2), 0))]);
+			// past `EnactmentPeriod` from the upgrade, not migrated.
+			<DispatchQueue<Test>>::insert(10, vec![Some((set_balance_proposal(3), 1))]);
+
+			Democracy::on_initialize(1);
+			assert!(<DispatchQueue<Test>>::get(2).is_empty());
+			assert_eq!(Scheduler::agenda(2).len(), 1);
+			assert_eq!(<DispatchQueue<Test>>::get(10).len(), 1);
+			assert_eq!(<DispatchQueueMigration<Test>>::get(), None);
+
+			// migrated proposals can be cancelled.
+			assert_ok!(Democracy::cancel_queued(Origin::ROOT, 0));
+			fast_forward_to(3);
+			assert_eq!(Balances::free_balance(&42), 0);
 
-			assert_noop!(Democracy::cancel_queued(Origin::ROOT, 3, 0, 0), "proposal not found");
-			assert_noop!(Democracy::cancel_queued(Origin::ROOT, 4, 1, 0), "proposal not found");
-			assert_ok!(Democracy::cancel_queued(Origin::ROOT, 4, 0, 0));
-			assert_eq!(Democracy::dispatch_queue(4), vec![None]);
+			// the proposals left in the queue are still enacted.
+			fast_forward_to(11);
+			assert_eq!(Balances::free_balance(&42), 3);
+			assert!(<DispatchQueue<Test>>::get(10).is_empty());
 		});
 	}
 
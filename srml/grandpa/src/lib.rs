@@ -19,8 +19,9 @@
 //! This manages the GRANDPA authority set ready for the native code.
 //! These authorities are only for GRANDPA finality, not for consensus overall.
 //!
-//! In the future, it will also handle misbehavior reports, and on-chain
-//! finality notifications.
+//! It also checks the reports of equivocating voters, which it forwards to the
+//! offences handler. In the future, it will also handle on-chain finality
+//! notifications.
 //!
 //! For full integration with GRANDPA, the `GrandpaApi` should be implemented.
 //! The necessary items are re-exported via the `fg_primitives` crate.
@@ -30,21 +31,30 @@
 // re-export since this is necessary for `impl_apis` in runtime.
 pub use substrate_finality_grandpa_primitives as fg_primitives;
 
-use rstd::prelude::*;
+use rstd::{result, prelude::*};
 use codec::{self as codec, Encode, Decode, Error};
-use support::{decl_event, decl_storage, decl_module, dispatch::Result, storage};
+use support::{
+	decl_event, decl_storage, decl_module, dispatch::Result, storage, Parameter,
+	traits::{KeyOwnerProofSystem, GetSessionNumber, GetValidatorCount},
+};
 use sr_primitives::{
-	generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, Perbill,
+	generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, KeyTypeId, Perbill, RuntimeAppPublic,
+};
+use sr_primitives::transaction_validity::{
+	TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority, TransactionLongevity,
 };
 use sr_staking_primitives::{
 	SessionIndex,
-	offence::{Offence, Kind},
+	offence::{Offence, Kind, ReportOffence},
 };
 use fg_primitives::{
 	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId, RoundNumber,
+	OpaqueKeyOwnershipProof,
 };
-pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
-use system::{ensure_signed, DigestOf};
+pub use fg_primitives::{
+	AuthorityId, AuthorityList, AuthorityWeight, EquivocationProof, VersionedAuthorityList,
+};
+use system::{ensure_none, ensure_signed, DigestOf};
 
 mod mock;
 mod tests;
@@ -52,6 +62,28 @@ mod tests;
 pub trait Trait: system::Trait {
 	/// The event type of this module.
 	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// The system proving that a key was owned by a validator of a given session,
+	/// which is needed to check the equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The proof of key ownership of `KeyOwnerProofSystem`.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The full identification of a key owner, as given by `KeyOwnerProofSystem`.
+	type KeyOwnerIdentification: Parameter;
+
+	/// Where the offences of the equivocating voters are reported, e.g.
+	/// `srml-offences`.
+	type ReportEquivocation: ReportOffence<
+		Self::AccountId,
+		Self::KeyOwnerIdentification,
+		GrandpaEquivocationOffence<Self::KeyOwnerIdentification>,
+	>;
 }
 
 /// A stored pending change, old format.
@@ -178,6 +210,21 @@ decl_module! {
 			// FIXME: https://github.com/paritytech/substrate/issues/1112
 		}
 
+		/// Report a voter for casting two different votes at the same stage of a round.
+		///
+		/// The report is an unsigned extrinsic, checked in `validate_unsigned`, which
+		/// block authors create from the equivocations observed by their node.
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+			key_owner_proof: T::KeyOwnerProof
+		) {
+			ensure_none(origin)?;
+
+			let offence = Self::check_equivocation_report(equivocation_proof, key_owner_proof)?;
+			T::ReportEquivocation::report_offence(vec![], offence);
+		}
+
 		fn on_initialize() {
 			#[cfg(feature = "migrate-authorities")]
 			Self::migrate_authorities();
@@ -340,6 +387,66 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Generate a proof that the given authority is part of the validator set of
+	/// the given authority set, to report its equivocations with
+	/// `equivocation_report_call`.
+	///
+	/// Only the ownership of the keys of the current session, and thus of the
+	/// current authority set, can be proved.
+	pub fn generate_key_ownership_proof(
+		set_id: SetId,
+		authority_id: AuthorityId,
+	) -> Option<OpaqueKeyOwnershipProof> {
+		if set_id != Self::current_set_id() {
+			return None;
+		}
+
+		T::KeyOwnerProofSystem::prove((<AuthorityId as RuntimeAppPublic>::ID, authority_id))
+			.map(|proof| OpaqueKeyOwnershipProof::new(proof.encode()))
+	}
+
+	/// Create the call reporting the given equivocation, with a proof of key
+	/// ownership of the offender generated by `generate_key_ownership_proof`.
+	///
+	/// Returns `None` if the proof of key ownership can't be decoded.
+	pub fn equivocation_report_call(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: OpaqueKeyOwnershipProof,
+	) -> Option<Call<T>> {
+		key_owner_proof.decode().map(|key_owner_proof|
+			Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof)
+		)
+	}
+
+	/// Check an equivocation report, returning the offence it proves.
+	fn check_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> result::Result<GrandpaEquivocationOffence<T::KeyOwnerIdentification>, &'static str> {
+		let session_index = key_owner_proof.session();
+		let validator_set_count = key_owner_proof.validator_count();
+		let time_slot = GrandpaTimeSlot {
+			set_id: equivocation_proof.set_id,
+			round: equivocation_proof.round,
+		};
+
+		// the key ownership must be proved in the session of the authority set.
+		if Self::session_for_set(time_slot.set_id) != Some(session_index) {
+			return Err("Invalid key ownership proof session")
+		}
+
+		if !fg_primitives::check_equivocation_proof(&equivocation_proof) {
+			return Err("Invalid equivocation proof")
+		}
+
+		let offender = T::KeyOwnerProofSystem::check_proof(
+			(<AuthorityId as RuntimeAppPublic>::ID, equivocation_proof.offender),
+			key_owner_proof,
+		).ok_or("Invalid key ownership proof")?;
+
+		Ok(GrandpaEquivocationOffence { time_slot, session_index, validator_set_count, offender })
+	}
+
 	/// Deposit one of this module's logs.
 	fn deposit_log(log: ConsensusLog<T::BlockNumber>) {
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode());
@@ -456,18 +563,50 @@ impl<T: Trait> finality_tracker::OnFinalizationStalled<T::BlockNumber> for Modul
 	}
 }
 
+#[allow(deprecated)]
+impl<T: Trait> support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof) = call {
+			let tag = (
+				&equivocation_proof.offender,
+				equivocation_proof.set_id,
+				equivocation_proof.round,
+				equivocation_proof.stage,
+			).encode();
+
+			if Self::check_equivocation_report(equivocation_proof.clone(), key_owner_proof.clone()).is_err() {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				// a single report per offender and stage of a round.
+				provides: vec![tag],
+				longevity: TransactionLongevity::max_value(),
+				// the reports are only included by the authors which observed the equivocation.
+				propagate: false,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
 /// A round number and set id which point on the time of an offence.
 #[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
-struct GrandpaTimeSlot {
+pub struct GrandpaTimeSlot {
 	// The order of these matters for `derive(Ord)`.
 	set_id: SetId,
 	round: RoundNumber,
 }
 
-// TODO [slashing]: Integrate this.
 /// A grandpa equivocation offence report.
-#[allow(dead_code)]
-struct GrandpaEquivocationOffence<FullIdentification> {
+///
+/// When a voter cast two different votes at the same stage of a round.
+pub struct GrandpaEquivocationOffence<FullIdentification> {
 	/// Time slot at which this incident happened.
 	time_slot: GrandpaTimeSlot,
 	/// The session index in which the incident happened.
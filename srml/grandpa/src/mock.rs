@@ -40,6 +40,10 @@ pub struct Test;
 
 impl Trait for Test {
 	type Event = TestEvent;
+	type KeyOwnerProofSystem = ();
+	type KeyOwnerProof = support::Void;
+	type KeyOwnerIdentification = ();
+	type ReportEquivocation = ();
 }
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
@@ -309,6 +309,40 @@ fn time_slot_have_sane_ord() {
 	assert!(FIXTURE.windows(2).all(|f| f[0] < f[1]));
 }
 
+#[test]
+fn equivocation_proofs_are_checked() {
+	use fg_primitives::{AuthorityPair, SignedVote, VoteStage};
+	use primitives::{crypto::Pair, H256};
+
+	let pair = AuthorityPair::from_seed(&[1; 32]);
+	let vote = |target_hash: H256, target_number, signer: &AuthorityPair| {
+		let payload = (VoteStage::Prevote, target_hash, target_number, 3u64, 1u64).encode();
+		SignedVote { target_hash, target_number, signature: signer.sign(&payload) }
+	};
+	let proof = |first, second| EquivocationProof {
+		set_id: 1,
+		round: 3,
+		stage: VoteStage::Prevote,
+		offender: pair.public(),
+		first,
+		second,
+	};
+
+	let first = vote(H256::repeat_byte(1), 10u64, &pair);
+	assert!(fg_primitives::check_equivocation_proof(&proof(first.clone(), vote(H256::repeat_byte(2), 10, &pair))));
+	assert!(fg_primitives::check_equivocation_proof(&proof(first.clone(), vote(H256::repeat_byte(1), 11, &pair))));
+
+	// the same vote twice isn't an equivocation.
+	assert!(!fg_primitives::check_equivocation_proof(&proof(first.clone(), first.clone())));
+	// neither are votes signed by someone else.
+	let other = AuthorityPair::from_seed(&[2; 32]);
+	assert!(!fg_primitives::check_equivocation_proof(&proof(first.clone(), vote(H256::repeat_byte(2), 10, &other))));
+	// nor votes cast at another stage.
+	let mut precommits = proof(first, vote(H256::repeat_byte(2), 10, &pair));
+	precommits.stage = VoteStage::Precommit;
+	assert!(!fg_primitives::check_equivocation_proof(&precommits));
+}
+
 #[test]
 #[cfg(feature = "migrate-authorities")]
 fn authorities_migration() {
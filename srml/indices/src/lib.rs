@@ -14,16 +14,42 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-//! An index is a short form of an address. This module handles allocation
-//! of indices for a newly created accounts.
+//! # Indices Module
+//!
+//! An index is a short form of an address. This module lets accounts claim indices, so that
+//! users can share a short numeric address instead of a full account id.
+//!
+//! ## Overview
+//!
+//! An index is claimed by reserving a deposit of `Deposit` in the claiming account, which is
+//! returned when the index is freed. The holder of an index may transfer it to another account,
+//! or freeze it: a frozen index is permanently assigned to its holder, and its deposit is lost.
+//!
+//! ### Reclamation
+//!
+//! An index which is not frozen and whose holder is a dead account can be reclaimed by anyone,
+//! who then pays the deposit for it. This keeps indices from being locked forever by accounts
+//! which no longer exist.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `claim` - Claim a free index, reserving the deposit.
+//! * `transfer` - Transfer an index to another account, along with its deposit.
+//! * `free` - Release an index; the deposit is returned.
+//! * `force_transfer` - Assign an index to an account, with no deposit.
+//! * `freeze` - Permanently assign an index to its holder; the deposit is lost.
+//! * `reclaim` - Claim an index held by a dead account.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use rstd::{prelude::*, marker::PhantomData, convert::TryInto};
-use codec::{Encode, Codec};
-use support::{Parameter, decl_module, decl_event, decl_storage};
-use sr_primitives::traits::{One, SimpleArithmetic, StaticLookup, Member, LookupError};
-use system::{IsDeadAccount, OnNewAccount};
+use rstd::prelude::*;
+use codec::Codec;
+use support::{Parameter, decl_module, decl_event, decl_storage, ensure};
+use support::traits::{Currency, ReservableCurrency, Get};
+use sr_primitives::traits::{SimpleArithmetic, StaticLookup, Member, LookupError, Zero, One};
+use system::{IsDeadAccount, ensure_signed, ensure_root};
 
 use self::address::Address as RawAddress;
 
@@ -32,28 +58,12 @@ mod mock;
 pub mod address;
 mod tests;
 
-/// Number of account IDs stored per enum set.
+/// Number of account IDs stored per enum set, before the indices could be claimed.
 const ENUM_SET_SIZE: u32 = 64;
 
 pub type Address<T> = RawAddress<<T as system::Trait>::AccountId, <T as Trait>::AccountIndex>;
 
-/// Turn an Id into an Index, or None for the purpose of getting
-/// a hint at a possibly desired index.
-pub trait ResolveHint<AccountId, AccountIndex> {
-	/// Turn an Id into an Index, or None for the purpose of getting
-	/// a hint at a possibly desired index.
-	fn resolve_hint(who: &AccountId) -> Option<AccountIndex>;
-}
-
-/// Simple encode-based resolve hint implementation.
-pub struct SimpleResolveHint<AccountId, AccountIndex>(PhantomData<(AccountId, AccountIndex)>);
-impl<AccountId: Encode, AccountIndex: From<u32>>
-	ResolveHint<AccountId, AccountIndex> for SimpleResolveHint<AccountId, AccountIndex>
-{
-	fn resolve_hint(who: &AccountId) -> Option<AccountIndex> {
-		Some(AccountIndex::from(who.using_encoded(|e| e[0] as u32 + e[1] as u32 * 256)))
-	}
-}
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 /// The module's config trait.
 pub trait Trait: system::Trait {
@@ -61,12 +71,15 @@ pub trait Trait: system::Trait {
 	/// can hold.
 	type AccountIndex: Parameter + Member + Codec + Default + SimpleArithmetic + Copy;
 
+	/// The currency in which the deposits are reserved.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit needed to claim an index.
+	type Deposit: Get<BalanceOf<Self>>;
+
 	/// Whether an account is dead or not.
 	type IsDeadAccount: IsDeadAccount<Self::AccountId>;
 
-	/// How to turn an id into an index.
-	type ResolveHint: ResolveHint<Self::AccountId, Self::AccountIndex>;
-
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -74,6 +87,157 @@ pub trait Trait: system::Trait {
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
+
+		/// The deposit needed to claim an index.
+		const Deposit: BalanceOf<T> = T::Deposit::get();
+
+		fn on_initialize() {
+			if !DidMigrate::exists() {
+				DidMigrate::put(true);
+				Self::do_migrate();
+			}
+		}
+
+		/// Claim a free index, reserving a deposit of `Deposit`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		fn claim(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(!<Accounts<T>>::exists(index), "Index already claimed");
+			let deposit = T::Deposit::get();
+			T::Currency::reserve(&who, deposit)?;
+			<Accounts<T>>::insert(index, (who.clone(), deposit, false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(who, index));
+		}
+
+		/// Transfer an index held by the sender to another account, along with its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - At most two balance operations.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		fn transfer(origin, new: <T::Lookup as StaticLookup>::Source, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+			let new = T::Lookup::lookup(new)?;
+			ensure!(who != new, "Cannot transfer an index to its holder");
+
+			let (holder, deposit, frozen) = <Accounts<T>>::get(index).ok_or("Index not claimed")?;
+			ensure!(holder == who, "Not the holder of the index");
+			ensure!(!frozen, "Index is frozen");
+
+			let remaining = T::Currency::repatriate_reserved(&who, &new, deposit)?;
+			let moved = deposit - remaining;
+			// the deposit only stays reserved if the new holder is able to reserve it.
+			let deposit = if T::Currency::reserve(&new, moved).is_ok() { moved } else { Zero::zero() };
+			<Accounts<T>>::insert(index, (new.clone(), deposit, false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(new, index));
+		}
+
+		/// Free an index held by the sender, returning the deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		fn free(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			let (holder, deposit, frozen) = <Accounts<T>>::get(index).ok_or("Index not claimed")?;
+			ensure!(holder == who, "Not the holder of the index");
+			ensure!(!frozen, "Index is frozen");
+
+			<Accounts<T>>::remove(index);
+			let _ = T::Currency::unreserve(&who, deposit);
+
+			Self::deposit_event(RawEvent::IndexFreed(index));
+		}
+
+		/// Assign an index to an account, with no deposit. The deposit of the previous holder, if
+		/// any, is returned to them.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - At most one balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		fn force_transfer(origin, new: <T::Lookup as StaticLookup>::Source, index: T::AccountIndex) {
+			ensure_root(origin)?;
+			let new = T::Lookup::lookup(new)?;
+
+			if let Some((holder, deposit, _)) = <Accounts<T>>::get(index) {
+				let _ = T::Currency::unreserve(&holder, deposit);
+			}
+			<Accounts<T>>::insert(index, (new.clone(), Zero::zero(), false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(new, index));
+		}
+
+		/// Permanently assign an index to the sender holding it. The deposit is lost.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		fn freeze(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			let (holder, deposit, frozen) = <Accounts<T>>::get(index).ok_or("Index not claimed")?;
+			ensure!(holder == who, "Not the holder of the index");
+			ensure!(!frozen, "Index is frozen");
+
+			let _ = T::Currency::slash_reserved(&who, deposit);
+			<Accounts<T>>::insert(index, (who.clone(), Zero::zero(), true));
+
+			Self::deposit_event(RawEvent::IndexFrozen(index, who));
+		}
+
+		/// Claim an index held by a dead account, reserving a deposit of `Deposit`.
+		///
+		/// Frozen indices can't be reclaimed.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		fn reclaim(origin, index: T::AccountIndex) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(Self::can_reclaim(index), "Index can't be reclaimed");
+			let deposit = T::Deposit::get();
+			T::Currency::reserve(&who, deposit)?;
+			<Accounts<T>>::insert(index, (who.clone(), deposit, false));
+
+			Self::deposit_event(RawEvent::IndexAssigned(who, index));
+		}
 	}
 }
 
@@ -82,36 +246,42 @@ decl_event!(
 		<T as system::Trait>::AccountId,
 		<T as Trait>::AccountIndex
 	{
-		/// A new account index was assigned.
-		///
-		/// This event is not triggered when an existing index is reassigned
-		/// to another `AccountId`.
-		NewAccountIndex(AccountId, AccountIndex),
+		/// An account index was assigned.
+		IndexAssigned(AccountId, AccountIndex),
+		/// An account index was freed.
+		IndexFreed(AccountIndex),
+		/// An account index was frozen to its current holder.
+		IndexFrozen(AccountIndex, AccountId),
 	}
 );
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Indices {
-		/// The next free enumeration set.
-		pub NextEnumSet get(fn next_enum_set) build(|config: &GenesisConfig<T>| {
-			(config.ids.len() as u32 / ENUM_SET_SIZE).into()
-		}): T::AccountIndex;
-
-		/// The enumeration sets.
-		pub EnumSet get(fn enum_set) build(|config: &GenesisConfig<T>| {
-			(0..((config.ids.len() as u32) + ENUM_SET_SIZE - 1) / ENUM_SET_SIZE)
-				.map(|i| (
-					i.into(),
-					config.ids[
-						(i * ENUM_SET_SIZE) as usize..
-						config.ids.len().min(((i + 1) * ENUM_SET_SIZE) as usize)
-					].to_owned(),
-				))
-				.collect::<Vec<_>>()
-		}): map T::AccountIndex => Vec<T::AccountId>;
+		/// The holder of each claimed index, along with the deposit reserved for it and whether it
+		/// is frozen.
+		pub Accounts get(fn accounts): map T::AccountIndex => Option<(T::AccountId, BalanceOf<T>, bool)>;
+
+		/// The next free enumeration set, from before the indices could be claimed.
+		///
+		/// Only read by the migration to `Accounts`.
+		NextEnumSet: T::AccountIndex;
+
+		/// The enumeration sets, from before the indices could be claimed.
+		///
+		/// Only read by the migration to `Accounts`.
+		EnumSet: map T::AccountIndex => Vec<T::AccountId>;
+
+		/// Have the enumeration sets been migrated to `Accounts`?
+		DidMigrate: bool;
 	}
 	add_extra_genesis {
+		/// Accounts which are given the indices `0..ids.len()` at genesis, with no deposit.
 		config(ids): Vec<T::AccountId>;
+		build(|config: &GenesisConfig<T>| {
+			for (i, who) in config.ids.iter().enumerate() {
+				<Accounts<T>>::insert(T::AccountIndex::from(i as u32), (who.clone(), BalanceOf::<T>::zero(), false));
+			}
+		});
 	}
 }
 
@@ -120,21 +290,15 @@ impl<T: Trait> Module<T> {
 
 	/// Lookup an T::AccountIndex to get an Id, if there's one there.
 	pub fn lookup_index(index: T::AccountIndex) -> Option<T::AccountId> {
-		let enum_set_size = Self::enum_set_size();
-		let set = Self::enum_set(index / enum_set_size);
-		let i: usize = (index % enum_set_size).try_into().ok()?;
-		set.get(i).cloned()
+		<Accounts<T>>::get(index).map(|(who, _, _)| who)
 	}
 
-	/// `true` if the account `index` is ready for reclaim.
-	pub fn can_reclaim(try_index: T::AccountIndex) -> bool {
-		let enum_set_size = Self::enum_set_size();
-		let try_set = Self::enum_set(try_index / enum_set_size);
-		let maybe_usize: Result<usize, _> = (try_index % enum_set_size).try_into();
-		if let Ok(i) = maybe_usize {
-			i < try_set.len() && T::IsDeadAccount::is_dead_account(&try_set[i])
-		} else {
-			false
+	/// `true` if the account `index` is ready for reclaim: it is not frozen and its holder is
+	/// dead.
+	pub fn can_reclaim(index: T::AccountIndex) -> bool {
+		match <Accounts<T>>::get(index) {
+			Some((holder, _, frozen)) => !frozen && T::IsDeadAccount::is_dead_account(&holder),
+			None => false,
 		}
 	}
 
@@ -148,76 +312,23 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	// PUBLIC MUTABLES (DANGEROUS)
-
-	fn enum_set_size() -> T::AccountIndex {
-		ENUM_SET_SIZE.into()
-	}
-}
-
-impl<T: Trait> OnNewAccount<T::AccountId> for Module<T> {
-	// Implementation of the config type managing the creation of new accounts.
-	// See Balances module for a concrete example.
-	//
-	// # <weight>
-	// - Independent of the arguments.
-	// - Given the correct value of `Self::next_enum_set`, it always has a limited
-	//   number of reads and writes and no complex computation.
-	//
-	// As for storage, calling this function with _non-dead-indices_ will linearly grow the length of
-	// of `Self::enum_set`. Appropriate economic incentives should exist to make callers of this
-	// function provide a `who` argument that reclaims a dead account.
-	//
-	// At the time of this writing, only the Balances module calls this function upon creation
-	// of new accounts.
-	// # </weight>
-	fn on_new_account(who: &T::AccountId) {
-		let enum_set_size = Self::enum_set_size();
-		let next_set_index = Self::next_enum_set();
-
-		if let Some(try_index) = T::ResolveHint::resolve_hint(who) {
-			// then check to see if this account id identifies a dead account index.
-			let set_index = try_index / enum_set_size;
-			let mut try_set = Self::enum_set(set_index);
-			if let Ok(item_index) = (try_index % enum_set_size).try_into() {
-				if item_index < try_set.len() {
-					if T::IsDeadAccount::is_dead_account(&try_set[item_index]) {
-						// yup - this index refers to a dead account. can be reused.
-						try_set[item_index] = who.clone();
-						<EnumSet<T>>::insert(set_index, try_set);
-
-						return
-					}
+	/// Move the accounts of the enumeration sets to `Accounts`, with no deposit.
+	///
+	/// Before the indices could be claimed, each new account was given the next index, and the
+	/// accounts were stored by sets of `ENUM_SET_SIZE`.
+	fn do_migrate() {
+		let enum_set_size = T::AccountIndex::from(ENUM_SET_SIZE);
+		let next_set = <NextEnumSet<T>>::take();
+		let mut set = T::AccountIndex::zero();
+		while set <= next_set {
+			for (i, who) in <EnumSet<T>>::take(set).into_iter().enumerate() {
+				let index = set * enum_set_size + T::AccountIndex::from(i as u32);
+				if !<Accounts<T>>::exists(index) {
+					<Accounts<T>>::insert(index, (who, BalanceOf::<T>::zero(), false));
 				}
 			}
+			set += One::one();
 		}
-
-		// insert normally as a back up
-		let mut set_index = next_set_index;
-		// defensive only: this loop should never iterate since we keep NextEnumSet up to date
-		// later.
-		let mut set = loop {
-			let set = Self::enum_set(set_index);
-			if set.len() < ENUM_SET_SIZE as usize {
-				break set;
-			}
-			set_index += One::one();
-		};
-
-		let index = set_index * enum_set_size + T::AccountIndex::from(set.len() as u32);
-
-		// update set.
-		set.push(who.clone());
-
-		// keep NextEnumSet up to date
-		if set.len() == ENUM_SET_SIZE as usize {
-			<NextEnumSet<T>>::put(set_index + One::one());
-		}
-
-		// write set.
-		<EnumSet<T>>::insert(set_index, set);
-
-		Self::deposit_event(RawEvent::NewAccountIndex(who.clone(), index));
 	}
 }
 
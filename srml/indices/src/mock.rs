@@ -18,50 +18,17 @@
 
 #![cfg(test)]
 
-use std::collections::HashSet;
-use ref_thread_local::{ref_thread_local, RefThreadLocal};
 use sr_primitives::testing::Header;
 use sr_primitives::Perbill;
 use primitives::H256;
 use support::{impl_outer_origin, parameter_types};
 use {runtime_io, system};
-use crate::{GenesisConfig, Module, Trait, IsDeadAccount, OnNewAccount, ResolveHint};
+use crate::{GenesisConfig, Module, Trait};
 
 impl_outer_origin!{
 	pub enum Origin for Runtime {}
 }
 
-ref_thread_local! {
-	static managed ALIVE: HashSet<u64> = HashSet::new();
-}
-
-pub fn make_account(who: u64) {
-	ALIVE.borrow_mut().insert(who);
-	Indices::on_new_account(&who);
-}
-
-pub fn kill_account(who: u64) {
-	ALIVE.borrow_mut().remove(&who);
-}
-
-pub struct TestIsDeadAccount {}
-impl IsDeadAccount<u64> for TestIsDeadAccount {
-	fn is_dead_account(who: &u64) -> bool {
-		!ALIVE.borrow_mut().contains(who)
-	}
-}
-
-pub struct TestResolveHint;
-impl ResolveHint<u64, u64> for TestResolveHint {
-	fn resolve_hint(who: &u64) -> Option<u64> {
-		if *who < 256 {
-			None
-		} else {
-			Some(*who - 256)
-		}
-	}
-}
-
 // Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Runtime;
@@ -88,25 +55,43 @@ impl system::Trait for Runtime {
 	type AvailableBlockRatio = AvailableBlockRatio;
 	type Version = ();
 }
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const TransferFee: u64 = 0;
+	pub const CreationFee: u64 = 0;
+}
+impl balances::Trait for Runtime {
+	type Balance = u64;
+	type OnFreeBalanceZero = ();
+	type OnNewAccount = ();
+	type Event = ();
+	type TransferPayment = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type TransferFee = TransferFee;
+	type CreationFee = CreationFee;
+}
+parameter_types! {
+	pub const Deposit: u64 = 2;
+}
 impl Trait for Runtime {
 	type AccountIndex = u64;
-	type IsDeadAccount = TestIsDeadAccount;
-	type ResolveHint = TestResolveHint;
+	type Currency = Balances;
+	type Deposit = Deposit;
+	type IsDeadAccount = Balances;
 	type Event = ();
 }
 
 pub fn new_test_ext() -> runtime_io::TestExternalities {
-	{
-		let mut h = ALIVE.borrow_mut();
-		h.clear();
-		for i in 1..5 { h.insert(i); }
-	}
-
 	let mut t = system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	balances::GenesisConfig::<Runtime> {
+		balances: vec![(1, 10), (2, 10), (3, 10), (4, 10), (5, 10)],
+	}.assimilate_storage(&mut t).unwrap();
 	GenesisConfig::<Runtime> {
 		ids: vec![1, 2, 3, 4]
 	}.assimilate_storage(&mut t).unwrap();
 	t.into()
 }
 
+pub type Balances = balances::Module<Runtime>;
 pub type Indices = Module<Runtime>;
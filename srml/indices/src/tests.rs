@@ -19,7 +19,8 @@
 #![cfg(test)]
 
 use super::*;
-use crate::mock::{Indices, new_test_ext, make_account, kill_account, TestIsDeadAccount};
+use support::{assert_ok, assert_noop};
+use crate::mock::{Balances, Indices, Origin, Runtime, new_test_ext};
 
 #[test]
 fn indexing_lookup_should_work() {
@@ -29,39 +30,105 @@ fn indexing_lookup_should_work() {
 		assert_eq!(Indices::lookup_index(2), Some(3));
 		assert_eq!(Indices::lookup_index(3), Some(4));
 		assert_eq!(Indices::lookup_index(4), None);
+		assert_eq!(Indices::lookup(address::Address::Index(1)), Ok(2));
+		assert_eq!(Indices::lookup(address::Address::Id(5)), Ok(5));
 	});
 }
 
 #[test]
-fn default_indexing_on_new_accounts_should_work() {
+fn claiming_and_freeing_should_work() {
 	new_test_ext().execute_with(|| {
-		assert_eq!(Indices::lookup_index(4), None);
-		make_account(5);
-		assert_eq!(Indices::lookup_index(4), Some(5));
+		assert_noop!(Indices::claim(Origin::signed(5), 0), "Index already claimed");
+		assert_ok!(Indices::claim(Origin::signed(5), 10));
+		assert_eq!(Indices::lookup_index(10), Some(5));
+		assert_eq!(Balances::reserved_balance(&5), 2);
+
+		assert_noop!(Indices::free(Origin::signed(1), 10), "Not the holder of the index");
+		assert_ok!(Indices::free(Origin::signed(5), 10));
+		assert_eq!(Indices::lookup_index(10), None);
+		assert_eq!(Balances::reserved_balance(&5), 0);
+		assert_eq!(Balances::free_balance(&5), 10);
 	});
 }
 
 #[test]
-fn reclaim_indexing_on_new_accounts_should_work() {
+fn transferring_should_move_the_deposit() {
 	new_test_ext().execute_with(|| {
-		assert_eq!(Indices::lookup_index(1), Some(2));
-		assert_eq!(Indices::lookup_index(4), None);
+		assert_ok!(Indices::claim(Origin::signed(5), 10));
+		assert_noop!(Indices::transfer(Origin::signed(1), 2, 10), "Not the holder of the index");
+		assert_noop!(Indices::transfer(Origin::signed(5), 5, 10), "Cannot transfer an index to its holder");
 
-		kill_account(2);					// index 1 no longer locked to id 2
+		assert_ok!(Indices::transfer(Origin::signed(5), 3, 10));
+		assert_eq!(Indices::lookup_index(10), Some(3));
+		assert_eq!(Balances::total_balance(&5), 8);
+		assert_eq!(Balances::reserved_balance(&3), 2);
+		assert_eq!(Indices::accounts(10), Some((3, 2, false)));
+	});
+}
 
-		make_account(1 + 256);				// id 257 takes index 1.
-		assert_eq!(Indices::lookup_index(1), Some(257));
+#[test]
+fn force_transfer_should_return_the_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Indices::claim(Origin::signed(5), 10));
+		assert_noop!(Indices::force_transfer(Origin::signed(1), 3, 10), "RequireRootOrigin");
+		assert_ok!(Indices::force_transfer(Origin::ROOT, 3, 10));
+		assert_eq!(Indices::accounts(10), Some((3, 0, false)));
+		assert_eq!(Balances::reserved_balance(&5), 0);
+		assert_eq!(Balances::free_balance(&5), 10);
 	});
 }
 
 #[test]
-fn alive_account_should_prevent_reclaim() {
+fn frozen_index_should_be_permanent() {
 	new_test_ext().execute_with(|| {
-		assert!(!TestIsDeadAccount::is_dead_account(&2));
-		assert_eq!(Indices::lookup_index(1), Some(2));
-		assert_eq!(Indices::lookup_index(4), None);
+		assert_ok!(Indices::claim(Origin::signed(5), 10));
+		assert_ok!(Indices::freeze(Origin::signed(5), 10));
+		assert_eq!(Balances::total_balance(&5), 8);
+		assert_eq!(Indices::accounts(10), Some((5, 0, true)));
+
+		assert_noop!(Indices::free(Origin::signed(5), 10), "Index is frozen");
+		assert_noop!(Indices::transfer(Origin::signed(5), 3, 10), "Index is frozen");
+		assert_noop!(Indices::freeze(Origin::signed(5), 10), "Index is frozen");
+
+		// even once its holder is dead
+		let _ = Balances::slash(&5, 8);
+		assert!(!Indices::can_reclaim(10));
+	});
+}
+
+#[test]
+fn index_of_dead_account_should_be_reclaimable() {
+	new_test_ext().execute_with(|| {
+		assert!(!Indices::can_reclaim(1));
+		assert_noop!(Indices::reclaim(Origin::signed(5), 1), "Index can't be reclaimed");
+		assert_noop!(Indices::reclaim(Origin::signed(5), 10), "Index can't be reclaimed");
+
+		let _ = Balances::slash(&2, 10);
+		assert!(Indices::can_reclaim(1));
+		assert_ok!(Indices::reclaim(Origin::signed(5), 1));
+		assert_eq!(Indices::lookup_index(1), Some(5));
+		assert_eq!(Balances::reserved_balance(&5), 2);
+	});
+}
 
-		make_account(1 + 256);				// id 257 takes index 1.
-		assert_eq!(Indices::lookup_index(4), Some(257));
+#[test]
+fn enum_sets_should_be_migrated() {
+	new_test_ext().execute_with(|| {
+		use sr_primitives::traits::OnInitialize;
+
+		<EnumSet<Runtime>>::insert(0, vec![1, 2, 3, 4]);
+		<EnumSet<Runtime>>::insert(1, vec![20, 21]);
+		<NextEnumSet<Runtime>>::put(1);
+
+		assert!(!DidMigrate::get());
+		<Indices as OnInitialize<u64>>::on_initialize(1);
+		assert!(DidMigrate::get());
+
+		assert_eq!(Indices::lookup_index(3), Some(4));
+		assert_eq!(Indices::accounts(64), Some((20, 0, false)));
+		assert_eq!(Indices::lookup_index(65), Some(21));
+		assert_eq!(Indices::lookup_index(66), None);
+		assert!(!<EnumSet<Runtime>>::exists(1));
+		assert!(!<NextEnumSet<Runtime>>::exists());
 	});
 }
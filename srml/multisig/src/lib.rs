@@ -0,0 +1,598 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Multisig Module
+//!
+//! - [`multisig::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A module for dispatching calls on behalf of a multi-signature account: a deterministic account
+//! derived from a set of signatories and a threshold, which dispatches a call once `threshold`
+//! of its signatories approved it.
+//!
+//! The first approval of a call opens a multisig operation, for which a deposit is reserved from
+//! the account of the approver. The operation is identified by the multi-account and the hash of
+//! the call, and the following approvals must give the timepoint, i.e. the block height and
+//! extrinsic index, at which it was opened. An approval can't thus be replayed on a later
+//! operation of the same call.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `as_multi` - Approve a call, and dispatch it from the multi-account if the approval reaches
+//!   the threshold.
+//! * `approve_as_multi` - Approve a call from its hash, without dispatching it.
+//! * `cancel_as_multi` - Cancel a multisig operation opened by the sender; the deposit is returned.
+//!
+//! ### Public Functions
+//!
+//! * `multi_account_id` - The account of a set of signatories and a threshold.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use sr_primitives::{
+	DispatchError, RuntimeDebug, traits::Dispatchable, weights::SimpleDispatchInfo,
+};
+use support::{
+	decl_module, decl_event, decl_storage, ensure, Parameter,
+	traits::{Currency, ReservableCurrency, Get},
+};
+use system::ensure_signed;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The overarching call type.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin>;
+
+	/// The currency mechanism.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The base amount of currency needed to reserve for opening a multisig operation.
+	type MultisigDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed per unit threshold when opening a multisig operation.
+	type MultisigDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum amount of signatories allowed for a multi-account.
+	type MaxSignatories: Get<u16>;
+}
+
+/// A global extrinsic index, formed as the extrinsic index within a block, together with that
+/// block's height. This allows a transaction in which a multisig operation of a particular
+/// composite was opened to be uniquely identified.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Timepoint<BlockNumber> {
+	/// The height of the chain at the point in time.
+	pub height: BlockNumber,
+	/// The index of the extrinsic at the point in time.
+	pub index: u32,
+}
+
+/// An open multisig operation.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Multisig<BlockNumber, Balance, AccountId> {
+	/// The extrinsic when the multisig operation was opened.
+	when: Timepoint<BlockNumber>,
+	/// The amount held in reserve of the `depositor`, to be returned once the operation ends.
+	deposit: Balance,
+	/// The account who opened it (i.e. the first to approve it).
+	depositor: AccountId,
+	/// The approvals achieved so far, including the depositor. Always sorted.
+	approvals: Vec<AccountId>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Multisig {
+		/// The set of open multisig operations, by multi-account and call hash.
+		pub Multisigs get(fn multisigs):
+			double_map T::AccountId, blake2_256([u8; 32])
+			=> Option<Multisig<T::BlockNumber, BalanceOf<T>, T::AccountId>>;
+	}
+}
+
+decl_event!(
+	/// Events type.
+	pub enum Event<T> where
+		AccountId = <T as system::Trait>::AccountId,
+		BlockNumber = <T as system::Trait>::BlockNumber,
+	{
+		/// A new multisig operation was opened. First param is the account that is approving,
+		/// second is the multi-account.
+		NewMultisig(AccountId, AccountId),
+		/// A multisig operation was approved by an account. First param is the account that is
+		/// approving, third is the multi-account.
+		MultisigApproval(AccountId, Timepoint<BlockNumber>, AccountId),
+		/// A multisig operation was executed. First param is the account that is approving, third
+		/// is the multi-account.
+		MultisigExecuted(AccountId, Timepoint<BlockNumber>, AccountId, rstd::result::Result<(), DispatchError>),
+		/// A multisig operation was cancelled. First param is the account that is cancelling,
+		/// third is the multi-account.
+		MultisigCancelled(AccountId, Timepoint<BlockNumber>, AccountId),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// The base amount of currency needed to reserve for opening a multisig operation.
+		const MultisigDepositBase: BalanceOf<T> = T::MultisigDepositBase::get();
+
+		/// The amount of currency needed per unit threshold when opening a multisig operation.
+		const MultisigDepositFactor: BalanceOf<T> = T::MultisigDepositFactor::get();
+
+		/// The maximum amount of signatories allowed for a multi-account.
+		const MaxSignatories: u16 = T::MaxSignatories::get();
+
+		/// Register approval for a dispatch to be made from a deterministic composite account if
+		/// approved by a total of `threshold` of `other_signatories` and the sender, dispatching
+		/// it if the approval reaches the threshold.
+		///
+		/// If this is the first approval, then a deposit of `MultisigDepositBase` plus `threshold`
+		/// times `MultisigDepositFactor` is reserved, and returned once the call is dispatched or
+		/// the operation is cancelled.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `threshold`: The total number of approvals needed for this dispatch to execute.
+		/// - `other_signatories`: The accounts (other than the sender) who can approve this
+		///   dispatch. May not be empty and must be sorted.
+		/// - `maybe_timepoint`: The timepoint at which the operation was opened, `None` if this is
+		///   the first approval.
+		/// - `call`: The call to be dispatched.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of signatories, plus the weight of `call`.
+		/// - Up to one balance-reserve or unreserve operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let who = ensure_signed(origin)?;
+			let signatories = Self::ensure_signatories(threshold, other_signatories, &who)?;
+			let id = Self::multi_account_id(&signatories, threshold);
+			let call_hash = call.using_encoded(runtime_io::blake2_256);
+
+			if let Some(mut m) = <Multisigs<T>>::get(&id, &call_hash) {
+				let timepoint = maybe_timepoint.ok_or("No timepoint")?;
+				ensure!(m.when == timepoint, "Wrong timepoint");
+
+				if let Err(pos) = m.approvals.binary_search(&who) {
+					// the approval of the sender is the last one needed when `threshold - 1`
+					// accounts approved already.
+					if (m.approvals.len() as u16) < threshold - 1 {
+						m.approvals.insert(pos, who.clone());
+						<Multisigs<T>>::insert(&id, &call_hash, m);
+						Self::deposit_event(RawEvent::MultisigApproval(who, timepoint, id));
+						return Ok(())
+					}
+				} else if (m.approvals.len() as u16) < threshold {
+					return Err("Already approved")
+				}
+
+				let result = (*call).dispatch(system::RawOrigin::Signed(id.clone()).into());
+				let _ = T::Currency::unreserve(&m.depositor, m.deposit);
+				<Multisigs<T>>::remove(&id, &call_hash);
+				Self::deposit_event(RawEvent::MultisigExecuted(who, timepoint, id, result.map_err(Into::into)));
+			} else {
+				ensure!(maybe_timepoint.is_none(), "Unexpected timepoint");
+
+				if threshold > 1 {
+					let deposit = Self::deposit(threshold);
+					T::Currency::reserve(&who, deposit)?;
+					<Multisigs<T>>::insert(&id, &call_hash, Multisig {
+						when: Self::timepoint(),
+						deposit,
+						depositor: who.clone(),
+						approvals: vec![who.clone()],
+					});
+					Self::deposit_event(RawEvent::NewMultisig(who, id));
+				} else {
+					let result = (*call).dispatch(system::RawOrigin::Signed(id.clone()).into());
+					Self::deposit_event(
+						RawEvent::MultisigExecuted(who, Self::timepoint(), id, result.map_err(Into::into))
+					);
+				}
+			}
+		}
+
+		/// Register approval for a dispatch to be made from a deterministic composite account if
+		/// approved by a total of `threshold` of `other_signatories` and the sender.
+		///
+		/// The call is only given by its hash, so it can't be dispatched: the final approval must
+		/// be made with `as_multi`.
+		///
+		/// If this is the first approval, then a deposit of `MultisigDepositBase` plus `threshold`
+		/// times `MultisigDepositFactor` is reserved, and returned once the call is dispatched or
+		/// the operation is cancelled.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `threshold`: The total number of approvals needed for this dispatch to execute.
+		/// - `other_signatories`: The accounts (other than the sender) who can approve this
+		///   dispatch. May not be empty and must be sorted.
+		/// - `maybe_timepoint`: The timepoint at which the operation was opened, `None` if this is
+		///   the first approval.
+		/// - `call_hash`: The blake2-256 hash of the call to be dispatched.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of signatories.
+		/// - Up to one balance-reserve operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn approve_as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call_hash: [u8; 32],
+		) {
+			let who = ensure_signed(origin)?;
+			let signatories = Self::ensure_signatories(threshold, other_signatories, &who)?;
+			ensure!(threshold > 1, "Threshold too low");
+			let id = Self::multi_account_id(&signatories, threshold);
+
+			if let Some(mut m) = <Multisigs<T>>::get(&id, &call_hash) {
+				let timepoint = maybe_timepoint.ok_or("No timepoint")?;
+				ensure!(m.when == timepoint, "Wrong timepoint");
+				ensure!((m.approvals.len() as u16) < threshold - 1, "Final approval needs the call");
+
+				let pos = m.approvals.binary_search(&who).err().ok_or("Already approved")?;
+				m.approvals.insert(pos, who.clone());
+				<Multisigs<T>>::insert(&id, &call_hash, m);
+				Self::deposit_event(RawEvent::MultisigApproval(who, timepoint, id));
+			} else {
+				ensure!(maybe_timepoint.is_none(), "Unexpected timepoint");
+
+				let deposit = Self::deposit(threshold);
+				T::Currency::reserve(&who, deposit)?;
+				<Multisigs<T>>::insert(&id, &call_hash, Multisig {
+					when: Self::timepoint(),
+					deposit,
+					depositor: who.clone(),
+					approvals: vec![who.clone()],
+				});
+				Self::deposit_event(RawEvent::NewMultisig(who, id));
+			}
+		}
+
+		/// Cancel a multisig operation opened by the sender, returning its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the account which opened the
+		/// operation.
+		///
+		/// - `threshold`: The total number of approvals needed for this dispatch to execute.
+		/// - `other_signatories`: The accounts (other than the sender) who can approve this
+		///   dispatch. May not be empty and must be sorted.
+		/// - `timepoint`: The timepoint at which the operation was opened.
+		/// - `call_hash`: The blake2-256 hash of the call to be dispatched.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of signatories.
+		/// - One balance-unreserve operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn cancel_as_multi(
+			origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			timepoint: Timepoint<T::BlockNumber>,
+			call_hash: [u8; 32],
+		) {
+			let who = ensure_signed(origin)?;
+			let signatories = Self::ensure_signatories(threshold, other_signatories, &who)?;
+			let id = Self::multi_account_id(&signatories, threshold);
+
+			let m = <Multisigs<T>>::get(&id, &call_hash).ok_or("Not found")?;
+			ensure!(m.when == timepoint, "Wrong timepoint");
+			ensure!(m.depositor == who, "Not owner");
+
+			let _ = T::Currency::unreserve(&m.depositor, m.deposit);
+			<Multisigs<T>>::remove(&id, &call_hash);
+			Self::deposit_event(RawEvent::MultisigCancelled(who, timepoint, id));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Derive a multi-account ID from the sorted list of accounts and the threshold that are
+	/// required.
+	///
+	/// NOTE: `who` must be sorted. If it is not, then you'll get the wrong answer.
+	pub fn multi_account_id(who: &[T::AccountId], threshold: u16) -> T::AccountId {
+		let entropy = (b"modlpy/multisig_", who, threshold).using_encoded(runtime_io::blake2_256);
+		T::AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+	}
+
+	/// The current timepoint.
+	pub fn timepoint() -> Timepoint<T::BlockNumber> {
+		Timepoint {
+			height: <system::Module<T>>::block_number(),
+			index: <system::Module<T>>::extrinsic_index().unwrap_or_default(),
+		}
+	}
+
+	/// The deposit reserved when opening a multisig operation with the given threshold.
+	fn deposit(threshold: u16) -> BalanceOf<T> {
+		T::MultisigDepositBase::get() + T::MultisigDepositFactor::get() * threshold.into()
+	}
+
+	/// Check the threshold and the signatories of a multi-account, returning all its signatories
+	/// (i.e. `other_signatories` along with the sender `who`), sorted.
+	fn ensure_signatories(
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		who: &T::AccountId,
+	) -> rstd::result::Result<Vec<T::AccountId>, &'static str> {
+		ensure!(threshold >= 1, "Threshold too low");
+		ensure!(!other_signatories.is_empty(), "Too few signatories");
+		ensure!(
+			other_signatories.len() < T::MaxSignatories::get() as usize,
+			"Too many signatories"
+		);
+		ensure!(other_signatories.len() >= threshold as usize - 1, "Threshold too high");
+
+		let mut signatories = other_signatories;
+		ensure!(signatories.windows(2).all(|pair| pair[0] < pair[1]), "Signatories out of order");
+		let pos = signatories.binary_search(who).err().ok_or("Sender in signatories")?;
+		signatories.insert(pos, who.clone());
+		Ok(signatories)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch};
+	use primitives::H256;
+	use sr_primitives::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			balances::Balances,
+			multisig::Multisig,
+		}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const MultisigDepositBase: u64 = 1;
+		pub const MultisigDepositFactor: u64 = 1;
+		pub const MaxSignatories: u16 = 3;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Call = Call;
+		type Currency = Balances;
+		type MultisigDepositBase = MultisigDepositBase;
+		type MultisigDepositFactor = MultisigDepositFactor;
+		type MaxSignatories = MaxSignatories;
+	}
+	type Balances = balances::Module<Test>;
+	type Multisig = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10), (4, 10), (5, 10)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn now() -> Timepoint<u64> {
+		Multisig::timepoint()
+	}
+
+	#[test]
+	fn multisig_deposit_is_taken_and_returned() {
+		new_test_ext().execute_with(|| {
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 2);
+			assert_ok!(Balances::transfer(Origin::signed(1), multi, 5));
+			assert_ok!(Balances::transfer(Origin::signed(2), multi, 5));
+			assert_ok!(Balances::transfer(Origin::signed(3), multi, 5));
+
+			let call = Box::new(Call::Balances(balances::Call::transfer(6, 15)));
+			assert_ok!(Multisig::as_multi(Origin::signed(1), 2, vec![2, 3], None, call.clone()));
+			assert_eq!(Balances::free_balance(1), 2);
+			assert_eq!(Balances::reserved_balance(1), 3);
+
+			assert_ok!(Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], Some(now()), call));
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(6), 15);
+		});
+	}
+
+	#[test]
+	fn cancel_multisig_returns_deposit() {
+		new_test_ext().execute_with(|| {
+			let call = Call::Balances(balances::Call::transfer(6, 15));
+			let hash = call.using_encoded(runtime_io::blake2_256);
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, hash));
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(2), 3, vec![1, 3], Some(now()), hash));
+			assert_eq!(Balances::free_balance(1), 6);
+			assert_eq!(Balances::reserved_balance(1), 4);
+
+			assert_noop!(
+				Multisig::cancel_as_multi(Origin::signed(2), 3, vec![1, 3], now(), hash),
+				"Not owner",
+			);
+			assert_ok!(Multisig::cancel_as_multi(Origin::signed(1), 3, vec![2, 3], now(), hash));
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert!(Multisig::multisigs(&Multisig::multi_account_id(&[1, 2, 3][..], 3), &hash).is_none());
+		});
+	}
+
+	#[test]
+	fn multisig_3_of_3_works() {
+		new_test_ext().execute_with(|| {
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 3);
+			assert_ok!(Balances::transfer(Origin::signed(1), multi, 5));
+			assert_ok!(Balances::transfer(Origin::signed(2), multi, 5));
+			assert_ok!(Balances::transfer(Origin::signed(3), multi, 5));
+
+			let call = Box::new(Call::Balances(balances::Call::transfer(6, 15)));
+			let hash = call.using_encoded(runtime_io::blake2_256);
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, hash));
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(2), 3, vec![1, 3], Some(now()), hash));
+			assert_eq!(Balances::free_balance(6), 0);
+
+			assert_ok!(Multisig::as_multi(Origin::signed(3), 3, vec![1, 2], Some(now()), call));
+			assert_eq!(Balances::free_balance(6), 15);
+		});
+	}
+
+	#[test]
+	fn multisig_1_of_3_dispatches_immediately() {
+		new_test_ext().execute_with(|| {
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 1);
+			assert_ok!(Balances::transfer(Origin::signed(1), multi, 5));
+
+			let call = Box::new(Call::Balances(balances::Call::transfer(6, 5)));
+			assert_ok!(Multisig::as_multi(Origin::signed(2), 1, vec![1, 3], None, call));
+			assert_eq!(Balances::free_balance(6), 5);
+			assert_eq!(Balances::reserved_balance(2), 0);
+		});
+	}
+
+	#[test]
+	fn approvals_are_checked() {
+		new_test_ext().execute_with(|| {
+			let call = Box::new(Call::Balances(balances::Call::transfer(6, 15)));
+			let hash = call.using_encoded(runtime_io::blake2_256);
+
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 0, vec![2, 3], None, call.clone()),
+				"Threshold too low",
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![], None, call.clone()),
+				"Too few signatories",
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![2, 3, 4], None, call.clone()),
+				"Too many signatories",
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![3, 2], None, call.clone()),
+				"Signatories out of order",
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![1, 2], None, call.clone()),
+				"Sender in signatories",
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![2, 3], Some(now()), call.clone()),
+				"Unexpected timepoint",
+			);
+
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, hash));
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], Some(now()), hash),
+				"Already approved",
+			);
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(2), 3, vec![1, 3], None, hash),
+				"No timepoint",
+			);
+			let wrong_timepoint = Timepoint { height: 2, index: 0 };
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(2), 3, vec![1, 3], Some(wrong_timepoint), call.clone()),
+				"Wrong timepoint",
+			);
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(2), 3, vec![1, 3], Some(now()), hash));
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(3), 3, vec![1, 2], Some(now()), hash),
+				"Final approval needs the call",
+			);
+		});
+	}
+}
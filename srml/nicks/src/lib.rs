@@ -40,11 +40,11 @@
 
 use rstd::prelude::*;
 use sr_primitives::{
-	traits::{StaticLookup, EnsureOrigin, Zero}, weights::SimpleDispatchInfo
+	traits::{StaticLookup, EnsureOrigin, Zero, Saturating}, weights::SimpleDispatchInfo
 };
 use support::{
 	decl_module, decl_event, decl_storage, ensure, traits::{
-		Currency, ReservableCurrency, OnUnbalanced, Get
+		Currency, NamedReservableCurrency, ReserveIdentifier, OnUnbalanced, Get
 	},
 };
 use system::{ensure_signed, ensure_root};
@@ -52,12 +52,15 @@ use system::{ensure_signed, ensure_root};
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
 
+/// The identifier under which the deposits for names are reserved.
+const NICKS_ID: ReserveIdentifier = *b"nicks   ";
+
 pub trait Trait: system::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 
 	/// The currency trait.
-	type Currency: ReservableCurrency<Self::AccountId>;
+	type Currency: NamedReservableCurrency<Self::AccountId>;
 
 	/// Reservation fee.
 	type ReservationFee: Get<BalanceOf<Self>>;
@@ -139,7 +142,7 @@ decl_module! {
 				deposit
 			} else {
 				let deposit = T::ReservationFee::get();
-				T::Currency::reserve(&sender, deposit.clone())?;
+				T::Currency::reserve_named(&NICKS_ID, &sender, deposit.clone())?;
 				Self::deposit_event(RawEvent::NameChanged(sender.clone()));
 				deposit
 			};
@@ -162,7 +165,8 @@ decl_module! {
 
 			let deposit = <NameOf<T>>::take(&sender).ok_or("Not named")?.1;
 
-			let _ = T::Currency::unreserve(&sender, deposit.clone());
+			Self::migrate_deposit(&sender, deposit);
+			let _ = T::Currency::unreserve_named(&NICKS_ID, &sender, deposit.clone());
 
 			Self::deposit_event(RawEvent::NameCleared(sender, deposit));
 		}
@@ -192,7 +196,8 @@ decl_module! {
 			// Grab their deposit (and check that they have one).
 			let deposit = <NameOf<T>>::take(&target).ok_or("Not named")?.1;
 			// Slash their deposit from them.
-			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&target, deposit.clone()).0);
+			Self::migrate_deposit(&target, deposit);
+			T::Slashed::on_unbalanced(T::Currency::slash_reserved_named(&NICKS_ID, &target, deposit.clone()).0);
 
 			Self::deposit_event(RawEvent::NameKilled(target, deposit));
 		}
@@ -225,6 +230,15 @@ decl_module! {
 	}
 }
 
+impl<T: Trait> Module<T> {
+	/// Move the part of the deposit of `who` which was reserved before deposits were named into the
+	/// named reserve of this module.
+	fn migrate_deposit(who: &T::AccountId, deposit: BalanceOf<T>) {
+		let named = T::Currency::reserved_balance_named(&NICKS_ID, who);
+		let _ = T::Currency::name_reserved(&NICKS_ID, who, deposit.saturating_sub(named));
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -314,7 +328,6 @@ mod tests {
 				(1, 10),
 				(2, 10),
 			],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		t.into()
 	}
@@ -365,6 +378,24 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn deposits_reserved_before_being_named_should_be_returned() {
+		new_test_ext().execute_with(|| {
+			use support::traits::ReservableCurrency;
+
+			assert_ok!(Balances::reserve(&1, 2));
+			<NameOf<Test>>::insert(1, (b"Gav".to_vec(), 2));
+			assert_ok!(Nicks::clear_name(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(&1), 0);
+			assert_eq!(Balances::free_balance(&1), 10);
+
+			assert_ok!(Balances::reserve(&2, 2));
+			<NameOf<Test>>::insert(2, (b"Dave".to_vec(), 2));
+			assert_ok!(Nicks::kill_name(Origin::signed(1), 2));
+			assert_eq!(Balances::total_balance(&2), 8);
+		});
+	}
+
 	#[test]
 	fn error_catching_should_work() {
 		new_test_ext().execute_with(|| {
@@ -0,0 +1,718 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Proxy Module
+//!
+//! - [`proxy::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A module allowing accounts to give permission to other accounts to dispatch some types of
+//! calls from their signed origin.
+//!
+//! Each proxy is given a type, which filters the calls it is allowed to make on behalf of the
+//! account, e.g. only the calls which don't transfer funds. A deposit is reserved from the
+//! account for each of its proxies.
+//!
+//! A proxy may also be given a delay: it then has to announce the hash of each call it intends
+//! to make, and can only make it once the delay passed. In the meantime, the account may reject
+//! the announcement, e.g. if the key of the proxy was compromised. A deposit is reserved from the
+//! proxy for each of its pending announcements.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `proxy` - Dispatch a call on behalf of an account, as one of its proxies without delay.
+//! * `add_proxy` - Register a proxy of the sender.
+//! * `remove_proxy` - Unregister a proxy of the sender.
+//! * `remove_proxies` - Unregister all the proxies of the sender.
+//! * `announce` - Announce a call to be made on behalf of an account, as one of its proxies.
+//! * `remove_announcement` - Remove an announcement made by the sender.
+//! * `reject_announcement` - Remove an announcement made by a proxy of the sender.
+//! * `proxy_announced` - Dispatch an announced call, once the delay of the proxy passed.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use sr_primitives::{
+	DispatchError, RuntimeDebug, weights::SimpleDispatchInfo,
+	traits::{Dispatchable, Member, Saturating, Zero},
+};
+use support::{
+	decl_module, decl_event, decl_storage, ensure, Parameter, IsSubType,
+	traits::{Currency, ReservableCurrency, Get, InstanceFilter},
+};
+use system::ensure_signed;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// The hash of a call, as announced by a proxy.
+pub type CallHash = [u8; 32];
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The overarching call type.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + IsSubType<Module<Self>, Self>;
+
+	/// The currency mechanism.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The types of proxies, filtering the calls they are allowed to make. The default type is
+	/// expected to allow all the calls.
+	type ProxyType: Parameter + Member + Ord + Default + InstanceFilter<<Self as Trait>::Call>;
+
+	/// The base amount of currency needed to reserve for having proxies.
+	type ProxyDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed to reserve per proxy.
+	type ProxyDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum amount of proxies allowed for a single account.
+	type MaxProxies: Get<u16>;
+
+	/// The base amount of currency needed to reserve for having pending announcements.
+	type AnnouncementDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed to reserve per pending announcement.
+	type AnnouncementDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum amount of pending announcements allowed for a single proxy.
+	type MaxPending: Get<u32>;
+}
+
+/// The definition of a proxy.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub struct ProxyDefinition<AccountId, ProxyType, BlockNumber> {
+	/// The account which may act on behalf of another.
+	pub delegate: AccountId,
+	/// The type of calls the proxy is allowed to make.
+	pub proxy_type: ProxyType,
+	/// The number of blocks that an announcement must be in place for before the corresponding
+	/// call may be dispatched. If zero, then no announcement is needed.
+	pub delay: BlockNumber,
+}
+
+/// A call announced by a proxy.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct Announcement<AccountId, Hash, BlockNumber> {
+	/// The account on behalf of which the call is to be made.
+	pub real: AccountId,
+	/// The hash of the call to be made.
+	pub call_hash: Hash,
+	/// The height at which the announcement was made.
+	pub height: BlockNumber,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		/// The proxies of each account, along with the amount held on deposit.
+		pub Proxies get(fn proxies): map T::AccountId
+			=> (Vec<ProxyDefinition<T::AccountId, T::ProxyType, T::BlockNumber>>, BalanceOf<T>);
+
+		/// The pending announcements of each proxy, along with the amount held on deposit.
+		pub Announcements get(fn announcements): map T::AccountId
+			=> (Vec<Announcement<T::AccountId, CallHash, T::BlockNumber>>, BalanceOf<T>);
+	}
+}
+
+decl_event!(
+	/// Events type.
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+		/// A call was dispatched by a proxy, with the given result.
+		ProxyExecuted(rstd::result::Result<(), DispatchError>),
+		/// A call was announced by a proxy. First param is the account on behalf of which the
+		/// call is to be made, second is the proxy.
+		Announced(AccountId, AccountId, CallHash),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// The base amount of currency needed to reserve for having proxies.
+		const ProxyDepositBase: BalanceOf<T> = T::ProxyDepositBase::get();
+
+		/// The amount of currency needed to reserve per proxy.
+		const ProxyDepositFactor: BalanceOf<T> = T::ProxyDepositFactor::get();
+
+		/// The maximum amount of proxies allowed for a single account.
+		const MaxProxies: u16 = T::MaxProxies::get();
+
+		/// The base amount of currency needed to reserve for having pending announcements.
+		const AnnouncementDepositBase: BalanceOf<T> = T::AnnouncementDepositBase::get();
+
+		/// The amount of currency needed to reserve per pending announcement.
+		const AnnouncementDepositFactor: BalanceOf<T> = T::AnnouncementDepositFactor::get();
+
+		/// The maximum amount of pending announcements allowed for a single proxy.
+		const MaxPending: u32 = T::MaxPending::get();
+
+		/// Dispatch the given `call` from an account that the sender is authorised for through
+		/// `add_proxy`, without delay.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `force_proxy_type`: Specify the exact proxy type to be used and checked for this call.
+		/// - `call`: The call to be made by the `real` account.
+		///
+		/// # <weight>
+		/// - `O(P)` where `P` is the number of proxies of `real`, plus the weight of `call`.
+		/// - One storage read.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn proxy(
+			origin,
+			real: T::AccountId,
+			force_proxy_type: Option<T::ProxyType>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let who = ensure_signed(origin)?;
+			let def = Self::find_proxy(&real, &who, force_proxy_type)?;
+			ensure!(def.delay.is_zero(), "Unannounced");
+
+			Self::do_proxy(def, real, *call)?;
+		}
+
+		/// Register a proxy account for the sender that is able to make calls on its behalf.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `delegate`: The account that the sender would like to make a proxy.
+		/// - `proxy_type`: The permissions allowed for this proxy account.
+		/// - `delay`: The announcement period required of the calls made by the proxy. Will
+		///   generally be zero.
+		///
+		/// # <weight>
+		/// - `O(P)` where `P` is the number of proxies of the sender.
+		/// - Up to one balance-reserve operation.
+		/// - One storage read and write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn add_proxy(origin, delegate: T::AccountId, proxy_type: T::ProxyType, delay: T::BlockNumber) {
+			let who = ensure_signed(origin)?;
+			ensure!(who != delegate, "No self proxy");
+
+			let (mut proxies, old_deposit) = <Proxies<T>>::get(&who);
+			ensure!(proxies.len() < T::MaxProxies::get() as usize, "Too many proxies");
+			let def = ProxyDefinition { delegate, proxy_type, delay };
+			let pos = proxies.binary_search(&def).err().ok_or("Duplicate")?;
+			proxies.insert(pos, def);
+
+			let new_deposit = Self::deposit(
+				T::ProxyDepositBase::get(),
+				T::ProxyDepositFactor::get(),
+				proxies.len(),
+			);
+			Self::rereserve(&who, old_deposit, new_deposit)?;
+			<Proxies<T>>::insert(&who, (proxies, new_deposit));
+		}
+
+		/// Unregister a proxy account for the sender.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `delegate`: The account that the sender would like to remove as a proxy.
+		/// - `proxy_type`: The permissions currently enabled for the removed proxy account.
+		/// - `delay`: The announcement period of the removed proxy account.
+		///
+		/// # <weight>
+		/// - `O(P)` where `P` is the number of proxies of the sender.
+		/// - One balance-unreserve operation.
+		/// - One storage read and write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_proxy(origin, delegate: T::AccountId, proxy_type: T::ProxyType, delay: T::BlockNumber) {
+			let who = ensure_signed(origin)?;
+
+			let (mut proxies, old_deposit) = <Proxies<T>>::get(&who);
+			let def = ProxyDefinition { delegate, proxy_type, delay };
+			let pos = proxies.binary_search(&def).map_err(|_| "Not found")?;
+			proxies.remove(pos);
+
+			let new_deposit = Self::deposit(
+				T::ProxyDepositBase::get(),
+				T::ProxyDepositFactor::get(),
+				proxies.len(),
+			);
+			Self::rereserve(&who, old_deposit, new_deposit)?;
+			if proxies.is_empty() {
+				<Proxies<T>>::remove(&who);
+			} else {
+				<Proxies<T>>::insert(&who, (proxies, new_deposit));
+			}
+		}
+
+		/// Unregister all proxy accounts for the sender, returning the deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One balance-unreserve operation.
+		/// - One storage removal.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_proxies(origin) {
+			let who = ensure_signed(origin)?;
+			let (_, deposit) = <Proxies<T>>::take(&who);
+			let _ = T::Currency::unreserve(&who, deposit);
+		}
+
+		/// Publish the hash of a proxy-call that will be made in the future.
+		///
+		/// The call can then be dispatched through `proxy_announced` once the delay of the proxy
+		/// passed, unless the account rejects it in the meantime.
+		///
+		/// The dispatch origin for this call must be _Signed_, by a proxy of `real`.
+		///
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `call_hash`: The blake2-256 hash of the call to be made by the `real` account.
+		///
+		/// # <weight>
+		/// - `O(A + P)` where `A` is the number of announcements of the sender and `P` the number
+		///   of proxies of `real`.
+		/// - Up to one balance-reserve operation.
+		/// - Two storage reads and one write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn announce(origin, real: T::AccountId, call_hash: CallHash) {
+			let who = ensure_signed(origin)?;
+			ensure!(
+				<Proxies<T>>::get(&real).0.iter().any(|def| def.delegate == who),
+				"Not a proxy"
+			);
+
+			let (mut pending, old_deposit) = <Announcements<T>>::get(&who);
+			ensure!(pending.len() < T::MaxPending::get() as usize, "Too many announcements");
+			pending.push(Announcement {
+				real: real.clone(),
+				call_hash,
+				height: <system::Module<T>>::block_number(),
+			});
+
+			let new_deposit = Self::deposit(
+				T::AnnouncementDepositBase::get(),
+				T::AnnouncementDepositFactor::get(),
+				pending.len(),
+			);
+			Self::rereserve(&who, old_deposit, new_deposit)?;
+			<Announcements<T>>::insert(&who, (pending, new_deposit));
+			Self::deposit_event(RawEvent::Announced(real, who, call_hash));
+		}
+
+		/// Remove a given announcement made by the sender, returning its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `real`: The account that the proxy announced a call on behalf of.
+		/// - `call_hash`: The hash of the announced call.
+		///
+		/// # <weight>
+		/// - `O(A)` where `A` is the number of announcements of the sender.
+		/// - One balance-unreserve operation.
+		/// - One storage read and write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_announcement(origin, real: T::AccountId, call_hash: CallHash) {
+			let who = ensure_signed(origin)?;
+			Self::edit_announcements(&who, |ann| ann.real != real || ann.call_hash != call_hash)?;
+		}
+
+		/// Remove a given announcement made by a proxy of the sender, returning the deposit of
+		/// the proxy.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `delegate`: The proxy which announced the call.
+		/// - `call_hash`: The hash of the announced call.
+		///
+		/// # <weight>
+		/// - `O(A)` where `A` is the number of announcements of `delegate`.
+		/// - One balance-unreserve operation.
+		/// - One storage read and write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn reject_announcement(origin, delegate: T::AccountId, call_hash: CallHash) {
+			let who = ensure_signed(origin)?;
+			Self::edit_announcements(&delegate, |ann| ann.real != who || ann.call_hash != call_hash)?;
+		}
+
+		/// Dispatch the given `call` from an account that `delegate` is authorised for through
+		/// `add_proxy`, once the announcement of the call by `delegate` is old enough.
+		///
+		/// Removes the announcement, returning its deposit to `delegate`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `delegate`: The proxy which announced the call.
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `force_proxy_type`: Specify the exact proxy type to be used and checked for this call.
+		/// - `call`: The call to be made by the `real` account.
+		///
+		/// # <weight>
+		/// - `O(A + P)` where `A` is the number of announcements of `delegate` and `P` the number
+		///   of proxies of `real`, plus the weight of `call`.
+		/// - One balance-unreserve operation.
+		/// - Two storage reads and one write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn proxy_announced(
+			origin,
+			delegate: T::AccountId,
+			real: T::AccountId,
+			force_proxy_type: Option<T::ProxyType>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			ensure_signed(origin)?;
+			let def = Self::find_proxy(&real, &delegate, force_proxy_type)?;
+
+			let call_hash = call.using_encoded(runtime_io::blake2_256);
+			let now = <system::Module<T>>::block_number();
+			Self::edit_announcements(&delegate, |ann|
+				ann.real != real || ann.call_hash != call_hash || now.saturating_sub(ann.height) < def.delay
+			)?;
+
+			Self::do_proxy(def, real, *call)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Find the definition of the proxy `delegate` of `real`, of the given type if any.
+	fn find_proxy(
+		real: &T::AccountId,
+		delegate: &T::AccountId,
+		force_proxy_type: Option<T::ProxyType>,
+	) -> rstd::result::Result<ProxyDefinition<T::AccountId, T::ProxyType, T::BlockNumber>, &'static str> {
+		<Proxies<T>>::get(real).0.into_iter()
+			.find(|def| &def.delegate == delegate &&
+				force_proxy_type.as_ref().map_or(true, |proxy_type| &def.proxy_type == proxy_type)
+			)
+			.ok_or("Not a proxy")
+	}
+
+	/// Dispatch `call` from `real`, if the proxy is allowed to make it.
+	fn do_proxy(
+		def: ProxyDefinition<T::AccountId, T::ProxyType, T::BlockNumber>,
+		real: T::AccountId,
+		call: <T as Trait>::Call,
+	) -> support::dispatch::Result {
+		ensure!(Self::is_allowed(&def.proxy_type, &call), "Unproxyable");
+
+		let result = call.dispatch(system::RawOrigin::Signed(real).into());
+		Self::deposit_event(RawEvent::ProxyExecuted(result.map_err(Into::into)));
+		Ok(())
+	}
+
+	/// Is a proxy of the given type allowed to make the given call?
+	///
+	/// Besides the filter of the type, a proxy can't change the proxies of the account to gain
+	/// more permissions than it has.
+	fn is_allowed(proxy_type: &T::ProxyType, call: &<T as Trait>::Call) -> bool {
+		match call.is_sub_type() {
+			Some(Call::add_proxy(_, other_type, _)) | Some(Call::remove_proxy(_, other_type, _))
+				if !proxy_type.is_superset(other_type) => false,
+			Some(Call::remove_proxies()) if proxy_type != &T::ProxyType::default() => false,
+			_ => proxy_type.filter(call),
+		}
+	}
+
+	/// Keep only the announcements of `delegate` for which `f` returns `true`, returning the
+	/// deposit of the removed ones.
+	///
+	/// Fails if no announcement is removed.
+	fn edit_announcements(
+		delegate: &T::AccountId,
+		f: impl FnMut(&Announcement<T::AccountId, CallHash, T::BlockNumber>) -> bool,
+	) -> support::dispatch::Result {
+		let (mut pending, old_deposit) = <Announcements<T>>::get(delegate);
+		let len = pending.len();
+		pending.retain(f);
+		ensure!(pending.len() < len, "Not found");
+
+		let new_deposit = Self::deposit(
+			T::AnnouncementDepositBase::get(),
+			T::AnnouncementDepositFactor::get(),
+			pending.len(),
+		);
+		Self::rereserve(delegate, old_deposit, new_deposit)?;
+		if pending.is_empty() {
+			<Announcements<T>>::remove(delegate);
+		} else {
+			<Announcements<T>>::insert(delegate, (pending, new_deposit));
+		}
+		Ok(())
+	}
+
+	/// The deposit needed for the given number of items.
+	fn deposit(base: BalanceOf<T>, factor: BalanceOf<T>, count: usize) -> BalanceOf<T> {
+		if count == 0 {
+			Zero::zero()
+		} else {
+			base + factor * (count as u32).into()
+		}
+	}
+
+	/// Update the amount reserved from `who` from `old_deposit` to `new_deposit`.
+	fn rereserve(
+		who: &T::AccountId,
+		old_deposit: BalanceOf<T>,
+		new_deposit: BalanceOf<T>,
+	) -> support::dispatch::Result {
+		if new_deposit > old_deposit {
+			T::Currency::reserve(who, new_deposit - old_deposit)?;
+		} else if new_deposit < old_deposit {
+			let _ = T::Currency::unreserve(who, old_deposit - new_deposit);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch};
+	use primitives::H256;
+	use sr_primitives::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			balances::Balances,
+			proxy::Proxy,
+		}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const ProxyDepositBase: u64 = 1;
+		pub const ProxyDepositFactor: u64 = 1;
+		pub const MaxProxies: u16 = 4;
+		pub const AnnouncementDepositBase: u64 = 1;
+		pub const AnnouncementDepositFactor: u64 = 1;
+		pub const MaxPending: u32 = 2;
+	}
+	#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+	pub enum ProxyType {
+		Any,
+		JustTransfer,
+	}
+	impl Default for ProxyType {
+		fn default() -> Self { ProxyType::Any }
+	}
+	impl InstanceFilter<Call> for ProxyType {
+		fn filter(&self, c: &Call) -> bool {
+			match (self, c) {
+				(ProxyType::Any, _) => true,
+				(ProxyType::JustTransfer, Call::Balances(balances::Call::transfer(..))) => true,
+				(ProxyType::JustTransfer, _) => false,
+			}
+		}
+		fn is_superset(&self, o: &Self) -> bool {
+			self == &ProxyType::Any || self == o
+		}
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Call = Call;
+		type Currency = Balances;
+		type ProxyType = ProxyType;
+		type ProxyDepositBase = ProxyDepositBase;
+		type ProxyDepositFactor = ProxyDepositFactor;
+		type MaxProxies = MaxProxies;
+		type AnnouncementDepositBase = AnnouncementDepositBase;
+		type AnnouncementDepositFactor = AnnouncementDepositFactor;
+		type MaxPending = MaxPending;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Proxy = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10), (4, 10), (5, 10)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn transfer(to: u64, value: u64) -> Box<Call> {
+		Box::new(Call::Balances(balances::Call::transfer(to, value)))
+	}
+
+	#[test]
+	fn add_remove_proxies_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0), "Duplicate");
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::JustTransfer, 0));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::Any, 0));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 4, ProxyType::Any, 0));
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 5, ProxyType::Any, 0), "Too many proxies");
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 1, ProxyType::Any, 0), "No self proxy");
+			assert_eq!(Balances::reserved_balance(1), 5);
+
+			assert_noop!(Proxy::remove_proxy(Origin::signed(1), 5, ProxyType::Any, 0), "Not found");
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 4, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(1), 4);
+			assert_ok!(Proxy::remove_proxies(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert!(Proxy::proxies(1).0.is_empty());
+		});
+	}
+
+	#[test]
+	fn filtering_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::JustTransfer, 0));
+
+			assert_noop!(Proxy::proxy(Origin::signed(4), 1, None, transfer(6, 1)), "Not a proxy");
+			assert_ok!(Proxy::proxy(Origin::signed(3), 1, None, transfer(6, 1)));
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, Some(ProxyType::Any), transfer(6, 1)));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), 1, Some(ProxyType::JustTransfer), transfer(6, 1)),
+				"Not a proxy",
+			);
+			assert_eq!(Balances::free_balance(6), 2);
+
+			// a proxy can't give itself more permissions.
+			let add_proxy = Box::new(Call::Proxy(super::Call::add_proxy(3, ProxyType::Any, 0)));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, None, add_proxy.clone()), "Unproxyable");
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, add_proxy));
+			let remove_proxies = Box::new(Call::Proxy(super::Call::remove_proxies()));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, None, remove_proxies), "Unproxyable");
+		});
+	}
+
+	#[test]
+	fn delayed_proxies_must_announce_their_calls() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 1));
+			assert_noop!(Proxy::proxy(Origin::signed(2), 1, None, transfer(6, 1)), "Unannounced");
+
+			let call_hash = transfer(6, 1).using_encoded(runtime_io::blake2_256);
+			assert_noop!(Proxy::announce(Origin::signed(3), 1, call_hash), "Not a proxy");
+			assert_ok!(Proxy::announce(Origin::signed(2), 1, call_hash));
+			assert_eq!(Balances::reserved_balance(2), 2);
+
+			// the delay didn't pass yet.
+			assert_noop!(
+				Proxy::proxy_announced(Origin::signed(3), 2, 1, None, transfer(6, 1)),
+				"Not found",
+			);
+			System::set_block_number(2);
+			assert_noop!(
+				Proxy::proxy_announced(Origin::signed(3), 2, 1, None, transfer(6, 2)),
+				"Not found",
+			);
+			assert_ok!(Proxy::proxy_announced(Origin::signed(3), 2, 1, None, transfer(6, 1)));
+			assert_eq!(Balances::free_balance(6), 1);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert!(Proxy::announcements(2).0.is_empty());
+		});
+	}
+
+	#[test]
+	fn announcements_can_be_removed_and_rejected() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 1));
+			let call_hash = transfer(6, 1).using_encoded(runtime_io::blake2_256);
+			let other_call_hash = transfer(6, 2).using_encoded(runtime_io::blake2_256);
+			assert_ok!(Proxy::announce(Origin::signed(2), 1, call_hash));
+			assert_ok!(Proxy::announce(Origin::signed(2), 1, other_call_hash));
+			assert_noop!(Proxy::announce(Origin::signed(2), 1, call_hash), "Too many announcements");
+			assert_eq!(Balances::reserved_balance(2), 3);
+
+			assert_noop!(Proxy::reject_announcement(Origin::signed(3), 2, call_hash), "Not found");
+			assert_ok!(Proxy::reject_announcement(Origin::signed(1), 2, call_hash));
+			assert_eq!(Balances::reserved_balance(2), 2);
+			assert_ok!(Proxy::remove_announcement(Origin::signed(2), 1, other_call_hash));
+			assert_eq!(Balances::reserved_balance(2), 0);
+
+			System::set_block_number(2);
+			assert_noop!(
+				Proxy::proxy_announced(Origin::signed(3), 2, 1, None, transfer(6, 1)),
+				"Not found",
+			);
+		});
+	}
+}
@@ -0,0 +1,570 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Scheduler Module
+//!
+//! - [`scheduler::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A module for scheduling dispatches, from the _Root_ origin, at a given block in the future.
+//!
+//! A scheduled dispatch may be periodic, i.e. repeated a given number of times every given number
+//! of blocks, and may be named so that it can be looked up and cancelled later on.
+//!
+//! The dispatches scheduled for a block are made at its initialization, ordered by priority. Those
+//! of a priority lower than `HARD_DEADLINE` are only made as long as their cumulated weight stays
+//! below `MaximumWeight`; the others are postponed to the next block.
+//!
+//! Besides the dispatchable functions, the module implements the `schedule::Anon` and
+//! `schedule::Named` traits, so that other modules may schedule their own dispatches.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `schedule` - Schedule a dispatch, which may be periodic, to occur at the end of some block.
+//! * `cancel` - Cancel a scheduled dispatch, specified by block number and index.
+//! * `schedule_named` - Augments the `schedule` interface with an additional `Vec<u8>` parameter
+//!   that can be used for identification.
+//! * `cancel_named` - The named complement to the cancel function.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use sr_primitives::{
+	DispatchError, RuntimeDebug,
+	traits::{Dispatchable, One, Zero},
+	weights::{GetDispatchInfo, SimpleDispatchInfo, Weight},
+};
+use support::{
+	decl_module, decl_storage, decl_event, Parameter,
+	traits::{Get, schedule::{self, Period, Priority, HARD_DEADLINE}},
+};
+use system::ensure_root;
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The aggregated call type.
+	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin> + GetDispatchInfo;
+
+	/// The maximum weight that may be scheduled per block for any dispatchables of less priority
+	/// than `schedule::HARD_DEADLINE`.
+	type MaximumWeight: Get<Weight>;
+}
+
+/// Just a simple index for naming period tasks.
+pub type PeriodicIndex = u32;
+
+/// The location of a scheduled task that can be used to remove it.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// Information regarding an item to be executed in the future.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct Scheduled<Call, BlockNumber> {
+	/// The unique identity for this task, if there is one.
+	pub maybe_id: Option<Vec<u8>>,
+	/// This task's priority.
+	pub priority: Priority,
+	/// The call to be dispatched.
+	pub call: Call,
+	/// If the call is periodic, then this points to the information concerning that.
+	pub maybe_periodic: Option<Period<BlockNumber>>,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		/// Items to be executed, indexed by the block number that they should be executed on.
+		pub Agenda get(fn agenda):
+			map T::BlockNumber => Vec<Option<Scheduled<<T as Trait>::Call, T::BlockNumber>>>;
+
+		/// Lookup from identity to the block number and index of the task.
+		pub Lookup get(fn lookup): map Vec<u8> => Option<TaskAddress<T::BlockNumber>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where <T as system::Trait>::BlockNumber {
+		/// A task was scheduled at the given block and index.
+		Scheduled(BlockNumber, u32),
+		/// The task scheduled at the given block and index was cancelled.
+		Canceled(BlockNumber, u32),
+		/// A task was dispatched, with the given result.
+		Dispatched(TaskAddress<BlockNumber>, Option<Vec<u8>>, rstd::result::Result<(), DispatchError>),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// The maximum weight that may be scheduled per block for any dispatchables of less
+		/// priority than `schedule::HARD_DEADLINE`.
+		const MaximumWeight: Weight = T::MaximumWeight::get();
+
+		/// Anonymously schedule a task.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One storage mutation (codec `O(S)` where `S` is the number of tasks of the block).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule(origin,
+			when: T::BlockNumber,
+			maybe_periodic: Option<Period<T::BlockNumber>>,
+			priority: Priority,
+			call: Box<<T as Trait>::Call>,
+		) {
+			ensure_root(origin)?;
+			let _ = Self::do_schedule(None, when, maybe_periodic, priority, *call);
+		}
+
+		/// Cancel an anonymously scheduled task.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of tasks of the block.
+		/// - One storage mutation (codec `O(S)`).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel(origin, when: T::BlockNumber, index: u32) {
+			ensure_root(origin)?;
+			Self::do_cancel((when, index)).map_err(|_| "Not found")?;
+		}
+
+		/// Schedule a named task.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One storage read and two writes (codec `O(S)` where `S` is the number of tasks of the
+		///   block).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule_named(origin,
+			id: Vec<u8>,
+			when: T::BlockNumber,
+			maybe_periodic: Option<Period<T::BlockNumber>>,
+			priority: Priority,
+			call: Box<<T as Trait>::Call>,
+		) {
+			ensure_root(origin)?;
+			Self::do_schedule_named(id, when, maybe_periodic, priority, *call)
+				.map_err(|_| "Duplicate name")?;
+		}
+
+		/// Cancel a named scheduled task.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of tasks of the block.
+		/// - One storage read and two writes (codec `O(S)`).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel_named(origin, id: Vec<u8>) {
+			ensure_root(origin)?;
+			Self::do_cancel_named(id).map_err(|_| "Not found")?;
+		}
+
+		fn on_initialize(now: T::BlockNumber) {
+			let limit = T::MaximumWeight::get();
+			let mut queued = <Agenda<T>>::take(now).into_iter()
+				.enumerate()
+				.filter_map(|(index, s)| s.map(|inner| (index as u32, inner)))
+				.collect::<Vec<_>>();
+			queued.sort_by_key(|(_, s)| s.priority);
+
+			let mut total_weight: Weight = 0;
+			for (order, (index, mut s)) in queued.into_iter().enumerate() {
+				let next_total_weight = total_weight.saturating_add(s.call.get_dispatch_info().weight);
+				// A task is dispatched if its priority is at least `HARD_DEADLINE`, if it doesn't
+				// push the weight past the limit or if it's the first one of the block, so that
+				// any task is eventually dispatched. It's postponed to the next block otherwise.
+				if s.priority > HARD_DEADLINE && next_total_weight > limit && order > 0 {
+					Self::reschedule(now + One::one(), s);
+					continue
+				}
+				total_weight = next_total_weight;
+
+				let result = s.call.clone().dispatch(system::RawOrigin::Root.into());
+				let maybe_id = s.maybe_id.clone();
+				if let Some((period, count)) = s.maybe_periodic {
+					s.maybe_periodic = if count > 1 { Some((period, count - 1)) } else { None };
+					Self::reschedule(now + period, s);
+				} else if let Some(ref id) = s.maybe_id {
+					<Lookup<T>>::remove(id);
+				}
+				Self::deposit_event(RawEvent::Dispatched((now, index), maybe_id, result.map_err(Into::into)));
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn do_schedule(
+		maybe_id: Option<Vec<u8>>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: <T as Trait>::Call,
+	) -> TaskAddress<T::BlockNumber> {
+		// sanitize maybe_periodic
+		let maybe_periodic = maybe_periodic
+			.filter(|p| p.1 > 1 && !p.0.is_zero())
+			// Remove one from the number of repetitions since we will schedule one now.
+			.map(|(p, c)| (p, c - 1));
+		let address = Self::reschedule(when, Scheduled { maybe_id, priority, call, maybe_periodic });
+		Self::deposit_event(RawEvent::Scheduled(address.0, address.1));
+		address
+	}
+
+	fn do_schedule_named(
+		id: Vec<u8>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: <T as Trait>::Call,
+	) -> Result<TaskAddress<T::BlockNumber>, ()> {
+		// ensure id it is unique
+		if <Lookup<T>>::exists(&id) {
+			return Err(())
+		}
+		Ok(Self::do_schedule(Some(id), when, maybe_periodic, priority, call))
+	}
+
+	fn do_cancel((when, index): TaskAddress<T::BlockNumber>) -> Result<(), ()> {
+		let removed = <Agenda<T>>::mutate(when, |agenda| {
+			agenda.get_mut(index as usize).and_then(Option::take)
+		});
+		if let Some(s) = removed {
+			if let Some(id) = s.maybe_id {
+				<Lookup<T>>::remove(id);
+			}
+			Self::deposit_event(RawEvent::Canceled(when, index));
+			Ok(())
+		} else {
+			Err(())
+		}
+	}
+
+	fn do_cancel_named(id: Vec<u8>) -> Result<(), ()> {
+		let address = <Lookup<T>>::get(&id).ok_or(())?;
+		Self::do_cancel(address)
+	}
+
+	/// Add a task to the agenda of the given block, keeping the lookup of its name up to date.
+	fn reschedule(
+		when: T::BlockNumber,
+		s: Scheduled<<T as Trait>::Call, T::BlockNumber>,
+	) -> TaskAddress<T::BlockNumber> {
+		let index = <Agenda<T>>::decode_len(when).unwrap_or(0) as u32;
+		if let Some(ref id) = s.maybe_id {
+			<Lookup<T>>::insert(id, (when, index));
+		}
+		<Agenda<T>>::mutate(when, |agenda| agenda.push(Some(s)));
+		(when, index)
+	}
+}
+
+impl<T: Trait> schedule::Anon<T::BlockNumber, <T as Trait>::Call> for Module<T> {
+	type Address = TaskAddress<T::BlockNumber>;
+
+	fn schedule(
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: <T as Trait>::Call,
+	) -> Self::Address {
+		Self::do_schedule(None, when, maybe_periodic, priority, call)
+	}
+
+	fn cancel(address: Self::Address) -> Result<(), ()> {
+		Self::do_cancel(address)
+	}
+}
+
+impl<T: Trait> schedule::Named<T::BlockNumber, <T as Trait>::Call> for Module<T> {
+	type Address = TaskAddress<T::BlockNumber>;
+
+	fn schedule_named(
+		id: Vec<u8>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<Period<T::BlockNumber>>,
+		priority: Priority,
+		call: <T as Trait>::Call,
+	) -> Result<Self::Address, ()> {
+		Self::do_schedule_named(id, when, maybe_periodic, priority, call)
+	}
+
+	fn cancel_named(id: Vec<u8>) -> Result<(), ()> {
+		Self::do_cancel_named(id)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use support::{assert_ok, assert_noop, impl_outer_origin, impl_outer_dispatch, parameter_types};
+	use primitives::H256;
+	use sr_primitives::{Perbill, traits::{BlakeTwo256, IdentityLookup, OnInitialize}, testing::Header};
+
+	mod logger {
+		use super::*;
+		use std::cell::RefCell;
+
+		thread_local! {
+			static LOG: RefCell<Vec<u32>> = RefCell::new(Vec::new());
+		}
+
+		pub fn log() -> Vec<u32> {
+			LOG.with(|log| log.borrow().clone())
+		}
+
+		pub trait Trait: system::Trait {}
+
+		decl_module! {
+			pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
+				#[weight = SimpleDispatchInfo::FixedNormal(10)]
+				fn log(origin, i: u32) {
+					ensure_root(origin)?;
+					LOG.with(|log| log.borrow_mut().push(i));
+				}
+
+				#[weight = SimpleDispatchInfo::FixedNormal(600)]
+				fn log_heavy(origin, i: u32) {
+					ensure_root(origin)?;
+					LOG.with(|log| log.borrow_mut().push(i));
+				}
+			}
+		}
+	}
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			logger::Logger,
+		}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 2048;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	impl logger::Trait for Test {}
+	parameter_types! {
+		pub const MaximumWeight: Weight = 1_000;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Call = Call;
+		type MaximumWeight = MaximumWeight;
+	}
+	type System = system::Module<Test>;
+	type Scheduler = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		t.into()
+	}
+
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			System::set_block_number(System::block_number() + 1);
+			Scheduler::on_initialize(System::block_number());
+		}
+	}
+
+	fn log(i: u32) -> Box<Call> {
+		Box::new(Call::Logger(logger::Call::log(i)))
+	}
+
+	fn log_heavy(i: u32) -> Box<Call> {
+		Box::new(Call::Logger(logger::Call::log_heavy(i)))
+	}
+
+	#[test]
+	fn basic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(Scheduler::schedule(Origin::signed(1), 4, None, 127, log(42)), "RequireRootOrigin");
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, log(42)));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42]);
+			assert!(Scheduler::agenda(4).is_empty());
+		});
+	}
+
+	#[test]
+	fn periodic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			// at #4, every 3 blocks, 3 times.
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, Some((3, 3)), 127, log(42)));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(6);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(7);
+			assert_eq!(logger::log(), vec![42, 42]);
+			run_to_block(10);
+			assert_eq!(logger::log(), vec![42, 42, 42]);
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42, 42, 42]);
+		});
+	}
+
+	#[test]
+	fn cancel_named_scheduling_works_with_normal_cancel() {
+		new_test_ext().execute_with(|| {
+			// at #4.
+			assert_ok!(Scheduler::schedule_named(Origin::ROOT, 1u32.encode(), 4, None, 127, log(69)));
+			assert_noop!(
+				Scheduler::schedule_named(Origin::ROOT, 1u32.encode(), 5, None, 127, log(69)),
+				"Duplicate name",
+			);
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, log(42)));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()));
+			assert_noop!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()), "Not found");
+			assert_ok!(Scheduler::cancel(Origin::ROOT, 4, 1));
+			assert_noop!(Scheduler::cancel(Origin::ROOT, 4, 1), "Not found");
+			run_to_block(100);
+			assert!(logger::log().is_empty());
+		});
+	}
+
+	#[test]
+	fn cancel_named_periodic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			// at #4, every 3 blocks, 3 times.
+			assert_ok!(Scheduler::schedule_named(Origin::ROOT, 1u32.encode(), 4, Some((3, 3)), 127, log(42)));
+			// same id results in error.
+			assert!(<Scheduler as schedule::Named<_, _>>::schedule_named(
+				1u32.encode(), 4, None, 127, *log(69),
+			).is_err());
+			// different id is ok.
+			assert_ok!(Scheduler::schedule_named(Origin::ROOT, 2u32.encode(), 8, None, 127, log(69)));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(6);
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()));
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42, 69]);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_weight_limits() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, log_heavy(42)));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, log_heavy(69)));
+			// 69 and 42 do not fit together
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![42, 69]);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_hard_deadlines_more() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 0, log_heavy(42)));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 0, log_heavy(69)));
+			// With base weights, 69 and 42 should not fit together, but do because of hard deadlines
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42, 69]);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_priority_ordering() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 1, log(42)));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 0, log(69)));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![69, 42]);
+		});
+	}
+
+	#[test]
+	fn postponed_named_task_can_be_cancelled() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, log_heavy(42)));
+			assert_ok!(Scheduler::schedule_named(Origin::ROOT, 1u32.encode(), 4, None, 255, log_heavy(69)));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42]);
+			assert_eq!(Scheduler::lookup(1u32.encode()), Some((5, 0)));
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()));
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42]);
+		});
+	}
+}
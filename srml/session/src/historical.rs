@@ -27,10 +27,10 @@
 
 use rstd::prelude::*;
 use codec::{Encode, Decode};
-use sr_primitives::KeyTypeId;
+use sr_primitives::{KeyTypeId, RuntimeDebug};
 use sr_primitives::traits::{Convert, OpaqueKeys, Hash as HashT};
 use support::{decl_module, decl_storage};
-use support::{Parameter, print};
+use support::{Parameter, print, traits::{GetSessionNumber, GetValidatorCount}};
 use substrate_trie::{MemoryDB, Trie, TrieMut, Recorder, EMPTY_PREFIX};
 use substrate_trie::trie_types::{TrieDBMut, TrieDB};
 use super::{SessionIndex, Module as SessionModule};
@@ -267,10 +267,23 @@ impl<T: Trait> ProvingTrie<T> {
 }
 
 /// Proof of ownership of a specific key.
-#[derive(Encode, Decode, Clone)]
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub struct Proof {
 	session: SessionIndex,
 	trie_nodes: Vec<Vec<u8>>,
+	validator_count: ValidatorCount,
+}
+
+impl GetSessionNumber for Proof {
+	fn session(&self) -> SessionIndex {
+		self.session
+	}
+}
+
+impl GetValidatorCount for Proof {
+	fn validator_count(&self) -> ValidatorCount {
+		self.validator_count
+	}
 }
 
 impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId, D)>
@@ -281,6 +294,7 @@ impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId,
 
 	fn prove(key: (KeyTypeId, D)) -> Option<Self::Proof> {
 		let session = <SessionModule<T>>::current_index();
+		let validator_count = <SessionModule<T>>::validators().len() as ValidatorCount;
 		let trie = ProvingTrie::<T>::generate_for(session).ok()?;
 
 		let (id, data) = key;
@@ -288,6 +302,7 @@ impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId,
 		trie.prove(id, data.as_ref()).map(|trie_nodes| Proof {
 			session,
 			trie_nodes,
+			validator_count,
 		})
 	}
 
@@ -295,11 +310,19 @@ impl<T: Trait, D: AsRef<[u8]>> support::traits::KeyOwnerProofSystem<(KeyTypeId,
 		let (id, data) = key;
 
 		if proof.session == <SessionModule<T>>::current_index() {
+			if proof.validator_count != <SessionModule<T>>::validators().len() as ValidatorCount {
+				return None
+			}
+
 			<SessionModule<T>>::key_owner(id, data.as_ref()).and_then(|owner|
 				T::FullIdentificationOf::convert(owner.clone()).map(move |id| (owner, id))
 			)
 		} else {
-			let (root, _) = <HistoricalSessions<T>>::get(&proof.session)?;
+			let (root, validator_count) = <HistoricalSessions<T>>::get(&proof.session)?;
+			if proof.validator_count != validator_count {
+				return None
+			}
+
 			let trie = ProvingTrie::<T>::from_nodes(root, &proof.trie_nodes);
 
 			trie.query(id, data.as_ref())
@@ -345,6 +368,11 @@ mod tests {
 			// proof-checking in the same session is OK.
 			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), proof.clone()).is_some());
 
+			// but the size of the validator set can't be forged.
+			let mut forged = proof.clone();
+			forged.validator_count += 1;
+			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), forged.clone()).is_none());
+
 			set_next_validators(vec![1, 2, 4]);
 			force_new_session();
 
@@ -360,6 +388,7 @@ mod tests {
 
 			// proof-checking in the next session is also OK.
 			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), proof.clone()).is_some());
+			assert!(Historical::check_proof((DUMMY, &encoded_key_1[..]), forged).is_none());
 
 			set_next_validators(vec![1, 2, 5]);
 
@@ -108,6 +108,11 @@
 //! determined, a value is deducted from the balance of the validator and all the nominators who
 //! voted for this validator (values are deducted from the _stash_ account of the slashed entity).
 //!
+//! Slashes are applied `SlashDeferDuration` eras after they are reported, and can be cancelled in
+//! the meantime with [`cancel_deferred_slash`](./enum.Call.html#variant.cancel_deferred_slash).
+//! A stash is only slashed once for the largest of the offences committed within one of its
+//! slashing spans; see the [`slashing`](./slashing/index.html) module for details.
+//!
 //! Similar to slashing, rewards are also shared among a validator and its associated nominators.
 //! Yet, the reward funds are not always transferred to the stash account and can be configured.
 //! See [Reward Calculation](#reward-calculation) for more details.
@@ -167,7 +172,7 @@
 //!
 //! ### Reward Calculation
 //!
-//! Validators and nominators are rewarded at the end of each era. The total reward of an era is
+//! Validators and nominators are rewarded for each era. The total reward of an era is
 //! calculated using the era duration and the staking rate (the total amount of tokens staked by
 //! nominators and validators, divided by the total token supply). It aims to incentivise toward a
 //! defined staking rate. The full specification can be found
@@ -182,6 +187,11 @@
 //! [`authorship::EventHandler`](../srml_authorship/trait.EventHandler.html) to add reward points
 //! to block producer and block producer of referenced uncles.
 //!
+//! Rewards are not paid automatically. At the end of an era its total payout and the points of
+//! each validator are recorded, and anyone can then pay out a validator and its nominators with
+//! [`payout_stakers`](./enum.Call.html#variant.payout_stakers). Rewards for eras older than
+//! [`HistoryDepth`](./struct.HistoryDepth.html) can no longer be claimed.
+//!
 //! The validator and its nominator split their reward as following:
 //!
 //! The validator can declare an amount, named
@@ -250,14 +260,15 @@ mod mock;
 mod tests;
 
 pub mod inflation;
+pub mod slashing;
 
-use rstd::{prelude::*, result};
+use rstd::{prelude::*, result, collections::btree_map::BTreeMap};
 use codec::{HasCompact, Encode, Decode};
 use support::{
 	decl_module, decl_event, decl_storage, ensure,
 	traits::{
 		Currency, OnFreeBalanceZero, LockIdentifier, LockableCurrency,
-		WithdrawReasons, OnUnbalanced, Imbalance, Get, Time
+		WithdrawReasons, OnUnbalanced, Imbalance, Get, Time, EnsureOrigin,
 	}
 };
 use session::{historical::OnSessionEnding, SelectInitialValidators};
@@ -313,6 +324,16 @@ impl EraPoints {
 	}
 }
 
+/// Reward points of a finished era, keyed by validator stash. Used to split the era payout
+/// when validators claim their rewards.
+#[derive(PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct EraRewardPoints<AccountId: Ord> {
+	/// Total number of points. Equals the sum of reward points for each validator.
+	pub total: Points,
+	/// The reward points earned by a given validator.
+	pub individual: BTreeMap<AccountId, Points>,
+}
+
 /// Indicates the initial status of the staker.
 #[derive(RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -431,14 +452,6 @@ pub struct Exposure<AccountId, Balance: HasCompact> {
 	pub others: Vec<IndividualExposure<AccountId, Balance>>,
 }
 
-/// A slashing event occurred, slashing a validator for a given amount of balance.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode, Default, RuntimeDebug)]
-pub struct SlashJournalEntry<AccountId, Balance: HasCompact> {
-	who: AccountId,
-	amount: Balance,
-	own_slash: Balance, // the amount of `who`'s own exposure that was slashed
-}
-
 pub type BalanceOf<T> =
 	<<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 type PositiveImbalanceOf<T> =
@@ -519,6 +532,14 @@ pub trait Trait: system::Trait {
 	/// Number of eras that staked funds must remain bonded for.
 	type BondingDuration: Get<EraIndex>;
 
+	/// Number of eras that slashes are deferred by after being computed. This should be less
+	/// than the bonding duration. Set to 0 to apply slashes immediately, without any opportunity
+	/// for intervention.
+	type SlashDeferDuration: Get<EraIndex>;
+
+	/// The origin which can cancel a deferred slash. Root can always do this.
+	type SlashCancelOrigin: EnsureOrigin<Self::Origin>;
+
 	/// Interface for interacting with a session module.
 	type SessionInterface: self::SessionInterface<Self::AccountId>;
 
@@ -594,6 +615,35 @@ decl_storage! {
 		/// Rewards for the current era. Using indices of current elected set.
 		CurrentEraPointsEarned get(fn current_era_reward): EraPoints;
 
+		/// Number of eras for which rewards can still be claimed.
+		///
+		/// Per-era information is kept for eras in `[current_era - history_depth; current_era]`
+		/// and pruned at the start of each new era.
+		pub HistoryDepth get(fn history_depth): EraIndex = 84;
+
+		/// Exposure of each validator, snapshotted when the era's validator set was elected.
+		///
+		/// Keyed by era index and then by validator stash.
+		pub ErasStakers get(fn eras_stakers):
+			double_map EraIndex, twox_128(T::AccountId) => Exposure<T::AccountId, BalanceOf<T>>;
+
+		/// Preferences of each validator, snapshotted when the era's validator set was elected.
+		///
+		/// Keyed by era index and then by validator stash.
+		pub ErasValidatorPrefs get(fn eras_validator_prefs):
+			double_map EraIndex, twox_128(T::AccountId) => ValidatorPrefs<BalanceOf<T>>;
+
+		/// The total payout set aside for validators and nominators of a finished era.
+		pub ErasValidatorReward get(fn eras_validator_reward): map EraIndex => Option<BalanceOf<T>>;
+
+		/// Reward points earned by each validator in a finished era.
+		pub ErasRewardPoints get(fn eras_reward_points): map EraIndex => EraRewardPoints<T::AccountId>;
+
+		/// Whether the reward of a validator (and its nominators) for an era has been paid out.
+		///
+		/// Keyed by era index and then by validator stash.
+		pub ErasRewardClaimed get(fn eras_reward_claimed): double_map EraIndex, twox_128(T::AccountId) => bool;
+
 		/// The amount of balance actively at stake for each validator slot, currently.
 		///
 		/// This is used to derive rewards and punishments.
@@ -612,9 +662,16 @@ decl_storage! {
 		/// A mapping from still-bonded eras to the first session index of that era.
 		BondedEras: Vec<(EraIndex, SessionIndex)>;
 
-		/// All slashes that have occurred in a given era.
-		EraSlashJournal get(fn era_slash_journal):
-			map EraIndex => Vec<SlashJournalEntry<T::AccountId, BalanceOf<T>>>;
+		/// All slashes that are yet to be applied, keyed by the era they are applied at.
+		pub UnappliedSlashes get(fn unapplied_slashes):
+			map EraIndex => Vec<slashing::UnappliedSlash<T::AccountId, BalanceOf<T>>>;
+
+		/// The slashing spans of the stashes that have been slashed.
+		pub SlashingSpans get(fn slashing_spans): map T::AccountId => Option<slashing::SlashingSpans>;
+
+		/// The largest slash of a stash in each of its tracked spans, keyed by stash and span index.
+		pub SpanSlash get(fn span_slash):
+			map (T::AccountId, slashing::SpanIndex) => slashing::SpanRecord<BalanceOf<T>>;
 	}
 	add_extra_genesis {
 		config(stakers):
@@ -652,11 +709,15 @@ decl_storage! {
 
 decl_event!(
 	pub enum Event<T> where Balance = BalanceOf<T>, <T as system::Trait>::AccountId {
-		/// All validators have been rewarded by the first balance; the second is the remainder
-		/// from the maximum amount of reward.
-		Reward(Balance, Balance),
+		/// The payout of an era has been set aside for its validators and nominators to claim,
+		/// by the first balance; the second is the remainder from the maximum amount of reward.
+		EraPayout(EraIndex, Balance, Balance),
+		/// A staker has been paid the given amount of reward.
+		Reward(AccountId, Balance),
 		/// One validator (and its nominators) has been slashed by the given amount.
 		Slash(AccountId, Balance),
+		/// The slash of one validator (and its nominators) has been deferred to the given era.
+		SlashDeferred(AccountId, EraIndex),
 		/// An old slashing report from a prior era was discarded because it could
 		/// not be processed.
 		OldSlashingReportDiscarded(SessionIndex),
@@ -671,6 +732,9 @@ decl_module! {
 		/// Number of eras that staked funds must remain bonded for.
 		const BondingDuration: EraIndex = T::BondingDuration::get();
 
+		/// Number of eras that slashes are deferred by after being computed.
+		const SlashDeferDuration: EraIndex = T::SlashDeferDuration::get();
+
 		fn deposit_event() = default;
 
 		fn on_finalize() {
@@ -1018,6 +1082,82 @@ decl_module! {
 			ensure_root(origin)?;
 			ForceEra::put(Forcing::ForceAlways);
 		}
+
+		/// Cancel the deferred slashes that are to be applied at `era`, given by their indices in
+		/// `UnappliedSlashes`.
+		///
+		/// `slash_indices` must be sorted and free of duplicates. The span records of the cancelled
+		/// slashes are reverted, so the stashes can be slashed again for their offences.
+		///
+		/// The dispatch origin for this call must be `SlashCancelOrigin` or _Root_.
+		///
+		/// # <weight>
+		/// - O(S + N) where S is the number of slashes deferred to `era` and N the number of stashes
+		///   in the cancelled slashes.
+		/// - One storage read and one write, plus two reads and one write per cancelled stash.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FreeOperational]
+		fn cancel_deferred_slash(origin, era: EraIndex, slash_indices: Vec<u32>) {
+			T::SlashCancelOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)
+				.map_err(|_| "bad origin")?;
+
+			ensure!(!slash_indices.is_empty(), "no slash to cancel");
+			ensure!(
+				slash_indices.windows(2).all(|pair| pair[0] < pair[1]),
+				"slash indices are not sorted and unique",
+			);
+
+			let mut unapplied = <UnappliedSlashes<T>>::get(era);
+			let last = *slash_indices.last().expect("slash_indices is not empty; qed");
+			ensure!((last as usize) < unapplied.len(), "invalid slash index");
+
+			for index in slash_indices.into_iter().rev() {
+				let cancelled = unapplied.remove(index as usize);
+				slashing::revert_slash::<T>(&cancelled);
+			}
+
+			<UnappliedSlashes<T>>::insert(era, &unapplied);
+		}
+
+		/// Pay out the reward of the validator `validator_stash` and its nominators for the
+		/// finished era `era`.
+		///
+		/// Anyone may claim on behalf of the stakers; each of them is paid into its own reward
+		/// destination. A reward can only be claimed once, and only while `era` is within
+		/// `HistoryDepth` eras of the current one.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(N) where N is the number of nominators backing the validator in `era`.
+		/// - Four storage reads of the era's information, one write to mark it as claimed.
+		/// - One balance deposit per staker, plus a ledger write for each `Staked` payee.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn payout_stakers(origin, validator_stash: T::AccountId, era: EraIndex) {
+			ensure_signed(origin)?;
+			Self::do_payout_stakers(validator_stash, era)?;
+		}
+
+		/// Set the number of eras for which rewards can be claimed.
+		///
+		/// Lowering it prunes the information of the eras that fall out of the new history.
+		///
+		/// # <weight>
+		/// - O(E) where E is the number of pruned eras.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FreeOperational]
+		fn set_history_depth(origin, #[compact] new_history_depth: EraIndex) {
+			ensure_root(origin)?;
+			let current_era = Self::current_era();
+			let first_kept = current_era.saturating_sub(new_history_depth);
+			for era in current_era.saturating_sub(Self::history_depth())..first_kept {
+				Self::clear_era_information(era);
+			}
+			HistoryDepth::put(new_history_depth);
+		}
 	}
 }
 
@@ -1048,74 +1188,11 @@ impl<T: Trait> Module<T> {
 		<Ledger<T>>::insert(controller, ledger);
 	}
 
-	/// Slash a given validator by a specific amount with given (historical) exposure.
-	///
-	/// Removes the slash from the validator's balance by preference,
-	/// and reduces the nominators' balance if needed.
-	///
-	/// Returns the resulting `NegativeImbalance` to allow distributing the slashed amount and
-	/// pushes an entry onto the slash journal.
-	fn slash_validator(
-		stash: &T::AccountId,
-		slash: BalanceOf<T>,
-		exposure: &Exposure<T::AccountId, BalanceOf<T>>,
-		journal: &mut Vec<SlashJournalEntry<T::AccountId, BalanceOf<T>>>,
-	) -> NegativeImbalanceOf<T> {
-		// The amount we are actually going to slash (can't be bigger than the validator's total
-		// exposure)
-		let slash = slash.min(exposure.total);
-
-		// limit what we'll slash of the stash's own to only what's in
-		// the exposure.
-		//
-		// note: this is fine only because we limit reports of the current era.
-		// otherwise, these funds may have already been slashed due to something
-		// reported from a prior era.
-		let already_slashed_own = journal.iter()
-			.filter(|entry| &entry.who == stash)
-			.map(|entry| entry.own_slash)
-			.fold(<BalanceOf<T>>::zero(), |a, c| a.saturating_add(c));
-
-		let own_remaining = exposure.own.saturating_sub(already_slashed_own);
-
-		// The amount we'll slash from the validator's stash directly.
-		let own_slash = own_remaining.min(slash);
-		let (mut imbalance, missing) = T::Currency::slash(stash, own_slash);
-		let own_slash = own_slash - missing;
-		// The amount remaining that we can't slash from the validator,
-		// that must be taken from the nominators.
-		let rest_slash = slash - own_slash;
-		if !rest_slash.is_zero() {
-			// The total to be slashed from the nominators.
-			let total = exposure.total - exposure.own;
-			if !total.is_zero() {
-				for i in exposure.others.iter() {
-					let per_u64 = Perbill::from_rational_approximation(i.value, total);
-					// best effort - not much that can be done on fail.
-					imbalance.subsume(T::Currency::slash(&i.who, per_u64 * rest_slash).0)
-				}
-			}
-		}
-
-		journal.push(SlashJournalEntry {
-			who: stash.clone(),
-			own_slash: own_slash.clone(),
-			amount: slash,
-		});
-
-		// trigger the event
-		Self::deposit_event(
-			RawEvent::Slash(stash.clone(), slash)
-		);
-
-		imbalance
-	}
-
 	/// Actually make a payment to a staker. This uses the currency's reward function
 	/// to pay the right payee for the given staker account.
 	fn make_payout(stash: &T::AccountId, amount: BalanceOf<T>) -> Option<PositiveImbalanceOf<T>> {
 		let dest = Self::payee(stash);
-		match dest {
+		let imbalance = match dest {
 			RewardDestination::Controller => Self::bonded(stash)
 				.and_then(|controller|
 					T::Currency::deposit_into_existing(&controller, amount).ok()
@@ -1131,20 +1208,46 @@ impl<T: Trait> Module<T> {
 					Self::update_ledger(&controller, &l);
 					r
 				}),
+		};
+		if let Some(ref imbalance) = imbalance {
+			Self::deposit_event(RawEvent::Reward(stash.clone(), imbalance.peek()));
 		}
+		imbalance
+	}
+
+	/// Pay out the reward of `validator_stash` and its nominators for the finished `era`.
+	fn do_payout_stakers(validator_stash: T::AccountId, era: EraIndex) -> result::Result<(), &'static str> {
+		let current_era = Self::current_era();
+		ensure!(era < current_era, "era has not finished yet");
+		ensure!(era >= current_era.saturating_sub(Self::history_depth()), "era is out of history");
+		let era_payout = <ErasValidatorReward<T>>::get(era).ok_or("era has no payout")?;
+		ensure!(!<ErasRewardClaimed<T>>::get(era, &validator_stash), "reward already claimed");
+
+		let era_reward_points = <ErasRewardPoints<T>>::get(era);
+		let validator_points = era_reward_points.individual.get(&validator_stash).cloned()
+			.unwrap_or_else(Zero::zero);
+		ensure!(!validator_points.is_zero(), "validator earned no points in this era");
+
+		<ErasRewardClaimed<T>>::insert(era, &validator_stash, true);
+
+		let reward = Perbill::from_rational_approximation(validator_points, era_reward_points.total)
+			* era_payout;
+		T::Reward::on_unbalanced(Self::reward_validator(era, &validator_stash, reward));
+
+		Ok(())
 	}
 
 	/// Reward a given validator by a specific amount. Add the reward to the validator's, and its
-	/// nominators' balance, pro-rata based on their exposure, after having removed the validator's
-	/// pre-payout cut.
-	fn reward_validator(stash: &T::AccountId, reward: BalanceOf<T>) -> PositiveImbalanceOf<T> {
-		let off_the_table = reward.min(Self::validators(stash).validator_payment);
+	/// nominators' balance, pro-rata based on their exposure in `era`, after having removed the
+	/// validator's pre-payout cut.
+	fn reward_validator(era: EraIndex, stash: &T::AccountId, reward: BalanceOf<T>) -> PositiveImbalanceOf<T> {
+		let off_the_table = reward.min(<ErasValidatorPrefs<T>>::get(era, stash).validator_payment);
 		let reward = reward - off_the_table;
 		let mut imbalance = <PositiveImbalanceOf<T>>::zero();
 		let validator_cut = if reward.is_zero() {
 			Zero::zero()
 		} else {
-			let exposure = Self::stakers(stash);
+			let exposure = <ErasStakers<T>>::get(era, stash);
 			let total = exposure.total.max(One::one());
 
 			for i in &exposure.others {
@@ -1186,7 +1289,8 @@ impl<T: Trait> Module<T> {
 	/// NOTE: This always happens immediately before a session change to ensure that new validators
 	/// get a chance to set their session keys.
 	fn new_era(start_session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
-		// Payout
+		// Set aside the payout of the era; it is claimed through `payout_stakers`.
+		let era = Self::current_era();
 		let points = CurrentEraPointsEarned::take();
 		let now = T::Time::now();
 		let previous_era_start = <CurrentEraStart<T>>::mutate(|v| {
@@ -1207,30 +1311,36 @@ impl<T: Trait> Module<T> {
 				era_duration.saturated_into::<u64>(),
 			);
 
-			let mut total_imbalance = <PositiveImbalanceOf<T>>::zero();
-
-			for (v, p) in validators.iter().zip(points.individual.into_iter()) {
-				if p != 0 {
-					let reward = Perbill::from_rational_approximation(p, points.total) * total_payout;
-					total_imbalance.subsume(Self::reward_validator(v, reward));
-				}
-			}
+			// Nobody can claim the payout if no points were earned.
+			let total_payout = if points.total.is_zero() { Zero::zero() } else { total_payout };
 
-			// assert!(total_imbalance.peek() == total_payout)
-			let total_payout = total_imbalance.peek();
+			let individual = validators.into_iter()
+				.zip(points.individual.into_iter())
+				.filter(|&(_, p)| p != 0)
+				.collect();
+			<ErasRewardPoints<T>>::insert(era, EraRewardPoints { total: points.total, individual });
+			<ErasValidatorReward<T>>::insert(era, total_payout);
 
 			let rest = max_payout.saturating_sub(total_payout);
-			Self::deposit_event(RawEvent::Reward(total_payout, rest));
+			Self::deposit_event(RawEvent::EraPayout(era, total_payout, rest));
 
-			T::Reward::on_unbalanced(total_imbalance);
 			T::RewardRemainder::on_unbalanced(T::Currency::issue(rest));
 		}
 
 		// Increment current era.
 		let current_era = CurrentEra::mutate(|s| { *s += 1; *s });
 
-		// prune journal for last era.
-		<EraSlashJournal<T>>::remove(current_era - 1);
+		// prune the information of the era falling out of history.
+		let history_depth = Self::history_depth();
+		if current_era > history_depth {
+			Self::clear_era_information(current_era - history_depth - 1);
+		}
+
+		// apply the slashes that were deferred to this era.
+		let slash_reward_fraction = SlashRewardFraction::get();
+		for unapplied in <UnappliedSlashes<T>>::take(current_era) {
+			slashing::apply_slash::<T>(unapplied, slash_reward_fraction);
+		}
 
 		CurrentEraStartSessionIndex::mutate(|v| {
 			*v = start_session_index;
@@ -1324,7 +1434,9 @@ impl<T: Trait> Module<T> {
 				<Stakers<T>>::remove(v);
 			}
 
-			// Populate Stakers and figure out the minimum stake behind a slot.
+			// Populate Stakers, snapshot them for the era and figure out the minimum stake behind
+			// a slot.
+			let era = Self::current_era();
 			let mut slot_stake = BalanceOf::<T>::max_value();
 			for (c, s) in supports.into_iter() {
 				// build `struct exposure` from `support`
@@ -1343,7 +1455,9 @@ impl<T: Trait> Module<T> {
 				if exposure.total < slot_stake {
 					slot_stake = exposure.total;
 				}
-				<Stakers<T>>::insert(&c, exposure.clone());
+				<ErasStakers<T>>::insert(era, &c, &exposure);
+				<ErasValidatorPrefs<T>>::insert(era, &c, Self::validators(&c));
+				<Stakers<T>>::insert(&c, exposure);
 			}
 
 			// Update slot stake.
@@ -1368,6 +1482,15 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Remove the per-era information kept for claiming the rewards of `era`.
+	fn clear_era_information(era: EraIndex) {
+		<ErasStakers<T>>::remove_prefix(era);
+		<ErasValidatorPrefs<T>>::remove_prefix(era);
+		<ErasValidatorReward<T>>::remove(era);
+		<ErasRewardPoints<T>>::remove(era);
+		<ErasRewardClaimed<T>>::remove_prefix(era);
+	}
+
 	/// Remove all associated data of a stash account from the staking system.
 	///
 	/// This is called :
@@ -1380,6 +1503,7 @@ impl<T: Trait> Module<T> {
 		<Payee<T>>::remove(stash);
 		<Validators<T>>::remove(stash);
 		<Nominators<T>>::remove(stash);
+		slashing::clear_stash_metadata::<T>(stash);
 	}
 
 	/// Add reward points to validators using their stash account ID.
@@ -1513,11 +1637,10 @@ impl <T: Trait> OnOffenceHandler<T::AccountId, session::historical::Identificati
 		offenders: &[OffenceDetails<T::AccountId, session::historical::IdentificationTuple<T>>],
 		slash_fraction: &[Perbill],
 	) {
-		let mut remaining_imbalance = <NegativeImbalanceOf<T>>::zero();
 		let slash_reward_fraction = SlashRewardFraction::get();
+		let slash_defer_duration = T::SlashDeferDuration::get();
 
 		let era_now = Self::current_era();
-		let mut journal = Self::era_slash_journal(era_now);
 		for (details, slash_fraction) in offenders.iter().zip(slash_fraction) {
 			let stash = &details.offender.0;
 			let exposure = &details.offender.1;
@@ -1534,46 +1657,34 @@ impl <T: Trait> OnOffenceHandler<T::AccountId, session::historical::Identificati
 				Self::ensure_new_era();
 			}
 
-			// calculate the amount to slash
-			let slash_exposure = exposure.total;
-			let amount = *slash_fraction * slash_exposure;
-			// in some cases `slash_fraction` can be just `0`,
-			// which means we are not slashing this time.
-			if amount.is_zero() {
-				continue;
-			}
+			// calculate the amount to slash. In some cases `slash_fraction` can be just `0`, or the
+			// offence was already punished in the same span, which means we are not slashing
+			// this time.
+			let unapplied = match slashing::compute_slash::<T>(
+				era_now,
+				stash,
+				exposure,
+				*slash_fraction,
+				details.reporters.clone(),
+			) {
+				Some(unapplied) => unapplied,
+				None => continue,
+			};
 
 			// make sure to disable validator till the end of this session
 			if T::SessionInterface::disable_validator(stash).unwrap_or(false) {
 				// force a new era, to select a new validator set
 				Self::ensure_new_era();
 			}
-			// actually slash the validator
-			let slashed_amount = Self::slash_validator(stash, amount, exposure, &mut journal);
-
-			// distribute the rewards according to the slash
-			let slash_reward = slash_reward_fraction * slashed_amount.peek();
-			if !slash_reward.is_zero() && !details.reporters.is_empty() {
-				let (mut reward, rest) = slashed_amount.split(slash_reward);
-				// split the reward between reporters equally. Division cannot fail because
-				// we guarded against it in the enclosing if.
-				let per_reporter = reward.peek() / (details.reporters.len() as u32).into();
-				for reporter in &details.reporters {
-					let (reporter_reward, rest) = reward.split(per_reporter);
-					reward = rest;
-					T::Currency::resolve_creating(reporter, reporter_reward);
-				}
-				// The rest goes to the treasury.
-				remaining_imbalance.subsume(reward);
-				remaining_imbalance.subsume(rest);
+
+			if slash_defer_duration == 0 {
+				slashing::apply_slash::<T>(unapplied, slash_reward_fraction);
 			} else {
-				remaining_imbalance.subsume(slashed_amount);
+				let apply_at = era_now + slash_defer_duration;
+				Self::deposit_event(RawEvent::SlashDeferred(stash.clone(), apply_at));
+				<UnappliedSlashes<T>>::mutate(apply_at, |slashes| slashes.push(unapplied));
 			}
 		}
-		<EraSlashJournal<T>>::insert(era_now, journal);
-
-		// Handle the rest of imbalances
-		T::Slash::on_unbalanced(remaining_imbalance);
 	}
 }
 
@@ -0,0 +1,269 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Slashing spans and the computation and application of slashes.
+//!
+//! A slashing span of a stash starts when it becomes exposed and ends when the stash is slashed
+//! as a validator. Within a span, a stash is only slashed for the largest of the offences it is
+//! punished for rather than for their sum, so that a single fault that is reported several times
+//! does not slash the stash repeatedly.
+//!
+//! Slashes are computed as soon as an offence is reported, but may be deferred by
+//! `SlashDeferDuration` eras before they are applied, giving governance the opportunity to
+//! cancel slashes caused by bugs.
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use sr_primitives::{Perbill, RuntimeDebug, traits::{Zero, Saturating}};
+use support::traits::{Currency, Imbalance, OnUnbalanced, Get};
+use crate::{
+	Module, Trait, EraIndex, Exposure, BalanceOf, NegativeImbalanceOf, RawEvent, SpanSlash,
+};
+
+/// Counter for the slashing spans of a stash.
+pub type SpanIndex = u32;
+
+/// The slashing spans of a stash.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct SlashingSpans {
+	/// The index of the current span.
+	pub span_index: SpanIndex,
+	/// The era at which the current span started.
+	pub last_start: EraIndex,
+	/// The eras at which the prior spans that are still tracked started, most recent last.
+	pub prior: Vec<EraIndex>,
+}
+
+impl SlashingSpans {
+	/// Slashing spans with a single span starting at `start`.
+	pub(crate) fn new(start: EraIndex) -> Self {
+		SlashingSpans { span_index: 0, last_start: start, prior: Vec::new() }
+	}
+
+	/// End the current span at `now`; the next one starts at the following era.
+	///
+	/// Returns `false` if the current span has already been ended at `now`.
+	pub(crate) fn end_span(&mut self, now: EraIndex) -> bool {
+		let next_start = now.saturating_add(1);
+		if next_start <= self.last_start {
+			return false;
+		}
+
+		self.prior.push(self.last_start);
+		self.last_start = next_start;
+		self.span_index += 1;
+		true
+	}
+
+	/// The index of the span containing `era`, if it is still tracked.
+	pub(crate) fn span_for_era(&self, era: EraIndex) -> Option<SpanIndex> {
+		if era >= self.last_start {
+			return Some(self.span_index);
+		}
+
+		self.prior.iter()
+			.rev()
+			.position(|&start| era >= start)
+			.map(|back| self.span_index - 1 - back as SpanIndex)
+	}
+
+	/// Stop tracking the prior spans that ended before `first_kept`.
+	///
+	/// Returns the range of the indices of the spans that are no longer tracked.
+	pub(crate) fn prune(&mut self, first_kept: EraIndex) -> rstd::ops::Range<SpanIndex> {
+		let oldest = self.span_index - self.prior.len() as SpanIndex;
+		// each prior span ends where the following one starts.
+		let n_to_prune = self.prior.iter()
+			.skip(1)
+			.chain(rstd::iter::once(&self.last_start))
+			.take(self.prior.len())
+			.take_while(|&&end| end <= first_kept)
+			.count();
+		self.prior.drain(..n_to_prune);
+		oldest..oldest + n_to_prune as SpanIndex
+	}
+}
+
+/// The total amount a stash has been slashed for in a span.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct SpanRecord<Balance> {
+	/// The largest slash the stash has been punished for in the span.
+	pub slashed: Balance,
+}
+
+/// A slash that has been computed but not applied yet.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct UnappliedSlash<AccountId, Balance> {
+	/// The stash of the slashed validator.
+	pub validator: AccountId,
+	/// The amount slashed from the validator's own stake.
+	pub own: Balance,
+	/// The amounts slashed from the nominators backing the validator, keyed by stash.
+	pub others: Vec<(AccountId, Balance)>,
+	/// The reporters of the offence, who share the slash reward.
+	pub reporters: Vec<AccountId>,
+	/// The era in which the slash was computed, whose spans recorded it.
+	pub era: EraIndex,
+}
+
+/// Compute the slash of a validator, and its nominators, for an offence committed in `era_now`
+/// with the given `exposure`.
+///
+/// The slash of each stash only includes what exceeds the slashes it has already been punished
+/// for in the same span. The span of the validator is then ended. Returns `None` if there is
+/// nothing to slash.
+pub(crate) fn compute_slash<T: Trait>(
+	era_now: EraIndex,
+	stash: &T::AccountId,
+	exposure: &Exposure<T::AccountId, BalanceOf<T>>,
+	slash_fraction: Perbill,
+	reporters: Vec<T::AccountId>,
+) -> Option<UnappliedSlash<T::AccountId, BalanceOf<T>>> {
+	let slash = slash_fraction * exposure.total;
+	if slash.is_zero() {
+		return None;
+	}
+
+	// The validator's own stake is slashed first, the rest is taken from the nominators pro-rata.
+	let own_slash = exposure.own.min(slash);
+	let rest_slash = slash - own_slash;
+	let nominators_total = exposure.total.saturating_sub(exposure.own);
+
+	let own = slash_in_span::<T>(stash, own_slash, era_now, true);
+	let others = if rest_slash.is_zero() || nominators_total.is_zero() {
+		Vec::new()
+	} else {
+		exposure.others.iter()
+			.map(|i| {
+				let per_u64 = Perbill::from_rational_approximation(i.value, nominators_total);
+				let amount = slash_in_span::<T>(&i.who, per_u64 * rest_slash, era_now, false);
+				(i.who.clone(), amount)
+			})
+			.filter(|&(_, amount)| !amount.is_zero())
+			.collect()
+	};
+
+	if own.is_zero() && others.is_empty() {
+		return None;
+	}
+
+	Some(UnappliedSlash { validator: stash.clone(), own, others, reporters, era: era_now })
+}
+
+/// Record a slash of `target` against `stash` in the span containing `era_now`, and return the
+/// part of it that exceeds what the stash has already been slashed for in that span.
+fn slash_in_span<T: Trait>(
+	stash: &T::AccountId,
+	target: BalanceOf<T>,
+	era_now: EraIndex,
+	end_span: bool,
+) -> BalanceOf<T> {
+	let mut spans = <Module<T>>::slashing_spans(stash).unwrap_or_else(|| SlashingSpans::new(0));
+
+	let first_kept = era_now.saturating_sub(T::BondingDuration::get());
+	for pruned in spans.prune(first_kept) {
+		<SpanSlash<T>>::remove(&(stash.clone(), pruned));
+	}
+
+	let span = spans.span_for_era(era_now).unwrap_or(spans.span_index);
+	let key = (stash.clone(), span);
+	let mut record = <SpanSlash<T>>::get(&key);
+	let extra = target.saturating_sub(record.slashed);
+	if !extra.is_zero() {
+		record.slashed = target;
+		<SpanSlash<T>>::insert(&key, record);
+	}
+
+	if end_span && !target.is_zero() {
+		spans.end_span(era_now);
+	}
+	<crate::SlashingSpans<T>>::insert(stash, spans);
+
+	extra
+}
+
+/// Revert the span records of a slash which is cancelled before being applied, so that the
+/// stashes are slashed in full for the next offence punished in the same spans.
+pub(crate) fn revert_slash<T: Trait>(unapplied: &UnappliedSlash<T::AccountId, BalanceOf<T>>) {
+	unslash_in_span::<T>(&unapplied.validator, unapplied.own, unapplied.era);
+	for (nominator, amount) in &unapplied.others {
+		unslash_in_span::<T>(nominator, *amount, unapplied.era);
+	}
+}
+
+/// Remove `amount` from the record of `stash` in the span containing `era`, if it's still tracked.
+fn unslash_in_span<T: Trait>(stash: &T::AccountId, amount: BalanceOf<T>, era: EraIndex) {
+	let span = match <Module<T>>::slashing_spans(stash).and_then(|spans| spans.span_for_era(era)) {
+		Some(span) => span,
+		None => return,
+	};
+	let key = (stash.clone(), span);
+	let remaining = <SpanSlash<T>>::get(&key).slashed.saturating_sub(amount);
+	if remaining.is_zero() {
+		<SpanSlash<T>>::remove(&key);
+	} else {
+		<SpanSlash<T>>::insert(&key, SpanRecord { slashed: remaining });
+	}
+}
+
+/// Apply a previously computed slash, rewarding its reporters with the given fraction of the
+/// slashed amount.
+pub(crate) fn apply_slash<T: Trait>(
+	unapplied: UnappliedSlash<T::AccountId, BalanceOf<T>>,
+	slash_reward_fraction: Perbill,
+) {
+	let (mut slashed, _) = T::Currency::slash(&unapplied.validator, unapplied.own);
+	for (nominator, amount) in &unapplied.others {
+		// best effort - not much that can be done on fail.
+		slashed.subsume(T::Currency::slash(nominator, *amount).0);
+	}
+
+	let total = unapplied.others.iter()
+		.fold(unapplied.own, |acc, &(_, amount)| acc.saturating_add(amount));
+	<Module<T>>::deposit_event(RawEvent::Slash(unapplied.validator, total));
+
+	// distribute the rewards according to the slash
+	let slash_reward = slash_reward_fraction * slashed.peek();
+	let mut remaining_imbalance = <NegativeImbalanceOf<T>>::zero();
+	if !slash_reward.is_zero() && !unapplied.reporters.is_empty() {
+		let (mut reward, rest) = slashed.split(slash_reward);
+		// split the reward between reporters equally. Division cannot fail because
+		// we guarded against it in the enclosing if.
+		let per_reporter = reward.peek() / (unapplied.reporters.len() as u32).into();
+		for reporter in &unapplied.reporters {
+			let (reporter_reward, rest) = reward.split(per_reporter);
+			reward = rest;
+			T::Currency::resolve_creating(reporter, reporter_reward);
+		}
+		// The rest goes to the treasury.
+		remaining_imbalance.subsume(reward);
+		remaining_imbalance.subsume(rest);
+	} else {
+		remaining_imbalance.subsume(slashed);
+	}
+
+	T::Slash::on_unbalanced(remaining_imbalance);
+}
+
+/// Remove the slashing spans of `stash`, and the records of its tracked spans.
+pub(crate) fn clear_stash_metadata<T: Trait>(stash: &T::AccountId) {
+	if let Some(spans) = <crate::SlashingSpans<T>>::take(stash) {
+		let oldest = spans.span_index - spans.prior.len() as SpanIndex;
+		for span in oldest..=spans.span_index {
+			<SpanSlash<T>>::remove(&(stash.clone(), span));
+		}
+	}
+}
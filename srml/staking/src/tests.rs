@@ -219,6 +219,10 @@ fn rewards_should_work() {
 		assert_eq!(Staking::current_era(), 1);
 		assert_eq!(Session::current_index(), 3);
 
+		// No reward until it is claimed.
+		assert_eq!(Balances::total_balance(&10), init_balance_10);
+		make_all_reward_payment(0);
+
 		// 11 validator has 2/3 of the total rewards and half half for it and its nominator
 		assert_eq_error_rate!(Balances::total_balance(&2), init_balance_2 + total_payout / 3, 1);
 		assert_eq_error_rate!(Balances::total_balance(&10), init_balance_10 + total_payout / 3, 1);
@@ -248,6 +252,7 @@ fn multi_era_reward_should_work() {
 		start_session(3);
 
 		assert_eq!(Staking::current_era(), 1);
+		make_all_reward_payment(0);
 		assert_eq!(Balances::total_balance(&10), init_balance_10 + total_payout_0);
 
 		start_session(4);
@@ -258,6 +263,7 @@ fn multi_era_reward_should_work() {
 
 		// new era is triggered here.
 		start_session(5);
+		make_all_reward_payment(1);
 
 		// pay time
 		assert_eq!(Balances::total_balance(&10), init_balance_10 + total_payout_0 + total_payout_1);
@@ -462,6 +468,7 @@ fn nominating_and_rewards_should_work() {
 			<Module<Test>>::reward_by_ids(vec![(11, 10)]); // must be no-op
 
 			start_era(1);
+			make_all_reward_payment(0);
 
 			// 10 and 20 have more votes, they will be chosen by phragmen.
 			assert_eq_uvec!(validator_controllers(), vec![20, 10]);
@@ -537,6 +544,7 @@ fn nominating_and_rewards_should_work() {
 			<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 			start_era(2);
+			make_all_reward_payment(1);
 
 			// nothing else will happen, era ends and rewards are paid again,
 			// it is expected that nominators will also be paid. See below
@@ -632,6 +640,7 @@ fn nominators_also_get_slashed() {
 
 		// new era, pay rewards,
 		start_era(1);
+		make_all_reward_payment(0);
 
 		// Nominator stash didn't collect any.
 		assert_eq!(Balances::total_balance(&2), initial_balance);
@@ -891,6 +900,7 @@ fn reward_destination_works() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(1);
+		make_all_reward_payment(0);
 
 		// Check that RewardDestination is Staked (default)
 		assert_eq!(Staking::payee(&11), RewardDestination::Staked);
@@ -913,6 +923,7 @@ fn reward_destination_works() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(2);
+		make_all_reward_payment(1);
 
 		// Check that RewardDestination is Stash
 		assert_eq!(Staking::payee(&11), RewardDestination::Stash);
@@ -940,6 +951,7 @@ fn reward_destination_works() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(3);
+		make_all_reward_payment(2);
 
 		// Check that RewardDestination is Controller
 		assert_eq!(Staking::payee(&11), RewardDestination::Controller);
@@ -975,15 +987,19 @@ fn validator_payment_prefs_work() {
 		let _ = Balances::make_free_balance_be(&2, 500);
 
 		// add a dummy nominator.
-		<Stakers<Test>>::insert(&11, Exposure {
+		let exposure = Exposure {
 			own: 500, // equal division indicates that the reward will be equally divided among validator and nominator.
 			total: 1000,
 			others: vec![IndividualExposure {who: 2, value: 500 }]
-		});
+		};
+		<Stakers<Test>>::insert(&11, &exposure);
+		<ErasStakers<Test>>::insert(0, &11, exposure);
 		<Payee<Test>>::insert(&2, RewardDestination::Stash);
-		<Validators<Test>>::insert(&11, ValidatorPrefs {
+		let prefs = ValidatorPrefs {
 			validator_payment: validator_cut
-		});
+		};
+		<Validators<Test>>::insert(&11, &prefs);
+		<ErasValidatorPrefs<Test>>::insert(0, &11, prefs);
 
 		// Compute total payout now for whole duration as other parameter won't change
 		let total_payout_0 = current_total_payout_for_duration(3000);
@@ -991,6 +1007,7 @@ fn validator_payment_prefs_work() {
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 
 		start_era(1);
+		make_all_reward_payment(0);
 
 		// whats left to be shared is the sum of 3 rounds minus the validator's cut.
 		let shared_cut = total_payout_0 - validator_cut;
@@ -1197,8 +1214,10 @@ fn slot_stake_is_least_staked_validator_and_exposure_defines_maximum_punishment(
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
 		<Module<Test>>::reward_by_ids(vec![(21, 1)]);
 
-		// New era --> rewards are paid --> stakes are changed
+		// New era --> rewards are paid --> stakes are changed in the following era
 		start_era(1);
+		make_all_reward_payment(0);
+		start_era(2);
 
 		// -- new balances + reward
 		assert_eq!(Staking::stakers(&11).total, 1000 + total_payout_0 / 2);
@@ -1481,6 +1500,7 @@ fn bond_with_little_staked_value_bounded_by_slot_stake() {
 			assert!(total_payout_0 > 100); // Test is meaningfull if reward something
 			reward_all_elected();
 			start_era(1);
+			make_all_reward_payment(0);
 
 			// 2 is elected.
 			// and fucks up the slot stake.
@@ -1496,6 +1516,7 @@ fn bond_with_little_staked_value_bounded_by_slot_stake() {
 			assert!(total_payout_1 > 100); // Test is meaningfull if reward something
 			reward_all_elected();
 			start_era(2);
+			make_all_reward_payment(1);
 
 			assert_eq_uvec!(validator_controllers(), vec![20, 10, 2]);
 			assert_eq!(Staking::slot_stake(), 1);
@@ -1651,21 +1672,23 @@ fn reward_validator_slashing_validator_doesnt_overflow() {
 
 		// Set staker
 		let _ = Balances::make_free_balance_be(&11, stake);
-		<Stakers<Test>>::insert(&11, Exposure { total: stake, own: stake, others: vec![] });
+		<ErasStakers<Test>>::insert(0, &11, Exposure { total: stake, own: stake, others: vec![] });
 
 		// Check reward
-		let _ = Staking::reward_validator(&11, reward_slash);
+		let _ = Staking::reward_validator(0, &11, reward_slash);
 		assert_eq!(Balances::total_balance(&11), stake * 2);
 
 		// Set staker
 		let _ = Balances::make_free_balance_be(&11, stake);
 		let _ = Balances::make_free_balance_be(&2, stake);
-		<Stakers<Test>>::insert(&11, Exposure { total: stake, own: 1, others: vec![
+		let exposure = Exposure { total: stake, own: 1, others: vec![
 			IndividualExposure { who: 2, value: stake - 1 }
-		]});
+		]};
 
 		// Check slashing
-		let _ = Staking::slash_validator(&11, reward_slash, &Staking::stakers(&11), &mut Vec::new());
+		let unapplied = slashing::compute_slash::<Test>(0, &11, &exposure, Perbill::one(), vec![])
+			.expect("the exposure is slashed in full");
+		slashing::apply_slash::<Test>(unapplied, Perbill::zero());
 		assert_eq!(Balances::total_balance(&11), stake - 1);
 		assert_eq!(Balances::total_balance(&2), 1);
 	})
@@ -1928,3 +1951,192 @@ fn dont_slash_if_fraction_is_zero() {
 		assert_eq!(Balances::free_balance(&11), 1000);
 	});
 }
+
+#[test]
+fn rewards_are_claimed_only_once() {
+	ExtBuilder::default().nominate(false).build().execute_with(|| {
+		let init_balance_11 = Balances::total_balance(&11);
+
+		let total_payout_0 = current_total_payout_for_duration(3000);
+		assert!(total_payout_0 > 10); // Test is meaningful if reward something
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		// The era has not finished yet.
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 11, 0),
+			"era has not finished yet",
+		);
+
+		start_era(1);
+
+		// Nothing is paid at the end of the era, the payout is only set aside.
+		assert_eq!(Balances::total_balance(&11), init_balance_11);
+		assert_eq!(Staking::eras_validator_reward(0), Some(total_payout_0));
+
+		// 21 earned no points.
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 21, 0),
+			"validator earned no points in this era",
+		);
+
+		assert_ok!(Staking::payout_stakers(Origin::signed(1337), 11, 0));
+		assert_eq!(Balances::total_balance(&11), init_balance_11 + total_payout_0);
+
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 11, 0),
+			"reward already claimed",
+		);
+	});
+}
+
+#[test]
+fn rewards_out_of_history_cannot_be_claimed() {
+	ExtBuilder::default().nominate(false).build().execute_with(|| {
+		HistoryDepth::put(2);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		start_era(1);
+		start_era(2);
+		assert!(Staking::eras_validator_reward(0).is_some());
+
+		// Era 0 falls out of history and its information is pruned.
+		start_era(3);
+		assert_eq!(Staking::eras_validator_reward(0), None);
+		assert_eq!(Staking::eras_reward_points(0), Default::default());
+		assert_eq!(Staking::eras_stakers(0, &11).total, 0);
+		assert_noop!(
+			Staking::payout_stakers(Origin::signed(1337), 11, 0),
+			"era is out of history",
+		);
+
+		// Lowering the history depth prunes the older eras right away.
+		assert!(Staking::eras_validator_reward(1).is_some());
+		assert_ok!(Staking::set_history_depth(Origin::ROOT, 1));
+		assert_eq!(Staking::history_depth(), 1);
+		assert_eq!(Staking::eras_validator_reward(1), None);
+		assert!(Staking::eras_validator_reward(2).is_some());
+	});
+}
+
+#[test]
+fn offences_in_the_same_span_are_slashed_once() {
+	ExtBuilder::default().nominate(false).build().execute_with(|| {
+		assert_eq!(Balances::free_balance(&11), 1000);
+		assert_eq!(Staking::stakers(&11).own, 1000);
+
+		let offence = [OffenceDetails {
+			offender: (11, Staking::stakers(&11)),
+			reporters: vec![],
+		}];
+
+		Staking::on_offence(&offence, &[Perbill::from_percent(10)]);
+		assert_eq!(Balances::free_balance(&11), 900);
+		// The slash ended the span.
+		assert_eq!(Staking::slashing_spans(&11).map(|s| s.span_index), Some(1));
+
+		// The same offence is not slashed again...
+		Staking::on_offence(&offence, &[Perbill::from_percent(10)]);
+		assert_eq!(Balances::free_balance(&11), 900);
+
+		// ...and a bigger one is only slashed for the excess.
+		Staking::on_offence(&offence, &[Perbill::from_percent(20)]);
+		assert_eq!(Balances::free_balance(&11), 800);
+		assert_eq!(Staking::slashing_spans(&11).map(|s| s.span_index), Some(1));
+	});
+}
+
+#[test]
+fn deferred_slashes_are_applied_later() {
+	ExtBuilder::default().nominate(false).slash_defer_duration(2).build().execute_with(|| {
+		assert_eq!(Balances::free_balance(&11), 1000);
+
+		Staking::on_offence(
+			&[OffenceDetails {
+				offender: (11, Staking::stakers(&11)),
+				reporters: vec![],
+			}],
+			&[Perbill::from_percent(10)],
+		);
+
+		// The slash is only queued.
+		assert_eq!(Balances::free_balance(&11), 1000);
+		assert_eq!(Staking::unapplied_slashes(2).len(), 1);
+
+		while Staking::current_era() < 1 {
+			advance_session();
+		}
+		assert_eq!(Balances::free_balance(&11), 1000);
+
+		while Staking::current_era() < 2 {
+			advance_session();
+		}
+		assert_eq!(Balances::free_balance(&11), 900);
+		assert!(Staking::unapplied_slashes(2).is_empty());
+	});
+}
+
+#[test]
+fn deferred_slashes_can_be_cancelled() {
+	ExtBuilder::default().nominate(false).slash_defer_duration(2).build().execute_with(|| {
+		let initial_balance_11 = Balances::free_balance(&11);
+		let initial_balance_21 = Balances::free_balance(&21);
+		let slash_21 = Perbill::from_percent(10) * Staking::stakers(&21).own;
+
+		Staking::on_offence(
+			&[
+				OffenceDetails { offender: (11, Staking::stakers(&11)), reporters: vec![] },
+				OffenceDetails { offender: (21, Staking::stakers(&21)), reporters: vec![] },
+			],
+			&[Perbill::from_percent(10), Perbill::from_percent(10)],
+		);
+		assert_eq!(Staking::unapplied_slashes(2).len(), 2);
+
+		assert_noop!(Staking::cancel_deferred_slash(Origin::signed(1), 2, vec![0]), "bad origin");
+		assert_noop!(Staking::cancel_deferred_slash(Origin::ROOT, 2, vec![]), "no slash to cancel");
+		assert_noop!(
+			Staking::cancel_deferred_slash(Origin::ROOT, 2, vec![1, 0]),
+			"slash indices are not sorted and unique",
+		);
+		assert_noop!(Staking::cancel_deferred_slash(Origin::ROOT, 2, vec![2]), "invalid slash index");
+
+		// Cancel the slash of 11, which no longer counts in its span.
+		assert!(!Staking::span_slash(&(11, 0)).slashed.is_zero());
+		assert_ok!(Staking::cancel_deferred_slash(Origin::ROOT, 2, vec![0]));
+		assert_eq!(Staking::unapplied_slashes(2).len(), 1);
+		assert!(Staking::span_slash(&(11, 0)).slashed.is_zero());
+		assert!(!Staking::span_slash(&(21, 0)).slashed.is_zero());
+
+		while Staking::current_era() < 2 {
+			advance_session();
+		}
+		assert_eq!(Balances::free_balance(&11), initial_balance_11);
+		assert_eq!(Balances::free_balance(&21), initial_balance_21 - slash_21);
+	});
+}
+
+#[test]
+fn cancelled_slashes_do_not_count_in_their_span() {
+	ExtBuilder::default().nominate(false).slash_defer_duration(2).build().execute_with(|| {
+		let initial_balance_11 = Balances::free_balance(&11);
+		let slash_11 = Perbill::from_percent(10) * Staking::stakers(&11).own;
+
+		Staking::on_offence(
+			&[OffenceDetails { offender: (11, Staking::stakers(&11)), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+		);
+		assert_ok!(Staking::cancel_deferred_slash(Origin::ROOT, 2, vec![0]));
+
+		// A later offence in the same span is slashed in full.
+		Staking::on_offence(
+			&[OffenceDetails { offender: (11, Staking::stakers(&11)), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+		);
+		assert_eq!(Staking::unapplied_slashes(2).len(), 1);
+		assert_eq!(Staking::unapplied_slashes(2)[0].own, slash_11);
+
+		while Staking::current_era() < 2 {
+			advance_session();
+		}
+		assert_eq!(Balances::free_balance(&11), initial_balance_11 - slash_11);
+	});
+}
@@ -25,6 +25,35 @@ pub mod hashed;
 pub mod child;
 pub mod generator;
 
+/// Outcome of the closure run by [`with_transaction`].
+pub enum TransactionOutcome<R> {
+	/// Keep the storage changes made by the closure.
+	Commit(R),
+	/// Revert the storage changes made by the closure.
+	Rollback(R),
+}
+
+/// Execute `f` in a storage transaction, then keep or revert the storage changes it made
+/// according to the returned [`TransactionOutcome`].
+///
+/// Transactions can be nested: the changes kept by an inner transaction are still reverted if
+/// an enclosing one is rolled back.
+pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+	runtime_io::storage_start_transaction();
+	match f() {
+		TransactionOutcome::Commit(result) => {
+			runtime_io::storage_commit_transaction()
+				.expect("the transaction started above is still open; qed");
+			result
+		},
+		TransactionOutcome::Rollback(result) => {
+			runtime_io::storage_rollback_transaction()
+				.expect("the transaction started above is still open; qed");
+			result
+		},
+	}
+}
+
 /// A trait for working with macro-generated storage values under the substrate storage API.
 ///
 /// Details on implementation can be found at
@@ -305,3 +334,38 @@ pub trait StorageDoubleMap<K1: FullEncode, K2: FullEncode, V: FullCodec> {
 		Items: IntoIterator<Item=EncodeLikeItem> + Clone + EncodeLike<V>,
 		Items::IntoIter: ExactSizeIterator;
 }
+
+#[cfg(test)]
+mod tests {
+	use runtime_io::TestExternalities;
+	use super::*;
+
+	#[test]
+	fn with_transaction_works() {
+		TestExternalities::default().execute_with(|| {
+			unhashed::put(b"a", &1u32);
+
+			let result = with_transaction(|| {
+				unhashed::put(b"a", &2u32);
+
+				with_transaction(|| {
+					unhashed::put(b"b", &3u32);
+					TransactionOutcome::Commit(())
+				});
+				assert_eq!(unhashed::get::<u32>(b"b"), Some(3));
+
+				with_transaction(|| {
+					unhashed::kill(b"a");
+					TransactionOutcome::Rollback(())
+				});
+				assert_eq!(unhashed::get::<u32>(b"a"), Some(2));
+
+				TransactionOutcome::Rollback(42)
+			});
+
+			assert_eq!(result, 42);
+			assert_eq!(unhashed::get::<u32>(b"a"), Some(1));
+			assert_eq!(unhashed::get::<u32>(b"b"), None);
+		});
+	}
+}
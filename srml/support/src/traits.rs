@@ -62,6 +62,12 @@ impl<V: PartialEq, T: Get<V>> Contains<V> for T {
 	}
 }
 
+/// A `Contains` implementation which can also tell how many values it contains.
+pub trait ContainsCount<T>: Contains<T> {
+	/// Return the number of values contained.
+	fn count() -> usize;
+}
+
 /// The account with the given id was killed.
 #[impl_trait_for_tuples::impl_for_tuples(30)]
 pub trait OnFreeBalanceZero<AccountId> {
@@ -122,6 +128,47 @@ pub trait KeyOwnerProofSystem<Key> {
 	fn check_proof(key: Key, proof: Self::Proof) -> Option<Self::IdentificationTuple>;
 }
 
+/// A key owner proof system which can't prove anything, for chains which
+/// don't track the key owners.
+impl<Key> KeyOwnerProofSystem<Key> for () {
+	type Proof = crate::Void;
+	type IdentificationTuple = ();
+
+	fn prove(_key: Key) -> Option<Self::Proof> {
+		None
+	}
+
+	fn check_proof(_key: Key, proof: Self::Proof) -> Option<Self::IdentificationTuple> {
+		match proof {}
+	}
+}
+
+/// Something which knows the index of the session it was produced in, e.g. a
+/// proof of key ownership.
+pub trait GetSessionNumber {
+	/// The index of the session.
+	fn session(&self) -> u32;
+}
+
+/// Something which knows the size of the validator set of the session it was
+/// produced in, e.g. a proof of key ownership.
+pub trait GetValidatorCount {
+	/// The number of validators of the session.
+	fn validator_count(&self) -> u32;
+}
+
+impl GetSessionNumber for crate::Void {
+	fn session(&self) -> u32 {
+		match *self {}
+	}
+}
+
+impl GetValidatorCount for crate::Void {
+	fn validator_count(&self) -> u32 {
+		match *self {}
+	}
+}
+
 /// Handler for when some currency "account" decreased in balance for
 /// some reason.
 ///
@@ -545,6 +592,64 @@ pub trait ReservableCurrency<AccountId>: Currency<AccountId> {
 /// they can be individually replaced or removed.
 pub type LockIdentifier = [u8; 8];
 
+/// An identifier for a named reserve. Used for disambiguating different reserves so that
+/// they can be individually increased or released.
+pub type ReserveIdentifier = [u8; 8];
+
+/// A currency where funds can be reserved under an identifier, so that several modules can reserve
+/// funds of the same account independently and only release their own portion.
+///
+/// Named reserves are portions of the reserved balance. The functions of `ReservableCurrency` only
+/// act on the portion of the reserved balance that isn't reserved under any identifier.
+pub trait NamedReservableCurrency<AccountId>: ReservableCurrency<AccountId> {
+	/// The amount of the balance of `who` that is reserved under `id`.
+	fn reserved_balance_named(id: &ReserveIdentifier, who: &AccountId) -> Self::Balance;
+
+	/// Moves `value` from balance to the reserve named `id`.
+	///
+	/// If the free balance is lower than `value`, then no funds will be moved and an `Err` will
+	/// be returned.
+	fn reserve_named(
+		id: &ReserveIdentifier,
+		who: &AccountId,
+		value: Self::Balance,
+	) -> result::Result<(), &'static str>;
+
+	/// Moves up to `value` from the reserve named `id` to free balance. This function cannot fail.
+	///
+	/// If the reserve named `id` is less than `value`, then the remaining amount will be returned.
+	fn unreserve_named(id: &ReserveIdentifier, who: &AccountId, value: Self::Balance) -> Self::Balance;
+
+	/// Deducts up to `value` from the reserve named `id`. This function cannot fail.
+	///
+	/// If the reserve named `id` is less than `value`, then a non-zero second item will be
+	/// returned.
+	fn slash_reserved_named(
+		id: &ReserveIdentifier,
+		who: &AccountId,
+		value: Self::Balance,
+	) -> (Self::NegativeImbalance, Self::Balance);
+
+	/// Moves up to `value` from the reserve named `id` of account `slashed` to free balance of
+	/// account `beneficiary`. `beneficiary` must exist for this to succeed. If it does not, `Err`
+	/// will be returned.
+	///
+	/// If the reserve named `id` is less than `value`, then `Ok(non_zero)` will be returned.
+	fn repatriate_reserved_named(
+		id: &ReserveIdentifier,
+		slashed: &AccountId,
+		beneficiary: &AccountId,
+		value: Self::Balance,
+	) -> result::Result<Self::Balance, &'static str>;
+
+	/// Moves up to `value` of the reserved balance of `who` that isn't reserved under any
+	/// identifier to the reserve named `id`. This function cannot fail.
+	///
+	/// This lets modules migrate the funds they reserved before they used named reserves. If not
+	/// enough funds are reserved without identifier, then the remaining amount will be returned.
+	fn name_reserved(id: &ReserveIdentifier, who: &AccountId, value: Self::Balance) -> Self::Balance;
+}
+
 /// A currency whose accounts can have liquidity restrictions.
 pub trait LockableCurrency<AccountId>: Currency<AccountId> {
 	/// The quantity used to denote time; usually just a `BlockNumber`.
@@ -609,6 +714,42 @@ bitmask! {
 	}
 }
 
+/// A set of fungible assets, identified by an `AssetId`, whose balances are held by accounts.
+///
+/// This is the currency-like interface through which a module may deal with non-native tokens.
+pub trait MultiAsset<AccountId> {
+	/// The identifier of an asset.
+	type AssetId: FullCodec + Copy + MaybeSerializeDeserialize + Debug;
+
+	/// The balance of an account in an asset.
+	type Balance: SimpleArithmetic + FullCodec + Copy + MaybeSerializeDeserialize + Debug + Default;
+
+	/// Whether the asset `id` exists.
+	fn exists(id: Self::AssetId) -> bool;
+
+	/// The total amount of the asset `id` in existence.
+	fn total_supply(id: Self::AssetId) -> Self::Balance;
+
+	/// The balance of `who` in the asset `id`.
+	fn balance(id: Self::AssetId, who: &AccountId) -> Self::Balance;
+
+	/// Transfer `amount` of the asset `id` from `source` to `dest`.
+	fn transfer(
+		id: Self::AssetId,
+		source: &AccountId,
+		dest: &AccountId,
+		amount: Self::Balance,
+	) -> result::Result<(), &'static str>;
+
+	/// Create `amount` of the asset `id` into the account of `who`, increasing the total supply.
+	fn deposit(id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> result::Result<(), &'static str>;
+
+	/// Remove `amount` of the asset `id` from the account of `who`, decreasing the total supply.
+	///
+	/// Fails, without doing anything, if `who` doesn't have that much.
+	fn withdraw(id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> result::Result<(), &'static str>;
+}
+
 pub trait Time {
 	type Moment: SimpleArithmetic + FullCodec + Clone + Default + Copy;
 
@@ -695,12 +836,17 @@ pub trait ChangeMembers<AccountId: Clone + Ord> {
 		}
 		(incoming, outgoing)
 	}
+
+	/// Set the prime member. It is cleared on every membership change, so whoever manages the
+	/// membership should set it again afterwards if it should persist.
+	fn set_prime(_prime: Option<AccountId>) {}
 }
 
 impl<T: Clone + Ord> ChangeMembers<T> for () {
 	fn change_members(_: &[T], _: &[T], _: Vec<T>) {}
 	fn change_members_sorted(_: &[T], _: &[T], _: &[T]) {}
 	fn set_members_sorted(_: &[T], _: &[T]) {}
+	fn set_prime(_: Option<T>) {}
 }
 
 /// Trait for type that can handle the initialization of account IDs at genesis.
@@ -732,3 +878,91 @@ pub trait Randomness<Output> {
 		Self::random(&[][..])
 	}
 }
+
+/// Something that can decide whether the calls of a given type are allowed, e.g. a type of proxy.
+pub trait InstanceFilter<T>: Sized + Send + Sync {
+	/// Is the given value allowed by this instance?
+	fn filter(&self, _: &T) -> bool;
+
+	/// Does `self` allow at least everything that `_o` allows?
+	fn is_superset(&self, _o: &Self) -> bool { false }
+}
+
+impl<T> InstanceFilter<T> for () {
+	fn filter(&self, _: &T) -> bool { true }
+	fn is_superset(&self, _o: &Self) -> bool { true }
+}
+
+/// Types and traits for the scheduling of calls to be dispatched in a later block.
+pub mod schedule {
+	use super::*;
+
+	/// Information relating to the period of a scheduled task. First item is the length of the
+	/// period and the second is the number of times it should be executed in total before the task
+	/// is considered finished and removed.
+	pub type Period<BlockNumber> = (BlockNumber, u32);
+
+	/// Priority with which a call is scheduled. It's just a linear amount with lowest values meaning
+	/// higher priority.
+	pub type Priority = u8;
+
+	/// The highest priority. We invert the value so that normal sorting will place the highest
+	/// priority at the beginning of the list.
+	pub const HIGHEST_PRIORITY: Priority = 0;
+	/// Anything of this value or lower will definitely be scheduled on the block that they ask for,
+	/// even if it breaches the weight limit of the scheduler.
+	pub const HARD_DEADLINE: Priority = 63;
+	/// The lowest priority. Most stuff should be around here.
+	pub const LOWEST_PRIORITY: Priority = 255;
+
+	/// A type that can be used as a scheduler.
+	pub trait Anon<BlockNumber, Call> {
+		/// An address which can be used for removing a scheduled task.
+		type Address: Codec + Clone + Eq + Debug;
+
+		/// Schedule a one-off dispatch to happen at the beginning of some block in the future.
+		///
+		/// This is not named.
+		fn schedule(
+			when: BlockNumber,
+			maybe_periodic: Option<Period<BlockNumber>>,
+			priority: Priority,
+			call: Call,
+		) -> Self::Address;
+
+		/// Cancel a scheduled task. If periodic, then it will cancel all further instances of that,
+		/// also.
+		///
+		/// Will return an error if the `address` is invalid.
+		///
+		/// NOTE: This guaranteed to work only *before* the point that it is due to be executed.
+		/// If it ends up being delayed beyond the point of execution, then it cannot be cancelled.
+		fn cancel(address: Self::Address) -> result::Result<(), ()>;
+	}
+
+	/// A type that can be used as a scheduler of named tasks.
+	pub trait Named<BlockNumber, Call> {
+		/// An address which can be used for removing a scheduled task.
+		type Address: Codec + Clone + Eq + Debug;
+
+		/// Schedule a one-off dispatch to happen at the beginning of some block in the future.
+		///
+		/// Fails if a task of the same `id` is already scheduled.
+		fn schedule_named(
+			id: Vec<u8>,
+			when: BlockNumber,
+			maybe_periodic: Option<Period<BlockNumber>>,
+			priority: Priority,
+			call: Call,
+		) -> result::Result<Self::Address, ()>;
+
+		/// Cancel a scheduled, named task. If periodic, then it will cancel all further instances
+		/// of that, also.
+		///
+		/// Will return an error if the `id` is invalid.
+		///
+		/// NOTE: This guaranteed to work only *before* the point that it is due to be executed.
+		/// If it ends up being delayed beyond the point of execution, then it cannot be cancelled.
+		fn cancel_named(id: Vec<u8>) -> result::Result<(), ()>;
+	}
+}
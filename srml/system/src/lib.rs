@@ -102,7 +102,7 @@ use sr_primitives::{
 	weights::{Weight, DispatchInfo, DispatchClass, SimpleDispatchInfo},
 	transaction_validity::{
 		ValidTransaction, TransactionPriority, TransactionLongevity, TransactionValidityError,
-		InvalidTransaction, TransactionValidity,
+		InvalidTransaction, TransactionValidity, OPERATIONAL_PRIORITY,
 	},
 	traits::{
 		self, CheckEqual, SimpleArithmetic, Zero, SignedExtension, Lookup, LookupError,
@@ -121,7 +121,6 @@ use codec::{Encode, Decode};
 #[cfg(any(feature = "std", test))]
 use runtime_io::TestExternalities;
 
-#[cfg(any(feature = "std", test))]
 use primitives::ChangesTrieConfiguration;
 
 pub mod offchain;
@@ -282,6 +281,28 @@ decl_module! {
 			ensure_root(origin)?;
 			storage::unhashed::kill_prefix(&prefix);
 		}
+
+		/// Set the new changes trie configuration, or disable changes tries with `None`.
+		///
+		/// The configuration is used starting from the next block. A `ChangesTrieSignal` is
+		/// deposited in the digest of the current block so that nodes can track the change, so
+		/// the configuration must not be changed through `set_storage`.
+		#[weight = SimpleDispatchInfo::FixedOperational(20_000)]
+		pub fn set_changes_trie_config(origin, changes_trie_config: Option<ChangesTrieConfiguration>) {
+			ensure_root(origin)?;
+			match changes_trie_config.clone() {
+				Some(changes_trie_config) => storage::unhashed::put_raw(
+					well_known_keys::CHANGES_TRIE_CONFIG,
+					&changes_trie_config.encode(),
+				),
+				None => storage::unhashed::kill(well_known_keys::CHANGES_TRIE_CONFIG),
+			}
+
+			let log = generic::DigestItem::ChangesTrieSignal(
+				generic::ChangesTrieSignal::NewConfiguration(changes_trie_config),
+			);
+			Self::deposit_log(log);
+		}
 	}
 }
 
@@ -566,21 +587,18 @@ impl<T: Trait> Module<T> {
 			old_event_count
 		};
 
-		// Appending can only fail if `Events<T>` can not be decoded or
-		// when we try to insert more than `u32::max_value()` events.
-		//
-		// We perform early return if we've reached the maximum capacity of the event list,
-		// so `Events<T>` seems to be corrupted. Also, this has happened after the start of execution
-		// (since the event list is cleared at the block initialization).
-		if <Events<T>>::append([event].iter()).is_err() {
-			// The most sensible thing to do here is to just ignore this event and wait until the
-			// new block.
-			return;
-		}
+		// Append the event without decoding the events already deposited in this block, which
+		// would make depositing events quadratic in their number. The number of events is bounded
+		// by the event count above, and a corrupted `Events<T>`, which doesn't start with a valid
+		// length, is simply replaced by the new event.
+		runtime_io::storage_append(
+			&<Events<T> as storage::StorageValue<_>>::hashed_key(),
+			&event.encode(),
+		);
 
 		let block_no = Self::block_number();
 		for topic in topics {
-			// The same applies here.
+			// If the topics of the block can't be decoded, the event just isn't indexed by them.
 			if <EventTopics<T>>::append(&(), topic, &[(block_no, event_idx)]).is_err() {
 				return;
 			}
@@ -812,7 +830,7 @@ impl<T: Trait + Send + Sync> CheckWeight<T> {
 	fn get_priority(info: DispatchInfo) -> TransactionPriority {
 		match info.class {
 			DispatchClass::Normal => info.weight.into(),
-			DispatchClass::Operational => Bounded::max_value()
+			DispatchClass::Operational => OPERATIONAL_PRIORITY,
 		}
 	}
 
@@ -1208,6 +1226,31 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn set_changes_trie_config_works() {
+		new_test_ext().execute_with(|| {
+			let config = ChangesTrieConfiguration::new(4, 2);
+			System::initialize(&1, &[0u8; 32].into(), &[0u8; 32].into(), &Default::default());
+			assert!(System::set_changes_trie_config(Origin::signed(1), Some(config.clone())).is_err());
+			assert!(System::set_changes_trie_config(RawOrigin::Root.into(), Some(config.clone())).is_ok());
+			assert_eq!(
+				storage::unhashed::get_raw(well_known_keys::CHANGES_TRIE_CONFIG),
+				Some(config.encode()),
+			);
+			let header = System::finalize();
+			assert_eq!(
+				header.digest.logs,
+				vec![generic::DigestItem::ChangesTrieSignal(
+					generic::ChangesTrieSignal::NewConfiguration(Some(config)),
+				)],
+			);
+
+			System::initialize(&2, &[0u8; 32].into(), &[0u8; 32].into(), &Default::default());
+			assert!(System::set_changes_trie_config(RawOrigin::Root.into(), None).is_ok());
+			assert_eq!(storage::unhashed::get_raw(well_known_keys::CHANGES_TRIE_CONFIG), None);
+		});
+	}
+
 	#[test]
 	fn deposit_event_topics() {
 		new_test_ext().execute_with(|| {
@@ -1418,7 +1461,7 @@ mod tests {
 				.validate(&1, CALL, op, len)
 				.unwrap()
 				.priority;
-			assert_eq!(priority, Bounded::max_value());
+			assert_eq!(priority, OPERATIONAL_PRIORITY);
 		})
 	}
 
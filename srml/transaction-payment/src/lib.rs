@@ -41,7 +41,7 @@ use sr_primitives::{
 	Fixed64,
 	transaction_validity::{
 		TransactionPriority, ValidTransaction, InvalidTransaction, TransactionValidityError,
-		TransactionValidity,
+		TransactionValidity, OPERATIONAL_PRIORITY,
 	},
 	traits::{Zero, Saturating, SignedExtension, SaturatedConversion, Convert},
 	weights::{Weight, DispatchInfo, GetDispatchInfo},
@@ -218,7 +218,8 @@ impl<T: Trait + Send + Sync> SignedExtension for ChargeTransactionPayment<T>
 		let mut r = ValidTransaction::default();
 		// NOTE: we probably want to maximize the _fee (of any type) per weight unit_ here, which
 		// will be a bit more than setting the priority to tip. For now, this is enough.
-		r.priority = fee.saturated_into::<TransactionPriority>();
+		// The operational priority is reserved, whatever the fee.
+		r.priority = fee.saturated_into::<TransactionPriority>().min(OPERATIONAL_PRIORITY - 1);
 		Ok(r)
 	}
 }
@@ -380,7 +381,6 @@ mod tests {
 					(5, 50 * self.balance_factor),
 					(6, 60 * self.balance_factor)
 				],
-				vesting: vec![],
 			}.assimilate_storage(&mut t).unwrap();
 			t.into()
 		}
@@ -40,6 +40,15 @@
 //! deposit will be returned or slashed if the proposal is approved or rejected
 //! respectively.
 //! - **Pot:** Unspent funds accumulated by the treasury module.
+//! - **Tipping:** A small payout from the pot to a beneficiary, whose value is the median of the
+//! values declared by the tippers.
+//! - **Tipper:** An account allowed to declare tip values, e.g. a council member.
+//! - **Finder:** The account which reported a beneficiary for a tip. It gets a share of the tip
+//! when it's paid out.
+//! - **Bounty:** A spending proposal for a work item whose beneficiary isn't known yet. Once
+//! approved, it's funded into an account of its own at the next spend period.
+//! - **Curator:** The account, assigned by the council, which manages a funded bounty and awards
+//! it to a beneficiary. It gets a fee for it and puts a deposit at stake.
 //!
 //! ## Interface
 //!
@@ -50,6 +59,22 @@
 //! - `configure` - Configure the module's proposal requirements.
 //! - `reject_proposal` - Reject a proposal, slashing the deposit.
 //! - `approve_proposal` - Accept the proposal, returning the deposit.
+//! - `spend_funds` - Award the approved proposals and burn part of the remaining pot. It is
+//!   scheduled by the module itself through the `Scheduler` every `SpendPeriod` blocks.
+//! - `report_awesome` - Report a beneficiary deserving a tip and stake the required deposit.
+//! - `retract_tip` - Retract a tip reported by the caller, returning the deposit.
+//! - `tip_new` - Open a tip for a beneficiary, declaring the caller's tip value; only for tippers.
+//! - `tip` - Declare a value for an open tip; only for tippers. Once a majority of the tippers
+//!   declared a value, the tip closes after `TipCountdown` blocks.
+//! - `close_tip` - Pay out a tip whose countdown is over; the median of the values is paid.
+//! - `propose_bounty` - Propose a bounty and stake the required deposit.
+//! - `approve_bounty` - Accept a bounty, funding it at the next spend period.
+//! - `propose_curator` - Propose a curator, with its fee, for a funded bounty.
+//! - `unassign_curator` - Unassign the curator of a bounty.
+//! - `accept_curator` - Accept to be the curator of a bounty, staking the curator deposit.
+//! - `award_bounty` - Award a bounty to a beneficiary; only for its curator.
+//! - `claim_bounty` - Pay out an awarded bounty once `BountyDepositPayoutDelay` has passed.
+//! - `close_bounty` - Cancel a bounty, returning its funds to the pot.
 //!
 //! ## GenesisConfig
 //!
@@ -63,15 +88,15 @@ use rstd::prelude::*;
 use support::{decl_module, decl_storage, decl_event, ensure, print};
 use support::traits::{
 	Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced,
-	ReservableCurrency, WithdrawReason
+	ReservableCurrency, WithdrawReason, schedule, Contains, ContainsCount,
 };
-use sr_primitives::{Permill, ModuleId};
+use sr_primitives::{Permill, Percent, ModuleId, RuntimeDebug};
 use sr_primitives::traits::{
-	Zero, EnsureOrigin, StaticLookup, AccountIdConversion, Saturating
+	Zero, EnsureOrigin, StaticLookup, AccountIdConversion, Saturating, Hash,
 };
 use sr_primitives::weights::SimpleDispatchInfo;
 use codec::{Encode, Decode};
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
 
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 type PositiveImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::PositiveImbalance;
@@ -79,6 +104,9 @@ type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::
 
 const MODULE_ID: ModuleId = ModuleId(*b"py/trsry");
 
+/// The name under which the periodic spend is scheduled.
+const SPEND_ID: &[u8] = b"py/trsry:spend";
+
 pub trait Trait: system::Trait {
 	/// The staking balance.
 	type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
@@ -107,10 +135,52 @@ pub trait Trait: system::Trait {
 
 	/// Percentage of spare funds (if any) that are burnt per spend period.
 	type Burn: Get<Permill>;
+
+	/// The aggregated call type, through which the periodic spend is dispatched.
+	type Call: From<Call<Self>>;
+
+	/// The scheduler through which the periodic spend is dispatched.
+	type Scheduler: schedule::Named<Self::BlockNumber, <Self as Trait>::Call>;
+
+	/// The accounts which may declare tip values.
+	type Tippers: ContainsCount<Self::AccountId>;
+
+	/// The period for which a tip remains open after a majority of the tippers declared a value.
+	type TipCountdown: Get<Self::BlockNumber>;
+
+	/// The share of a tip which goes to its finder.
+	type TipFindersFee: Get<Percent>;
+
+	/// The amount held on deposit for reporting a tip.
+	type TipReportDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount held on deposit per byte of the reason of a reported tip.
+	type TipReportDepositPerByte: Get<BalanceOf<Self>>;
+
+	/// The maximum length of the reason of a tip, or of the description of a bounty.
+	type MaximumReasonLength: Get<u32>;
+
+	/// The amount held on deposit for proposing a bounty.
+	type BountyDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount held on deposit per byte of the description of a bounty.
+	type BountyDepositPerByte: Get<BalanceOf<Self>>;
+
+	/// The minimum value of a bounty.
+	type BountyValueMinimum: Get<BalanceOf<Self>>;
+
+	/// The share of the fee of a curator which it must put on deposit.
+	type BountyCuratorDeposit: Get<Permill>;
+
+	/// The delay between the award of a bounty and the moment it can be claimed.
+	type BountyDepositPayoutDelay: Get<Self::BlockNumber>;
 }
 
 type ProposalIndex = u32;
 
+/// An index of a bounty. Just a `u32`.
+pub type BountyIndex = u32;
+
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		/// Fraction of a proposal's value that should be bonded in order to place the proposal.
@@ -126,6 +196,37 @@ decl_module! {
 		/// Percentage of spare funds (if any) that are burnt per spend period.
 		const Burn: Permill = T::Burn::get();
 
+		/// The period for which a tip remains open after a majority of the tippers declared a
+		/// value.
+		const TipCountdown: T::BlockNumber = T::TipCountdown::get();
+
+		/// The share of a tip which goes to its finder.
+		const TipFindersFee: Percent = T::TipFindersFee::get();
+
+		/// The amount held on deposit for reporting a tip.
+		const TipReportDepositBase: BalanceOf<T> = T::TipReportDepositBase::get();
+
+		/// The amount held on deposit per byte of the reason of a reported tip.
+		const TipReportDepositPerByte: BalanceOf<T> = T::TipReportDepositPerByte::get();
+
+		/// The maximum length of the reason of a tip, or of the description of a bounty.
+		const MaximumReasonLength: u32 = T::MaximumReasonLength::get();
+
+		/// The amount held on deposit for proposing a bounty.
+		const BountyDepositBase: BalanceOf<T> = T::BountyDepositBase::get();
+
+		/// The amount held on deposit per byte of the description of a bounty.
+		const BountyDepositPerByte: BalanceOf<T> = T::BountyDepositPerByte::get();
+
+		/// The minimum value of a bounty.
+		const BountyValueMinimum: BalanceOf<T> = T::BountyValueMinimum::get();
+
+		/// The share of the fee of a curator which it must put on deposit.
+		const BountyCuratorDeposit: Permill = T::BountyCuratorDeposit::get();
+
+		/// The delay between the award of a bounty and the moment it can be claimed.
+		const BountyDepositPayoutDelay: T::BlockNumber = T::BountyDepositPayoutDelay::get();
+
 		fn deposit_event() = default;
 		/// Put forward a suggestion for spending. A deposit proportional to the value
 		/// is reserved and slashed if the proposal is rejected. It is returned once the
@@ -190,10 +291,451 @@ decl_module! {
 			Approvals::mutate(|v| v.push(proposal_id));
 		}
 
-		fn on_finalize(n: T::BlockNumber) {
-			// Check to see if we should spend some funds!
-			if (n % T::SpendPeriod::get()).is_zero() {
-				Self::spend_funds();
+		/// Spend the funds of the pot on the approved proposals, burning part of what remains.
+		///
+		/// The dispatch origin for this call must be _Root_. It is scheduled by the module itself
+		/// every `SpendPeriod` blocks.
+		///
+		/// # <weight>
+		/// - O(A) where A is the number of approved proposals.
+		/// - One DB change and one DB clear per awarded proposal.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(1_000_000)]
+		fn spend_funds(origin) {
+			ensure_root(origin)?;
+			Self::do_spend_funds();
+		}
+
+		/// Report something `reason` that deserves a tip and claim any eventual finder's fee.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// A deposit of `TipReportDepositBase` plus `TipReportDepositPerByte` for each byte of
+		/// `reason` is reserved and returned once the tip is closed or retracted.
+		///
+		/// # <weight>
+		/// - O(R) where R is the length of `reason`.
+		/// - One balance operation.
+		/// - Two DB entries.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn report_awesome(origin, reason: Vec<u8>, who: T::AccountId) {
+			let finder = ensure_signed(origin)?;
+			ensure!(reason.len() <= T::MaximumReasonLength::get() as usize, "Reason too big");
+
+			let reason_hash = T::Hashing::hash(&reason[..]);
+			ensure!(!Reasons::<T>::exists(&reason_hash), "Reason already known");
+			let hash = T::Hashing::hash_of(&(&reason_hash, &who));
+			ensure!(!Tips::<T>::exists(&hash), "Tip already known");
+
+			let deposit = T::TipReportDepositBase::get()
+				+ T::TipReportDepositPerByte::get() * (reason.len() as u32).into();
+			T::Currency::reserve(&finder, deposit).map_err(|_| "Finder's balance too low")?;
+
+			Reasons::<T>::insert(&reason_hash, &reason);
+			let tip = OpenTip { reason: reason_hash, who, finder: Some((finder, deposit)), closes: None, tips: vec![] };
+			Tips::<T>::insert(&hash, tip);
+			Self::deposit_event(RawEvent::NewTip(hash));
+		}
+
+		/// Retract a prior tip-report from `report_awesome`, and cancel the process of tipping.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the finder of the tip. Its
+		/// deposit is returned.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - Two DB clears.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn retract_tip(origin, hash: T::Hash) {
+			let who = ensure_signed(origin)?;
+			let tip = Tips::<T>::get(&hash).ok_or("No tip at that hash")?;
+			let (finder, deposit) = tip.finder.ok_or("Not the finder")?;
+			ensure!(finder == who, "Not the finder");
+
+			Reasons::<T>::remove(&tip.reason);
+			Tips::<T>::remove(&hash);
+			let _ = T::Currency::unreserve(&who, deposit);
+			Self::deposit_event(RawEvent::TipRetracted(hash));
+		}
+
+		/// Give a tip for something new; no finder's fee will be taken.
+		///
+		/// The dispatch origin for this call must be _Signed_ and a tipper.
+		///
+		/// # <weight>
+		/// - O(R) where R is the length of `reason`.
+		/// - Two DB entries.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+		fn tip_new(origin, reason: Vec<u8>, who: T::AccountId, #[compact] tip_value: BalanceOf<T>) {
+			let tipper = ensure_signed(origin)?;
+			ensure!(T::Tippers::contains(&tipper), "Not a tipper");
+			ensure!(reason.len() <= T::MaximumReasonLength::get() as usize, "Reason too big");
+
+			let reason_hash = T::Hashing::hash(&reason[..]);
+			ensure!(!Reasons::<T>::exists(&reason_hash), "Reason already known");
+			let hash = T::Hashing::hash_of(&(&reason_hash, &who));
+			ensure!(!Tips::<T>::exists(&hash), "Tip already known");
+
+			Reasons::<T>::insert(&reason_hash, &reason);
+			Self::deposit_event(RawEvent::NewTip(hash));
+			let tips = vec![(tipper, tip_value)];
+			let tip = OpenTip { reason: reason_hash, who, finder: None, closes: None, tips };
+			Self::insert_tip_and_check_closing(hash, tip);
+		}
+
+		/// Declare a tip value for an already-open tip.
+		///
+		/// The dispatch origin for this call must be _Signed_ and a tipper. Any value it already
+		/// declared for the tip is replaced.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of tippers.
+		/// - One DB change.
+		/// - Up to one event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn tip(origin, hash: T::Hash, #[compact] tip_value: BalanceOf<T>) {
+			let tipper = ensure_signed(origin)?;
+			ensure!(T::Tippers::contains(&tipper), "Not a tipper");
+
+			let mut tip = Tips::<T>::get(&hash).ok_or("No tip at that hash")?;
+			match tip.tips.binary_search_by_key(&&tipper, |x| &x.0) {
+				Ok(pos) => tip.tips[pos].1 = tip_value,
+				Err(pos) => tip.tips.insert(pos, (tipper, tip_value)),
+			}
+			Self::insert_tip_and_check_closing(hash, tip);
+		}
+
+		/// Close and payout a tip.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// The tip must have been declared by a majority of the tippers and its countdown must be
+		/// over. The median of the values declared by the current tippers is paid out of the pot,
+		/// the finder's fee going to the finder of the tip, if any.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of tippers.
+		/// - Up to three balance operations.
+		/// - Two DB clears.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn close_tip(origin, hash: T::Hash) {
+			ensure_signed(origin)?;
+
+			let tip = Tips::<T>::get(&hash).ok_or("No tip at that hash")?;
+			let n = tip.closes.as_ref().ok_or("Tip not yet closing")?;
+			ensure!(system::Module::<T>::block_number() >= *n, "Tip still closing");
+			// Only the values of those who are still tippers count.
+			ensure!(
+				tip.tips.iter().filter(|(tipper, _)| T::Tippers::contains(tipper)).count() >= Self::tip_threshold(),
+				"Not enough tippers"
+			);
+
+			Reasons::<T>::remove(&tip.reason);
+			Tips::<T>::remove(&hash);
+			Self::payout_tip(hash, tip);
+		}
+
+		/// Propose a new bounty, of `value` and described by `description`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// A deposit of `BountyDepositBase` plus `BountyDepositPerByte` for each byte of
+		/// `description` is reserved. It's slashed if the bounty is rejected and returned once the
+		/// bounty is funded.
+		///
+		/// # <weight>
+		/// - O(D) where D is the length of `description`.
+		/// - One balance operation.
+		/// - Two DB entries.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn propose_bounty(origin, #[compact] value: BalanceOf<T>, description: Vec<u8>) {
+			let proposer = ensure_signed(origin)?;
+			ensure!(description.len() <= T::MaximumReasonLength::get() as usize, "Reason too big");
+			ensure!(value >= T::BountyValueMinimum::get(), "Invalid bounty value");
+
+			let bond = T::BountyDepositBase::get()
+				+ T::BountyDepositPerByte::get() * (description.len() as u32).into();
+			T::Currency::reserve(&proposer, bond).map_err(|_| "Proposer's balance too low")?;
+
+			let index = Self::bounty_count();
+			BountyCount::put(index + 1);
+			let bounty = Bounty {
+				proposer,
+				value,
+				fee: Zero::zero(),
+				curator_deposit: Zero::zero(),
+				bond,
+				status: BountyStatus::Proposed,
+			};
+			<Bounties<T>>::insert(index, bounty);
+			BountyDescriptions::insert(index, description);
+			Self::deposit_event(RawEvent::BountyProposed(index));
+		}
+
+		/// Approve a bounty, which will be funded at the next spend period.
+		///
+		/// The dispatch origin for this call must be `ApproveOrigin`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two DB changes.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn approve_bounty(origin, #[compact] bounty_id: BountyIndex) {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			let mut bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			ensure!(bounty.status == BountyStatus::Proposed, "Unexpected bounty status");
+			bounty.status = BountyStatus::Approved;
+			<Bounties<T>>::insert(bounty_id, bounty);
+			BountyApprovals::mutate(|v| v.push(bounty_id));
+		}
+
+		/// Propose `curator`, with a fee of `fee`, for a funded bounty.
+		///
+		/// The dispatch origin for this call must be `ApproveOrigin`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn propose_curator(
+			origin,
+			#[compact] bounty_id: BountyIndex,
+			curator: <T::Lookup as StaticLookup>::Source,
+			#[compact] fee: BalanceOf<T>
+		) {
+			T::ApproveOrigin::ensure_origin(origin)?;
+			let curator = T::Lookup::lookup(curator)?;
+
+			let mut bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			ensure!(bounty.status == BountyStatus::Funded, "Unexpected bounty status");
+			ensure!(fee < bounty.value, "Curator fee too high");
+			bounty.status = BountyStatus::CuratorProposed { curator };
+			bounty.fee = fee;
+			<Bounties<T>>::insert(bounty_id, bounty);
+		}
+
+		/// Unassign the curator of a bounty, which goes back to being funded.
+		///
+		/// The dispatch origin for this call must be either `RejectOrigin`, in which case the
+		/// deposit of an active curator is slashed, or _Signed_ by the curator itself, in which
+		/// case its deposit is returned.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Up to one balance operation.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn unassign_curator(origin, #[compact] bounty_id: BountyIndex) {
+			let maybe_sender = match T::RejectOrigin::try_origin(origin) {
+				Ok(_) => None,
+				Err(origin) => Some(ensure_signed(origin)?),
+			};
+
+			let mut bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			match bounty.status {
+				BountyStatus::CuratorProposed { ref curator } => {
+					if let Some(ref sender) = maybe_sender {
+						ensure!(sender == curator, "Require curator");
+					}
+				}
+				BountyStatus::Active { ref curator } => {
+					if let Some(ref sender) = maybe_sender {
+						ensure!(sender == curator, "Require curator");
+						let _ = T::Currency::unreserve(curator, bounty.curator_deposit);
+					} else {
+						let imbalance = T::Currency::slash_reserved(curator, bounty.curator_deposit).0;
+						T::ProposalRejection::on_unbalanced(imbalance);
+					}
+				}
+				_ => Err("Unexpected bounty status")?,
+			}
+			bounty.status = BountyStatus::Funded;
+			bounty.curator_deposit = Zero::zero();
+			<Bounties<T>>::insert(bounty_id, bounty);
+		}
+
+		/// Accept to be the curator of a bounty, putting the curator deposit at stake.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the proposed curator.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One DB change.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn accept_curator(origin, #[compact] bounty_id: BountyIndex) {
+			let signer = ensure_signed(origin)?;
+
+			let mut bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			let curator = match bounty.status {
+				BountyStatus::CuratorProposed { curator } => curator,
+				_ => Err("Unexpected bounty status")?,
+			};
+			ensure!(signer == curator, "Require curator");
+
+			let deposit = T::BountyCuratorDeposit::get() * bounty.fee;
+			T::Currency::reserve(&curator, deposit).map_err(|_| "Curator's balance too low")?;
+			bounty.curator_deposit = deposit;
+			bounty.status = BountyStatus::Active { curator };
+			<Bounties<T>>::insert(bounty_id, bounty);
+		}
+
+		/// Award a bounty to `beneficiary`, who can claim it after `BountyDepositPayoutDelay`.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the curator of the bounty.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One DB change.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn award_bounty(
+			origin,
+			#[compact] bounty_id: BountyIndex,
+			beneficiary: <T::Lookup as StaticLookup>::Source
+		) {
+			let signer = ensure_signed(origin)?;
+			let beneficiary = T::Lookup::lookup(beneficiary)?;
+
+			let mut bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			let curator = match bounty.status {
+				BountyStatus::Active { curator } => curator,
+				_ => Err("Unexpected bounty status")?,
+			};
+			ensure!(signer == curator, "Require curator");
+
+			let unlock_at = system::Module::<T>::block_number() + T::BountyDepositPayoutDelay::get();
+			bounty.status = BountyStatus::PendingPayout { curator, beneficiary: beneficiary.clone(), unlock_at };
+			<Bounties<T>>::insert(bounty_id, bounty);
+			Self::deposit_event(RawEvent::BountyAwarded(bounty_id, beneficiary));
+		}
+
+		/// Pay out an awarded bounty: its fee to the curator, whose deposit is returned, and the
+		/// rest of its funds to the beneficiary.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Three balance operations.
+		/// - Two DB clears.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn claim_bounty(origin, #[compact] bounty_id: BountyIndex) {
+			ensure_signed(origin)?;
+
+			let bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			let (curator, beneficiary) = match bounty.status {
+				BountyStatus::PendingPayout { curator, beneficiary, unlock_at } => {
+					ensure!(system::Module::<T>::block_number() >= unlock_at, "Premature");
+					(curator, beneficiary)
+				}
+				_ => Err("Unexpected bounty status")?,
+			};
+
+			let bounty_account = Self::bounty_account_id(bounty_id);
+			let balance = T::Currency::free_balance(&bounty_account);
+			let fee = bounty.fee.min(balance);
+			let payout = balance - fee;
+			let _ = T::Currency::unreserve(&curator, bounty.curator_deposit);
+			// the bounty account is emptied, so none of these can fail.
+			let _ = T::Currency::transfer(&bounty_account, &curator, fee, ExistenceRequirement::AllowDeath);
+			let _ = T::Currency::transfer(&bounty_account, &beneficiary, payout, ExistenceRequirement::AllowDeath);
+
+			<Bounties<T>>::remove(bounty_id);
+			BountyDescriptions::remove(bounty_id);
+			Self::deposit_event(RawEvent::BountyClaimed(bounty_id, payout, beneficiary));
+		}
+
+		/// Cancel a bounty which isn't awarded yet.
+		///
+		/// A proposed bounty is rejected and its deposit slashed. The funds of a funded one are
+		/// returned to the pot, along with the deposit of its curator, if any.
+		///
+		/// The dispatch origin for this call must be `RejectOrigin`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Up to two balance operations.
+		/// - Two DB clears.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn close_bounty(origin, #[compact] bounty_id: BountyIndex) {
+			T::RejectOrigin::ensure_origin(origin)?;
+
+			let bounty = Self::bounties(bounty_id).ok_or("No bounty at that index")?;
+			match bounty.status {
+				BountyStatus::Proposed => {
+					let imbalance = T::Currency::slash_reserved(&bounty.proposer, bounty.bond).0;
+					T::ProposalRejection::on_unbalanced(imbalance);
+					<Bounties<T>>::remove(bounty_id);
+					BountyDescriptions::remove(bounty_id);
+					Self::deposit_event(RawEvent::BountyRejected(bounty_id, bounty.bond));
+					return Ok(());
+				}
+				BountyStatus::Approved => {
+					// the bounty is awaiting funding: drop it from the approvals and refund its
+					// proposer, as it would have been at funding.
+					BountyApprovals::mutate(|v| v.retain(|&i| i != bounty_id));
+					let _ = T::Currency::unreserve(&bounty.proposer, bounty.bond);
+				}
+				BountyStatus::Funded | BountyStatus::CuratorProposed { .. } => {}
+				BountyStatus::Active { ref curator } => {
+					let _ = T::Currency::unreserve(curator, bounty.curator_deposit);
+				}
+				BountyStatus::PendingPayout { .. } => Err("Pending payout")?,
+			}
+
+			let bounty_account = Self::bounty_account_id(bounty_id);
+			let balance = T::Currency::free_balance(&bounty_account);
+			// the bounty account is emptied into the pot, so this can't fail.
+			let _ = T::Currency::transfer(
+				&bounty_account,
+				&Self::account_id(),
+				balance,
+				ExistenceRequirement::AllowDeath,
+			);
+			<Bounties<T>>::remove(bounty_id);
+			BountyDescriptions::remove(bounty_id);
+			Self::deposit_event(RawEvent::BountyCanceled(bounty_id));
+		}
+
+		fn on_initialize(n: T::BlockNumber) {
+			// Schedule the periodic spend, at the next multiple of the spend period, if it's not
+			// already the case.
+			if !SpendScheduled::get() {
+				let period = T::SpendPeriod::get();
+				let when = n - n % period + period;
+				if T::Scheduler::schedule_named(
+					SPEND_ID.to_vec(),
+					when,
+					Some((period, u32::max_value())),
+					schedule::HARD_DEADLINE,
+					Call::spend_funds().into(),
+				).is_err() {
+					print("LOGIC ERROR: on_initialize/schedule_named failed");
+				}
+				SpendScheduled::put(true);
 			}
 		}
 	}
@@ -209,6 +751,69 @@ pub struct Proposal<AccountId, Balance> {
 	bond: Balance,
 }
 
+/// A tip, open until it's closed and paid out.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct OpenTip<AccountId, Balance, BlockNumber, Hash> {
+	/// The hash of the reason for the tip. The reason itself is stored in `Reasons`.
+	reason: Hash,
+	/// The account to be tipped.
+	who: AccountId,
+	/// The account which reported the tip and the amount it has on deposit for it, if any.
+	finder: Option<(AccountId, Balance)>,
+	/// The block number at which the tip may be closed, once a majority of the tippers declared
+	/// a value.
+	closes: Option<BlockNumber>,
+	/// The values declared by the tippers, sorted by tipper.
+	tips: Vec<(AccountId, Balance)>,
+}
+
+/// The status of a bounty.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum BountyStatus<AccountId, BlockNumber> {
+	/// The bounty is proposed and waiting for approval.
+	Proposed,
+	/// The bounty is approved and waiting to be funded at the next spend period.
+	Approved,
+	/// The bounty is funded and waiting for a curator.
+	Funded,
+	/// A curator has been proposed by the council and is waiting for their acceptance.
+	CuratorProposed {
+		/// The proposed curator.
+		curator: AccountId,
+	},
+	/// The bounty is managed by its curator.
+	Active {
+		/// The curator of the bounty.
+		curator: AccountId,
+	},
+	/// The bounty has been awarded and can be claimed once unlocked.
+	PendingPayout {
+		/// The curator of the bounty.
+		curator: AccountId,
+		/// The beneficiary of the bounty.
+		beneficiary: AccountId,
+		/// The block number from which the bounty can be claimed.
+		unlock_at: BlockNumber,
+	},
+}
+
+/// A bounty.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Bounty<AccountId, Balance, BlockNumber> {
+	/// The account proposing it.
+	proposer: AccountId,
+	/// The (total) amount that should be paid if the bounty is awarded.
+	value: Balance,
+	/// The curator fee, included in `value`.
+	fee: Balance,
+	/// The amount held on deposit by the curator.
+	curator_deposit: Balance,
+	/// The amount held on deposit by the proposer, returned when the bounty is funded.
+	bond: Balance,
+	/// The status of the bounty.
+	status: BountyStatus<AccountId, BlockNumber>,
+}
+
 decl_storage! {
 	trait Store for Module<T: Trait> as Treasury {
 		/// Number of proposals that have been made.
@@ -219,6 +824,28 @@ decl_storage! {
 
 		/// Proposal indices that have been approved but not yet awarded.
 		Approvals get(fn approvals): Vec<ProposalIndex>;
+
+		/// Whether the periodic spend has been scheduled.
+		SpendScheduled get(fn spend_scheduled): bool;
+
+		/// Tips that are not yet completed, keyed by the hash of `(reason, who)`.
+		pub Tips get(fn tips): map T::Hash => Option<OpenTip<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Hash>>;
+
+		/// The reasons of the open tips, keyed by their hash.
+		pub Reasons get(fn reasons): map T::Hash => Option<Vec<u8>>;
+
+		/// Number of bounties that have been proposed.
+		pub BountyCount get(fn bounty_count): BountyIndex;
+
+		/// Bounties that have been proposed.
+		pub Bounties get(fn bounties):
+			map BountyIndex => Option<Bounty<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+		/// The descriptions of the bounties.
+		pub BountyDescriptions get(fn bounty_descriptions): map BountyIndex => Option<Vec<u8>>;
+
+		/// Bounty indices that have been approved but not yet funded.
+		pub BountyApprovals get(fn bounty_approvals): Vec<BountyIndex>;
 	}
 	add_extra_genesis {
 		build(|_config| {
@@ -235,7 +862,8 @@ decl_event!(
 	pub enum Event<T>
 	where
 		Balance = BalanceOf<T>,
-		<T as system::Trait>::AccountId
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
 	{
 		/// New proposal.
 		Proposed(ProposalIndex),
@@ -249,6 +877,26 @@ decl_event!(
 		Rollover(Balance),
 		/// Some funds have been deposited.
 		Deposit(Balance),
+		/// A new tip suggestion has been opened.
+		NewTip(Hash),
+		/// A tip suggestion has reached threshold and is closing.
+		TipClosing(Hash),
+		/// A tip suggestion has been closed.
+		TipClosed(Hash, AccountId, Balance),
+		/// A tip suggestion has been retracted.
+		TipRetracted(Hash),
+		/// New bounty proposal.
+		BountyProposed(BountyIndex),
+		/// A bounty proposal was rejected; funds were slashed.
+		BountyRejected(BountyIndex, Balance),
+		/// A bounty proposal is funded and became active.
+		BountyBecameActive(BountyIndex),
+		/// A bounty is awarded to a beneficiary.
+		BountyAwarded(BountyIndex, AccountId),
+		/// A bounty is claimed by its beneficiary.
+		BountyClaimed(BountyIndex, Balance, AccountId),
+		/// A bounty is cancelled.
+		BountyCanceled(BountyIndex),
 	}
 );
 
@@ -263,13 +911,18 @@ impl<T: Trait> Module<T> {
 		MODULE_ID.into_account()
 	}
 
+	/// The account ID of a bounty, holding its funds.
+	pub fn bounty_account_id(id: BountyIndex) -> T::AccountId {
+		MODULE_ID.into_sub_account(("bt", id))
+	}
+
 	/// The needed bond for a proposal whose spend is `value`.
 	fn calculate_bond(value: BalanceOf<T>) -> BalanceOf<T> {
 		T::ProposalBondMinimum::get().max(T::ProposalBond::get() * value)
 	}
 
 	// Spend some money!
-	fn spend_funds() {
+	fn do_spend_funds() {
 		let mut budget_remaining = Self::pot();
 		Self::deposit_event(RawEvent::Spending(budget_remaining));
 
@@ -301,6 +954,34 @@ impl<T: Trait> Module<T> {
 			});
 		});
 
+		BountyApprovals::mutate(|v| {
+			v.retain(|&index| {
+				// Should always be true, but shouldn't panic if false or we're screwed.
+				if let Some(mut bounty) = Self::bounties(index) {
+					if bounty.value <= budget_remaining {
+						budget_remaining -= bounty.value;
+
+						// return their deposit.
+						let _ = T::Currency::unreserve(&bounty.proposer, bounty.bond);
+
+						// fund the bounty account.
+						let bounty_account = Self::bounty_account_id(index);
+						imbalance.subsume(T::Currency::deposit_creating(&bounty_account, bounty.value));
+
+						bounty.status = BountyStatus::Funded;
+						<Bounties<T>>::insert(index, bounty);
+						Self::deposit_event(RawEvent::BountyBecameActive(index));
+						false
+					} else {
+						missed_any = true;
+						true
+					}
+				} else {
+					false
+				}
+			});
+		});
+
 		if !missed_any {
 			// burn some proportion of the remaining budget if we run a surplus.
 			let burn = (T::Burn::get() * budget_remaining).min(budget_remaining);
@@ -327,6 +1008,50 @@ impl<T: Trait> Module<T> {
 		Self::deposit_event(RawEvent::Rollover(budget_remaining));
 	}
 
+	/// The number of tippers which must declare a value before a tip starts closing: a majority.
+	fn tip_threshold() -> usize {
+		(T::Tippers::count() + 1) / 2
+	}
+
+	/// Store `tip` under `hash`, starting its countdown if enough tippers declared a value.
+	fn insert_tip_and_check_closing(
+		hash: T::Hash,
+		mut tip: OpenTip<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Hash>,
+	) {
+		if tip.closes.is_none() && tip.tips.len() >= Self::tip_threshold() {
+			tip.closes = Some(system::Module::<T>::block_number() + T::TipCountdown::get());
+			Self::deposit_event(RawEvent::TipClosing(hash));
+		}
+		Tips::<T>::insert(&hash, tip);
+	}
+
+	/// Pay out the median of the values declared for `tip` by the current tippers, returning the
+	/// deposit of its finder.
+	fn payout_tip(hash: T::Hash, tip: OpenTip<T::AccountId, BalanceOf<T>, T::BlockNumber, T::Hash>) {
+		let mut tips = tip.tips.into_iter()
+			.filter(|(tipper, _)| T::Tippers::contains(tipper))
+			.map(|(_, value)| value)
+			.collect::<Vec<_>>();
+		tips.sort();
+		let treasury = Self::account_id();
+		let max_payout = Self::pot();
+		let mut payout = tips.get(tips.len() / 2).cloned().unwrap_or_else(Zero::zero).min(max_payout);
+		if let Some((finder, deposit)) = tip.finder {
+			let _ = T::Currency::unreserve(&finder, deposit);
+			if finder != tip.who {
+				// pay out the finder's fee.
+				let finders_fee = T::TipFindersFee::get() * payout;
+				payout -= finders_fee;
+				// this should go through given we checked it's at most the free balance, but still
+				// we only make a best-effort.
+				let _ = T::Currency::transfer(&treasury, &finder, finders_fee, ExistenceRequirement::KeepAlive);
+			}
+		}
+		// same as above: best-effort only.
+		let _ = T::Currency::transfer(&treasury, &tip.who, payout, ExistenceRequirement::KeepAlive);
+		Self::deposit_event(RawEvent::TipClosed(hash, tip.who, payout));
+	}
+
 	/// Return the amount of money in the pot.
 	// The existential deposit is not part of the pot so treasury account never gets deleted.
 	fn pot() -> BalanceOf<T> {
@@ -352,9 +1077,10 @@ mod tests {
 	use super::*;
 
 	use support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
+	use std::cell::RefCell;
 	use primitives::H256;
 	use sr_primitives::{
-		traits::{BlakeTwo256, OnFinalize, IdentityLookup}, testing::Header, Perbill
+		traits::{BlakeTwo256, OnInitialize, IdentityLookup}, testing::Header, Perbill
 	};
 
 	impl_outer_origin! {
@@ -408,6 +1134,53 @@ mod tests {
 		pub const SpendPeriod: u64 = 2;
 		pub const Burn: Permill = Permill::from_percent(50);
 	}
+	thread_local! {
+		static SCHEDULED: RefCell<Vec<(Vec<u8>, u64, Option<schedule::Period<u64>>)>> =
+			RefCell::new(Vec::new());
+	}
+	/// A scheduler that only records what it is asked to schedule.
+	pub struct TestScheduler;
+	impl schedule::Named<u64, Call<Test>> for TestScheduler {
+		type Address = u64;
+
+		fn schedule_named(
+			id: Vec<u8>,
+			when: u64,
+			maybe_periodic: Option<schedule::Period<u64>>,
+			_priority: schedule::Priority,
+			_call: Call<Test>,
+		) -> Result<u64, ()> {
+			SCHEDULED.with(|s| s.borrow_mut().push((id, when, maybe_periodic)));
+			Ok(when)
+		}
+
+		fn cancel_named(_id: Vec<u8>) -> Result<(), ()> {
+			Err(())
+		}
+	}
+	pub struct TenToFourteen;
+	impl Contains<u64> for TenToFourteen {
+		fn contains(n: &u64) -> bool {
+			*n >= 10 && *n <= 14
+		}
+	}
+	impl ContainsCount<u64> for TenToFourteen {
+		fn count() -> usize {
+			5
+		}
+	}
+	parameter_types! {
+		pub const TipCountdown: u64 = 1;
+		pub const TipFindersFee: Percent = Percent::from_percent(20);
+		pub const TipReportDepositBase: u64 = 1;
+		pub const TipReportDepositPerByte: u64 = 1;
+		pub const MaximumReasonLength: u32 = 16;
+		pub const BountyDepositBase: u64 = 80;
+		pub const BountyDepositPerByte: u64 = 1;
+		pub const BountyValueMinimum: u64 = 1;
+		pub const BountyCuratorDeposit: Permill = Permill::from_percent(50);
+		pub const BountyDepositPayoutDelay: u64 = 3;
+	}
 	impl Trait for Test {
 		type Currency = balances::Module<Test>;
 		type ApproveOrigin = system::EnsureRoot<u64>;
@@ -418,7 +1191,21 @@ mod tests {
 		type ProposalBondMinimum = ProposalBondMinimum;
 		type SpendPeriod = SpendPeriod;
 		type Burn = Burn;
+		type Call = Call<Test>;
+		type Scheduler = TestScheduler;
+		type Tippers = TenToFourteen;
+		type TipCountdown = TipCountdown;
+		type TipFindersFee = TipFindersFee;
+		type TipReportDepositBase = TipReportDepositBase;
+		type TipReportDepositPerByte = TipReportDepositPerByte;
+		type MaximumReasonLength = MaximumReasonLength;
+		type BountyDepositBase = BountyDepositBase;
+		type BountyDepositPerByte = BountyDepositPerByte;
+		type BountyValueMinimum = BountyValueMinimum;
+		type BountyCuratorDeposit = BountyCuratorDeposit;
+		type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
 	}
+	type System = system::Module<Test>;
 	type Balances = balances::Module<Test>;
 	type Treasury = Module<Test>;
 
@@ -427,7 +1214,6 @@ mod tests {
 		balances::GenesisConfig::<Test>{
 			// Total issuance will be 200 with treasury account initialized at ED.
 			balances: vec![(0, 100), (1, 98), (2, 1)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
 		t.into()
@@ -483,12 +1269,179 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100, 3));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0));
 
-			<Treasury as OnFinalize<u64>>::on_finalize(1);
+			<Treasury as OnInitialize<u64>>::on_initialize(1);
 			assert_eq!(Balances::free_balance(&3), 0);
 			assert_eq!(Treasury::pot(), 100);
 		});
 	}
 
+	#[test]
+	fn spend_is_scheduled_once() {
+		new_test_ext().execute_with(|| {
+			<Treasury as OnInitialize<u64>>::on_initialize(3);
+			<Treasury as OnInitialize<u64>>::on_initialize(4);
+			assert!(Treasury::spend_scheduled());
+			assert_eq!(
+				SCHEDULED.with(|s| s.borrow().clone()),
+				vec![(SPEND_ID.to_vec(), 4, Some((2, u32::max_value())))],
+			);
+		});
+	}
+
+	#[test]
+	fn spend_funds_requires_root() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(Treasury::spend_funds(Origin::signed(0)), "RequireRootOrigin");
+		});
+	}
+
+	fn tip_hash() -> H256 {
+		BlakeTwo256::hash_of(&(BlakeTwo256::hash(b"awesome.dot"), 3u64))
+	}
+
+	#[test]
+	fn tip_new_cannot_be_used_twice() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_noop!(Treasury::tip_new(Origin::signed(0), b"awesome.dot".to_vec(), 3, 10), "Not a tipper");
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 10));
+			assert_noop!(
+				Treasury::tip_new(Origin::signed(11), b"awesome.dot".to_vec(), 3, 10),
+				"Reason already known"
+			);
+		});
+	}
+
+	#[test]
+	fn report_awesome_and_tip_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_noop!(
+				Treasury::report_awesome(Origin::signed(0), b"a reason far too long".to_vec(), 3),
+				"Reason too big"
+			);
+			assert_noop!(
+				Treasury::report_awesome(Origin::signed(2), b"awesome.dot".to_vec(), 3),
+				"Finder's balance too low"
+			);
+			assert_ok!(Treasury::report_awesome(Origin::signed(0), b"awesome.dot".to_vec(), 3));
+			assert_eq!(Balances::reserved_balance(&0), 12);
+			assert_eq!(Balances::free_balance(&0), 88);
+			assert_eq!(Treasury::reasons(BlakeTwo256::hash(b"awesome.dot")), Some(b"awesome.dot".to_vec()));
+
+			let h = tip_hash();
+			assert_noop!(Treasury::tip(Origin::signed(0), h, 10), "Not a tipper");
+			assert_ok!(Treasury::tip(Origin::signed(10), h, 10));
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 10));
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h), "Tip not yet closing");
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 10));
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h), "Tip still closing");
+
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(100), h));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 102);
+			assert_eq!(Balances::free_balance(&3), 8);
+			assert_eq!(Treasury::tips(h), None);
+			assert_eq!(Treasury::reasons(BlakeTwo256::hash(b"awesome.dot")), None);
+		});
+	}
+
+	#[test]
+	fn report_awesome_from_beneficiary_and_tip_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::report_awesome(Origin::signed(0), b"awesome.dot".to_vec(), 0));
+			assert_eq!(Balances::reserved_balance(&0), 12);
+			assert_eq!(Balances::free_balance(&0), 88);
+			let h = BlakeTwo256::hash_of(&(BlakeTwo256::hash(b"awesome.dot"), 0u64));
+			assert_ok!(Treasury::tip(Origin::signed(10), h, 10));
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 10));
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(100), h));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 110);
+		});
+	}
+
+	#[test]
+	fn close_tip_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_eq!(Treasury::pot(), 100);
+
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 10));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 10));
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h), "Tip not yet closing");
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 10));
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h), "Tip still closing");
+
+			System::set_block_number(2);
+			assert_noop!(Treasury::close_tip(Origin::NONE, h), "RequireSignedOrigin");
+			assert_ok!(Treasury::close_tip(Origin::signed(0), h));
+			assert_eq!(Balances::free_balance(&3), 10);
+			assert_noop!(Treasury::close_tip(Origin::signed(100), h), "No tip at that hash");
+		});
+	}
+
+	#[test]
+	fn retract_tip_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::report_awesome(Origin::signed(0), b"awesome.dot".to_vec(), 3));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(10), h, 10));
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 10));
+			assert_noop!(Treasury::retract_tip(Origin::signed(10), h), "Not the finder");
+			assert_ok!(Treasury::retract_tip(Origin::signed(0), h));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 100);
+			System::set_block_number(2);
+			assert_noop!(Treasury::close_tip(Origin::signed(0), h), "No tip at that hash");
+		});
+	}
+
+	#[test]
+	fn tip_median_calculation_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 0));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 10));
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 1000000));
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(0), h));
+			assert_eq!(Balances::free_balance(&3), 10);
+		});
+	}
+
+	#[test]
+	fn tip_changing_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_ok!(Treasury::tip_new(Origin::signed(10), b"awesome.dot".to_vec(), 3, 10000));
+			let h = tip_hash();
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 10000));
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 10000));
+			assert_ok!(Treasury::tip(Origin::signed(13), h, 0));
+			assert_ok!(Treasury::tip(Origin::signed(14), h, 0));
+			assert_ok!(Treasury::tip(Origin::signed(12), h, 1000));
+			assert_ok!(Treasury::tip(Origin::signed(11), h, 100));
+			assert_ok!(Treasury::tip(Origin::signed(10), h, 10));
+			System::set_block_number(2);
+			assert_ok!(Treasury::close_tip(Origin::signed(0), h));
+			assert_eq!(Balances::free_balance(&3), 10);
+		});
+	}
+
 	#[test]
 	fn unused_pot_should_diminish() {
 		new_test_ext().execute_with(|| {
@@ -496,7 +1449,7 @@ mod tests {
 			Balances::make_free_balance_be(&Treasury::account_id(), 101);
 			assert_eq!(Balances::total_issuance(), init_total_issuance + 100);
 
-			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Treasury::pot(), 50);
 			assert_eq!(Balances::total_issuance(), init_total_issuance + 50);
 		});
@@ -510,7 +1463,7 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100, 3));
 			assert_ok!(Treasury::reject_proposal(Origin::ROOT, 0));
 
-			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Balances::free_balance(&3), 0);
 			assert_eq!(Treasury::pot(), 50);
 		});
@@ -561,7 +1514,7 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 100, 3));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0));
 
-			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Balances::free_balance(&3), 100);
 			assert_eq!(Treasury::pot(), 0);
 		});
@@ -576,11 +1529,11 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 150, 3));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0));
 
-			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Treasury::pot(), 100); // Pot hasn't changed
 
 			Balances::deposit_into_existing(&Treasury::account_id(), 100);
-			<Treasury as OnFinalize<u64>>::on_finalize(4);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Balances::free_balance(&3), 150); // Fund has been spent
 			assert_eq!(Treasury::pot(), 25); // Pot has finally changed
 		});
@@ -598,13 +1551,13 @@ mod tests {
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), treasury_balance, 3));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0));
 
-			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Treasury::pot(), 100); // Pot hasn't changed
 
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), Treasury::pot(), 3));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 1));
 
-			<Treasury as OnFinalize<u64>>::on_finalize(4);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Treasury::pot(), 0); // Pot is emptied
 			assert_eq!(Balances::free_balance(&Treasury::account_id()), 1); // but the account is still there
 		});
@@ -617,7 +1570,6 @@ mod tests {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test>{
 			balances: vec![(0, 100), (1, 99), (2, 1)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		// Treasury genesis config is not build thus treasury account does not exist
 		let mut t: runtime_io::TestExternalities = t.into();
@@ -630,7 +1582,7 @@ mod tests {
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 0));
 			assert_ok!(Treasury::propose_spend(Origin::signed(0), 1, 3));
 			assert_ok!(Treasury::approve_proposal(Origin::ROOT, 1));
-			<Treasury as OnFinalize<u64>>::on_finalize(2);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 			assert_eq!(Treasury::pot(), 0); // Pot hasn't changed
 			assert_eq!(Balances::free_balance(&3), 0); // Balance of `3` hasn't changed
 
@@ -638,10 +1590,160 @@ mod tests {
 			assert_eq!(Treasury::pot(), 99); // Pot now contains funds
 			assert_eq!(Balances::free_balance(&Treasury::account_id()), 100); // Account does exist
 
-			<Treasury as OnFinalize<u64>>::on_finalize(4);
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
 
 			assert_eq!(Treasury::pot(), 0); // Pot has changed
 			assert_eq!(Balances::free_balance(&3), 99); // Balance of `3` has changed
 		});
 	}
+
+	#[test]
+	fn propose_bounty_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 10, b"1234567890".to_vec()));
+			let deposit: u64 = 80 + 10;
+			assert_eq!(Balances::reserved_balance(&0), deposit);
+			assert_eq!(Balances::free_balance(&0), 100 - deposit);
+			assert_eq!(Treasury::bounties(0), Some(Bounty {
+				proposer: 0,
+				fee: 0,
+				curator_deposit: 0,
+				value: 10,
+				bond: deposit,
+				status: BountyStatus::Proposed,
+			}));
+			assert_eq!(Treasury::bounty_descriptions(0), Some(b"1234567890".to_vec()));
+			assert_eq!(Treasury::bounty_count(), 1);
+		});
+	}
+
+	#[test]
+	fn propose_bounty_validation_works() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Treasury::propose_bounty(Origin::signed(0), 10, [0; 17].to_vec()),
+				"Reason too big"
+			);
+			assert_noop!(
+				Treasury::propose_bounty(Origin::signed(0), 0, b"1234567890".to_vec()),
+				"Invalid bounty value"
+			);
+			assert_noop!(
+				Treasury::propose_bounty(Origin::signed(2), 10, b"1234567890".to_vec()),
+				"Proposer's balance too low"
+			);
+		});
+	}
+
+	#[test]
+	fn close_proposed_bounty_slashes_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 10, b"12345".to_vec()));
+			assert_noop!(Treasury::close_bounty(Origin::signed(0), 0), "RequireRootOrigin");
+			assert_ok!(Treasury::close_bounty(Origin::ROOT, 0));
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 100 - 85);
+			assert_eq!(Treasury::bounties(0), None);
+			assert_eq!(Treasury::bounty_descriptions(0), None);
+			assert_noop!(Treasury::close_bounty(Origin::ROOT, 0), "No bounty at that index");
+		});
+	}
+
+	#[test]
+	fn approve_bounty_works() {
+		new_test_ext().execute_with(|| {
+			Balances::make_free_balance_be(&Treasury::account_id(), 101);
+			assert_noop!(Treasury::approve_bounty(Origin::ROOT, 0), "No bounty at that index");
+			assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+			assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+			assert_noop!(Treasury::approve_bounty(Origin::ROOT, 0), "Unexpected bounty status");
+			assert_eq!(Treasury::bounty_approvals(), vec![0]);
+
+			assert_ok!(Treasury::spend_funds(Origin::ROOT));
+			// the proposer gets their deposit back and the bounty is funded.
+			assert_eq!(Balances::reserved_balance(&0), 0);
+			assert_eq!(Balances::free_balance(&0), 100);
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 50);
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Funded);
+			assert!(Treasury::bounty_approvals().is_empty());
+			// the rest of the pot is subject to the burn.
+			assert_eq!(Treasury::pot(), 25);
+		});
+	}
+
+	fn funded_bounty() {
+		Balances::make_free_balance_be(&Treasury::account_id(), 101);
+		Balances::make_free_balance_be(&4, 10);
+		assert_ok!(Treasury::propose_bounty(Origin::signed(0), 50, b"12345".to_vec()));
+		assert_ok!(Treasury::approve_bounty(Origin::ROOT, 0));
+		assert_ok!(Treasury::spend_funds(Origin::ROOT));
+	}
+
+	#[test]
+	fn award_and_claim_bounty_works() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			funded_bounty();
+			assert_noop!(Treasury::propose_curator(Origin::ROOT, 0, 4, 50), "Curator fee too high");
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_noop!(Treasury::accept_curator(Origin::signed(1), 0), "Require curator");
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+			assert_eq!(Balances::reserved_balance(&4), 2);
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Active { curator: 4 });
+
+			assert_noop!(Treasury::award_bounty(Origin::signed(1), 0, 3), "Require curator");
+			assert_ok!(Treasury::award_bounty(Origin::signed(4), 0, 3));
+			assert_noop!(Treasury::close_bounty(Origin::ROOT, 0), "Pending payout");
+			assert_noop!(Treasury::claim_bounty(Origin::signed(1), 0), "Premature");
+
+			System::set_block_number(4);
+			assert_ok!(Treasury::claim_bounty(Origin::signed(1), 0));
+			assert_eq!(Balances::reserved_balance(&4), 0);
+			assert_eq!(Balances::free_balance(&4), 14);
+			assert_eq!(Balances::free_balance(&3), 46);
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 0);
+			assert_eq!(Treasury::bounties(0), None);
+			assert_eq!(Treasury::bounty_descriptions(0), None);
+		});
+	}
+
+	#[test]
+	fn unassign_curator_works() {
+		new_test_ext().execute_with(|| {
+			funded_bounty();
+			assert_noop!(Treasury::unassign_curator(Origin::signed(4), 0), "Unexpected bounty status");
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_noop!(Treasury::unassign_curator(Origin::signed(1), 0), "Require curator");
+			assert_ok!(Treasury::unassign_curator(Origin::signed(4), 0));
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Funded);
+
+			// an active curator unassigning themselves gets their deposit back.
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+			assert_ok!(Treasury::unassign_curator(Origin::signed(4), 0));
+			assert_eq!(Balances::free_balance(&4), 10);
+
+			// an active curator unassigned by the council loses their deposit.
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+			assert_ok!(Treasury::unassign_curator(Origin::ROOT, 0));
+			assert_eq!(Balances::reserved_balance(&4), 0);
+			assert_eq!(Balances::free_balance(&4), 8);
+			assert_eq!(Treasury::bounties(0).unwrap().status, BountyStatus::Funded);
+		});
+	}
+
+	#[test]
+	fn close_funded_bounty_returns_funds_to_the_pot() {
+		new_test_ext().execute_with(|| {
+			funded_bounty();
+			assert_ok!(Treasury::propose_curator(Origin::ROOT, 0, 4, 4));
+			assert_ok!(Treasury::accept_curator(Origin::signed(4), 0));
+			assert_ok!(Treasury::close_bounty(Origin::ROOT, 0));
+			assert_eq!(Balances::free_balance(&4), 10);
+			assert_eq!(Balances::free_balance(&Treasury::bounty_account_id(0)), 0);
+			assert_eq!(Treasury::pot(), 75);
+			assert_eq!(Treasury::bounties(0), None);
+		});
+	}
 }
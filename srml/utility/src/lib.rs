@@ -16,14 +16,30 @@
 
 //! # Utility Module
 //! A module full of useful helpers for practical chain management.
+//!
+//! ## Overview
+//!
+//! The utility module allows a batch of calls to be dispatched from a single extrinsic, e.g. to
+//! `bond` and `nominate` at once. Each call of the batch is dispatched with the origin of the
+//! batch itself, so batching gives no privilege that the calls wouldn't have on their own.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `batch` - Dispatch a batch of calls, stopping at the first one that fails.
+//! * `batch_all` - Dispatch a batch of calls, failing as a whole if any of them fails.
 
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use rstd::prelude::*;
-use support::{decl_module, decl_event, Parameter};
-use system::ensure_root;
-use sr_primitives::{traits::Dispatchable, weights::SimpleDispatchInfo, DispatchError};
+use support::{decl_module, decl_event, Parameter, storage::{with_transaction, TransactionOutcome}};
+use sr_primitives::{
+	DispatchError,
+	traits::Dispatchable,
+	weights::{Weight, GetDispatchInfo, WeighData, ClassifyDispatch, DispatchClass},
+};
 
 /// Configuration trait.
 pub trait Trait: system::Trait {
@@ -31,13 +47,44 @@ pub trait Trait: system::Trait {
 	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
 
 	/// The overarching call type.
-	type Call: Parameter + Dispatchable<Origin=Self::Origin>;
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+}
+
+/// The weight of a batch: a base weight plus the weight of each of its calls.
+///
+/// A batch is operational only if all of its calls are.
+struct BatchWeight(Weight);
+
+impl<Call: GetDispatchInfo> WeighData<(&Vec<Call>,)> for BatchWeight {
+	fn weigh_data(&self, (calls,): (&Vec<Call>,)) -> Weight {
+		calls.iter()
+			.map(|call| call.get_dispatch_info().weight)
+			.fold(self.0, |total, weight| total.saturating_add(weight))
+	}
+}
+
+impl<Call: GetDispatchInfo> ClassifyDispatch<(&Vec<Call>,)> for BatchWeight {
+	fn classify_dispatch(&self, (calls,): (&Vec<Call>,)) -> DispatchClass {
+		let all_operational = calls.iter()
+			.all(|call| call.get_dispatch_info().class == DispatchClass::Operational);
+		if all_operational {
+			DispatchClass::Operational
+		} else {
+			DispatchClass::Normal
+		}
+	}
 }
 
 decl_event!(
 	/// Events type.
 	pub enum Event {
-		BatchExecuted(Vec<Result<(), DispatchError>>),
+		/// Batch of dispatches did not complete fully. Index of first failing dispatch given, as
+		/// well as the error.
+		BatchInterrupted(u32, DispatchError),
+		/// Batch of dispatches completed fully with no error.
+		BatchCompleted,
+		/// A single item within a batch of dispatches has completed with no error.
+		ItemCompleted,
 	}
 );
 
@@ -46,26 +93,76 @@ decl_module! {
 		/// Deposit one of this module's events by using the default implementation.
 		fn deposit_event() = default;
 
-		/// Send a batch of dispatch calls (only root).
-		#[weight = SimpleDispatchInfo::FreeOperational]
+		/// Send a batch of dispatch calls.
+		///
+		/// Each call is dispatched with the origin of the batch. An `ItemCompleted` event is
+		/// deposited for each call that succeeds. The batch stops at the first call that fails,
+		/// depositing a `BatchInterrupted` event, and deposits `BatchCompleted` otherwise. In both
+		/// cases the batch itself succeeds.
+		///
+		/// # <weight>
+		/// - The sum of the weights of the `calls`.
+		/// - One event per call.
+		/// # </weight>
+		#[weight = BatchWeight(10_000)]
 		fn batch(origin, calls: Vec<<T as Trait>::Call>) {
-			ensure_root(origin)?;
-			let results = calls.into_iter()
-				.map(|call| call.dispatch(system::RawOrigin::Root.into()))
-				.map(|res| res.map_err(Into::into))
-				.collect::<Vec<_>>();
-			Self::deposit_event(Event::BatchExecuted(results));
+			let origin = Self::system_origin(origin)?;
+			for (index, call) in calls.into_iter().enumerate() {
+				if let Err(e) = call.dispatch(origin.clone().into()) {
+					Self::deposit_event(Event::BatchInterrupted(index as u32, e.into()));
+					return Ok(());
+				}
+				Self::deposit_event(Event::ItemCompleted);
+			}
+			Self::deposit_event(Event::BatchCompleted);
+		}
+
+		/// Send a batch of dispatch calls, failing as a whole if any of them fails.
+		///
+		/// Each call is dispatched with the origin of the batch. An `ItemCompleted` event is
+		/// deposited for each call that succeeds and `BatchCompleted` once they all have. The
+		/// batch stops at the first call that fails and returns its error.
+		///
+		/// The calls are dispatched in a storage transaction, so when one fails, the storage
+		/// changes of all the calls of the batch are reverted.
+		///
+		/// # <weight>
+		/// - The sum of the weights of the `calls`.
+		/// - One event per call.
+		/// # </weight>
+		#[weight = BatchWeight(10_000)]
+		fn batch_all(origin, calls: Vec<<T as Trait>::Call>) {
+			let origin = Self::system_origin(origin)?;
+			with_transaction(|| {
+				for call in calls {
+					if let Err(e) = call.dispatch(origin.clone().into()) {
+						let message = Into::<DispatchError>::into(e).message.unwrap_or("Batch item failed");
+						return TransactionOutcome::Rollback(Err(message));
+					}
+					Self::deposit_event(Event::ItemCompleted);
+				}
+				Self::deposit_event(Event::BatchCompleted);
+				TransactionOutcome::Commit(Ok(()))
+			})?;
 		}
 	}
 }
 
+impl<T: Trait> Module<T> {
+	/// The system origin of `origin`, which the calls of a batch are dispatched with.
+	fn system_origin(origin: T::Origin) -> Result<system::RawOrigin<T::AccountId>, &'static str> {
+		origin.into().map_err(|_| "bad origin")
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
-	use support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch};
+	use support::{assert_ok, impl_outer_origin, parameter_types, impl_outer_dispatch};
 	use primitives::H256;
 	use sr_primitives::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use sr_primitives::weights::GetDispatchInfo;
 
 	impl_outer_origin! {
 		pub enum Origin for Test {}
@@ -133,20 +230,19 @@ mod tests {
 		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		balances::GenesisConfig::<Test> {
 			balances: vec![(1, 10), (2, 0)],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		t.into()
 	}
 
+	fn transfer(value: u64) -> Call {
+		Call::Balances(balances::Call::transfer(2, value))
+	}
+
 	#[test]
 	fn batch_works() {
 		new_test_ext().execute_with(|| {
 			assert_eq!(Balances::free_balance(1), 10);
 			assert_eq!(Balances::free_balance(2), 0);
-			assert_noop!(Utility::batch(Origin::signed(1), vec![
-				Call::Balances(balances::Call::force_transfer(1, 2, 5)),
-				Call::Balances(balances::Call::force_transfer(1, 2, 5))
-			]), "RequireRootOrigin");
 			assert_ok!(Utility::batch(Origin::ROOT, vec![
 				Call::Balances(balances::Call::force_transfer(1, 2, 5)),
 				Call::Balances(balances::Call::force_transfer(1, 2, 5))
@@ -155,4 +251,61 @@ mod tests {
 			assert_eq!(Balances::free_balance(2), 10);
 		});
 	}
+
+	#[test]
+	fn batch_with_signed_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Utility::batch(Origin::signed(1), vec![transfer(5), transfer(5)]));
+			assert_eq!(Balances::free_balance(1), 0);
+			assert_eq!(Balances::free_balance(2), 10);
+		});
+	}
+
+	#[test]
+	fn batch_keeps_the_origin_of_its_calls() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Utility::batch(Origin::signed(1), vec![
+				Call::Balances(balances::Call::force_transfer(1, 2, 5)),
+			]));
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::free_balance(2), 0);
+		});
+	}
+
+	#[test]
+	fn batch_early_exit_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Utility::batch(Origin::signed(1), vec![transfer(5), transfer(10), transfer(5)]));
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::free_balance(2), 5);
+		});
+	}
+
+	#[test]
+	fn batch_all_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Utility::batch_all(Origin::signed(1), vec![transfer(5), transfer(5)]));
+			assert_eq!(Balances::free_balance(1), 0);
+			assert_eq!(Balances::free_balance(2), 10);
+		});
+	}
+
+	#[test]
+	fn batch_all_fails_with_its_first_failing_call() {
+		new_test_ext().execute_with(|| {
+			assert!(Utility::batch_all(Origin::signed(1), vec![transfer(10), transfer(10), transfer(5)]).is_err());
+			// the transfer of the first call is reverted.
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::free_balance(2), 0);
+		});
+	}
+
+	#[test]
+	fn batch_weight_is_the_sum_of_its_calls() {
+		let calls = vec![transfer(5), transfer(5)];
+		let calls_weight: Weight = calls.iter().map(|c| c.get_dispatch_info().weight).sum();
+		let info = Call::Utility(super::Call::batch(calls)).get_dispatch_info();
+		assert_eq!(info.weight, 10_000 + calls_weight);
+		assert_eq!(info.class, DispatchClass::Normal);
+	}
 }
@@ -0,0 +1,621 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Vesting Module
+//!
+//! - [`vesting::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A simple module providing a means of placing a linear curve on an account's locked balance.
+//! This module ensures that there is a lock in place preventing the balance to drop below the
+//! *unvested* amount for any reason other than transaction fee payment.
+//!
+//! As the amount vested increases over time, the amount unvested reduces. However, locks remain
+//! in place and explicit action is needed on behalf of the user to ensure that the amount locked
+//! is equivalent to the amount remaining to be vested. This is done through a dispatchable
+//! function, either `vest` (in typical case where the sender is calling on their own behalf) or
+//! `vest_other` in case the sender is calling on another account's behalf.
+//!
+//! The vesting schedules are either set at genesis, or created by transferring some funds along
+//! with the schedule through `vested_transfer`.
+//!
+//! ### Migration from the balances module
+//!
+//! Vesting used to be part of the balances module. Its schedules are stored in a map which can't
+//! be iterated, so they can't be moved all at once on upgrade. Instead the schedule of an account
+//! is moved here, and its lock set, the first time the account vests. The balances module keeps
+//! enforcing the schedules which weren't moved yet.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `vest` - Update the lock, reducing it in line with the amount "vested" so far.
+//! - `vest_other` - Update the lock of another account, reducing it in line with the amount
+//!   "vested" so far.
+//! - `vested_transfer` - Transfer some funds to another account, along with a vesting schedule
+//!   locking them.
+//!
+//! ### Public Functions
+//!
+//! - `vesting_balance` - Get the amount that is currently being vested and cannot be transferred
+//!   out of an account.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use rstd::prelude::*;
+use codec::{Encode, Decode};
+use sr_primitives::{
+	RuntimeDebug, weights::SimpleDispatchInfo,
+	traits::{SimpleArithmetic, StaticLookup, Zero, One, Convert, Bounded, Saturating},
+};
+use support::{
+	decl_module, decl_event, decl_storage, ensure,
+	traits::{
+		Currency, LockableCurrency, WithdrawReason, WithdrawReasons, LockIdentifier, Get,
+		ExistenceRequirement,
+	},
+};
+use system::ensure_signed;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+const VESTING_ID: LockIdentifier = *b"vesting ";
+
+/// The storage prefix of the vesting schedules of the balances module, from when vesting was part
+/// of it.
+const LEGACY_VESTING_PREFIX: &[u8] = b"Balances Vesting";
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency trait.
+	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+
+	/// Convert the block number into a balance.
+	type BlockNumberToBalance: Convert<Self::BlockNumber, BalanceOf<Self>>;
+
+	/// The minimum amount transferred to create a vesting schedule through `vested_transfer`.
+	type MinVestedTransfer: Get<BalanceOf<Self>>;
+}
+
+/// Struct to encode the vesting schedule of an individual account.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingInfo<Balance, BlockNumber> {
+	/// Locked amount at genesis.
+	pub locked: Balance,
+	/// Amount that gets unlocked every block after `starting_block`.
+	pub per_block: Balance,
+	/// Starting block for unlocking(vesting).
+	pub starting_block: BlockNumber,
+}
+
+impl<
+	Balance: SimpleArithmetic + Copy,
+	BlockNumber: SimpleArithmetic + Copy,
+> VestingInfo<Balance, BlockNumber> {
+	/// Amount locked at block `n`.
+	pub fn locked_at<BlockNumberToBalance: Convert<BlockNumber, Balance>>(&self, n: BlockNumber) -> Balance {
+		// Number of blocks that count toward vesting
+		// Saturating to 0 when n < starting_block
+		let vested_block_count = n.saturating_sub(self.starting_block);
+		// Return amount that is still locked in vesting
+		if let Some(x) = BlockNumberToBalance::convert(vested_block_count).checked_mul(&self.per_block) {
+			self.locked.max(x) - x
+		} else {
+			Zero::zero()
+		}
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Vesting {
+		/// Information regarding the vesting of a given account.
+		pub Vesting get(fn vesting): map T::AccountId => Option<VestingInfo<BalanceOf<T>, T::BlockNumber>>;
+	}
+	add_extra_genesis {
+		config(vesting): Vec<(T::AccountId, T::BlockNumber, T::BlockNumber, BalanceOf<T>)>;
+		// ^^ begin, length, amount liquid at genesis
+		build(|config: &GenesisConfig<T>| {
+			// Generate initial vesting configuration
+			// * who - Account which we are generating vesting configuration for
+			// * begin - Block when the account will start to vest
+			// * length - Number of blocks from `begin` until fully vested
+			// * liquid - Number of units which can be spent before vesting begins
+			for &(ref who, begin, length, liquid) in config.vesting.iter() {
+				let balance = T::Currency::free_balance(who);
+				assert!(!balance.is_zero(), "Currencies must be init'd before vesting");
+				// Total genesis `balance` minus `liquid` equals funds locked for vesting
+				let locked = balance.saturating_sub(liquid);
+				let length = T::BlockNumberToBalance::convert(length);
+				// Number of units unlocked per block after `begin`
+				let per_block = locked / length.max(One::one());
+
+				Vesting::<T>::insert(who, VestingInfo {
+					locked,
+					per_block,
+					starting_block: begin,
+				});
+				let reasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+				T::Currency::set_lock(VESTING_ID, who, locked, T::BlockNumber::max_value(), reasons);
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, Balance = BalanceOf<T> {
+		/// The amount vested has been updated. This could indicate more funds are available. The
+		/// balance given is the amount which is left unvested (and thus locked).
+		VestingUpdated(AccountId, Balance),
+		/// An account (given) has become fully vested. No further vesting can happen.
+		VestingCompleted(AccountId),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The minimum amount transferred to create a vesting schedule through `vested_transfer`.
+		const MinVestedTransfer: BalanceOf<T> = T::MinVestedTransfer::get();
+
+		fn deposit_event() = default;
+
+		/// Unlock any vested funds of the sender account.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the sender must have funds still
+		/// locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One balance-lock operation.
+		/// - One storage read (codec `O(1)`) and up to one removal.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn vest(origin) {
+			let who = ensure_signed(origin)?;
+			Self::update_lock(who)?;
+		}
+
+		/// Unlock any vested funds of a `target` account.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account whose vested funds should be unlocked. Must have funds still
+		/// locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One balance-lock operation.
+		/// - One storage read (codec `O(1)`) and up to one removal.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn vest_other(origin, target: <T::Lookup as StaticLookup>::Source) {
+			ensure_signed(origin)?;
+			Self::update_lock(T::Lookup::lookup(target)?)?;
+		}
+
+		/// Create a vested transfer: transfer `schedule.locked` from the sender to `target`, and
+		/// lock it there according to `schedule`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account that should receive the funds. Must not already have a vesting
+		/// schedule.
+		/// - `schedule`: The vesting schedule attached to the transfer. At least
+		/// `MinVestedTransfer` must be locked.
+		///
+		/// Emits `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(1)`.
+		/// - One balance transfer and one balance-lock operation.
+		/// - One storage read (codec `O(1)`) and one write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn vested_transfer(
+			origin,
+			target: <T::Lookup as StaticLookup>::Source,
+			schedule: VestingInfo<BalanceOf<T>, T::BlockNumber>,
+		) {
+			let transactor = ensure_signed(origin)?;
+			ensure!(schedule.locked >= T::MinVestedTransfer::get(), "Amount too low");
+
+			let who = T::Lookup::lookup(target)?;
+			Self::migrate_legacy_schedule(&who);
+			ensure!(!<Vesting<T>>::exists(&who), "Existing vesting schedule");
+
+			T::Currency::transfer(&transactor, &who, schedule.locked, ExistenceRequirement::AllowDeath)?;
+			<Vesting<T>>::insert(&who, schedule);
+			Self::update_lock(who)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Get the amount that is currently being vested and cannot be transferred out of this account.
+	pub fn vesting_balance(who: &T::AccountId) -> BalanceOf<T> {
+		if let Some(v) = Self::vesting(who) {
+			let now = <system::Module<T>>::block_number();
+			T::Currency::free_balance(who).min(v.locked_at::<T::BlockNumberToBalance>(now))
+		} else {
+			Zero::zero()
+		}
+	}
+
+	/// (Re)set or remove the module's currency lock on `who`'s account in accordance with their
+	/// current unvested amount.
+	fn update_lock(who: T::AccountId) -> support::dispatch::Result {
+		Self::migrate_legacy_schedule(&who);
+		let vesting = Self::vesting(&who).ok_or("Not vesting")?;
+		let now = <system::Module<T>>::block_number();
+		let locked_now = vesting.locked_at::<T::BlockNumberToBalance>(now);
+
+		if locked_now.is_zero() {
+			T::Currency::remove_lock(VESTING_ID, &who);
+			<Vesting<T>>::remove(&who);
+			Self::deposit_event(RawEvent::VestingCompleted(who));
+		} else {
+			let reasons: WithdrawReasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+			T::Currency::set_lock(VESTING_ID, &who, locked_now, T::BlockNumber::max_value(), reasons);
+			Self::deposit_event(RawEvent::VestingUpdated(who, locked_now));
+		}
+		Ok(())
+	}
+
+	/// The storage key of the vesting schedule of `who` in the balances module.
+	fn legacy_schedule_key(who: &T::AccountId) -> [u8; 32] {
+		let mut key = LEGACY_VESTING_PREFIX.to_vec();
+		who.encode_to(&mut key);
+		runtime_io::blake2_256(&key)
+	}
+
+	/// Move the vesting schedule of `who` from the balances module, if it still has one there.
+	///
+	/// The schedule is left there if `who` already has a schedule here, it keeps being enforced
+	/// by the balances module.
+	fn migrate_legacy_schedule(who: &T::AccountId) {
+		if <Vesting<T>>::exists(who) {
+			return;
+		}
+		let key = Self::legacy_schedule_key(who);
+		if let Some(schedule) = support::storage::unhashed::take::<VestingInfo<BalanceOf<T>, T::BlockNumber>>(&key) {
+			<Vesting<T>>::insert(who, schedule);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, StorageMap};
+	use primitives::H256;
+	use sr_primitives::{Perbill, traits::{BlakeTwo256, IdentityLookup, ConvertInto}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: u32 = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 256;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const MinVestedTransfer: u64 = 256 * 2;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type BlockNumberToBalance = ConvertInto;
+		type MinVestedTransfer = MinVestedTransfer;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type Vesting = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		balances::GenesisConfig::<Test> {
+			balances: vec![
+				(1, 256 * 10),
+				(2, 256 * 20),
+				(3, 256 * 30),
+				(4, 256 * 40),
+				(12, 256 * 10),
+			],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Test> {
+			vesting: vec![
+				(1, 0, 10, 256 * 5),
+				(2, 10, 20, 0),
+				(12, 10, 20, 256 * 5),
+			],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn check_vesting_status() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(System::block_number(), 1);
+			let user1_free_balance = Balances::free_balance(&1);
+			let user2_free_balance = Balances::free_balance(&2);
+			let user12_free_balance = Balances::free_balance(&12);
+			assert_eq!(user1_free_balance, 256 * 10); // Account 1 has free balance
+			assert_eq!(user2_free_balance, 256 * 20); // Account 2 has free balance
+			assert_eq!(user12_free_balance, 256 * 10); // Account 12 has free balance
+			let user1_vesting_schedule = VestingInfo {
+				locked: 256 * 5,
+				per_block: 128, // Vesting over 10 blocks
+				starting_block: 0,
+			};
+			let user2_vesting_schedule = VestingInfo {
+				locked: 256 * 20,
+				per_block: 256, // Vesting over 20 blocks
+				starting_block: 10,
+			};
+			let user12_vesting_schedule = VestingInfo {
+				locked: 256 * 5,
+				per_block: 64, // Vesting over 20 blocks
+				starting_block: 10,
+			};
+			assert_eq!(Vesting::vesting(&1), Some(user1_vesting_schedule)); // Account 1 has a vesting schedule
+			assert_eq!(Vesting::vesting(&2), Some(user2_vesting_schedule)); // Account 2 has a vesting schedule
+			assert_eq!(Vesting::vesting(&12), Some(user12_vesting_schedule)); // Account 12 has a vesting schedule
+
+			// Account 1 has only 128 units vested from their illiquid 256 * 5 units at block 1
+			assert_eq!(Vesting::vesting_balance(&1), 128 * 9);
+			// Account 2 has their full balance locked
+			assert_eq!(Vesting::vesting_balance(&2), user2_free_balance);
+			// Account 12 has only their illiquid funds locked
+			assert_eq!(Vesting::vesting_balance(&12), user12_free_balance - 256 * 5);
+
+			System::set_block_number(10);
+			assert_eq!(System::block_number(), 10);
+
+			// Account 1 has fully vested by block 10
+			assert_eq!(Vesting::vesting_balance(&1), 0);
+			// Account 2 has started vesting by block 10
+			assert_eq!(Vesting::vesting_balance(&2), user2_free_balance);
+			// Account 12 has started vesting by block 10
+			assert_eq!(Vesting::vesting_balance(&12), user12_free_balance - 256 * 5);
+
+			System::set_block_number(30);
+			assert_eq!(System::block_number(), 30);
+
+			assert_eq!(Vesting::vesting_balance(&1), 0); // Account 1 is still fully vested, and not negative
+			assert_eq!(Vesting::vesting_balance(&2), 0); // Account 2 has fully vested by block 30
+			assert_eq!(Vesting::vesting_balance(&12), 0); // Account 2 has fully vested by block 30
+		});
+	}
+
+	#[test]
+	fn unvested_balance_should_not_transfer() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(System::block_number(), 1);
+			let user1_free_balance = Balances::free_balance(&1);
+			assert_eq!(user1_free_balance, 2560); // Account 1 has free balance
+			// Account 1 has only 128 units vested at block 1 (plus 1280 unvested)
+			assert_eq!(Vesting::vesting_balance(&1), 1152);
+			assert_noop!(
+				Balances::transfer(Some(1).into(), 2, 1281),
+				"account liquidity restrictions prevent withdrawal",
+			); // Account 1 cannot send more than vested amount
+		});
+	}
+
+	#[test]
+	fn vested_balance_should_transfer() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(System::block_number(), 1);
+			let user1_free_balance = Balances::free_balance(&1);
+			assert_eq!(user1_free_balance, 2560); // Account 1 has free balance
+			// Account 1 has only 128 units vested at block 1 (plus 1280 unvested)
+			assert_eq!(Vesting::vesting_balance(&1), 1152);
+			assert_noop!(
+				Balances::transfer(Some(1).into(), 2, 1408),
+				"account liquidity restrictions prevent withdrawal",
+			); // The lock is only updated by `vest`
+			assert_ok!(Vesting::vest(Some(1).into()));
+			assert_ok!(Balances::transfer(Some(1).into(), 2, 1408));
+		});
+	}
+
+	#[test]
+	fn legacy_schedule_should_be_migrated_on_vest() {
+		new_test_ext().execute_with(|| {
+			<balances::Vesting<Test>>::insert(3, balances::VestingSchedule {
+				locked: 256 * 30,
+				per_block: 256,
+				starting_block: 10,
+			});
+			// the schedule is enforced by the balances module until it's migrated
+			assert_noop!(
+				Balances::transfer(Some(3).into(), 1, 256),
+				"vesting balance too high to send value",
+			);
+
+			assert_ok!(Vesting::vest_other(Some(1).into(), 3));
+			assert_eq!(Balances::legacy_vesting(&3), None);
+			assert_eq!(Vesting::vesting(&3), Some(VestingInfo {
+				locked: 256 * 30,
+				per_block: 256,
+				starting_block: 10,
+			}));
+			assert_noop!(
+				Balances::transfer(Some(3).into(), 1, 256),
+				"account liquidity restrictions prevent withdrawal",
+			);
+
+			System::set_block_number(40);
+			assert_ok!(Vesting::vest(Some(3).into()));
+			assert_eq!(Vesting::vesting(&3), None);
+			assert_ok!(Balances::transfer(Some(3).into(), 1, 256));
+		});
+	}
+
+	#[test]
+	fn vested_balance_should_transfer_using_vest_other() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(Vesting::vesting_balance(&1), 1152);
+			assert_ok!(Vesting::vest_other(Some(2).into(), 1));
+			assert_ok!(Balances::transfer(Some(1).into(), 2, 1408));
+			assert_noop!(Vesting::vest_other(Some(2).into(), 3), "Not vesting");
+		});
+	}
+
+	#[test]
+	fn extra_balance_should_transfer() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(System::block_number(), 1);
+			assert_ok!(Balances::transfer(Some(3).into(), 1, 100));
+			assert_ok!(Balances::transfer(Some(3).into(), 2, 100));
+
+			let user1_free_balance = Balances::free_balance(&1);
+			assert_eq!(user1_free_balance, 2660); // Account 1 has 100 more free balance than normal
+
+			let user2_free_balance = Balances::free_balance(&2);
+			assert_eq!(user2_free_balance, 5220); // Account 2 has 100 more free balance than normal
+
+			// Account 1 has only 128 units vested at block 1 (plus 1380 unvested)
+			assert_eq!(Vesting::vesting_balance(&1), 1152);
+			assert_ok!(Vesting::vest(Some(1).into()));
+			assert_ok!(Balances::transfer(Some(1).into(), 3, 1508)); // Account 1 can send extra units gained
+
+			// Account 2 has no units vested at block 1, but gained 100
+			assert_eq!(Vesting::vesting_balance(&2), 5120);
+			assert_ok!(Vesting::vest(Some(2).into()));
+			assert_ok!(Balances::transfer(Some(2).into(), 3, 100)); // Account 2 can send extra units gained
+		});
+	}
+
+	#[test]
+	fn liquid_funds_should_transfer_with_delayed_vesting() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(System::block_number(), 1);
+			let user12_free_balance = Balances::free_balance(&12);
+
+			assert_eq!(user12_free_balance, 2560); // Account 12 has free balance
+			// Account 12 has liquid funds
+			assert_eq!(Vesting::vesting_balance(&12), user12_free_balance - 256 * 5);
+
+			// Account 12 has delayed vesting
+			let user12_vesting_schedule = VestingInfo {
+				locked: 256 * 5,
+				per_block: 64, // Vesting over 20 blocks
+				starting_block: 10,
+			};
+			assert_eq!(Vesting::vesting(&12), Some(user12_vesting_schedule));
+
+			// Account 12 can still send liquid funds
+			assert_ok!(Balances::transfer(Some(12).into(), 3, 256 * 5));
+		});
+	}
+
+	#[test]
+	fn vesting_completes() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(10);
+			assert_ok!(Vesting::vest(Some(1).into()));
+			assert_eq!(Vesting::vesting(&1), None);
+			assert!(Balances::locks(&1).is_empty());
+			assert_noop!(Vesting::vest(Some(1).into()), "Not vesting");
+			assert_ok!(Balances::transfer(Some(1).into(), 2, 256 * 10));
+		});
+	}
+
+	#[test]
+	fn vested_transfer_works() {
+		new_test_ext().execute_with(|| {
+			let schedule = VestingInfo {
+				locked: 256 * 5,
+				per_block: 64, // Vesting over 20 blocks
+				starting_block: 10,
+			};
+			let too_small = VestingInfo { locked: 256, ..schedule };
+			assert_noop!(Vesting::vested_transfer(Some(3).into(), 4, too_small), "Amount too low");
+			assert_noop!(
+				Vesting::vested_transfer(Some(3).into(), 1, schedule),
+				"Existing vesting schedule",
+			);
+
+			assert_ok!(Vesting::vested_transfer(Some(3).into(), 4, schedule));
+			assert_eq!(Balances::free_balance(&4), 256 * 45);
+			assert_eq!(Vesting::vesting(&4), Some(schedule));
+			assert_eq!(Vesting::vesting_balance(&4), 256 * 5);
+			assert_noop!(
+				Balances::transfer(Some(4).into(), 2, 256 * 40 + 1),
+				"account liquidity restrictions prevent withdrawal",
+			);
+			assert_ok!(Balances::transfer(Some(4).into(), 2, 256 * 40));
+		});
+	}
+}